@@ -0,0 +1,208 @@
+//! Ed25519 keys and signatures, for interop with off-chain services that sign with ed25519.
+//!
+//! Signatures are verified with the rules of ZIP-215, which accept the same signatures whether
+//! they are verified one by one or in a batch.
+
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Binary, StdError};
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "rand")]
+use crate::ContractPrng;
+
+pub const PRIVATE_KEY_SIZE: usize = 32;
+pub const PUBLIC_KEY_SIZE: usize = 32;
+pub const SIGNATURE_SIZE: usize = 64;
+
+pub struct PrivateKey {
+    inner: ed25519_zebra::SigningKey,
+}
+
+pub struct PublicKey {
+    inner: ed25519_zebra::VerificationKey,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    inner: ed25519_zebra::Signature,
+}
+
+impl PrivateKey {
+    /// Parses the 32-byte seed of a private key. Every seed is a valid key.
+    pub fn parse(raw: &[u8; PRIVATE_KEY_SIZE]) -> Result<Self, StdError> {
+        Ok(PrivateKey {
+            inner: ed25519_zebra::SigningKey::from(*raw),
+        })
+    }
+
+    /// Generates a new private key from the random bytes of `rng`
+    #[cfg(feature = "rand")]
+    pub fn generate(rng: &mut ContractPrng) -> Self {
+        PrivateKey {
+            inner: ed25519_zebra::SigningKey::new(rng),
+        }
+    }
+
+    /// Returns the 32-byte seed of the key
+    pub fn serialize(&self) -> [u8; PRIVATE_KEY_SIZE] {
+        // will never fail since the seed is exactly 32 bytes.
+        <[u8; PRIVATE_KEY_SIZE]>::try_from(self.inner.as_ref()).unwrap()
+    }
+
+    pub fn pubkey(&self) -> PublicKey {
+        PublicKey {
+            inner: ed25519_zebra::VerificationKey::from(&self.inner),
+        }
+    }
+
+    /// Signs a message, which is hashed as part of the signature scheme. Signing is
+    /// deterministic, so signing the same message twice gives the same signature.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        Signature {
+            inner: self.inner.sign(msg),
+        }
+    }
+}
+
+impl PublicKey {
+    pub fn parse(p: &[u8]) -> Result<PublicKey, StdError> {
+        ed25519_zebra::VerificationKey::try_from(p)
+            .map(|key| PublicKey { inner: key })
+            .map_err(|err| StdError::generic_err(format!("Error parsing PublicKey: {err}")))
+    }
+
+    pub fn serialize(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.inner.into()
+    }
+
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), StdError> {
+        self.inner
+            .verify(&signature.inner, msg)
+            .map_err(|err| StdError::generic_err(format!("Error verifying Signature: {err}")))
+    }
+}
+
+impl Signature {
+    pub fn parse(p: &[u8; SIGNATURE_SIZE]) -> Result<Signature, StdError> {
+        Ok(Signature {
+            inner: ed25519_zebra::Signature::from(*p),
+        })
+    }
+
+    pub fn parse_slice(p: &[u8]) -> Result<Signature, StdError> {
+        ed25519_zebra::Signature::try_from(p)
+            .map(|sig| Signature { inner: sig })
+            .map_err(|err| StdError::generic_err(format!("Error parsing Signature: {err}")))
+    }
+
+    pub fn serialize(&self) -> [u8; SIGNATURE_SIZE] {
+        self.inner.into()
+    }
+}
+
+fixed_size_bytes!(
+    /// The bytes of an ed25519 signature, for embedding in messages
+    SignatureBytes,
+    SIGNATURE_SIZE
+);
+
+fixed_size_bytes!(
+    /// The bytes of an ed25519 public key, for embedding in messages
+    PubKeyBytes,
+    PUBLIC_KEY_SIZE
+);
+
+impl SignatureBytes {
+    /// Parses the bytes as a `Signature`
+    pub fn to_signature(&self) -> Result<Signature, StdError> {
+        Signature::parse(&self.0)
+    }
+}
+
+impl From<&Signature> for SignatureBytes {
+    fn from(signature: &Signature) -> Self {
+        Self(signature.serialize())
+    }
+}
+
+impl PubKeyBytes {
+    /// Parses the bytes as a `PublicKey`
+    pub fn to_public_key(&self) -> Result<PublicKey, StdError> {
+        PublicKey::parse(&self.0)
+    }
+}
+
+impl From<&PublicKey> for PubKeyBytes {
+    fn from(public_key: &PublicKey) -> Self {
+        Self(public_key.serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rfc8032_vector() {
+        // test 2 of RFC 8032, section 7.1
+        let seed = hex("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb");
+        let pk = PrivateKey::parse(&<[u8; 32]>::try_from(seed.as_slice()).unwrap()).unwrap();
+        let pubkey = pk.pubkey();
+        assert_eq!(
+            pubkey.serialize().to_vec(),
+            hex("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c")
+        );
+
+        let signature = pk.sign(&[0x72]);
+        assert_eq!(
+            signature.serialize().to_vec(),
+            hex(concat!(
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+                "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+            ))
+        );
+        assert!(pubkey.verify(&[0x72], &signature).is_ok());
+        assert!(pubkey.verify(&[0x73], &signature).is_err());
+        assert_eq!(pk.serialize().to_vec(), seed);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_generate() {
+        let mut rng = ContractPrng::new(b"seed", b"entropy");
+        let alice = PrivateKey::generate(&mut rng);
+        let bob = PrivateKey::generate(&mut rng);
+        assert_ne!(alice.serialize(), bob.serialize());
+
+        let signature = alice.sign(b"payload");
+        assert!(alice.pubkey().verify(b"payload", &signature).is_ok());
+        assert!(bob.pubkey().verify(b"payload", &signature).is_err());
+    }
+
+    #[test]
+    fn test_fixed_size_bytes() {
+        let pk = PrivateKey::parse(&[1; PRIVATE_KEY_SIZE]).unwrap();
+        let signature = SignatureBytes::from(&pk.sign(b"test"));
+        let pubkey = PubKeyBytes::from(&pk.pubkey());
+
+        let json = cosmwasm_std::to_vec(&signature).unwrap();
+        let parsed: SignatureBytes = cosmwasm_std::from_slice(&json).unwrap();
+        assert_eq!(parsed, signature);
+
+        assert!(pubkey
+            .to_public_key()
+            .unwrap()
+            .verify(b"test", &signature.to_signature().unwrap())
+            .is_ok());
+        assert!(PubKeyBytes::try_from(&Binary(vec![2; SIGNATURE_SIZE])).is_err());
+    }
+}
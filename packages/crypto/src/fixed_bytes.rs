@@ -0,0 +1,66 @@
+/// Defines a newtype over a fixed number of bytes, that is validated when created and
+/// serialized as base64, like `Binary`
+macro_rules! fixed_size_bytes {
+    ($(#[$meta:meta])* $name:ident, $size:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, JsonSchema)]
+        pub struct $name(#[schemars(with = "String")] [u8; $size]);
+
+        impl $name {
+            pub const fn new(bytes: [u8; $size]) -> Self {
+                Self(bytes)
+            }
+
+            pub fn as_slice(&self) -> &[u8] {
+                &self.0
+            }
+
+            pub fn to_array(self) -> [u8; $size] {
+                self.0
+            }
+        }
+
+        impl From<[u8; $size]> for $name {
+            fn from(bytes: [u8; $size]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = StdError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+                <[u8; $size]>::try_from(bytes).map(Self).map_err(|_| {
+                    StdError::invalid_data_size($size, bytes.len())
+                })
+            }
+        }
+
+        impl TryFrom<&Binary> for $name {
+            type Error = StdError;
+
+            fn try_from(binary: &Binary) -> Result<Self, Self::Error> {
+                Self::try_from(binary.as_slice())
+            }
+        }
+
+        impl From<$name> for Binary {
+            fn from(bytes: $name) -> Self {
+                Binary(bytes.0.to_vec())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                Binary::from(self.as_slice()).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let binary = Binary::deserialize(deserializer)?;
+                Self::try_from(&binary).map_err(de::Error::custom)
+            }
+        }
+    };
+}
@@ -12,6 +12,98 @@ pub fn sha_256(data: &[u8]) -> [u8; SHA256_HASH_SIZE] {
     result
 }
 
+/// Returns the SHA-256 hash of each of the slices
+pub fn sha_256_many(data: &[&[u8]]) -> Vec<[u8; SHA256_HASH_SIZE]> {
+    data.iter().map(|item| sha_256(item)).collect()
+}
+
+/// Prefixes the hash of a leaf of a Merkle tree, as in RFC 6962
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+/// Prefixes the hash of an inner node of a Merkle tree, as in RFC 6962, so that an inner node
+/// can't be passed off as a leaf
+const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Returns the node of a leaf of a Merkle tree
+fn hash_leaf(leaf: &[u8; SHA256_HASH_SIZE]) -> [u8; SHA256_HASH_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update([MERKLE_LEAF_PREFIX]);
+    hasher.update(leaf);
+    hasher.finalize().into()
+}
+
+/// Returns the hash of a pair of nodes of a Merkle tree, which is the hash of the smaller node
+/// followed by the larger one, so proofs don't need to tell on which side each sibling is
+fn hash_pair(a: &[u8; SHA256_HASH_SIZE], b: &[u8; SHA256_HASH_SIZE]) -> [u8; SHA256_HASH_SIZE] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update([MERKLE_NODE_PREFIX]);
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+/// Returns the parent level of a level of a Merkle tree. The last node of an odd level is
+/// promoted as is.
+fn merkle_parents(level: &[[u8; SHA256_HASH_SIZE]]) -> Vec<[u8; SHA256_HASH_SIZE]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [a, b] => hash_pair(a, b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Returns the root of the Merkle tree of `leaves` with sorted-pair hashing, or `None` if there
+/// are no leaves. The leaves should be hashes of the data, e.g. with [`sha_256`], and not the
+/// data itself. Leaves and inner nodes are hashed with different prefixes, as in RFC 6962.
+pub fn merkle_root(leaves: &[[u8; SHA256_HASH_SIZE]]) -> Option<[u8; SHA256_HASH_SIZE]> {
+    if leaves.is_empty() {
+        return None;
+    }
+    let mut level: Vec<_> = leaves.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = merkle_parents(&level);
+    }
+    Some(level[0])
+}
+
+/// Returns the proof that the leaf at `index` is in the Merkle tree of `leaves`, i.e. the
+/// siblings of the nodes on the path from the leaf to the root, or `None` if `index` is out of
+/// bounds
+pub fn merkle_proof(
+    leaves: &[[u8; SHA256_HASH_SIZE]],
+    mut index: usize,
+) -> Option<Vec<[u8; SHA256_HASH_SIZE]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut proof = vec![];
+    let mut level: Vec<_> = leaves.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = merkle_parents(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Returns whether `proof` proves that `leaf` is in the Merkle tree with the given `root`, as
+/// computed by [`merkle_root`] and [`merkle_proof`]
+pub fn verify_merkle_proof(
+    root: &[u8; SHA256_HASH_SIZE],
+    leaf: &[u8; SHA256_HASH_SIZE],
+    proof: &[[u8; SHA256_HASH_SIZE]],
+) -> bool {
+    let computed = proof
+        .iter()
+        .fold(hash_leaf(leaf), |node, sibling| hash_pair(&node, sibling));
+    &computed == root
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,4 +124,64 @@ mod tests {
         ];
         assert_eq!(r, r_expected);
     }
+
+    #[test]
+    fn test_sha_256_many() {
+        let hashes = sha_256_many(&[b"test", b"random_string_123"]);
+        assert_eq!(
+            hashes,
+            vec![sha_256(b"test"), sha_256(b"random_string_123")]
+        );
+        assert!(sha_256_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_merkle_tree() {
+        assert_eq!(merkle_root(&[]), None);
+
+        let leaf = sha_256(b"alice");
+        assert_eq!(merkle_root(&[leaf]), Some(hash_leaf(&leaf)));
+        assert!(verify_merkle_proof(
+            &hash_leaf(&leaf),
+            &leaf,
+            &merkle_proof(&[leaf], 0).unwrap()
+        ));
+
+        let data: Vec<Vec<u8>> = (0..7u8).map(|i| vec![i]).collect();
+        let data: Vec<&[u8]> = data.iter().map(|item| item.as_slice()).collect();
+        let leaves = sha_256_many(&data);
+        for len in 2..=leaves.len() {
+            let leaves = &leaves[..len];
+            let root = merkle_root(leaves).unwrap();
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(leaves, index).unwrap();
+                assert!(verify_merkle_proof(&root, leaf, &proof));
+                assert!(!verify_merkle_proof(&root, &sha_256(b"mallory"), &proof));
+                assert!(!verify_merkle_proof(&root, leaf, &proof[1..]));
+            }
+            assert_eq!(merkle_proof(leaves, len), None);
+        }
+
+        // sorted-pair hashing doesn't depend on the order of siblings
+        let (a, b) = (sha_256(b"a"), sha_256(b"b"));
+        assert_eq!(merkle_root(&[a, b]), merkle_root(&[b, a]));
+        assert_eq!(
+            merkle_root(&[a, b]),
+            Some(hash_pair(&hash_leaf(&a), &hash_leaf(&b)))
+        );
+    }
+
+    #[test]
+    fn test_merkle_inner_node_as_leaf() {
+        let leaves = sha_256_many(&[b"a", b"b", b"c", b"d"]);
+        let root = merkle_root(&leaves).unwrap();
+        let proof = merkle_proof(&leaves, 0).unwrap();
+        assert!(verify_merkle_proof(&root, &leaves[0], &proof));
+
+        // the parent of the first two leaves, with the rest of the proof of the first leaf,
+        // would prove a leaf that isn't in the tree without the leaf prefix
+        let inner = hash_pair(&hash_leaf(&leaves[0]), &hash_leaf(&leaves[1]));
+        assert_eq!(hash_pair(&inner, &proof[1]), root);
+        assert!(!verify_merkle_proof(&root, &inner, &proof[1..]));
+    }
 }
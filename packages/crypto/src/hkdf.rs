@@ -1,10 +1,17 @@
 use cosmwasm_std::{StdError, StdResult};
-use hkdf::{hmac::Hmac, Hkdf};
+use hkdf::{
+    hmac::{Hmac, Mac},
+    Hkdf,
+};
 use sha2::{Sha256, Sha512};
 
 // Create alias for HMAC-SHA256
 pub type HmacSha256 = Hmac<Sha256>;
 
+pub const HMAC_SHA_256_SIZE: usize = 32;
+
+/// Derives `length` bytes of key material from `ikm`, as in RFC 5869. `length` can be at most
+/// 255 * 32 bytes.
 pub fn hkdf_sha_256(
     salt: &Option<Vec<u8>>,
     ikm: &[u8],
@@ -20,6 +27,8 @@ pub fn hkdf_sha_256(
     }
 }
 
+/// Derives `length` bytes of key material from `ikm`, as in RFC 5869. `length` can be at most
+/// 255 * 64 bytes.
 pub fn hkdf_sha_512(
     salt: &Option<Vec<u8>>,
     ikm: &[u8],
@@ -34,3 +43,98 @@ pub fn hkdf_sha_512(
         Err(e) => Err(StdError::generic_err(format!("{:?}", e))),
     }
 }
+
+/// Returns the HMAC-SHA256 of `data` with `key`, as in RFC 2104. Keys of any length are accepted.
+pub fn hmac_sha_256(key: &[u8], data: &[u8]) -> [u8; HMAC_SHA_256_SIZE] {
+    // will never fail since HMAC accepts keys of any length.
+    let mut mac = HmacSha256::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Checks in constant time that `tag` is the HMAC-SHA256 of `data` with `key`
+pub fn verify_hmac_sha_256(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    // will never fail since HMAC accepts keys of any length.
+    let mut mac = HmacSha256::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.verify_slice(tag).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_hmac_sha_256_rfc4231() {
+        // test cases 2 and 6 of RFC 4231
+        let tag = hmac_sha_256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            tag.to_vec(),
+            hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        assert!(verify_hmac_sha_256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &tag
+        ));
+        assert!(!verify_hmac_sha_256(b"Jefe", b"what do ya want?", &tag));
+        assert!(!verify_hmac_sha_256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &tag[..31]
+        ));
+
+        // a key longer than the block size is hashed first
+        let tag = hmac_sha_256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        assert_eq!(
+            tag.to_vec(),
+            hex("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+    }
+
+    #[test]
+    fn test_hkdf_sha_256_rfc5869() {
+        // test cases 1 and 3 of RFC 5869
+        let ikm = [0x0b; 22];
+        let okm = hkdf_sha_256(
+            &Some(hex("000102030405060708090a0b0c")),
+            &ikm,
+            &hex("f0f1f2f3f4f5f6f7f8f9"),
+            42,
+        )
+        .unwrap();
+        assert_eq!(
+            okm,
+            hex(concat!(
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf",
+                "34007208d5b887185865"
+            ))
+        );
+
+        let okm = hkdf_sha_256(&None, &ikm, b"", 42).unwrap();
+        assert_eq!(
+            okm,
+            hex(concat!(
+                "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d",
+                "9d201395faa4b61a96c8"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_hkdf_length() {
+        assert!(hkdf_sha_256(&None, b"ikm", b"", 255 * 32).is_ok());
+        assert!(hkdf_sha_256(&None, b"ikm", b"", 255 * 32 + 1).is_err());
+        assert!(hkdf_sha_512(&None, b"ikm", b"", 255 * 64 + 1).is_err());
+    }
+}
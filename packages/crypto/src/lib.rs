@@ -1,14 +1,34 @@
 #![doc = include_str!("../Readme.md")]
 
+#[cfg(any(feature = "ecc-secp256k1", feature = "ecc-ed25519"))]
+#[macro_use]
+mod fixed_bytes;
+
+#[cfg(feature = "ecc-ed25519")]
+pub mod ed25519;
 #[cfg(feature = "hash")]
 mod hash;
+#[cfg(feature = "password")]
+pub mod password;
+#[cfg(feature = "pedersen")]
+pub mod pedersen;
 #[cfg(feature = "rand")]
 mod rng;
+#[cfg(feature = "schnorr")]
+pub mod schnorr;
 #[cfg(feature = "ecc-secp256k1")]
 pub mod secp256k1;
+#[cfg(feature = "voprf")]
+pub mod voprf;
+#[cfg(feature = "vrf")]
+pub mod vrf;
+#[cfg(feature = "vss")]
+pub mod vss;
 
 #[cfg(feature = "hash")]
-pub use hash::{sha_256, SHA256_HASH_SIZE};
+pub use hash::{
+    merkle_proof, merkle_root, sha_256, sha_256_many, verify_merkle_proof, SHA256_HASH_SIZE,
+};
 
 #[cfg(feature = "rand")]
 pub use rng::ContractPrng;
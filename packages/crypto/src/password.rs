@@ -0,0 +1,189 @@
+//! Argon2id password hashing, to check passphrases such as claim codes of gift links without
+//! storing them.
+//!
+//! Unlike a plain sha256 of the passphrase, the hash is salted, so the same passphrase in two
+//! contracts or two gifts has unrelated hashes, and each guess costs an Argon2id evaluation to
+//! whoever got a copy of the hash. The default parameters are much lighter than the ones
+//! recommended for servers, so that verifying a passphrase fits in the gas budget of a
+//! transaction. Passphrases should thus be generated at random, rather than chosen by users.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use cosmwasm_std::{Binary, StdError, StdResult};
+
+/// The size of the salt of a hash
+pub const SALT_SIZE: usize = 16;
+/// The size of the output of Argon2id
+pub const OUTPUT_SIZE: usize = 32;
+/// The size of a serialized [`PasswordHash`]
+pub const PASSWORD_HASH_SIZE: usize = 8 + SALT_SIZE + OUTPUT_SIZE;
+
+/// The cost parameters of Argon2id
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PasswordParams {
+    /// the memory used, in KiB, at least 8
+    pub memory_kib: u32,
+    /// the number of passes over the memory, at least 1
+    pub iterations: u32,
+}
+
+impl Default for PasswordParams {
+    /// 64 KiB of memory and 2 passes, sized for the gas budget of a transaction
+    fn default() -> Self {
+        Self {
+            memory_kib: 64,
+            iterations: 2,
+        }
+    }
+}
+
+/// The hash of a password, along with its salt and parameters, to store in place of the password
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PasswordHash {
+    params: PasswordParams,
+    salt: [u8; SALT_SIZE],
+    output: [u8; OUTPUT_SIZE],
+}
+
+impl PasswordHash {
+    pub fn serialize(&self) -> [u8; PASSWORD_HASH_SIZE] {
+        let mut bytes = [0u8; PASSWORD_HASH_SIZE];
+        bytes[..4].copy_from_slice(&self.params.memory_kib.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.params.iterations.to_be_bytes());
+        bytes[8..8 + SALT_SIZE].copy_from_slice(&self.salt);
+        bytes[8 + SALT_SIZE..].copy_from_slice(&self.output);
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> StdResult<Self> {
+        let bytes: &[u8; PASSWORD_HASH_SIZE] = bytes
+            .try_into()
+            .map_err(|_| StdError::invalid_data_size(PASSWORD_HASH_SIZE, bytes.len()))?;
+        let (params, rest) = bytes.split_at(8);
+        let (salt, output) = rest.split_at(SALT_SIZE);
+        Ok(Self {
+            params: PasswordParams {
+                memory_kib: u32::from_be_bytes(params[..4].try_into().unwrap()),
+                iterations: u32::from_be_bytes(params[4..].try_into().unwrap()),
+            },
+            salt: salt.try_into().unwrap(),
+            output: output.try_into().unwrap(),
+        })
+    }
+
+    pub fn params(&self) -> PasswordParams {
+        self.params
+    }
+
+    /// Returns whether `password` is the password of this hash. The outputs are compared in
+    /// constant time.
+    pub fn verify(&self, password: &[u8]) -> StdResult<bool> {
+        let output = argon2id(password, &self.salt, self.params)?;
+        let diff = output
+            .iter()
+            .zip(self.output.iter())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b));
+        Ok(diff == 0)
+    }
+}
+
+impl From<&PasswordHash> for Binary {
+    fn from(hash: &PasswordHash) -> Self {
+        Binary(hash.serialize().to_vec())
+    }
+}
+
+/// Hashes a password with the default parameters.
+///
+/// # Arguments
+///
+/// * `password` - the password, which should be random
+/// * `salt` - a salt unique to this password, e.g. taken from a `ContractPrng`
+pub fn hash_password(password: &[u8], salt: &[u8; SALT_SIZE]) -> StdResult<PasswordHash> {
+    hash_password_with_params(password, salt, PasswordParams::default())
+}
+
+/// Hashes a password with the given parameters.
+pub fn hash_password_with_params(
+    password: &[u8],
+    salt: &[u8; SALT_SIZE],
+    params: PasswordParams,
+) -> StdResult<PasswordHash> {
+    Ok(PasswordHash {
+        params,
+        salt: *salt,
+        output: argon2id(password, salt, params)?,
+    })
+}
+
+/// Returns whether `password` is the password of a serialized [`PasswordHash`].
+pub fn verify_password(password: &[u8], hash: &[u8]) -> StdResult<bool> {
+    PasswordHash::parse(hash)?.verify(password)
+}
+
+fn argon2id(
+    password: &[u8],
+    salt: &[u8; SALT_SIZE],
+    params: PasswordParams,
+) -> StdResult<[u8; OUTPUT_SIZE]> {
+    let params = Params::new(params.memory_kib, params.iterations, 1, Some(OUTPUT_SIZE))
+        .map_err(|err| StdError::generic_err(format!("password: {err}")))?;
+    let mut output = [0u8; OUTPUT_SIZE];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, &mut output)
+        .map_err(|err| StdError::generic_err(format!("password: {err}")))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SALT: [u8; SALT_SIZE] = [7; SALT_SIZE];
+
+    #[test]
+    fn test_hash_and_verify() -> StdResult<()> {
+        let hash = hash_password(b"gift-7f3k-29xq", &SALT)?;
+        let stored = Binary::from(&hash);
+        assert!(verify_password(b"gift-7f3k-29xq", &stored)?);
+        assert!(!verify_password(b"gift-7f3k-29xr", &stored)?);
+        assert_eq!(PasswordHash::parse(&stored)?, hash);
+
+        // the same password has unrelated hashes with other salts
+        let other = hash_password(b"gift-7f3k-29xq", &[8; SALT_SIZE])?;
+        assert_ne!(
+            other.serialize()[8 + SALT_SIZE..],
+            hash.serialize()[8 + SALT_SIZE..]
+        );
+
+        assert!(verify_password(b"gift-7f3k-29xq", &stored[1..]).is_err());
+        let weak = PasswordParams {
+            memory_kib: 4,
+            iterations: 1,
+        };
+        assert!(hash_password_with_params(b"password", &SALT, weak).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vectors() -> StdResult<()> {
+        let hash = hash_password(b"password", &SALT)?;
+        assert_eq!(
+            Binary::from(&hash).to_base64(),
+            "AAAAQAAAAAIHBwcHBwcHBwcHBwcHBwcHxQ9ctnID3c1o2y6KKiXkoZFZy8ySNlaDqTPViBE25s4="
+        );
+
+        let params = PasswordParams {
+            memory_kib: 32,
+            iterations: 3,
+        };
+        let hash = hash_password_with_params(b"", &SALT, params)?;
+        assert_eq!(hash.params(), params);
+        assert_eq!(
+            Binary::from(&hash).to_base64(),
+            "AAAAIAAAAAMHBwcHBwcHBwcHBwcHBwcHs7VNid01oiI169dgQRYnTUgTgEYIOjQE02rKQL/XO2E="
+        );
+
+        Ok(())
+    }
+}
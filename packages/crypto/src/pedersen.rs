@@ -0,0 +1,306 @@
+//! Pedersen commitments on ristretto255, and verification of Bulletproof range proofs, for
+//! contracts with confidential amounts.
+//!
+//! A commitment hides a value behind a random blinding factor, yet commitments can be added and
+//! subtracted, which adds and subtracts their values and blindings. A range proof, generated
+//! off-chain by whoever knows the value and blinding, shows that the value of a commitment fits
+//! in a number of bits, without revealing it. Together, they let a contract, and anyone auditing
+//! it, check e.g. that a confidential transfer doesn't create tokens or overdraw a balance.
+//!
+//! The commitments and proofs are compatible with the `bulletproofs` crate, with its default
+//! generators.
+
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use cosmwasm_std::{StdError, StdResult};
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+
+/// The size of a serialized [`Commitment`]
+pub const COMMITMENT_SIZE: usize = 32;
+/// The size of a blinding factor
+pub const BLINDING_SIZE: usize = 32;
+/// The largest number of bits a range proof can prove a value fits in
+pub const MAX_RANGE_BITS: usize = 64;
+
+/// A Pedersen commitment to a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Commitment {
+    inner: RistrettoPoint,
+}
+
+impl Commitment {
+    pub fn parse(bytes: &[u8]) -> StdResult<Self> {
+        CompressedRistretto::from_slice(bytes)
+            .ok()
+            .and_then(|point| point.decompress())
+            .map(|inner| Commitment { inner })
+            .ok_or_else(|| StdError::generic_err("Error parsing Commitment: invalid point"))
+    }
+
+    pub fn serialize(&self) -> [u8; COMMITMENT_SIZE] {
+        self.inner.compress().to_bytes()
+    }
+
+    /// Returns the commitment to the sum of the values, with the sum of the blindings
+    pub fn add(&self, other: &Commitment) -> Commitment {
+        Commitment {
+            inner: self.inner + other.inner,
+        }
+    }
+
+    /// Returns the commitment to the difference of the values, with the difference of the
+    /// blindings
+    pub fn sub(&self, other: &Commitment) -> Commitment {
+        Commitment {
+            inner: self.inner - other.inner,
+        }
+    }
+
+    /// Returns whether this is the commitment to `value` with `blinding`
+    pub fn opens_to(&self, value: u64, blinding: &[u8; BLINDING_SIZE]) -> bool {
+        *self == commit(value, blinding)
+    }
+}
+
+/// Commits to `value`.
+///
+/// # Arguments
+///
+/// * `value` - the value to hide
+/// * `blinding` - a random blinding factor, e.g. taken from a `ContractPrng`, which opens the
+///   commitment and must be kept secret along with the value
+pub fn commit(value: u64, blinding: &[u8; BLINDING_SIZE]) -> Commitment {
+    Commitment {
+        inner: PedersenGens::default().commit(Scalar::from(value), scalar(blinding)),
+    }
+}
+
+/// Returns the sum of two blinding factors, which opens the sum of their commitments
+pub fn add_blindings(a: &[u8; BLINDING_SIZE], b: &[u8; BLINDING_SIZE]) -> [u8; BLINDING_SIZE] {
+    (scalar(a) + scalar(b)).to_bytes()
+}
+
+/// Returns the difference of two blinding factors, which opens the difference of their
+/// commitments
+pub fn sub_blindings(a: &[u8; BLINDING_SIZE], b: &[u8; BLINDING_SIZE]) -> [u8; BLINDING_SIZE] {
+    (scalar(a) - scalar(b)).to_bytes()
+}
+
+/// Proves that `value` fits in `bits` bits, which must be 8, 16, 32 or 64, and returns the proof
+/// along with the commitment to `value`.
+///
+/// This is meant for clients and tests, as proving costs far more than verifying. The `label`
+/// separates the proofs of different protocols, and must be the one passed to
+/// [`verify_range_proof`].
+pub fn prove_range<R: RngCore + CryptoRng>(
+    value: u64,
+    blinding: &[u8; BLINDING_SIZE],
+    bits: usize,
+    label: &'static [u8],
+    rng: &mut R,
+) -> StdResult<(Vec<u8>, Commitment)> {
+    if bits < MAX_RANGE_BITS && value >> bits != 0 {
+        return Err(StdError::generic_err(format!(
+            "range proof: {value} doesn't fit in {bits} bits"
+        )));
+    }
+    let (proof, commitment) = RangeProof::prove_single_with_rng(
+        &BulletproofGens::new(MAX_RANGE_BITS, 1),
+        &PedersenGens::default(),
+        &mut Transcript::new(label),
+        value,
+        &scalar(blinding),
+        bits,
+        rng,
+    )
+    .map_err(|err| StdError::generic_err(format!("range proof: {err:?}")))?;
+    Ok((proof.to_bytes(), Commitment::parse(commitment.as_bytes())?))
+}
+
+/// Returns whether `proof` proves that the value of `commitment` fits in `bits` bits.
+///
+/// # Arguments
+///
+/// * `commitment` - the commitment to the value
+/// * `proof` - the serialized range proof
+/// * `bits` - the number of bits, which must be 8, 16, 32 or 64
+/// * `label` - the label the proof was generated with
+/// * `rng` - randomness for batching the checks of the verification, e.g. a `ContractPrng`
+pub fn verify_range_proof<R: RngCore + CryptoRng>(
+    commitment: &Commitment,
+    proof: &[u8],
+    bits: usize,
+    label: &'static [u8],
+    rng: &mut R,
+) -> StdResult<bool> {
+    verify_aggregated_range_proof(&[*commitment], proof, bits, label, rng)
+}
+
+/// Returns whether `proof` proves that the values of all the `commitments` fit in `bits` bits.
+/// The number of commitments must be a power of two.
+pub fn verify_aggregated_range_proof<R: RngCore + CryptoRng>(
+    commitments: &[Commitment],
+    proof: &[u8],
+    bits: usize,
+    label: &'static [u8],
+    rng: &mut R,
+) -> StdResult<bool> {
+    if !matches!(bits, 8 | 16 | 32 | 64) {
+        return Err(StdError::generic_err(format!(
+            "range proof: invalid number of bits {bits}"
+        )));
+    }
+    if !commitments.len().is_power_of_two() {
+        return Err(StdError::generic_err(format!(
+            "range proof: the number of commitments, {}, is not a power of two",
+            commitments.len()
+        )));
+    }
+    let proof = RangeProof::from_bytes(proof)
+        .map_err(|err| StdError::generic_err(format!("Error parsing RangeProof: {err:?}")))?;
+    let commitments: Vec<CompressedRistretto> = commitments
+        .iter()
+        .map(|commitment| commitment.inner.compress())
+        .collect();
+
+    Ok(proof
+        .verify_multiple_with_rng(
+            &BulletproofGens::new(MAX_RANGE_BITS, commitments.len()),
+            &PedersenGens::default(),
+            &mut Transcript::new(label),
+            &commitments,
+            bits,
+            rng,
+        )
+        .is_ok())
+}
+
+fn scalar(bytes: &[u8; 32]) -> Scalar {
+    Scalar::from_bytes_mod_order(*bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContractPrng;
+
+    const LABEL: &[u8] = b"confidential transfer";
+
+    #[test]
+    fn test_commitments() -> StdResult<()> {
+        let balance = commit(100, &[1; 32]);
+        let amount = commit(30, &[2; 32]);
+        let rest = balance.sub(&amount);
+        assert!(rest.opens_to(70, &sub_blindings(&[1; 32], &[2; 32])));
+        assert!(!rest.opens_to(71, &sub_blindings(&[1; 32], &[2; 32])));
+        assert!(rest.add(&amount).opens_to(
+            100,
+            &add_blindings(&sub_blindings(&[1; 32], &[2; 32]), &[2; 32])
+        ));
+
+        assert_eq!(Commitment::parse(&balance.serialize())?, balance);
+        assert!(Commitment::parse(&[0xff; COMMITMENT_SIZE]).is_err());
+        assert!(Commitment::parse(&balance.serialize()[1..]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_proof() -> StdResult<()> {
+        let mut rng = ContractPrng::new(b"seed", b"entropy");
+        let blinding = rng.rand_bytes();
+        let (proof, commitment) = prove_range(1000, &blinding, 32, LABEL, &mut rng)?;
+        assert!(commitment.opens_to(1000, &blinding));
+
+        assert!(verify_range_proof(
+            &commitment,
+            &proof,
+            32,
+            LABEL,
+            &mut rng
+        )?);
+        // the proof is only valid for its commitment, number of bits and label
+        let other = commit(1000, &rng.rand_bytes());
+        assert!(!verify_range_proof(&other, &proof, 32, LABEL, &mut rng)?);
+        assert!(!verify_range_proof(
+            &commitment,
+            &proof,
+            32,
+            b"other",
+            &mut rng
+        )?);
+        assert!(!verify_range_proof(
+            &commitment,
+            &proof,
+            64,
+            LABEL,
+            &mut rng
+        )?);
+
+        assert!(verify_range_proof(&commitment, &proof[1..], 32, LABEL, &mut rng).is_err());
+        assert!(verify_range_proof(&commitment, &proof, 12, LABEL, &mut rng).is_err());
+        assert!(prove_range(256, &blinding, 8, LABEL, &mut rng).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregated_range_proof() -> StdResult<()> {
+        let mut rng = ContractPrng::new(b"seed", b"entropy");
+        let (blindings, values) = ([[3; 32], [4; 32]], [5u64, 1 << 40]);
+        let (proof, commitments) = RangeProof::prove_multiple_with_rng(
+            &BulletproofGens::new(MAX_RANGE_BITS, 2),
+            &PedersenGens::default(),
+            &mut Transcript::new(LABEL),
+            &values,
+            &blindings.map(|blinding| scalar(&blinding)),
+            64,
+            &mut rng,
+        )
+        .unwrap();
+        let commitments = commitments
+            .iter()
+            .map(|commitment| Commitment::parse(commitment.as_bytes()))
+            .collect::<StdResult<Vec<_>>>()?;
+        assert_eq!(commitments[1], commit(1 << 40, &[4; 32]));
+
+        let proof = proof.to_bytes();
+        assert!(verify_aggregated_range_proof(
+            &commitments,
+            &proof,
+            64,
+            LABEL,
+            &mut rng
+        )?);
+        assert!(
+            verify_aggregated_range_proof(&commitments[..1], &proof, 64, LABEL, &mut rng)
+                .is_ok_and(|valid| !valid)
+        );
+        let three = [commitments[0], commitments[1], commitments[1]];
+        assert!(verify_aggregated_range_proof(&three, &proof, 64, LABEL, &mut rng).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_negative_balance() -> StdResult<()> {
+        let mut rng = ContractPrng::new(b"seed", b"entropy");
+        // a transfer of 30 out of a balance of 20 leaves a commitment to -10, which wraps
+        // around the group order and can't be proven to fit in 64 bits
+        let balance = commit(20, &[1; 32]);
+        let amount = commit(30, &[2; 32]);
+        let rest = balance.sub(&amount);
+        let (proof, _) = prove_range(
+            u64::MAX,
+            &sub_blindings(&[1; 32], &[2; 32]),
+            64,
+            LABEL,
+            &mut rng,
+        )?;
+        assert!(!verify_range_proof(&rest, &proof, 64, LABEL, &mut rng)?);
+
+        Ok(())
+    }
+}
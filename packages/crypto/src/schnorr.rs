@@ -0,0 +1,234 @@
+//! BIP-340 Schnorr signatures over secp256k1, as used by Taproot.
+//!
+//! Public keys are x-only: they are the 32-byte x coordinate of the point, whose y coordinate is
+//! implicitly even. The aggregate key and signature of a MuSig2 session are an ordinary key and
+//! signature, so they are verified with [`verify`] like any other.
+
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Binary, StdError};
+use schemars::JsonSchema;
+use secp256k1::{KeyPair, Message, Secp256k1, XOnlyPublicKey};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::secp256k1::{PrivateKey, MESSAGE_SIZE};
+#[cfg(feature = "rand")]
+use crate::ContractPrng;
+
+pub const PUBLIC_KEY_SIZE: usize = secp256k1::constants::SCHNORR_PUBLIC_KEY_SIZE;
+pub const SIGNATURE_SIZE: usize = secp256k1::constants::SCHNORR_SIGNATURE_SIZE;
+pub const AUX_RAND_SIZE: usize = 32;
+
+/// An x-only public key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    inner: XOnlyPublicKey,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    inner: secp256k1::schnorr::Signature,
+}
+
+impl PublicKey {
+    pub fn parse(p: &[u8]) -> Result<PublicKey, StdError> {
+        XOnlyPublicKey::from_slice(p)
+            .map(|key| PublicKey { inner: key })
+            .map_err(|err| StdError::generic_err(format!("Error parsing PublicKey: {err}")))
+    }
+
+    pub fn serialize(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.inner.serialize()
+    }
+}
+
+impl From<&PrivateKey> for PublicKey {
+    /// Returns the x-only public key of a private key
+    fn from(private_key: &PrivateKey) -> Self {
+        Self::from(&private_key.pubkey())
+    }
+}
+
+impl From<&crate::secp256k1::PublicKey> for PublicKey {
+    /// Returns the x-only form of a public key, dropping the parity of its y coordinate
+    fn from(public_key: &crate::secp256k1::PublicKey) -> Self {
+        PublicKey {
+            inner: XOnlyPublicKey::from(public_key.inner),
+        }
+    }
+}
+
+impl Signature {
+    pub fn parse(p: &[u8; SIGNATURE_SIZE]) -> Result<Signature, StdError> {
+        Self::parse_slice(p)
+    }
+
+    pub fn parse_slice(p: &[u8]) -> Result<Signature, StdError> {
+        secp256k1::schnorr::Signature::from_slice(p)
+            .map(|sig| Signature { inner: sig })
+            .map_err(|err| StdError::generic_err(format!("Error parsing Signature: {err}")))
+    }
+
+    pub fn serialize(&self) -> [u8; SIGNATURE_SIZE] {
+        *self.inner.as_ref()
+    }
+}
+
+/// Signs a 32-byte message, e.g. a Taproot sighash, with the auxiliary random data of BIP-340.
+/// The auxiliary data only protects against side channels, so signing with all zeros is still
+/// secure, and is what the test vectors of BIP-340 use.
+pub fn sign(
+    private_key: &PrivateKey,
+    msg: &[u8; MESSAGE_SIZE],
+    aux_rand: &[u8; AUX_RAND_SIZE],
+) -> Signature {
+    let secp = Secp256k1::signing_only();
+    let keypair = KeyPair::from_secret_key(&secp, &private_key.inner);
+    // will never fail since the message is exactly 32 bytes.
+    let message = Message::from_slice(msg).unwrap();
+    Signature {
+        inner: secp.sign_schnorr_with_aux_rand(&message, &keypair, aux_rand),
+    }
+}
+
+/// Signs a 32-byte message, with auxiliary random data from `rng`
+#[cfg(feature = "rand")]
+pub fn sign_with_rng(
+    private_key: &PrivateKey,
+    msg: &[u8; MESSAGE_SIZE],
+    rng: &mut ContractPrng,
+) -> Signature {
+    sign(private_key, msg, &rng.rand_bytes())
+}
+
+/// Verifies the signature of a 32-byte message
+pub fn verify(
+    public_key: &PublicKey,
+    msg: &[u8; MESSAGE_SIZE],
+    signature: &Signature,
+) -> Result<(), StdError> {
+    let secp = Secp256k1::verification_only();
+    // will never fail since the message is exactly 32 bytes.
+    let message = Message::from_slice(msg).unwrap();
+    secp.verify_schnorr(&signature.inner, &message, &public_key.inner)
+        .map_err(|err| StdError::generic_err(format!("Error verifying Signature: {err}")))
+}
+
+fixed_size_bytes!(
+    /// The bytes of a BIP-340 signature, for embedding in messages
+    SignatureBytes,
+    SIGNATURE_SIZE
+);
+
+fixed_size_bytes!(
+    /// The bytes of an x-only public key, for embedding in messages
+    PubKeyBytes,
+    PUBLIC_KEY_SIZE
+);
+
+impl SignatureBytes {
+    /// Parses the bytes as a `Signature`
+    pub fn to_signature(&self) -> Result<Signature, StdError> {
+        Signature::parse(&self.0)
+    }
+}
+
+impl From<&Signature> for SignatureBytes {
+    fn from(signature: &Signature) -> Self {
+        Self(signature.serialize())
+    }
+}
+
+impl PubKeyBytes {
+    /// Parses the bytes as a `PublicKey`
+    pub fn to_public_key(&self) -> Result<PublicKey, StdError> {
+        PublicKey::parse(&self.0)
+    }
+}
+
+impl From<&PublicKey> for PubKeyBytes {
+    fn from(public_key: &PublicKey) -> Self {
+        Self(public_key.serialize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex<const N: usize>(hex: &str) -> [u8; N] {
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        <[u8; N]>::try_from(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_bip340_vectors() {
+        // vectors 0 and 1 of BIP-340
+        let vectors = [
+            (
+                "0000000000000000000000000000000000000000000000000000000000000003",
+                "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                concat!(
+                    "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215",
+                    "25F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0"
+                ),
+            ),
+            (
+                "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+                "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+                concat!(
+                    "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE3341",
+                    "8906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A"
+                ),
+            ),
+        ];
+
+        for (secret_key, public_key, aux_rand, msg, signature) in vectors {
+            let private_key = PrivateKey::parse(&hex(secret_key)).unwrap();
+            let public_key = PublicKey::parse(&hex::<32>(public_key)).unwrap();
+            assert_eq!(PublicKey::from(&private_key), public_key);
+
+            let msg = hex(msg);
+            let expected = Signature::parse(&hex(signature)).unwrap();
+            assert_eq!(sign(&private_key, &msg, &hex(aux_rand)), expected);
+            assert!(verify(&public_key, &msg, &expected).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects() {
+        let private_key = PrivateKey::parse(&[1; 32]).unwrap();
+        let public_key = PublicKey::from(&private_key);
+        let signature = sign(&private_key, &[2; 32], &[0; 32]);
+
+        assert!(verify(&public_key, &[3; 32], &signature).is_err());
+        let other = PublicKey::from(&PrivateKey::parse(&[4; 32]).unwrap());
+        assert!(verify(&other, &[2; 32], &signature).is_err());
+
+        // round trip through the message types
+        let bytes = SignatureBytes::from(&signature);
+        let signer = PubKeyBytes::from(&public_key);
+        assert!(verify(
+            &signer.to_public_key().unwrap(),
+            &[2; 32],
+            &bytes.to_signature().unwrap()
+        )
+        .is_ok());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sign_with_rng() {
+        let private_key = PrivateKey::parse(&[1; 32]).unwrap();
+        let mut rng = ContractPrng::new(b"seed", b"entropy");
+        let signature = sign_with_rng(&private_key, &[2; 32], &mut rng);
+        assert!(verify(&PublicKey::from(&private_key), &[2; 32], &signature).is_ok());
+    }
+}
@@ -1,20 +1,28 @@
 pub use secp256k1::constants::{COMPACT_SIGNATURE_SIZE as SIGNATURE_SIZE, MESSAGE_SIZE};
 use secp256k1::ecdsa::Signature as SecpSignature;
 
-use cosmwasm_std::{Api, StdError};
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Api, Binary, StdError};
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 pub const PRIVATE_KEY_SIZE: usize = secp256k1::constants::SECRET_KEY_SIZE;
 pub const PUBLIC_KEY_SIZE: usize = secp256k1::constants::UNCOMPRESSED_PUBLIC_KEY_SIZE;
 pub const COMPRESSED_PUBLIC_KEY_SIZE: usize = secp256k1::constants::PUBLIC_KEY_SIZE;
 
+#[cfg(feature = "hkdf")]
+const DERIVE_KEYPAIR_SALT: &[u8] = b"secret-toolkit-derive-keypair";
+
 pub struct PrivateKey {
-    inner: secp256k1::SecretKey,
+    pub(crate) inner: secp256k1::SecretKey,
 }
 
 pub struct PublicKey {
-    inner: secp256k1::PublicKey,
+    pub(crate) inner: secp256k1::PublicKey,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Signature {
     inner: SecpSignature,
 }
@@ -45,6 +53,25 @@ impl PrivateKey {
 
         Signature { inner: sig }
     }
+
+    /// Signs a 32-byte message hash, e.g. the `sha_256` of the message, inside the contract
+    /// rather than through the `Api`. The nonce is derived deterministically from the key and the
+    /// hash as specified by RFC 6979, so signing the same hash twice gives the same signature,
+    /// and the signature is normalized to its low-S form.
+    pub fn sign_hash(&self, msg_hash: &[u8; MESSAGE_SIZE]) -> Signature {
+        let secp = secp256k1::Secp256k1::signing_only();
+        // will never fail since the message is exactly 32 bytes.
+        let message = secp256k1::Message::from_slice(msg_hash).unwrap();
+        Signature {
+            inner: secp.sign_ecdsa(&message, &self.inner),
+        }
+    }
+
+    /// Returns the ECDH shared secret of this key and `pubkey`, which is the same as the one
+    /// computed from the private key of `pubkey` and the public key of this key.
+    pub fn shared_secret(&self, pubkey: &PublicKey) -> [u8; 32] {
+        secp256k1::ecdh::SharedSecret::new(&pubkey.inner, &self.inner).secret_bytes()
+    }
 }
 
 impl PublicKey {
@@ -68,6 +95,20 @@ impl PublicKey {
         // will never fail since we guarantee that the inputs are valid.
         api.secp256k1_verify(data, sig, pk).unwrap()
     }
+
+    /// Verifies a signature of a 32-byte message hash inside the contract rather than through
+    /// the `Api`. Like the chain, it rejects signatures that aren't in their low-S form.
+    pub fn verify_hash(
+        &self,
+        msg_hash: &[u8; MESSAGE_SIZE],
+        signature: &Signature,
+    ) -> Result<(), StdError> {
+        let secp = secp256k1::Secp256k1::verification_only();
+        // will never fail since the message is exactly 32 bytes.
+        let message = secp256k1::Message::from_slice(msg_hash).unwrap();
+        secp.verify_ecdsa(&message, &signature.inner, &self.inner)
+            .map_err(|err| StdError::generic_err(format!("Error verifying Signature: {err}")))
+    }
 }
 
 impl Signature {
@@ -83,9 +124,91 @@ impl Signature {
             .map_err(|err| StdError::generic_err(format!("Error parsing Signature: {err}")))
     }
 
+    /// Parses a DER encoded signature, as produced by most wallets and HSMs
+    pub fn parse_der(p: &[u8]) -> Result<Signature, StdError> {
+        SecpSignature::from_der(p)
+            .map(|sig| Signature { inner: sig })
+            .map_err(|err| StdError::generic_err(format!("Error parsing Signature: {err}")))
+    }
+
     pub fn serialize(&self) -> [u8; SIGNATURE_SIZE] {
         self.inner.serialize_compact()
     }
+
+    /// Serializes the signature in its DER encoding, which takes up to 72 bytes
+    pub fn serialize_der(&self) -> Vec<u8> {
+        self.inner.serialize_der().to_vec()
+    }
+
+    /// Returns the low-S form of the signature, which is the only one `verify_hash` and the
+    /// chain accept
+    pub fn normalize_s(&self) -> Signature {
+        let mut inner = self.inner;
+        inner.normalize_s();
+        Signature { inner }
+    }
+}
+
+fixed_size_bytes!(
+    /// The bytes of a compact secp256k1 signature, for embedding in messages
+    SignatureBytes,
+    SIGNATURE_SIZE
+);
+
+fixed_size_bytes!(
+    /// The bytes of a compressed secp256k1 public key, for embedding in messages
+    PubKeyBytes,
+    COMPRESSED_PUBLIC_KEY_SIZE
+);
+
+impl SignatureBytes {
+    /// Parses the bytes as a `Signature`
+    pub fn to_signature(&self) -> Result<Signature, StdError> {
+        Signature::parse(&self.0)
+    }
+}
+
+impl From<&Signature> for SignatureBytes {
+    fn from(signature: &Signature) -> Self {
+        Self(signature.serialize())
+    }
+}
+
+impl PubKeyBytes {
+    /// Parses the bytes as a `PublicKey`
+    pub fn to_public_key(&self) -> Result<PublicKey, StdError> {
+        PublicKey::parse(&self.0)
+    }
+}
+
+impl From<&PublicKey> for PubKeyBytes {
+    fn from(public_key: &PublicKey) -> Self {
+        Self(public_key.serialize_compressed())
+    }
+}
+
+/// Deterministically derives a key pair from a secret seed and a label.
+///
+/// The same seed and label always produce the same key pair, so a contract can regenerate its
+/// signing keys (e.g. after a migration) from its seed instead of storing the private keys.
+/// Different labels produce unrelated key pairs.
+#[cfg(feature = "hkdf")]
+pub fn derive_keypair(seed: &[u8], label: &[u8]) -> (PrivateKey, PublicKey) {
+    let salt = Some(DERIVE_KEYPAIR_SALT.to_vec());
+    let mut counter = 0u8;
+    loop {
+        let info = [label, &[counter]].concat();
+        // will never fail since the requested length is well below the HKDF maximum.
+        let okm = crate::hkdf::hkdf_sha_256(&salt, seed, &info, PRIVATE_KEY_SIZE).unwrap();
+        // the output is an invalid secret key with negligible probability, in which case we
+        // derive again with the next counter.
+        if let Ok(inner) = secp256k1::SecretKey::from_slice(&okm) {
+            let private_key = PrivateKey { inner };
+            let public_key = private_key.pubkey();
+            return (private_key, public_key);
+        }
+        counter = counter.wrapping_add(1);
+    }
 }
 
 #[cfg(test)]
@@ -128,4 +251,114 @@ mod tests {
         let pubkey = pk.pubkey();
         assert!(pubkey.verify(&data_hash, signature, mock_api));
     }
+
+    #[test]
+    fn test_sign_hash() {
+        // the RFC 6979 test vector of the private key 1
+        let mut raw = [0u8; PRIVATE_KEY_SIZE];
+        raw[31] = 1;
+        let pk = PrivateKey::parse(&raw).unwrap();
+        let msg_hash = sha_256(b"Satoshi Nakamoto");
+
+        let signature = pk.sign_hash(&msg_hash);
+        assert_eq!(
+            hex(&signature.serialize()),
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+             2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5"
+        );
+        assert_eq!(pk.sign_hash(&msg_hash), signature);
+
+        let pubkey = pk.pubkey();
+        assert!(pubkey.verify_hash(&msg_hash, &signature).is_ok());
+        assert!(pubkey
+            .verify_hash(&sha_256(b"Satoshi"), &signature)
+            .is_err());
+        let other = PrivateKey::parse(&[2; PRIVATE_KEY_SIZE]).unwrap().pubkey();
+        assert!(other.verify_hash(&msg_hash, &signature).is_err());
+
+        // the signatures are compatible with those of the `Api`
+        let mock_api = MockApi::default();
+        assert!(pubkey.verify(&msg_hash, pk.sign_hash(&msg_hash), mock_api));
+        let signature = pk.sign(b"Satoshi Nakamoto", mock_api);
+        assert!(pubkey.verify_hash(&msg_hash, &signature).is_ok());
+    }
+
+    #[test]
+    fn test_signature_der() {
+        let pk = PrivateKey::parse(&[1; PRIVATE_KEY_SIZE]).unwrap();
+        let signature = pk.sign_hash(&sha_256(b"test"));
+
+        let der = signature.serialize_der();
+        assert_eq!(der[0], 0x30);
+        assert_eq!(Signature::parse_der(&der).unwrap(), signature);
+        assert!(Signature::parse_der(&signature.serialize()).is_err());
+        assert_eq!(signature.normalize_s(), signature);
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_shared_secret() {
+        let alice = PrivateKey::parse(&[1; PRIVATE_KEY_SIZE]).unwrap();
+        let bob = PrivateKey::parse(&[2; PRIVATE_KEY_SIZE]).unwrap();
+
+        assert_eq!(
+            alice.shared_secret(&bob.pubkey()),
+            bob.shared_secret(&alice.pubkey())
+        );
+        assert_ne!(
+            alice.shared_secret(&bob.pubkey()),
+            alice.shared_secret(&alice.pubkey())
+        );
+    }
+
+    #[test]
+    fn test_fixed_size_bytes() {
+        let mock_api = MockApi::default();
+        let pk = PrivateKey::parse(&[1; PRIVATE_KEY_SIZE]).unwrap();
+        let signature = SignatureBytes::from(&pk.sign(b"test", mock_api));
+        let pubkey = PubKeyBytes::from(&pk.pubkey());
+
+        let json = cosmwasm_std::to_vec(&signature).unwrap();
+        let parsed: SignatureBytes = cosmwasm_std::from_slice(&json).unwrap();
+        assert_eq!(parsed, signature);
+        assert_eq!(
+            json,
+            cosmwasm_std::to_vec(&Binary::from(signature)).unwrap()
+        );
+
+        assert!(pubkey.to_public_key().unwrap().verify(
+            &sha_256(b"test"),
+            signature.to_signature().unwrap(),
+            mock_api
+        ));
+
+        // wrong sizes are rejected
+        let short = cosmwasm_std::to_vec(&Binary(vec![1; SIGNATURE_SIZE - 1])).unwrap();
+        assert!(cosmwasm_std::from_slice::<SignatureBytes>(&short).is_err());
+        assert_eq!(
+            PubKeyBytes::try_from(&Binary(vec![2; PUBLIC_KEY_SIZE])),
+            Err(StdError::invalid_data_size(
+                COMPRESSED_PUBLIC_KEY_SIZE,
+                PUBLIC_KEY_SIZE
+            ))
+        );
+    }
+
+    #[cfg(feature = "hkdf")]
+    #[test]
+    fn test_derive_keypair() {
+        let (privkey, pubkey) = derive_keypair(b"seed", b"label");
+        let (privkey2, pubkey2) = derive_keypair(b"seed", b"label");
+        assert_eq!(privkey.serialize(), privkey2.serialize());
+        assert_eq!(pubkey.serialize(), pubkey2.serialize());
+        assert_eq!(pubkey.serialize(), privkey.pubkey().serialize());
+
+        let (other, _) = derive_keypair(b"seed", b"other label");
+        assert_ne!(privkey.serialize(), other.serialize());
+        let (other, _) = derive_keypair(b"other seed", b"label");
+        assert_ne!(privkey.serialize(), other.serialize());
+    }
 }
@@ -0,0 +1,334 @@
+//! A verifiable oblivious pseudorandom function (VOPRF) over secp256k1, for issuing blinded
+//! single-use tokens, in the style of Privacy Pass.
+//!
+//! The issuer holds a private key `k`. To get a token for an input `x` (e.g. a random nonce),
+//! the client blinds `x` with a random blind, and the issuer evaluates the blinded element
+//! with `k`, along with a proof that it used the key of its public key. The client checks the
+//! proof and unblinds the result into the token output. When the client later redeems `(x,
+//! output)`, the issuer recomputes the output from `x` and `k`, without being able to link it
+//! to the issuance, as it only ever saw the blinded element.
+//!
+//! Tokens are single-use only if the contract records the inputs it has redeemed.
+
+use cosmwasm_std::{Binary, StdError, StdResult};
+use secp256k1::{Scalar, Secp256k1};
+use subtle::ConstantTimeEq;
+
+use crate::secp256k1::{PrivateKey, PublicKey, PRIVATE_KEY_SIZE};
+use crate::sha_256;
+
+/// The size of the output of a token
+pub const VOPRF_OUTPUT_SIZE: usize = 32;
+/// The size of a serialized [`DleqProof`]
+pub const DLEQ_PROOF_SIZE: usize = 2 * PRIVATE_KEY_SIZE;
+
+const HASH_TO_CURVE_DOMAIN: &[u8] = b"secret-toolkit-voprf-hash-to-curve";
+const NONCE_DOMAIN: &[u8] = b"secret-toolkit-voprf-nonce";
+const CHALLENGE_DOMAIN: &[u8] = b"secret-toolkit-voprf-challenge";
+const OUTPUT_DOMAIN: &[u8] = b"secret-toolkit-voprf-output";
+
+/// A proof that an evaluated element was computed with the private key of the issuer's public
+/// key, i.e. that the discrete log of the public key and of the evaluated element relative to
+/// the blinded element are equal.
+pub struct DleqProof {
+    challenge: Scalar,
+    response: Scalar,
+}
+
+impl DleqProof {
+    pub fn serialize(&self) -> [u8; DLEQ_PROOF_SIZE] {
+        let mut bytes = [0u8; DLEQ_PROOF_SIZE];
+        bytes[..PRIVATE_KEY_SIZE].copy_from_slice(&self.challenge.to_be_bytes());
+        bytes[PRIVATE_KEY_SIZE..].copy_from_slice(&self.response.to_be_bytes());
+        bytes
+    }
+
+    pub fn parse(bytes: &[u8]) -> StdResult<Self> {
+        if bytes.len() != DLEQ_PROOF_SIZE {
+            return Err(StdError::invalid_data_size(DLEQ_PROOF_SIZE, bytes.len()));
+        }
+        let (challenge, response) = bytes.split_at(PRIVATE_KEY_SIZE);
+        Ok(Self {
+            challenge: to_scalar(challenge)?,
+            response: to_scalar(response)?,
+        })
+    }
+}
+
+impl From<&DleqProof> for Binary {
+    fn from(proof: &DleqProof) -> Self {
+        Binary(proof.serialize().to_vec())
+    }
+}
+
+/// Returns the point that an input is mapped to. Its discrete log is unknown.
+pub fn hash_to_curve(input: &[u8]) -> PublicKey {
+    let mut counter = 0u32;
+    loop {
+        let hash = sha_256(&[HASH_TO_CURVE_DOMAIN, &counter.to_be_bytes(), input].concat());
+        // half of the hashes are the x coordinate of a point, so this ends after a couple of
+        // tries
+        if let Ok(inner) = secp256k1::PublicKey::from_slice(&[[0x02].as_slice(), &hash].concat()) {
+            return PublicKey { inner };
+        }
+        counter = counter.wrapping_add(1);
+    }
+}
+
+/// Returns the blinded element of `input`, to be sent to the issuer. This is done by the
+/// client.
+///
+/// # Arguments
+///
+/// * `input` - the input of the token, which must be random and kept secret until redemption
+/// * `blind` - a random key, that must be kept to finalize the token
+pub fn blind(input: &[u8], blind: &PrivateKey) -> StdResult<PublicKey> {
+    combine(&hash_to_curve(input), &blind.pubkey())
+}
+
+/// Returns the blinded element evaluated with the issuer's private key, along with a proof
+/// that it was evaluated with that key. This is done by the issuer.
+///
+/// # Arguments
+///
+/// * `key` - the private key of the issuer
+/// * `blinded` - the blinded element sent by the client
+pub fn evaluate(key: &PrivateKey, blinded: &PublicKey) -> StdResult<(PublicKey, DleqProof)> {
+    let evaluated = mul(blinded, &Scalar::from(key.inner))?;
+
+    // the nonce is derived from the key and the blinded element, so it is never reused for
+    // another blinded element
+    let nonce = to_secret_key(&sha_256(
+        &[
+            NONCE_DOMAIN,
+            &key.serialize(),
+            &blinded.serialize_compressed(),
+        ]
+        .concat(),
+    ))?;
+    let nonce_commitment = PrivateKey { inner: nonce }.pubkey();
+    let blinded_commitment = mul(blinded, &Scalar::from(nonce))?;
+
+    let challenge = challenge(
+        &key.pubkey(),
+        blinded,
+        &evaluated,
+        &nonce_commitment,
+        &blinded_commitment,
+    )?;
+    // response = nonce - challenge * key
+    let product = key
+        .inner
+        .mul_tweak(&challenge)
+        .map_err(|err| StdError::generic_err(format!("voprf: {err}")))?;
+    let response = nonce
+        .add_tweak(&Scalar::from(product.negate()))
+        .map_err(|err| StdError::generic_err(format!("voprf: {err}")))?;
+
+    Ok((
+        evaluated,
+        DleqProof {
+            challenge,
+            response: Scalar::from(response),
+        },
+    ))
+}
+
+/// Verifies the proof of the evaluated element, and returns the output of the token. This is
+/// done by the client.
+///
+/// # Arguments
+///
+/// * `input` - the input of the token
+/// * `blind` - the blind used to blind the input
+/// * `issuer` - the public key of the issuer
+/// * `evaluated` - the evaluated element returned by the issuer
+/// * `proof` - the proof returned by the issuer
+pub fn finalize(
+    input: &[u8],
+    blind: &PrivateKey,
+    issuer: &PublicKey,
+    evaluated: &PublicKey,
+    proof: &DleqProof,
+) -> StdResult<[u8; VOPRF_OUTPUT_SIZE]> {
+    let blinded = self::blind(input, blind)?;
+    verify_proof(issuer, &blinded, evaluated, proof)?;
+
+    // evaluated = key * (H(input) + blind * G) = key * H(input) + blind * issuer
+    let unblinded = combine(
+        evaluated,
+        &negate(&mul(issuer, &Scalar::from(blind.inner))?),
+    )?;
+    Ok(output(input, &unblinded))
+}
+
+/// Returns the output of the token with the given input. This is done by the issuer when the
+/// token is redeemed, to check the output presented by the client.
+pub fn redemption_output(key: &PrivateKey, input: &[u8]) -> StdResult<[u8; VOPRF_OUTPUT_SIZE]> {
+    let evaluated = mul(&hash_to_curve(input), &Scalar::from(key.inner))?;
+    Ok(output(input, &evaluated))
+}
+
+/// Returns whether `output` is the output of the token with the given input. This is done by
+/// the issuer when the token is redeemed. The outputs are compared in constant time, so that
+/// the time it takes doesn't tell how much of a guessed output is right.
+pub fn verify_redemption(key: &PrivateKey, input: &[u8], output: &[u8]) -> StdResult<bool> {
+    Ok(bool::from(redemption_output(key, input)?.ct_eq(output)))
+}
+
+fn verify_proof(
+    issuer: &PublicKey,
+    blinded: &PublicKey,
+    evaluated: &PublicKey,
+    proof: &DleqProof,
+) -> StdResult<()> {
+    let invalid = || StdError::generic_err("voprf: invalid proof");
+    let response = to_secret_key(&proof.response.to_be_bytes()).map_err(|_| invalid())?;
+
+    // nonce * G = response * G + challenge * issuer
+    let nonce_commitment = combine(
+        &PrivateKey { inner: response }.pubkey(),
+        &mul(issuer, &proof.challenge)?,
+    )?;
+    // nonce * blinded = response * blinded + challenge * evaluated
+    let blinded_commitment = combine(
+        &mul(blinded, &proof.response)?,
+        &mul(evaluated, &proof.challenge)?,
+    )?;
+
+    let challenge = challenge(
+        issuer,
+        blinded,
+        evaluated,
+        &nonce_commitment,
+        &blinded_commitment,
+    )?;
+    if challenge != proof.challenge {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+fn challenge(
+    issuer: &PublicKey,
+    blinded: &PublicKey,
+    evaluated: &PublicKey,
+    nonce_commitment: &PublicKey,
+    blinded_commitment: &PublicKey,
+) -> StdResult<Scalar> {
+    let points = [
+        issuer,
+        blinded,
+        evaluated,
+        nonce_commitment,
+        blinded_commitment,
+    ];
+    let mut transcript = CHALLENGE_DOMAIN.to_vec();
+    for point in points {
+        transcript.extend_from_slice(&point.serialize_compressed());
+    }
+    to_scalar(&sha_256(&transcript))
+}
+
+fn output(input: &[u8], evaluated: &PublicKey) -> [u8; VOPRF_OUTPUT_SIZE] {
+    sha_256(
+        &[
+            OUTPUT_DOMAIN,
+            &(input.len() as u64).to_be_bytes(),
+            input,
+            &evaluated.serialize_compressed(),
+        ]
+        .concat(),
+    )
+}
+
+fn to_scalar(bytes: &[u8]) -> StdResult<Scalar> {
+    let bytes: [u8; PRIVATE_KEY_SIZE] = bytes
+        .try_into()
+        .map_err(|_| StdError::invalid_data_size(PRIVATE_KEY_SIZE, bytes.len()))?;
+    Scalar::from_be_bytes(bytes).map_err(|err| StdError::generic_err(format!("voprf: {err}")))
+}
+
+fn to_secret_key(bytes: &[u8]) -> StdResult<secp256k1::SecretKey> {
+    secp256k1::SecretKey::from_slice(bytes)
+        .map_err(|err| StdError::generic_err(format!("voprf: {err}")))
+}
+
+fn mul(point: &PublicKey, scalar: &Scalar) -> StdResult<PublicKey> {
+    let secp = Secp256k1::verification_only();
+    point
+        .inner
+        .mul_tweak(&secp, scalar)
+        .map(|inner| PublicKey { inner })
+        .map_err(|err| StdError::generic_err(format!("voprf: {err}")))
+}
+
+fn combine(a: &PublicKey, b: &PublicKey) -> StdResult<PublicKey> {
+    a.inner
+        .combine(&b.inner)
+        .map(|inner| PublicKey { inner })
+        .map_err(|err| StdError::generic_err(format!("voprf: {err}")))
+}
+
+fn negate(point: &PublicKey) -> PublicKey {
+    let secp = Secp256k1::verification_only();
+    PublicKey {
+        inner: point.inner.negate(&secp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issuer() -> PrivateKey {
+        PrivateKey::parse(&[7; PRIVATE_KEY_SIZE]).unwrap()
+    }
+
+    #[test]
+    fn test_issue_and_redeem() -> StdResult<()> {
+        let key = issuer();
+        let input = b"random token nonce";
+        let blind_key = PrivateKey::parse(&[9; PRIVATE_KEY_SIZE])?;
+
+        let blinded = self::blind(input, &blind_key)?;
+        let (evaluated, proof) = evaluate(&key, &blinded)?;
+        let proof = DleqProof::parse(&proof.serialize())?;
+        let output = finalize(input, &blind_key, &key.pubkey(), &evaluated, &proof)?;
+
+        assert!(verify_redemption(&key, input, &output)?);
+        assert!(!verify_redemption(&key, b"another nonce", &output)?);
+        let other = PrivateKey::parse(&[8; PRIVATE_KEY_SIZE])?;
+        assert!(!verify_redemption(&other, input, &output)?);
+        assert!(!verify_redemption(&key, input, &output[..VOPRF_OUTPUT_SIZE - 1])?);
+
+        // the issuer never sees anything derived from the input alone
+        assert_ne!(
+            blinded.serialize_compressed(),
+            hash_to_curve(input).serialize_compressed()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_proof() -> StdResult<()> {
+        let key = issuer();
+        let input = b"random token nonce";
+        let blind_key = PrivateKey::parse(&[9; PRIVATE_KEY_SIZE])?;
+        let blinded = self::blind(input, &blind_key)?;
+
+        // an issuer that evaluates with another key than the one it published is detected
+        let other = PrivateKey::parse(&[8; PRIVATE_KEY_SIZE])?;
+        let (evaluated, proof) = evaluate(&other, &blinded)?;
+        assert!(finalize(input, &blind_key, &key.pubkey(), &evaluated, &proof).is_err());
+
+        let (evaluated, proof) = evaluate(&key, &blinded)?;
+        let mut bytes = proof.serialize();
+        bytes[DLEQ_PROOF_SIZE - 1] ^= 1;
+        let tampered = DleqProof::parse(&bytes)?;
+        assert!(finalize(input, &blind_key, &key.pubkey(), &evaluated, &tampered).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+//! A verifiable random function (VRF) over secp256k1, following the ECVRF of RFC 9381.
+//!
+//! The holder of a private key computes, for any input, an output that looks random to anyone
+//! without the key, along with a proof that it is the only output of that input for its public
+//! key. A contract can thus accept randomness submitted by a designated key holder, e.g. for a
+//! lottery draw, and anyone can later audit the draw with the input, the proof and the public key.
+//!
+//! RFC 9381 defines no ciphersuite for secp256k1, so this module runs the procedures of its
+//! ECVRF-P256-SHA256-TAI suite (section 5.5) on secp256k1: the input is encoded to the curve by
+//! try-and-increment, the nonce is generated as in RFC 6979, and challenges and outputs are
+//! hashed with SHA-256. The suite string is `0xFE` instead of the `0x01` of P-256, so proofs
+//! of the two suites can't be mixed up. The same procedures are tested with P-256 against the
+//! examples of the RFC.
+//!
+//! As there is a single valid output for each input, the key holder can't bias the randomness,
+//! as long as the input is fixed before the key holder can act on it, e.g. the id of the draw.
+//! It can only withhold it, so contracts should combine it with [`ContractPrng`] and decide what
+//! happens when no randomness is submitted in time.
+
+use cosmwasm_std::{Binary, StdError, StdResult};
+use k256::elliptic_curve::{
+    self,
+    bigint::{ArrayEncoding, U256},
+    consts::U32,
+    group::Curve,
+    ops::Reduce,
+    sec1::{EncodedPoint, FromEncodedPoint, ToEncodedPoint},
+    AffinePoint, CurveArithmetic, FieldBytes, Group, PrimeField, ProjectivePoint, Scalar,
+};
+use k256::Secp256k1;
+use sha2::{Digest, Sha256};
+
+use crate::secp256k1::{PrivateKey, PublicKey, COMPRESSED_PUBLIC_KEY_SIZE, PRIVATE_KEY_SIZE};
+use crate::ContractPrng;
+
+/// The size of the output of the VRF
+pub const VRF_OUTPUT_SIZE: usize = 32;
+/// The size of a serialized [`VrfProof`]
+pub const VRF_PROOF_SIZE: usize = COMPRESSED_PUBLIC_KEY_SIZE + CHALLENGE_SIZE + PRIVATE_KEY_SIZE;
+
+/// The suite string of ECVRF-SECP256K1-SHA256-TAI
+const SUITE_STRING: u8 = 0xFE;
+/// The size of challenges, half the size of scalars as in the suites of RFC 9381
+const CHALLENGE_SIZE: usize = 16;
+
+const ENCODE_TO_CURVE_DOMAIN: u8 = 0x01;
+const CHALLENGE_DOMAIN: u8 = 0x02;
+const PROOF_TO_HASH_DOMAIN: u8 = 0x03;
+const DOMAIN_BACK: u8 = 0x00;
+
+/// A proof that `gamma` is the input encoded to the curve and multiplied by the private key of a
+/// public key. The output of the VRF is derived from `gamma`.
+pub struct VrfProof {
+    inner: Proof<Secp256k1>,
+}
+
+impl VrfProof {
+    pub fn serialize(&self) -> [u8; VRF_PROOF_SIZE] {
+        self.inner.serialize()
+    }
+
+    pub fn parse(bytes: &[u8]) -> StdResult<Self> {
+        Proof::parse(bytes).map(|inner| Self { inner })
+    }
+
+    /// Returns the output of the VRF, without verifying the proof. Use [`verify`] to get the
+    /// output of a proof that was not computed locally.
+    pub fn output(&self) -> [u8; VRF_OUTPUT_SIZE] {
+        self.inner.output(SUITE_STRING)
+    }
+}
+
+impl From<&VrfProof> for Binary {
+    fn from(proof: &VrfProof) -> Self {
+        Binary(proof.serialize().to_vec())
+    }
+}
+
+/// Returns the proof of the VRF for the given input, from which the output can be derived with
+/// [`VrfProof::output`]. This is done by the key holder.
+pub fn prove(key: &PrivateKey, input: &[u8]) -> StdResult<VrfProof> {
+    let secret = Option::from(Scalar::<Secp256k1>::from_repr(key.serialize().into()))
+        .ok_or_else(|| StdError::generic_err("vrf: invalid private key"))?;
+    Ok(VrfProof {
+        inner: Proof::prove(SUITE_STRING, &secret, input),
+    })
+}
+
+/// Verifies the proof of the VRF for the given input and public key, and returns its output.
+/// This is done by the contract, or anyone auditing it.
+pub fn verify(
+    pubkey: &PublicKey,
+    input: &[u8],
+    proof: &VrfProof,
+) -> StdResult<[u8; VRF_OUTPUT_SIZE]> {
+    let pubkey = parse_point::<Secp256k1>(&pubkey.serialize_compressed())
+        .ok_or_else(|| StdError::generic_err("vrf: invalid public key"))?;
+    proof.inner.verify(SUITE_STRING, &pubkey, input)
+}
+
+/// Returns a [`ContractPrng`] seeded with the output of the VRF, e.g. to draw the winners of a
+/// lottery. `entropy` can be used to derive several independent generators from one output.
+pub fn prng(output: &[u8; VRF_OUTPUT_SIZE], entropy: &[u8]) -> ContractPrng {
+    ContractPrng::new(output, entropy)
+}
+
+/// The curves with 32 byte fields and cofactor 1 that the TAI suites of RFC 9381 work on
+trait TaiCurve: CurveArithmetic + elliptic_curve::Curve<FieldBytesSize = U32, Uint = U256>
+where
+    AffinePoint<Self>: FromEncodedPoint<Self> + ToEncodedPoint<Self>,
+{
+}
+
+impl<C> TaiCurve for C
+where
+    C: CurveArithmetic + elliptic_curve::Curve<FieldBytesSize = U32, Uint = U256>,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+}
+
+struct Proof<C: TaiCurve>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    gamma: ProjectivePoint<C>,
+    challenge: [u8; CHALLENGE_SIZE],
+    response: Scalar<C>,
+}
+
+impl<C: TaiCurve> Proof<C>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    /// ECVRF_prove of section 5.1
+    fn prove(suite: u8, secret: &Scalar<C>, input: &[u8]) -> Self {
+        let pubkey = serialize_point::<C>(&(ProjectivePoint::<C>::generator() * secret));
+        let hashed = encode_to_curve::<C>(suite, &pubkey, input);
+        let gamma = hashed * secret;
+
+        let nonce = nonce::<C>(secret, &hashed);
+        let challenge = challenge(
+            suite,
+            [
+                &pubkey,
+                &serialize_point::<C>(&hashed),
+                &serialize_point::<C>(&gamma),
+                &serialize_point::<C>(&(ProjectivePoint::<C>::generator() * nonce)),
+                &serialize_point::<C>(&(hashed * nonce)),
+            ],
+        );
+        let response = nonce + challenge_scalar::<C>(&challenge) * secret;
+
+        Self {
+            gamma,
+            challenge,
+            response,
+        }
+    }
+
+    /// ECVRF_verify of section 5.3
+    fn verify(
+        &self,
+        suite: u8,
+        pubkey: &ProjectivePoint<C>,
+        input: &[u8],
+    ) -> StdResult<[u8; VRF_OUTPUT_SIZE]> {
+        // the curves have cofactor 1, so the only public key of low order is the identity
+        if bool::from(pubkey.is_identity()) {
+            return Err(StdError::generic_err("vrf: invalid public key"));
+        }
+        let pubkey_bytes = serialize_point::<C>(pubkey);
+        let hashed = encode_to_curve::<C>(suite, &pubkey_bytes, input);
+        let scalar = challenge_scalar::<C>(&self.challenge);
+
+        let key_commitment = ProjectivePoint::<C>::generator() * self.response - *pubkey * scalar;
+        let hashed_commitment = hashed * self.response - self.gamma * scalar;
+        let expected = challenge(
+            suite,
+            [
+                &pubkey_bytes,
+                &serialize_point::<C>(&hashed),
+                &serialize_point::<C>(&self.gamma),
+                &serialize_point::<C>(&key_commitment),
+                &serialize_point::<C>(&hashed_commitment),
+            ],
+        );
+        if expected != self.challenge {
+            return Err(StdError::generic_err("vrf: invalid proof"));
+        }
+        Ok(self.output(suite))
+    }
+
+    /// ECVRF_proof_to_hash of section 5.2
+    fn output(&self, suite: u8) -> [u8; VRF_OUTPUT_SIZE] {
+        Sha256::new()
+            .chain_update([suite, PROOF_TO_HASH_DOMAIN])
+            .chain_update(serialize_point::<C>(&self.gamma))
+            .chain_update([DOMAIN_BACK])
+            .finalize()
+            .into()
+    }
+
+    fn serialize(&self) -> [u8; VRF_PROOF_SIZE] {
+        let mut bytes = [0u8; VRF_PROOF_SIZE];
+        let (gamma, rest) = bytes.split_at_mut(COMPRESSED_PUBLIC_KEY_SIZE);
+        let (challenge, response) = rest.split_at_mut(CHALLENGE_SIZE);
+        gamma.copy_from_slice(&serialize_point::<C>(&self.gamma));
+        challenge.copy_from_slice(&self.challenge);
+        response.copy_from_slice(&self.response.to_repr());
+        bytes
+    }
+
+    /// ECVRF_decode_proof of section 5.4.4
+    fn parse(bytes: &[u8]) -> StdResult<Self> {
+        if bytes.len() != VRF_PROOF_SIZE {
+            return Err(StdError::invalid_data_size(VRF_PROOF_SIZE, bytes.len()));
+        }
+        let invalid = || StdError::generic_err("vrf: invalid proof");
+        let (gamma, rest) = bytes.split_at(COMPRESSED_PUBLIC_KEY_SIZE);
+        let (challenge, response) = rest.split_at(CHALLENGE_SIZE);
+        Ok(Self {
+            gamma: parse_point::<C>(gamma).ok_or_else(invalid)?,
+            challenge: challenge.try_into().map_err(|_| invalid())?,
+            // responses of q or more are rejected
+            response: Option::from(Scalar::<C>::from_repr(*FieldBytes::<C>::from_slice(
+                response,
+            )))
+            .ok_or_else(invalid)?,
+        })
+    }
+}
+
+/// ECVRF_encode_to_curve_try_and_increment of section 5.4.1.1
+fn encode_to_curve<C: TaiCurve>(suite: u8, pubkey: &[u8], input: &[u8]) -> ProjectivePoint<C>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    for counter in 0..=u8::MAX {
+        let hash = Sha256::new()
+            .chain_update([suite, ENCODE_TO_CURVE_DOMAIN])
+            .chain_update(pubkey)
+            .chain_update(input)
+            .chain_update([counter, DOMAIN_BACK])
+            .finalize();
+        // half of the hashes are the x coordinate of a point, so this ends after a couple of
+        // tries
+        if let Some(point) = parse_point::<C>(&[[0x02].as_slice(), &hash].concat()) {
+            return point;
+        }
+    }
+    // the odds of 256 hashes in a row not being on the curve are 2^-256
+    unreachable!("vrf: no valid point after 256 tries")
+}
+
+/// ECVRF_nonce_generation_RFC6979 of section 5.4.2.1
+fn nonce<C: TaiCurve>(secret: &Scalar<C>, hashed: &ProjectivePoint<C>) -> Scalar<C>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    let digest = Sha256::digest(serialize_point::<C>(hashed));
+    let digest = <Scalar<C> as Reduce<U256>>::reduce_bytes(&digest);
+    let nonce = rfc6979::generate_k::<Sha256, U32>(
+        &secret.to_repr(),
+        &C::ORDER.to_be_byte_array(),
+        &digest.to_repr(),
+        b"",
+    );
+    // generate_k only returns scalars in [1, q)
+    Option::from(Scalar::<C>::from_repr(nonce)).unwrap_or_default()
+}
+
+/// ECVRF_challenge_generation of section 5.4.3
+fn challenge(suite: u8, points: [&[u8]; 5]) -> [u8; CHALLENGE_SIZE] {
+    let mut hasher = Sha256::new().chain_update([suite, CHALLENGE_DOMAIN]);
+    for point in points {
+        hasher.update(point);
+    }
+    let hash = hasher.chain_update([DOMAIN_BACK]).finalize();
+    let mut challenge = [0u8; CHALLENGE_SIZE];
+    challenge.copy_from_slice(&hash[..CHALLENGE_SIZE]);
+    challenge
+}
+
+fn challenge_scalar<C: TaiCurve>(challenge: &[u8; CHALLENGE_SIZE]) -> Scalar<C>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    let mut bytes = FieldBytes::<C>::default();
+    bytes[PRIVATE_KEY_SIZE - CHALLENGE_SIZE..].copy_from_slice(challenge);
+    // 128 bit integers are always smaller than q
+    <Scalar<C> as Reduce<U256>>::reduce_bytes(&bytes)
+}
+
+fn serialize_point<C: TaiCurve>(point: &ProjectivePoint<C>) -> Vec<u8>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    point.to_affine().to_encoded_point(true).as_bytes().to_vec()
+}
+
+/// Parses a compressed point, rejecting the identity
+fn parse_point<C: TaiCurve>(bytes: &[u8]) -> Option<ProjectivePoint<C>>
+where
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    if bytes.len() != COMPRESSED_PUBLIC_KEY_SIZE {
+        return None;
+    }
+    let point = EncodedPoint::<C>::from_bytes(bytes).ok()?;
+    Option::<AffinePoint<C>>::from(AffinePoint::<C>::from_encoded_point(&point))
+        .map(ProjectivePoint::<C>::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> PrivateKey {
+        PrivateKey::parse(&[7; PRIVATE_KEY_SIZE]).unwrap()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn unhex(string: &str) -> Vec<u8> {
+        (0..string.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&string[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Examples 10 and 11 of ECVRF-P256-SHA256-TAI in appendix B.1 of RFC 9381
+    #[test]
+    fn test_rfc_9381_vectors() -> StdResult<()> {
+        type P256 = p256::NistP256;
+        const P256_SUITE_STRING: u8 = 0x01;
+
+        let secret = Scalar::<P256>::from_repr(*FieldBytes::<P256>::from_slice(&unhex(
+            "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        )))
+        .unwrap();
+        let pubkey = <ProjectivePoint<P256> as Group>::generator() * secret;
+        assert_eq!(
+            hex(&serialize_point::<P256>(&pubkey)),
+            "0360fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"
+        );
+
+        let vectors = [
+            (
+                b"sample".as_slice(),
+                "0272a877532e9ac193aff4401234266f59900a4a9e3fc3cfc6a4b7e467a15d06d4",
+                "035b5c726e8c0e2c488a107c600578ee75cb702343c153cb1eb8dec77f4b5071b4a53f0a46f018bc2c56e58d383f2305e0975972c26feea0eb122fe7893c15af376b33edf7de17c6ea056d4d82de6bc02f",
+                "a3ad7b0ef73d8fc6655053ea22f9bede8c743f08bbed3d38821f0e16474b505e",
+            ),
+            (
+                b"test".as_slice(),
+                "02173119b4fff5e6f8afed4868a29fe8920f1b54c2cf89cc7b301d0d473de6b974",
+                "034dac60aba508ba0c01aa9be80377ebd7562c4a52d74722e0abae7dc3080ddb56c19e067b15a8a8174905b13617804534214f935b94c2287f797e393eb0816969d864f37625b443f30f1a5a33f2b3c854",
+                "a284f94ceec2ff4b3794629da7cbafa49121972671b466cab4ce170aa365f26d",
+            ),
+        ];
+        for (input, hashed, pi, beta) in vectors {
+            let encoded = encode_to_curve::<P256>(
+                P256_SUITE_STRING,
+                &serialize_point::<P256>(&pubkey),
+                input,
+            );
+            assert_eq!(hex(&serialize_point::<P256>(&encoded)), hashed);
+
+            let proof = Proof::<P256>::prove(P256_SUITE_STRING, &secret, input);
+            assert_eq!(hex(&proof.serialize()), pi);
+            assert_eq!(hex(&proof.output(P256_SUITE_STRING)), beta);
+
+            let proof = Proof::<P256>::parse(&unhex(pi))?;
+            let output = proof.verify(P256_SUITE_STRING, &pubkey, input)?;
+            assert_eq!(hex(&output), beta);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prove_and_verify() -> StdResult<()> {
+        let key = key();
+        let input = b"lottery round 12";
+
+        let proof = prove(&key, input)?;
+        let proof = VrfProof::parse(&proof.serialize())?;
+        let output = verify(&key.pubkey(), input, &proof)?;
+        assert_eq!(output, proof.output());
+
+        // the output is deterministic, and differs for other inputs and keys
+        assert_eq!(prove(&key, input)?.output(), output);
+        assert_ne!(prove(&key, b"lottery round 13")?.output(), output);
+        let other = PrivateKey::parse(&[8; PRIVATE_KEY_SIZE])?;
+        assert_ne!(prove(&other, input)?.output(), output);
+
+        // the suite string separates the outputs from those of other suites on secp256k1
+        let secret = Scalar::<Secp256k1>::from_repr(key.serialize().into()).unwrap();
+        assert_ne!(
+            Proof::<Secp256k1>::prove(0x01, &secret, input).output(0x01),
+            output
+        );
+        assert_eq!(
+            (<ProjectivePoint<Secp256k1> as Group>::generator() * secret)
+                .to_affine()
+                .to_encoded_point(true)
+                .as_bytes(),
+            key.pubkey().serialize_compressed()
+        );
+
+        let mut prng = prng(&output, b"winners");
+        assert_eq!(
+            prng.rand_bytes(),
+            ContractPrng::new(&output, b"winners").rand_bytes()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_proof() -> StdResult<()> {
+        let key = key();
+        let input = b"lottery round 12";
+        let proof = prove(&key, input)?;
+
+        assert!(verify(&key.pubkey(), b"lottery round 13", &proof).is_err());
+        let other = PrivateKey::parse(&[8; PRIVATE_KEY_SIZE])?;
+        assert!(verify(&other.pubkey(), input, &proof).is_err());
+
+        // swapping gamma for the one of another input is detected
+        let mut bytes = proof.serialize();
+        bytes[..COMPRESSED_PUBLIC_KEY_SIZE].copy_from_slice(
+            &prove(&key, b"lottery round 13")?.serialize()[..COMPRESSED_PUBLIC_KEY_SIZE],
+        );
+        assert!(verify(&key.pubkey(), input, &VrfProof::parse(&bytes)?).is_err());
+
+        let mut bytes = proof.serialize();
+        bytes[VRF_PROOF_SIZE - 1] ^= 1;
+        assert!(verify(&key.pubkey(), input, &VrfProof::parse(&bytes)?).is_err());
+
+        // responses of q or more don't parse
+        let mut bytes = proof.serialize();
+        bytes[VRF_PROOF_SIZE - PRIVATE_KEY_SIZE..].copy_from_slice(&[0xff; PRIVATE_KEY_SIZE]);
+        assert!(VrfProof::parse(&bytes).is_err());
+        assert!(VrfProof::parse(&bytes[1..]).is_err());
+
+        Ok(())
+    }
+}
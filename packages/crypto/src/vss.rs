@@ -0,0 +1,419 @@
+//! Verification of Feldman verifiable secret sharing (VSS) over secp256k1, for coordinating a
+//! `(t, n)` distributed key generation among off-chain participants.
+//!
+//! Each participant deals shares of a random secret with a polynomial of degree `t - 1`, whose
+//! coefficients `a_0, ..., a_{t-1}` it commits to publicly as `a_j * G`. The participant with
+//! index `i` (from 1 to `n`) receives the share `f(i)` privately, and checks it against the
+//! commitments. When a share doesn't match, its recipient files a complaint with the contract,
+//! and the accused dealer answers it by revealing the share publicly. The contract checks the
+//! revealed share, and disqualifies the dealer if it is invalid, or if the dealer doesn't answer
+//! in time. A complaint alone never disqualifies a dealer, so a participant can't get an honest
+//! dealer disqualified. The group public key is the sum of the commitments to `a_0` of the
+//! qualified dealers, and any `t` participants can sign or decrypt with their summed shares.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult};
+use secp256k1::{Scalar, Secp256k1};
+
+use crate::secp256k1::{PrivateKey, PubKeyBytes, PublicKey};
+
+/// Returns the public key of the share of the participant with index `index`, i.e. `f(index) *
+/// G`, computed from the commitments to the coefficients of the polynomial `f`, from `a_0` up.
+pub fn share_public_key(commitments: &[PubKeyBytes], index: u32) -> StdResult<PublicKey> {
+    if index == 0 {
+        return Err(StdError::generic_err("vss: participant indexes start at 1"));
+    }
+    let mut index_bytes = [0u8; 32];
+    index_bytes[28..].copy_from_slice(&index.to_be_bytes());
+    let index = Scalar::from_be_bytes(index_bytes)
+        .map_err(|err| StdError::generic_err(format!("vss: {err}")))?;
+
+    // Horner's method: f(i) * G = (((a_{t-1} * i + a_{t-2}) * i + ...) * i + a_0) * G
+    let secp = Secp256k1::verification_only();
+    let mut commitments = commitments.iter().rev();
+    let last = commitments
+        .next()
+        .ok_or_else(|| StdError::generic_err("vss: no commitments"))?;
+    let mut point = last.to_public_key()?.inner;
+    for commitment in commitments {
+        let commitment = commitment.to_public_key()?;
+        point = point
+            .mul_tweak(&secp, &index)
+            .and_then(|point| point.combine(&commitment.inner))
+            .map_err(|err| StdError::generic_err(format!("vss: {err}")))?;
+    }
+    Ok(PublicKey { inner: point })
+}
+
+/// Returns whether `share` is the share of the participant with index `index` of the polynomial
+/// committed to by `commitments`
+pub fn verify_share(
+    commitments: &[PubKeyBytes],
+    index: u32,
+    share: &PrivateKey,
+) -> StdResult<bool> {
+    let expected = share_public_key(commitments, index)?;
+    Ok(share.pubkey().serialize_compressed() == expected.serialize_compressed())
+}
+
+/// The commitments published by a dealer
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Dealing {
+    pub dealer: u32,
+    pub commitments: Vec<PubKeyBytes>,
+}
+
+/// A complaint of a participant about the share it received from a dealer
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Complaint {
+    pub dealer: u32,
+    pub recipient: u32,
+}
+
+/// The state of a distributed key generation, to be stored by the coordinating contract
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Dkg {
+    /// the number of shares needed to use the key
+    pub threshold: u32,
+    /// the number of participants, whose indexes go from 1 to `participants`
+    pub participants: u32,
+    /// the dealings submitted so far
+    pub dealings: Vec<Dealing>,
+    /// the complaints that their dealer didn't answer yet
+    pub complaints: Vec<Complaint>,
+    /// the dealers that dealt an invalid share, or didn't answer a complaint, sorted
+    pub misbehaving: Vec<u32>,
+}
+
+impl Dkg {
+    /// Starts a `(threshold, participants)` distributed key generation
+    pub fn new(threshold: u32, participants: u32) -> StdResult<Self> {
+        if threshold == 0 || threshold > participants {
+            return Err(StdError::generic_err(
+                "vss: the threshold must be between 1 and the number of participants",
+            ));
+        }
+        Ok(Self {
+            threshold,
+            participants,
+            dealings: vec![],
+            complaints: vec![],
+            misbehaving: vec![],
+        })
+    }
+
+    fn check_index(&self, index: u32) -> StdResult<()> {
+        if index == 0 || index > self.participants {
+            return Err(StdError::generic_err(format!(
+                "vss: unknown participant {index}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn dealing(&self, dealer: u32) -> Option<&Dealing> {
+        self.dealings
+            .iter()
+            .find(|dealing| dealing.dealer == dealer)
+    }
+
+    /// Records the commitments of a dealer. Fails if the dealer already submitted its
+    /// commitments, or if there isn't one valid commitment per coefficient.
+    pub fn submit_commitments(
+        &mut self,
+        dealer: u32,
+        commitments: Vec<PubKeyBytes>,
+    ) -> StdResult<()> {
+        self.check_index(dealer)?;
+        if self.dealing(dealer).is_some() {
+            return Err(StdError::generic_err(format!(
+                "vss: dealer {dealer} already submitted its commitments"
+            )));
+        }
+        if commitments.len() != self.threshold as usize {
+            return Err(StdError::generic_err(format!(
+                "vss: expected {} commitments, got {}",
+                self.threshold,
+                commitments.len()
+            )));
+        }
+        for commitment in &commitments {
+            commitment.to_public_key()?;
+        }
+        self.dealings.push(Dealing {
+            dealer,
+            commitments,
+        });
+        Ok(())
+    }
+
+    /// Records the complaint of `recipient` about the share it received from `dealer`, which the
+    /// dealer must then answer with `answer_complaint`. Fails if the dealer has no commitments,
+    /// or if the same complaint is already pending.
+    pub fn complain(&mut self, dealer: u32, recipient: u32) -> StdResult<()> {
+        self.check_index(recipient)?;
+        if self.dealing(dealer).is_none() {
+            return Err(StdError::generic_err(format!(
+                "vss: dealer {dealer} has no commitments"
+            )));
+        }
+        let complaint = Complaint { dealer, recipient };
+        if self.complaints.contains(&complaint) {
+            return Err(StdError::generic_err(format!(
+                "vss: participant {recipient} already complained about dealer {dealer}"
+            )));
+        }
+        self.complaints.push(complaint);
+        Ok(())
+    }
+
+    /// Resolves a pending complaint with the share of `recipient` that `dealer` revealed, and
+    /// disqualifies the dealer if the share is invalid. Returns whether the share is valid, in
+    /// which case the complaint was unfounded, and the recipient should use the revealed share.
+    pub fn answer_complaint(
+        &mut self,
+        dealer: u32,
+        recipient: u32,
+        share: &PrivateKey,
+    ) -> StdResult<bool> {
+        let complaint = Complaint { dealer, recipient };
+        let pos = self
+            .complaints
+            .iter()
+            .position(|pending| *pending == complaint)
+            .ok_or_else(|| {
+                StdError::generic_err(format!(
+                    "vss: participant {recipient} has no pending complaint about dealer {dealer}"
+                ))
+            })?;
+        // will never fail since complaints are only recorded for dealers with commitments.
+        let dealing = self.dealing(dealer).unwrap();
+        let valid = verify_share(&dealing.commitments, recipient, share)?;
+        self.complaints.remove(pos);
+        if !valid {
+            self.disqualify(dealer);
+        }
+        Ok(valid)
+    }
+
+    /// Disqualifies the dealers of the complaints that weren't answered, once the time given to
+    /// answer them is over, and returns them, sorted
+    pub fn expire_complaints(&mut self) -> Vec<u32> {
+        let mut dealers: Vec<u32> = self
+            .complaints
+            .drain(..)
+            .map(|complaint| complaint.dealer)
+            .collect();
+        dealers.sort_unstable();
+        dealers.dedup();
+        for dealer in &dealers {
+            self.disqualify(*dealer);
+        }
+        dealers
+    }
+
+    fn disqualify(&mut self, dealer: u32) {
+        if let Err(pos) = self.misbehaving.binary_search(&dealer) {
+            self.misbehaving.insert(pos, dealer);
+        }
+    }
+
+    /// Returns whether a dealer was disqualified
+    pub fn is_misbehaving(&self, dealer: u32) -> bool {
+        self.misbehaving.binary_search(&dealer).is_ok()
+    }
+
+    /// Returns the dealers that submitted their commitments and weren't disqualified, sorted
+    pub fn qualified(&self) -> Vec<u32> {
+        let mut qualified: Vec<u32> = self
+            .dealings
+            .iter()
+            .map(|dealing| dealing.dealer)
+            .filter(|dealer| !self.is_misbehaving(*dealer))
+            .collect();
+        qualified.sort_unstable();
+        qualified
+    }
+
+    /// Returns the sum of the points computed by `point` for each qualified dealing. Fails while
+    /// complaints are pending, as the qualified dealers aren't known yet.
+    fn sum(&self, point: impl Fn(&Dealing) -> StdResult<PublicKey>) -> StdResult<PublicKey> {
+        if !self.complaints.is_empty() {
+            return Err(StdError::generic_err("vss: complaints are pending"));
+        }
+        let mut points = self
+            .dealings
+            .iter()
+            .filter(|dealing| !self.is_misbehaving(dealing.dealer))
+            .map(point);
+        let first = points
+            .next()
+            .ok_or_else(|| StdError::generic_err("vss: no qualified dealers"))??;
+        points.try_fold(first, |sum, point| {
+            sum.inner
+                .combine(&point?.inner)
+                .map(|inner| PublicKey { inner })
+                .map_err(|err| StdError::generic_err(format!("vss: {err}")))
+        })
+    }
+
+    /// Returns the public key of the group, which is the sum of the commitments to the secrets
+    /// of the qualified dealers
+    pub fn group_public_key(&self) -> StdResult<PublicKey> {
+        self.sum(|dealing| dealing.commitments[0].to_public_key())
+    }
+
+    /// Returns the public key of the share of the group key held by a participant, which is the
+    /// sum of the shares it received from the qualified dealers, e.g. to verify its partial
+    /// signatures
+    pub fn public_key_share(&self, index: u32) -> StdResult<PublicKey> {
+        self.check_index(index)?;
+        self.sum(|dealing| share_public_key(&dealing.commitments, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secp256k1::PRIVATE_KEY_SIZE;
+
+    fn scalar(value: u32) -> Scalar {
+        let mut bytes = [0u8; PRIVATE_KEY_SIZE];
+        bytes[28..].copy_from_slice(&value.to_be_bytes());
+        Scalar::from_be_bytes(bytes).unwrap()
+    }
+
+    /// A dealer with the polynomial of the given coefficients, from `a_0` up
+    struct Dealer(Vec<secp256k1::SecretKey>);
+
+    impl Dealer {
+        fn new(seed: u8, threshold: usize) -> Self {
+            Self(
+                (0..threshold)
+                    .map(|j| secp256k1::SecretKey::from_slice(&[seed + j as u8; 32]).unwrap())
+                    .collect(),
+            )
+        }
+
+        fn commitments(&self) -> Vec<PubKeyBytes> {
+            self.0
+                .iter()
+                .map(|coefficient| {
+                    PubKeyBytes::from(
+                        &PrivateKey {
+                            inner: *coefficient,
+                        }
+                        .pubkey(),
+                    )
+                })
+                .collect()
+        }
+
+        fn share(&self, index: u32) -> PrivateKey {
+            let mut coefficients = self.0.iter().rev();
+            let mut share = *coefficients.next().unwrap();
+            for coefficient in coefficients {
+                share = share
+                    .mul_tweak(&scalar(index))
+                    .unwrap()
+                    .add_tweak(&Scalar::from(*coefficient))
+                    .unwrap();
+            }
+            PrivateKey { inner: share }
+        }
+    }
+
+    #[test]
+    fn test_verify_share() -> StdResult<()> {
+        let dealer = Dealer::new(1, 3);
+        let commitments = dealer.commitments();
+        for index in 1..=5 {
+            assert!(verify_share(&commitments, index, &dealer.share(index))?);
+        }
+        assert!(!verify_share(&commitments, 2, &dealer.share(1))?);
+        assert!(!verify_share(&commitments[..2], 2, &dealer.share(2))?);
+        assert!(verify_share(&commitments, 0, &dealer.share(1)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dkg() -> StdResult<()> {
+        let dealers = [Dealer::new(1, 2), Dealer::new(10, 2), Dealer::new(20, 2)];
+        let mut dkg = Dkg::new(2, 3)?;
+        assert!(Dkg::new(4, 3).is_err());
+
+        for (dealer, index) in dealers.iter().zip(1..) {
+            dkg.submit_commitments(index, dealer.commitments())?;
+        }
+        assert!(dkg.submit_commitments(1, dealers[0].commitments()).is_err());
+        assert!(dkg.submit_commitments(4, dealers[0].commitments()).is_err());
+        assert_eq!(dkg.qualified(), vec![1, 2, 3]);
+
+        // an unfounded complaint doesn't disqualify the dealer
+        dkg.complain(2, 3)?;
+        assert!(dkg.complain(2, 3).is_err());
+        assert!(dkg.group_public_key().is_err());
+        assert!(dkg.answer_complaint(2, 3, &dealers[1].share(3))?);
+        assert!(dkg.answer_complaint(2, 3, &dealers[1].share(3)).is_err());
+        // dealer 3 dealt a share of another polynomial to participant 1
+        dkg.complain(3, 1)?;
+        assert!(!dkg.answer_complaint(3, 1, &dealers[0].share(1))?);
+        assert!(dkg.is_misbehaving(3));
+        assert_eq!(dkg.misbehaving, vec![3]);
+        assert_eq!(dkg.qualified(), vec![1, 2]);
+
+        // the group key is the sum of the secrets of the qualified dealers
+        let secret = dealers[0].0[0]
+            .add_tweak(&Scalar::from(dealers[1].0[0]))
+            .unwrap();
+        assert_eq!(
+            dkg.group_public_key()?.serialize_compressed(),
+            PrivateKey { inner: secret }.pubkey().serialize_compressed()
+        );
+        let share = dealers[0]
+            .share(2)
+            .inner
+            .add_tweak(&Scalar::from(dealers[1].share(2).inner));
+        assert_eq!(
+            dkg.public_key_share(2)?.serialize_compressed(),
+            PrivateKey {
+                inner: share.unwrap()
+            }
+            .pubkey()
+            .serialize_compressed()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dkg_false_complaint() -> StdResult<()> {
+        let dealers = [Dealer::new(1, 2), Dealer::new(10, 2)];
+        let mut dkg = Dkg::new(2, 3)?;
+        for (dealer, index) in dealers.iter().zip(1..) {
+            dkg.submit_commitments(index, dealer.commitments())?;
+        }
+
+        // participant 3 falsely complains about dealer 1, which answers with the valid share
+        dkg.complain(1, 3)?;
+        assert!(dkg.answer_complaint(1, 3, &dealers[0].share(3))?);
+        // a dealer that didn't receive a complaint can't be made to answer one
+        assert!(dkg.answer_complaint(2, 3, &dealers[0].share(3)).is_err());
+        assert!(dkg.expire_complaints().is_empty());
+        assert!(!dkg.is_misbehaving(1));
+        assert_eq!(dkg.qualified(), vec![1, 2]);
+
+        // a dealer that doesn't answer is disqualified
+        dkg.complain(2, 1)?;
+        dkg.complain(2, 3)?;
+        assert_eq!(dkg.expire_complaints(), vec![2]);
+        assert!(dkg.complaints.is_empty());
+        assert_eq!(dkg.qualified(), vec![1]);
+        dkg.group_public_key()?;
+
+        Ok(())
+    }
+}
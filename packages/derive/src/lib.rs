@@ -0,0 +1,287 @@
+#![doc = include_str!("../Readme.md")]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, ExprLit, Fields, Lit, LitByteStr, Meta,
+    Path, Result,
+};
+
+/// Derives the const definitions of the storage collections of a contract from a struct whose
+/// fields are the collections, each with its namespace. See the crate documentation.
+#[proc_macro_derive(ContractState, attributes(namespace, contract_state))]
+pub fn derive_contract_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_contract_state(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A field of the state
+struct Collection {
+    field: syn::Ident,
+    vis: syn::Visibility,
+    ty: syn::Type,
+    namespace: Vec<u8>,
+    namespace_span: Span,
+    save_default: bool,
+}
+
+fn expand_contract_state(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "ContractState can't be derived for generic structs",
+        ));
+    }
+    let krate = crate_path(&input.attrs)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "ContractState can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "ContractState can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut collections = vec![];
+    for field in fields {
+        // will never fail since the fields are named.
+        let ident = field.ident.clone().unwrap();
+        let mut namespace = None;
+        let mut save_default = false;
+        for attr in &field.attrs {
+            if attr.path().is_ident("namespace") {
+                namespace = Some(namespace_value(&attr.meta)?);
+            } else if attr.path().is_ident("contract_state") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("default") {
+                        save_default = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `default`"))
+                    }
+                })?;
+            }
+        }
+        let (namespace, namespace_span) = namespace.ok_or_else(|| {
+            Error::new_spanned(
+                &ident,
+                "missing namespace, e.g. `#[namespace = \"config\"]`",
+            )
+        })?;
+        collections.push(Collection {
+            field: ident,
+            vis: field.vis.clone(),
+            ty: field.ty.clone(),
+            namespace,
+            namespace_span,
+            save_default,
+        });
+    }
+
+    let namespaces: Vec<(String, Vec<u8>)> = collections
+        .iter()
+        .map(|c| (c.field.to_string(), c.namespace.clone()))
+        .collect();
+    if let Some((i, j)) = find_collision(&namespaces) {
+        return Err(Error::new(
+            collections[j].namespace_span,
+            format!(
+                "the namespace of `{}` collides with the namespace of `{}`, since one is a prefix of the other",
+                collections[j].field, collections[i].field
+            ),
+        ));
+    }
+
+    let consts = collections.iter().map(|c| {
+        let const_name = namespace_const(&c.field);
+        let vis = &c.vis;
+        let namespace = LitByteStr::new(&c.namespace, c.namespace_span);
+        let doc = format!("the namespace of the `{}` collection", c.field);
+        quote! {
+            #[doc = #doc]
+            #vis const #const_name: &'static [u8] = #namespace;
+        }
+    });
+    let fields_init = collections.iter().map(|c| {
+        let Collection { field, ty, .. } = c;
+        let const_name = namespace_const(field);
+        quote! { #field: <#ty>::new(Self::#const_name) }
+    });
+    let namespace_entries = collections.iter().map(|c| {
+        let field = c.field.to_string();
+        let const_name = namespace_const(&c.field);
+        quote! { (#field, Self::#const_name) }
+    });
+    let defaults = collections.iter().filter(|c| c.save_default).map(|c| {
+        let field = &c.field;
+        quote! { state.#field.save(storage, &::core::default::Default::default())?; }
+    });
+
+    Ok(quote! {
+        impl #name {
+            #(#consts)*
+
+            /// the name and namespace of each collection, in the order of the fields
+            pub const NAMESPACES: &'static [(&'static str, &'static [u8])] =
+                &[#(#namespace_entries),*];
+
+            /// Returns the collections of the state
+            pub const fn new() -> Self {
+                Self { #(#fields_init),* }
+            }
+
+            /// Checks that the namespaces of the collections don't collide, saves the default
+            /// value of the collections marked with `#[contract_state(default)]`, and returns
+            /// the collections of the state. Usually called in `instantiate`.
+            pub fn init(
+                storage: &mut dyn #krate::__private::Storage,
+            ) -> #krate::__private::StdResult<Self> {
+                #krate::namespace::check_namespaces(Self::NAMESPACES)?;
+                let state = Self::new();
+                #(#defaults)*
+                Ok(state)
+            }
+        }
+    })
+}
+
+/// Returns the name of the namespace constant of a field, e.g. `BALANCES_NAMESPACE`
+fn namespace_const(field: &syn::Ident) -> syn::Ident {
+    format_ident!("{}_NAMESPACE", field.to_string().to_uppercase())
+}
+
+/// Returns the path of the storage package, `secret_toolkit_storage` unless it is set with
+/// `#[contract_state(crate = "...")]`
+fn crate_path(attrs: &[syn::Attribute]) -> Result<Path> {
+    let mut krate: Path = syn::parse_quote!(::secret_toolkit_storage);
+    for attr in attrs {
+        if attr.path().is_ident("contract_state") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    let path: syn::LitStr = meta.value()?.parse()?;
+                    krate = path.parse()?;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `crate`"))
+                }
+            })?;
+        }
+    }
+    Ok(krate)
+}
+
+/// Parses `#[namespace = "..."]` or `#[namespace = b"..."]`
+fn namespace_value(meta: &Meta) -> Result<(Vec<u8>, Span)> {
+    let value = match meta {
+        Meta::NameValue(meta) => &meta.value,
+        _ => {
+            return Err(Error::new_spanned(
+                meta,
+                "expected a namespace, e.g. `#[namespace = \"config\"]`",
+            ))
+        }
+    };
+    let namespace = match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => (lit.value().into_bytes(), lit.span()),
+        Expr::Lit(ExprLit {
+            lit: Lit::ByteStr(lit),
+            ..
+        }) => (lit.value(), lit.span()),
+        _ => {
+            return Err(Error::new_spanned(
+                value,
+                "the namespace must be a string or byte string literal",
+            ))
+        }
+    };
+    if namespace.0.is_empty() {
+        return Err(Error::new(namespace.1, "the namespace can't be empty"));
+    }
+    Ok(namespace)
+}
+
+/// Returns the indices of the first two namespaces where one is a prefix of (or equal to) the
+/// other, like `secret_toolkit_storage::check_namespaces`
+fn find_collision(namespaces: &[(String, Vec<u8>)]) -> Option<(usize, usize)> {
+    for (i, (_, a)) in namespaces.iter().enumerate() {
+        for (j, (_, b)) in namespaces.iter().enumerate().skip(i + 1) {
+            if a.starts_with(b) || b.starts_with(a) {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespaces(names: &[&str]) -> Vec<(String, Vec<u8>)> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), name.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_collision() {
+        assert_eq!(
+            find_collision(&namespaces(&["config", "balances", "owner"])),
+            None
+        );
+        assert_eq!(
+            find_collision(&namespaces(&["bal", "config", "balance"])),
+            Some((0, 2))
+        );
+        assert_eq!(find_collision(&namespaces(&["a", "a"])), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let error = |input: DeriveInput| expand_contract_state(input).unwrap_err().to_string();
+
+        assert!(error(syn::parse_quote! {
+            struct State {
+                #[namespace = "bal"]
+                bal: Item<'static, u8>,
+                #[namespace = b"balance"]
+                balance: Item<'static, u8>,
+            }
+        })
+        .contains("the namespace of `balance` collides with the namespace of `bal`"));
+        assert!(error(syn::parse_quote! {
+            struct State {
+                config: Item<'static, u8>,
+            }
+        })
+        .starts_with("missing namespace"));
+        assert!(error(syn::parse_quote! {
+            struct State {
+                #[namespace = 1]
+                config: Item<'static, u8>,
+            }
+        })
+        .starts_with("the namespace must be"));
+        assert!(error(syn::parse_quote! {
+            struct State(Item<'static, u8>);
+        })
+        .contains("named fields"));
+    }
+}
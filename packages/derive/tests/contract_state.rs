@@ -0,0 +1,65 @@
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::StdResult;
+use secret_toolkit_derive::ContractState;
+use secret_toolkit_storage::{AppendStore, Item, Keymap};
+
+#[derive(ContractState)]
+pub struct State {
+    #[namespace = "config"]
+    #[contract_state(default)]
+    pub config: Item<'static, u64>,
+    #[namespace = b"balances"]
+    pub balances: Keymap<'static, String, u128>,
+    #[namespace = "history"]
+    history: AppendStore<'static, String>,
+}
+
+pub static STATE: State = State::new();
+
+#[test]
+fn test_contract_state() -> StdResult<()> {
+    let mut storage = MockStorage::new();
+    assert!(STATE.config.is_empty(&storage));
+    let state = State::init(&mut storage)?;
+    assert_eq!(state.config.load(&storage)?, 0);
+
+    STATE
+        .balances
+        .insert(&mut storage, &"alice".to_string(), &100)?;
+    assert_eq!(
+        STATE.balances.get(&storage, &"alice".to_string()),
+        Some(100)
+    );
+    STATE.history.push(&mut storage, &"deposit".to_string())?;
+    assert_eq!(STATE.history.get_len(&storage)?, 1);
+    assert_eq!(State::HISTORY_NAMESPACE, b"history");
+
+    assert_eq!(
+        State::NAMESPACES,
+        &[
+            ("config", b"config".as_slice()),
+            ("balances", b"balances"),
+            ("history", b"history")
+        ]
+    );
+    Ok(())
+}
+
+mod custom_crate_path {
+    use secret_toolkit_derive::ContractState;
+    use secret_toolkit_storage as storage;
+
+    #[derive(ContractState)]
+    #[contract_state(crate = "storage")]
+    pub struct State {
+        #[namespace = "owner"]
+        pub owner: storage::Item<'static, String>,
+    }
+
+    #[test]
+    fn test_custom_crate_path() {
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        let state = State::init(&mut storage).unwrap();
+        assert!(state.owner.is_empty(&storage));
+    }
+}
@@ -0,0 +1,187 @@
+//! An "aggregator" accumulates statistics bucketed by dimensions (e.g. per day per asset) and
+//! only reveals a bucket once enough distinct contributors have added to it.
+//!
+//! This enforces k-anonymity: a bucket with fewer than `k` contributors could reveal the
+//! behavior of individual users, so it is reported as if it were empty.
+
+use std::marker::PhantomData;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage, Uint128};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+const BUCKET_PREFIX: &[u8] = b"bucket/";
+const CONTRIBUTOR_PREFIX: &[u8] = b"contributor/";
+
+/// The statistics of a bucket
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BucketStats {
+    /// the number of distinct contributors
+    pub contributors: u32,
+    /// the number of recorded values
+    pub count: u64,
+    /// the sum of the recorded values
+    pub sum: Uint128,
+}
+
+/// Accumulates statistics bucketed by a key `K`, which is usually a tuple of dimensions such
+/// as `(day, asset)`. A bucket is only revealed once at least `min_contributors` distinct
+/// contributors have added to it.
+pub struct Aggregator<'a, K, Ser = Bincode2>
+where
+    K: Serialize,
+    Ser: Serde,
+{
+    namespace: &'a [u8],
+    min_contributors: u32,
+    key_type: PhantomData<K>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, K: Serialize, Ser: Serde> Aggregator<'a, K, Ser> {
+    /// constructor
+    ///
+    /// # Panics
+    /// Panics if `min_contributors` is zero.
+    pub const fn new(namespace: &'a [u8], min_contributors: u32) -> Self {
+        if min_contributors == 0 {
+            panic!("zero minimum contributors used in aggregator")
+        }
+        Self {
+            namespace,
+            min_contributors,
+            key_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// Returns the minimum number of contributors a bucket needs before it is revealed
+    pub fn min_contributors(&self) -> u32 {
+        self.min_contributors
+    }
+
+    fn bucket_key(&self, bucket: &K) -> StdResult<Vec<u8>> {
+        Ok([self.namespace, BUCKET_PREFIX, &Ser::serialize(bucket)?].concat())
+    }
+
+    fn contributor_key(&self, bucket: &K, contributor: &[u8]) -> StdResult<Vec<u8>> {
+        let bucket = Ser::serialize(bucket)?;
+        let bucket_len = u16::try_from(bucket.len())
+            .map_err(|_| StdError::generic_err("aggregator bucket key is too long"))?;
+        Ok([
+            self.namespace,
+            CONTRIBUTOR_PREFIX,
+            &bucket_len.to_be_bytes(),
+            &bucket,
+            contributor,
+        ]
+        .concat())
+    }
+
+    fn load_stats(&self, storage: &dyn Storage, bucket: &K) -> StdResult<BucketStats> {
+        match storage.get(&self.bucket_key(bucket)?) {
+            Some(data) => Bincode2::deserialize(&data),
+            None => Ok(BucketStats::default()),
+        }
+    }
+
+    /// Records a value contributed to a bucket. A contributor (e.g. the canonical address of
+    /// the user) is only counted once per bucket, no matter how many values they record.
+    pub fn record(
+        &self,
+        storage: &mut dyn Storage,
+        bucket: &K,
+        contributor: &[u8],
+        value: Uint128,
+    ) -> StdResult<()> {
+        let mut stats = self.load_stats(storage, bucket)?;
+
+        stats.sum = stats.sum.checked_add(value)?;
+        stats.count += 1;
+
+        let contributor_key = self.contributor_key(bucket, contributor)?;
+        if storage.get(&contributor_key).is_none() {
+            // empty values are not allowed, so we store an unimportant byte
+            storage.set(&contributor_key, &[1]);
+            stats.contributors += 1;
+        }
+
+        storage.set(&self.bucket_key(bucket)?, &Bincode2::serialize(&stats)?);
+        Ok(())
+    }
+
+    /// Returns the statistics of a bucket, or `None` if it has fewer contributors than
+    /// required, in which case nothing about it is revealed.
+    pub fn get(&self, storage: &dyn Storage, bucket: &K) -> StdResult<Option<BucketStats>> {
+        let stats = self.load_stats(storage, bucket)?;
+        if stats.contributors < self.min_contributors {
+            return Ok(None);
+        }
+        Ok(Some(stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_threshold() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let volume: Aggregator<(u64, String)> = Aggregator::new(b"volume", 3);
+        let bucket = (19000, "uscrt".to_string());
+
+        volume.record(&mut storage, &bucket, b"alice", Uint128::new(10))?;
+        volume.record(&mut storage, &bucket, b"bob", Uint128::new(20))?;
+        // recording again does not count as another contributor
+        volume.record(&mut storage, &bucket, b"bob", Uint128::new(30))?;
+        assert_eq!(volume.get(&storage, &bucket)?, None);
+
+        volume.record(&mut storage, &bucket, b"carol", Uint128::new(40))?;
+        assert_eq!(
+            volume.get(&storage, &bucket)?,
+            Some(BucketStats {
+                contributors: 3,
+                count: 4,
+                sum: Uint128::new(100),
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_buckets_are_separate() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let volume: Aggregator<(u64, String)> = Aggregator::new(b"volume", 1);
+        let day_1 = (1, "a".to_string());
+        let day_2 = (2, "a".to_string());
+
+        volume.record(&mut storage, &day_1, b"alice", Uint128::new(1))?;
+        volume.record(&mut storage, &day_2, b"alice", Uint128::new(2))?;
+
+        assert_eq!(volume.get(&storage, &day_1)?.unwrap().sum, Uint128::new(1));
+        assert_eq!(volume.get(&storage, &day_2)?.unwrap().sum, Uint128::new(2));
+        assert_eq!(volume.get(&storage, &(1, "b".to_string()))?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let volume: Aggregator<u64> = Aggregator::new(b"volume", 1);
+
+        volume.record(&mut storage, &1, b"alice", Uint128::MAX)?;
+        assert!(volume
+            .record(&mut storage, &1, b"bob", Uint128::new(1))
+            .is_err());
+        assert_eq!(volume.get(&storage, &1)?.unwrap().contributors, 1);
+
+        Ok(())
+    }
+}
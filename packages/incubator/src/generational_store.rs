@@ -13,7 +13,10 @@
 //! which in turn was inspired by [Catherine West's closing keynote at RustConf 2018](https://www.youtube.com/watch?v=aKLntZcp27M).
 //!
 
+use std::cmp::Ordering;
 use std::convert::TryInto;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -29,13 +32,21 @@ const CAPACITY_KEY: &[u8] = b"cap";
 const FREE_ENTRY: u8 = 0x00;
 const OCCUPIED_ENTRY: u8 = 0x01;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd)]
-pub struct Index {
+/// A handle to an entry of a generational store holding values of type `T`.
+///
+/// The type parameter keeps handles from one store from being passed to a store of another
+/// type by mistake. It is not part of the serialized form, which is just the index and the
+/// generation, so persisted handles can be read back as any `Index<T>`.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Index<T> {
     index: u32,
     generation: u64,
+    #[serde(skip)]
+    item_type: PhantomData<fn() -> T>,
 }
 
-impl Index {
+impl<T> Index<T> {
     /// Create a new `Index` from its raw parts.
     ///
     /// The parts must have been returned from an earlier call to
@@ -43,10 +54,11 @@ impl Index {
     ///
     /// Providing arbitrary values will lead to malformed indices and ultimately
     /// panics.
-    pub fn from_raw_parts(a: u32, b: u64) -> Index {
+    pub fn from_raw_parts(a: u32, b: u64) -> Index<T> {
         Index {
             index: a,
             generation: b,
+            item_type: PhantomData,
         }
     }
 
@@ -54,12 +66,56 @@ impl Index {
     ///
     /// This niche method is useful for converting an `Index` into another
     /// identifier type. Usually, you should prefer a newtype wrapper around
-    /// `Index` like `pub struct MyIdentifier(Index);`.  However, for external
+    /// `Index` like `pub struct MyIdentifier(Index<T>);`.  However, for external
     /// types whose definition you can't customize, but which you can construct
     /// instances of, this method can be useful.
     pub fn into_raw_parts(self) -> (u32, u64) {
         (self.index, self.generation)
     }
+
+    /// Convert this `Index` into a handle to a store of another type.
+    ///
+    /// This is only meaningful when the stored type changed, e.g. when migrating a store to a new
+    /// version of its values, and the entries kept their positions.
+    pub fn cast<U>(self) -> Index<U> {
+        Index::from_raw_parts(self.index, self.generation)
+    }
+}
+
+// Manual implementations, because the derived ones would require `T` to implement the traits too
+impl<T> Clone for Index<T> {
+    fn clone(&self) -> Self {
+        Index::from_raw_parts(self.index, self.generation)
+    }
+}
+
+impl<T> fmt::Debug for Index<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Index")
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl<T> PartialEq for Index<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.index, self.generation) == (other.index, other.generation)
+    }
+}
+
+impl<T> Eq for Index<T> {}
+
+impl<T> PartialOrd for Index<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self.index, self.generation).partial_cmp(&(other.index, other.generation))
+    }
+}
+
+impl<T> Hash for Index<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.index, self.generation).hash(state);
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -226,7 +282,7 @@ where
         })
     }
 
-    pub fn insert(&mut self, value: T) -> Index {
+    pub fn insert(&mut self, value: T) -> Index<T> {
         match self.try_insert(value) {
             Ok(i) => i,
             Err(value) => {
@@ -236,7 +292,7 @@ where
         }
     }
 
-    pub fn try_insert(&mut self, value: T) -> Result<Index, T> {
+    pub fn try_insert(&mut self, value: T) -> Result<Index<T>, T> {
         match self.try_alloc_next_index() {
             None => Err(value),
             Some(index) => {
@@ -261,7 +317,7 @@ where
         }
     }
 
-    fn try_alloc_next_index(&mut self) -> Option<Index> {
+    fn try_alloc_next_index(&mut self) -> Option<Index<T>> {
         let i = self.as_readonly().get_at_unchecked(self.free_list_head);
         let old_free_list_head = self.free_list_head;
         match i {
@@ -270,17 +326,14 @@ where
                 Entry::Free { next_free } => {
                     self.set_free_list_head(next_free);
                     self.set_length(self.len + 1);
-                    Some(Index {
-                        index: old_free_list_head,
-                        generation: self.generation,
-                    })
+                    Some(Index::from_raw_parts(old_free_list_head, self.generation))
                 }
             },
             _ => None,
         }
     }
 
-    fn insert_slow_path(&mut self, value: T) -> StdResult<Index> {
+    fn insert_slow_path(&mut self, value: T) -> StdResult<Index<T>> {
         let start = self.capacity;
         // initialize next empty
         let entry: Entry<T> = Entry::Free {
@@ -296,7 +349,7 @@ where
     }
 
     // removes the entry at a given index
-    pub fn remove(&mut self, i: Index) -> StdResult<Option<T>> {
+    pub fn remove(&mut self, i: Index<T>) -> StdResult<Option<T>> {
         match self.get_at_unchecked(i.index) {
             Ok(entry) => match entry {
                 Entry::Occupied { generation, .. } if i.generation == generation => {
@@ -324,7 +377,7 @@ where
 
     // updates the entry value at a given index, must already be occupied or fails
     // if successful, returns the old value
-    pub fn update(&mut self, i: Index, new_value: T) -> StdResult<Option<T>> {
+    pub fn update(&mut self, i: Index<T>, new_value: T) -> StdResult<Option<T>> {
         match self.get_at_unchecked(i.index) {
             Ok(entry) => match entry {
                 Entry::Occupied { generation, value } if i.generation == generation => {
@@ -362,12 +415,12 @@ where
     }
 
     /// Return an iterator over the items in the collection
-    pub fn iter(&self) -> Iter<T, Ser> {
+    pub fn iter(&self) -> Iter<'_, T, Ser> {
         self.as_readonly().iter()
     }
 
     /// Get the value stored at a given index.
-    pub fn get(&self, index: Index) -> Option<T> {
+    pub fn get(&self, index: Index<T>) -> Option<T> {
         self.as_readonly().get(index)
     }
 
@@ -379,7 +432,7 @@ where
         self.as_readonly().get_at_unchecked(pos)
     }
 
-    pub fn contains(&self, i: Index) -> bool {
+    pub fn contains(&self, i: Index<T>) -> bool {
         self.get(i).is_some()
     }
 
@@ -438,7 +491,7 @@ where
     }
 
     /// Gain access to the implementation of the immutable methods
-    fn as_readonly(&self) -> GenerationalStore<T, Ser> {
+    fn as_readonly(&self) -> GenerationalStore<'_, T, Ser> {
         GenerationalStore {
             storage: self.storage,
             item_type: self.item_type,
@@ -622,7 +675,7 @@ where
         }
     }
 
-    pub fn get(&self, i: Index) -> Option<T> {
+    pub fn get(&self, i: Index<T>) -> Option<T> {
         let item = self.get_at_unchecked(i.index);
         match item {
             Ok(item) => match item {
@@ -633,7 +686,7 @@ where
         }
     }
 
-    pub fn contains(&self, i: Index) -> bool {
+    pub fn contains(&self, i: Index<T>) -> bool {
         self.get(i).is_some()
     }
 }
@@ -643,10 +696,10 @@ where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
 {
-    type Item = (Option<Index>, Entry<T>);
+    type Item = (Option<Index<T>>, Entry<T>);
 
     // alternate version, see below
-    //type Item = (Index, T);
+    //type Item = (Index<T>, T);
 
     type IntoIter = Iter<'a, T, Ser>;
 
@@ -697,7 +750,7 @@ where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
 {
-    type Item = (Option<Index>, Entry<T>);
+    type Item = (Option<Index<T>>, Entry<T>);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.start >= self.end {
@@ -711,10 +764,7 @@ where
                     Some((None, entry))
                 }
                 Entry::Occupied { generation, .. } => {
-                    let index = Index {
-                        index: self.start,
-                        generation,
-                    };
+                    let index = Index::from_raw_parts(self.start, generation);
                     self.start += 1;
                     Some((Some(index), entry))
                 }
@@ -724,7 +774,7 @@ where
     }
 
     /* alternative version - automatically filters Free entries
-    type Item = (Index, T);
+    type Item = (Index<T>, T);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.start >= self.end {
@@ -741,10 +791,7 @@ where
                             continue;
                         },
                         Entry::Occupied {generation, value} => {
-                            let index = Index {
-                                index: self.start + hop,
-                                generation,
-                            };
+                            let index = Index::from_raw_parts(self.start + hop, generation);
                             self.start += hop + 1;
                             return Some((index, value))
                         }
@@ -795,10 +842,7 @@ where
             Ok(entry) => match entry {
                 Entry::Free { .. } => Some((None, entry)),
                 Entry::Occupied { generation, .. } => {
-                    let index = Index {
-                        index: self.start,
-                        generation,
-                    };
+                    let index = Index::from_raw_parts(self.start, generation);
                     Some((Some(index), entry))
                 }
             },
@@ -847,13 +891,7 @@ mod tests {
         assert_eq!(gen_store.get(delta), Some(String::from("Delta")));
 
         assert_eq!(gen_store.len(), 4_u32);
-        assert_eq!(
-            gen_store.get(Index {
-                index: 1,
-                generation: 2
-            }),
-            None
-        );
+        assert_eq!(gen_store.get(Index::from_raw_parts(1, 2)), None);
 
         Ok(())
     }
@@ -879,21 +917,9 @@ mod tests {
         let delta = gen_store.insert(String::from("Delta"));
         assert_eq!(gen_store.get(delta.clone()), Some(String::from("Delta")));
         // check that the generation has updated
-        assert_ne!(
-            delta,
-            Index {
-                index: 1,
-                generation: 0
-            }
-        );
+        assert_ne!(delta, Index::from_raw_parts(1, 0));
         // delta has filled the slot where beta was but generation is now 1
-        assert_eq!(
-            delta,
-            Index {
-                index: 1,
-                generation: 1
-            }
-        );
+        assert_eq!(delta, Index::from_raw_parts(1, 1));
 
         // cannot remove twice
         assert!(gen_store.remove(beta).is_err());
@@ -953,4 +979,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_index_serialization() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut gen_store = GenerationalStoreMut::attach_or_create(&mut storage)?;
+        gen_store.insert(String::from("Alpha"));
+        let beta = gen_store.insert(String::from("Beta"));
+
+        // a handle can be persisted, e.g. in another struct, and read back
+        let serialized = Bincode2::serialize(&beta)?;
+        let deserialized: Index<String> = Bincode2::deserialize(&serialized)?;
+        assert_eq!(deserialized, beta);
+        assert_eq!(gen_store.get(deserialized), Some(String::from("Beta")));
+
+        // the type parameter is not part of the serialized form
+        let raw: (u32, u64) = Bincode2::deserialize(&serialized)?;
+        assert_eq!(raw, beta.clone().into_raw_parts());
+
+        // casting keeps the index and generation
+        let cast: Index<u32> = beta.clone().cast();
+        assert_eq!(cast.into_raw_parts(), beta.into_raw_parts());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,697 @@
+//! An "interval store" is a storage wrapper that implements an augmented interval tree.
+//! https://en.wikipedia.org/wiki/Interval_tree#Augmented_tree
+//!
+//! Intervals are half-open (`[start, end)`) and are kept in an AVL tree ordered by their start,
+//! where every node also tracks the maximum end of its subtree. This allows answering overlap
+//! queries without scanning all the stored intervals.
+//!
+//! Insertion O(log n)
+//! Removal O(log n)
+//! Checking if a range is free O(log n)
+//! Listing overlapping intervals O(k + log n) where k is the number of results
+//!
+use std::cmp::Ordering;
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+const LEN_KEY: &[u8] = b"len";
+const ROOT_KEY: &[u8] = b"root";
+const NEXT_NODE_KEY: &[u8] = b"next";
+const NODE_PREFIX: &[u8] = b"node/";
+const ID_PREFIX: &[u8] = b"id/";
+
+/// An interval `[start, end)` stored under an id
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Interval<Id> {
+    pub start: u64,
+    pub end: u64,
+    pub id: Id,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Node<Id> {
+    interval: Interval<Id>,
+    left: Option<u32>,
+    right: Option<u32>,
+    height: u32,
+    /// the maximum end of all the intervals in this subtree
+    max_end: u64,
+}
+
+impl<Id> Node<Id> {
+    /// the key the tree is ordered by. The node index breaks ties between identical ranges.
+    fn key(&self, idx: u32) -> (u64, u64, u32) {
+        (self.interval.start, self.interval.end, idx)
+    }
+}
+
+fn node_key(idx: u32) -> Vec<u8> {
+    [NODE_PREFIX, &idx.to_be_bytes()].concat()
+}
+
+fn parse_u32(bytes: &[u8]) -> StdResult<u32> {
+    let array = bytes
+        .try_into()
+        .map_err(|err| StdError::parse_err("u32", err))?;
+    Ok(u32::from_be_bytes(array))
+}
+
+fn check_range(start: u64, end: u64) -> StdResult<()> {
+    if start >= end {
+        return Err(StdError::generic_err(
+            "IntervalStore range start must be lower than its end",
+        ));
+    }
+    Ok(())
+}
+
+// Mutable interval store
+
+/// A type allowing both reads from and writes to the interval store at a given storage location.
+pub struct IntervalStoreMut<'a, Id, Ser = Bincode2>
+where
+    Id: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    storage: &'a mut dyn Storage,
+    item_type: PhantomData<*const Id>,
+    serialization_type: PhantomData<*const Ser>,
+    len: u32,
+}
+
+impl<'a, Id> IntervalStoreMut<'a, Id, Bincode2>
+where
+    Id: Serialize + DeserializeOwned,
+{
+    /// Try to use the provided storage as an IntervalStore. If it doesn't seem to be one, then
+    /// initialize it as one.
+    ///
+    /// Returns Err if the contents of the storage can not be parsed.
+    pub fn attach_or_create(storage: &'a mut dyn Storage) -> StdResult<Self> {
+        IntervalStoreMut::attach_or_create_with_serialization(storage, Bincode2)
+    }
+
+    /// Try to use the provided storage as an IntervalStore.
+    ///
+    /// Returns None if the provided storage doesn't seem like an IntervalStore.
+    /// Returns Err if the contents of the storage can not be parsed.
+    pub fn attach(storage: &'a mut dyn Storage) -> Option<StdResult<Self>> {
+        IntervalStoreMut::attach_with_serialization(storage, Bincode2)
+    }
+}
+
+impl<'a, Id, Ser> IntervalStoreMut<'a, Id, Ser>
+where
+    Id: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// Try to use the provided storage as an IntervalStore. If it doesn't seem to be one, then
+    /// initialize it as one. This method allows choosing the serialization format you want to use.
+    ///
+    /// Returns Err if the contents of the storage can not be parsed.
+    pub fn attach_or_create_with_serialization(
+        storage: &'a mut dyn Storage,
+        _ser: Ser,
+    ) -> StdResult<Self> {
+        if let Some(len_vec) = storage.get(LEN_KEY) {
+            Self::new(storage, &len_vec)
+        } else {
+            let len_vec = 0_u32.to_be_bytes();
+            storage.set(LEN_KEY, &len_vec);
+            Self::new(storage, &len_vec)
+        }
+    }
+
+    /// Try to use the provided storage as an IntervalStore.
+    /// This method allows choosing the serialization format you want to use.
+    ///
+    /// Returns None if the provided storage doesn't seem like an IntervalStore.
+    /// Returns Err if the contents of the storage can not be parsed.
+    pub fn attach_with_serialization(
+        storage: &'a mut dyn Storage,
+        _ser: Ser,
+    ) -> Option<StdResult<Self>> {
+        let len_vec = storage.get(LEN_KEY)?;
+        Some(Self::new(storage, &len_vec))
+    }
+
+    fn new(storage: &'a mut dyn Storage, len_vec: &[u8]) -> StdResult<Self> {
+        let len = parse_u32(len_vec)?;
+
+        Ok(Self {
+            storage,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+            len,
+        })
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn storage(&mut self) -> &mut dyn Storage {
+        self.storage
+    }
+
+    pub fn readonly_storage(&self) -> &dyn Storage {
+        self.storage
+    }
+
+    /// Returns the interval stored under an id, if any
+    pub fn get(&self, id: &Id) -> StdResult<Option<Interval<Id>>> {
+        self.as_readonly().get(id)
+    }
+
+    /// Returns true if no stored interval overlaps `[start, end)`
+    pub fn is_free(&self, start: u64, end: u64) -> StdResult<bool> {
+        self.as_readonly().is_free(start, end)
+    }
+
+    /// Returns all the stored intervals overlapping `[start, end)`, ordered by their start
+    pub fn overlapping(&self, start: u64, end: u64) -> StdResult<Vec<Interval<Id>>> {
+        self.as_readonly().overlapping(start, end)
+    }
+
+    /// Books `[start, end)` under `id` if no stored interval overlaps it.
+    ///
+    /// # Errors
+    /// Will return an error if the range is already (partially) booked, or if the id is taken.
+    pub fn book(&mut self, start: u64, end: u64, id: Id) -> StdResult<()> {
+        if !self.is_free(start, end)? {
+            return Err(StdError::generic_err(format!(
+                "IntervalStore range [{start}, {end}) is not free"
+            )));
+        }
+        self.insert(start, end, id)
+    }
+
+    /// Stores `[start, end)` under `id`, even if it overlaps other stored intervals.
+    ///
+    /// # Errors
+    /// Will return an error if the range is empty, or if the id is taken.
+    pub fn insert(&mut self, start: u64, end: u64, id: Id) -> StdResult<()> {
+        check_range(start, end)?;
+        let id_key = [ID_PREFIX, &Ser::serialize(&id)?].concat();
+        if self.storage.get(&id_key).is_some() {
+            return Err(StdError::generic_err("IntervalStore id is already in use"));
+        }
+
+        let idx = match self.storage.get(NEXT_NODE_KEY) {
+            Some(bytes) => parse_u32(&bytes)?,
+            None => 0,
+        };
+        self.storage.set(NEXT_NODE_KEY, &(idx + 1).to_be_bytes());
+        self.storage.set(&id_key, &idx.to_be_bytes());
+
+        let node = Node {
+            interval: Interval { start, end, id },
+            left: None,
+            right: None,
+            height: 1,
+            max_end: end,
+        };
+        self.save_node(idx, &node)?;
+
+        let root = self.as_readonly().root()?;
+        let root = self.insert_at(root, idx, node.key(idx))?;
+        self.set_root(Some(root));
+        self.set_length(self.len + 1);
+
+        Ok(())
+    }
+
+    /// Removes the interval stored under `id` and returns it, if it exists
+    pub fn remove(&mut self, id: &Id) -> StdResult<Option<Interval<Id>>> {
+        let id_key = [ID_PREFIX, &Ser::serialize(id)?].concat();
+        let idx = match self.storage.get(&id_key) {
+            Some(bytes) => parse_u32(&bytes)?,
+            None => return Ok(None),
+        };
+        let node = self.load_node(idx)?;
+
+        let root = self.as_readonly().root()?;
+        let root = self.remove_at(root, node.key(idx))?;
+        self.set_root(root);
+        self.storage.remove(&id_key);
+        self.set_length(self.len - 1);
+
+        Ok(Some(node.interval))
+    }
+
+    /// inserts the (already saved) node at `idx` into the subtree and returns the new subtree root
+    fn insert_at(
+        &mut self,
+        subtree: Option<u32>,
+        idx: u32,
+        key: (u64, u64, u32),
+    ) -> StdResult<u32> {
+        let root = match subtree {
+            Some(root) => root,
+            None => return Ok(idx),
+        };
+
+        let mut node = self.load_node(root)?;
+        if key < node.key(root) {
+            node.left = Some(self.insert_at(node.left, idx, key)?);
+        } else {
+            node.right = Some(self.insert_at(node.right, idx, key)?);
+        }
+        self.rebalance(root, node)
+    }
+
+    /// removes the node with the given key from the subtree and returns the new subtree root
+    fn remove_at(&mut self, subtree: Option<u32>, key: (u64, u64, u32)) -> StdResult<Option<u32>> {
+        let root = subtree.ok_or_else(|| StdError::generic_err("IntervalStore is corrupted"))?;
+
+        let mut node = self.load_node(root)?;
+        match key.cmp(&node.key(root)) {
+            Ordering::Less => {
+                node.left = self.remove_at(node.left, key)?;
+                self.rebalance(root, node).map(Some)
+            }
+            Ordering::Greater => {
+                node.right = self.remove_at(node.right, key)?;
+                self.rebalance(root, node).map(Some)
+            }
+            Ordering::Equal => {
+                self.storage.remove(&node_key(root));
+                match (node.left, node.right) {
+                    (None, None) => Ok(None),
+                    (Some(child), None) | (None, Some(child)) => Ok(Some(child)),
+                    (Some(left), Some(right)) => {
+                        // replace the removed node with the leftmost node of its right subtree
+                        let (right, successor) = self.remove_min(right)?;
+                        let mut successor_node = self.load_node(successor)?;
+                        successor_node.left = Some(left);
+                        successor_node.right = right;
+                        self.rebalance(successor, successor_node).map(Some)
+                    }
+                }
+            }
+        }
+    }
+
+    /// detaches the leftmost node of the subtree, and returns the new subtree root and the
+    /// detached node
+    fn remove_min(&mut self, root: u32) -> StdResult<(Option<u32>, u32)> {
+        let mut node = self.load_node(root)?;
+        match node.left {
+            None => Ok((node.right, root)),
+            Some(left) => {
+                let (left, min) = self.remove_min(left)?;
+                node.left = left;
+                Ok((Some(self.rebalance(root, node)?), min))
+            }
+        }
+    }
+
+    /// recomputes the height and max end of a node from its children, and saves it
+    fn update(&mut self, idx: u32, node: &mut Node<Id>) -> StdResult<()> {
+        let readonly = self.as_readonly();
+        let (left_height, left_max) = readonly.summary(node.left)?;
+        let (right_height, right_max) = readonly.summary(node.right)?;
+        node.height = 1 + left_height.max(right_height);
+        node.max_end = node.interval.end.max(left_max).max(right_max);
+        self.save_node(idx, node)
+    }
+
+    fn rotate_left(&mut self, idx: u32, mut node: Node<Id>) -> StdResult<u32> {
+        let pivot = node
+            .right
+            .ok_or_else(|| StdError::generic_err("IntervalStore is corrupted"))?;
+        let mut pivot_node = self.load_node(pivot)?;
+        node.right = pivot_node.left;
+        self.update(idx, &mut node)?;
+        pivot_node.left = Some(idx);
+        self.update(pivot, &mut pivot_node)?;
+        Ok(pivot)
+    }
+
+    fn rotate_right(&mut self, idx: u32, mut node: Node<Id>) -> StdResult<u32> {
+        let pivot = node
+            .left
+            .ok_or_else(|| StdError::generic_err("IntervalStore is corrupted"))?;
+        let mut pivot_node = self.load_node(pivot)?;
+        node.left = pivot_node.right;
+        self.update(idx, &mut node)?;
+        pivot_node.right = Some(idx);
+        self.update(pivot, &mut pivot_node)?;
+        Ok(pivot)
+    }
+
+    /// restores the AVL balance of a node whose children may have changed, and returns the
+    /// new subtree root
+    fn rebalance(&mut self, idx: u32, mut node: Node<Id>) -> StdResult<u32> {
+        let readonly = self.as_readonly();
+        let left_height = readonly.summary(node.left)?.0;
+        let right_height = readonly.summary(node.right)?.0;
+
+        if left_height > right_height + 1 {
+            // will never fail since the left subtree is higher than the right one
+            let left = node.left.unwrap();
+            let left_node = self.load_node(left)?;
+            let readonly = self.as_readonly();
+            if readonly.summary(left_node.left)?.0 < readonly.summary(left_node.right)?.0 {
+                node.left = Some(self.rotate_left(left, left_node)?);
+            }
+            self.rotate_right(idx, node)
+        } else if right_height > left_height + 1 {
+            // will never fail since the right subtree is higher than the left one
+            let right = node.right.unwrap();
+            let right_node = self.load_node(right)?;
+            let readonly = self.as_readonly();
+            if readonly.summary(right_node.right)?.0 < readonly.summary(right_node.left)?.0 {
+                node.right = Some(self.rotate_right(right, right_node)?);
+            }
+            self.rotate_left(idx, node)
+        } else {
+            self.update(idx, &mut node)?;
+            Ok(idx)
+        }
+    }
+
+    fn load_node(&self, idx: u32) -> StdResult<Node<Id>> {
+        self.as_readonly().load_node(idx)
+    }
+
+    fn save_node(&mut self, idx: u32, node: &Node<Id>) -> StdResult<()> {
+        self.storage.set(&node_key(idx), &Ser::serialize(node)?);
+        Ok(())
+    }
+
+    fn set_root(&mut self, root: Option<u32>) {
+        match root {
+            Some(root) => self.storage.set(ROOT_KEY, &root.to_be_bytes()),
+            None => self.storage.remove(ROOT_KEY),
+        }
+    }
+
+    /// Set the length of the collection
+    fn set_length(&mut self, len: u32) {
+        self.storage.set(LEN_KEY, &len.to_be_bytes());
+        self.len = len;
+    }
+
+    /// Gain access to the implementation of the immutable methods
+    fn as_readonly(&self) -> IntervalStore<'_, Id, Ser> {
+        IntervalStore {
+            storage: self.storage,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+            len: self.len,
+        }
+    }
+}
+
+// Readonly interval store
+
+/// A type allowing only reads from an interval store. useful in the context of queries.
+pub struct IntervalStore<'a, Id, Ser = Bincode2>
+where
+    Id: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    storage: &'a dyn Storage,
+    item_type: PhantomData<*const Id>,
+    serialization_type: PhantomData<*const Ser>,
+    len: u32,
+}
+
+impl<'a, Id> IntervalStore<'a, Id, Bincode2>
+where
+    Id: Serialize + DeserializeOwned,
+{
+    /// Try to use the provided storage as an IntervalStore.
+    ///
+    /// Returns None if the provided storage doesn't seem like an IntervalStore.
+    /// Returns Err if the contents of the storage can not be parsed.
+    pub fn attach(storage: &'a dyn Storage) -> Option<StdResult<Self>> {
+        IntervalStore::attach_with_serialization(storage, Bincode2)
+    }
+}
+
+impl<'a, Id, Ser> IntervalStore<'a, Id, Ser>
+where
+    Id: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// Try to use the provided storage as an IntervalStore.
+    /// This method allows choosing the serialization format you want to use.
+    ///
+    /// Returns None if the provided storage doesn't seem like an IntervalStore.
+    /// Returns Err if the contents of the storage can not be parsed.
+    pub fn attach_with_serialization(
+        storage: &'a dyn Storage,
+        _ser: Ser,
+    ) -> Option<StdResult<Self>> {
+        let len_vec = storage.get(LEN_KEY)?;
+        Some(IntervalStore::new(storage, &len_vec))
+    }
+
+    fn new(storage: &'a dyn Storage, len_vec: &[u8]) -> StdResult<Self> {
+        let len = parse_u32(len_vec)?;
+
+        Ok(Self {
+            storage,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+            len,
+        })
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn readonly_storage(&self) -> &'a dyn Storage {
+        self.storage
+    }
+
+    /// Returns the interval stored under an id, if any
+    pub fn get(&self, id: &Id) -> StdResult<Option<Interval<Id>>> {
+        let id_key = [ID_PREFIX, &Ser::serialize(id)?].concat();
+        match self.storage.get(&id_key) {
+            Some(bytes) => Ok(Some(self.load_node(parse_u32(&bytes)?)?.interval)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns true if no stored interval overlaps `[start, end)`
+    pub fn is_free(&self, start: u64, end: u64) -> StdResult<bool> {
+        check_range(start, end)?;
+
+        let mut subtree = self.root()?;
+        while let Some(idx) = subtree {
+            let node = self.load_node(idx)?;
+            if node.max_end <= start {
+                break;
+            }
+            if node.interval.start < end && node.interval.end > start {
+                return Ok(false);
+            }
+            // if an interval in the left subtree ends after `start` but none of them overlap,
+            // then it starts after `end`, and so do all the intervals in the right subtree.
+            let (_, left_max) = self.summary(node.left)?;
+            subtree = if left_max > start {
+                node.left
+            } else if node.interval.start < end {
+                node.right
+            } else {
+                None
+            };
+        }
+
+        Ok(true)
+    }
+
+    /// Returns all the stored intervals overlapping `[start, end)`, ordered by their start
+    pub fn overlapping(&self, start: u64, end: u64) -> StdResult<Vec<Interval<Id>>> {
+        check_range(start, end)?;
+
+        let mut result = vec![];
+        self.collect_overlapping(self.root()?, start, end, &mut result)?;
+        Ok(result)
+    }
+
+    fn collect_overlapping(
+        &self,
+        subtree: Option<u32>,
+        start: u64,
+        end: u64,
+        result: &mut Vec<Interval<Id>>,
+    ) -> StdResult<()> {
+        let idx = match subtree {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+        let node = self.load_node(idx)?;
+        if node.max_end <= start {
+            // nothing in this subtree ends after the range starts
+            return Ok(());
+        }
+
+        self.collect_overlapping(node.left, start, end, result)?;
+        if node.interval.start < end {
+            let right = node.right;
+            if node.interval.end > start {
+                result.push(node.interval);
+            }
+            // intervals in the right subtree start after this one
+            self.collect_overlapping(right, start, end, result)?;
+        }
+
+        Ok(())
+    }
+
+    fn root(&self) -> StdResult<Option<u32>> {
+        self.storage
+            .get(ROOT_KEY)
+            .map(|bytes| parse_u32(&bytes))
+            .transpose()
+    }
+
+    /// returns the height and max end of a subtree
+    fn summary(&self, subtree: Option<u32>) -> StdResult<(u32, u64)> {
+        match subtree {
+            Some(idx) => {
+                let node = self.load_node(idx)?;
+                Ok((node.height, node.max_end))
+            }
+            None => Ok((0, 0)),
+        }
+    }
+
+    fn load_node(&self, idx: u32) -> StdResult<Node<Id>> {
+        let serialized = self
+            .storage
+            .get(&node_key(idx))
+            .ok_or_else(|| StdError::generic_err(format!("No node found at index {idx}")))?;
+        Ser::deserialize(&serialized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_book() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut store = IntervalStoreMut::attach_or_create(&mut storage)?;
+
+        store.book(10, 20, 1u32)?;
+        store.book(20, 30, 2u32)?;
+        store.book(0, 10, 3u32)?;
+        assert_eq!(store.len(), 3);
+
+        assert!(store.book(15, 25, 4u32).is_err());
+        assert!(store.book(5, 6, 4u32).is_err());
+        assert!(store.book(30, 40, 1u32).is_err());
+        assert!(store.book(40, 40, 4u32).is_err());
+        assert_eq!(store.len(), 3);
+
+        assert!(!store.is_free(29, 31)?);
+        assert!(store.is_free(30, 31)?);
+
+        assert_eq!(
+            store.remove(&1)?,
+            Some(Interval {
+                start: 10,
+                end: 20,
+                id: 1
+            })
+        );
+        assert_eq!(store.remove(&1)?, None);
+        assert!(store.is_free(10, 20)?);
+        store.book(12, 18, 4u32)?;
+        assert_eq!(store.get(&4)?.map(|i| (i.start, i.end)), Some((12, 18)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlapping() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut store = IntervalStoreMut::attach_or_create(&mut storage)?;
+
+        store.insert(0, 100, "a".to_string())?;
+        store.insert(10, 20, "b".to_string())?;
+        store.insert(15, 30, "c".to_string())?;
+        store.insert(40, 50, "d".to_string())?;
+
+        let ids = |intervals: Vec<Interval<String>>| {
+            intervals.into_iter().map(|i| i.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(store.overlapping(18, 42)?), vec!["a", "b", "c", "d"]);
+        assert_eq!(ids(store.overlapping(30, 40)?), vec!["a"]);
+        assert_eq!(ids(store.overlapping(100, 200)?), Vec::<String>::new());
+
+        let readonly = IntervalStore::<String>::attach(&storage).unwrap()?;
+        assert_eq!(readonly.len(), 4);
+        assert_eq!(ids(readonly.overlapping(20, 21)?), vec!["a", "c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_against_brute_force() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut store = IntervalStoreMut::attach_or_create(&mut storage)?;
+        let mut expected: Vec<Interval<u32>> = vec![];
+
+        // simple deterministic LCG so the test is reproducible
+        let mut seed = 42u64;
+        let mut next = |modulo: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) % modulo
+        };
+
+        for id in 0..300u32 {
+            if next(3) == 0 && !expected.is_empty() {
+                let removed = expected.remove(next(expected.len() as u64) as usize);
+                assert_eq!(store.remove(&removed.id)?, Some(removed));
+            } else {
+                let start = next(1000);
+                let end = start + 1 + next(50);
+                store.insert(start, end, id)?;
+                expected.push(Interval { start, end, id });
+            }
+
+            let start = next(1000);
+            let end = start + 1 + next(50);
+            let mut brute: Vec<u32> = expected
+                .iter()
+                .filter(|i| i.start < end && i.end > start)
+                .map(|i| i.id)
+                .collect();
+            brute.sort_unstable();
+            let mut found: Vec<u32> = store
+                .overlapping(start, end)?
+                .into_iter()
+                .map(|i| i.id)
+                .collect();
+            found.sort_unstable();
+            assert_eq!(found, brute);
+            assert_eq!(store.is_free(start, end)?, brute.is_empty());
+            assert_eq!(store.len() as usize, expected.len());
+        }
+
+        Ok(())
+    }
+}
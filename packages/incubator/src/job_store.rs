@@ -0,0 +1,429 @@
+//! A "job store" is a queue of jobs that workers (e.g. keeper bots) claim with a lease.
+//!
+//! A worker that claims a job must keep its lease alive with heartbeats until it completes
+//! the job. If the lease expires, the job can be reclaimed and claimed again by another
+//! worker, so a job is never lost when its worker goes offline.
+//!
+//! Jobs are claimed in the order they were pushed, and reclaimed jobs go back to the end of
+//! the queue. Leases are reclaimed in the order they expire in.
+
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, BlockInfo, StdError, StdResult, Storage};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+use secret_toolkit_storage::{DequeStore, Keyset};
+
+const NEXT_ID_KEY: &[u8] = b"next_id";
+const HEAD_KEY: &[u8] = b"head";
+const TAIL_KEY: &[u8] = b"tail";
+const CLAIMED_KEY: &[u8] = b"claimed";
+const DEADLINES_KEY: &[u8] = b"deadlines";
+const JOB_PREFIX: &[u8] = b"job/";
+const QUEUE_PREFIX: &[u8] = b"queue/";
+
+/// The lease of a claimed job
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Lease {
+    /// the worker that claimed the job
+    pub worker: Addr,
+    /// the time in seconds at which the lease expires, unless it is renewed
+    pub expires_at: u64,
+}
+
+/// A job, along with its lease if it is claimed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Job<T> {
+    pub payload: T,
+    /// `None` while the job waits in the queue
+    pub lease: Option<Lease>,
+    /// the number of times the job was claimed
+    pub attempts: u32,
+}
+
+/// A queue of jobs of type `T`, that workers claim with a lease of `lease_duration` seconds
+pub struct JobStore<'a, T, Ser = Bincode2>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    namespace: &'a [u8],
+    lease_duration: u64,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> JobStore<'a, T, Ser> {
+    /// constructor
+    ///
+    /// # Panics
+    /// Panics if `lease_duration` is zero.
+    pub const fn new(namespace: &'a [u8], lease_duration: u64) -> Self {
+        if lease_duration == 0 {
+            panic!("zero lease duration used in job store")
+        }
+        Self {
+            namespace,
+            lease_duration,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// Returns the number of seconds a lease lasts after a claim or a heartbeat
+    pub fn lease_duration(&self) -> u64 {
+        self.lease_duration
+    }
+
+    fn key(&self, key: &[u8]) -> Vec<u8> {
+        [self.namespace, key].concat()
+    }
+
+    fn job_key(&self, id: u64) -> Vec<u8> {
+        [self.namespace, JOB_PREFIX, &id.to_be_bytes()].concat()
+    }
+
+    fn queue_key(&self, pos: u64) -> Vec<u8> {
+        [self.namespace, QUEUE_PREFIX, &pos.to_be_bytes()].concat()
+    }
+
+    fn load_u64(&self, storage: &dyn Storage, key: &[u8]) -> StdResult<u64> {
+        match storage.get(&self.key(key)) {
+            Some(data) => Bincode2::deserialize(&data),
+            None => Ok(0),
+        }
+    }
+
+    fn save_u64(&self, storage: &mut dyn Storage, key: &[u8], value: u64) -> StdResult<()> {
+        storage.set(&self.key(key), &Bincode2::serialize(&value)?);
+        Ok(())
+    }
+
+    /// the ids of the claimed jobs
+    fn claimed_ids(&self) -> Keyset<'a, u64> {
+        Keyset::new(self.namespace).add_suffix(CLAIMED_KEY)
+    }
+
+    /// the expiration time of each lease given by a claim or a heartbeat, along with the id of
+    /// its job, in the order they expire in, as the block time only goes forward
+    fn deadlines(&self) -> DequeStore<'a, (u64, u64)> {
+        DequeStore::new(self.namespace).add_suffix(DEADLINES_KEY)
+    }
+
+    /// Gives the lease of a job to `worker`, until `lease_duration` seconds from now
+    fn lease(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        id: u64,
+        job: &mut Job<T>,
+        worker: &Addr,
+    ) -> StdResult<()> {
+        let expires_at = block.time.seconds() + self.lease_duration;
+        job.lease = Some(Lease {
+            worker: worker.clone(),
+            expires_at,
+        });
+        self.save_job(storage, id, job)?;
+        self.deadlines().push_back(storage, &(expires_at, id))
+    }
+
+    fn save_job(&self, storage: &mut dyn Storage, id: u64, job: &Job<T>) -> StdResult<()> {
+        storage.set(&self.job_key(id), &Ser::serialize(job)?);
+        Ok(())
+    }
+
+    fn enqueue(&self, storage: &mut dyn Storage, id: u64) -> StdResult<()> {
+        let tail = self.load_u64(storage, TAIL_KEY)?;
+        storage.set(&self.queue_key(tail), &id.to_be_bytes());
+        self.save_u64(storage, TAIL_KEY, tail + 1)
+    }
+
+    /// Returns the job with the given id, if it wasn't completed
+    pub fn get(&self, storage: &dyn Storage, id: u64) -> StdResult<Option<Job<T>>> {
+        match storage.get(&self.job_key(id)) {
+            Some(data) => Ser::deserialize(&data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of jobs waiting to be claimed
+    pub fn pending_len(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self.load_u64(storage, TAIL_KEY)? - self.load_u64(storage, HEAD_KEY)?)
+    }
+
+    /// Returns the ids of the claimed jobs, whether their lease expired or not
+    pub fn claimed(&self, storage: &dyn Storage) -> StdResult<Vec<u64>> {
+        self.claimed_ids().iter(storage)?.collect()
+    }
+
+    /// Adds a job at the end of the queue, and returns its id
+    pub fn push(&self, storage: &mut dyn Storage, payload: T) -> StdResult<u64> {
+        let id = self.load_u64(storage, NEXT_ID_KEY)?;
+        self.save_u64(storage, NEXT_ID_KEY, id + 1)?;
+
+        let job = Job {
+            payload,
+            lease: None,
+            attempts: 0,
+        };
+        self.save_job(storage, id, &job)?;
+        self.enqueue(storage, id)?;
+        Ok(id)
+    }
+
+    /// Claims the job at the front of the queue for `worker`, and returns it along with its id.
+    /// Returns `None` if no job is waiting.
+    pub fn claim_next(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        worker: &Addr,
+    ) -> StdResult<Option<(u64, Job<T>)>> {
+        let head = self.load_u64(storage, HEAD_KEY)?;
+        if head == self.load_u64(storage, TAIL_KEY)? {
+            return Ok(None);
+        }
+        let queue_key = self.queue_key(head);
+        let id = storage
+            .get(&queue_key)
+            .and_then(|data| data.try_into().ok())
+            .map(u64::from_be_bytes)
+            .ok_or_else(|| StdError::generic_err("job store queue is corrupted"))?;
+        storage.remove(&queue_key);
+        self.save_u64(storage, HEAD_KEY, head + 1)?;
+
+        let mut job = self
+            .get(storage, id)?
+            .ok_or_else(|| StdError::not_found("job"))?;
+        job.attempts += 1;
+        self.lease(storage, block, id, &mut job, worker)?;
+        self.claimed_ids().insert(storage, &id)?;
+
+        Ok(Some((id, job)))
+    }
+
+    /// Loads a job whose lease is held by `worker` and hasn't expired
+    fn load_leased(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        id: u64,
+        worker: &Addr,
+    ) -> StdResult<Job<T>> {
+        let job = self
+            .get(storage, id)?
+            .ok_or_else(|| StdError::not_found("job"))?;
+        match &job.lease {
+            Some(lease) if &lease.worker == worker && lease.expires_at > block.time.seconds() => {
+                Ok(job)
+            }
+            _ => Err(StdError::generic_err(format!(
+                "job {id} is not leased to {worker}"
+            ))),
+        }
+    }
+
+    /// Renews the lease of a job claimed by `worker`, which must not have expired yet
+    pub fn heartbeat(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        id: u64,
+        worker: &Addr,
+    ) -> StdResult<()> {
+        let mut job = self.load_leased(storage, block, id, worker)?;
+        self.lease(storage, block, id, &mut job, worker)
+    }
+
+    /// Removes a job claimed by `worker`, whose lease must not have expired yet, and returns
+    /// its payload
+    pub fn complete(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        id: u64,
+        worker: &Addr,
+    ) -> StdResult<T> {
+        let job = self.load_leased(storage, block, id, worker)?;
+        storage.remove(&self.job_key(id));
+        self.claimed_ids().remove(storage, &id)?;
+
+        Ok(job.payload)
+    }
+
+    /// Puts the jobs whose lease expired back at the end of the queue, going through up to
+    /// `limit` expired leases, the oldest first, and returns the ids of the reclaimed jobs.
+    /// Leases that were renewed by a heartbeat or whose job was completed count towards
+    /// `limit` but reclaim nothing.
+    pub fn reclaim_expired(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        limit: u32,
+    ) -> StdResult<Vec<u64>> {
+        let now = block.time.seconds();
+        let deadlines = self.deadlines();
+        let claimed = self.claimed_ids();
+        let mut reclaimed = vec![];
+        for _ in 0..limit {
+            match deadlines.peek_front(storage)? {
+                Some((expires_at, _)) if expires_at <= now => {}
+                _ => break,
+            }
+            let (expires_at, id) = deadlines.pop_front(storage)?;
+            let mut job = match self.get(storage, id)? {
+                Some(job) => job,
+                None => continue,
+            };
+            // only the last lease of a job is still current
+            if job.lease.as_ref().map(|lease| lease.expires_at) != Some(expires_at) {
+                continue;
+            }
+            job.lease = None;
+            self.save_job(storage, id, &job)?;
+            self.enqueue(storage, id)?;
+            claimed.remove(storage, &id)?;
+            reclaimed.push(id);
+        }
+        Ok(reclaimed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    fn block_at(seconds: u64) -> BlockInfo {
+        let mut block = mock_env().block;
+        block.time = cosmwasm_std::Timestamp::from_seconds(seconds);
+        block
+    }
+
+    #[test]
+    fn test_claim_and_complete() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let jobs: JobStore<String> = JobStore::new(b"jobs", 60);
+        let keeper = Addr::unchecked("keeper");
+
+        let first = jobs.push(&mut storage, "liquidate alice".to_string())?;
+        let second = jobs.push(&mut storage, "liquidate bob".to_string())?;
+        assert_eq!(jobs.pending_len(&storage)?, 2);
+
+        let (id, job) = jobs
+            .claim_next(&mut storage, &block_at(100), &keeper)?
+            .unwrap();
+        assert_eq!(id, first);
+        assert_eq!(job.payload, "liquidate alice");
+        assert_eq!(job.attempts, 1);
+        assert_eq!(
+            job.lease,
+            Some(Lease {
+                worker: keeper.clone(),
+                expires_at: 160
+            })
+        );
+        assert_eq!(jobs.pending_len(&storage)?, 1);
+        assert_eq!(jobs.claimed(&storage)?, vec![first]);
+
+        // only the worker holding the lease can complete the job
+        let other = Addr::unchecked("other");
+        assert!(jobs
+            .complete(&mut storage, &block_at(110), first, &other)
+            .is_err());
+        assert_eq!(
+            jobs.complete(&mut storage, &block_at(110), first, &keeper)?,
+            "liquidate alice"
+        );
+        assert_eq!(jobs.get(&storage, first)?, None);
+        assert!(jobs.claimed(&storage)?.is_empty());
+
+        let (id, _) = jobs
+            .claim_next(&mut storage, &block_at(120), &other)?
+            .unwrap();
+        assert_eq!(id, second);
+        assert_eq!(jobs.claim_next(&mut storage, &block_at(120), &other)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lease_expiry() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let jobs: JobStore<u32> = JobStore::new(b"jobs", 60);
+        let keeper = Addr::unchecked("keeper");
+        let backup = Addr::unchecked("backup");
+
+        let id = jobs.push(&mut storage, 7)?;
+        jobs.claim_next(&mut storage, &block_at(100), &keeper)?;
+
+        // heartbeats keep the lease alive
+        jobs.heartbeat(&mut storage, &block_at(150), id, &keeper)?;
+        assert!(jobs
+            .reclaim_expired(&mut storage, &block_at(200), 10)?
+            .is_empty());
+
+        // without heartbeats the lease expires, and the job can be claimed again
+        assert!(jobs
+            .heartbeat(&mut storage, &block_at(210), id, &keeper)
+            .is_err());
+        assert_eq!(
+            jobs.reclaim_expired(&mut storage, &block_at(210), 10)?,
+            vec![id]
+        );
+        assert_eq!(jobs.pending_len(&storage)?, 1);
+
+        let (reclaimed_id, job) = jobs
+            .claim_next(&mut storage, &block_at(220), &backup)?
+            .unwrap();
+        assert_eq!(reclaimed_id, id);
+        assert_eq!(job.attempts, 2);
+        assert!(jobs
+            .complete(&mut storage, &block_at(230), id, &keeper)
+            .is_err());
+        assert_eq!(jobs.complete(&mut storage, &block_at(230), id, &backup)?, 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reclaim_expired_limit() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let jobs: JobStore<u32> = JobStore::new(b"jobs", 60);
+        let keeper = Addr::unchecked("keeper");
+
+        for payload in 0..5 {
+            jobs.push(&mut storage, payload)?;
+        }
+        for time in [100, 101, 102, 103, 104] {
+            jobs.claim_next(&mut storage, &block_at(time), &keeper)?;
+        }
+        // job 1 is kept alive, and job 3 is completed
+        jobs.heartbeat(&mut storage, &block_at(150), 1, &keeper)?;
+        jobs.complete(&mut storage, &block_at(150), 3, &keeper)?;
+        assert_eq!(jobs.claimed(&storage)?.len(), 4);
+
+        // the stale leases of jobs 1 and 3 count towards the limit
+        let reclaim =
+            |storage: &mut MockStorage, time| jobs.reclaim_expired(storage, &block_at(time), 2);
+        assert_eq!(reclaim(&mut storage, 200)?, vec![0]);
+        assert_eq!(reclaim(&mut storage, 200)?, vec![2]);
+        assert_eq!(reclaim(&mut storage, 200)?, vec![4]);
+        assert!(reclaim(&mut storage, 200)?.is_empty());
+        assert_eq!(jobs.claimed(&storage)?, vec![1]);
+        assert_eq!(jobs.pending_len(&storage)?, 3);
+
+        assert_eq!(reclaim(&mut storage, 210)?, vec![1]);
+        assert!(jobs.claimed(&storage)?.is_empty());
+        assert_eq!(
+            jobs.claim_next(&mut storage, &block_at(220), &keeper)?
+                .map(|(id, job)| (id, job.attempts)),
+            Some((0, 2))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+//! A double-entry ledger, for treasuries and other contracts that keep accounts.
+//!
+//! Every transaction is a list of postings that debit or credit accounts, and is rejected unless
+//! its debits sum to its credits. Since every transaction is balanced, the debits of all the
+//! accounts always sum to their credits, which the trial balance checks. Transactions are kept
+//! in a journal, so the balance of every account can be audited.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage, Uint128};
+
+use secret_toolkit_storage::{AppendStore, Keymap};
+
+const ACCOUNTS_SUFFIX: &[u8] = b"accounts";
+const JOURNAL_SUFFIX: &[u8] = b"journal";
+
+/// The side of an account a posting is recorded on
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+/// The kind of an account, which decides on which side its balance normally is
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountKind {
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+impl AccountKind {
+    /// Returns the side that increases accounts of this kind
+    pub fn normal_side(&self) -> Side {
+        match self {
+            AccountKind::Asset | AccountKind::Expense => Side::Debit,
+            AccountKind::Liability | AccountKind::Equity | AccountKind::Income => Side::Credit,
+        }
+    }
+}
+
+/// An account, with the totals of its debits and credits
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub kind: AccountKind,
+    pub debits: Uint128,
+    pub credits: Uint128,
+}
+
+impl Account {
+    /// Returns the balance of the account on its normal side, or `None` if the account is
+    /// overdrawn, i.e. has a balance on the other side
+    pub fn balance(&self) -> Option<Uint128> {
+        match self.kind.normal_side() {
+            Side::Debit => self.debits.checked_sub(self.credits).ok(),
+            Side::Credit => self.credits.checked_sub(self.debits).ok(),
+        }
+    }
+}
+
+/// A debit or credit of an account
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Posting {
+    pub account: String,
+    pub side: Side,
+    pub amount: Uint128,
+}
+
+impl Posting {
+    pub fn debit(account: impl Into<String>, amount: Uint128) -> Self {
+        Self {
+            account: account.into(),
+            side: Side::Debit,
+            amount,
+        }
+    }
+
+    pub fn credit(account: impl Into<String>, amount: Uint128) -> Self {
+        Self {
+            account: account.into(),
+            side: Side::Credit,
+            amount,
+        }
+    }
+}
+
+/// A balanced transaction, as recorded in the journal
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub block_height: u64,
+    pub memo: Option<String>,
+    pub postings: Vec<Posting>,
+}
+
+/// The balances of all the accounts, and the totals of their debits and credits
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct TrialBalance {
+    pub accounts: Vec<(String, Account)>,
+    pub total_debits: Uint128,
+    pub total_credits: Uint128,
+}
+
+impl TrialBalance {
+    /// Returns whether the debits of all the accounts sum to their credits
+    pub fn is_balanced(&self) -> bool {
+        self.total_debits == self.total_credits
+    }
+}
+
+/// A double-entry ledger, whose accounts and journal are kept under `namespace`
+pub struct Ledger<'a> {
+    namespace: &'a [u8],
+}
+
+impl<'a> Ledger<'a> {
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self { namespace }
+    }
+
+    fn accounts(&self) -> Keymap<'a, String, Account> {
+        Keymap::new(self.namespace).add_suffix(ACCOUNTS_SUFFIX)
+    }
+
+    fn journal(&self) -> AppendStore<'a, Transaction> {
+        AppendStore::new(self.namespace).add_suffix(JOURNAL_SUFFIX)
+    }
+
+    /// Opens an account, which must be done before posting to it. Fails if an account with this
+    /// name is already open.
+    pub fn open_account(
+        &self,
+        storage: &mut dyn Storage,
+        name: &str,
+        kind: AccountKind,
+    ) -> StdResult<()> {
+        let accounts = self.accounts();
+        let name = name.to_string();
+        if accounts.contains(storage, &name) {
+            return Err(StdError::generic_err(format!(
+                "account {name} is already open"
+            )));
+        }
+        accounts.insert(
+            storage,
+            &name,
+            &Account {
+                kind,
+                debits: Uint128::zero(),
+                credits: Uint128::zero(),
+            },
+        )
+    }
+
+    /// Returns an account, or `None` if it isn't open
+    pub fn account(&self, storage: &dyn Storage, name: &str) -> Option<Account> {
+        self.accounts().get(storage, &name.to_string())
+    }
+
+    /// Records a transaction, and returns its position in the journal.
+    ///
+    /// Fails, without changing any account, unless the debits of the postings sum to their
+    /// credits, every amount is positive and every account is open.
+    pub fn post(
+        &self,
+        storage: &mut dyn Storage,
+        block_height: u64,
+        memo: Option<String>,
+        postings: Vec<Posting>,
+    ) -> StdResult<u32> {
+        if postings.len() < 2 {
+            return Err(StdError::generic_err(
+                "a transaction needs at least two postings",
+            ));
+        }
+
+        let accounts = self.accounts();
+        let mut updated: Vec<(String, Account)> = vec![];
+        let mut debits = Uint128::zero();
+        let mut credits = Uint128::zero();
+        for posting in &postings {
+            if posting.amount.is_zero() {
+                return Err(StdError::generic_err(format!(
+                    "posting to account {} has a zero amount",
+                    posting.account
+                )));
+            }
+            let index = match updated
+                .iter()
+                .position(|(name, _)| *name == posting.account)
+            {
+                Some(index) => index,
+                None => {
+                    let account = accounts.get(storage, &posting.account).ok_or_else(|| {
+                        StdError::generic_err(format!("account {} is not open", posting.account))
+                    })?;
+                    updated.push((posting.account.clone(), account));
+                    updated.len() - 1
+                }
+            };
+            let account = &mut updated[index].1;
+            match posting.side {
+                Side::Debit => {
+                    account.debits = account.debits.checked_add(posting.amount)?;
+                    debits = debits.checked_add(posting.amount)?;
+                }
+                Side::Credit => {
+                    account.credits = account.credits.checked_add(posting.amount)?;
+                    credits = credits.checked_add(posting.amount)?;
+                }
+            }
+        }
+        if debits != credits {
+            return Err(StdError::generic_err(format!(
+                "unbalanced transaction: debits of {debits} but credits of {credits}"
+            )));
+        }
+
+        for (name, account) in &updated {
+            accounts.insert(storage, name, account)?;
+        }
+        let journal = self.journal();
+        let position = journal.get_len(storage)?;
+        journal.push(
+            storage,
+            &Transaction {
+                block_height,
+                memo,
+                postings,
+            },
+        )?;
+
+        Ok(position)
+    }
+
+    /// Returns the transaction at a position of the journal
+    pub fn transaction(&self, storage: &dyn Storage, position: u32) -> StdResult<Transaction> {
+        self.journal().get_at(storage, position)
+    }
+
+    /// Returns a page of the journal, the earliest transactions first, and the number of
+    /// transactions
+    pub fn transactions(
+        &self,
+        storage: &dyn Storage,
+        page: u32,
+        page_size: u32,
+    ) -> StdResult<(Vec<Transaction>, u32)> {
+        let journal = self.journal();
+        Ok((
+            journal.paging(storage, page, page_size)?,
+            journal.get_len(storage)?,
+        ))
+    }
+
+    /// Returns the trial balance of the ledger. This loads every account, so it is meant for
+    /// queries and audits rather than transactions.
+    pub fn trial_balance(&self, storage: &dyn Storage) -> StdResult<TrialBalance> {
+        let accounts = self
+            .accounts()
+            .iter(storage)?
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut total_debits = Uint128::zero();
+        let mut total_credits = Uint128::zero();
+        for (_, account) in &accounts {
+            total_debits = total_debits.checked_add(account.debits)?;
+            total_credits = total_credits.checked_add(account.credits)?;
+        }
+
+        Ok(TrialBalance {
+            accounts,
+            total_debits,
+            total_credits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    fn treasury(storage: &mut dyn Storage) -> StdResult<Ledger<'static>> {
+        let ledger = Ledger::new(b"treasury");
+        ledger.open_account(storage, "cash", AccountKind::Asset)?;
+        ledger.open_account(storage, "grants", AccountKind::Expense)?;
+        ledger.open_account(storage, "donations", AccountKind::Income)?;
+        Ok(ledger)
+    }
+
+    #[test]
+    fn test_post() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let ledger = treasury(&mut storage)?;
+
+        ledger.post(
+            &mut storage,
+            10,
+            Some("donation".to_string()),
+            vec![
+                Posting::debit("cash", Uint128::new(1000)),
+                Posting::credit("donations", Uint128::new(1000)),
+            ],
+        )?;
+        let position = ledger.post(
+            &mut storage,
+            12,
+            None,
+            vec![
+                Posting::debit("grants", Uint128::new(300)),
+                Posting::debit("grants", Uint128::new(100)),
+                Posting::credit("cash", Uint128::new(400)),
+            ],
+        )?;
+        assert_eq!(position, 1);
+
+        let cash = ledger.account(&storage, "cash").unwrap();
+        assert_eq!(cash.balance(), Some(Uint128::new(600)));
+        assert_eq!(
+            ledger.account(&storage, "grants").unwrap().balance(),
+            Some(Uint128::new(400))
+        );
+        assert_eq!(
+            ledger.account(&storage, "donations").unwrap().balance(),
+            Some(Uint128::new(1000))
+        );
+
+        let (transactions, total) = ledger.transactions(&storage, 0, 10)?;
+        assert_eq!(total, 2);
+        assert_eq!(transactions[0].memo.as_deref(), Some("donation"));
+        assert_eq!(ledger.transaction(&storage, 1)?.postings.len(), 3);
+
+        let trial_balance = ledger.trial_balance(&storage)?;
+        assert!(trial_balance.is_balanced());
+        assert_eq!(trial_balance.total_debits, Uint128::new(1400));
+        assert_eq!(trial_balance.accounts.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_transactions() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let ledger = treasury(&mut storage)?;
+
+        let err = ledger
+            .post(
+                &mut storage,
+                1,
+                None,
+                vec![
+                    Posting::debit("cash", Uint128::new(10)),
+                    Posting::credit("donations", Uint128::new(9)),
+                ],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("unbalanced transaction: debits of 10 but credits of 9")
+        );
+        assert!(ledger
+            .post(
+                &mut storage,
+                1,
+                None,
+                vec![
+                    Posting::debit("cash", Uint128::new(10)),
+                    Posting::credit("loans", Uint128::new(10)),
+                ],
+            )
+            .is_err());
+        assert!(ledger
+            .post(
+                &mut storage,
+                1,
+                None,
+                vec![
+                    Posting::debit("cash", Uint128::zero()),
+                    Posting::credit("donations", Uint128::zero())
+                ],
+            )
+            .is_err());
+        assert!(ledger
+            .post(
+                &mut storage,
+                1,
+                None,
+                vec![Posting::debit("cash", Uint128::new(1))]
+            )
+            .is_err());
+        assert!(ledger
+            .open_account(&mut storage, "cash", AccountKind::Liability)
+            .is_err());
+
+        // nothing was recorded
+        assert_eq!(ledger.transactions(&storage, 0, 10)?.1, 0);
+        assert_eq!(
+            ledger.account(&storage, "cash").unwrap().debits,
+            Uint128::zero()
+        );
+
+        // paying out more cash than there is overdraws the account
+        ledger.post(
+            &mut storage,
+            2,
+            None,
+            vec![
+                Posting::debit("grants", Uint128::new(5)),
+                Posting::credit("cash", Uint128::new(5)),
+            ],
+        )?;
+        assert_eq!(ledger.account(&storage, "cash").unwrap().balance(), None);
+        assert!(ledger.trial_balance(&storage)?.is_balanced());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,18 @@
-#![doc = include_str!("../Readme.md")]
+#![cfg_attr(
+    all(
+        feature = "generational-store",
+        feature = "maxheap",
+        feature = "interval-store",
+        feature = "ring-signature",
+        feature = "aggregator",
+        feature = "job-store",
+        feature = "ledger",
+        feature = "state-machine",
+        feature = "sparse-merkle",
+        feature = "lru-store"
+    ),
+    doc = include_str!("../Readme.md")
+)]
 
 #[cfg(feature = "generational-store")]
 pub mod generational_store;
@@ -9,3 +23,43 @@ pub use generational_store::{GenerationalStore, GenerationalStoreMut};
 pub mod maxheap;
 #[cfg(feature = "maxheap")]
 pub use maxheap::{MaxHeapStore, MaxHeapStoreMut};
+
+#[cfg(feature = "interval-store")]
+pub mod interval_store;
+#[cfg(feature = "interval-store")]
+pub use interval_store::{Interval, IntervalStore, IntervalStoreMut};
+
+#[cfg(feature = "ring-signature")]
+pub mod ring_signature;
+#[cfg(feature = "ring-signature")]
+pub use ring_signature::{KeyImageStore, RingSignature};
+
+#[cfg(feature = "aggregator")]
+pub mod aggregator;
+#[cfg(feature = "aggregator")]
+pub use aggregator::{Aggregator, BucketStats};
+
+#[cfg(feature = "job-store")]
+pub mod job_store;
+#[cfg(feature = "job-store")]
+pub use job_store::{Job, JobStore, Lease};
+
+#[cfg(feature = "ledger")]
+pub mod ledger;
+#[cfg(feature = "ledger")]
+pub use ledger::{Ledger, Posting, TrialBalance};
+
+#[cfg(feature = "state-machine")]
+pub mod state_machine;
+#[cfg(feature = "state-machine")]
+pub use state_machine::{StateChange, StateMachine};
+
+#[cfg(feature = "sparse-merkle")]
+pub mod sparse_merkle;
+#[cfg(feature = "sparse-merkle")]
+pub use sparse_merkle::{SparseMerkleProof, SparseMerkleTree};
+
+#[cfg(feature = "lru-store")]
+pub mod lru_store;
+#[cfg(feature = "lru-store")]
+pub use lru_store::LruStore;
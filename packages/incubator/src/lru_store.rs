@@ -0,0 +1,363 @@
+//! An "LRU store" is a map with a capacity, that evicts its least recently used entry when an
+//! insertion would exceed the capacity, e.g. to cache the results of cross-contract queries
+//! (oracle prices, code hashes) without growing the storage of the contract forever.
+//!
+//! The entries are linked to each other by their keys, from the most recently used one (the
+//! head) to the least recently used one (the tail), so that using, inserting or evicting an
+//! entry only writes the entry and its neighbours.
+
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use cosmwasm_std::{StdResult, Storage};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+const META_KEY: &[u8] = b"meta";
+const ENTRY_PREFIX: &[u8] = b"entry/";
+
+/// The ends of the list of entries, and its length
+#[derive(Serialize, Deserialize, Default)]
+struct Meta {
+    /// the key of the most recently used entry
+    head: Option<Vec<u8>>,
+    /// the key of the least recently used entry
+    tail: Option<Vec<u8>>,
+    len: u32,
+}
+
+/// An entry, along with the keys of its neighbours
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// the serialized value
+    value: Vec<u8>,
+    /// the key of the entry used more recently
+    prev: Option<Vec<u8>>,
+    /// the key of the entry used less recently
+    next: Option<Vec<u8>>,
+}
+
+/// A map from `K` to `V` that keeps at most `capacity` entries, evicting the least recently
+/// used ones
+pub struct LruStore<'a, K, V, Ser = Bincode2>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    namespace: &'a [u8],
+    capacity: u32,
+    key_type: PhantomData<K>,
+    value_type: PhantomData<V>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, K, V, Ser> LruStore<'a, K, V, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub const fn new(namespace: &'a [u8], capacity: u32) -> Self {
+        if capacity == 0 {
+            panic!("zero capacity used in lru store")
+        }
+        Self {
+            namespace,
+            capacity,
+            key_type: PhantomData,
+            value_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// Returns the number of entries the store keeps
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    fn serialize_key(&self, key: &K) -> StdResult<Vec<u8>> {
+        Bincode2::serialize(key)
+    }
+
+    fn entry_key(&self, key: &[u8]) -> Vec<u8> {
+        [self.namespace, ENTRY_PREFIX, key].concat()
+    }
+
+    fn load_meta(&self, storage: &dyn Storage) -> StdResult<Meta> {
+        match storage.get(&[self.namespace, META_KEY].concat()) {
+            Some(data) => Bincode2::deserialize(&data),
+            None => Ok(Meta::default()),
+        }
+    }
+
+    fn save_meta(&self, storage: &mut dyn Storage, meta: &Meta) -> StdResult<()> {
+        storage.set(
+            &[self.namespace, META_KEY].concat(),
+            &Bincode2::serialize(meta)?,
+        );
+        Ok(())
+    }
+
+    fn load_entry(&self, storage: &dyn Storage, key: &[u8]) -> StdResult<Option<Entry>> {
+        match storage.get(&self.entry_key(key)) {
+            Some(data) => Bincode2::deserialize(&data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn save_entry(&self, storage: &mut dyn Storage, key: &[u8], entry: &Entry) -> StdResult<()> {
+        storage.set(&self.entry_key(key), &Bincode2::serialize(entry)?);
+        Ok(())
+    }
+
+    /// Sets the `prev` or `next` link of the entry with the given key, if any
+    fn set_link(
+        &self,
+        storage: &mut dyn Storage,
+        key: &[u8],
+        set: impl FnOnce(&mut Entry),
+    ) -> StdResult<()> {
+        if let Some(mut entry) = self.load_entry(storage, key)? {
+            set(&mut entry);
+            self.save_entry(storage, key, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the entry from the list, linking its neighbours to each other
+    fn unlink(&self, storage: &mut dyn Storage, meta: &mut Meta, entry: &Entry) -> StdResult<()> {
+        match &entry.prev {
+            Some(prev) => self.set_link(storage, prev, |e| e.next = entry.next.clone())?,
+            None => meta.head = entry.next.clone(),
+        }
+        match &entry.next {
+            Some(next) => self.set_link(storage, next, |e| e.prev = entry.prev.clone())?,
+            None => meta.tail = entry.prev.clone(),
+        }
+        Ok(())
+    }
+
+    /// Saves the entry as the most recently used one
+    fn push_front(
+        &self,
+        storage: &mut dyn Storage,
+        meta: &mut Meta,
+        key: &[u8],
+        mut entry: Entry,
+    ) -> StdResult<()> {
+        entry.prev = None;
+        entry.next = meta.head.replace(key.to_vec());
+        match &entry.next {
+            Some(next) => self.set_link(storage, next, |e| e.prev = Some(key.to_vec()))?,
+            None => meta.tail = Some(key.to_vec()),
+        }
+        self.save_entry(storage, key, &entry)
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        Ok(self.load_meta(storage)?.len)
+    }
+
+    /// Returns whether the store has no entries
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.len(storage)? == 0)
+    }
+
+    /// Returns whether the store has an entry for `key`, without using it
+    pub fn contains(&self, storage: &dyn Storage, key: &K) -> StdResult<bool> {
+        Ok(storage
+            .get(&self.entry_key(&self.serialize_key(key)?))
+            .is_some())
+    }
+
+    /// Returns the value of `key` without using it, e.g. in queries, which can't write to the
+    /// storage
+    pub fn peek(&self, storage: &dyn Storage, key: &K) -> StdResult<Option<V>> {
+        match self.load_entry(storage, &self.serialize_key(key)?)? {
+            Some(entry) => Ser::deserialize(&entry.value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the value of `key`, and makes it the most recently used entry
+    pub fn get(&self, storage: &mut dyn Storage, key: &K) -> StdResult<Option<V>> {
+        let key = self.serialize_key(key)?;
+        let entry = match self.load_entry(storage, &key)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let value = Ser::deserialize(&entry.value)?;
+        let mut meta = self.load_meta(storage)?;
+        if meta.head.as_deref() != Some(key.as_slice()) {
+            self.unlink(storage, &mut meta, &entry)?;
+            self.push_front(storage, &mut meta, &key, entry)?;
+            self.save_meta(storage, &meta)?;
+        }
+        Ok(Some(value))
+    }
+
+    /// Sets the value of `key`, and makes it the most recently used entry. If the store was full
+    /// and didn't have an entry for `key`, the least recently used entry is evicted and returned.
+    pub fn insert(
+        &self,
+        storage: &mut dyn Storage,
+        key: &K,
+        value: &V,
+    ) -> StdResult<Option<(K, V)>> {
+        let key = self.serialize_key(key)?;
+        let mut meta = self.load_meta(storage)?;
+        let entry = Entry {
+            value: Ser::serialize(value)?,
+            prev: None,
+            next: None,
+        };
+
+        let mut evicted = None;
+        match self.load_entry(storage, &key)? {
+            Some(old) => self.unlink(storage, &mut meta, &old)?,
+            None if meta.len == self.capacity => {
+                if let Some(tail) = meta.tail.clone() {
+                    if let Some(old) = self.load_entry(storage, &tail)? {
+                        self.unlink(storage, &mut meta, &old)?;
+                        storage.remove(&self.entry_key(&tail));
+                        evicted =
+                            Some((Bincode2::deserialize(&tail)?, Ser::deserialize(&old.value)?));
+                    }
+                }
+            }
+            None => meta.len += 1,
+        }
+        self.push_front(storage, &mut meta, &key, entry)?;
+        self.save_meta(storage, &meta)?;
+
+        Ok(evicted)
+    }
+
+    /// Removes the entry of `key`, and returns its value, if it had one
+    pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<Option<V>> {
+        let key = self.serialize_key(key)?;
+        let entry = match self.load_entry(storage, &key)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let mut meta = self.load_meta(storage)?;
+        self.unlink(storage, &mut meta, &entry)?;
+        meta.len -= 1;
+        storage.remove(&self.entry_key(&key));
+        self.save_meta(storage, &meta)?;
+        Ser::deserialize(&entry.value).map(Some)
+    }
+
+    /// Returns up to `limit` entries, from the most recently used one, without using them
+    pub fn entries(&self, storage: &dyn Storage, limit: u32) -> StdResult<Vec<(K, V)>> {
+        let mut entries = vec![];
+        let mut next = self.load_meta(storage)?.head;
+        while let Some(key) = next {
+            if entries.len() >= limit as usize {
+                break;
+            }
+            let entry = match self.load_entry(storage, &key)? {
+                Some(entry) => entry,
+                None => break,
+            };
+            entries.push((
+                Bincode2::deserialize(&key)?,
+                Ser::deserialize(&entry.value)?,
+            ));
+            next = entry.next;
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use secret_toolkit_serialization::Json;
+
+    static PRICES: LruStore<String, u64> = LruStore::new(b"prices", 3);
+
+    fn keys(storage: &dyn Storage) -> StdResult<Vec<String>> {
+        Ok(PRICES
+            .entries(storage, 10)?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    #[test]
+    fn test_eviction() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(PRICES.is_empty(&storage)?);
+
+        for (denom, price) in [("uscrt", 1), ("uatom", 10), ("uosmo", 2)] {
+            assert_eq!(
+                PRICES.insert(&mut storage, &denom.to_string(), &price)?,
+                None
+            );
+        }
+        assert_eq!(keys(&storage)?, vec!["uosmo", "uatom", "uscrt"]);
+
+        // using an entry protects it from the next eviction
+        assert_eq!(PRICES.get(&mut storage, &"uscrt".to_string())?, Some(1));
+        assert_eq!(keys(&storage)?, vec!["uscrt", "uosmo", "uatom"]);
+        assert_eq!(
+            PRICES.insert(&mut storage, &"ujuno".to_string(), &3)?,
+            Some(("uatom".to_string(), 10))
+        );
+        assert_eq!(PRICES.len(&storage)?, 3);
+        assert!(!PRICES.contains(&storage, &"uatom".to_string())?);
+        assert_eq!(keys(&storage)?, vec!["ujuno", "uscrt", "uosmo"]);
+
+        // peeking doesn't use the entry
+        assert_eq!(PRICES.peek(&storage, &"uosmo".to_string())?, Some(2));
+        assert_eq!(
+            PRICES.insert(&mut storage, &"uatom".to_string(), &11)?,
+            Some(("uosmo".to_string(), 2))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_and_remove() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let hashes: LruStore<u64, String, Json> = LruStore::new(b"code_hashes", 2);
+
+        hashes.insert(&mut storage, &1, &"a".to_string())?;
+        hashes.insert(&mut storage, &2, &"b".to_string())?;
+        // updating an entry doesn't evict another one, and uses it
+        assert_eq!(hashes.insert(&mut storage, &1, &"c".to_string())?, None);
+        assert_eq!(hashes.len(&storage)?, 2);
+        assert_eq!(
+            hashes.entries(&storage, 10)?,
+            vec![(1, "c".to_string()), (2, "b".to_string())]
+        );
+
+        assert_eq!(hashes.remove(&mut storage, &1)?, Some("c".to_string()));
+        assert_eq!(hashes.remove(&mut storage, &1)?, None);
+        assert_eq!(hashes.len(&storage)?, 1);
+        assert_eq!(hashes.entries(&storage, 10)?, vec![(2, "b".to_string())]);
+
+        hashes.insert(&mut storage, &3, &"d".to_string())?;
+        assert_eq!(
+            hashes.insert(&mut storage, &4, &"e".to_string())?,
+            Some((2, "b".to_string()))
+        );
+        assert_eq!(hashes.remove(&mut storage, &4)?, Some("e".to_string()));
+        assert_eq!(hashes.remove(&mut storage, &3)?, Some("d".to_string()));
+        assert!(hashes.is_empty(&storage)?);
+        assert_eq!(hashes.entries(&storage, 10)?, vec![]);
+        assert_eq!(hashes.entries(&storage, 0)?, vec![]);
+
+        Ok(())
+    }
+}
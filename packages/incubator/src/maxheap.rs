@@ -246,7 +246,7 @@ where
     }
 
     /// Gain access to the implementation of the immutable methods
-    fn as_readonly(&self) -> MaxHeapStore<T, Ser> {
+    fn as_readonly(&self) -> MaxHeapStore<'_, T, Ser> {
         MaxHeapStore {
             storage: self.storage,
             item_type: self.item_type,
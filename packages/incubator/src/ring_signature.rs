@@ -0,0 +1,368 @@
+//! Linkable ring signatures (LSAG) over secp256k1.
+//!
+//! A ring signature proves that the signer holds the secret key of one of the public keys in a
+//! ring, without revealing which one. Every signature also carries a key image, which is unique
+//! per signer and context, so a `KeyImageStore` can reject a second signature by the same
+//! member (e.g. a second vote on the same proposal) while keeping the member anonymous.
+//!
+//! The scheme follows "Linkable Spontaneous Anonymous Group Signature for Ad Hoc Groups"
+//! (Liu, Wei, Wong), with hashes to the curve and to scalars per RFC 9380.
+
+use cosmwasm_std::{Binary, StdError, StdResult, Storage};
+use k256::elliptic_curve::group::GroupEncoding;
+use k256::elliptic_curve::hash2curve::{ExpandMsgXmd, GroupDigest};
+use k256::elliptic_curve::{Field, PrimeField};
+use k256::{FieldBytes, ProjectivePoint, PublicKey, Scalar, Secp256k1};
+use rand_core::{CryptoRng, RngCore};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+const KEY_IMAGE_DST: &[u8] = b"secret-toolkit-lsag-secp256k1-key-image";
+const CHALLENGE_DST: &[u8] = b"secret-toolkit-lsag-secp256k1-challenge";
+
+/// A linkable ring signature
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct RingSignature {
+    /// the key image of the signer, identical for all signatures by the same key and context
+    pub key_image: Binary,
+    /// the initial challenge scalar
+    pub challenge: Binary,
+    /// one response scalar per member of the ring
+    pub responses: Vec<Binary>,
+}
+
+fn decode_point(bytes: &[u8]) -> StdResult<ProjectivePoint> {
+    PublicKey::from_sec1_bytes(bytes)
+        .map(|key| key.to_projective())
+        .map_err(|_| StdError::generic_err("invalid secp256k1 point"))
+}
+
+fn encode_point(point: &ProjectivePoint) -> Vec<u8> {
+    point.to_affine().to_bytes().to_vec()
+}
+
+fn decode_scalar(bytes: &[u8]) -> StdResult<Scalar> {
+    if bytes.len() != 32 {
+        return Err(StdError::generic_err("invalid secp256k1 scalar"));
+    }
+    Option::from(Scalar::from_repr(FieldBytes::clone_from_slice(bytes)))
+        .ok_or_else(|| StdError::generic_err("invalid secp256k1 scalar"))
+}
+
+fn encode_scalar(scalar: &Scalar) -> Binary {
+    Binary(scalar.to_repr().to_vec())
+}
+
+/// Hashes a ring member to the point its key image is derived from
+fn key_image_base(context: &[u8], member: &[u8]) -> StdResult<ProjectivePoint> {
+    Secp256k1::hash_from_bytes::<ExpandMsgXmd<Sha256>>(&[context, member], &[KEY_IMAGE_DST])
+        .map_err(|_| StdError::generic_err("failed to hash to curve"))
+}
+
+/// The ring, encoded once so it can be included in every challenge
+struct Ring {
+    points: Vec<ProjectivePoint>,
+    bases: Vec<ProjectivePoint>,
+    encoded: Vec<u8>,
+}
+
+impl Ring {
+    fn new(members: &[Binary], context: &[u8]) -> StdResult<Self> {
+        if members.is_empty() {
+            return Err(StdError::generic_err("ring must not be empty"));
+        }
+
+        let mut encoded = (members.len() as u32).to_be_bytes().to_vec();
+        let mut points = Vec::with_capacity(members.len());
+        let mut bases = Vec::with_capacity(members.len());
+        for member in members {
+            let point = decode_point(member)?;
+            let member = encode_point(&point);
+            bases.push(key_image_base(context, &member)?);
+            encoded.extend_from_slice(&member);
+            points.push(point);
+        }
+
+        Ok(Self {
+            points,
+            bases,
+            encoded,
+        })
+    }
+
+    fn challenge(
+        &self,
+        key_image: &[u8],
+        message: &[u8],
+        l: &ProjectivePoint,
+        r: &ProjectivePoint,
+    ) -> StdResult<Scalar> {
+        let l = encode_point(l);
+        let r = encode_point(r);
+        Secp256k1::hash_to_scalar::<ExpandMsgXmd<Sha256>>(
+            &[&self.encoded, key_image, &l, &r, message],
+            &[CHALLENGE_DST],
+        )
+        .map_err(|_| StdError::generic_err("failed to hash to scalar"))
+    }
+}
+
+/// Verifies a ring signature.
+///
+/// Returns an error if the inputs are malformed, and `false` if the signature is invalid.
+///
+/// # Arguments
+///
+/// * `ring` - the compressed or uncompressed SEC1 public keys of the ring members
+/// * `context` - scopes the key image, e.g. a proposal id, so signatures in different contexts
+///   can not be linked to each other
+/// * `message` - the signed message
+/// * `signature` - the signature to verify
+pub fn verify_ring_signature(
+    ring: &[Binary],
+    context: &[u8],
+    message: &[u8],
+    signature: &RingSignature,
+) -> StdResult<bool> {
+    let ring = Ring::new(ring, context)?;
+    if signature.responses.len() != ring.points.len() {
+        return Err(StdError::generic_err(
+            "ring signature must have one response per ring member",
+        ));
+    }
+
+    let key_image_point = decode_point(&signature.key_image)?;
+    let key_image = encode_point(&key_image_point);
+    let initial = decode_scalar(&signature.challenge)?;
+
+    let mut challenge = initial;
+    for ((point, base), response) in ring
+        .points
+        .iter()
+        .zip(&ring.bases)
+        .zip(&signature.responses)
+    {
+        let response = decode_scalar(response)?;
+        let l = ProjectivePoint::GENERATOR * response + point * &challenge;
+        let r = base * &response + key_image_point * challenge;
+        challenge = ring.challenge(&key_image, message, &l, &r)?;
+    }
+
+    Ok(challenge == initial)
+}
+
+/// Signs a message on behalf of a ring.
+///
+/// This is meant for clients and tests; contracts only need to verify signatures.
+///
+/// # Arguments
+///
+/// * `ring` - the compressed or uncompressed SEC1 public keys of the ring members
+/// * `index` - the position of the signer's public key in the ring
+/// * `secret_key` - the 32 byte secret key of the signer
+/// * `context` - scopes the key image, see `verify_ring_signature`
+/// * `message` - the message to sign
+/// * `rng` - a cryptographically secure random number generator
+pub fn sign_ring_signature(
+    ring: &[Binary],
+    index: usize,
+    secret_key: &[u8],
+    context: &[u8],
+    message: &[u8],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> StdResult<RingSignature> {
+    let ring = Ring::new(ring, context)?;
+    let size = ring.points.len();
+    if index >= size {
+        return Err(StdError::generic_err("signer index is out of the ring"));
+    }
+    let secret = decode_scalar(secret_key)?;
+    if ProjectivePoint::GENERATOR * secret != ring.points[index] {
+        return Err(StdError::generic_err(
+            "secret key does not match the ring member",
+        ));
+    }
+
+    let key_image = encode_point(&(ring.bases[index] * secret));
+    let key_image_point = decode_point(&key_image)?;
+
+    let mut challenges = vec![Scalar::ZERO; size];
+    let mut responses = vec![Scalar::ZERO; size];
+
+    let nonce = Scalar::random(&mut *rng);
+    let mut next = (index + 1) % size;
+    challenges[next] = ring.challenge(
+        &key_image,
+        message,
+        &(ProjectivePoint::GENERATOR * nonce),
+        &(ring.bases[index] * nonce),
+    )?;
+    while next != index {
+        let i = next;
+        responses[i] = Scalar::random(&mut *rng);
+        let l = ProjectivePoint::GENERATOR * responses[i] + ring.points[i] * challenges[i];
+        let r = ring.bases[i] * responses[i] + key_image_point * challenges[i];
+        next = (i + 1) % size;
+        challenges[next] = ring.challenge(&key_image, message, &l, &r)?;
+    }
+    responses[index] = nonce - challenges[index] * secret;
+
+    Ok(RingSignature {
+        key_image: Binary(key_image),
+        challenge: encode_scalar(&challenges[0]),
+        responses: responses.iter().map(encode_scalar).collect(),
+    })
+}
+
+/// Keeps track of used key images, so each ring member can only sign once per context.
+pub struct KeyImageStore<'a> {
+    namespace: &'a [u8],
+}
+
+impl<'a> KeyImageStore<'a> {
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self { namespace }
+    }
+
+    fn key(&self, key_image: &[u8]) -> Vec<u8> {
+        [self.namespace, key_image].concat()
+    }
+
+    /// Returns true if the key image was already used
+    pub fn is_used(&self, storage: &dyn Storage, key_image: &[u8]) -> bool {
+        storage.get(&self.key(key_image)).is_some()
+    }
+
+    /// Marks the key image as used, failing if it was already used
+    pub fn mark_used(&self, storage: &mut dyn Storage, key_image: &[u8]) -> StdResult<()> {
+        if self.is_used(storage, key_image) {
+            return Err(StdError::generic_err("key image was already used"));
+        }
+        // empty values are not allowed, so we store an unimportant byte
+        storage.set(&self.key(key_image), &[1]);
+
+        Ok(())
+    }
+
+    /// Verifies a ring signature and marks its key image as used
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - a mutable reference to the storage the key images are kept in
+    /// * `ring` - the compressed or uncompressed SEC1 public keys of the ring members
+    /// * `context` - scopes the key image, see `verify_ring_signature`
+    /// * `message` - the signed message
+    /// * `signature` - the signature to verify
+    pub fn verify_and_consume(
+        &self,
+        storage: &mut dyn Storage,
+        ring: &[Binary],
+        context: &[u8],
+        message: &[u8],
+        signature: &RingSignature,
+    ) -> StdResult<()> {
+        if !verify_ring_signature(ring, context, message, signature)? {
+            return Err(StdError::generic_err("Failed to verify the ring signature"));
+        }
+        // the point was validated by the verification, so its canonical encoding can be used
+        let key_image = encode_point(&decode_point(&signature.key_image)?);
+        self.mark_used(storage, &key_image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    fn keys(rng: &mut ChaChaRng, count: usize) -> (Vec<Vec<u8>>, Vec<Binary>) {
+        let secrets: Vec<Scalar> = (0..count).map(|_| Scalar::random(&mut *rng)).collect();
+        let ring = secrets
+            .iter()
+            .map(|secret| Binary(encode_point(&(ProjectivePoint::GENERATOR * secret))))
+            .collect();
+        let secrets = secrets.iter().map(|s| s.to_repr().to_vec()).collect();
+        (secrets, ring)
+    }
+
+    #[test]
+    fn test_sign_and_verify() -> StdResult<()> {
+        let mut rng = ChaChaRng::seed_from_u64(1);
+        let (secrets, ring) = keys(&mut rng, 5);
+
+        for (index, secret) in secrets.iter().enumerate() {
+            let signature =
+                sign_ring_signature(&ring, index, secret, b"proposal-1", b"yes", &mut rng)?;
+            assert!(verify_ring_signature(
+                &ring,
+                b"proposal-1",
+                b"yes",
+                &signature
+            )?);
+            assert!(!verify_ring_signature(
+                &ring,
+                b"proposal-1",
+                b"no",
+                &signature
+            )?);
+            assert!(!verify_ring_signature(
+                &ring,
+                b"proposal-2",
+                b"yes",
+                &signature
+            )?);
+
+            let mut reordered = ring.clone();
+            reordered.swap(0, 1);
+            assert!(!verify_ring_signature(
+                &reordered,
+                b"proposal-1",
+                b"yes",
+                &signature
+            )?);
+        }
+
+        let (_, single) = keys(&mut rng, 1);
+        assert!(sign_ring_signature(&single, 0, &secrets[0], b"", b"", &mut rng).is_err());
+        assert!(sign_ring_signature(&ring, 5, &secrets[0], b"", b"", &mut rng).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_images_link_signers() -> StdResult<()> {
+        let mut rng = ChaChaRng::seed_from_u64(2);
+        let (secrets, ring) = keys(&mut rng, 3);
+
+        let first = sign_ring_signature(&ring, 1, &secrets[1], b"vote", b"a", &mut rng)?;
+        let second = sign_ring_signature(&ring, 1, &secrets[1], b"vote", b"b", &mut rng)?;
+        let other_member = sign_ring_signature(&ring, 2, &secrets[2], b"vote", b"a", &mut rng)?;
+        let other_context = sign_ring_signature(&ring, 1, &secrets[1], b"poll", b"a", &mut rng)?;
+
+        assert_eq!(first.key_image, second.key_image);
+        assert_ne!(first.key_image, other_member.key_image);
+        assert_ne!(first.key_image, other_context.key_image);
+
+        let mut storage = MockStorage::new();
+        let store = KeyImageStore::new(b"key_images");
+        store.verify_and_consume(&mut storage, &ring, b"vote", b"a", &first)?;
+        assert!(store.is_used(&storage, &first.key_image));
+        assert_eq!(
+            store.verify_and_consume(&mut storage, &ring, b"vote", b"b", &second),
+            Err(StdError::generic_err("key image was already used"))
+        );
+        store.verify_and_consume(&mut storage, &ring, b"vote", b"a", &other_member)?;
+
+        let mut forged = other_context.clone();
+        forged.responses.pop();
+        assert!(store
+            .verify_and_consume(&mut storage, &ring, b"poll", b"a", &forged)
+            .is_err());
+        assert!(!store.is_used(&storage, &other_context.key_image));
+
+        Ok(())
+    }
+}
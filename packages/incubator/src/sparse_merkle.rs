@@ -0,0 +1,273 @@
+//! A sparse Merkle tree over 256-bit keys, for sharing individual entries of a large private
+//! dataset with users, who can verify them against a published root without the rest of the
+//! dataset.
+//!
+//! Leaves are hashed as `sha256(0x00 || key || sha256(value))`, and internal nodes as
+//! `sha256(0x01 || left || right)`, except that the hash of an empty subtree is all zeros at
+//! every level, so that only the nodes of non-empty subtrees are stored. The bits of the key,
+//! from the most significant one, choose the path from the root to its leaf.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{Binary, Storage};
+
+/// the number of levels below the root, one for each bit of the keys
+const DEPTH: u16 = 256;
+const EMPTY: [u8; 32] = [0u8; 32];
+const NODES: &[u8] = b"nodes";
+const VALUES: &[u8] = b"values";
+
+/// A proof that a key has a value, or no value, in a tree with a given root
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseMerkleProof {
+    /// the hashes of the siblings of the path from the leaf to the root, along with their level,
+    /// from the leaf up. The siblings that are the root of an empty subtree are left out.
+    pub siblings: Vec<(u16, Binary)>,
+}
+
+/// A sparse Merkle tree whose nodes and values are stored under `namespace`.
+///
+/// Each update writes the 256 nodes on the path of its key, so it is only suited to contracts
+/// that update few entries at a time.
+pub struct SparseMerkleTree<'a> {
+    namespace: &'a [u8],
+}
+
+impl<'a> SparseMerkleTree<'a> {
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self { namespace }
+    }
+
+    fn node_key(&self, level: u16, path: &[u8; 32]) -> Vec<u8> {
+        [self.namespace, NODES, &level.to_be_bytes(), path].concat()
+    }
+
+    fn value_key(&self, key: &[u8; 32]) -> Vec<u8> {
+        [self.namespace, VALUES, key].concat()
+    }
+
+    fn node(&self, storage: &dyn Storage, level: u16, path: &[u8; 32]) -> [u8; 32] {
+        storage
+            .get(&self.node_key(level, path))
+            .and_then(|hash| hash.try_into().ok())
+            .unwrap_or(EMPTY)
+    }
+
+    fn set_node(&self, storage: &mut dyn Storage, level: u16, path: &[u8; 32], hash: &[u8; 32]) {
+        let node_key = self.node_key(level, path);
+        if *hash == EMPTY {
+            storage.remove(&node_key);
+        } else {
+            storage.set(&node_key, hash);
+        }
+    }
+
+    /// Returns the root of the tree, which is all zeros for an empty tree
+    pub fn root(&self, storage: &dyn Storage) -> [u8; 32] {
+        self.node(storage, DEPTH, &EMPTY)
+    }
+
+    /// Returns the value of a key, if it has one
+    pub fn get(&self, storage: &dyn Storage, key: &[u8; 32]) -> Option<Vec<u8>> {
+        storage.get(&self.value_key(key))
+    }
+
+    /// Returns the value of a key, if it has one, along with the proof of it against the current
+    /// root
+    pub fn get_with_proof(
+        &self,
+        storage: &dyn Storage,
+        key: &[u8; 32],
+    ) -> (Option<Vec<u8>>, SparseMerkleProof) {
+        let siblings = (0..DEPTH)
+            .filter_map(|level| {
+                let sibling = self.node(storage, level, &sibling_path(key, level));
+                (sibling != EMPTY).then(|| (level, Binary::from(sibling.as_slice())))
+            })
+            .collect();
+        (self.get(storage, key), SparseMerkleProof { siblings })
+    }
+
+    /// Sets the value of a key, or removes it if `value` is `None`, and returns the new root
+    pub fn update(
+        &self,
+        storage: &mut dyn Storage,
+        key: &[u8; 32],
+        value: Option<&[u8]>,
+    ) -> [u8; 32] {
+        match value {
+            Some(value) => storage.set(&self.value_key(key), value),
+            None => storage.remove(&self.value_key(key)),
+        }
+
+        let mut hash = leaf_hash(key, value);
+        for level in 0..DEPTH {
+            self.set_node(storage, level, &masked_path(key, level), &hash);
+            let sibling = self.node(storage, level, &sibling_path(key, level));
+            hash = parent_hash(is_right(key, level), &hash, &sibling);
+        }
+        self.set_node(storage, DEPTH, &EMPTY, &hash);
+
+        hash
+    }
+}
+
+/// Returns whether `proof` proves that `key` has `value` (or no value, if `None`) in the tree
+/// with the given root. This doesn't need the storage, so light clients can use it.
+pub fn verify_proof(
+    root: &[u8; 32],
+    key: &[u8; 32],
+    value: Option<&[u8]>,
+    proof: &SparseMerkleProof,
+) -> bool {
+    let mut siblings = proof.siblings.iter().peekable();
+    let mut hash = leaf_hash(key, value);
+    for level in 0..DEPTH {
+        let sibling = match siblings.next_if(|(sibling_level, _)| *sibling_level == level) {
+            Some((_, sibling)) => match sibling.as_slice().try_into() {
+                Ok(sibling) => sibling,
+                Err(_) => return false,
+            },
+            None => EMPTY,
+        };
+        hash = parent_hash(is_right(key, level), &hash, &sibling);
+    }
+    siblings.next().is_none() && hash == *root
+}
+
+fn leaf_hash(key: &[u8; 32], value: Option<&[u8]>) -> [u8; 32] {
+    match value {
+        Some(value) => Sha256::new()
+            .chain_update([0x00])
+            .chain_update(key)
+            .chain_update(Sha256::digest(value))
+            .finalize()
+            .into(),
+        None => EMPTY,
+    }
+}
+
+fn parent_hash(is_right: bool, node: &[u8; 32], sibling: &[u8; 32]) -> [u8; 32] {
+    if *node == EMPTY && *sibling == EMPTY {
+        return EMPTY;
+    }
+    let (left, right) = if is_right {
+        (sibling, node)
+    } else {
+        (node, sibling)
+    };
+    Sha256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Returns whether the node on the path of `key` at `level` is the right child of its parent
+fn is_right(key: &[u8; 32], level: u16) -> bool {
+    let bit = (DEPTH - 1 - level) as usize;
+    key[bit / 8] & (0x80 >> (bit % 8)) != 0
+}
+
+/// Returns the path identifying the node on the path of `key` at `level`, which is the key
+/// without its `level` least significant bits
+fn masked_path(key: &[u8; 32], level: u16) -> [u8; 32] {
+    let mut path = *key;
+    for bit in (DEPTH - level) as usize..DEPTH as usize {
+        path[bit / 8] &= !(0x80 >> (bit % 8));
+    }
+    path
+}
+
+/// Returns the path identifying the sibling of the node on the path of `key` at `level`
+fn sibling_path(key: &[u8; 32], level: u16) -> [u8; 32] {
+    let mut path = masked_path(key, level);
+    let bit = (DEPTH - 1 - level) as usize;
+    path[bit / 8] ^= 0x80 >> (bit % 8);
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    static TREE: SparseMerkleTree = SparseMerkleTree::new(b"tree");
+
+    fn key(byte: u8) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        key[0] = byte;
+        key[31] = byte;
+        key
+    }
+
+    #[test]
+    fn test_update_and_root() {
+        let mut storage = MockStorage::new();
+        assert_eq!(TREE.root(&storage), EMPTY);
+
+        let root_a = TREE.update(&mut storage, &key(0x01), Some(b"a"));
+        assert_ne!(root_a, EMPTY);
+        assert_eq!(TREE.root(&storage), root_a);
+        let root_ab = TREE.update(&mut storage, &key(0x81), Some(b"b"));
+        assert_eq!(TREE.get(&storage, &key(0x81)), Some(b"b".to_vec()));
+
+        // the root doesn't depend on the order of the updates
+        let mut other_storage = MockStorage::new();
+        TREE.update(&mut other_storage, &key(0x81), Some(b"b"));
+        TREE.update(&mut other_storage, &key(0x01), Some(b"a"));
+        assert_eq!(TREE.root(&other_storage), root_ab);
+
+        // removing a key restores the previous root, and removes its nodes
+        assert_eq!(TREE.update(&mut storage, &key(0x81), None), root_a);
+        assert_eq!(TREE.get(&storage, &key(0x81)), None);
+        assert_eq!(TREE.update(&mut storage, &key(0x01), None), EMPTY);
+        assert_eq!(storage.get(&TREE.node_key(0, &key(0x01))), None);
+        assert_eq!(storage.get(&TREE.node_key(255, &EMPTY)), None);
+    }
+
+    #[test]
+    fn test_proofs() {
+        let mut storage = MockStorage::new();
+        for byte in [0x01, 0x02, 0x81, 0xff] {
+            TREE.update(&mut storage, &key(byte), Some(&[byte]));
+        }
+        let root = TREE.root(&storage);
+
+        let (value, proof) = TREE.get_with_proof(&storage, &key(0x02));
+        assert_eq!(value, Some(vec![0x02]));
+        assert!(verify_proof(&root, &key(0x02), Some(&[0x02]), &proof));
+        assert!(!verify_proof(&root, &key(0x02), Some(&[0x03]), &proof));
+        assert!(!verify_proof(&root, &key(0x02), None, &proof));
+        assert!(!verify_proof(&root, &key(0x01), Some(&[0x02]), &proof));
+
+        // a key without a value can be proven absent
+        let (value, proof) = TREE.get_with_proof(&storage, &key(0x03));
+        assert_eq!(value, None);
+        assert!(verify_proof(&root, &key(0x03), None, &proof));
+        assert!(!verify_proof(&root, &key(0x03), Some(&[0x03]), &proof));
+
+        // a tampered proof is rejected
+        let (_, mut proof) = TREE.get_with_proof(&storage, &key(0xff));
+        assert!(verify_proof(&root, &key(0xff), Some(&[0xff]), &proof));
+        proof.siblings[0].1 = Binary::from([1u8; 32].as_slice());
+        assert!(!verify_proof(&root, &key(0xff), Some(&[0xff]), &proof));
+        proof.siblings.truncate(0);
+        assert!(!verify_proof(&root, &key(0xff), Some(&[0xff]), &proof));
+
+        // proofs are against the root they were made with
+        TREE.update(&mut storage, &key(0x05), Some(b"new"));
+        let (_, proof) = TREE.get_with_proof(&storage, &key(0x02));
+        assert!(!verify_proof(&root, &key(0x02), Some(&[0x02]), &proof));
+        assert!(verify_proof(
+            &TREE.root(&storage),
+            &key(0x02),
+            Some(&[0x02]),
+            &proof
+        ));
+    }
+}
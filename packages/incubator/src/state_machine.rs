@@ -0,0 +1,261 @@
+//! A state machine, for the lifecycles of escrows, auctions, loans and other entities that move
+//! through a fixed set of states.
+//!
+//! The allowed transitions are declared up front, and the current state of each entity is kept
+//! in storage. A transition that isn't declared is rejected, and every transition is recorded in
+//! the history of its entity, so the lifecycle of an entity can be audited.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use cosmwasm_std::{BlockInfo, StdError, StdResult, Storage, Timestamp};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+use secret_toolkit_storage::{AppendStore, Keymap};
+
+const STATES_SUFFIX: &[u8] = b"states";
+const HISTORY_SUFFIX: &[u8] = b"history";
+
+/// A change of the state of an entity, as recorded in its history
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct StateChange<S> {
+    /// the previous state, or `None` when the entity was started
+    pub from: Option<S>,
+    pub to: S,
+    pub block_height: u64,
+    pub time: Timestamp,
+}
+
+/// A state machine over the states `S` of entities identified by `E`, whose states and history
+/// are kept under `namespace`
+pub struct StateMachine<'a, E, S>
+where
+    E: Serialize + DeserializeOwned,
+    S: Serialize + DeserializeOwned + Clone + PartialEq + Debug,
+{
+    namespace: &'a [u8],
+    initial: S,
+    transitions: &'a [(S, S)],
+    entity_type: PhantomData<E>,
+}
+
+impl<'a, E, S> StateMachine<'a, E, S>
+where
+    E: Serialize + DeserializeOwned,
+    S: Serialize + DeserializeOwned + Clone + PartialEq + Debug,
+{
+    /// constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - the storage namespace of the states and history of the entities
+    /// * `initial` - the state entities are started in
+    /// * `transitions` - the allowed transitions, as pairs of the state they are from and the
+    ///   state they are to
+    pub const fn new(namespace: &'a [u8], initial: S, transitions: &'a [(S, S)]) -> Self {
+        Self {
+            namespace,
+            initial,
+            transitions,
+            entity_type: PhantomData,
+        }
+    }
+
+    fn states(&self) -> Keymap<'a, E, S> {
+        Keymap::new(self.namespace).add_suffix(STATES_SUFFIX)
+    }
+
+    fn history_of(&self, entity: &E) -> StdResult<AppendStore<'a, StateChange<S>>> {
+        Ok(AppendStore::new(self.namespace)
+            .add_suffix(HISTORY_SUFFIX)
+            .add_suffix(&Bincode2::serialize(entity)?))
+    }
+
+    /// Returns whether the transition from `from` to `to` was declared
+    pub fn can_transition(&self, from: &S, to: &S) -> bool {
+        self.transitions
+            .iter()
+            .any(|(allowed_from, allowed_to)| allowed_from == from && allowed_to == to)
+    }
+
+    /// Returns whether no transition leaves `state`, e.g. for the settled state of an escrow
+    pub fn is_final(&self, state: &S) -> bool {
+        !self.transitions.iter().any(|(from, _)| from == state)
+    }
+
+    /// Starts an entity in the initial state. Fails if the entity was already started.
+    pub fn start(&self, storage: &mut dyn Storage, block: &BlockInfo, entity: &E) -> StdResult<()> {
+        let states = self.states();
+        if states.contains(storage, entity) {
+            return Err(StdError::generic_err(
+                "state machine: entity was already started",
+            ));
+        }
+        states.insert(storage, entity, &self.initial)?;
+        self.history_of(entity)?.push(
+            storage,
+            &StateChange {
+                from: None,
+                to: self.initial.clone(),
+                block_height: block.height,
+                time: block.time,
+            },
+        )
+    }
+
+    /// Returns the current state of an entity, or `None` if it wasn't started
+    pub fn state(&self, storage: &dyn Storage, entity: &E) -> Option<S> {
+        self.states().get(storage, entity)
+    }
+
+    /// Moves an entity to the state `to`, records the change in its history, and returns the
+    /// state it was in.
+    ///
+    /// Fails, without changing the state, if the entity wasn't started or the transition from
+    /// its current state to `to` wasn't declared.
+    pub fn transition(
+        &self,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        entity: &E,
+        to: S,
+    ) -> StdResult<S> {
+        let states = self.states();
+        let from = states
+            .get(storage, entity)
+            .ok_or_else(|| StdError::generic_err("state machine: entity was not started"))?;
+        if !self.can_transition(&from, &to) {
+            return Err(StdError::generic_err(format!(
+                "state machine: invalid transition from {from:?} to {to:?}"
+            )));
+        }
+
+        states.insert(storage, entity, &to)?;
+        self.history_of(entity)?.push(
+            storage,
+            &StateChange {
+                from: Some(from.clone()),
+                to,
+                block_height: block.height,
+                time: block.time,
+            },
+        )?;
+
+        Ok(from)
+    }
+
+    /// Returns a page of the history of an entity, the earliest changes first, and the number of
+    /// changes
+    pub fn history(
+        &self,
+        storage: &dyn Storage,
+        entity: &E,
+        page: u32,
+        page_size: u32,
+    ) -> StdResult<(Vec<StateChange<S>>, u32)> {
+        let history = self.history_of(entity)?;
+        Ok((
+            history.paging(storage, page, page_size)?,
+            history.get_len(storage)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    enum Escrow {
+        Open,
+        Funded,
+        Released,
+        Refunded,
+    }
+
+    static ESCROWS: StateMachine<u64, Escrow> = StateMachine::new(
+        b"escrows",
+        Escrow::Open,
+        &[
+            (Escrow::Open, Escrow::Funded),
+            (Escrow::Funded, Escrow::Released),
+            (Escrow::Funded, Escrow::Refunded),
+        ],
+    );
+
+    #[test]
+    fn test_transitions() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut block = mock_env().block;
+
+        ESCROWS.start(&mut storage, &block, &1)?;
+        ESCROWS.start(&mut storage, &block, &2)?;
+        assert_eq!(ESCROWS.state(&storage, &1), Some(Escrow::Open));
+        assert_eq!(ESCROWS.state(&storage, &3), None);
+
+        block.height += 1;
+        assert_eq!(
+            ESCROWS.transition(&mut storage, &block, &1, Escrow::Funded)?,
+            Escrow::Open
+        );
+        block.height += 1;
+        ESCROWS.transition(&mut storage, &block, &1, Escrow::Released)?;
+        assert_eq!(ESCROWS.state(&storage, &1), Some(Escrow::Released));
+        assert!(ESCROWS.is_final(&Escrow::Released));
+        assert!(!ESCROWS.is_final(&Escrow::Funded));
+
+        // the other entity has its own state and history
+        assert_eq!(ESCROWS.state(&storage, &2), Some(Escrow::Open));
+        assert_eq!(ESCROWS.history(&storage, &2, 0, 10)?.1, 1);
+
+        let (history, total) = ESCROWS.history(&storage, &1, 0, 10)?;
+        assert_eq!(total, 3);
+        assert_eq!(history[0].from, None);
+        assert_eq!(
+            history[2],
+            StateChange {
+                from: Some(Escrow::Funded),
+                to: Escrow::Released,
+                block_height: block.height,
+                time: block.time,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_transitions() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let block = mock_env().block;
+
+        assert!(ESCROWS
+            .transition(&mut storage, &block, &1, Escrow::Funded)
+            .is_err());
+
+        ESCROWS.start(&mut storage, &block, &1)?;
+        assert!(ESCROWS.start(&mut storage, &block, &1).is_err());
+        assert_eq!(
+            ESCROWS
+                .transition(&mut storage, &block, &1, Escrow::Released)
+                .unwrap_err(),
+            StdError::generic_err("state machine: invalid transition from Open to Released")
+        );
+
+        ESCROWS.transition(&mut storage, &block, &1, Escrow::Funded)?;
+        ESCROWS.transition(&mut storage, &block, &1, Escrow::Refunded)?;
+        assert!(ESCROWS
+            .transition(&mut storage, &block, &1, Escrow::Released)
+            .is_err());
+
+        // nothing was recorded for the rejected transitions
+        assert_eq!(ESCROWS.state(&storage, &1), Some(Escrow::Refunded));
+        assert_eq!(ESCROWS.history(&storage, &1, 0, 10)?.1, 3);
+
+        Ok(())
+    }
+}
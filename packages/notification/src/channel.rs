@@ -0,0 +1,184 @@
+//! Declaration of direct channels whose CBOR encoding and CDDL schema are generated together.
+
+/// Declares the payload struct of a direct channel, and implements [`DirectChannel`] for it.
+///
+/// The fields are encoded in the order they are declared, and the CDDL schema served by the
+/// `ChannelInfo` query is generated from the same declaration, so the two can't drift apart.
+/// The supported field types, and their CDDL types, are:
+///
+/// * `bool` - `bool`
+/// * `u8`, `u16`, `u32`, `u64` - `uint .size 1`, `uint .size 2`, `uint .size 4`, `uint .size 8`
+/// * `u128` - `biguint .size 8`, of which only the 8 low bytes are encoded
+/// * `Addr` - `bstr .size 20`, the canonical address
+///
+/// ```
+/// # use cosmwasm_std::{testing::mock_dependencies, StdResult};
+/// use secret_toolkit_notification::{direct_channel, DirectChannel};
+///
+/// direct_channel! {
+///     #[channel_id = "my_channel"]
+///     #[derive(Clone, Debug)]
+///     pub struct MyNotification {
+///         pub amount: u128,
+///         pub memo_len: u8,
+///     }
+/// }
+///
+/// assert_eq!(
+///     MyNotification::CDDL_SCHEMA,
+///     "my_channel=[amount:biguint .size 8,memo_len:uint .size 1]"
+/// );
+///
+/// let payload = MyNotification { amount: 1000, memo_len: 12 };
+/// assert_eq!(payload.to_cbor(&mock_dependencies().api)?.len(), MyNotification::PAYLOAD_SIZE);
+/// # Ok::<(), cosmwasm_std::StdError>(())
+/// ```
+///
+/// [`DirectChannel`]: crate::DirectChannel
+#[macro_export]
+macro_rules! direct_channel {
+    (
+        #[channel_id = $channel_id:literal]
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ident),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),+
+        }
+
+        impl $crate::DirectChannel for $name {
+            const CHANNEL_ID: &'static str = $channel_id;
+            const CDDL_SCHEMA: &'static str = concat!(
+                $channel_id,
+                "=[",
+                $crate::direct_channel!(@cddl_fields $($field: $ty),+),
+                "]"
+            );
+            const ELEMENTS: u64 = [$(stringify!($field)),+].len() as u64;
+            const PAYLOAD_SIZE: usize = if Self::ELEMENTS < 24 {
+                $crate::CBL_ARRAY_SHORT
+            } else if Self::ELEMENTS < 256 {
+                $crate::CBL_ARRAY_MEDIUM
+            } else {
+                $crate::CBL_ARRAY_LARGE
+            } $(+ $crate::direct_channel!(@size $ty))+;
+
+            // `api` is only used by address fields
+            #[allow(unused_variables)]
+            fn encode_cbor(
+                &self,
+                api: &dyn ::cosmwasm_std::Api,
+                encoder: &mut ::minicbor::Encoder<&mut [u8]>,
+            ) -> ::cosmwasm_std::StdResult<()> {
+                $($crate::direct_channel!(@encode $ty, api, encoder, &self.$field);)+
+                Ok(())
+            }
+        }
+    };
+
+    (@cddl_fields $field:ident : $ty:ident) => {
+        concat!(stringify!($field), ":", $crate::direct_channel!(@cddl $ty))
+    };
+    (@cddl_fields $field:ident : $ty:ident, $($rest:ident : $rest_ty:ident),+) => {
+        concat!(
+            stringify!($field),
+            ":",
+            $crate::direct_channel!(@cddl $ty),
+            ",",
+            $crate::direct_channel!(@cddl_fields $($rest: $rest_ty),+)
+        )
+    };
+
+    (@cddl bool) => { "bool" };
+    (@cddl u8) => { "uint .size 1" };
+    (@cddl u16) => { "uint .size 2" };
+    (@cddl u32) => { "uint .size 4" };
+    (@cddl u64) => { "uint .size 8" };
+    (@cddl u128) => { "biguint .size 8" };
+    (@cddl Addr) => { "bstr .size 20" };
+
+    (@size bool) => { 1 };
+    (@size u8) => { $crate::CBL_U8 };
+    (@size u16) => { $crate::CBL_U16 };
+    (@size u32) => { $crate::CBL_U32 };
+    (@size u64) => { $crate::CBL_U53 };
+    (@size u128) => { $crate::CBL_BIGNUM_U64 };
+    (@size Addr) => { $crate::CBL_ADDRESS };
+
+    (@encode bool, $api:ident, $encoder:ident, $value:expr) => {
+        $encoder.bool(*$value).map_err($crate::cbor_to_std_error)?
+    };
+    (@encode u8, $api:ident, $encoder:ident, $value:expr) => {
+        $crate::EncoderExt::ext_u8($encoder, *$value)?
+    };
+    (@encode u16, $api:ident, $encoder:ident, $value:expr) => {
+        $encoder.u16(*$value).map_err($crate::cbor_to_std_error)?
+    };
+    (@encode u32, $api:ident, $encoder:ident, $value:expr) => {
+        $crate::EncoderExt::ext_u32($encoder, *$value)?
+    };
+    (@encode u64, $api:ident, $encoder:ident, $value:expr) => {
+        $encoder.u64(*$value).map_err($crate::cbor_to_std_error)?
+    };
+    (@encode u128, $api:ident, $encoder:ident, $value:expr) => {
+        $crate::EncoderExt::ext_u64_from_u128($encoder, *$value)?
+    };
+    (@encode Addr, $api:ident, $encoder:ident, $value:expr) => {
+        $crate::EncoderExt::ext_address($encoder, $api.addr_canonicalize($value.as_str())?)?
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::StdResult;
+    use minicbor::Decoder;
+
+    use crate::DirectChannel;
+
+    direct_channel! {
+        #[channel_id = "transfer"]
+        #[derive(Clone, Debug)]
+        struct Transfer {
+            /// the amount received
+            amount: u128,
+            block: u64,
+            index: u16,
+            refund: bool,
+        }
+    }
+
+    #[test]
+    fn test_direct_channel() -> StdResult<()> {
+        assert_eq!(Transfer::CHANNEL_ID, "transfer");
+        assert_eq!(
+            Transfer::CDDL_SCHEMA,
+            "transfer=[amount:biguint .size 8,block:uint .size 8,index:uint .size 2,refund:bool]"
+        );
+        assert_eq!(Transfer::ELEMENTS, 4);
+        assert_eq!(Transfer::PAYLOAD_SIZE, 1 + 10 + 9 + 3 + 1);
+
+        let deps = mock_dependencies();
+        let transfer = Transfer {
+            amount: 1000,
+            block: 12345,
+            index: 7,
+            refund: true,
+        };
+        let cbor = transfer.to_cbor(&deps.api)?;
+        assert_eq!(cbor.len(), Transfer::PAYLOAD_SIZE);
+
+        let mut decoder = Decoder::new(&cbor);
+        assert_eq!(decoder.array().unwrap(), Some(4));
+        decoder.tag().unwrap();
+        assert_eq!(decoder.bytes().unwrap(), &1000u64.to_be_bytes());
+        assert_eq!(decoder.u64().unwrap(), 12345);
+        assert_eq!(decoder.u16().unwrap(), 7);
+        assert!(decoder.bool().unwrap());
+
+        Ok(())
+    }
+}
@@ -1,10 +1,24 @@
 use crate::cipher_data;
-use cosmwasm_std::{Binary, CanonicalAddr, StdResult};
-use hkdf::hmac::Mac;
-use secret_toolkit_crypto::{hkdf_sha_256, sha_256, HmacSha256};
+use cosmwasm_std::{Binary, CanonicalAddr, StdError, StdResult, Storage};
+use secret_toolkit_crypto::{hkdf_sha_256, hmac_sha_256, sha_256};
 
 pub const SEED_LEN: usize = 32; // 256 bits
 
+/// Storage key of the internal secret used by `DirectChannel::notify` and `GroupChannel::notify_all`
+pub const NOTIFICATION_SECRET_KEY: &[u8] = b"snip52_internal_secret";
+
+/// store the internal secret that notification seeds are derived from, e.g. during instantiation
+pub fn store_notification_secret(storage: &mut dyn Storage, secret: &[u8]) {
+    storage.set(NOTIFICATION_SECRET_KEY, secret);
+}
+
+/// load the internal secret that notification seeds are derived from
+pub fn load_notification_secret(storage: &dyn Storage) -> StdResult<Vec<u8>> {
+    storage
+        .get(NOTIFICATION_SECRET_KEY)
+        .ok_or_else(|| StdError::generic_err("notification secret has not been set"))
+}
+
 ///
 /// fn notification_id
 ///
@@ -19,14 +33,10 @@ pub fn notification_id(seed: &Binary, channel: &str, tx_hash: &str) -> StdResult
     ]
     .concat();
 
-    // create HMAC from seed
-    let mut mac: HmacSha256 = HmacSha256::new_from_slice(seed.0.as_slice()).unwrap();
-
-    // add material to input stream
-    mac.update(material.as_slice());
-
-    // finalize the digest and convert to CW Binary
-    Ok(Binary::from(mac.finalize().into_bytes().as_slice()))
+    // HMAC the material with the seed and convert to CW Binary
+    Ok(Binary::from(
+        hmac_sha_256(seed.0.as_slice(), material.as_slice()).as_slice(),
+    ))
 }
 
 ///
@@ -93,6 +103,6 @@ fn zero_pad_right(message: &mut Vec<u8>, block_size: usize) -> &mut Vec<u8> {
 
     let missing = block_size - surplus;
     message.reserve(missing);
-    message.extend(std::iter::repeat(0x00).take(missing));
+    message.resize(message.len() + missing, 0x00);
     message
 }
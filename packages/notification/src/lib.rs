@@ -1,6 +1,7 @@
 #![doc = include_str!("../Readme.md")]
 
 pub mod cbor;
+pub mod channel;
 pub mod cipher;
 pub mod funcs;
 pub mod structs;
@@ -1,9 +1,28 @@
-use cosmwasm_std::{Addr, Api, Binary, Env, StdError, StdResult, Uint64};
+use std::collections::HashSet;
+
+use cosmwasm_std::{
+    Addr, Api, Attribute, Binary, CanonicalAddr, Env, StdError, StdResult, Storage, Uint64,
+};
 use minicbor::Encoder;
+use primitive_types::U256;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::{cbor_to_std_error, encrypt_notification_data, get_seed, notification_id};
+use secret_toolkit_crypto::{hkdf_sha_512, sha_256};
+
+use crate::{
+    cbor_to_std_error, encrypt_notification_data, get_seed, load_notification_secret,
+    notification_id, xor_bytes,
+};
+
+/// Length of the id prepended to each packet of a group notification
+pub const PACKET_ID_SIZE: usize = 8;
+
+/// Derives the key of the packet of a group notification from the notification id, as in
+/// SNIP-52: HKDF-SHA512 with a zero salt over the bytes of the id after the packet id.
+fn packet_key(id: &[u8], size: usize) -> StdResult<Vec<u8>> {
+    hkdf_sha_512(&Some(vec![0u8; 64]), &id[PACKET_ID_SIZE..], b"", size)
+}
 
 #[derive(Serialize, Debug, Deserialize, Clone)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
@@ -47,6 +66,31 @@ pub trait DirectChannel {
 
     /// CBOR encodes notification data into the encoder
     fn encode_cbor(&self, api: &dyn Api, encoder: &mut Encoder<&mut [u8]>) -> StdResult<()>;
+
+    /// Builds the plaintext attribute that notifies `recipient` of `payload`.
+    ///
+    /// This loads the internal secret stored with `store_notification_secret`, derives the
+    /// recipient's seed and the notification id from the tx hash, and encodes and encrypts the
+    /// payload. Add the result to the response using `Response::add_attribute`.
+    fn notify(
+        storage: &dyn Storage,
+        api: &dyn Api,
+        env: &Env,
+        recipient: Addr,
+        payload: Self,
+    ) -> StdResult<Attribute>
+    where
+        Self: Sized,
+    {
+        let secret = load_notification_secret(storage)?;
+        let notification = Notification::new(recipient, payload)
+            .to_txhash_notification(api, env, &secret, None)?;
+
+        Ok(Attribute::new_plaintext(
+            notification.id_plaintext(),
+            notification.data_plaintext(),
+        ))
+    }
 }
 
 impl<T: DirectChannel> Notification<T> {
@@ -187,4 +231,223 @@ pub trait GroupChannel<D: DirectChannel> {
     fn build_packet(&self, api: &dyn Api, data: &D) -> StdResult<Vec<u8>>;
 
     fn notifications(&self) -> &Vec<Notification<D>>;
+
+    /// Builds the plaintext attribute that notifies all the recipients of the group.
+    ///
+    /// The attribute is named `snip52:#{CHANNEL_ID}` and its value is the base64 encoding of a
+    /// `BLOOM_M` bits bloom filter over the recipients' notification ids, followed by one packet
+    /// per recipient. Each packet is made of the first `PACKET_ID_SIZE` bytes of the recipient's
+    /// notification id, followed by the output of `build_packet` encrypted with a key derived
+    /// from that id. A recipient appearing more than once in the group is an error, as it would
+    /// get a single packet.
+    fn notify_all(&self, storage: &dyn Storage, api: &dyn Api, env: &Env) -> StdResult<Attribute> {
+        let secret = load_notification_secret(storage)?;
+        let tx_hash = env
+            .transaction
+            .clone()
+            .ok_or(StdError::generic_err("no tx hash found"))?
+            .hash
+            .to_ascii_uppercase();
+
+        let mut recipients: Vec<(CanonicalAddr, &Notification<D>)> = vec![];
+        let mut seen: HashSet<CanonicalAddr> = HashSet::new();
+        for notification in self.notifications() {
+            let recipient = api.addr_canonicalize(notification.notification_for.as_str())?;
+            if !seen.insert(recipient.clone()) {
+                return Err(StdError::generic_err(format!(
+                    "group notification has more than one notification for {}",
+                    notification.notification_for
+                )));
+            }
+            recipients.push((recipient, notification));
+        }
+
+        if recipients.len() > Self::BLOOM_N {
+            return Err(StdError::generic_err(format!(
+                "group notification can not have more than {} recipients",
+                Self::BLOOM_N
+            )));
+        }
+
+        let mut bloom_filter = vec![0u8; (Self::BLOOM_M / 8) as usize];
+        let mut packets = vec![];
+        for (recipient, notification) in recipients {
+            let seed = get_seed(&recipient, &secret)?;
+            let id = notification_id(&seed, Self::CHANNEL_ID, &tx_hash)?;
+
+            // each group of BLOOM_M_LOG2 bits of the id's hash selects a bit to set
+            let hash = U256::from_big_endian(&sha_256(id.as_slice()));
+            for i in 0..Self::BLOOM_K {
+                let shift = 256 - Self::BLOOM_M_LOG2 * (i + 1);
+                let bit = ((hash >> shift) & U256::from(Self::BLOOM_M - 1)).as_usize();
+                let byte = bloom_filter.len() - 1 - bit / 8;
+                bloom_filter[byte] |= 1 << (bit % 8);
+            }
+
+            let packet_plaintext = self.build_packet(api, &notification.data)?;
+            if packet_plaintext.len() != Self::PACKET_SIZE {
+                return Err(StdError::generic_err(format!(
+                    "group notification packet must be {} bytes long",
+                    Self::PACKET_SIZE
+                )));
+            }
+            let packet_key = packet_key(id.as_slice(), Self::PACKET_SIZE)?;
+            packets.extend_from_slice(&id.as_slice()[..PACKET_ID_SIZE]);
+            packets.extend(xor_bytes(&packet_plaintext, &packet_key));
+        }
+
+        Ok(Attribute::new_plaintext(
+            format!("snip52:#{}", Self::CHANNEL_ID),
+            Binary::from([bloom_filter, packets].concat()).to_base64(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store_notification_secret;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::TransactionInfo;
+
+    #[derive(Serialize, Debug, Deserialize, Clone, Eq, PartialEq)]
+    struct Ping {
+        value: u8,
+    }
+
+    impl DirectChannel for Ping {
+        const CHANNEL_ID: &'static str = "ping";
+        const CDDL_SCHEMA: &'static str = "ping=[value:uint]";
+        const ELEMENTS: u64 = 1;
+        const PAYLOAD_SIZE: usize = crate::CBL_ARRAY_SHORT + crate::CBL_U8;
+
+        fn encode_cbor(&self, _api: &dyn Api, encoder: &mut Encoder<&mut [u8]>) -> StdResult<()> {
+            encoder.u8(self.value).map_err(cbor_to_std_error)?;
+            Ok(())
+        }
+    }
+
+    struct PingGroup {
+        notifications: Vec<Notification<Ping>>,
+    }
+
+    impl GroupChannel<Ping> for PingGroup {
+        const CHANNEL_ID: &'static str = "pings";
+        const BLOOM_N: usize = 4;
+        const BLOOM_M: u32 = 64;
+        const BLOOM_K: u32 = 3;
+        const PACKET_SIZE: usize = 1;
+
+        fn build_packet(&self, _api: &dyn Api, data: &Ping) -> StdResult<Vec<u8>> {
+            Ok(vec![data.value])
+        }
+
+        fn notifications(&self) -> &Vec<Notification<Ping>> {
+            &self.notifications
+        }
+    }
+
+    fn env_with_tx() -> Env {
+        let mut env = mock_env();
+        env.transaction = Some(TransactionInfo {
+            index: 0,
+            hash: "a".repeat(64),
+        });
+        env
+    }
+
+    #[test]
+    fn test_notify() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = env_with_tx();
+        let recipient = Addr::unchecked("recipient");
+
+        let no_secret = Ping::notify(
+            &deps.storage,
+            &deps.api,
+            &env,
+            recipient.clone(),
+            Ping { value: 1 },
+        );
+        assert!(no_secret.is_err());
+
+        store_notification_secret(&mut deps.storage, b"secret");
+        let attribute = Ping::notify(
+            &deps.storage,
+            &deps.api,
+            &env,
+            recipient.clone(),
+            Ping { value: 1 },
+        )?;
+        let expected = Notification::new(recipient, Ping { value: 1 })
+            .to_txhash_notification(&deps.api, &env, b"secret", None)?;
+        assert_eq!(
+            attribute,
+            Attribute::new_plaintext(expected.id_plaintext(), expected.data_plaintext())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_key() -> StdResult<()> {
+        // HKDF-SHA512 with a zero salt over the bytes 8 to 32 of the id, as in SNIP-52
+        let id: Vec<u8> = (0u8..32).collect();
+        assert_eq!(
+            packet_key(&id, 16)?,
+            vec![
+                0xe2, 0xcd, 0x07, 0x39, 0x16, 0x85, 0x5c, 0x0b, 0x10, 0xcc, 0x24, 0x09, 0x94, 0x6d,
+                0xbd, 0x48
+            ]
+        );
+        // the packet id isn't part of the key
+        let mut other_id = id.clone();
+        other_id[..PACKET_ID_SIZE].fill(0xff);
+        assert_eq!(packet_key(&other_id, 16)?, packet_key(&id, 16)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notify_all() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = env_with_tx();
+        store_notification_secret(&mut deps.storage, b"secret");
+
+        let mut group = PingGroup {
+            notifications: vec![
+                Notification::new(Addr::unchecked("alice"), Ping { value: 1 }),
+                Notification::new(Addr::unchecked("bob"), Ping { value: 2 }),
+                Notification::new(Addr::unchecked("carol"), Ping { value: 3 }),
+            ],
+        };
+        let attribute = group.notify_all(&deps.storage, &deps.api, &env)?;
+        assert_eq!(attribute.key, "snip52:#pings");
+        assert!(!attribute.encrypted);
+
+        let value = Binary::from_base64(&attribute.value)?;
+        let packet_len = PACKET_ID_SIZE + PingGroup::PACKET_SIZE;
+        assert_eq!(value.len(), 64 / 8 + 3 * packet_len);
+
+        // the bloom filter contains at least one bit per recipient and at most BLOOM_K each
+        let bits: u32 = value[..8].iter().map(|b| b.count_ones()).sum();
+        assert!((1..=9).contains(&bits));
+
+        // alice's packet can be decrypted with her notification id
+        let alice = deps.api.addr_canonicalize("alice")?;
+        let seed = get_seed(&alice, b"secret")?;
+        let id = notification_id(&seed, PingGroup::CHANNEL_ID, &"A".repeat(64))?;
+        let packet = &value[8..8 + packet_len];
+        assert_eq!(&packet[..PACKET_ID_SIZE], &id.as_slice()[..PACKET_ID_SIZE]);
+        let key = packet_key(id.as_slice(), PingGroup::PACKET_SIZE)?;
+        assert_eq!(xor_bytes(&packet[PACKET_ID_SIZE..], &key), vec![1]);
+
+        // a recipient appearing twice is an error rather than a skipped or merged packet
+        group
+            .notifications
+            .push(Notification::new(Addr::unchecked("bob"), Ping { value: 4 }));
+        assert!(group.notify_all(&deps.storage, &deps.api, &env).is_err());
+
+        Ok(())
+    }
 }
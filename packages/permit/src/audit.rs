@@ -0,0 +1,154 @@
+//! Optional log of the permits validated for each account, behind the `audit-log` feature.
+
+use cosmwasm_std::{StdResult, Storage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use secret_toolkit_serialization::Json;
+use secret_toolkit_storage::AppendStore;
+
+use crate::{Permissions, Permit};
+
+const PREFIX_AUDIT_LOG: &[u8] = b"permit_audit_log";
+
+/// An entry of the `PermitAuditLog`, recording a successful validation of a permit
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(bound = "")]
+pub struct PermitAuditEntry<Permission: Permissions> {
+    pub account: String,
+    pub permit_name: String,
+    pub permissions: Vec<Permission>,
+    pub block_height: u64,
+}
+
+/// Optional log of the permits validated for each account.
+///
+/// Each account has its own log, so that the contract can let an account query the uses of its
+/// permits, authenticated with a permit or viewing key of that account, without making the log
+/// public. Recording an entry writes to storage, so it can only be done while handling a
+/// transaction, not in queries.
+pub struct PermitAuditLog;
+
+impl PermitAuditLog {
+    fn entries<Permission: Permissions>(
+        storage_prefix: &str,
+        account: &str,
+    ) -> AppendStore<'static, PermitAuditEntry<Permission>, Json> {
+        AppendStore::new(PREFIX_AUDIT_LOG)
+            .add_suffix(storage_prefix.as_bytes())
+            .add_suffix(account.as_bytes())
+    }
+
+    /// Appends an entry to the log of `account`, which should be called after `validate`
+    /// accepted the permit
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - a mutable reference to the contract's storage
+    /// * `storage_prefix` - the prefix the log is kept under
+    /// * `account` - the account that signed the permit, as returned by `validate`
+    /// * `permit` - the permit that was validated
+    /// * `block_height` - the current block height
+    pub fn record<Permission: Permissions>(
+        storage: &mut dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit: &Permit<Permission>,
+        block_height: u64,
+    ) -> StdResult<PermitAuditEntry<Permission>> {
+        let entry = PermitAuditEntry {
+            account: account.to_string(),
+            permit_name: permit.params.permit_name.clone(),
+            permissions: permit.params.permissions.clone(),
+            block_height,
+        };
+        Self::entries(storage_prefix, account).push(storage, &entry)?;
+
+        Ok(entry)
+    }
+
+    /// Returns a page of the log of an account, the most recent entries first, and the total
+    /// number of entries. Only return it to the account itself, once authenticated.
+    pub fn query<Permission: Permissions>(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        page: u32,
+        page_size: u32,
+    ) -> StdResult<(Vec<PermitAuditEntry<Permission>>, u32)> {
+        let entries = Self::entries(storage_prefix, account);
+        let total = entries.get_len(storage)?;
+        let page = entries
+            .iter(storage)?
+            .rev()
+            .skip((page as usize) * (page_size as usize))
+            .take(page_size as usize)
+            .collect::<StdResult<_>>()?;
+
+        Ok((page, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PermitParams, PermitSignature, PubKey, TokenPermissions};
+    use cosmwasm_std::{testing::MockStorage, Binary};
+
+    #[test]
+    fn test_audit_log() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let permit = |name: &str, permission| Permit {
+            params: PermitParams {
+                allowed_tokens: vec![],
+                permit_name: name.to_string(),
+                chain_id: "secret-4".to_string(),
+                permissions: vec![permission],
+            },
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: Binary::default(),
+                },
+                signature: Binary::default(),
+            },
+        };
+
+        let balance = permit("balance", TokenPermissions::Balance);
+        let history = permit("history", TokenPermissions::History);
+        PermitAuditLog::record(&mut storage, "test", "alice", &balance, 10)?;
+        PermitAuditLog::record(&mut storage, "test", "alice", &history, 11)?;
+        let entry = PermitAuditLog::record(&mut storage, "test", "alice", &balance, 12)?;
+        assert_eq!(
+            entry,
+            PermitAuditEntry {
+                account: "alice".to_string(),
+                permit_name: "balance".to_string(),
+                permissions: vec![TokenPermissions::Balance],
+                block_height: 12,
+            }
+        );
+        PermitAuditLog::record(&mut storage, "test", "bob", &history, 13)?;
+
+        let (entries, total) =
+            PermitAuditLog::query::<TokenPermissions>(&storage, "test", "alice", 0, 2)?;
+        assert_eq!(total, 3);
+        let blocks: Vec<_> = entries.iter().map(|e| e.block_height).collect();
+        assert_eq!(blocks, vec![12, 11]);
+        let (entries, _) =
+            PermitAuditLog::query::<TokenPermissions>(&storage, "test", "alice", 1, 2)?;
+        assert_eq!(entries[0].permit_name, "balance");
+        assert_eq!(entries[0].block_height, 10);
+
+        // other accounts and prefixes are logged separately
+        let (entries, total) =
+            PermitAuditLog::query::<TokenPermissions>(&storage, "test", "bob", 0, 10)?;
+        assert_eq!(total, 1);
+        assert_eq!(entries[0].account, "bob");
+        let (_, total) =
+            PermitAuditLog::query::<TokenPermissions>(&storage, "other", "alice", 0, 10)?;
+        assert_eq!(total, 0);
+
+        Ok(())
+    }
+}
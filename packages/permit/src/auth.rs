@@ -0,0 +1,236 @@
+//! Authentication of queries with either a permit, a viewing key, or the auth token of a
+//! contract.
+//!
+//! Contracts, e.g. account-abstraction wallets, can't sign permits, so they can't use permit-gated
+//! queries. Instead, a contract registers an auth token in an execute handshake, by calling
+//! [`ContractAuthTokens::register`] through a message of the queried contract, which returns the
+//! token in the data of its response. Only the contract that registered it can read it, and it
+//! then passes the token in its queries, which [`authenticate`] accepts alongside permits and
+//! viewing keys.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Binary, Deps, Env, MessageInfo, StdError, StdResult, Storage};
+
+use secret_toolkit_crypto::{sha_256, ContractPrng};
+use secret_toolkit_storage::Keymap;
+
+use crate::{validate, Permissions, Permit, TokenPermissions};
+
+const PREFIX_AUTH_TOKENS: &[u8] = b"contract_auth_tokens";
+pub const AUTH_TOKEN_PREFIX: &str = "auth_";
+
+/// The credentials a query is authenticated with
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case", bound = "")]
+pub enum Auth<Permission: Permissions = TokenPermissions> {
+    Permit(Permit<Permission>),
+    ViewingKey {
+        address: String,
+        key: String,
+    },
+    /// the auth token registered by a contract with `ContractAuthTokens::register`
+    ContractToken {
+        address: String,
+        token: String,
+    },
+}
+
+/// The auth tokens registered by contracts, of which only the hashes are stored
+pub struct ContractAuthTokens;
+
+impl ContractAuthTokens {
+    fn tokens(storage_prefix: &str) -> Keymap<'static, String, [u8; 32]> {
+        Keymap::new(PREFIX_AUTH_TOKENS).add_suffix(storage_prefix.as_bytes())
+    }
+
+    /// Creates a new auth token for `info.sender`, replacing its previous one, and returns it.
+    ///
+    /// Call this while handling an execute message, and return the token in the data of the
+    /// response, so that only the sender can read it. The random entropy should be provided by
+    /// the sender.
+    pub fn register(
+        storage: &mut dyn Storage,
+        storage_prefix: &str,
+        env: &Env,
+        info: &MessageInfo,
+        entropy: &[u8],
+    ) -> StdResult<String> {
+        let seed = env
+            .block
+            .random
+            .as_ref()
+            .map(Binary::as_slice)
+            .unwrap_or_default();
+        let mut rng_entropy = Vec::with_capacity(16 + info.sender.as_str().len() + entropy.len());
+        rng_entropy.extend_from_slice(&env.block.height.to_be_bytes());
+        rng_entropy.extend_from_slice(&env.block.time.nanos().to_be_bytes());
+        rng_entropy.extend_from_slice(info.sender.as_bytes());
+        rng_entropy.extend_from_slice(entropy);
+
+        let token = AUTH_TOKEN_PREFIX.to_string()
+            + &Binary::from(ContractPrng::new(seed, &rng_entropy).rand_bytes()).to_base64();
+        Self::tokens(storage_prefix).insert(
+            storage,
+            &info.sender.to_string(),
+            &sha_256(token.as_bytes()),
+        )?;
+
+        Ok(token)
+    }
+
+    /// Revokes the auth token of a contract
+    pub fn revoke(storage: &mut dyn Storage, storage_prefix: &str, address: &str) -> StdResult<()> {
+        Self::tokens(storage_prefix).remove(storage, &address.to_string())
+    }
+
+    /// Checks that `token` is the auth token registered by the contract at `address`
+    pub fn check(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        address: &str,
+        token: &str,
+    ) -> StdResult<()> {
+        // comparing the hashes doesn't leak the registered token through timing
+        match Self::tokens(storage_prefix).get(storage, &address.to_string()) {
+            Some(hash) if hash == sha_256(token.as_bytes()) => Ok(()),
+            _ => Err(StdError::generic_err("unauthorized")),
+        }
+    }
+}
+
+/// Authenticates a query, and returns the address of the account it was made by.
+///
+/// # Arguments
+///
+/// * `deps` - the dependencies of the queried contract
+/// * `storage_prefix` - the storage prefix of the revoked permits and of the contract auth tokens
+/// * `auth` - the credentials the query was made with
+/// * `current_token_address` - the address of the queried contract, which permits must apply to
+/// * `hrp` - the human readable part of the addresses, `"secret"` if `None`
+/// * `check_viewing_key` - checks a viewing key, usually `ViewingKey::check` of
+///   `secret-toolkit-viewing-key`
+pub fn authenticate<Permission: Permissions>(
+    deps: Deps,
+    storage_prefix: &str,
+    auth: &Auth<Permission>,
+    current_token_address: String,
+    hrp: Option<&str>,
+    check_viewing_key: impl FnOnce(&dyn Storage, &str, &str) -> StdResult<()>,
+) -> StdResult<String> {
+    match auth {
+        Auth::Permit(permit) => validate(deps, storage_prefix, permit, current_token_address, hrp),
+        Auth::ViewingKey { address, key } => {
+            check_viewing_key(deps.storage, address, key)?;
+            Ok(address.clone())
+        }
+        Auth::ContractToken { address, token } => {
+            ContractAuthTokens::check(deps.storage, storage_prefix, address, token)?;
+            Ok(address.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    fn check_viewing_key(_storage: &dyn Storage, address: &str, key: &str) -> StdResult<()> {
+        if address == "secret1user" && key == "api_key_user" {
+            Ok(())
+        } else {
+            Err(StdError::generic_err("unauthorized"))
+        }
+    }
+
+    #[test]
+    fn test_contract_auth_tokens() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("secret1wallet", &[]);
+
+        let token =
+            ContractAuthTokens::register(&mut deps.storage, "auth", &env, &info, b"entropy")?;
+        assert!(token.starts_with(AUTH_TOKEN_PREFIX));
+        ContractAuthTokens::check(&deps.storage, "auth", "secret1wallet", &token)?;
+        assert!(ContractAuthTokens::check(&deps.storage, "auth", "secret1other", &token).is_err());
+        assert!(
+            ContractAuthTokens::check(&deps.storage, "other", "secret1wallet", &token).is_err()
+        );
+
+        // registering again replaces the token
+        let new_token =
+            ContractAuthTokens::register(&mut deps.storage, "auth", &env, &info, b"more entropy")?;
+        assert_ne!(new_token, token);
+        assert!(ContractAuthTokens::check(&deps.storage, "auth", "secret1wallet", &token).is_err());
+        ContractAuthTokens::check(&deps.storage, "auth", "secret1wallet", &new_token)?;
+
+        ContractAuthTokens::revoke(&mut deps.storage, "auth", "secret1wallet")?;
+        assert!(
+            ContractAuthTokens::check(&deps.storage, "auth", "secret1wallet", &new_token).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_authenticate() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let token = ContractAuthTokens::register(
+            &mut deps.storage,
+            "auth",
+            &mock_env(),
+            &mock_info("secret1wallet", &[]),
+            b"entropy",
+        )?;
+
+        let auth: Auth<TokenPermissions> = Auth::ContractToken {
+            address: "secret1wallet".to_string(),
+            token: token.clone(),
+        };
+        assert_eq!(
+            authenticate(
+                deps.as_ref(),
+                "auth",
+                &auth,
+                "token".to_string(),
+                None,
+                check_viewing_key
+            )?,
+            "secret1wallet"
+        );
+
+        let auth: Auth<TokenPermissions> = Auth::ContractToken {
+            address: "secret1user".to_string(),
+            token,
+        };
+        assert!(authenticate(
+            deps.as_ref(),
+            "auth",
+            &auth,
+            "token".to_string(),
+            None,
+            check_viewing_key
+        )
+        .is_err());
+
+        let auth: Auth<TokenPermissions> = cosmwasm_std::from_slice(
+            br#"{"viewing_key":{"address":"secret1user","key":"api_key_user"}}"#,
+        )?;
+        assert_eq!(
+            authenticate(
+                deps.as_ref(),
+                "auth",
+                &auth,
+                "token".to_string(),
+                None,
+                check_viewing_key
+            )?,
+            "secret1user"
+        );
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use cosmwasm_std::{to_binary, Binary, CanonicalAddr, Deps, StdError, StdResult};
 use ripemd::{Digest, Ripemd160};
 
-use crate::{Permissions, Permit, RevokedPermits, SignedPermit};
+use crate::{Permissions, Permit, PermitParams, RevokedPermits, SignedPermit};
 use bech32::{ToBase32, Variant};
 use secret_toolkit_crypto::sha_256;
 
@@ -11,15 +11,60 @@ pub fn validate<Permission: Permissions>(
     permit: &Permit<Permission>,
     current_token_address: String,
     hrp: Option<&str>,
+) -> StdResult<String> {
+    let account = validate_params(
+        deps,
+        storage_prefix,
+        &permit.params,
+        &permit.signature.pub_key.value,
+        current_token_address,
+        hrp,
+    )?;
+
+    // Validate signature, reference: https://github.com/enigmampc/SecretNetwork/blob/f591ed0cb3af28608df3bf19d6cfb733cca48100/cosmwasm/packages/wasmi-runtime/src/crypto/secp256k1.rs#L49-L82
+    let signed_bytes = to_binary(&SignedPermit::from_params(&permit.params))?;
+    let pubkey = &permit.signature.pub_key.value;
+    let verified = match permit.signature.pub_key.r#type.as_str() {
+        #[cfg(feature = "secp256r1")]
+        crate::secp256r1::SECP256R1_PUB_KEY_TYPE => crate::secp256r1::verify_signature(
+            signed_bytes.as_slice(),
+            &permit.signature.signature,
+            pubkey,
+        )?,
+        _ => {
+            let signed_bytes_hash = sha_256(signed_bytes.as_slice());
+            deps.api
+                .secp256k1_verify(&signed_bytes_hash, &permit.signature.signature.0, &pubkey.0)
+                .map_err(|err| StdError::generic_err(err.to_string()))?
+        }
+    };
+
+    if !verified {
+        return Err(StdError::generic_err(
+            "Failed to verify signatures for the given permit",
+        ));
+    }
+
+    Ok(account)
+}
+
+/// Checks that the params apply to the token and weren't revoked by the account of `pubkey`,
+/// and returns that account. The signature is left to the caller.
+pub(crate) fn validate_params<Permission: Permissions>(
+    deps: Deps,
+    storage_prefix: &str,
+    params: &PermitParams<Permission>,
+    pubkey: &Binary,
+    current_token_address: String,
+    hrp: Option<&str>,
 ) -> StdResult<String> {
     let account_hrp = hrp.unwrap_or("secret");
 
-    if !permit.check_token(&current_token_address) {
+    if !params.allowed_tokens.contains(&current_token_address) {
         return Err(StdError::generic_err(format!(
             "Permit doesn't apply to token {:?}, allowed tokens: {:?}",
             current_token_address.as_str(),
-            permit
-                .params
+            params
                 .allowed_tokens
                 .iter()
                 .map(|a| a.as_str())
@@ -28,13 +73,11 @@ pub fn validate<Permission: Permissions>(
     }
 
     // Derive account from pubkey
-    let pubkey = &permit.signature.pub_key.value;
-
     let base32_addr = pubkey_to_account(pubkey).0.as_slice().to_base32();
     let account: String = bech32::encode(account_hrp, base32_addr, Variant::Bech32).unwrap();
 
     // Validate permit_name
-    let permit_name = &permit.params.permit_name;
+    let permit_name = &params.permit_name;
     let is_permit_revoked =
         RevokedPermits::is_permit_revoked(deps.storage, storage_prefix, &account, permit_name);
     if is_permit_revoked {
@@ -45,22 +88,96 @@ pub fn validate<Permission: Permissions>(
         )));
     }
 
-    // Validate signature, reference: https://github.com/enigmampc/SecretNetwork/blob/f591ed0cb3af28608df3bf19d6cfb733cca48100/cosmwasm/packages/wasmi-runtime/src/crypto/secp256k1.rs#L49-L82
-    let signed_bytes = to_binary(&SignedPermit::from_params(&permit.params))?;
-    let signed_bytes_hash = sha_256(signed_bytes.as_slice());
+    // Validate the permit itself, in case it was revoked by id or by creation height
+    if RevokedPermits::is_permit_id_revoked(
+        deps.storage,
+        storage_prefix,
+        &account,
+        &permit_id(params)?,
+    ) {
+        return Err(StdError::generic_err(format!(
+            "Permit {:?} was revoked by account {:?}",
+            permit_name,
+            account.as_str()
+        )));
+    }
+    if let Some(height) =
+        RevokedPermits::revoked_before_height(deps.storage, storage_prefix, &account)?
+    {
+        if params.created_at().is_none_or(|created| created < height) {
+            return Err(StdError::generic_err(format!(
+                "Permit {:?} was revoked by account {:?}, as it was not created at or after block {}",
+                permit_name,
+                account.as_str(),
+                height
+            )));
+        }
+    }
 
-    let verified = deps
-        .api
-        .secp256k1_verify(&signed_bytes_hash, &permit.signature.signature.0, &pubkey.0)
-        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(account)
+}
 
-    if !verified {
-        return Err(StdError::generic_err(
-            "Failed to verify signatures for the given permit",
-        ));
+/// the order of the secp256k1 curve
+const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+/// half the order of the secp256k1 curve, the largest low `s`
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Returns the id of a permit, for use with `RevokedPermits`. It is the hash of the sign doc of
+/// its params, which doesn't depend on the signature, so every signature of the same params,
+/// whether malleated, re-encoded or made by a passkey, has the same id.
+pub fn permit_id<Permission: Permissions>(
+    params: &PermitParams<Permission>,
+) -> StdResult<[u8; 32]> {
+    let signed_bytes = to_binary(&SignedPermit::from_params(params))?;
+    Ok(sha_256(signed_bytes.as_slice()))
+}
+
+/// Returns a hash identifying a permit, for use with `PermitUsageTracker`.
+///
+/// An ECDSA signature `(r, s)` and its twin `(r, n - s)` verify the same permit, so the hash is
+/// that of the signature normalized to its low-S form, and the holder of a permit can't get a
+/// new hash by flipping `s`.
+pub fn permit_hash<Permission: Permissions>(permit: &Permit<Permission>) -> [u8; 32] {
+    let signature = permit.signature.signature.as_slice();
+    match permit.signature.pub_key.r#type.as_str() {
+        #[cfg(feature = "secp256r1")]
+        crate::secp256r1::SECP256R1_PUB_KEY_TYPE => {
+            sha_256(&crate::secp256r1::normalize_signature(signature))
+        }
+        _ => sha_256(&normalize_secp256k1_signature(signature)),
     }
+}
 
-    Ok(account)
+/// Returns the 64 bytes of a secp256k1 signature with a low `s`. Other lengths are returned as
+/// they are, since they can't be verified anyway.
+fn normalize_secp256k1_signature(signature: &[u8]) -> Vec<u8> {
+    let mut normalized = signature.to_vec();
+    if signature.len() == 64 {
+        // will never fail since the signature is 64 bytes long.
+        let s = <[u8; 32]>::try_from(&signature[32..]).unwrap();
+        if s > SECP256K1_HALF_ORDER {
+            normalized[32..].copy_from_slice(&negate_secp256k1_scalar(&s));
+        }
+    }
+    normalized
+}
+
+/// Returns `n - s`, where `n` is the order of the secp256k1 curve and `s <= n`
+fn negate_secp256k1_scalar(s: &[u8; 32]) -> [u8; 32] {
+    let mut negated = [0u8; 32];
+    let mut borrow = 0u16;
+    for i in (0..32).rev() {
+        let diff = 0x100 + SECP256K1_ORDER[i] as u16 - s[i] as u16 - borrow;
+        negated[i] = diff as u8;
+        borrow = 1 - (diff >> 8);
+    }
+    negated
 }
 
 pub fn pubkey_to_account(pubkey: &Binary) -> CanonicalAddr {
@@ -72,8 +189,10 @@ pub fn pubkey_to_account(pubkey: &Binary) -> CanonicalAddr {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::sign_permit;
     use crate::{PermitParams, PermitSignature, PubKey, TokenPermissions};
     use cosmwasm_std::testing::mock_dependencies;
+    use secret_toolkit_crypto::secp256k1::PrivateKey;
 
     #[test]
     fn test_verify_permit() {
@@ -120,4 +239,121 @@ mod tests {
             "cosmos1399pyvvk3hvwgxwt3udkslsc5jl3rqv4x4rq7r".to_string()
         );
     }
+
+    #[test]
+    fn test_revoked_permits() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let key = PrivateKey::parse(&[7; 32])?;
+        let sign = |permit_name: &str| {
+            let params = PermitParams {
+                allowed_tokens: vec!["token".to_string()],
+                permit_name: permit_name.to_string(),
+                chain_id: "secret-4".to_string(),
+                permissions: vec![TokenPermissions::Balance],
+            };
+            sign_permit(params, &key)
+        };
+        let validate =
+            |deps: Deps, permit: &Permit| validate(deps, "test", permit, "token".to_string(), None);
+
+        // revoking a permit by id doesn't revoke another permit with the same name
+        let permit = sign("balances")?;
+        let account = validate(deps.as_ref(), &permit)?;
+        let mut renewed = sign("balances")?;
+        renewed.params.chain_id = "pulsar-3".to_string();
+        let renewed = sign_permit(renewed.params, &key)?;
+        RevokedPermits::revoke_permit_id(
+            &mut deps.storage,
+            "test",
+            &account,
+            &permit_id(&permit.params)?,
+        )?;
+        assert_eq!(
+            validate(deps.as_ref(), &permit),
+            Err(StdError::generic_err(format!(
+                "Permit \"balances\" was revoked by account {account:?}"
+            )))
+        );
+        validate(deps.as_ref(), &renewed)?;
+
+        // revoking by creation height also revokes permits without a creation height
+        let old = sign("balances@99")?;
+        let new = sign("balances@100")?;
+        RevokedPermits::revoke_permits_created_before(&mut deps.storage, "test", &account, 100)?;
+        assert!(validate(deps.as_ref(), &old).is_err());
+        assert!(validate(deps.as_ref(), &renewed).is_err());
+        validate(deps.as_ref(), &new)?;
+
+        // the height can't be lowered
+        RevokedPermits::revoke_permits_created_before(&mut deps.storage, "test", &account, 50)?;
+        assert_eq!(
+            RevokedPermits::revoked_before_height(&deps.storage, "test", &account)?,
+            Some(100)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_permit_hash_high_s() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let key = PrivateKey::parse(&[7; 32])?;
+        let params = PermitParams {
+            allowed_tokens: vec!["token".to_string()],
+            permit_name: "balances".to_string(),
+            chain_id: "secret-4".to_string(),
+            permissions: vec![TokenPermissions::Balance],
+        };
+        let permit = sign_permit(params, &key)?;
+        let validate =
+            |deps: Deps, permit: &Permit| validate(deps, "test", permit, "token".to_string(), None);
+
+        // the twin signature with a high s verifies too, but has the same hash
+        let mut flipped = permit.clone();
+        let s = <[u8; 32]>::try_from(&permit.signature.signature.0[32..]).unwrap();
+        flipped.signature.signature.0[32..].copy_from_slice(&negate_secp256k1_scalar(&s));
+        assert_ne!(flipped.signature.signature, permit.signature.signature);
+        let account = validate(deps.as_ref(), &flipped)?;
+        assert_eq!(permit_hash(&flipped), permit_hash(&permit));
+        assert_eq!(permit_id(&flipped.params)?, permit_id(&permit.params)?);
+
+        RevokedPermits::revoke_permit_id(
+            &mut deps.storage,
+            "test",
+            &account,
+            &permit_id(&permit.params)?,
+        )?;
+        assert!(validate(deps.as_ref(), &permit).is_err());
+        assert!(validate(deps.as_ref(), &flipped).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_negate_secp256k1_scalar() {
+        let mut one = [0; 32];
+        one[31] = 1;
+        let mut order_minus_one = SECP256K1_ORDER;
+        order_minus_one[31] -= 1;
+        assert_eq!(negate_secp256k1_scalar(&one), order_minus_one);
+        assert_eq!(negate_secp256k1_scalar(&order_minus_one), one);
+        assert_eq!(negate_secp256k1_scalar(&SECP256K1_ORDER), [0; 32]);
+        assert!(negate_secp256k1_scalar(&SECP256K1_HALF_ORDER) > SECP256K1_HALF_ORDER);
+    }
+
+    #[test]
+    fn test_created_at() {
+        let params = |permit_name: &str| PermitParams::<TokenPermissions> {
+            allowed_tokens: vec![],
+            permit_name: permit_name.to_string(),
+            chain_id: "secret-4".to_string(),
+            permissions: vec![],
+        };
+        assert_eq!(params("balances@4820312").created_at(), Some(4820312));
+        assert_eq!(params("a@b@12").created_at(), Some(12));
+        assert_eq!(params("balances").created_at(), None);
+        assert_eq!(params("balances@").created_at(), None);
+        assert_eq!(params("balances@+12").created_at(), None);
+        assert_eq!(params("balances@12x").created_at(), None);
+    }
 }
@@ -1,9 +1,30 @@
 #![doc = include_str!("../Readme.md")]
 
+#[cfg(feature = "audit-log")]
+pub mod audit;
+#[cfg(feature = "contract-auth")]
+pub mod auth;
 pub mod funcs;
+#[cfg(feature = "secp256r1")]
+pub mod secp256r1;
 pub mod state;
 pub mod structs;
+#[cfg(any(test, all(feature = "test-utils", not(target_arch = "wasm32"))))]
+pub mod testing;
+#[cfg(feature = "usage-tracker")]
+pub mod usage;
 
+#[cfg(feature = "audit-log")]
+pub use audit::*;
+#[cfg(feature = "contract-auth")]
+pub use auth::*;
 pub use funcs::*;
+#[cfg(feature = "secp256r1")]
+pub use secp256r1::{
+    passkey_permit_hash, validate_passkey_permit, PasskeyPermit, PasskeySignature,
+    SECP256R1_PUB_KEY_TYPE,
+};
 pub use state::*;
 pub use structs::*;
+#[cfg(feature = "usage-tracker")]
+pub use usage::*;
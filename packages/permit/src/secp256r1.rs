@@ -0,0 +1,404 @@
+//! Permits signed with secp256r1 (P-256) keys, which is the curve of passkeys and other WebAuthn
+//! authenticators.
+//!
+//! A permit whose public key has the type [`SECP256R1_PUB_KEY_TYPE`] is verified by
+//! [`validate`](crate::validate) as a P-256 ECDSA signature, with SHA-256, of the same sign doc
+//! as a secp256k1 permit. Passkeys can't sign arbitrary bytes, so their permits are
+//! [`PasskeyPermit`]s, validated by [`validate_passkey_permit`].
+//!
+//! The account of a secp256r1 key is derived like that of a secp256k1 key: it is
+//! `ripemd160(sha256(pubkey))` of the 33 bytes compressed SEC1 encoding of the key, encoded in
+//! bech32 with the hrp passed to the validation. Other encodings of the key are rejected, so that
+//! each key has a single account. The chain has no secp256r1 accounts, so these accounts can
+//! only be used with permits, and not sign transactions.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{from_slice, to_binary, Binary, Deps, StdError, StdResult};
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use secret_toolkit_crypto::sha_256;
+
+use crate::{validate_params, Permissions, PermitParams, PubKey, SignedPermit, TokenPermissions};
+
+/// the type of the public keys of permits signed with secp256r1 keys
+pub const SECP256R1_PUB_KEY_TYPE: &str = "tendermint/PubKeySecp256r1";
+
+/// the length of the compressed SEC1 encoding of a public key
+const COMPRESSED_PUB_KEY_LEN: usize = 33;
+/// the length of the authenticator data, without its optional extensions
+const MIN_AUTHENTICATOR_DATA_LEN: usize = 37;
+/// the flag of the authenticator data set when the user was present
+const USER_PRESENT: u8 = 0x01;
+
+/// Returns the key, if it is a compressed secp256r1 public key
+fn verifying_key(pubkey: &Binary) -> StdResult<VerifyingKey> {
+    if pubkey.len() != COMPRESSED_PUB_KEY_LEN {
+        return Err(StdError::generic_err(
+            "secp256r1 public keys must be compressed",
+        ));
+    }
+    VerifyingKey::from_sec1_bytes(pubkey.as_slice())
+        .map_err(|_| StdError::generic_err("invalid secp256r1 public key"))
+}
+
+/// Returns whether `signature`, of 64 bytes, is the signature of `message` by `pubkey`. Only
+/// signatures with a low `s` are accepted, so that a permit has a single signature, and a
+/// single hash.
+pub(crate) fn verify_signature(
+    message: &[u8],
+    signature: &Binary,
+    pubkey: &Binary,
+) -> StdResult<bool> {
+    let key = verifying_key(pubkey)?;
+    let signature = Signature::from_slice(signature.as_slice())
+        .map_err(|_| StdError::generic_err("invalid secp256r1 signature"))?;
+    if signature.normalize_s().is_some() {
+        return Err(StdError::generic_err(
+            "secp256r1 signatures must have a low s",
+        ));
+    }
+    Ok(key.verify(message, &signature).is_ok())
+}
+
+/// A permit signed by a passkey, or another WebAuthn authenticator.
+///
+/// The authenticator is asked for an assertion whose challenge is the SHA-256 hash of the sign
+/// doc of the params, the same bytes that a wallet signs for a [`Permit`](crate::Permit). It
+/// signs its authenticator data followed by the SHA-256 hash of the client data, which holds the
+/// challenge.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PasskeyPermit<Permission: Permissions = TokenPermissions> {
+    #[serde(bound = "")]
+    pub params: PermitParams<Permission>,
+    pub signature: PasskeySignature,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PasskeySignature {
+    /// the public key of the passkey, whose type is ignored
+    pub pub_key: PubKey,
+    /// the signature of the assertion, either DER encoded as returned by WebAuthn, or 64 bytes
+    pub signature: Binary,
+    /// the `authenticatorData` of the assertion
+    pub authenticator_data: Binary,
+    /// the `clientDataJSON` of the assertion
+    pub client_data_json: Binary,
+}
+
+/// The fields of the client data of an assertion that are checked
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    ty: String,
+    challenge: String,
+}
+
+impl PasskeySignature {
+    /// Returns the signature as 64 bytes with a low `s`, whatever its encoding
+    fn normalized_signature(&self) -> StdResult<Signature> {
+        let bytes = self.signature.as_slice();
+        let signature = Signature::from_der(bytes)
+            .or_else(|_| Signature::from_slice(bytes))
+            .map_err(|_| StdError::generic_err("invalid secp256r1 signature"))?;
+        Ok(signature.normalize_s().unwrap_or(signature))
+    }
+}
+
+/// Returns the 64 bytes of a signature with a low `s`, whatever its encoding. Invalid signatures
+/// are returned as they are, since they can't be verified anyway.
+pub(crate) fn normalize_signature(signature: &[u8]) -> Vec<u8> {
+    match Signature::from_der(signature).or_else(|_| Signature::from_slice(signature)) {
+        Ok(signature) => signature
+            .normalize_s()
+            .unwrap_or(signature)
+            .to_bytes()
+            .to_vec(),
+        Err(_) => signature.to_vec(),
+    }
+}
+
+/// Returns a hash identifying a passkey permit, for use with `PermitUsageTracker`. It doesn't
+/// depend on the encoding of the signature.
+pub fn passkey_permit_hash<Permission: Permissions>(
+    permit: &PasskeyPermit<Permission>,
+) -> StdResult<[u8; 32]> {
+    let signature = permit.signature.normalized_signature()?;
+    Ok(sha_256(&signature.to_bytes()))
+}
+
+/// Validates a permit signed by a passkey like [`validate`](crate::validate) does for other
+/// permits, and returns the account of its key
+pub fn validate_passkey_permit<Permission: Permissions>(
+    deps: Deps,
+    storage_prefix: &str,
+    permit: &PasskeyPermit<Permission>,
+    current_token_address: String,
+    hrp: Option<&str>,
+) -> StdResult<String> {
+    let signature = &permit.signature;
+    let account = validate_params(
+        deps,
+        storage_prefix,
+        &permit.params,
+        &signature.pub_key.value,
+        current_token_address,
+        hrp,
+    )?;
+
+    let authenticator_data = signature.authenticator_data.as_slice();
+    if authenticator_data.len() < MIN_AUTHENTICATOR_DATA_LEN {
+        return Err(StdError::generic_err("invalid authenticator data"));
+    }
+    if authenticator_data[32] & USER_PRESENT == 0 {
+        return Err(StdError::generic_err(
+            "the passkey assertion was made without the user",
+        ));
+    }
+
+    let client_data: ClientData = from_slice(signature.client_data_json.as_slice())?;
+    if client_data.ty != "webauthn.get" {
+        return Err(StdError::generic_err(format!(
+            "unexpected client data type {:?}",
+            client_data.ty
+        )));
+    }
+    let sign_doc = to_binary(&SignedPermit::from_params(&permit.params))?;
+    if decode_base64url(&client_data.challenge)? != sha_256(sign_doc.as_slice()) {
+        return Err(StdError::generic_err(
+            "the challenge of the passkey assertion is not the permit",
+        ));
+    }
+
+    let message = [
+        authenticator_data,
+        &sha_256(signature.client_data_json.as_slice()),
+    ]
+    .concat();
+    let key = verifying_key(&signature.pub_key.value)?;
+    if key
+        .verify(&message, &signature.normalized_signature()?)
+        .is_err()
+    {
+        return Err(StdError::generic_err(
+            "Failed to verify signatures for the given permit",
+        ));
+    }
+
+    Ok(account)
+}
+
+/// Decodes the unpadded base64url encoding WebAuthn uses for challenges
+fn decode_base64url(encoded: &str) -> StdResult<Vec<u8>> {
+    let mut base64: String = encoded
+        .trim_end_matches('=')
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    base64.push_str(&"=".repeat((4 - base64.len() % 4) % 4));
+    Ok(Binary::from_base64(&base64)?.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{permit_hash, permit_id, validate, Permit, PermitSignature, RevokedPermits};
+    use bech32::{ToBase32, Variant};
+    use cosmwasm_std::testing::mock_dependencies;
+    use p256::ecdsa::{signature::Signer, SigningKey};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_slice(&[7; 32]).unwrap()
+    }
+
+    fn pub_key(key: &SigningKey) -> PubKey {
+        PubKey {
+            r#type: SECP256R1_PUB_KEY_TYPE.to_string(),
+            value: Binary::from(key.verifying_key().to_encoded_point(true).as_bytes()),
+        }
+    }
+
+    fn params() -> PermitParams {
+        PermitParams {
+            allowed_tokens: vec!["token".to_string()],
+            permit_name: "balances".to_string(),
+            chain_id: "secret-4".to_string(),
+            permissions: vec![TokenPermissions::Balance],
+        }
+    }
+
+    fn encode_base64url(bytes: &[u8]) -> String {
+        Binary::from(bytes)
+            .to_base64()
+            .trim_end_matches('=')
+            .replace('+', "-")
+            .replace('/', "_")
+    }
+
+    /// Signs the permit like a passkey, with the given client data type and challenge
+    fn sign_passkey_permit(key: &SigningKey, ty: &str, challenge: &[u8]) -> PasskeyPermit {
+        let mut authenticator_data = vec![0x49; 32];
+        authenticator_data.extend_from_slice(&[USER_PRESENT, 0, 0, 0, 1]);
+        let client_data_json = format!(
+            r#"{{"type":"{ty}","challenge":"{}","origin":"https://wallet.example","crossOrigin":false}}"#,
+            encode_base64url(challenge)
+        );
+        let message = [
+            authenticator_data.as_slice(),
+            &sha_256(client_data_json.as_bytes()),
+        ]
+        .concat();
+        let signature: Signature = key.sign(&message);
+        PasskeyPermit {
+            params: params(),
+            signature: PasskeySignature {
+                pub_key: pub_key(key),
+                signature: Binary::from(signature.to_der().as_bytes()),
+                authenticator_data: Binary::from(authenticator_data),
+                client_data_json: Binary::from(client_data_json.as_bytes()),
+            },
+        }
+    }
+
+    #[test]
+    fn test_secp256r1_permit() -> StdResult<()> {
+        let deps = mock_dependencies();
+        let key = signing_key();
+        let sign_doc = to_binary(&SignedPermit::from_params(&params()))?;
+        let signature: Signature = key.sign(sign_doc.as_slice());
+        let signature = signature.normalize_s().unwrap_or(signature);
+        let permit = Permit {
+            params: params(),
+            signature: PermitSignature {
+                pub_key: pub_key(&key),
+                signature: Binary::from(signature.to_bytes().as_slice()),
+            },
+        };
+
+        // the account is derived from the compressed key, like for secp256k1 keys
+        let account = validate(deps.as_ref(), "test", &permit, "token".to_string(), None)?;
+        let canonical = crate::pubkey_to_account(&permit.signature.pub_key.value);
+        let expected =
+            bech32::encode("secret", canonical.as_slice().to_base32(), Variant::Bech32).unwrap();
+        assert_eq!(account, expected);
+
+        // the same signature with a high s is rejected
+        let mut high_s = permit.clone();
+        let (r, s) = signature.split_scalars();
+        high_s.signature.signature = Binary::from(
+            Signature::from_scalars(r, -*s)
+                .unwrap()
+                .to_bytes()
+                .as_slice(),
+        );
+        assert!(validate(deps.as_ref(), "test", &high_s, "token".to_string(), None).is_err());
+
+        // the uncompressed key would give the key another account
+        let mut uncompressed = permit.clone();
+        uncompressed.signature.pub_key.value =
+            Binary::from(key.verifying_key().to_encoded_point(false).as_bytes());
+        assert!(validate(
+            deps.as_ref(),
+            "test",
+            &uncompressed,
+            "token".to_string(),
+            None
+        )
+        .is_err());
+
+        let mut tampered = permit.clone();
+        tampered.params.permit_name = "other".to_string();
+        assert_eq!(
+            validate(deps.as_ref(), "test", &tampered, "token".to_string(), None),
+            Err(StdError::generic_err(
+                "Failed to verify signatures for the given permit"
+            ))
+        );
+        assert!(permit_hash(&permit) != [0; 32]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_passkey_permit() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let key = signing_key();
+        let sign_doc = to_binary(&SignedPermit::from_params(&params()))?;
+        let permit = sign_passkey_permit(&key, "webauthn.get", &sha_256(sign_doc.as_slice()));
+
+        let account =
+            validate_passkey_permit(deps.as_ref(), "test", &permit, "token".to_string(), None)?;
+        assert!(account.starts_with("secret1"));
+        assert!(
+            validate_passkey_permit(deps.as_ref(), "test", &permit, "other".to_string(), None)
+                .is_err()
+        );
+
+        // the assertion must be for this permit
+        let other = sign_passkey_permit(&key, "webauthn.get", &[0; 32]);
+        assert_eq!(
+            validate_passkey_permit(deps.as_ref(), "test", &other, "token".to_string(), None),
+            Err(StdError::generic_err(
+                "the challenge of the passkey assertion is not the permit"
+            ))
+        );
+        let create = sign_passkey_permit(&key, "webauthn.create", &sha_256(sign_doc.as_slice()));
+        assert!(
+            validate_passkey_permit(deps.as_ref(), "test", &create, "token".to_string(), None)
+                .is_err()
+        );
+        let mut absent = permit.clone();
+        let mut authenticator_data = absent.signature.authenticator_data.0.clone();
+        authenticator_data[32] = 0;
+        absent.signature.authenticator_data = Binary::from(authenticator_data);
+        assert!(
+            validate_passkey_permit(deps.as_ref(), "test", &absent, "token".to_string(), None)
+                .is_err()
+        );
+
+        // the hash doesn't depend on the encoding of the signature
+        let mut raw = permit.clone();
+        raw.signature.signature = Binary::from(
+            permit
+                .signature
+                .normalized_signature()?
+                .to_bytes()
+                .as_slice(),
+        );
+        assert_eq!(passkey_permit_hash(&raw)?, passkey_permit_hash(&permit)?);
+
+        // revoking it by id also revokes the same params signed by a wallet
+        let signature: Signature = key.sign(sign_doc.as_slice());
+        let signature = signature.normalize_s().unwrap_or(signature);
+        let wallet = Permit {
+            params: params(),
+            signature: PermitSignature {
+                pub_key: pub_key(&key),
+                signature: Binary::from(signature.to_bytes().as_slice()),
+            },
+        };
+        assert_eq!(
+            validate(deps.as_ref(), "test", &wallet, "token".to_string(), None)?,
+            account
+        );
+        RevokedPermits::revoke_permit_id(
+            &mut deps.storage,
+            "test",
+            &account,
+            &permit_id(&permit.params)?,
+        )?;
+        assert!(
+            validate_passkey_permit(deps.as_ref(), "test", &raw, "token".to_string(), None)
+                .is_err()
+        );
+        assert!(validate(deps.as_ref(), "test", &wallet, "token".to_string(), None).is_err());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,9 @@
-use cosmwasm_std::Storage;
+use cosmwasm_std::{Binary, StdResult, Storage};
+
+use secret_toolkit_storage::{Item, Keymap};
+
+const PREFIX_REVOKED_IDS: &[u8] = b"revoked_permit_ids";
+const PREFIX_REVOKED_BEFORE: &[u8] = b"permits_revoked_before";
 
 pub struct RevokedPermits;
 
@@ -29,4 +34,138 @@ impl RevokedPermits {
         // https://github.com/scrtlabs/cosmwasm/blob/f7e2b1dbf11e113e258d796288752503a5012367/packages/std/src/storage.rs#L30
         storage.set(storage_key.as_bytes(), "_".as_bytes())
     }
+
+    fn revoked_ids(storage_prefix: &str, account: &str) -> Keymap<'static, Binary, bool> {
+        Keymap::new(PREFIX_REVOKED_IDS)
+            .add_suffix(storage_prefix.as_bytes())
+            .add_suffix(account.as_bytes())
+    }
+
+    fn revoked_before(storage_prefix: &str, account: &str) -> Item<'static, u64> {
+        Item::new(PREFIX_REVOKED_BEFORE)
+            .add_suffix(storage_prefix.as_bytes())
+            .add_suffix(account.as_bytes())
+    }
+
+    /// Returns whether a specific permit was revoked, by its id (see `permit_id`)
+    pub fn is_permit_id_revoked(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit_id: &[u8],
+    ) -> bool {
+        Self::revoked_ids(storage_prefix, account).contains(storage, &Binary::from(permit_id))
+    }
+
+    /// Revokes a specific permit by its id (see `permit_id`). Unlike `revoke_permit`, other
+    /// permits signed with the same name are still valid.
+    pub fn revoke_permit_id(
+        storage: &mut dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit_id: &[u8],
+    ) -> StdResult<()> {
+        Self::revoked_ids(storage_prefix, account).insert(storage, &Binary::from(permit_id), &true)
+    }
+
+    /// Restores a permit revoked with `revoke_permit_id`. It is still rejected if it was also
+    /// revoked by name or by creation height.
+    pub fn unrevoke_permit_id(
+        storage: &mut dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit_id: &[u8],
+    ) -> StdResult<()> {
+        Self::revoked_ids(storage_prefix, account).remove(storage, &Binary::from(permit_id))
+    }
+
+    /// Returns a page of the ids of the permits the account revoked with `revoke_permit_id`, so
+    /// it can manage them, and the total number of such permits
+    pub fn query_revoked_permit_ids(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        page: u32,
+        page_size: u32,
+    ) -> StdResult<(Vec<Binary>, u32)> {
+        let revoked_ids = Self::revoked_ids(storage_prefix, account);
+        let total = revoked_ids.get_len(storage)?;
+        let page = revoked_ids.paging_keys(storage, page, page_size)?;
+
+        Ok((page, total))
+    }
+
+    /// Returns the height before which the permits of the account are revoked, if any
+    pub fn revoked_before_height(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+    ) -> StdResult<Option<u64>> {
+        Self::revoked_before(storage_prefix, account).may_load(storage)
+    }
+
+    /// Revokes every permit of the account created before `height`, along with every permit
+    /// whose creation height is unknown. See `PermitParams::created_at` for the convention
+    /// that gives the creation height of permits.
+    ///
+    /// The height can only be raised, so an earlier revocation can't be undone.
+    pub fn revoke_permits_created_before(
+        storage: &mut dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        height: u64,
+    ) -> StdResult<()> {
+        let revoked_before = Self::revoked_before(storage_prefix, account);
+        if revoked_before.may_load(storage)?.unwrap_or_default() >= height {
+            return Ok(());
+        }
+        revoked_before.save(storage, &height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_revoked_permit_ids() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        for id in [b"id1", b"id2", b"id3"] {
+            RevokedPermits::revoke_permit_id(&mut storage, "test", "alice", id)?;
+        }
+        // revoking twice doesn't list the permit twice
+        RevokedPermits::revoke_permit_id(&mut storage, "test", "alice", b"id2")?;
+        RevokedPermits::revoke_permit_id(&mut storage, "test", "bob", b"id4")?;
+
+        let (ids, total) =
+            RevokedPermits::query_revoked_permit_ids(&storage, "test", "alice", 0, 2)?;
+        assert_eq!(total, 3);
+        assert_eq!(ids, vec![Binary::from(b"id1"), Binary::from(b"id2")]);
+        let (ids, _) = RevokedPermits::query_revoked_permit_ids(&storage, "test", "alice", 1, 2)?;
+        assert_eq!(ids, vec![Binary::from(b"id3")]);
+
+        RevokedPermits::unrevoke_permit_id(&mut storage, "test", "alice", b"id1")?;
+        assert!(!RevokedPermits::is_permit_id_revoked(
+            &storage, "test", "alice", b"id1"
+        ));
+        assert!(RevokedPermits::is_permit_id_revoked(
+            &storage, "test", "alice", b"id3"
+        ));
+        let (ids, total) =
+            RevokedPermits::query_revoked_permit_ids(&storage, "test", "alice", 0, 10)?;
+        assert_eq!(total, 2);
+        assert_eq!(ids.len(), 2);
+
+        // other accounts and prefixes are kept separately
+        let (ids, total) =
+            RevokedPermits::query_revoked_permit_ids(&storage, "test", "bob", 0, 10)?;
+        assert_eq!((ids, total), (vec![Binary::from(b"id4")], 1));
+        let (_, total) =
+            RevokedPermits::query_revoked_permit_ids(&storage, "other", "alice", 0, 10)?;
+        assert_eq!(total, 0);
+
+        Ok(())
+    }
 }
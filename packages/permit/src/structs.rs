@@ -34,6 +34,22 @@ pub struct PermitParams<Permission: Permissions = TokenPermissions> {
     pub permissions: Vec<Permission>,
 }
 
+impl<Permission: Permissions> PermitParams<Permission> {
+    /// Returns the block height the permit was created at, if its name follows the
+    /// `<name>@<height>` convention, e.g. `"balances@4820312"`.
+    ///
+    /// Since the name is signed, the height can't be changed without signing a new permit.
+    /// Permits that don't follow the convention are rejected once their account revoked the
+    /// permits created before some height, see `RevokedPermits::revoke_permits_created_before`.
+    pub fn created_at(&self) -> Option<u64> {
+        let (_, height) = self.permit_name.rsplit_once('@')?;
+        if height.is_empty() || !height.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        height.parse().ok()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct PermitSignature {
@@ -44,9 +60,10 @@ pub struct PermitSignature {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct PubKey {
-    /// ignored, but must be "tendermint/PubKeySecp256k1" otherwise the verification will fail
+    /// ignored, but must be "tendermint/PubKeySecp256k1" otherwise the verification will fail,
+    /// unless it is "tendermint/PubKeySecp256r1" with the `secp256r1` feature
     pub r#type: String,
-    /// Secp256k1 PubKey
+    /// Secp256k1 PubKey, or compressed Secp256r1 PubKey
     pub value: Binary,
 }
 
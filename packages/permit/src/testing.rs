@@ -0,0 +1,96 @@
+//! Helpers to generate valid permits in unit tests, instead of hardcoding permits signed by a
+//! wallet.
+
+use cosmwasm_std::{testing::MockApi, to_binary, Binary, StdResult};
+
+use secret_toolkit_crypto::secp256k1::PrivateKey;
+
+use crate::{Permissions, Permit, PermitParams, PermitSignature, PubKey, SignedPermit};
+
+/// the type of the public keys of permits signed by wallets
+pub const PUB_KEY_TYPE: &str = "tendermint/PubKeySecp256k1";
+
+/// Returns the bytes of the StdSignDoc a wallet signs when creating a permit with these params
+pub fn sign_doc_bytes<Permission: Permissions>(
+    params: &PermitParams<Permission>,
+) -> StdResult<Binary> {
+    to_binary(&SignedPermit::from_params(params))
+}
+
+/// Signs the params with the given key, and returns the resulting permit
+///
+/// ```
+/// # use cosmwasm_std::{testing::mock_dependencies, StdResult};
+/// # use secret_toolkit_crypto::secp256k1::PrivateKey;
+/// # use secret_toolkit_permit::{testing::sign_permit, validate, PermitParams, TokenPermissions};
+/// let deps = mock_dependencies();
+/// let key = PrivateKey::parse(&[1; 32])?;
+/// let params = PermitParams {
+///     allowed_tokens: vec!["token".to_string()],
+///     permit_name: "test".to_string(),
+///     chain_id: "secret-4".to_string(),
+///     permissions: vec![TokenPermissions::Balance],
+/// };
+///
+/// let permit = sign_permit(params, &key)?;
+/// assert!(validate(deps.as_ref(), "revoked_permits", &permit, "token".to_string(), None).is_ok());
+/// # Ok::<(), cosmwasm_std::StdError>(())
+/// ```
+pub fn sign_permit<Permission: Permissions>(
+    params: PermitParams<Permission>,
+    key: &PrivateKey,
+) -> StdResult<Permit<Permission>> {
+    let signature = key.sign(sign_doc_bytes(&params)?.as_slice(), MockApi::default());
+    Ok(Permit {
+        params,
+        signature: PermitSignature {
+            pub_key: PubKey {
+                r#type: PUB_KEY_TYPE.to_string(),
+                value: Binary::from(key.pubkey().serialize_compressed().as_slice()),
+            },
+            signature: Binary::from(signature.serialize().as_slice()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{validate, TokenPermissions};
+    use cosmwasm_std::testing::mock_dependencies;
+
+    fn params() -> PermitParams {
+        PermitParams {
+            allowed_tokens: vec!["token".to_string()],
+            permit_name: "test".to_string(),
+            chain_id: "secret-4".to_string(),
+            permissions: vec![TokenPermissions::History],
+        }
+    }
+
+    #[test]
+    fn test_sign_doc_bytes() -> StdResult<()> {
+        assert_eq!(
+            String::from_utf8(sign_doc_bytes(&params())?.0).unwrap(),
+            r#"{"account_number":"0","chain_id":"secret-4","fee":{"amount":[{"amount":"0","denom":"uscrt"}],"gas":"1"},"memo":"","msgs":[{"type":"query_permit","value":{"allowed_tokens":["token"],"permissions":["history"],"permit_name":"test"}}],"sequence":"0"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_permit() -> StdResult<()> {
+        let deps = mock_dependencies();
+        let key = PrivateKey::parse(&[7; 32])?;
+
+        let permit = sign_permit(params(), &key)?;
+        let account = validate(deps.as_ref(), "test", &permit, "token".to_string(), None)?;
+        assert!(account.starts_with("secret1"));
+
+        // tampering with the params invalidates the signature
+        let mut tampered = permit;
+        tampered.params.permissions.push(TokenPermissions::Owner);
+        assert!(validate(deps.as_ref(), "test", &tampered, "token".to_string(), None).is_err());
+
+        Ok(())
+    }
+}
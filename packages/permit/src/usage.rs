@@ -0,0 +1,210 @@
+//! Optional usage accounting for permits, behind the `usage-tracker` feature.
+
+use cosmwasm_std::{Binary, StdError, StdResult, Storage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use secret_toolkit_serialization::Json;
+use secret_toolkit_storage::Keymap;
+
+const PREFIX_USAGE: &[u8] = b"permit_usage";
+
+/// How many times a permit was used, and when it was last used
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct PermitUsage {
+    pub permit_name: String,
+    pub uses: u64,
+    pub last_used_block: u64,
+}
+
+/// Optional usage accounting for permits.
+///
+/// Uses are keyed by the hash of the permit (see `permit_hash`), so two permits with the same
+/// name are counted separately. Recording a use writes to storage, so it can only be done while
+/// handling a transaction, not in queries.
+pub struct PermitUsageTracker;
+
+impl PermitUsageTracker {
+    fn usages(storage_prefix: &str, account: &str) -> Keymap<'static, Binary, PermitUsage, Json> {
+        Keymap::new(PREFIX_USAGE)
+            .add_suffix(storage_prefix.as_bytes())
+            .add_suffix(account.as_bytes())
+    }
+
+    /// Returns the usage recorded for a permit, if it was ever used
+    pub fn get_usage(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit_hash: &[u8],
+    ) -> Option<PermitUsage> {
+        Self::usages(storage_prefix, account).get(storage, &Binary::from(permit_hash))
+    }
+
+    /// Fails if the permit was already used `max_uses` times or more.
+    ///
+    /// Use `Some(1)` as `max_uses` to only accept one-time permits.
+    pub fn check_use(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit_hash: &[u8],
+        max_uses: Option<u64>,
+    ) -> StdResult<()> {
+        let uses = Self::get_usage(storage, storage_prefix, account, permit_hash)
+            .map_or(0, |usage| usage.uses);
+        match max_uses {
+            Some(max_uses) if uses >= max_uses => Err(StdError::generic_err(format!(
+                "Permit was already used {uses} times, the maximum allowed is {max_uses}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the permit against `max_uses` and records another use of it
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - a mutable reference to the contract's storage
+    /// * `storage_prefix` - the prefix the usage counters are kept under
+    /// * `account` - the account that signed the permit
+    /// * `permit_name` - the name of the permit
+    /// * `permit_hash` - the hash identifying the permit, see `permit_hash`
+    /// * `block_height` - the current block height
+    /// * `max_uses` - reject the permit once it was used this many times
+    pub fn record_use(
+        storage: &mut dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        permit_name: &str,
+        permit_hash: &[u8],
+        block_height: u64,
+        max_uses: Option<u64>,
+    ) -> StdResult<PermitUsage> {
+        Self::check_use(storage, storage_prefix, account, permit_hash, max_uses)?;
+
+        let usages = Self::usages(storage_prefix, account);
+        let key = Binary::from(permit_hash);
+        let uses = usages.get(storage, &key).map_or(0, |usage| usage.uses);
+        let usage = PermitUsage {
+            permit_name: permit_name.to_string(),
+            uses: uses + 1,
+            last_used_block: block_height,
+        };
+        usages.insert(storage, &key, &usage)?;
+
+        Ok(usage)
+    }
+
+    /// Returns a page of the usage recorded for the permits of an account, and the total number
+    /// of permits the account used
+    pub fn query_usage(
+        storage: &dyn Storage,
+        storage_prefix: &str,
+        account: &str,
+        page: u32,
+        page_size: u32,
+    ) -> StdResult<(Vec<PermitUsage>, u32)> {
+        let usages = Self::usages(storage_prefix, account);
+        let total = usages.get_len(storage)?;
+        let page = usages
+            .paging(storage, page, page_size)?
+            .into_iter()
+            .map(|(_, usage)| usage)
+            .collect();
+
+        Ok((page, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_record_use() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        let usage = PermitUsageTracker::record_use(
+            &mut storage,
+            "test",
+            "alice",
+            "one",
+            b"hash1",
+            10,
+            None,
+        )?;
+        assert_eq!(usage.uses, 1);
+        let usage = PermitUsageTracker::record_use(
+            &mut storage,
+            "test",
+            "alice",
+            "one",
+            b"hash1",
+            12,
+            None,
+        )?;
+        assert_eq!(
+            usage,
+            PermitUsage {
+                permit_name: "one".to_string(),
+                uses: 2,
+                last_used_block: 12
+            }
+        );
+        PermitUsageTracker::record_use(&mut storage, "test", "alice", "two", b"hash2", 13, None)?;
+
+        // other accounts and prefixes are tracked separately
+        assert_eq!(
+            PermitUsageTracker::get_usage(&storage, "test", "bob", b"hash1"),
+            None
+        );
+        assert_eq!(
+            PermitUsageTracker::get_usage(&storage, "other", "alice", b"hash1"),
+            None
+        );
+
+        let (usages, total) = PermitUsageTracker::query_usage(&storage, "test", "alice", 0, 10)?;
+        assert_eq!(total, 2);
+        let counts: Vec<_> = usages
+            .iter()
+            .map(|u| (u.permit_name.as_str(), u.uses))
+            .collect();
+        assert_eq!(counts, vec![("one", 2), ("two", 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_one_time_permit() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        PermitUsageTracker::check_use(&storage, "test", "alice", b"hash", Some(1))?;
+        PermitUsageTracker::record_use(&mut storage, "test", "alice", "once", b"hash", 1, Some(1))?;
+
+        let err = PermitUsageTracker::record_use(
+            &mut storage,
+            "test",
+            "alice",
+            "once",
+            b"hash",
+            2,
+            Some(1),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Permit was already used 1 times, the maximum allowed is 1")
+        );
+        assert!(
+            PermitUsageTracker::check_use(&storage, "test", "alice", b"hash", Some(1)).is_err()
+        );
+        assert_eq!(
+            PermitUsageTracker::get_usage(&storage, "test", "alice", b"hash").map(|u| u.uses),
+            Some(1)
+        );
+
+        Ok(())
+    }
+}
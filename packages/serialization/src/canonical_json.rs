@@ -0,0 +1,253 @@
+use std::any::type_name;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use cosmwasm_std::{StdError, StdResult};
+
+use crate::Serde;
+
+/// Use canonical json for serialization, for payloads that are hashed or signed, such as
+/// permits or multisig payloads.
+///
+/// The output is compact json where the keys of every object are sorted by their bytes, so it
+/// doesn't depend on the order the fields of a struct were declared in. Like [`Json`](crate::Json),
+/// it doesn't support floats, which have several representations, nor maps. Deserialization
+/// accepts any json.
+#[derive(Copy, Clone, Debug)]
+pub struct CanonicalJson;
+
+impl Serde for CanonicalJson {
+    fn serialize<T: Serialize>(obj: &T) -> StdResult<Vec<u8>> {
+        let json = cosmwasm_std::to_vec(obj)?;
+        let mut pos = 0;
+        let canonical = canonicalize_value(&json, &mut pos)
+            .map_err(|err| StdError::serialize_err(type_name::<T>(), err))?;
+        skip_whitespace(&json, &mut pos);
+        if pos != json.len() {
+            return Err(StdError::serialize_err(
+                type_name::<T>(),
+                "trailing characters",
+            ));
+        }
+        Ok(canonical)
+    }
+
+    fn deserialize<T: DeserializeOwned>(data: &[u8]) -> StdResult<T> {
+        cosmwasm_std::from_slice(data)
+    }
+}
+
+fn skip_whitespace(json: &[u8], pos: &mut usize) {
+    while json.get(*pos).is_some_and(u8::is_ascii_whitespace) {
+        *pos += 1;
+    }
+}
+
+fn expect(json: &[u8], pos: &mut usize, byte: u8) -> Result<(), String> {
+    skip_whitespace(json, pos);
+    if json.get(*pos) != Some(&byte) {
+        return Err(format!("expected '{}' at {}", byte as char, pos));
+    }
+    *pos += 1;
+    Ok(())
+}
+
+/// Returns the canonical form of the json value starting at `pos`, and moves `pos` past it
+fn canonicalize_value(json: &[u8], pos: &mut usize) -> Result<Vec<u8>, String> {
+    skip_whitespace(json, pos);
+    match json.get(*pos) {
+        Some(b'{') => {
+            *pos += 1;
+            let mut members: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+            skip_whitespace(json, pos);
+            if json.get(*pos) == Some(&b'}') {
+                *pos += 1;
+            } else {
+                loop {
+                    skip_whitespace(json, pos);
+                    let key = string(json, pos)?;
+                    expect(json, pos, b':')?;
+                    members.push((key, canonicalize_value(json, pos)?));
+                    skip_whitespace(json, pos);
+                    match json.get(*pos) {
+                        Some(b',') => *pos += 1,
+                        Some(b'}') => {
+                            *pos += 1;
+                            break;
+                        }
+                        _ => return Err(format!("expected ',' or '}}' at {pos}")),
+                    }
+                }
+            }
+
+            members.sort_by(|(a, _), (b, _)| a.cmp(b));
+            if members.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                return Err("duplicate object key".to_string());
+            }
+            let mut out = vec![b'{'];
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                out.extend(key);
+                out.push(b':');
+                out.extend(value);
+            }
+            out.push(b'}');
+            Ok(out)
+        }
+        Some(b'[') => {
+            *pos += 1;
+            let mut out = vec![b'['];
+            skip_whitespace(json, pos);
+            if json.get(*pos) == Some(&b']') {
+                *pos += 1;
+            } else {
+                loop {
+                    out.extend(canonicalize_value(json, pos)?);
+                    skip_whitespace(json, pos);
+                    match json.get(*pos) {
+                        Some(b',') => {
+                            *pos += 1;
+                            out.push(b',');
+                        }
+                        Some(b']') => {
+                            *pos += 1;
+                            break;
+                        }
+                        _ => return Err(format!("expected ',' or ']' at {pos}")),
+                    }
+                }
+            }
+            out.push(b']');
+            Ok(out)
+        }
+        Some(b'"') => string(json, pos),
+        Some(b'-' | b'0'..=b'9') => {
+            let start = *pos;
+            while json
+                .get(*pos)
+                .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+            {
+                *pos += 1;
+            }
+            let number = &json[start..*pos];
+            if number.iter().any(|b| matches!(b, b'.' | b'e' | b'E')) {
+                return Err("floats are not supported".to_string());
+            }
+            Ok(number.to_vec())
+        }
+        _ => {
+            for literal in [b"true".as_slice(), b"false", b"null"] {
+                if json[*pos..].starts_with(literal) {
+                    *pos += literal.len();
+                    return Ok(literal.to_vec());
+                }
+            }
+            Err(format!("unexpected value at {pos}"))
+        }
+    }
+}
+
+/// Returns the string starting at `pos`, as is, and moves `pos` past it
+fn string(json: &[u8], pos: &mut usize) -> Result<Vec<u8>, String> {
+    if json.get(*pos) != Some(&b'"') {
+        return Err(format!("expected a string at {pos}"));
+    }
+    let start = *pos;
+    *pos += 1;
+    loop {
+        match json.get(*pos) {
+            Some(b'\\') => *pos += 2,
+            Some(b'"') => {
+                *pos += 1;
+                return Ok(json[start..*pos].to_vec());
+            }
+            Some(_) => *pos += 1,
+            None => return Err("unterminated string".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Params {
+        permit_name: String,
+        allowed_tokens: Vec<String>,
+        chain_id: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ReorderedParams {
+        chain_id: String,
+        allowed_tokens: Vec<String>,
+        permit_name: String,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Msg {
+        Transfer { to: String, amount: u64 },
+    }
+
+    #[derive(Serialize)]
+    struct Signed {
+        signature: Option<String>,
+        msg: Msg,
+    }
+
+    #[test]
+    fn test_canonical_json() -> StdResult<()> {
+        let params = Params {
+            permit_name: "balances".to_string(),
+            allowed_tokens: vec!["secret1b".to_string(), "secret1a".to_string()],
+            chain_id: "secret-4".to_string(),
+        };
+        let reordered = ReorderedParams {
+            chain_id: "secret-4".to_string(),
+            allowed_tokens: vec!["secret1b".to_string(), "secret1a".to_string()],
+            permit_name: "balances".to_string(),
+        };
+
+        let canonical = CanonicalJson::serialize(&params)?;
+        assert_eq!(
+            String::from_utf8(canonical.clone()).unwrap(),
+            r#"{"allowed_tokens":["secret1b","secret1a"],"chain_id":"secret-4","permit_name":"balances"}"#
+        );
+        assert_eq!(CanonicalJson::serialize(&reordered)?, canonical);
+        assert_eq!(CanonicalJson::deserialize::<Params>(&canonical)?, params);
+
+        let signed = Signed {
+            signature: None,
+            msg: Msg::Transfer {
+                to: "secret1a".to_string(),
+                amount: 5,
+            },
+        };
+        assert_eq!(
+            String::from_utf8(CanonicalJson::serialize(&signed)?).unwrap(),
+            r#"{"msg":{"transfer":{"amount":5,"to":"secret1a"}},"signature":null}"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonicalize_value() {
+        let json = br#" { "b" : [ 1, -2, {"y":true,"x":"a\"b"} ], "a" : null } "#;
+        let mut pos = 0;
+        assert_eq!(
+            String::from_utf8(canonicalize_value(json, &mut pos).unwrap()).unwrap(),
+            r#"{"a":null,"b":[1,-2,{"x":"a\"b","y":true}]}"#
+        );
+
+        assert!(canonicalize_value(b"[1.5]", &mut 0).is_err());
+        assert!(canonicalize_value(b"1e3", &mut 0).is_err());
+        assert!(canonicalize_value(br#"{"a":1,"a":2}"#, &mut 0).is_err());
+        assert!(canonicalize_value(br#"{"a":1"#, &mut 0).is_err());
+    }
+}
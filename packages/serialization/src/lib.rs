@@ -9,6 +9,8 @@ mod base64;
 #[cfg(feature = "bincode2")]
 mod bincode2;
 #[cfg(feature = "json")]
+mod canonical_json;
+#[cfg(feature = "json")]
 mod json;
 
 #[cfg(all(feature = "bincode2", feature = "base64"))]
@@ -21,6 +23,8 @@ pub use crate::base64::{Base64, Base64Of};
 #[cfg(feature = "bincode2")]
 pub use crate::bincode2::Bincode2;
 #[cfg(feature = "json")]
+pub use crate::canonical_json::CanonicalJson;
+#[cfg(feature = "json")]
 pub use crate::json::Json;
 
 /// This trait represents the ability to both serialize and deserialize using a specific format.
@@ -0,0 +1,242 @@
+//! Keeping the allowances a contract gives to a spender, e.g. a router, high enough and far
+//! enough from their expiration, without sending an `IncreaseAllowance` on every call.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CosmosMsg, CustomQuery, Env, QuerierWrapper, StdResult, Uint128};
+
+use secret_toolkit_utils::types::Contract;
+
+use crate::handle::increase_allowance_msg;
+use crate::query::{allowance_query, Allowance};
+
+/// When to refresh an allowance that expires, and how long a refreshed allowance lasts
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExpiryPolicy {
+    /// the allowance is refreshed if it expires within this many seconds
+    pub margin: u64,
+    /// the number of seconds a refreshed allowance lasts, or `None` to leave its expiration as
+    /// is. Tokens only clear the expiration of an allowance that already expired, so set this if
+    /// the allowance may have one.
+    pub duration: Option<u64>,
+}
+
+/// Returns the amount an `IncreaseAllowance` must add to `current` so that the spender can spend
+/// at least `min_amount` until at least `margin` seconds from now, along with the expiration to
+/// set, or `None` if the allowance doesn't need to be refreshed.
+///
+/// # Arguments
+///
+/// * `current` - the current allowance, as queried from the token
+/// * `env` - the environment of the current call, for the block time
+/// * `min_amount` - the amount the spender must be allowed to spend
+/// * `policy` - when to refresh an allowance that expires, and how long a refreshed one lasts
+pub fn allowance_increase(
+    current: &Allowance,
+    env: &Env,
+    min_amount: Uint128,
+    policy: &ExpiryPolicy,
+) -> Option<(Uint128, Option<u64>)> {
+    let now = env.block.time.seconds();
+    let expired = current
+        .expiration
+        .is_some_and(|expiration| expiration <= now);
+    let expiring = current
+        .expiration
+        .is_some_and(|expiration| expiration <= now.saturating_add(policy.margin));
+    if !expiring && current.allowance >= min_amount {
+        return None;
+    }
+
+    // tokens reset expired allowances before increasing them
+    let amount = if expired {
+        min_amount
+    } else {
+        min_amount.saturating_sub(current.allowance)
+    };
+    let expiration = policy.duration.map(|duration| now.saturating_add(duration));
+    Some((amount, expiration))
+}
+
+/// Queries the allowance this contract gives to `spender`, and returns the `IncreaseAllowance`
+/// message that lets it spend at least `min_amount` until at least `policy.margin` seconds from
+/// now, or `None` if the current allowance already does.
+///
+/// This avoids both sending redundant allowance transactions and letting transfers fail because
+/// an allowance expired.
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `env` - the environment of the current call, for the address of this contract and the block
+///   time
+/// * `token` - the token contract
+/// * `spender` - the address allowed to spend the tokens of this contract
+/// * `min_amount` - the amount the spender must be allowed to spend
+/// * `policy` - when to refresh an allowance that expires, and how long a refreshed one lasts
+/// * `key` - the viewing key of this contract on the token
+/// * `block_size` - pad the query and the message to blocks of this size
+#[allow(clippy::too_many_arguments)]
+pub fn ensure_allowance<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    env: &Env,
+    token: &Contract,
+    spender: String,
+    min_amount: Uint128,
+    policy: &ExpiryPolicy,
+    key: String,
+    block_size: usize,
+) -> StdResult<Option<CosmosMsg>> {
+    let current = allowance_query(
+        querier,
+        env.contract.address.to_string(),
+        spender.clone(),
+        key,
+        block_size,
+        token.hash.clone(),
+        token.address.clone(),
+    )?;
+    allowance_increase(&current, env, min_amount, policy)
+        .map(|(amount, expiration)| {
+            increase_allowance_msg(
+                spender,
+                amount,
+                expiration,
+                None,
+                block_size,
+                token.hash.clone(),
+                token.address.clone(),
+            )
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{
+        from_binary, Binary, ContractResult, Empty, Querier, QuerierResult, SystemResult, WasmMsg,
+    };
+
+    use crate::HandleMsg;
+
+    struct AllowanceQuerier {
+        allowance: Allowance,
+    }
+
+    impl Querier for AllowanceQuerier {
+        fn raw_query(&self, _request: &[u8]) -> QuerierResult {
+            let response = format!(
+                r#"{{"allowance":{}}}"#,
+                String::from_utf8(cosmwasm_std::to_vec(&self.allowance).unwrap()).unwrap()
+            );
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        }
+    }
+
+    fn allowance(amount: u128, expiration: Option<u64>) -> Allowance {
+        Allowance {
+            spender: "router".to_string(),
+            owner: mock_env().contract.address.to_string(),
+            allowance: Uint128::new(amount),
+            expiration,
+        }
+    }
+
+    #[test]
+    fn test_allowance_increase() {
+        let env = mock_env();
+        let now = env.block.time.seconds();
+        let policy = ExpiryPolicy {
+            margin: 600,
+            duration: Some(3600),
+        };
+        let min = Uint128::new(100);
+
+        assert_eq!(
+            allowance_increase(&allowance(100, None), &env, min, &policy),
+            None
+        );
+        assert_eq!(
+            allowance_increase(&allowance(150, Some(now + 601)), &env, min, &policy),
+            None
+        );
+        assert_eq!(
+            allowance_increase(&allowance(40, None), &env, min, &policy),
+            Some((Uint128::new(60), Some(now + 3600)))
+        );
+        // an allowance that expires soon is extended, even if it is high enough
+        assert_eq!(
+            allowance_increase(&allowance(150, Some(now + 600)), &env, min, &policy),
+            Some((Uint128::zero(), Some(now + 3600)))
+        );
+        // an expired allowance is reset by the token, so the whole amount is needed
+        assert_eq!(
+            allowance_increase(&allowance(150, Some(now)), &env, min, &policy),
+            Some((min, Some(now + 3600)))
+        );
+        assert_eq!(
+            allowance_increase(&allowance(40, None), &env, min, &ExpiryPolicy::default()),
+            Some((Uint128::new(60), None))
+        );
+    }
+
+    #[test]
+    fn test_ensure_allowance() -> StdResult<()> {
+        let env = mock_env();
+        let token = Contract {
+            address: "token".to_string(),
+            hash: "token_hash".to_string(),
+        };
+        let policy = ExpiryPolicy::default();
+
+        let querier = AllowanceQuerier {
+            allowance: allowance(100, None),
+        };
+        let msg = ensure_allowance(
+            QuerierWrapper::<Empty>::new(&querier),
+            &env,
+            &token,
+            "router".to_string(),
+            Uint128::new(100),
+            &policy,
+            "key".to_string(),
+            256,
+        )?;
+        assert_eq!(msg, None);
+
+        let msg = ensure_allowance(
+            QuerierWrapper::<Empty>::new(&querier),
+            &env,
+            &token,
+            "router".to_string(),
+            Uint128::new(250),
+            &policy,
+            "key".to_string(),
+            256,
+        )?;
+        let Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        })) = msg
+        else {
+            panic!("expected an IncreaseAllowance message");
+        };
+        assert_eq!(contract_addr, "token");
+        let msg: HandleMsg = from_binary(&Binary::from(
+            String::from_utf8(msg.0).unwrap().trim_end().as_bytes(),
+        ))?;
+        assert_eq!(
+            msg,
+            HandleMsg::IncreaseAllowance {
+                spender: "router".to_string(),
+                amount: Uint128::new(150),
+                expiration: None,
+                padding: None,
+            }
+        );
+
+        Ok(())
+    }
+}
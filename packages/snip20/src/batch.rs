@@ -1,7 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Binary, Uint128};
+use cosmwasm_std::{from_slice, Binary, Reply, StdError, StdResult, SubMsgResult, Uint128};
+
+use crate::handle::ResponseStatus;
 
 #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -167,3 +169,260 @@ impl BurnFromAction {
         }
     }
 }
+
+/// The result of one action of a batch message, listed by tokens that execute every action of
+/// a batch rather than reverting all of them when one fails
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ActionResult {
+    pub status: ResponseStatus,
+    pub error: Option<String>,
+}
+
+/// Responses of the SNIP20 batch messages, set as the data of the response
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchHandleAnswer {
+    BatchTransfer {
+        status: ResponseStatus,
+        #[serde(default)]
+        results: Option<Vec<ActionResult>>,
+    },
+    BatchSend {
+        status: ResponseStatus,
+        #[serde(default)]
+        results: Option<Vec<ActionResult>>,
+    },
+    BatchTransferFrom {
+        status: ResponseStatus,
+        #[serde(default)]
+        results: Option<Vec<ActionResult>>,
+    },
+    BatchSendFrom {
+        status: ResponseStatus,
+        #[serde(default)]
+        results: Option<Vec<ActionResult>>,
+    },
+    BatchMint {
+        status: ResponseStatus,
+        #[serde(default)]
+        results: Option<Vec<ActionResult>>,
+    },
+    BatchBurnFrom {
+        status: ResponseStatus,
+        #[serde(default)]
+        results: Option<Vec<ActionResult>>,
+    },
+}
+
+impl BatchHandleAnswer {
+    /// Returns the results of the actions, if the token listed them
+    pub fn into_results(self) -> Option<Vec<ActionResult>> {
+        match self {
+            BatchHandleAnswer::BatchTransfer { results, .. }
+            | BatchHandleAnswer::BatchSend { results, .. }
+            | BatchHandleAnswer::BatchTransferFrom { results, .. }
+            | BatchHandleAnswer::BatchSendFrom { results, .. }
+            | BatchHandleAnswer::BatchMint { results, .. }
+            | BatchHandleAnswer::BatchBurnFrom { results, .. } => results,
+        }
+    }
+}
+
+/// The status of one action of a batch message
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ActionStatus {
+    Succeeded,
+    /// the action itself failed
+    Failed { error: String },
+    /// the action was rolled back, or never executed, because the batch failed as a whole
+    Reverted,
+}
+
+/// The status of each action of a batch message, in the order of its actions
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchResult {
+    pub statuses: Vec<ActionStatus>,
+    /// the error of the batch, if it failed as a whole
+    pub error: Option<String>,
+}
+
+impl BatchResult {
+    /// Returns whether every action succeeded
+    pub fn all_succeeded(&self) -> bool {
+        self.statuses
+            .iter()
+            .all(|status| *status == ActionStatus::Succeeded)
+    }
+
+    /// Returns the positions of the actions that failed themselves
+    pub fn failed(&self) -> Vec<usize> {
+        self.statuses
+            .iter()
+            .enumerate()
+            .filter(|(_, status)| matches!(status, ActionStatus::Failed { .. }))
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// Returns the actions that didn't succeed, either because they failed or were reverted,
+    /// e.g. to retry only the payments to the recipients that weren't paid
+    pub fn unsucceeded<A: Clone>(&self, actions: &[A]) -> Vec<A> {
+        self.statuses
+            .iter()
+            .zip(actions)
+            .filter(|(status, _)| **status != ActionStatus::Succeeded)
+            .map(|(_, action)| action.clone())
+            .collect()
+    }
+}
+
+/// Returns the status of each action of a batch message executed as a sub-message, from its
+/// reply.
+///
+/// When the batch failed as a whole, every action is reverted. Otherwise, the actions are
+/// those listed in the answer of the token, or all succeeded if it doesn't list them, as is the
+/// case of the reference implementation, which executes a batch atomically.
+///
+/// # Arguments
+///
+/// * `reply` - the reply of the sub-message, which must be sent with `reply_always`
+/// * `action_count` - the number of actions of the batch
+pub fn parse_batch_answer(reply: &Reply, action_count: usize) -> StdResult<BatchResult> {
+    let response = match &reply.result {
+        SubMsgResult::Ok(response) => response,
+        SubMsgResult::Err(err) => {
+            return Ok(BatchResult {
+                statuses: vec![ActionStatus::Reverted; action_count],
+                error: Some(err.clone()),
+            })
+        }
+    };
+
+    let answer = match &response.data {
+        Some(data) => Some(from_slice::<BatchHandleAnswer>(execute_response_data(data))?),
+        None => None,
+    };
+    let statuses = match answer.and_then(BatchHandleAnswer::into_results) {
+        Some(results) => {
+            if results.len() != action_count {
+                return Err(StdError::generic_err(format!(
+                    "expected the results of {action_count} actions, got {}",
+                    results.len()
+                )));
+            }
+            results
+                .into_iter()
+                .map(|result| match result.status {
+                    ResponseStatus::Success => ActionStatus::Succeeded,
+                    ResponseStatus::Failure => ActionStatus::Failed {
+                        error: result.error.unwrap_or_default(),
+                    },
+                })
+                .collect()
+        }
+        None => vec![ActionStatus::Succeeded; action_count],
+    };
+    Ok(BatchResult {
+        statuses,
+        error: None,
+    })
+}
+
+/// Returns the status of each action of a [`BatchTransfer`](crate::HandleMsg::BatchTransfer)
+/// executed as a sub-message, from its reply. See [`parse_batch_answer`].
+pub fn parse_batch_transfer_answer(
+    reply: &Reply,
+    actions: &[TransferAction],
+) -> StdResult<BatchResult> {
+    parse_batch_answer(reply, actions.len())
+}
+
+/// Returns the data set by the executed contract, which may be wrapped in the protobuf
+/// encoding of a `MsgExecuteContractResponse`, i.e. a single length-delimited field 1
+fn execute_response_data(data: &Binary) -> &[u8] {
+    let data = data.as_slice();
+    if data.first() != Some(&0x0a) {
+        return data;
+    }
+    let mut len = 0usize;
+    for (i, byte) in data.iter().enumerate().skip(1).take(5) {
+        len |= ((byte & 0x7f) as usize) << (7 * (i - 1));
+        if byte & 0x80 == 0 {
+            return match data.get(i + 1..) {
+                Some(inner) if inner.len() == len => inner,
+                _ => data,
+            };
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::SubMsgResponse;
+
+    fn payroll() -> Vec<TransferAction> {
+        ["alice", "bob", "carol"]
+            .into_iter()
+            .map(|recipient| TransferAction::new(recipient.to_string(), Uint128::new(100), None))
+            .collect()
+    }
+
+    fn reply(data: Option<&[u8]>) -> Reply {
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: data.map(Binary::from),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_parse_batch_transfer_answer() -> StdResult<()> {
+        let actions = payroll();
+
+        let result = parse_batch_transfer_answer(
+            &reply(Some(br#"{"batch_transfer":{"status":"success"}}"#)),
+            &actions,
+        )?;
+        assert!(result.all_succeeded());
+        assert!(result.unsucceeded(&actions).is_empty());
+        assert!(parse_batch_transfer_answer(&reply(None), &actions)?.all_succeeded());
+
+        let answer = br#"{"batch_transfer":{"status":"success","results":[{"status":"success","error":null},{"status":"failure","error":"insufficient funds"},{"status":"success","error":null}]}}"#;
+        let result = parse_batch_transfer_answer(&reply(Some(answer)), &actions)?;
+        assert_eq!(
+            result.statuses[1],
+            ActionStatus::Failed {
+                error: "insufficient funds".to_string()
+            }
+        );
+        assert_eq!(result.failed(), vec![1]);
+        assert_eq!(result.unsucceeded(&actions), vec![actions[1].clone()]);
+        assert!(parse_batch_transfer_answer(&reply(Some(answer)), &actions[..2]).is_err());
+
+        let failed = Reply {
+            id: 1,
+            result: SubMsgResult::Err("contract is paused".to_string()),
+        };
+        let result = parse_batch_transfer_answer(&failed, &actions)?;
+        assert_eq!(result.statuses, vec![ActionStatus::Reverted; 3]);
+        assert_eq!(result.error.as_deref(), Some("contract is paused"));
+        assert!(result.failed().is_empty());
+        assert_eq!(result.unsucceeded(&actions), actions);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_response_data() {
+        let json = br#"{"batch_send":{"status":"success"}}"#;
+        let mut wrapped = vec![0x0a, json.len() as u8];
+        wrapped.extend_from_slice(json);
+        assert_eq!(execute_response_data(&Binary::from(wrapped)), json);
+        assert_eq!(execute_response_data(&Binary::from(json.as_slice())), json);
+    }
+}
@@ -0,0 +1,135 @@
+//! The action names and attribute keys of the events of SNIP-20 tokens, and the parsing of the
+//! transfers out of the response to a submessage. See `secret_toolkit_utils::events`.
+
+use cosmwasm_std::{StdError, StdResult, SubMsgResponse, Uint128};
+
+pub use secret_toolkit_utils::events::{
+    find_token_event, parse_token_events, TokenEvent, ACTION_KEY, CONTRACT_ADDRESS_KEY, WASM_EVENT,
+};
+
+/// The names of the actions of SNIP-20 tokens, after their handle messages
+pub mod actions {
+    pub const TRANSFER: &str = "transfer";
+    pub const SEND: &str = "send";
+    pub const TRANSFER_FROM: &str = "transfer_from";
+    pub const SEND_FROM: &str = "send_from";
+    pub const MINT: &str = "mint";
+    pub const BURN: &str = "burn";
+    pub const BURN_FROM: &str = "burn_from";
+    pub const DEPOSIT: &str = "deposit";
+    pub const REDEEM: &str = "redeem";
+    pub const INCREASE_ALLOWANCE: &str = "increase_allowance";
+    pub const DECREASE_ALLOWANCE: &str = "decrease_allowance";
+}
+
+/// The keys of the attributes of the events of SNIP-20 tokens
+pub mod keys {
+    pub const FROM: &str = "from";
+    pub const RECIPIENT: &str = "recipient";
+    pub const OWNER: &str = "owner";
+    pub const SPENDER: &str = "spender";
+    pub const AMOUNT: &str = "amount";
+    pub const MEMO: &str = "memo";
+}
+
+/// A movement of tokens, from a `transfer`, `send`, `transfer_from` or `send_from` event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransferEvent {
+    pub action: String,
+    pub from: String,
+    pub recipient: String,
+    pub amount: Uint128,
+    pub memo: Option<String>,
+}
+
+impl TransferEvent {
+    /// Returns whether an action moves tokens between accounts
+    pub fn is_transfer(action: &str) -> bool {
+        [
+            actions::TRANSFER,
+            actions::SEND,
+            actions::TRANSFER_FROM,
+            actions::SEND_FROM,
+        ]
+        .contains(&action)
+    }
+
+    /// Parses a transfer out of its event. Fails if the event isn't that of a transfer, or
+    /// misses an attribute.
+    pub fn from_event(event: &TokenEvent) -> StdResult<Self> {
+        if !Self::is_transfer(&event.action) {
+            return Err(StdError::generic_err(format!(
+                "{} event is not a transfer",
+                event.action
+            )));
+        }
+        Ok(Self {
+            action: event.action.clone(),
+            from: event.require(keys::FROM)?.to_string(),
+            recipient: event.require(keys::RECIPIENT)?.to_string(),
+            amount: event.require(keys::AMOUNT)?.parse()?,
+            memo: event.get(keys::MEMO).map(str::to_string),
+        })
+    }
+}
+
+/// Returns the transfers made by the token at `contract` in the response to a submessage
+pub fn parse_transfer_events(
+    response: &SubMsgResponse,
+    contract: &str,
+) -> StdResult<Vec<TransferEvent>> {
+    parse_token_events(response, contract)
+        .iter()
+        .filter(|event| TransferEvent::is_transfer(&event.action))
+        .map(TransferEvent::from_event)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::Event;
+
+    #[test]
+    fn test_parse_transfer_events() -> StdResult<()> {
+        let event = |action: &str| {
+            Event::new(WASM_EVENT)
+                .add_attribute(CONTRACT_ADDRESS_KEY, "secret1token")
+                .add_attribute(ACTION_KEY, action)
+                .add_attribute(keys::FROM, "alice")
+                .add_attribute(keys::RECIPIENT, "bob")
+                .add_attribute(keys::AMOUNT, "100")
+        };
+        let response = SubMsgResponse {
+            events: vec![
+                event(actions::SEND).add_attribute(keys::MEMO, "rent"),
+                event(actions::MINT),
+            ],
+            data: None,
+        };
+
+        assert_eq!(
+            parse_transfer_events(&response, "secret1token")?,
+            vec![TransferEvent {
+                action: actions::SEND.to_string(),
+                from: "alice".to_string(),
+                recipient: "bob".to_string(),
+                amount: Uint128::new(100),
+                memo: Some("rent".to_string()),
+            }]
+        );
+
+        let mint = find_token_event(&response, "secret1token", actions::MINT).unwrap();
+        assert!(TransferEvent::from_event(&mint).is_err());
+        let response = SubMsgResponse {
+            events: vec![Event::new(WASM_EVENT)
+                .add_attribute(CONTRACT_ADDRESS_KEY, "secret1token")
+                .add_attribute(ACTION_KEY, actions::TRANSFER)
+                .add_attribute(keys::AMOUNT, "100")],
+            data: None,
+        };
+        assert!(parse_transfer_events(&response, "secret1token").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+//! Conversions between amounts of a SNIP-20 token and of the native coin it wraps, following the
+//! `ExchangeRate` of the token.
+//!
+//! As in the reference implementation, when the token has at least as many decimals as the native
+//! coin, `rate` is the number of token units per unit of the native coin. Otherwise, it is the
+//! number of native units per unit of the token. The conversions compute with `Uint256` and
+//! round in an explicit direction, so that wrapping contracts never credit more than they hold.
+
+use cosmwasm_std::{StdError, StdResult, Uint128, Uint256};
+
+use crate::query::ExchangeRate;
+
+/// The number of decimals of SCRT, and of the other native coins wrapped by SNIP-20 tokens
+pub const NATIVE_DECIMALS: u8 = 6;
+
+/// The direction in which a conversion rounds amounts that are not a whole number of units
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Rounds towards zero, e.g. to mint the tokens of a deposit or pay out a redemption
+    Down,
+    /// Rounds away from zero, e.g. to compute the tokens to burn for a given native amount
+    Up,
+}
+
+/// Returns the amount of a token with `decimals` decimals that `native_amount` of the native
+/// coin is worth, at the exchange rate `rate`.
+pub fn to_token_amount(
+    native_amount: Uint128,
+    decimals: u8,
+    rate: Uint128,
+    rounding: Rounding,
+) -> StdResult<Uint128> {
+    if decimals >= NATIVE_DECIMALS {
+        mul_div(native_amount, rate, Uint128::one(), rounding)
+    } else {
+        mul_div(native_amount, Uint128::one(), rate, rounding)
+    }
+}
+
+/// Returns the amount of the native coin that `token_amount` of a token with `decimals`
+/// decimals is worth, at the exchange rate `rate`.
+pub fn to_native_amount(
+    token_amount: Uint128,
+    decimals: u8,
+    rate: Uint128,
+    rounding: Rounding,
+) -> StdResult<Uint128> {
+    if decimals >= NATIVE_DECIMALS {
+        mul_div(token_amount, Uint128::one(), rate, rounding)
+    } else {
+        mul_div(token_amount, rate, Uint128::one(), rounding)
+    }
+}
+
+impl ExchangeRate {
+    /// Returns the amount of a token with `decimals` decimals that `native_amount` of the native
+    /// coin is worth, at this exchange rate.
+    pub fn to_token_amount(
+        &self,
+        native_amount: Uint128,
+        decimals: u8,
+        rounding: Rounding,
+    ) -> StdResult<Uint128> {
+        to_token_amount(native_amount, decimals, self.rate, rounding)
+    }
+
+    /// Returns the amount of the native coin that `token_amount` of a token with `decimals`
+    /// decimals is worth, at this exchange rate.
+    pub fn to_native_amount(
+        &self,
+        token_amount: Uint128,
+        decimals: u8,
+        rounding: Rounding,
+    ) -> StdResult<Uint128> {
+        to_native_amount(token_amount, decimals, self.rate, rounding)
+    }
+}
+
+fn mul_div(
+    amount: Uint128,
+    numerator: Uint128,
+    denominator: Uint128,
+    rounding: Rounding,
+) -> StdResult<Uint128> {
+    if denominator.is_zero() {
+        return Err(StdError::generic_err("exchange rate must not be zero"));
+    }
+    let product = Uint256::from(amount) * Uint256::from(numerator);
+    let denominator = Uint256::from(denominator);
+    let mut result = product / denominator;
+    if rounding == Rounding::Up && !(product % denominator).is_zero() {
+        result += Uint256::one();
+    }
+    result.try_into().map_err(|_| {
+        StdError::generic_err(format!(
+            "converting {amount} at an exchange rate of {numerator}/{denominator} overflows"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_more_decimals_than_native() -> StdResult<()> {
+        // 18 decimals, so 10^12 token units per uscrt
+        let rate = ExchangeRate {
+            rate: Uint128::new(10u128.pow(12)),
+            denom: "SCRT".to_string(),
+        };
+        let token = rate.to_token_amount(Uint128::new(1_500_000), 18, Rounding::Down)?;
+        assert_eq!(token, Uint128::new(15 * 10u128.pow(17)));
+        assert_eq!(
+            rate.to_native_amount(token, 18, Rounding::Down)?,
+            Uint128::new(1_500_000)
+        );
+
+        // dust below one uscrt rounds in the requested direction
+        let dust = token + Uint128::new(1);
+        assert_eq!(
+            rate.to_native_amount(dust, 18, Rounding::Down)?,
+            Uint128::new(1_500_000)
+        );
+        assert_eq!(
+            rate.to_native_amount(dust, 18, Rounding::Up)?,
+            Uint128::new(1_500_001)
+        );
+
+        assert!(rate
+            .to_token_amount(Uint128::MAX, 18, Rounding::Down)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fewer_decimals_than_native() -> StdResult<()> {
+        // 2 decimals, so 10^4 uscrt per token unit
+        let rate = Uint128::new(10_000);
+        assert_eq!(
+            to_token_amount(Uint128::new(25_000), 2, rate, Rounding::Down)?,
+            Uint128::new(2)
+        );
+        assert_eq!(
+            to_token_amount(Uint128::new(25_000), 2, rate, Rounding::Up)?,
+            Uint128::new(3)
+        );
+        assert_eq!(
+            to_native_amount(Uint128::new(3), 2, rate, Rounding::Down)?,
+            Uint128::new(30_000)
+        );
+
+        // the intermediate product doesn't overflow
+        assert_eq!(
+            mul_div(Uint128::MAX, Uint128::MAX, Uint128::MAX, Rounding::Up)?,
+            Uint128::MAX
+        );
+
+        assert!(to_token_amount(Uint128::one(), 2, Uint128::zero(), Rounding::Down).is_err());
+
+        Ok(())
+    }
+}
@@ -1,14 +1,15 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{to_binary, Binary, Coin, CosmosMsg, StdResult, Uint128, WasmMsg};
 
 use crate::batch::{
     BurnFromAction, MintAction, SendAction, SendFromAction, TransferAction, TransferFromAction,
 };
+use crate::query::ContractStatusLevel;
 use secret_toolkit_utils::space_pad;
 
 /// SNIP20 token handle messages
-#[derive(Serialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
     // Native coin interactions
@@ -135,6 +136,40 @@ pub enum HandleMsg {
         minters: Vec<String>,
         padding: Option<String>,
     },
+
+    // Admin
+    ChangeAdmin {
+        address: String,
+        padding: Option<String>,
+    },
+    SetContractStatus {
+        level: ContractStatusLevel,
+        padding: Option<String>,
+    },
+    RevokePermit {
+        permit_name: String,
+        padding: Option<String>,
+    },
+}
+
+/// Status of an admin message, as returned in its response
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+/// Responses of the SNIP20 minter and admin messages, set as the data of the response
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAnswer {
+    AddMinters { status: ResponseStatus },
+    RemoveMinters { status: ResponseStatus },
+    SetMinters { status: ResponseStatus },
+    ChangeAdmin { status: ResponseStatus },
+    SetContractStatus { status: ResponseStatus },
+    RevokePermit { status: ResponseStatus },
 }
 
 impl HandleMsg {
@@ -146,7 +181,7 @@ impl HandleMsg {
     /// * `callback_code_hash` - String holding the code hash of the contract being called
     /// * `contract_addr` - address of the contract being called
     /// * `send_amount` - Optional Uint128 amount of native coin to send with the callback message
-    ///                 NOTE: Only a Deposit message should have an amount sent with it
+    ///   NOTE: Only a Deposit message should have an amount sent with it
     pub fn to_cosmos_msg(
         &self,
         mut block_size: usize,
@@ -175,6 +210,61 @@ impl HandleMsg {
         };
         Ok(execute.into())
     }
+
+    /// Returns the message with its memos, including those of the actions of batch messages,
+    /// normalized to `memo_len` bytes with [`normalize_memo`], so that their lengths don't tell
+    /// the transfers apart. Absent memos are left out.
+    pub fn with_normalized_memos(mut self, memo_len: usize) -> Self {
+        let normalize = |memo: &mut Option<String>| *memo = normalize_memo(memo.take(), memo_len);
+        match &mut self {
+            HandleMsg::Transfer { memo, .. }
+            | HandleMsg::Send { memo, .. }
+            | HandleMsg::Burn { memo, .. }
+            | HandleMsg::TransferFrom { memo, .. }
+            | HandleMsg::SendFrom { memo, .. }
+            | HandleMsg::BurnFrom { memo, .. }
+            | HandleMsg::Mint { memo, .. } => normalize(memo),
+            HandleMsg::BatchTransfer { actions, .. } => actions
+                .iter_mut()
+                .for_each(|action| normalize(&mut action.memo)),
+            HandleMsg::BatchSend { actions, .. } => actions
+                .iter_mut()
+                .for_each(|action| normalize(&mut action.memo)),
+            HandleMsg::BatchTransferFrom { actions, .. } => actions
+                .iter_mut()
+                .for_each(|action| normalize(&mut action.memo)),
+            HandleMsg::BatchSendFrom { actions, .. } => actions
+                .iter_mut()
+                .for_each(|action| normalize(&mut action.memo)),
+            HandleMsg::BatchBurnFrom { actions, .. } => actions
+                .iter_mut()
+                .for_each(|action| normalize(&mut action.memo)),
+            HandleMsg::BatchMint { actions, .. } => actions
+                .iter_mut()
+                .for_each(|action| normalize(&mut action.memo)),
+            _ => {}
+        }
+        self
+    }
+}
+
+/// Returns the memo truncated to `memo_len` bytes, on a character boundary, and padded with
+/// spaces to `memo_len` bytes, so that every memo has the same length in the transfer history.
+/// Returns `None` if there is no memo; pass an empty memo instead to hide that there is none.
+///
+/// # Arguments
+///
+/// * `memo` - the memo to normalize
+/// * `memo_len` - the length of the normalized memo, in bytes
+pub fn normalize_memo(memo: Option<String>, memo_len: usize) -> Option<String> {
+    let mut memo = memo?;
+    let mut len = memo.len().min(memo_len);
+    while !memo.is_char_boundary(len) {
+        len -= 1;
+    }
+    memo.truncate(len);
+    memo.push_str(&" ".repeat(memo_len - len));
+    Some(memo)
 }
 
 /// Returns a StdResult<CosmosMsg> used to execute Redeem
@@ -263,7 +353,7 @@ pub fn transfer_msg(
 /// * `recipient` - the address tokens are to be sent to
 /// * `amount` - Uint128 amount of tokens to send
 /// * `msg` - Optional base64 encoded string to pass to the recipient contract's
-///           Receive function
+///   Receive function
 /// * `memo` - A message to include in transaction
 /// * `padding` - Optional String used as padding if you don't want to use block padding
 /// * `block_size` - pad the message to blocks of this size
@@ -299,7 +389,7 @@ pub fn send_msg(
 /// * `recipient_code_hash` - override the `recipient_code_hash` registered with the `RegisterReceiver` interface
 /// * `amount` - Uint128 amount of tokens to send
 /// * `msg` - Optional base64 encoded string to pass to the recipient contract's
-///           Receive function
+///   Receive function
 /// * `memo` - A message to include in transaction
 /// * `padding` - Optional String used as padding if you don't want to use block padding
 /// * `block_size` - pad the message to blocks of this size
@@ -569,7 +659,7 @@ pub fn transfer_from_msg(
 /// * `recipient` - the address the tokens are to be sent to
 /// * `amount` - Uint128 amount of tokens to send
 /// * `msg` - Optional base64 encoded string to pass to the recipient contract's
-///           Receive function
+///   Receive function
 /// * `memo` - A message to include in transaction
 /// * `padding` - Optional String used as padding if you don't want to use block padding
 /// * `block_size` - pad the message to blocks of this size
@@ -608,7 +698,7 @@ pub fn send_from_msg(
 /// * `recipient_code_hash` - override the `recipient_code_hash` registered with the `RegisterReceiver` interface
 /// * `amount` - Uint128 amount of tokens to send
 /// * `msg` - Optional base64 encoded string to pass to the recipient contract's
-///           Receive function
+///   Receive function
 /// * `memo` - A message to include in transaction
 /// * `padding` - Optional String used as padding if you don't want to use block padding
 /// * `block_size` - pad the message to blocks of this size
@@ -860,3 +950,192 @@ pub fn set_minters_msg(
         None,
     )
 }
+
+/// Returns a StdResult<CosmosMsg> used to execute ChangeAdmin
+///
+/// # Arguments
+///
+/// * `address` - the address of the new admin
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the message to blocks of this size
+/// * `callback_code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn change_admin_msg(
+    address: String,
+    padding: Option<String>,
+    block_size: usize,
+    callback_code_hash: String,
+    contract_addr: String,
+) -> StdResult<CosmosMsg> {
+    HandleMsg::ChangeAdmin { address, padding }.to_cosmos_msg(
+        block_size,
+        callback_code_hash,
+        contract_addr,
+        None,
+    )
+}
+
+/// Returns a StdResult<CosmosMsg> used to execute SetContractStatus
+///
+/// # Arguments
+///
+/// * `level` - the new ContractStatusLevel of the contract
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the message to blocks of this size
+/// * `callback_code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn set_contract_status_msg(
+    level: ContractStatusLevel,
+    padding: Option<String>,
+    block_size: usize,
+    callback_code_hash: String,
+    contract_addr: String,
+) -> StdResult<CosmosMsg> {
+    HandleMsg::SetContractStatus { level, padding }.to_cosmos_msg(
+        block_size,
+        callback_code_hash,
+        contract_addr,
+        None,
+    )
+}
+
+/// Returns a StdResult<CosmosMsg> used to execute RevokePermit
+///
+/// # Arguments
+///
+/// * `permit_name` - the name of the permits of the calling contract to revoke
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the message to blocks of this size
+/// * `callback_code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn revoke_permit_msg(
+    permit_name: String,
+    padding: Option<String>,
+    block_size: usize,
+    callback_code_hash: String,
+    contract_addr: String,
+) -> StdResult<CosmosMsg> {
+    HandleMsg::RevokePermit {
+        permit_name,
+        padding,
+    }
+    .to_cosmos_msg(block_size, callback_code_hash, contract_addr, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::from_binary;
+
+    use super::*;
+
+    #[test]
+    fn test_admin_msgs() -> StdResult<()> {
+        let msg = set_contract_status_msg(
+            ContractStatusLevel::StopAllButRedeems,
+            None,
+            1,
+            "code hash".to_string(),
+            "token".to_string(),
+        )?;
+        let CosmosMsg::Wasm(WasmMsg::Execute { msg, funds, .. }) = msg else {
+            panic!("expected an execute message");
+        };
+        assert_eq!(
+            msg,
+            Binary::from(
+                br#"{"set_contract_status":{"level":"stop_all_but_redeems","padding":null}}"#
+            )
+        );
+        assert!(funds.is_empty());
+
+        let msg = revoke_permit_msg(
+            "balances".to_string(),
+            None,
+            256,
+            "code hash".to_string(),
+            "token".to_string(),
+        )?;
+        let CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) = msg else {
+            panic!("expected an execute message");
+        };
+        assert_eq!(msg.len(), 256);
+        assert!(msg.starts_with(br#"{"revoke_permit":{"permit_name":"balances""#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_answers() -> StdResult<()> {
+        let answer: HandleAnswer =
+            from_binary(&Binary::from(br#"{"change_admin":{"status":"success"}}"#))?;
+        assert_eq!(
+            answer,
+            HandleAnswer::ChangeAdmin {
+                status: ResponseStatus::Success
+            }
+        );
+        let answer: HandleAnswer =
+            from_binary(&Binary::from(br#"{"set_minters":{"status":"failure"}}"#))?;
+        assert_eq!(
+            answer,
+            HandleAnswer::SetMinters {
+                status: ResponseStatus::Failure
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_memo() {
+        assert_eq!(normalize_memo(None, 8), None);
+        assert_eq!(
+            normalize_memo(Some("rent".to_string()), 8).unwrap(),
+            "rent    "
+        );
+        assert_eq!(
+            normalize_memo(Some("invoice #42".to_string()), 8).unwrap(),
+            "invoice "
+        );
+        // "é" is 2 bytes long, so it doesn't fit in the last byte, which is padded instead
+        assert_eq!(
+            normalize_memo(Some("abcdefgé".to_string()), 8).unwrap(),
+            "abcdefg "
+        );
+        assert_eq!(normalize_memo(Some(String::new()), 2).unwrap(), "  ");
+    }
+
+    #[test]
+    fn test_with_normalized_memos() {
+        let transfer = HandleMsg::Transfer {
+            recipient: "alice".to_string(),
+            amount: Uint128::new(5),
+            memo: Some("rent".to_string()),
+            padding: None,
+        }
+        .with_normalized_memos(6);
+        let HandleMsg::Transfer { memo, .. } = transfer else {
+            panic!("expected a transfer");
+        };
+        assert_eq!(memo.unwrap(), "rent  ");
+
+        let batch = HandleMsg::BatchSend {
+            actions: vec![
+                SendAction::new("alice".to_string(), Uint128::new(1), None, None),
+                SendAction::new(
+                    "bob".to_string(),
+                    Uint128::new(2),
+                    None,
+                    Some("a long memo".to_string()),
+                ),
+            ],
+            padding: None,
+        }
+        .with_normalized_memos(6);
+        let HandleMsg::BatchSend { actions, .. } = batch else {
+            panic!("expected a batch send");
+        };
+        assert_eq!(actions[0].memo, None);
+        assert_eq!(actions[1].memo.as_deref(), Some("a long"));
+    }
+}
@@ -1,8 +1,18 @@
 #![doc = include_str!("../Readme.md")]
 
+pub mod allowance;
 pub mod batch;
+pub mod events;
+pub mod exchange;
 pub mod handle;
+#[cfg(feature = "memo-encryption")]
+pub mod memo;
 pub mod query;
+pub mod spender;
+#[cfg(any(test, all(feature = "test-utils", not(target_arch = "wasm32"))))]
+pub mod testing;
 
+pub use allowance::{ensure_allowance, ExpiryPolicy};
 pub use handle::*;
 pub use query::*;
+pub use spender::{AllowanceSpender, Reconciliation};
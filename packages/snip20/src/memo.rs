@@ -0,0 +1,194 @@
+use cosmwasm_std::{Binary, StdError, StdResult};
+
+use secret_toolkit_crypto::secp256k1::{derive_keypair, PrivateKey, PublicKey};
+use secret_toolkit_crypto::{hkdf_sha_256, sha_256};
+use secret_toolkit_notification::{cipher_data, decipher_data};
+
+/// the first byte of a memo encrypted with a shared seed
+const SEED_VERSION: u8 = 1;
+/// the first byte of a memo encrypted to a public key
+const PUBKEY_VERSION: u8 = 2;
+
+const MEMO_AAD: &[u8] = b"snip20-memo";
+const NONCE_LEN: usize = 12;
+const EPHEMERAL_PUBKEY_LEN: usize = 33;
+const LEN_PREFIX_LEN: usize = 2;
+
+/// The key a memo is encrypted to
+pub enum MemoKey<'a> {
+    /// a 32 byte seed shared with the recipient, such as their SNIP-52 notification seed
+    Seed(&'a Binary),
+    /// the recipient's secp256k1 public key
+    PubKey(&'a PublicKey),
+}
+
+/// Returns the memo encrypted to `key`, encoded as base64 to be used as the `memo` of a
+/// transfer.
+///
+/// The memo is truncated to `memo_size` bytes and padded to that size, so every encrypted
+/// memo of the same `memo_size` has the same length, regardless of its content.
+///
+/// # Arguments
+///
+/// * `memo` - the plaintext memo
+/// * `key` - the key the memo is encrypted to
+/// * `entropy` - random bytes that must be unique to each memo, e.g. `env.block.random` along
+///   with the index of the transfer in the transaction
+/// * `memo_size` - the number of plaintext bytes the encrypted memo holds
+pub fn encrypt_memo(
+    memo: &str,
+    key: &MemoKey,
+    entropy: &[u8],
+    memo_size: usize,
+) -> StdResult<String> {
+    let plaintext = pad_memo(memo, memo_size)?;
+    let nonce = &sha_256(&[entropy, b"nonce"].concat())[..NONCE_LEN];
+
+    let (header, cipher_key) = match key {
+        MemoKey::Seed(seed) => (vec![SEED_VERSION], seed.to_vec()),
+        MemoKey::PubKey(pubkey) => {
+            let (ephemeral, ephemeral_pubkey) = derive_keypair(entropy, MEMO_AAD);
+            let header = [
+                [PUBKEY_VERSION].as_slice(),
+                &ephemeral_pubkey.serialize_compressed(),
+            ]
+            .concat();
+            (header, shared_key(&ephemeral, pubkey)?)
+        }
+    };
+    let ciphertext = cipher_data(&cipher_key, nonce, &plaintext, MEMO_AAD)?;
+
+    Ok(Binary::from([header.as_slice(), nonce, &ciphertext].concat()).to_base64())
+}
+
+/// Returns the plaintext of a memo encrypted with [`encrypt_memo`] to [`MemoKey::Seed`]
+///
+/// # Arguments
+///
+/// * `memo` - the encrypted memo
+/// * `seed` - the seed shared with the sender
+pub fn decrypt_memo_with_seed(memo: &str, seed: &Binary) -> StdResult<String> {
+    let data = Binary::from_base64(memo)?;
+    match data.split_first() {
+        Some((&SEED_VERSION, rest)) => decrypt(seed, rest),
+        _ => Err(StdError::generic_err("memo was not encrypted with a seed")),
+    }
+}
+
+/// Returns the plaintext of a memo encrypted with [`encrypt_memo`] to [`MemoKey::PubKey`]
+///
+/// # Arguments
+///
+/// * `memo` - the encrypted memo
+/// * `private_key` - the private key of the public key the memo was encrypted to
+pub fn decrypt_memo_with_private_key(memo: &str, private_key: &PrivateKey) -> StdResult<String> {
+    let data = Binary::from_base64(memo)?;
+    match data.split_first() {
+        Some((&PUBKEY_VERSION, rest)) if rest.len() >= EPHEMERAL_PUBKEY_LEN => {
+            let (ephemeral_pubkey, rest) = rest.split_at(EPHEMERAL_PUBKEY_LEN);
+            let ephemeral_pubkey = PublicKey::parse(ephemeral_pubkey)?;
+            decrypt(&shared_key(private_key, &ephemeral_pubkey)?, rest)
+        }
+        _ => Err(StdError::generic_err(
+            "memo was not encrypted to a public key",
+        )),
+    }
+}
+
+fn shared_key(private_key: &PrivateKey, pubkey: &PublicKey) -> StdResult<Vec<u8>> {
+    hkdf_sha_256(&None, &private_key.shared_secret(pubkey), MEMO_AAD, 32)
+}
+
+fn decrypt(key: &[u8], data: &[u8]) -> StdResult<String> {
+    if data.len() < NONCE_LEN {
+        return Err(StdError::generic_err("encrypted memo is too short"));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = decipher_data(key, nonce, ciphertext, MEMO_AAD)?;
+    unpad_memo(&plaintext)
+}
+
+/// Truncates the memo to `memo_size` bytes, on a character boundary, and pads it to
+/// `memo_size` bytes after a length prefix
+fn pad_memo(memo: &str, memo_size: usize) -> StdResult<Vec<u8>> {
+    if memo_size > u16::MAX as usize {
+        return Err(StdError::generic_err(format!(
+            "memo size can not be larger than {}",
+            u16::MAX
+        )));
+    }
+    let mut len = memo.len().min(memo_size);
+    while !memo.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    let mut padded = Vec::with_capacity(LEN_PREFIX_LEN + memo_size);
+    padded.extend_from_slice(&(len as u16).to_be_bytes());
+    padded.extend_from_slice(&memo.as_bytes()[..len]);
+    padded.resize(LEN_PREFIX_LEN + memo_size, 0);
+    Ok(padded)
+}
+
+fn unpad_memo(padded: &[u8]) -> StdResult<String> {
+    let invalid = || StdError::generic_err("invalid memo padding");
+    if padded.len() < LEN_PREFIX_LEN {
+        return Err(invalid());
+    }
+    let (len, memo) = padded.split_at(LEN_PREFIX_LEN);
+    let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+    let memo = memo.get(..len).ok_or_else(invalid)?;
+    String::from_utf8(memo.to_vec()).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secret_toolkit_crypto::secp256k1::PRIVATE_KEY_SIZE;
+
+    #[test]
+    fn test_seed_memo() -> StdResult<()> {
+        let seed = Binary::from([3; 32]);
+        let memo = encrypt_memo("rent for june", &MemoKey::Seed(&seed), b"entropy", 32)?;
+
+        assert_eq!(decrypt_memo_with_seed(&memo, &seed)?, "rent for june");
+        assert!(decrypt_memo_with_seed(&memo, &Binary::from([4; 32])).is_err());
+
+        // memos of the same size have the same length
+        let other = encrypt_memo("hi", &MemoKey::Seed(&seed), b"other entropy", 32)?;
+        assert_eq!(memo.len(), other.len());
+        assert_ne!(memo, other);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pubkey_memo() -> StdResult<()> {
+        let recipient = PrivateKey::parse(&[5; PRIVATE_KEY_SIZE])?;
+        let memo = encrypt_memo(
+            "invoice #42",
+            &MemoKey::PubKey(&recipient.pubkey()),
+            b"entropy",
+            32,
+        )?;
+
+        assert_eq!(
+            decrypt_memo_with_private_key(&memo, &recipient)?,
+            "invoice #42"
+        );
+        let other = PrivateKey::parse(&[6; PRIVATE_KEY_SIZE])?;
+        assert!(decrypt_memo_with_private_key(&memo, &other).is_err());
+        assert!(decrypt_memo_with_seed(&memo, &Binary::from([5; 32])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memo_truncation() -> StdResult<()> {
+        let seed = Binary::from([3; 32]);
+        // "é" is 2 bytes long, so it doesn't fit in the remaining byte
+        let memo = encrypt_memo("abcé", &MemoKey::Seed(&seed), b"entropy", 4)?;
+        assert_eq!(decrypt_memo_with_seed(&memo, &seed)?, "abc");
+
+        Ok(())
+    }
+}
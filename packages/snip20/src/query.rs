@@ -7,7 +7,7 @@ use cosmwasm_std::{
     WasmQuery,
 };
 
-use secret_toolkit_utils::space_pad;
+use secret_toolkit_utils::{space_pad, QueryError};
 
 /// TokenInfo response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -30,7 +30,8 @@ pub struct TokenConfig {
 }
 
 /// Contract status
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
 pub enum ContractStatusLevel {
     NormalRun,
     StopAllButRedeems,
@@ -136,7 +137,7 @@ pub struct Minters {
 }
 
 /// SNIP20 queries
-#[derive(Serialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     TokenInfo {},
@@ -195,23 +196,94 @@ impl QueryMsg {
     pub fn query<C: CustomQuery, T: DeserializeOwned>(
         &self,
         querier: QuerierWrapper<C>,
-        mut block_size: usize,
+        block_size: usize,
         code_hash: String,
         contract_addr: String,
     ) -> StdResult<T> {
+        self.try_query(querier, block_size, code_hash, contract_addr)
+            .map_err(StdError::from)
+    }
+
+    /// Returns a Result<T, QueryError>, where T is the "Response" type that wraps the query
+    /// answer. Unlike `query`, the error keeps the error returned by the querier as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `querier` - a reference to the Querier dependency of the querying contract
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `code_hash` - String holding the code hash of the contract being queried
+    /// * `contract_addr` - address of the contract being queried
+    pub fn try_query<C: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: QuerierWrapper<C>,
+        mut block_size: usize,
+        code_hash: String,
+        contract_addr: String,
+    ) -> Result<T, QueryError> {
         // can not have block size of 0
         if block_size == 0 {
             block_size = 1;
         }
-        let mut msg = to_binary(self)?;
+        let query_error = |error| QueryError::new(self.to_string(), &contract_addr, error);
+        let mut msg = to_binary(self).map_err(query_error)?;
         space_pad(&mut msg.0, block_size);
         querier
             .query(&QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr,
+                contract_addr: contract_addr.clone(),
                 code_hash,
                 msg,
             }))
-            .map_err(|err| StdError::generic_err(format!("Error performing {self} query: {err}")))
+            .map_err(query_error)
+    }
+}
+
+/// Errors returned by the queries that require a viewing key
+#[derive(Debug)]
+pub enum Snip20QueryError {
+    /// the token rejected the viewing key with a ViewingKeyError response
+    Unauthorized {
+        msg: String,
+    },
+    /// the token answered with a response that does not match the query
+    InvalidResponse {
+        query: String,
+    },
+    /// the query could not be performed
+    Query(QueryError),
+    Std(StdError),
+}
+
+impl fmt::Display for Snip20QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Snip20QueryError::Unauthorized { msg } => write!(f, "unauthorized: {msg}"),
+            Snip20QueryError::InvalidResponse { query } => {
+                write!(f, "Invalid {query} query response")
+            }
+            Snip20QueryError::Query(err) => write!(f, "{err}"),
+            Snip20QueryError::Std(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<StdError> for Snip20QueryError {
+    fn from(err: StdError) -> Self {
+        Snip20QueryError::Std(err)
+    }
+}
+
+impl From<QueryError> for Snip20QueryError {
+    fn from(err: QueryError) -> Self {
+        Snip20QueryError::Query(err)
+    }
+}
+
+impl From<Snip20QueryError> for StdError {
+    fn from(err: Snip20QueryError) -> Self {
+        match err {
+            Snip20QueryError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
     }
 }
 
@@ -351,7 +423,7 @@ pub fn exchange_rate_query<C: CustomQuery>(
     Ok(answer.exchange_rate)
 }
 
-/// Returns a StdResult<Allowance> from performing Allowance query
+/// Returns a Result<Allowance, Snip20QueryError> from performing Allowance query
 ///
 /// # Arguments
 ///
@@ -371,13 +443,13 @@ pub fn allowance_query<C: CustomQuery>(
     block_size: usize,
     callback_code_hash: String,
     contract_addr: String,
-) -> StdResult<Allowance> {
+) -> Result<Allowance, Snip20QueryError> {
     let answer: AuthenticatedQueryResponse = QueryMsg::Allowance {
         owner,
         spender,
         key,
     }
-    .query(querier, block_size, callback_code_hash, contract_addr)?;
+    .try_query(querier, block_size, callback_code_hash, contract_addr)?;
     match answer {
         AuthenticatedQueryResponse::Allowance {
             spender,
@@ -390,14 +462,16 @@ pub fn allowance_query<C: CustomQuery>(
             allowance,
             expiration,
         }),
-        AuthenticatedQueryResponse::ViewingKeyError { .. } => {
-            Err(StdError::generic_err("unaithorized"))
+        AuthenticatedQueryResponse::ViewingKeyError { msg } => {
+            Err(Snip20QueryError::Unauthorized { msg })
         }
-        _ => Err(StdError::generic_err("Invalid Allowance query response")),
+        _ => Err(Snip20QueryError::InvalidResponse {
+            query: "Allowance".to_string(),
+        }),
     }
 }
 
-/// Returns a StdResult<Balance> from performing Balance query
+/// Returns a Result<Balance, Snip20QueryError> from performing Balance query
 ///
 /// # Arguments
 ///
@@ -414,8 +488,8 @@ pub fn balance_query<C: CustomQuery>(
     block_size: usize,
     callback_code_hash: String,
     contract_addr: String,
-) -> StdResult<Balance> {
-    let answer: AuthenticatedQueryResponse = QueryMsg::Balance { address, key }.query(
+) -> Result<Balance, Snip20QueryError> {
+    let answer: AuthenticatedQueryResponse = QueryMsg::Balance { address, key }.try_query(
         querier,
         block_size,
         callback_code_hash,
@@ -423,14 +497,16 @@ pub fn balance_query<C: CustomQuery>(
     )?;
     match answer {
         AuthenticatedQueryResponse::Balance { amount } => Ok(Balance { amount }),
-        AuthenticatedQueryResponse::ViewingKeyError { .. } => {
-            Err(StdError::generic_err("unaithorized"))
+        AuthenticatedQueryResponse::ViewingKeyError { msg } => {
+            Err(Snip20QueryError::Unauthorized { msg })
         }
-        _ => Err(StdError::generic_err("Invalid Balance query response")),
+        _ => Err(Snip20QueryError::InvalidResponse {
+            query: "Balance".to_string(),
+        }),
     }
 }
 
-/// Returns a StdResult<TransferHistory> from performing TransferHistory query
+/// Returns a Result<TransferHistory, Snip20QueryError> from performing TransferHistory query
 ///
 /// # Arguments
 ///
@@ -452,28 +528,28 @@ pub fn transfer_history_query<C: CustomQuery>(
     block_size: usize,
     callback_code_hash: String,
     contract_addr: String,
-) -> StdResult<TransferHistory> {
+) -> Result<TransferHistory, Snip20QueryError> {
     let answer: AuthenticatedQueryResponse = QueryMsg::TransferHistory {
         address,
         key,
         page,
         page_size,
     }
-    .query(querier, block_size, callback_code_hash, contract_addr)?;
+    .try_query(querier, block_size, callback_code_hash, contract_addr)?;
     match answer {
         AuthenticatedQueryResponse::TransferHistory { txs, total } => {
             Ok(TransferHistory { txs, total })
         }
-        AuthenticatedQueryResponse::ViewingKeyError { .. } => {
-            Err(StdError::generic_err("unaithorized"))
+        AuthenticatedQueryResponse::ViewingKeyError { msg } => {
+            Err(Snip20QueryError::Unauthorized { msg })
         }
-        _ => Err(StdError::generic_err(
-            "Invalid TransferHistory query response",
-        )),
+        _ => Err(Snip20QueryError::InvalidResponse {
+            query: "TransferHistory".to_string(),
+        }),
     }
 }
 
-/// Returns a StdResult<TransactionHistory> from performing TransactionHistory query
+/// Returns a Result<TransactionHistory, Snip20QueryError> from performing TransactionHistory query
 ///
 /// # Arguments
 ///
@@ -495,24 +571,24 @@ pub fn transaction_history_query<C: CustomQuery>(
     block_size: usize,
     callback_code_hash: String,
     contract_addr: String,
-) -> StdResult<TransactionHistory> {
+) -> Result<TransactionHistory, Snip20QueryError> {
     let answer: AuthenticatedQueryResponse = QueryMsg::TransactionHistory {
         address,
         key,
         page,
         page_size,
     }
-    .query(querier, block_size, callback_code_hash, contract_addr)?;
+    .try_query(querier, block_size, callback_code_hash, contract_addr)?;
     match answer {
         AuthenticatedQueryResponse::TransactionHistory { txs, total } => {
             Ok(TransactionHistory { txs, total })
         }
-        AuthenticatedQueryResponse::ViewingKeyError { .. } => {
-            Err(StdError::generic_err("unaithorized"))
+        AuthenticatedQueryResponse::ViewingKeyError { msg } => {
+            Err(Snip20QueryError::Unauthorized { msg })
         }
-        _ => Err(StdError::generic_err(
-            "Invalid TransactionHistory query response",
-        )),
+        _ => Err(Snip20QueryError::InvalidResponse {
+            query: "TransactionHistory".to_string(),
+        }),
     }
 }
 
@@ -534,3 +610,95 @@ pub fn minters_query<C: CustomQuery>(
         QueryMsg::Minters {}.query(querier, block_size, callback_code_hash, contract_addr)?;
     Ok(answer.minters)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{Binary, ContractResult, Empty, Querier, QuerierResult, SystemResult};
+
+    struct MyMockQuerier {
+        response: Binary,
+    }
+
+    impl Querier for MyMockQuerier {
+        fn raw_query(&self, _request: &[u8]) -> QuerierResult {
+            SystemResult::Ok(ContractResult::Ok(self.response.clone()))
+        }
+    }
+
+    fn balance(response: &[u8]) -> Result<Balance, Snip20QueryError> {
+        let querier = MyMockQuerier {
+            response: Binary::from(response),
+        };
+        balance_query(
+            QuerierWrapper::<Empty>::new(&querier),
+            "alice".to_string(),
+            "key".to_string(),
+            256,
+            "code hash".to_string(),
+            "contract".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_authenticated_query_errors() {
+        let response = balance(br#"{"balance":{"amount":"5"}}"#).unwrap();
+        assert_eq!(response.amount, Uint128::new(5));
+
+        let err = balance(br#"{"viewing_key_error":{"msg":"Wrong viewing key"}}"#).unwrap_err();
+        assert!(
+            matches!(&err, Snip20QueryError::Unauthorized { msg } if msg == "Wrong viewing key")
+        );
+        assert_eq!(
+            StdError::from(err),
+            StdError::generic_err("unauthorized: Wrong viewing key")
+        );
+
+        let err = balance(br#"{"transfer_history":{"txs":[],"total":0}}"#).unwrap_err();
+        assert!(matches!(&err, Snip20QueryError::InvalidResponse { query } if query == "Balance"));
+
+        let err = balance(b"not json").unwrap_err();
+        assert!(matches!(err, Snip20QueryError::Query(_)));
+    }
+
+    struct ErrorQuerier;
+
+    impl Querier for ErrorQuerier {
+        fn raw_query(&self, _request: &[u8]) -> QuerierResult {
+            SystemResult::Ok(ContractResult::Err("viewing key not set".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_query_error_keeps_contract_error() {
+        let querier = ErrorQuerier;
+        let err = balance_query(
+            QuerierWrapper::<Empty>::new(&querier),
+            "alice".to_string(),
+            "key".to_string(),
+            256,
+            "code hash".to_string(),
+            "contract".to_string(),
+        )
+        .unwrap_err();
+        let Snip20QueryError::Query(query_err) = err else {
+            panic!("unexpected error {err}");
+        };
+        assert_eq!(query_err.query, "Balance");
+        assert_eq!(query_err.contract_addr, "contract");
+        assert!(query_err.error.to_string().contains("viewing key not set"));
+
+        let err = token_info_query(
+            QuerierWrapper::<Empty>::new(&querier),
+            256,
+            "code hash".to_string(),
+            "contract".to_string(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Generic error: Error performing TokenInfo query on contract: Generic error: \
+             Querier contract error: viewing key not set"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    Binary, CosmosMsg, CustomQuery, QuerierWrapper, StdError, StdResult, Storage, Uint128,
+};
+
+use secret_toolkit_storage::Keymap;
+use secret_toolkit_utils::types::Contract;
+
+use crate::handle::{send_from_msg_with_code_hash, transfer_from_msg};
+use crate::query::balance_query;
+
+const PREFIX_OWNERS: &[u8] = b"owners";
+const PREFIX_RECIPIENTS: &[u8] = b"recipients";
+
+/// Helper for contracts that pull payments from users using their SNIP-20 allowances.
+///
+/// Every `TransferFrom`/`SendFrom` message built through this helper is recorded, both per owner
+/// the funds were pulled from and per recipient they were sent to, so that the recorded totals
+/// can later be reconciled against the actual token balances.
+pub struct AllowanceSpender<'a> {
+    namespace: &'a [u8],
+}
+
+/// The result of reconciling the recorded amount received by an address with its actual balance
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct Reconciliation {
+    /// the amount recorded as received (minus recorded outflows)
+    pub expected: Uint128,
+    /// the balance reported by the token contract
+    pub actual: Uint128,
+}
+
+impl Reconciliation {
+    /// Returns true if the actual balance covers the expected amount
+    pub fn is_balanced(&self) -> bool {
+        self.actual >= self.expected
+    }
+
+    /// Returns how much is missing from the actual balance
+    pub fn shortfall(&self) -> Uint128 {
+        self.expected.saturating_sub(self.actual)
+    }
+}
+
+impl<'a> AllowanceSpender<'a> {
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self { namespace }
+    }
+
+    fn owners(&self, token: &Contract) -> Keymap<'a, String, Uint128> {
+        Keymap::new(self.namespace)
+            .add_suffix(PREFIX_OWNERS)
+            .add_suffix(token.address.as_bytes())
+    }
+
+    fn recipients(&self, token: &Contract) -> Keymap<'a, String, Uint128> {
+        Keymap::new(self.namespace)
+            .add_suffix(PREFIX_RECIPIENTS)
+            .add_suffix(token.address.as_bytes())
+    }
+
+    fn record(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &str,
+        recipient: &str,
+        amount: Uint128,
+        token: &Contract,
+    ) -> StdResult<()> {
+        let owners = self.owners(token);
+        let pulled = owners.get(storage, &owner.to_string()).unwrap_or_default();
+        owners.insert(storage, &owner.to_string(), &pulled.checked_add(amount)?)?;
+
+        let recipients = self.recipients(token);
+        let received = recipients
+            .get(storage, &recipient.to_string())
+            .unwrap_or_default();
+        recipients.insert(
+            storage,
+            &recipient.to_string(),
+            &received.checked_add(amount)?,
+        )
+    }
+
+    /// Returns a StdResult<CosmosMsg> used to execute TransferFrom, and records the spent amount
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - a mutable reference to the storage the accounting is kept in
+    /// * `owner` - the address of the owner of the tokens to be spent
+    /// * `amount` - Uint128 amount of tokens to spend
+    /// * `recipient` - the address the tokens are to be sent to
+    /// * `memo` - A message to include in transaction
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `token` - the token contract being called
+    #[allow(clippy::too_many_arguments)]
+    pub fn spend_from(
+        &self,
+        storage: &mut dyn Storage,
+        owner: String,
+        amount: Uint128,
+        recipient: String,
+        memo: Option<String>,
+        block_size: usize,
+        token: &Contract,
+    ) -> StdResult<CosmosMsg> {
+        self.record(storage, &owner, &recipient, amount, token)?;
+        transfer_from_msg(
+            owner,
+            recipient,
+            amount,
+            memo,
+            None,
+            block_size,
+            token.hash.clone(),
+            token.address.clone(),
+        )
+    }
+
+    /// Returns a StdResult<CosmosMsg> used to execute SendFrom, and records the spent amount
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - a mutable reference to the storage the accounting is kept in
+    /// * `owner` - the address of the owner of the tokens to be spent
+    /// * `amount` - Uint128 amount of tokens to spend
+    /// * `recipient` - the contract the tokens are to be sent to
+    /// * `msg` - Optional base64 encoded string to pass to the recipient contract's
+    ///   Receive function
+    /// * `memo` - A message to include in transaction
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `token` - the token contract being called
+    #[allow(clippy::too_many_arguments)]
+    pub fn send_from(
+        &self,
+        storage: &mut dyn Storage,
+        owner: String,
+        amount: Uint128,
+        recipient: &Contract,
+        msg: Option<Binary>,
+        memo: Option<String>,
+        block_size: usize,
+        token: &Contract,
+    ) -> StdResult<CosmosMsg> {
+        self.record(storage, &owner, &recipient.address, amount, token)?;
+        send_from_msg_with_code_hash(
+            owner,
+            recipient.address.clone(),
+            Some(recipient.hash.clone()),
+            amount,
+            msg,
+            memo,
+            None,
+            block_size,
+            token.hash.clone(),
+            token.address.clone(),
+        )
+    }
+
+    /// Records tokens that left a recipient through other means (e.g. payouts), so they are not
+    /// reported as missing by `reconcile`
+    pub fn record_outflow(
+        &self,
+        storage: &mut dyn Storage,
+        recipient: &str,
+        amount: Uint128,
+        token: &Contract,
+    ) -> StdResult<()> {
+        let recipients = self.recipients(token);
+        let received = recipients
+            .get(storage, &recipient.to_string())
+            .unwrap_or_default();
+        let remaining = received.checked_sub(amount).map_err(|_| {
+            StdError::generic_err(format!(
+                "outflow of {amount} exceeds the {received} recorded for {recipient}"
+            ))
+        })?;
+        recipients.insert(storage, &recipient.to_string(), &remaining)
+    }
+
+    /// Returns the total amount spent from an owner's allowance
+    pub fn spent_from(&self, storage: &dyn Storage, owner: &str, token: &Contract) -> Uint128 {
+        self.owners(token)
+            .get(storage, &owner.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Returns the total amount recorded as received by a recipient
+    pub fn received_by(&self, storage: &dyn Storage, recipient: &str, token: &Contract) -> Uint128 {
+        self.recipients(token)
+            .get(storage, &recipient.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Queries the token balance of a recipient and compares it with the recorded amount
+    ///
+    /// # Arguments
+    ///
+    /// * `querier` - a reference to the Querier dependency of the querying contract
+    /// * `storage` - a reference to the storage the accounting is kept in
+    /// * `recipient` - the address whose balance should be reconciled
+    /// * `key` - String holding the authentication key needed to view the balance
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `token` - the token contract being queried
+    pub fn reconcile<C: CustomQuery>(
+        &self,
+        querier: QuerierWrapper<C>,
+        storage: &dyn Storage,
+        recipient: String,
+        key: String,
+        block_size: usize,
+        token: &Contract,
+    ) -> StdResult<Reconciliation> {
+        let expected = self.received_by(storage, &recipient, token);
+        let actual = balance_query(
+            querier,
+            recipient,
+            key,
+            block_size,
+            token.hash.clone(),
+            token.address.clone(),
+        )?
+        .amount;
+
+        Ok(Reconciliation { expected, actual })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    fn token() -> Contract {
+        Contract {
+            address: "token".to_string(),
+            hash: "token_hash".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_spend_from_accounting() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let spender = AllowanceSpender::new(b"spender");
+        let token = token();
+
+        spender.spend_from(
+            &mut storage,
+            "alice".to_string(),
+            Uint128::new(10),
+            "contract".to_string(),
+            None,
+            256,
+            &token,
+        )?;
+        spender.spend_from(
+            &mut storage,
+            "alice".to_string(),
+            Uint128::new(5),
+            "contract".to_string(),
+            None,
+            256,
+            &token,
+        )?;
+        spender.spend_from(
+            &mut storage,
+            "bob".to_string(),
+            Uint128::new(7),
+            "contract".to_string(),
+            None,
+            256,
+            &token,
+        )?;
+
+        assert_eq!(
+            spender.spent_from(&storage, "alice", &token),
+            Uint128::new(15)
+        );
+        assert_eq!(spender.spent_from(&storage, "bob", &token), Uint128::new(7));
+        assert_eq!(
+            spender.received_by(&storage, "contract", &token),
+            Uint128::new(22)
+        );
+
+        spender.record_outflow(&mut storage, "contract", Uint128::new(20), &token)?;
+        assert_eq!(
+            spender.received_by(&storage, "contract", &token),
+            Uint128::new(2)
+        );
+        assert!(spender
+            .record_outflow(&mut storage, "contract", Uint128::new(3), &token)
+            .is_err());
+
+        let other = Contract {
+            address: "other".to_string(),
+            hash: "other_hash".to_string(),
+        };
+        assert_eq!(
+            spender.spent_from(&storage, "alice", &other),
+            Uint128::zero()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconciliation() {
+        let reconciliation = Reconciliation {
+            expected: Uint128::new(10),
+            actual: Uint128::new(8),
+        };
+        assert!(!reconciliation.is_balanced());
+        assert_eq!(reconciliation.shortfall(), Uint128::new(2));
+
+        let reconciliation = Reconciliation {
+            expected: Uint128::new(10),
+            actual: Uint128::new(12),
+        };
+        assert!(reconciliation.is_balanced());
+        assert_eq!(reconciliation.shortfall(), Uint128::zero());
+    }
+}
@@ -0,0 +1,673 @@
+//! A minimal in-memory SNIP-20 token, to run integration tests of contracts that depend on
+//! SNIP-20 transfers and receives without deploying the real token code.
+//!
+//! `instantiate`, `execute` and `query` have the signatures of contract entry points, so they
+//! can be wrapped in the `ContractWrapper` of multi-test frameworks, or called directly in unit
+//! tests. The token supports transfers, sends (including the `Receive` callback), burns, mints,
+//! viewing keys and allowances, along with the `TokenInfo`, `Balance`, `Allowance` and
+//! `Minters` queries.
+//!
+//! [`MockFailures`] simulates a token that rejects viewing keys or allowances, to test how a
+//! contract handles these failures.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+    Uint128, WasmMsg,
+};
+
+use secret_toolkit_serialization::Bincode2;
+use secret_toolkit_storage::{Item, Keymap, KeymapBuilder, WithoutIter};
+use secret_toolkit_utils::router::variant_name;
+
+use crate::{Allowance, Balance, HandleMsg, Minters, QueryMsg, TokenInfo};
+
+static CONFIG: Item<MockConfig> = Item::new(b"mock_snip20_config");
+static FAILURES: Item<MockFailures> = Item::new(b"mock_snip20_failures");
+// The keymaps don't support iteration, so they don't cache a length that would be shared by the
+// mock tokens of different tests.
+static BALANCES: Keymap<String, Uint128, Bincode2, WithoutIter> =
+    KeymapBuilder::new(b"mock_snip20_balances")
+        .without_iter()
+        .build();
+static VIEWING_KEYS: Keymap<String, String, Bincode2, WithoutIter> =
+    KeymapBuilder::new(b"mock_snip20_viewing_keys")
+        .without_iter()
+        .build();
+static ALLOWANCES: Keymap<(String, String), MockAllowance, Bincode2, WithoutIter> =
+    KeymapBuilder::new(b"mock_snip20_allowances")
+        .without_iter()
+        .build();
+static RECEIVERS: Keymap<String, String, Bincode2, WithoutIter> =
+    KeymapBuilder::new(b"mock_snip20_receivers")
+        .without_iter()
+        .build();
+
+/// An initial balance of the mock token
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct InitialBalance {
+    pub address: String,
+    pub amount: Uint128,
+}
+
+/// Failures the mock token simulates
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MockFailures {
+    /// answer every query that requires a viewing key with a `ViewingKeyError`
+    pub reject_viewing_keys: bool,
+    /// fail every `TransferFrom`, `SendFrom` and `BurnFrom` as if the allowance was too low
+    pub reject_allowances: bool,
+}
+
+/// Instantiation message of the mock token
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct MockSnip20InitMsg {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub initial_balances: Vec<InitialBalance>,
+    /// the addresses allowed to mint
+    pub minters: Vec<String>,
+    #[serde(default)]
+    pub failures: MockFailures,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockConfig {
+    name: String,
+    symbol: String,
+    decimals: u8,
+    total_supply: Uint128,
+    minters: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockAllowance {
+    amount: Uint128,
+    expiration: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverHandleMsg {
+    Receive {
+        sender: String,
+        from: String,
+        amount: Uint128,
+        memo: Option<String>,
+        msg: Option<Binary>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum HandleAnswer {
+    CreateViewingKey { key: String },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryAnswer {
+    TokenInfo(TokenInfo),
+    Balance(Balance),
+    Allowance(Allowance),
+    Minters(Minters),
+    ViewingKeyError { msg: String },
+}
+
+/// Instantiates the mock token
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MockSnip20InitMsg,
+) -> StdResult<Response> {
+    let mut total_supply = Uint128::zero();
+    for balance in msg.initial_balances {
+        add_balance(deps.storage, &balance.address, balance.amount)?;
+        total_supply = total_supply.checked_add(balance.amount)?;
+    }
+    CONFIG.save(
+        deps.storage,
+        &MockConfig {
+            name: msg.name,
+            symbol: msg.symbol,
+            decimals: msg.decimals,
+            total_supply,
+            minters: msg.minters,
+        },
+    )?;
+    set_failures(deps.storage, &msg.failures)?;
+
+    Ok(Response::new())
+}
+
+/// Sets the failures the mock token simulates
+pub fn set_failures(storage: &mut dyn Storage, failures: &MockFailures) -> StdResult<()> {
+    FAILURES.save(storage, failures)
+}
+
+/// Handles the SNIP-20 messages supported by the mock token
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: HandleMsg) -> StdResult<Response> {
+    let sender = info.sender.to_string();
+    let mut response = Response::new().set_data(status_answer(&msg)?);
+    match msg {
+        HandleMsg::Transfer {
+            recipient, amount, ..
+        } => transfer(deps.storage, &sender, &recipient, amount)?,
+        HandleMsg::Send {
+            recipient,
+            recipient_code_hash,
+            amount,
+            msg,
+            memo,
+            ..
+        } => {
+            transfer(deps.storage, &sender, &recipient, amount)?;
+            let receive = receive_msg(
+                deps.storage,
+                &sender,
+                &sender,
+                recipient,
+                recipient_code_hash,
+                amount,
+                memo,
+                msg,
+            )?;
+            response = response.add_messages(receive);
+        }
+        HandleMsg::Burn { amount, .. } => burn(deps.storage, &sender, amount)?,
+        HandleMsg::RegisterReceive { code_hash, .. } => {
+            RECEIVERS.insert(deps.storage, &sender, &code_hash)?
+        }
+        HandleMsg::CreateViewingKey { entropy, .. } => {
+            let key = Binary::from(format!("{sender}:{entropy}:{}", env.block.height).as_bytes())
+                .to_base64();
+            VIEWING_KEYS.insert(deps.storage, &sender, &key)?;
+            response = response.set_data(to_binary(&HandleAnswer::CreateViewingKey { key })?);
+        }
+        HandleMsg::SetViewingKey { key, .. } => VIEWING_KEYS.insert(deps.storage, &sender, &key)?,
+        HandleMsg::IncreaseAllowance {
+            spender,
+            amount,
+            expiration,
+            ..
+        } => {
+            let key = (sender, spender);
+            let current = load_allowance(deps.storage, &key, &env);
+            let allowance = MockAllowance {
+                amount: current.saturating_add(amount),
+                expiration,
+            };
+            ALLOWANCES.insert(deps.storage, &key, &allowance)?;
+        }
+        HandleMsg::DecreaseAllowance {
+            spender,
+            amount,
+            expiration,
+            ..
+        } => {
+            let key = (sender, spender);
+            let current = load_allowance(deps.storage, &key, &env);
+            let allowance = MockAllowance {
+                amount: current.saturating_sub(amount),
+                expiration,
+            };
+            ALLOWANCES.insert(deps.storage, &key, &allowance)?;
+        }
+        HandleMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+            ..
+        } => {
+            spend_allowance(deps.storage, &env, &owner, &sender, amount)?;
+            transfer(deps.storage, &owner, &recipient, amount)?;
+        }
+        HandleMsg::SendFrom {
+            owner,
+            recipient,
+            recipient_code_hash,
+            amount,
+            msg,
+            memo,
+            ..
+        } => {
+            spend_allowance(deps.storage, &env, &owner, &sender, amount)?;
+            transfer(deps.storage, &owner, &recipient, amount)?;
+            let receive = receive_msg(
+                deps.storage,
+                &sender,
+                &owner,
+                recipient,
+                recipient_code_hash,
+                amount,
+                memo,
+                msg,
+            )?;
+            response = response.add_messages(receive);
+        }
+        HandleMsg::BurnFrom { owner, amount, .. } => {
+            spend_allowance(deps.storage, &env, &owner, &sender, amount)?;
+            burn(deps.storage, &owner, amount)?;
+        }
+        HandleMsg::Mint {
+            recipient, amount, ..
+        } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            if !config.minters.contains(&sender) {
+                return Err(StdError::generic_err(
+                    "Minting is allowed to minter accounts only",
+                ));
+            }
+            config.total_supply = config.total_supply.checked_add(amount)?;
+            CONFIG.save(deps.storage, &config)?;
+            add_balance(deps.storage, &recipient, amount)?;
+        }
+        msg => {
+            return Err(StdError::generic_err(format!(
+                "mock snip20: unsupported message '{}'",
+                variant_name(&msg)?
+            )))
+        }
+    }
+
+    Ok(response)
+}
+
+/// Answers the SNIP-20 queries supported by the mock token
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let answer = match msg {
+        QueryMsg::TokenInfo {} => {
+            let config = CONFIG.load(deps.storage)?;
+            QueryAnswer::TokenInfo(TokenInfo {
+                name: config.name,
+                symbol: config.symbol,
+                decimals: config.decimals,
+                total_supply: Some(config.total_supply),
+            })
+        }
+        QueryMsg::Minters {} => QueryAnswer::Minters(Minters {
+            minters: CONFIG.load(deps.storage)?.minters,
+        }),
+        QueryMsg::Balance { address, key } => match check_viewing_key(deps.storage, &address, &key)
+        {
+            Some(error) => error,
+            None => QueryAnswer::Balance(Balance {
+                amount: balance(deps.storage, &address),
+            }),
+        },
+        QueryMsg::Allowance {
+            owner,
+            spender,
+            key,
+        } => {
+            // either the owner's or the spender's viewing key is accepted
+            let error = check_viewing_key(deps.storage, &owner, &key).and(check_viewing_key(
+                deps.storage,
+                &spender,
+                &key,
+            ));
+            match error {
+                Some(error) => error,
+                None => {
+                    let key = (owner, spender);
+                    let expiration = ALLOWANCES
+                        .get(deps.storage, &key)
+                        .and_then(|allowance| allowance.expiration);
+                    let allowance = load_allowance(deps.storage, &key, &env);
+                    let (owner, spender) = key;
+                    QueryAnswer::Allowance(Allowance {
+                        spender,
+                        owner,
+                        allowance,
+                        expiration,
+                    })
+                }
+            }
+        }
+        msg => {
+            return Err(StdError::generic_err(format!(
+                "mock snip20: unsupported query '{}'",
+                variant_name(&msg)?
+            )))
+        }
+    };
+
+    to_binary(&answer)
+}
+
+/// Returns the balance of an address, without requiring a viewing key
+pub fn balance(storage: &dyn Storage, address: &str) -> Uint128 {
+    BALANCES
+        .get(storage, &address.to_string())
+        .unwrap_or_default()
+}
+
+/// Returns the `{"<msg>":{"status":"success"}}` answer of a handle message
+fn status_answer(msg: &HandleMsg) -> StdResult<Binary> {
+    let answer = format!(r#"{{"{}":{{"status":"success"}}}}"#, variant_name(msg)?);
+    Ok(Binary::from(answer.as_bytes()))
+}
+
+/// Returns a `ViewingKeyError` answer if the key is not the viewing key of the address
+fn check_viewing_key(storage: &dyn Storage, address: &str, key: &str) -> Option<QueryAnswer> {
+    let rejected = FAILURES
+        .may_load(storage)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+        .reject_viewing_keys;
+    let valid = VIEWING_KEYS.get(storage, &address.to_string()).as_deref() == Some(key);
+    if rejected || !valid {
+        return Some(QueryAnswer::ViewingKeyError {
+            msg: "Wrong viewing key for this address or viewing key not set".to_string(),
+        });
+    }
+    None
+}
+
+fn add_balance(storage: &mut dyn Storage, address: &str, amount: Uint128) -> StdResult<()> {
+    let balance = balance(storage, address).checked_add(amount)?;
+    BALANCES.insert(storage, &address.to_string(), &balance)
+}
+
+fn sub_balance(storage: &mut dyn Storage, address: &str, amount: Uint128) -> StdResult<()> {
+    let balance = balance(storage, address);
+    let balance = balance.checked_sub(amount).map_err(|_| {
+        StdError::generic_err(format!(
+            "insufficient funds: balance={balance}, required={amount}"
+        ))
+    })?;
+    BALANCES.insert(storage, &address.to_string(), &balance)
+}
+
+fn transfer(storage: &mut dyn Storage, from: &str, to: &str, amount: Uint128) -> StdResult<()> {
+    sub_balance(storage, from, amount)?;
+    add_balance(storage, to, amount)
+}
+
+fn burn(storage: &mut dyn Storage, owner: &str, amount: Uint128) -> StdResult<()> {
+    sub_balance(storage, owner, amount)?;
+    let mut config = CONFIG.load(storage)?;
+    config.total_supply = config.total_supply.checked_sub(amount)?;
+    CONFIG.save(storage, &config)
+}
+
+/// Returns the allowance of a (owner, spender) pair, or zero if it expired
+fn load_allowance(storage: &dyn Storage, key: &(String, String), env: &Env) -> Uint128 {
+    match ALLOWANCES.get(storage, key) {
+        Some(allowance)
+            if allowance
+                .expiration
+                .is_none_or(|expiration| env.block.time.seconds() < expiration) =>
+        {
+            allowance.amount
+        }
+        _ => Uint128::zero(),
+    }
+}
+
+fn spend_allowance(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &str,
+    spender: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let key = (owner.to_string(), spender.to_string());
+    let allowance = load_allowance(storage, &key, env);
+    let rejected = FAILURES
+        .may_load(storage)?
+        .unwrap_or_default()
+        .reject_allowances;
+    if rejected || allowance < amount {
+        return Err(StdError::generic_err(format!(
+            "insufficient allowance: allowance={allowance}, required={amount}"
+        )));
+    }
+    let expiration = ALLOWANCES.get(storage, &key).and_then(|a| a.expiration);
+    ALLOWANCES.insert(
+        storage,
+        &key,
+        &MockAllowance {
+            amount: allowance - amount,
+            expiration,
+        },
+    )
+}
+
+/// Returns the `Receive` callback of a send, if the recipient registered a code hash or the
+/// sender provided one
+#[allow(clippy::too_many_arguments)]
+fn receive_msg(
+    storage: &dyn Storage,
+    sender: &str,
+    from: &str,
+    recipient: String,
+    recipient_code_hash: Option<String>,
+    amount: Uint128,
+    memo: Option<String>,
+    msg: Option<Binary>,
+) -> StdResult<Option<WasmMsg>> {
+    let code_hash = match recipient_code_hash.or_else(|| RECEIVERS.get(storage, &recipient)) {
+        Some(code_hash) => code_hash,
+        None => return Ok(None),
+    };
+    let receive = ReceiverHandleMsg::Receive {
+        sender: sender.to_string(),
+        from: from.to_string(),
+        amount,
+        memo,
+        msg,
+    };
+    Ok(Some(WasmMsg::Execute {
+        contract_addr: recipient,
+        code_hash,
+        msg: to_binary(&receive)?,
+        funds: vec![],
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuthenticatedQueryResponse;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+    use cosmwasm_std::{from_binary, CosmosMsg, MemoryStorage, OwnedDeps};
+
+    fn token() -> StdResult<OwnedDeps<MemoryStorage, MockApi, MockQuerier>> {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            MockSnip20InitMsg {
+                name: "Secret SCRT".to_string(),
+                symbol: "SSCRT".to_string(),
+                decimals: 6,
+                initial_balances: vec![InitialBalance {
+                    address: "alice".to_string(),
+                    amount: Uint128::new(1000),
+                }],
+                minters: vec!["admin".to_string()],
+                failures: MockFailures::default(),
+            },
+        )?;
+        Ok(deps)
+    }
+
+    fn execute_as(
+        deps: &mut OwnedDeps<MemoryStorage, MockApi, MockQuerier>,
+        sender: &str,
+        msg: HandleMsg,
+    ) -> StdResult<Response> {
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    #[test]
+    fn test_send_and_receive() -> StdResult<()> {
+        let mut deps = token()?;
+        execute_as(
+            &mut deps,
+            "vault",
+            HandleMsg::RegisterReceive {
+                code_hash: "vault_hash".to_string(),
+                padding: None,
+            },
+        )?;
+
+        let response = execute_as(
+            &mut deps,
+            "alice",
+            HandleMsg::Send {
+                recipient: "vault".to_string(),
+                recipient_code_hash: None,
+                amount: Uint128::new(300),
+                msg: Some(Binary::from(b"{}")),
+                memo: None,
+                padding: None,
+            },
+        )?;
+        assert_eq!(
+            response.data,
+            Some(Binary::from(br#"{"send":{"status":"success"}}"#))
+        );
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "vault".to_string(),
+                code_hash: "vault_hash".to_string(),
+                msg: Binary::from(
+                    br#"{"receive":{"sender":"alice","from":"alice","amount":"300","memo":null,"msg":"e30="}}"#
+                ),
+                funds: vec![],
+            })
+        );
+        assert_eq!(balance(&deps.storage, "alice"), Uint128::new(700));
+        assert_eq!(balance(&deps.storage, "vault"), Uint128::new(300));
+
+        // transfers to contracts don't call them
+        let response = execute_as(
+            &mut deps,
+            "alice",
+            HandleMsg::Transfer {
+                recipient: "vault".to_string(),
+                amount: Uint128::new(1),
+                memo: None,
+                padding: None,
+            },
+        )?;
+        assert!(response.messages.is_empty());
+
+        let overdraft = execute_as(
+            &mut deps,
+            "bob",
+            HandleMsg::Transfer {
+                recipient: "alice".to_string(),
+                amount: Uint128::new(1),
+                memo: None,
+                padding: None,
+            },
+        );
+        assert!(overdraft.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_viewing_key_queries() -> StdResult<()> {
+        let mut deps = token()?;
+        execute_as(
+            &mut deps,
+            "alice",
+            HandleMsg::SetViewingKey {
+                key: "key".to_string(),
+                padding: None,
+            },
+        )?;
+        let balance_query = |key: &str| QueryMsg::Balance {
+            address: "alice".to_string(),
+            key: key.to_string(),
+        };
+
+        let answer = query(deps.as_ref(), mock_env(), balance_query("key"))?;
+        assert_eq!(answer, Binary::from(br#"{"balance":{"amount":"1000"}}"#));
+        let answer = from_binary(&query(deps.as_ref(), mock_env(), balance_query("wrong"))?)?;
+        assert!(matches!(
+            answer,
+            AuthenticatedQueryResponse::ViewingKeyError { .. }
+        ));
+
+        set_failures(
+            &mut deps.storage,
+            &MockFailures {
+                reject_viewing_keys: true,
+                reject_allowances: false,
+            },
+        )?;
+        let answer = from_binary(&query(deps.as_ref(), mock_env(), balance_query("key"))?)?;
+        assert!(matches!(
+            answer,
+            AuthenticatedQueryResponse::ViewingKeyError { .. }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allowances() -> StdResult<()> {
+        let mut deps = token()?;
+        let transfer_from = |amount: u128| HandleMsg::TransferFrom {
+            owner: "alice".to_string(),
+            recipient: "bob".to_string(),
+            amount: Uint128::new(amount),
+            memo: None,
+            padding: None,
+        };
+        execute_as(
+            &mut deps,
+            "alice",
+            HandleMsg::IncreaseAllowance {
+                spender: "spender".to_string(),
+                amount: Uint128::new(100),
+                expiration: None,
+                padding: None,
+            },
+        )?;
+
+        execute_as(&mut deps, "spender", transfer_from(60))?;
+        assert_eq!(balance(&deps.storage, "bob"), Uint128::new(60));
+        assert_eq!(
+            execute_as(&mut deps, "spender", transfer_from(60)),
+            Err(StdError::generic_err(
+                "insufficient allowance: allowance=40, required=60"
+            ))
+        );
+
+        set_failures(
+            &mut deps.storage,
+            &MockFailures {
+                reject_viewing_keys: false,
+                reject_allowances: true,
+            },
+        )?;
+        assert!(execute_as(&mut deps, "spender", transfer_from(10)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported() -> StdResult<()> {
+        let mut deps = token()?;
+        assert_eq!(
+            execute_as(&mut deps, "alice", HandleMsg::Deposit { padding: None }),
+            Err(StdError::generic_err(
+                "mock snip20: unsupported message 'deposit'"
+            ))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+//! Computing the [`SetWhitelistedApproval`](HandleMsg::SetWhitelistedApproval) messages that
+//! move the approvals of an owner from their current state, as listed by the
+//! [`NftDossier`](crate::QueryMsg::NftDossier) and
+//! [`InventoryApprovals`](crate::QueryMsg::InventoryApprovals) queries, to a desired state, e.g.
+//! for the "security reset" of a wallet contract.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cosmwasm_std::{CosmosMsg, StdResult};
+
+use crate::expiration::Expiration;
+use crate::handle::{AccessLevel, HandleMsg};
+use crate::query::Snip721Approval;
+
+/// The expirations of the permissions of an address, in the order of the fields of
+/// [`SetWhitelistedApproval`](HandleMsg::SetWhitelistedApproval)
+type Permissions = [Option<Expiration>; 3];
+
+fn permissions(approval: &Snip721Approval) -> Permissions {
+    [
+        approval.view_owner_expiration,
+        approval.view_private_metadata_expiration,
+        approval.transfer_expiration,
+    ]
+}
+
+fn by_address(approvals: &[Snip721Approval]) -> BTreeMap<&str, Permissions> {
+    approvals
+        .iter()
+        .map(|approval| (approval.address.as_str(), permissions(approval)))
+        .collect()
+}
+
+fn set_whitelisted_approval(
+    address: &str,
+    token_id: &Option<String>,
+    levels: [Option<AccessLevel>; 3],
+    expires: Option<Expiration>,
+    padding: &Option<String>,
+) -> HandleMsg {
+    let [view_owner, view_private_metadata, transfer] = levels;
+    HandleMsg::SetWhitelistedApproval {
+        address: address.to_string(),
+        token_id: token_id.clone(),
+        view_owner,
+        view_private_metadata,
+        transfer,
+        expires,
+        padding: padding.clone(),
+    }
+}
+
+/// Returns the fewest [`SetWhitelistedApproval`](HandleMsg::SetWhitelistedApproval) messages
+/// that turn the `current` approvals into the `desired` ones, sorted by address.
+///
+/// With a `token_id`, the approvals are those of the token, e.g. the `token_approvals` of an
+/// [`NftDossier`](crate::NftDossier), and are granted with
+/// [`ApproveToken`](AccessLevel::ApproveToken) and revoked with
+/// [`RevokeToken`](AccessLevel::RevokeToken). Without one, they are the approvals of the whole
+/// inventory, e.g. the `inventory_approvals` of [`InventoryApprovals`](crate::InventoryApprovals),
+/// and are granted with [`All`](AccessLevel::All) and revoked with
+/// [`None`](AccessLevel::None), which also revokes the approvals of the address for single
+/// tokens.
+///
+/// A message has a single expiration, so an address needs a message for each distinct
+/// expiration of the permissions it gains or whose expiration changes. Its revocations are
+/// made in the first of them.
+pub fn approval_delta(
+    current: &[Snip721Approval],
+    desired: &[Snip721Approval],
+    token_id: Option<String>,
+    padding: Option<String>,
+) -> Vec<HandleMsg> {
+    let (grant, revoke) = match token_id {
+        Some(_) => (AccessLevel::ApproveToken, AccessLevel::RevokeToken),
+        None => (AccessLevel::All, AccessLevel::None),
+    };
+    let current = by_address(current);
+    let desired = by_address(desired);
+    let addresses: BTreeSet<&str> = current.keys().chain(desired.keys()).copied().collect();
+
+    let mut msgs = vec![];
+    for address in addresses {
+        let current = current.get(address).copied().unwrap_or_default();
+        let desired = desired.get(address).copied().unwrap_or_default();
+
+        let mut revocations: [Option<AccessLevel>; 3] = Default::default();
+        // the permissions granted with each expiration, in the order they are first met
+        let mut grants: Vec<(Expiration, [Option<AccessLevel>; 3])> = vec![];
+        for (i, (current, desired)) in current.into_iter().zip(desired).enumerate() {
+            match (current, desired) {
+                (Some(_), None) => revocations[i] = Some(revoke.clone()),
+                (current, Some(expires)) if current != Some(expires) => {
+                    match grants.iter_mut().find(|(e, _)| *e == expires) {
+                        Some((_, levels)) => levels[i] = Some(grant.clone()),
+                        None => {
+                            let mut levels: [Option<AccessLevel>; 3] = Default::default();
+                            levels[i] = Some(grant.clone());
+                            grants.push((expires, levels));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let has_revocations = revocations.iter().any(Option::is_some);
+        if grants.is_empty() {
+            if has_revocations {
+                msgs.push(set_whitelisted_approval(
+                    address,
+                    &token_id,
+                    revocations,
+                    None,
+                    &padding,
+                ));
+            }
+            continue;
+        }
+        for (n, (expires, mut levels)) in grants.into_iter().enumerate() {
+            if n == 0 {
+                for (level, revocation) in levels.iter_mut().zip(&revocations) {
+                    if revocation.is_some() {
+                        *level = revocation.clone();
+                    }
+                }
+            }
+            msgs.push(set_whitelisted_approval(
+                address,
+                &token_id,
+                levels,
+                Some(expires),
+                &padding,
+            ));
+        }
+    }
+    msgs
+}
+
+/// Returns a StdResult<Vec<CosmosMsg>> of the messages of [`approval_delta`], to execute on
+/// the SNIP-721 contract
+///
+/// # Arguments
+///
+/// * `current` - the current approvals, of the token or of the whole inventory
+/// * `desired` - the desired approvals
+/// * `token_id` - Optional ID String of the token whose approvals are set, or None for the
+///   approvals of the whole inventory
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the messages to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+#[allow(clippy::too_many_arguments)]
+pub fn approval_delta_msgs(
+    current: &[Snip721Approval],
+    desired: &[Snip721Approval],
+    token_id: Option<String>,
+    padding: Option<String>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<Vec<CosmosMsg>> {
+    approval_delta(current, desired, token_id, padding)
+        .iter()
+        .map(|msg| msg.to_cosmos_msg(block_size, code_hash.clone(), contract_addr.clone(), None))
+        .collect()
+}
+
+/// Returns a StdResult<Vec<CosmosMsg>> revoking every permission of every address in
+/// `approvals`, e.g. the `inventory_approvals` of [`InventoryApprovals`](crate::InventoryApprovals)
+/// chained with the `token_approvals` of the dossiers of the tokens of the owner. Each address
+/// gets a single message setting all its permissions to [`None`](AccessLevel::None), which
+/// revokes its approvals for the whole inventory and for single tokens alike.
+///
+/// # Arguments
+///
+/// * `approvals` - the approvals of the owner, in any order and possibly repeating addresses
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the messages to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn revoke_all_approvals_msgs<'a>(
+    approvals: impl IntoIterator<Item = &'a Snip721Approval>,
+    padding: Option<String>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<Vec<CosmosMsg>> {
+    let addresses: BTreeSet<&str> = approvals
+        .into_iter()
+        .map(|approval| approval.address.as_str())
+        .collect();
+    addresses
+        .into_iter()
+        .map(|address| {
+            set_whitelisted_approval(
+                address,
+                &None,
+                [
+                    Some(AccessLevel::None),
+                    Some(AccessLevel::None),
+                    Some(AccessLevel::None),
+                ],
+                None,
+                &padding,
+            )
+            .to_cosmos_msg(block_size, code_hash.clone(), contract_addr.clone(), None)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{from_binary, WasmMsg};
+
+    fn approval(
+        address: &str,
+        view_owner: Option<Expiration>,
+        view_private_metadata: Option<Expiration>,
+        transfer: Option<Expiration>,
+    ) -> Snip721Approval {
+        Snip721Approval {
+            address: address.to_string(),
+            view_owner_expiration: view_owner,
+            view_private_metadata_expiration: view_private_metadata,
+            transfer_expiration: transfer,
+        }
+    }
+
+    fn msg(
+        address: &str,
+        token_id: Option<&str>,
+        levels: [Option<AccessLevel>; 3],
+        expires: Option<Expiration>,
+    ) -> HandleMsg {
+        set_whitelisted_approval(
+            address,
+            &token_id.map(str::to_string),
+            levels,
+            expires,
+            &None,
+        )
+    }
+
+    #[test]
+    fn test_approval_delta() {
+        use AccessLevel::{ApproveToken, RevokeToken};
+        use Expiration::{AtHeight, Never};
+
+        let current = vec![
+            approval("alice", Some(Never), Some(Never), None),
+            approval("bob", Some(AtHeight(10)), None, Some(AtHeight(10))),
+            approval("carol", None, None, Some(Never)),
+        ];
+        let desired = vec![
+            // unchanged
+            approval("carol", None, None, Some(Never)),
+            // loses the private metadata, gains transfer
+            approval("alice", Some(Never), None, Some(Never)),
+            // extends view owner, gains private metadata with another expiration
+            approval("bob", Some(AtHeight(20)), Some(Never), Some(AtHeight(10))),
+            // new
+            approval("dave", Some(Never), None, None),
+        ];
+
+        assert_eq!(
+            approval_delta(&current, &desired, Some("token".to_string()), None),
+            vec![
+                msg(
+                    "alice",
+                    Some("token"),
+                    [None, Some(RevokeToken), Some(ApproveToken)],
+                    Some(Never)
+                ),
+                msg(
+                    "bob",
+                    Some("token"),
+                    [Some(ApproveToken), None, None],
+                    Some(AtHeight(20))
+                ),
+                msg(
+                    "bob",
+                    Some("token"),
+                    [None, Some(ApproveToken), None],
+                    Some(Never)
+                ),
+                msg(
+                    "dave",
+                    Some("token"),
+                    [Some(ApproveToken), None, None],
+                    Some(Never)
+                ),
+            ]
+        );
+
+        // only revocations, for the whole inventory
+        assert_eq!(
+            approval_delta(&current, &current[2..], None, None),
+            vec![
+                msg(
+                    "alice",
+                    None,
+                    [Some(AccessLevel::None), Some(AccessLevel::None), None],
+                    None
+                ),
+                msg(
+                    "bob",
+                    None,
+                    [Some(AccessLevel::None), None, Some(AccessLevel::None)],
+                    None
+                ),
+            ]
+        );
+
+        assert!(approval_delta(&current, &current, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_revoke_all_approvals_msgs() -> StdResult<()> {
+        let inventory = [approval("bob", None, None, Some(Expiration::Never))];
+        let token = vec![
+            approval("alice", Some(Expiration::Never), None, None),
+            approval("bob", Some(Expiration::Never), None, None),
+        ];
+
+        let msgs = revoke_all_approvals_msgs(
+            inventory.iter().chain(&token),
+            None,
+            256,
+            "code hash".to_string(),
+            "contract".to_string(),
+        )?;
+        let msgs: Vec<HandleMsg> = msgs
+            .into_iter()
+            .map(|msg| match msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => from_binary(&msg),
+                _ => panic!("unexpected message"),
+            })
+            .collect::<StdResult<_>>()?;
+        let none = || Some(AccessLevel::None);
+        assert_eq!(
+            msgs,
+            vec![
+                msg("alice", None, [none(), none(), none()], None),
+                msg("bob", None, [none(), none(), none()], None),
+            ]
+        );
+
+        Ok(())
+    }
+}
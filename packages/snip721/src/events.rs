@@ -0,0 +1,113 @@
+//! The action names and attribute keys of the events of SNIP-721 tokens, and the parsing of the
+//! transfers out of the response to a submessage. See `secret_toolkit_utils::events`.
+
+use cosmwasm_std::{StdError, StdResult, SubMsgResponse};
+
+pub use secret_toolkit_utils::events::{
+    find_token_event, parse_token_events, TokenEvent, ACTION_KEY, CONTRACT_ADDRESS_KEY, WASM_EVENT,
+};
+
+/// The names of the actions of SNIP-721 tokens, after their handle messages
+pub mod actions {
+    pub const TRANSFER_NFT: &str = "transfer_nft";
+    pub const SEND_NFT: &str = "send_nft";
+    pub const BATCH_TRANSFER_NFT: &str = "batch_transfer_nft";
+    pub const BATCH_SEND_NFT: &str = "batch_send_nft";
+    pub const MINT_NFT: &str = "mint_nft";
+    pub const BURN_NFT: &str = "burn_nft";
+    pub const APPROVE: &str = "approve";
+    pub const REVOKE: &str = "revoke";
+    pub const SET_WHITELISTED_APPROVAL: &str = "set_whitelisted_approval";
+}
+
+/// The keys of the attributes of the events of SNIP-721 tokens
+pub mod keys {
+    pub const TOKEN_ID: &str = "token_id";
+    pub const OWNER: &str = "owner";
+    pub const SENDER: &str = "sender";
+    pub const RECIPIENT: &str = "recipient";
+    pub const MEMO: &str = "memo";
+}
+
+/// A transfer of a token, from a `transfer_nft` or `send_nft` event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NftTransferEvent {
+    pub action: String,
+    pub token_id: String,
+    pub recipient: String,
+    pub memo: Option<String>,
+}
+
+impl NftTransferEvent {
+    /// Returns whether an action transfers a token
+    pub fn is_transfer(action: &str) -> bool {
+        [actions::TRANSFER_NFT, actions::SEND_NFT].contains(&action)
+    }
+
+    /// Parses a transfer out of its event. Fails if the event isn't that of a transfer, or
+    /// misses an attribute.
+    pub fn from_event(event: &TokenEvent) -> StdResult<Self> {
+        if !Self::is_transfer(&event.action) {
+            return Err(StdError::generic_err(format!(
+                "{} event is not a transfer",
+                event.action
+            )));
+        }
+        Ok(Self {
+            action: event.action.clone(),
+            token_id: event.require(keys::TOKEN_ID)?.to_string(),
+            recipient: event.require(keys::RECIPIENT)?.to_string(),
+            memo: event.get(keys::MEMO).map(str::to_string),
+        })
+    }
+}
+
+/// Returns the transfers made by the token at `contract` in the response to a submessage
+pub fn parse_nft_transfer_events(
+    response: &SubMsgResponse,
+    contract: &str,
+) -> StdResult<Vec<NftTransferEvent>> {
+    parse_token_events(response, contract)
+        .iter()
+        .filter(|event| NftTransferEvent::is_transfer(&event.action))
+        .map(NftTransferEvent::from_event)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::Event;
+
+    #[test]
+    fn test_parse_nft_transfer_events() -> StdResult<()> {
+        let response = SubMsgResponse {
+            events: vec![
+                Event::new(WASM_EVENT)
+                    .add_attribute(CONTRACT_ADDRESS_KEY, "secret1nft")
+                    .add_attribute(ACTION_KEY, actions::MINT_NFT)
+                    .add_attribute(keys::TOKEN_ID, "card1"),
+                Event::new(WASM_EVENT)
+                    .add_attribute(CONTRACT_ADDRESS_KEY, "secret1nft")
+                    .add_attribute(ACTION_KEY, actions::TRANSFER_NFT)
+                    .add_attribute(keys::TOKEN_ID, "card1")
+                    .add_attribute(keys::RECIPIENT, "bob"),
+            ],
+            data: None,
+        };
+
+        assert_eq!(
+            parse_nft_transfer_events(&response, "secret1nft")?,
+            vec![NftTransferEvent {
+                action: actions::TRANSFER_NFT.to_string(),
+                token_id: "card1".to_string(),
+                recipient: "bob".to_string(),
+                memo: None,
+            }]
+        );
+        let mint = find_token_event(&response, "secret1nft", actions::MINT_NFT).unwrap();
+        assert!(NftTransferEvent::from_event(&mint).is_err());
+
+        Ok(())
+    }
+}
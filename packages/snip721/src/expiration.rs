@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_std::BlockInfo;
 use std::fmt;
 
+use secret_toolkit_utils::block::Expires;
+
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 /// at the given point in time and after, Expiration will be considered expired
@@ -49,12 +51,97 @@ impl Expiration {
     }
 }
 
+impl Expires for Expiration {
+    fn is_expired(&self, block: &BlockInfo) -> bool {
+        Expiration::is_expired(self, block)
+    }
+}
+
+#[cfg(feature = "cw-interop")]
+mod cw_interop {
+    use cosmwasm_std::StdError;
+    use cw_utils::Scheduled;
+    use vanilla_cosmwasm_std::Timestamp;
+
+    use super::Expiration;
+
+    /// Returns the seconds of `time`, rounded up, so the expiration is never earlier than
+    /// `time`
+    fn ceil_seconds(time: &Timestamp) -> u64 {
+        if time.subsec_nanos() == 0 {
+            time.seconds()
+        } else {
+            time.seconds().saturating_add(1)
+        }
+    }
+
+    impl From<cw_utils::Expiration> for Expiration {
+        fn from(expiration: cw_utils::Expiration) -> Self {
+            match expiration {
+                cw_utils::Expiration::AtHeight(height) => Expiration::AtHeight(height),
+                cw_utils::Expiration::AtTime(time) => Expiration::AtTime(ceil_seconds(&time)),
+                cw_utils::Expiration::Never {} => Expiration::Never,
+            }
+        }
+    }
+
+    impl From<Expiration> for cw_utils::Expiration {
+        fn from(expiration: Expiration) -> Self {
+            match expiration {
+                Expiration::AtHeight(height) => cw_utils::Expiration::AtHeight(height),
+                Expiration::AtTime(time) => {
+                    cw_utils::Expiration::AtTime(Timestamp::from_seconds(time))
+                }
+                Expiration::Never => cw_utils::Expiration::Never {},
+            }
+        }
+    }
+
+    impl From<Scheduled> for Expiration {
+        fn from(scheduled: Scheduled) -> Self {
+            match scheduled {
+                Scheduled::AtHeight(height) => Expiration::AtHeight(height),
+                Scheduled::AtTime(time) => Expiration::AtTime(ceil_seconds(&time)),
+            }
+        }
+    }
+
+    /// Fails for `Expiration::Never`, as a schedule is always triggered at some point
+    impl TryFrom<Expiration> for Scheduled {
+        type Error = StdError;
+
+        fn try_from(expiration: Expiration) -> Result<Self, Self::Error> {
+            match expiration {
+                Expiration::AtHeight(height) => Ok(Scheduled::AtHeight(height)),
+                Expiration::AtTime(time) => Ok(Scheduled::AtTime(Timestamp::from_seconds(time))),
+                Expiration::Never => Err(StdError::generic_err(
+                    "an expiration that never expires can't be scheduled",
+                )),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use cosmwasm_std::Timestamp;
 
     use super::*;
 
+    #[test]
+    fn test_expiration_window() {
+        let mut env = cosmwasm_std::testing::mock_env();
+        env.block.height = 1000;
+        let window = (Expiration::AtHeight(900), Expiration::AtTime(u64::MAX));
+        assert!(secret_toolkit_utils::block::assert_within(&env, &window.0, &window.1).is_ok());
+        assert!(secret_toolkit_utils::block::assert_within(
+            &env,
+            &window.0,
+            &Expiration::AtHeight(1000)
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_expiration() {
         let block_h1000_t1000000 = BlockInfo {
@@ -84,4 +171,40 @@ mod test {
         assert!(!exp_t1500000.is_expired(&block_h1000_t1000000));
         assert!(exp_t1500000.is_expired(&block_h2000_t2000000));
     }
+    #[cfg(feature = "cw-interop")]
+    #[test]
+    fn test_cw_utils_conversions() {
+        use cw_utils::Scheduled;
+        use vanilla_cosmwasm_std::Timestamp;
+
+        for expiration in [
+            Expiration::AtHeight(1000),
+            Expiration::AtTime(1000000),
+            Expiration::Never,
+        ] {
+            let cw_expiration: cw_utils::Expiration = expiration.into();
+            assert_eq!(Expiration::from(cw_expiration), expiration);
+        }
+        assert_eq!(
+            cw_utils::Expiration::from(Expiration::AtTime(1000000)),
+            cw_utils::Expiration::AtTime(Timestamp::from_seconds(1000000))
+        );
+        // times are rounded up to the next second
+        assert_eq!(
+            Expiration::from(cw_utils::Expiration::AtTime(Timestamp::from_nanos(
+                1_000_000_000_000_001
+            ))),
+            Expiration::AtTime(1000001)
+        );
+
+        assert_eq!(
+            Scheduled::try_from(Expiration::AtHeight(1000)),
+            Ok(Scheduled::AtHeight(1000))
+        );
+        assert_eq!(
+            Expiration::from(Scheduled::AtTime(Timestamp::from_seconds(5))),
+            Expiration::AtTime(5)
+        );
+        assert!(Scheduled::try_from(Expiration::Never).is_err());
+    }
 }
@@ -26,6 +26,19 @@ pub enum AccessLevel {
     None,
 }
 
+/// the level of operation of the contract, set with
+/// [`SetContractStatus`](HandleMsg::SetContractStatus)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractStatus {
+    /// all messages are allowed
+    Normal,
+    /// only messages that don't move tokens, e.g. setting viewing keys, are allowed
+    StopTransactions,
+    /// only admin messages are allowed
+    StopAll,
+}
+
 //
 // structs used for optional batch processing as implemented in the reference
 // contract
@@ -80,7 +93,7 @@ pub struct Send {
 }
 
 /// SNIP-721 contract handle messages
-#[derive(Serialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMsg {
     //
@@ -316,6 +329,31 @@ pub enum HandleMsg {
         /// optional message length padding
         padding: Option<String>,
     },
+
+    //
+    // Administration
+    //
+    /// change the admin of the contract
+    ChangeAdmin {
+        /// address of the new admin
+        address: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// set the level of operation of the contract
+    SetContractStatus {
+        /// status level
+        level: ContractStatus,
+        /// optional message length padding
+        padding: Option<String>,
+    },
+    /// revoke the permits with the given name that the sender signed
+    RevokePermit {
+        /// name of the permits to revoke
+        permit_name: String,
+        /// optional message length padding
+        padding: Option<String>,
+    },
 }
 
 impl HandleMsg {
@@ -327,8 +365,8 @@ impl HandleMsg {
     /// * `code_hash` - String holding the code hash of the contract being called
     /// * `contract_addr` - address of the contract being called
     /// * `send_amount` - Optional Uint128 amount of native coin to send with the callback message
-    ///                 NOTE: No SNIP721 messages send native coin, but the parameter is
-    ///                       included in case that ever changes
+    ///   NOTE: No SNIP721 messages send native coin, but the parameter is
+    ///   included in case that ever changes
     pub fn to_cosmos_msg(
         &self,
         mut block_size: usize,
@@ -397,10 +435,10 @@ pub fn transfer_nft_msg(
 /// # Arguments
 ///
 /// * `contract` - the address the token is to be sent to.  It does not have to be a
-///                contract address, but the field is named this for CW721 compliance
+///   contract address, but the field is named this for CW721 compliance
 /// * `token_id` - ID String of the token to send
 /// * `msg` - Optional base64 encoded message to pass to the recipient contract's
-///           (Batch)ReceiveNft function
+///   (Batch)ReceiveNft function
 /// * `memo` - Optional String memo for the tx
 /// * `padding` - Optional String used as padding if you don't want to use block padding
 /// * `block_size` - pad the message to blocks of this size
@@ -577,7 +615,7 @@ pub fn set_whitelisted_approval_msg(
 ///
 /// * `your_contracts_code_hash` - String holding the code hash of your contract
 /// * `also_implements_batch_receive_nft` - Optional bool that is true if your contract also
-///               implements BatchReceiveNft.  Defaults to false if omitted
+///   implements BatchReceiveNft.  Defaults to false if omitted
 /// * `padding` - Optional String used as padding if you don't want to use block padding
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being called
@@ -961,6 +999,81 @@ pub fn reveal_msg(
     )
 }
 
+//
+// Administration
+//
+
+/// Returns a StdResult<CosmosMsg> used to execute [`ChangeAdmin`](HandleMsg::ChangeAdmin)
+///
+/// # Arguments
+///
+/// * `address` - the address of the new admin
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn change_admin_msg(
+    address: String,
+    padding: Option<String>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<CosmosMsg> {
+    HandleMsg::ChangeAdmin { address, padding }.to_cosmos_msg(
+        block_size,
+        code_hash,
+        contract_addr,
+        None,
+    )
+}
+
+/// Returns a StdResult<CosmosMsg> used to execute [`SetContractStatus`](HandleMsg::SetContractStatus)
+///
+/// # Arguments
+///
+/// * `level` - the new ContractStatus of the contract
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn set_contract_status_msg(
+    level: ContractStatus,
+    padding: Option<String>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<CosmosMsg> {
+    HandleMsg::SetContractStatus { level, padding }.to_cosmos_msg(
+        block_size,
+        code_hash,
+        contract_addr,
+        None,
+    )
+}
+
+/// Returns a StdResult<CosmosMsg> used to execute [`RevokePermit`](HandleMsg::RevokePermit)
+///
+/// # Arguments
+///
+/// * `permit_name` - the name of the permits to revoke
+/// * `padding` - Optional String used as padding if you don't want to use block padding
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+pub fn revoke_permit_msg(
+    permit_name: String,
+    padding: Option<String>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<CosmosMsg> {
+    HandleMsg::RevokePermit {
+        permit_name,
+        padding,
+    }
+    .to_cosmos_msg(block_size, code_hash, contract_addr, None)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{Extension, Trait};
@@ -1854,4 +1967,87 @@ mod tests {
         assert_eq!(test_msg, expected_msg);
         Ok(())
     }
+
+    #[test]
+    fn test_change_admin_msg() -> StdResult<()> {
+        let address = "alice".to_string();
+        let padding = Some("padding".to_string());
+        let code_hash = "code hash".to_string();
+        let contract_addr = "contract".to_string();
+
+        let test_msg = change_admin_msg(
+            address.clone(),
+            padding.clone(),
+            256usize,
+            code_hash.clone(),
+            contract_addr.clone(),
+        )?;
+        let mut msg = to_binary(&HandleMsg::ChangeAdmin { address, padding })?;
+        let msg = space_pad(&mut msg.0, 256usize);
+        let expected_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            msg: Binary(msg.to_vec()),
+            contract_addr,
+            code_hash,
+            funds: vec![],
+        });
+        assert_eq!(test_msg, expected_msg);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_contract_status_msg() -> StdResult<()> {
+        let level = ContractStatus::StopTransactions;
+        let padding = Some("padding".to_string());
+        let code_hash = "code hash".to_string();
+        let contract_addr = "contract".to_string();
+
+        let test_msg = set_contract_status_msg(
+            level,
+            padding.clone(),
+            256usize,
+            code_hash.clone(),
+            contract_addr.clone(),
+        )?;
+        let mut msg = to_binary(&HandleMsg::SetContractStatus { level, padding })?;
+        assert!(String::from_utf8_lossy(&msg.0)
+            .starts_with(r#"{"set_contract_status":{"level":"stop_transactions""#));
+        let msg = space_pad(&mut msg.0, 256usize);
+        let expected_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            msg: Binary(msg.to_vec()),
+            contract_addr,
+            code_hash,
+            funds: vec![],
+        });
+        assert_eq!(test_msg, expected_msg);
+        Ok(())
+    }
+
+    #[test]
+    fn test_revoke_permit_msg() -> StdResult<()> {
+        let permit_name = "balances".to_string();
+        let padding = Some("padding".to_string());
+        let code_hash = "code hash".to_string();
+        let contract_addr = "contract".to_string();
+
+        let test_msg = revoke_permit_msg(
+            permit_name.clone(),
+            padding.clone(),
+            256usize,
+            code_hash.clone(),
+            contract_addr.clone(),
+        )?;
+        let mut msg = to_binary(&HandleMsg::RevokePermit {
+            permit_name,
+            padding,
+        })?;
+        let msg = space_pad(&mut msg.0, 256usize);
+        let expected_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            msg: Binary(msg.to_vec()),
+            contract_addr,
+            code_hash,
+            funds: vec![],
+        });
+        assert_eq!(test_msg, expected_msg);
+        Ok(())
+    }
 }
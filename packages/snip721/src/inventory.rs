@@ -0,0 +1,256 @@
+//! Tracking changes to the token inventory of an owner, e.g. to notify them when they gain or
+//! lose tokens, in conjunction with SNIP-52 channels.
+
+use std::collections::BTreeSet;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CustomQuery, QuerierWrapper, StdResult};
+
+#[cfg(feature = "inventory")]
+use cosmwasm_std::Storage;
+#[cfg(feature = "inventory")]
+use secret_toolkit_storage::Keymap;
+
+use crate::query::tokens_query;
+
+/// The tokens gained and lost by an owner between two snapshots of their inventory
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InventoryDiff {
+    /// the tokens in the current inventory that weren't in the previous one, sorted
+    pub gained: Vec<String>,
+    /// the tokens in the previous inventory that aren't in the current one, sorted
+    pub lost: Vec<String>,
+}
+
+impl InventoryDiff {
+    /// Computes the difference between two inventories, given in any order
+    pub fn between(previous: &[String], current: &[String]) -> Self {
+        let previous: BTreeSet<&String> = previous.iter().collect();
+        let current: BTreeSet<&String> = current.iter().collect();
+        Self {
+            gained: current
+                .difference(&previous)
+                .map(|t| t.to_string())
+                .collect(),
+            lost: previous
+                .difference(&current)
+                .map(|t| t.to_string())
+                .collect(),
+        }
+    }
+
+    /// Returns whether the inventory didn't change
+    pub fn is_empty(&self) -> bool {
+        self.gained.is_empty() && self.lost.is_empty()
+    }
+}
+
+/// Returns a StdResult<Vec<String>> of the whole token inventory of an owner, by performing
+/// [`Tokens`](crate::QueryMsg::Tokens) queries until the last page
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `owner` - the address whose token inventory is being requested
+/// * `viewer` - Optional address of the querier if different from the owner
+/// * `viewing_key` - Optional String holding the viewing key of the querier
+/// * `page_size` - the number of token ids requested by each query
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+#[allow(clippy::too_many_arguments)]
+pub fn all_tokens_of_query<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    owner: String,
+    viewer: Option<String>,
+    viewing_key: Option<String>,
+    page_size: u32,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<Vec<String>> {
+    let mut tokens: Vec<String> = Vec::new();
+    loop {
+        let page = tokens_query(
+            querier,
+            owner.clone(),
+            viewer.clone(),
+            viewing_key.clone(),
+            tokens.last().cloned(),
+            Some(page_size),
+            block_size,
+            code_hash.clone(),
+            contract_addr.clone(),
+        )?
+        .tokens;
+        let last_page = page.is_empty() || page.len() < page_size as usize;
+        tokens.extend(page);
+        if last_page {
+            return Ok(tokens);
+        }
+    }
+}
+
+/// The last known token inventory of each owner, stored under `namespace`, to compute what they
+/// gained and lost since then
+#[cfg(feature = "inventory")]
+pub struct InventorySnapshots<'a> {
+    namespace: &'a [u8],
+}
+
+#[cfg(feature = "inventory")]
+impl<'a> InventorySnapshots<'a> {
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self { namespace }
+    }
+
+    fn snapshots(&self) -> Keymap<'a, String, Vec<String>> {
+        Keymap::new(self.namespace)
+    }
+
+    /// Returns the last snapshot of the inventory of an owner, sorted, or an empty inventory if
+    /// none was taken
+    pub fn load(&self, storage: &dyn Storage, owner: &str) -> Vec<String> {
+        self.snapshots()
+            .get(storage, &owner.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the snapshot of the inventory of an owner with `current`, and returns what they
+    /// gained and lost since the previous snapshot
+    pub fn update(
+        &self,
+        storage: &mut dyn Storage,
+        owner: &str,
+        mut current: Vec<String>,
+    ) -> StdResult<InventoryDiff> {
+        let diff = InventoryDiff::between(&self.load(storage, owner), &current);
+        if !diff.is_empty() {
+            current.sort();
+            current.dedup();
+            self.snapshots()
+                .insert(storage, &owner.to_string(), &current)?;
+        }
+        Ok(diff)
+    }
+
+    /// Removes the snapshot of the inventory of an owner, e.g. when they unsubscribe
+    pub fn remove(&self, storage: &mut dyn Storage, owner: &str) -> StdResult<()> {
+        self.snapshots().remove(storage, &owner.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{
+        from_slice, to_binary, Binary, ContractResult, Empty, Querier, QuerierResult, QueryRequest,
+        SystemResult, WasmQuery,
+    };
+
+    use crate::query::{QueryMsg, TokenList};
+
+    fn tokens(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_inventory_diff() {
+        let diff = InventoryDiff::between(&tokens(&["a", "b", "c"]), &tokens(&["d", "c", "a"]));
+        assert_eq!(
+            diff,
+            InventoryDiff {
+                gained: tokens(&["d"]),
+                lost: tokens(&["b"]),
+            }
+        );
+        assert!(InventoryDiff::between(&tokens(&["a", "b"]), &tokens(&["b", "a"])).is_empty());
+    }
+
+    struct InventoryQuerier {
+        tokens: Vec<String>,
+    }
+
+    impl Querier for InventoryQuerier {
+        fn raw_query(&self, request: &[u8]) -> QuerierResult {
+            let QueryRequest::<Empty>::Wasm(WasmQuery::Smart { msg, .. }) =
+                from_slice(request).unwrap()
+            else {
+                panic!("expected a smart query");
+            };
+            let QueryMsg::Tokens {
+                start_after, limit, ..
+            } = from_slice(String::from_utf8(msg.0).unwrap().trim_end().as_bytes()).unwrap()
+            else {
+                panic!("expected a Tokens query");
+            };
+            let page: Vec<String> = self
+                .tokens
+                .iter()
+                .filter(|id| start_after.as_ref().is_none_or(|after| *id > after))
+                .take(limit.unwrap() as usize)
+                .cloned()
+                .collect();
+            let response = format!(
+                r#"{{"token_list":{}}}"#,
+                String::from_utf8(to_binary(&TokenList { tokens: page }).unwrap().0).unwrap()
+            );
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        }
+    }
+
+    #[test]
+    fn test_all_tokens_of_query() -> StdResult<()> {
+        for count in [0, 2, 3, 7] {
+            let querier = InventoryQuerier {
+                tokens: (0..count).map(|i| format!("token{i}")).collect(),
+            };
+            let all = all_tokens_of_query(
+                QuerierWrapper::<Empty>::new(&querier),
+                "alice".to_string(),
+                None,
+                Some("key".to_string()),
+                3,
+                256,
+                "code hash".to_string(),
+                "contract".to_string(),
+            )?;
+            assert_eq!(all, querier.tokens);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "inventory")]
+    #[test]
+    fn test_inventory_snapshots() -> StdResult<()> {
+        use cosmwasm_std::testing::MockStorage;
+
+        let mut storage = MockStorage::new();
+        let snapshots = InventorySnapshots::new(b"inventory");
+
+        let diff = snapshots.update(&mut storage, "alice", tokens(&["b", "a"]))?;
+        assert_eq!(diff.gained, tokens(&["a", "b"]));
+        assert_eq!(snapshots.load(&storage, "alice"), tokens(&["a", "b"]));
+        assert!(snapshots.load(&storage, "bob").is_empty());
+
+        let diff = snapshots.update(&mut storage, "alice", tokens(&["c", "a"]))?;
+        assert_eq!(
+            diff,
+            InventoryDiff {
+                gained: tokens(&["c"]),
+                lost: tokens(&["b"]),
+            }
+        );
+        assert!(snapshots
+            .update(&mut storage, "alice", tokens(&["a", "c"]))?
+            .is_empty());
+
+        snapshots.remove(&mut storage, "alice")?;
+        assert!(snapshots.load(&storage, "alice").is_empty());
+
+        Ok(())
+    }
+}
@@ -1,12 +1,25 @@
 #![doc = include_str!("../Readme.md")]
 
 //#![allow(clippy::field_reassign_with_default)]
+pub mod approvals;
+pub mod events;
 pub mod expiration;
 pub mod handle;
+pub mod inventory;
 pub mod metadata;
+#[cfg(feature = "permit")]
+pub mod permit;
 pub mod query;
+pub mod safe_transfer;
+#[cfg(any(test, all(feature = "test-utils", not(target_arch = "wasm32"))))]
+pub mod testing;
 
+pub use approvals::{approval_delta, approval_delta_msgs, revoke_all_approvals_msgs};
 pub use expiration::*;
 pub use handle::*;
+pub use inventory::*;
 pub use metadata::*;
+#[cfg(feature = "permit")]
+pub use permit::*;
 pub use query::*;
+pub use safe_transfer::{assert_receive_confirmed, safe_transfer_nft};
@@ -1,6 +1,10 @@
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use cosmwasm_std::StdError;
+
 // The response of both NftINfo and PrivateMetadata queries are Metadata
 //
 
@@ -83,3 +87,279 @@ pub struct Authentication {
     /// username used in basic authentication
     pub user: Option<String>,
 }
+
+/// URI schemes accepted by default by `MetadataBuilder`
+pub const DEFAULT_URI_SCHEMES: &[&str] = &["ipfs", "https", "data"];
+/// default maximum number of attributes accepted by `MetadataBuilder`
+pub const DEFAULT_MAX_ATTRIBUTES: usize = 64;
+/// default maximum length of each attribute field accepted by `MetadataBuilder`
+pub const DEFAULT_MAX_ATTRIBUTE_LEN: usize = 256;
+
+/// metadata validation errors
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataError {
+    /// a uri field does not use one of the allowed schemes
+    InvalidUriScheme { field: String, uri: String },
+    /// there are more attributes than allowed
+    TooManyAttributes { count: usize, max: usize },
+    /// a field of the attribute at `index` is longer than allowed
+    AttributeTooLong { index: usize, max: usize },
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataError::InvalidUriScheme { field, uri } => {
+                write!(f, "{field} uses a disallowed uri scheme: {uri}")
+            }
+            MetadataError::TooManyAttributes { count, max } => {
+                write!(f, "{count} attributes exceed the maximum of {max}")
+            }
+            MetadataError::AttributeTooLong { index, max } => {
+                write!(f, "attribute {index} exceeds the maximum length of {max}")
+            }
+        }
+    }
+}
+
+impl From<MetadataError> for StdError {
+    fn from(err: MetadataError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+/// Builds `Metadata` while validating uri schemes and attribute sizes
+///
+/// ```
+/// # use secret_toolkit_snip721::{Extension, MetadataBuilder};
+/// let metadata = MetadataBuilder::new()
+///     .token_uri("ipfs://QmHash")
+///     .max_attributes(10)
+///     .build()?;
+///
+/// assert!(MetadataBuilder::new().token_uri("ftp://host/file").build().is_err());
+/// # Ok::<(), secret_toolkit_snip721::MetadataError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct MetadataBuilder {
+    token_uri: Option<String>,
+    extension: Option<Extension>,
+    allowed_schemes: Vec<String>,
+    max_attributes: usize,
+    max_attribute_len: usize,
+}
+
+impl Default for MetadataBuilder {
+    fn default() -> Self {
+        Self {
+            token_uri: None,
+            extension: None,
+            allowed_schemes: DEFAULT_URI_SCHEMES.iter().map(|s| s.to_string()).collect(),
+            max_attributes: DEFAULT_MAX_ATTRIBUTES,
+            max_attribute_len: DEFAULT_MAX_ATTRIBUTE_LEN,
+        }
+    }
+}
+
+impl MetadataBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the uri for off-chain metadata
+    pub fn token_uri(mut self, token_uri: impl Into<String>) -> Self {
+        self.token_uri = Some(token_uri.into());
+        self
+    }
+
+    /// sets the on-chain metadata
+    pub fn extension(mut self, extension: Extension) -> Self {
+        self.extension = Some(extension);
+        self
+    }
+
+    /// replaces the allowed uri schemes (without the trailing `:`)
+    pub fn allowed_schemes(mut self, schemes: &[&str]) -> Self {
+        self.allowed_schemes = schemes.iter().map(|s| s.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// sets the maximum number of attributes
+    pub fn max_attributes(mut self, max: usize) -> Self {
+        self.max_attributes = max;
+        self
+    }
+
+    /// sets the maximum length of each field of an attribute
+    pub fn max_attribute_len(mut self, max: usize) -> Self {
+        self.max_attribute_len = max;
+        self
+    }
+
+    /// validates the metadata and returns it
+    pub fn build(self) -> Result<Metadata, MetadataError> {
+        if let Some(uri) = &self.token_uri {
+            self.check_uri("token_uri", uri)?;
+        }
+        if let Some(extension) = &self.extension {
+            self.check_extension(extension)?;
+        }
+
+        Ok(Metadata {
+            token_uri: self.token_uri,
+            extension: self.extension,
+        })
+    }
+
+    fn check_extension(&self, extension: &Extension) -> Result<(), MetadataError> {
+        let uris = [
+            ("image", &extension.image),
+            ("external_url", &extension.external_url),
+            ("animation_url", &extension.animation_url),
+            ("youtube_url", &extension.youtube_url),
+        ];
+        for (field, uri) in uris {
+            if let Some(uri) = uri {
+                self.check_uri(field, uri)?;
+            }
+        }
+        for media in extension.media.iter().flatten() {
+            self.check_uri("media", &media.url)?;
+        }
+
+        if let Some(attributes) = &extension.attributes {
+            if attributes.len() > self.max_attributes {
+                return Err(MetadataError::TooManyAttributes {
+                    count: attributes.len(),
+                    max: self.max_attributes,
+                });
+            }
+            for (index, attribute) in attributes.iter().enumerate() {
+                let too_long = [
+                    attribute.display_type.as_deref(),
+                    attribute.trait_type.as_deref(),
+                    Some(attribute.value.as_str()),
+                    attribute.max_value.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .any(|field| field.len() > self.max_attribute_len);
+                if too_long {
+                    return Err(MetadataError::AttributeTooLong {
+                        index,
+                        max: self.max_attribute_len,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_uri(&self, field: &str, uri: &str) -> Result<(), MetadataError> {
+        let allowed = uri
+            .split_once(':')
+            .map(|(scheme, _)| scheme.to_ascii_lowercase())
+            .is_some_and(|scheme| self.allowed_schemes.contains(&scheme));
+        if allowed {
+            Ok(())
+        } else {
+            Err(MetadataError::InvalidUriScheme {
+                field: field.to_string(),
+                uri: uri.to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(value: &str) -> Trait {
+        Trait {
+            value: value.to_string(),
+            ..Trait::default()
+        }
+    }
+
+    #[test]
+    fn test_uri_schemes() {
+        for uri in [
+            "ipfs://Qm",
+            "https://example.com/1.json",
+            "data:application/json,{}",
+        ] {
+            assert!(MetadataBuilder::new().token_uri(uri).build().is_ok());
+        }
+        assert_eq!(
+            MetadataBuilder::new()
+                .token_uri("http://example.com")
+                .build(),
+            Err(MetadataError::InvalidUriScheme {
+                field: "token_uri".to_string(),
+                uri: "http://example.com".to_string()
+            })
+        );
+        assert!(MetadataBuilder::new()
+            .token_uri("no scheme")
+            .build()
+            .is_err());
+
+        let builder = MetadataBuilder::new().allowed_schemes(&["AR"]);
+        assert!(builder.clone().token_uri("ar://tx").build().is_ok());
+        assert!(builder.token_uri("ipfs://Qm").build().is_err());
+
+        let extension = Extension {
+            image: Some("https://example.com/1.png".to_string()),
+            media: Some(vec![MediaFile {
+                url: "ftp://example.com/1.mp4".to_string(),
+                ..MediaFile::default()
+            }]),
+            ..Extension::default()
+        };
+        assert_eq!(
+            MetadataBuilder::new().extension(extension).build(),
+            Err(MetadataError::InvalidUriScheme {
+                field: "media".to_string(),
+                uri: "ftp://example.com/1.mp4".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_attribute_limits() {
+        let extension = Extension {
+            attributes: Some(vec![attribute("a"), attribute("bb"), attribute("ccc")]),
+            ..Extension::default()
+        };
+
+        let metadata = MetadataBuilder::new()
+            .extension(extension.clone())
+            .build()
+            .unwrap();
+        assert_eq!(metadata.extension, Some(extension.clone()));
+
+        assert_eq!(
+            MetadataBuilder::new()
+                .max_attributes(2)
+                .extension(extension.clone())
+                .build(),
+            Err(MetadataError::TooManyAttributes { count: 3, max: 2 })
+        );
+        assert_eq!(
+            MetadataBuilder::new()
+                .max_attribute_len(2)
+                .extension(extension)
+                .build(),
+            Err(MetadataError::AttributeTooLong { index: 2, max: 2 })
+        );
+
+        let err: StdError = MetadataError::TooManyAttributes { count: 3, max: 2 }.into();
+        assert_eq!(
+            err,
+            StdError::generic_err("3 attributes exceed the maximum of 2")
+        );
+    }
+}
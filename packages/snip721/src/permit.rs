@@ -0,0 +1,465 @@
+//! Authenticating SNIP-721 queries with SNIP-24 permits instead of viewing keys.
+//!
+//! A permitted query is sent as `{"with_permit": {"permit": ..., "query": ...}}`, where `query`
+//! is one of [`QueryWithPermit`]. The querier is the signer of the permit, so the queries take no
+//! [`ViewerInfo`](crate::ViewerInfo) or viewing key. SNIP-721 contracts expect permits with the
+//! `owner` permission.
+
+use core::fmt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, CustomQuery, QuerierWrapper, QueryRequest, StdError, StdResult, WasmQuery,
+};
+
+use crate::metadata::Metadata;
+use crate::query::{
+    BatchNftDossierElement, BatchNftDossierResponse, InventoryApprovals,
+    InventoryApprovalsResponse, NftDossier, NftDossierResponse, OwnerOf, OwnerOfResponse,
+    PrivateMetadataResponse, TokenList, TokenListResponse, TransactionHistory,
+    TransactionHistoryResponse,
+};
+use secret_toolkit_permit::Permit;
+use secret_toolkit_utils::{space_pad, QueryError};
+
+/// SNIP-721 queries authenticated by a permit
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    /// display the owner of the specified token, like [`OwnerOf`](crate::QueryMsg::OwnerOf)
+    OwnerOf {
+        token_id: String,
+        /// optionally include expired [Approvals](crate::Cw721Approval) in the response list
+        include_expired: Option<bool>,
+    },
+    /// displays the token's private [`Metadata`](Metadata)
+    PrivateMetadata { token_id: String },
+    /// displays all the information about a token that the permit signer has permission to see
+    NftDossier {
+        token_id: String,
+        /// optionally include expired [`Approvals`](crate::Snip721Approval) in the response list
+        include_expired: Option<bool>,
+    },
+    /// displays all the information about multiple tokens that the permit signer has
+    /// permission to see. This query is optional, so not every SNIP-721 contract supports it
+    BatchNftDossier {
+        token_ids: Vec<String>,
+        /// optionally include expired [`Approvals`](crate::Snip721Approval) in the response list
+        include_expired: Option<bool>,
+    },
+    /// list all the inventory-wide [`Approvals`](crate::Snip721Approval) of the permit signer
+    InventoryApprovals {
+        /// optionally include expired [`Approvals`](crate::Snip721Approval) in the response list
+        include_expired: Option<bool>,
+    },
+    /// displays a list of all the tokens belonging to the input owner in which the permit
+    /// signer has view_owner permission
+    Tokens {
+        owner: String,
+        /// optionally display only token ids that come after the input String in
+        /// lexicographical order
+        start_after: Option<String>,
+        /// optional number of token ids to display
+        limit: Option<u32>,
+    },
+    /// display the transaction history of the permit signer in reverse chronological order
+    TransactionHistory {
+        /// optional page to display
+        page: Option<u32>,
+        /// optional number of transactions per page
+        page_size: Option<u32>,
+    },
+}
+
+impl fmt::Display for QueryWithPermit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryWithPermit::OwnerOf { .. } => write!(f, "OwnerOf"),
+            QueryWithPermit::PrivateMetadata { .. } => write!(f, "PrivateMetadata"),
+            QueryWithPermit::NftDossier { .. } => write!(f, "NftDossier"),
+            QueryWithPermit::BatchNftDossier { .. } => write!(f, "BatchNftDossier"),
+            QueryWithPermit::InventoryApprovals { .. } => write!(f, "InventoryApprovals"),
+            QueryWithPermit::Tokens { .. } => write!(f, "Tokens"),
+            QueryWithPermit::TransactionHistory { .. } => write!(f, "TransactionHistory"),
+        }
+    }
+}
+
+/// the `with_permit` envelope of a [`QueryWithPermit`]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermitQueryMsg {
+    WithPermit {
+        permit: Permit,
+        query: QueryWithPermit,
+    },
+}
+
+impl fmt::Display for PermitQueryMsg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PermitQueryMsg::WithPermit { query, .. } => write!(f, "WithPermit({query})"),
+        }
+    }
+}
+
+impl PermitQueryMsg {
+    /// Returns a StdResult<T>, where T is the "Response" type that wraps the query answer
+    ///
+    /// # Arguments
+    ///
+    /// * `querier` - a reference to the Querier dependency of the querying contract
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `code_hash` - String holding the code hash of the contract being queried
+    /// * `contract_addr` - address of the contract being queried
+    pub fn query<C: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: QuerierWrapper<C>,
+        block_size: usize,
+        code_hash: String,
+        contract_addr: String,
+    ) -> StdResult<T> {
+        self.try_query(querier, block_size, code_hash, contract_addr)
+            .map_err(StdError::from)
+    }
+
+    /// Returns a Result<T, QueryError>, where T is the "Response" type that wraps the query
+    /// answer. Unlike `query`, the error keeps the error returned by the querier as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `querier` - a reference to the Querier dependency of the querying contract
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `code_hash` - String holding the code hash of the contract being queried
+    /// * `contract_addr` - address of the contract being queried
+    pub fn try_query<C: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: QuerierWrapper<C>,
+        mut block_size: usize,
+        code_hash: String,
+        contract_addr: String,
+    ) -> Result<T, QueryError> {
+        // can not have block size of 0
+        if block_size == 0 {
+            block_size = 1;
+        }
+        let query_error = |error| QueryError::new(self.to_string(), &contract_addr, error);
+        let mut msg = to_binary(self).map_err(query_error)?;
+        space_pad(&mut msg.0, block_size);
+        querier
+            .query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: contract_addr.clone(),
+                code_hash,
+                msg,
+            }))
+            .map_err(query_error)
+    }
+}
+
+impl QueryWithPermit {
+    /// Returns the query in its `with_permit` envelope
+    pub fn with_permit(self, permit: Permit) -> PermitQueryMsg {
+        PermitQueryMsg::WithPermit {
+            permit,
+            query: self,
+        }
+    }
+}
+
+/// Returns a StdResult<[`OwnerOf`](OwnerOf)> from performing [`OwnerOf`](QueryWithPermit::OwnerOf)
+/// query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the querier
+/// * `token_id` - ID of the token whose info is being requested
+/// * `include_expired` - Optionally include expired Approvals in the response list.  If
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+#[allow(clippy::too_many_arguments)]
+pub fn owner_of_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    token_id: String,
+    include_expired: Option<bool>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<OwnerOf> {
+    let answer: OwnerOfResponse = QueryWithPermit::OwnerOf {
+        token_id,
+        include_expired,
+    }
+    .with_permit(permit)
+    .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.owner_of)
+}
+
+/// Returns a StdResult<[`Metadata`](Metadata)> from performing
+/// [`PrivateMetadata`](QueryWithPermit::PrivateMetadata) query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the querier
+/// * `token_id` - ID of the token whose info is being requested
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+pub fn private_metadata_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    token_id: String,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<Metadata> {
+    let answer: PrivateMetadataResponse = QueryWithPermit::PrivateMetadata { token_id }
+        .with_permit(permit)
+        .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.private_metadata)
+}
+
+/// Returns a StdResult<[`NftDossier`](NftDossier)> from performing
+/// [`NftDossier`](QueryWithPermit::NftDossier) query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the querier
+/// * `token_id` - ID of the token whose info is being requested
+/// * `include_expired` - Optionally include expired Approvals in the response list.  If
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+#[allow(clippy::too_many_arguments)]
+pub fn nft_dossier_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    token_id: String,
+    include_expired: Option<bool>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<NftDossier> {
+    let answer: NftDossierResponse = QueryWithPermit::NftDossier {
+        token_id,
+        include_expired,
+    }
+    .with_permit(permit)
+    .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.nft_dossier)
+}
+
+/// Returns a StdResult<Vec<[`BatchNftDossierElement`](BatchNftDossierElement)>> from performing
+/// [`BatchNftDossier`](QueryWithPermit::BatchNftDossier) query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the querier
+/// * `token_ids` - IDs of the tokens whose info is being requested
+/// * `include_expired` - Optionally include expired Approvals in the response list.  If
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+#[allow(clippy::too_many_arguments)]
+pub fn batch_nft_dossier_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    token_ids: Vec<String>,
+    include_expired: Option<bool>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<Vec<BatchNftDossierElement>> {
+    let answer: BatchNftDossierResponse = QueryWithPermit::BatchNftDossier {
+        token_ids,
+        include_expired,
+    }
+    .with_permit(permit)
+    .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.batch_nft_dossier.nft_dossiers)
+}
+
+/// Returns a StdResult<[`InventoryApprovals`](InventoryApprovals)> from performing
+/// [`InventoryApprovals`](QueryWithPermit::InventoryApprovals) query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the owner of the inventory
+/// * `include_expired` - Optionally include expired Approvals in the response list.  If
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+pub fn inventory_approvals_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    include_expired: Option<bool>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<InventoryApprovals> {
+    let answer: InventoryApprovalsResponse =
+        QueryWithPermit::InventoryApprovals { include_expired }
+            .with_permit(permit)
+            .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.inventory_approvals)
+}
+
+/// Returns a StdResult<[`TokenList`](TokenList)> from performing [`Tokens`](QueryWithPermit::Tokens)
+/// query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the querier
+/// * `owner` - the address whose token inventory is being requested
+/// * `start_after` - Optionally display only token ids that come after this String in
+///   lexicographical order
+/// * `limit` - Optional u32 number of token ids to display
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+#[allow(clippy::too_many_arguments)]
+pub fn tokens_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<TokenList> {
+    let answer: TokenListResponse = QueryWithPermit::Tokens {
+        owner,
+        start_after,
+        limit,
+    }
+    .with_permit(permit)
+    .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.token_list)
+}
+
+/// Returns a StdResult<[`TransactionHistory`](TransactionHistory)> from performing
+/// [`TransactionHistory`](QueryWithPermit::TransactionHistory) query with a permit
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `permit` - the permit signed by the address whose history is being requested
+/// * `page` - Optional u32 page to display
+/// * `page_size` - Optional u32 number of transactions per page
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+pub fn transaction_history_query_with_permit<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    permit: Permit,
+    page: Option<u32>,
+    page_size: Option<u32>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<TransactionHistory> {
+    let answer: TransactionHistoryResponse =
+        QueryWithPermit::TransactionHistory { page, page_size }
+            .with_permit(permit)
+            .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.transaction_history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockQuerier;
+    use cosmwasm_std::{from_binary, Binary, ContractResult, Empty, SystemResult};
+    use secret_toolkit_permit::{PermitParams, PermitSignature, PubKey, TokenPermissions};
+    use serde_json::json;
+
+    fn permit() -> Permit {
+        Permit {
+            params: PermitParams {
+                allowed_tokens: vec!["secret1nft".to_string()],
+                permit_name: "wallet".to_string(),
+                chain_id: "secret-4".to_string(),
+                permissions: vec![TokenPermissions::Owner],
+            },
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: Binary::from(b"pubkey"),
+                },
+                signature: Binary::from(b"signature"),
+            },
+        }
+    }
+
+    #[test]
+    fn test_with_permit_envelope() -> StdResult<()> {
+        let msg = QueryWithPermit::NftDossier {
+            token_id: "card1".to_string(),
+            include_expired: None,
+        }
+        .with_permit(permit());
+        assert_eq!(msg.to_string(), "WithPermit(NftDossier)");
+
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(
+            json["with_permit"]["query"],
+            json!({"nft_dossier": {"token_id": "card1", "include_expired": null}})
+        );
+        assert_eq!(
+            json["with_permit"]["permit"]["params"]["permissions"],
+            json!(["owner"])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens_query_with_permit() -> StdResult<()> {
+        let mut querier: MockQuerier = MockQuerier::new(&[]);
+        querier.update_wasm(|request| {
+            let msg = match request {
+                WasmQuery::Smart { msg, .. } => msg,
+                _ => panic!("unexpected query"),
+            };
+            let msg: PermitQueryMsg = from_binary(msg).unwrap();
+            let tokens = match msg {
+                PermitQueryMsg::WithPermit {
+                    query: QueryWithPermit::Tokens { owner, .. },
+                    ..
+                } => vec![format!("{owner}'s token")],
+                _ => vec![],
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(
+                serde_json::to_vec(&json!({"token_list": {"tokens": tokens}})).unwrap(),
+            )))
+        });
+
+        let tokens = tokens_query_with_permit(
+            QuerierWrapper::<Empty>::new(&querier),
+            permit(),
+            "alice".to_string(),
+            None,
+            None,
+            256,
+            "code hash".to_string(),
+            "secret1nft".to_string(),
+        )?;
+        assert_eq!(tokens.tokens, vec!["alice's token".to_string()]);
+        Ok(())
+    }
+}
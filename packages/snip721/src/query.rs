@@ -8,7 +8,7 @@ use cosmwasm_std::{
 
 use crate::expiration::Expiration;
 use crate::metadata::Metadata;
-use secret_toolkit_utils::space_pad;
+use secret_toolkit_utils::{space_pad, QueryError};
 
 //
 // Structs Used for Input Parameters
@@ -136,6 +136,39 @@ pub struct NftDossier {
     pub inventory_approvals: Option<Vec<Snip721Approval>>,
 }
 
+/// the information about a token in the response of [`BatchNftDossier`](QueryMsg::BatchNftDossier)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchNftDossierElement {
+    pub token_id: String,
+    /// owner of the token if permitted to view it
+    pub owner: Option<String>,
+    /// the token's public metadata
+    pub public_metadata: Option<Metadata>,
+    /// the token's private metadata if permitted to view it
+    pub private_metadata: Option<Metadata>,
+    /// description of why private metadata is not displayed (if applicable)
+    pub display_private_metadata_error: Option<String>,
+    /// true if the owner is publicly viewable
+    pub owner_is_public: bool,
+    /// expiration of public display of ownership (if applicable)
+    pub public_ownership_expiration: Option<Expiration>,
+    /// true if private metadata is publicly viewable
+    pub private_metadata_is_public: bool,
+    /// expiration of public display of private metadata (if applicable)
+    pub private_metadata_is_public_expiration: Option<Expiration>,
+    /// approvals for this token (only viewable if queried by the owner)
+    pub token_approvals: Option<Vec<Snip721Approval>>,
+    /// approvals that apply to this token because they apply to all of
+    /// the owner's tokens (only viewable if queried by the owner)
+    pub inventory_approvals: Option<Vec<Snip721Approval>>,
+}
+
+/// response of [`BatchNftDossier`](QueryMsg::BatchNftDossier)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchNftDossier {
+    pub nft_dossiers: Vec<BatchNftDossierElement>,
+}
+
 /// response of [`TokenApprovals`](QueryMsg::TokenApprovals)
 ///
 /// list all the [`Approvals`](Snip721Approval) in place for a specified token if given the owner's viewing
@@ -274,7 +307,7 @@ pub struct VerifyTransferApproval {
 }
 
 /// SNIP-721 queries
-#[derive(Serialize, Clone, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     //
@@ -339,6 +372,17 @@ pub enum QueryMsg {
         /// false, expired [`Approvals`](Snip721Approval) will be filtered out of the response
         include_expired: Option<bool>,
     },
+    /// displays all the information about multiple tokens that the viewer has permission to
+    /// see, like [`NftDossier`](QueryMsg::NftDossier). This query is optional, so not every
+    /// SNIP-721 contract supports it
+    BatchNftDossier {
+        token_ids: Vec<String>,
+        /// optional address and key requesting to view the token information
+        viewer: Option<ViewerInfo>,
+        /// optionally include expired [`Approvals`](Snip721Approval) in the response list.  If ommitted or
+        /// false, expired [`Approvals`](Snip721Approval) will be filtered out of the response
+        include_expired: Option<bool>,
+    },
     /// list all the [`Approvals`](Snip721Approval) in place for a specified token if given the owner's viewing
     /// key
     TokenApprovals {
@@ -428,6 +472,7 @@ impl fmt::Display for QueryMsg {
             QueryMsg::AllNftInfo { .. } => write!(f, "AllNftInfo"),
             QueryMsg::PrivateMetadata { .. } => write!(f, "PrivateMetadata"),
             QueryMsg::NftDossier { .. } => write!(f, "NftDossier"),
+            QueryMsg::BatchNftDossier { .. } => write!(f, "BatchNftDossier"),
             QueryMsg::TokenApprovals { .. } => write!(f, "TokenApprovals"),
             QueryMsg::ApprovedForAll { .. } => write!(f, "ApprovedForAll"),
             QueryMsg::InventoryApprovals { .. } => write!(f, "InventoryApprovals"),
@@ -452,23 +497,44 @@ impl QueryMsg {
     pub fn query<C: CustomQuery, T: DeserializeOwned>(
         &self,
         querier: QuerierWrapper<C>,
-        mut block_size: usize,
+        block_size: usize,
         code_hash: String,
         contract_addr: String,
     ) -> StdResult<T> {
+        self.try_query(querier, block_size, code_hash, contract_addr)
+            .map_err(StdError::from)
+    }
+
+    /// Returns a Result<T, QueryError>, where T is the "Response" type that wraps the query
+    /// answer. Unlike `query`, the error keeps the error returned by the querier as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `querier` - a reference to the Querier dependency of the querying contract
+    /// * `block_size` - pad the message to blocks of this size
+    /// * `code_hash` - String holding the code hash of the contract being queried
+    /// * `contract_addr` - address of the contract being queried
+    pub fn try_query<C: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: QuerierWrapper<C>,
+        mut block_size: usize,
+        code_hash: String,
+        contract_addr: String,
+    ) -> Result<T, QueryError> {
         // can not have block size of 0
         if block_size == 0 {
             block_size = 1;
         }
-        let mut msg = to_binary(self)?;
+        let query_error = |error| QueryError::new(self.to_string(), &contract_addr, error);
+        let mut msg = to_binary(self).map_err(query_error)?;
         space_pad(&mut msg.0, block_size);
         querier
             .query(&QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr,
+                contract_addr: contract_addr.clone(),
                 code_hash,
                 msg,
             }))
-            .map_err(|err| StdError::generic_err(format!("Error performing {self} query: {err}")))
+            .map_err(query_error)
     }
 }
 
@@ -520,6 +586,12 @@ pub struct NftDossierResponse {
     pub nft_dossier: NftDossier,
 }
 
+/// wrapper to deserialize [`BatchNftDossier`](BatchNftDossier) responses
+#[derive(Serialize, Deserialize)]
+pub struct BatchNftDossierResponse {
+    pub batch_nft_dossier: BatchNftDossier,
+}
+
 /// wrapper to deserialize [`TokenApprovals`](TokenApprovals) responses
 #[derive(Serialize, Deserialize)]
 pub struct TokenApprovalsResponse {
@@ -609,7 +681,7 @@ pub fn num_tokens_query<C: CustomQuery>(
 /// * `querier` - a reference to the Querier dependency of the querying contract
 /// * `viewer` - Optional ViewerInfo holding the address and viewing key of the querier
 /// * `start_after` - Optionally display only token ids that come after this String in
-///                   lexicographical order
+///   lexicographical order
 /// * `limit` - Optional u32 number of token ids to display
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
@@ -640,8 +712,8 @@ pub fn all_tokens_query<C: CustomQuery>(
 /// * `token_id` - ID of the token whose info is being requested
 /// * `viewer` - Optional ViewerInfo holding the address and viewing key of the querier
 /// * `include_expired` - Optionally include expired Approvals in the response list.  If
-///                       ommitted or false, expired Approvals will be filtered out of
-///                       the response
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
 /// * `contract_addr` - address of the contract being queried
@@ -692,8 +764,8 @@ pub fn nft_info_query<C: CustomQuery>(
 /// * `token_id` - ID of the token whose info is being requested
 /// * `viewer` - Optional ViewerInfo holding the address and viewing key of the querier
 /// * `include_expired` - Optionally include expired Approvals in the response list.  If
-///                       ommitted or false, expired Approvals will be filtered out of
-///                       the response
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
 /// * `contract_addr` - address of the contract being queried
@@ -750,8 +822,8 @@ pub fn private_metadata_query<C: CustomQuery>(
 /// * `token_id` - ID of the token whose info is being requested
 /// * `viewer` - Optional ViewerInfo holding the address and viewing key of the querier
 /// * `include_expired` - Optionally include expired Approvals in the response list.  If
-///                       ommitted or false, expired Approvals will be filtered out of
-///                       the response
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
 /// * `contract_addr` - address of the contract being queried
@@ -773,6 +845,91 @@ pub fn nft_dossier_query<C: CustomQuery>(
     Ok(answer.nft_dossier)
 }
 
+/// Returns a StdResult<Vec<[`BatchNftDossierElement`](BatchNftDossierElement)>> from performing
+/// [`BatchNftDossier`](QueryMsg::BatchNftDossier) query
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `token_ids` - IDs of the tokens whose info is being requested
+/// * `viewer` - Optional ViewerInfo holding the address and viewing key of the querier
+/// * `include_expired` - Optionally include expired Approvals in the response list.  If
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+pub fn batch_nft_dossier_query<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    token_ids: Vec<String>,
+    viewer: Option<ViewerInfo>,
+    include_expired: Option<bool>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<Vec<BatchNftDossierElement>> {
+    let answer: BatchNftDossierResponse = QueryMsg::BatchNftDossier {
+        token_ids,
+        viewer,
+        include_expired,
+    }
+    .query(querier, block_size, code_hash, contract_addr)?;
+    Ok(answer.batch_nft_dossier.nft_dossiers)
+}
+
+/// Returns the public [`Metadata`](crate::metadata::Metadata) of each token, along with its ID,
+/// in the order of `token_ids`.
+///
+/// The metadata are fetched with a single [`BatchNftDossier`](QueryMsg::BatchNftDossier) query
+/// if the contract supports it. If it doesn't, or if the batch query fails, e.g. because one of
+/// the tokens doesn't exist, each token is queried with [`NftInfo`](QueryMsg::NftInfo) instead,
+/// so that a missing token only fails its own entry.
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `token_ids` - IDs of the tokens whose info is being requested
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+pub fn batch_nft_info_query<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    token_ids: Vec<String>,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> Vec<(String, StdResult<Metadata>)> {
+    let mut dossiers = batch_nft_dossier_query(
+        querier,
+        token_ids.clone(),
+        None,
+        None,
+        block_size,
+        code_hash.clone(),
+        contract_addr.clone(),
+    )
+    .unwrap_or_default();
+    token_ids
+        .into_iter()
+        .map(|token_id| {
+            let metadata = match dossiers.iter().position(|d| d.token_id == token_id) {
+                Some(pos) => Ok(dossiers
+                    .swap_remove(pos)
+                    .public_metadata
+                    .unwrap_or_default()),
+                None => nft_info_query(
+                    querier,
+                    token_id.clone(),
+                    block_size,
+                    code_hash.clone(),
+                    contract_addr.clone(),
+                ),
+            };
+            (token_id, metadata)
+        })
+        .collect()
+}
+
 /// Returns a StdResult<[`TokenApprovals`](TokenApprovals)> from performing [`TokenApprovals`](QueryMsg::TokenApprovals) query
 ///
 /// # Arguments
@@ -781,8 +938,8 @@ pub fn nft_dossier_query<C: CustomQuery>(
 /// * `token_id` - ID of the token whose info is being requested
 /// * `viewing_key` - String holding the viewing key of the token's owner
 /// * `include_expired` - Optionally include expired Approvals in the response list.  If
-///                       ommitted or false, expired Approvals will be filtered out of
-///                       the response
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
 /// * `contract_addr` - address of the contract being queried
@@ -812,8 +969,8 @@ pub fn token_approvals_query<C: CustomQuery>(
 /// * `owner` - the address whose approvals are being requested
 /// * `viewing_key` - Optional String holding the viewing key of the owner
 /// * `include_expired` - Optionally include expired Approvals in the response list.  If
-///                       ommitted or false, expired Approvals will be filtered out of
-///                       the response
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
 /// * `contract_addr` - address of the contract being queried
@@ -843,8 +1000,8 @@ pub fn approved_for_all_query<C: CustomQuery>(
 /// * `address` - the address whose approvals are being requested
 /// * `viewing_key` - String holding the viewing key of the specified address
 /// * `include_expired` - Optionally include expired Approvals in the response list.  If
-///                       ommitted or false, expired Approvals will be filtered out of
-///                       the response
+///   ommitted or false, expired Approvals will be filtered out of
+///   the response
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
 /// * `contract_addr` - address of the contract being queried
@@ -875,7 +1032,7 @@ pub fn inventory_approvals_query<C: CustomQuery>(
 /// * `viewer` - Optional address of the querier if different from the owner
 /// * `viewing_key` - Optional String holding the viewing key of the querier
 /// * `start_after` - Optionally display only token ids that come after this String in
-///                   lexicographical order
+///   lexicographical order
 /// * `limit` - Optional u32 number of token ids to display
 /// * `block_size` - pad the message to blocks of this size
 /// * `code_hash` - String holding the code hash of the contract being queried
@@ -1011,7 +1168,8 @@ mod tests {
 
     use super::*;
     use cosmwasm_std::{
-        to_vec, ContractResult, Empty, Querier, QuerierResult, SystemError, SystemResult,
+        from_slice, to_vec, ContractResult, Empty, Querier, QuerierResult, SystemError,
+        SystemResult,
     };
 
     macro_rules! try_querier_result {
@@ -2420,4 +2578,97 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_batch_nft_info_query() {
+        /// answers the queries of the tokens "a" and "b", with the batch query if it is supported
+        struct MyMockQuerier {
+            supports_batch: bool,
+        }
+
+        fn metadata(token_id: &str) -> Metadata {
+            Metadata {
+                token_uri: Some(format!("ipfs://{token_id}")),
+                extension: None,
+            }
+        }
+
+        impl Querier for MyMockQuerier {
+            fn raw_query(&self, request: &[u8]) -> QuerierResult {
+                let QueryRequest::<Empty>::Wasm(WasmQuery::Smart { msg, .. }) =
+                    from_slice(request).unwrap()
+                else {
+                    panic!("expected a smart query");
+                };
+                let msg: QueryMsg =
+                    from_slice(String::from_utf8(msg.0).unwrap().trim_end().as_bytes()).unwrap();
+                let response = match msg {
+                    QueryMsg::BatchNftDossier { token_ids, .. } if self.supports_batch => {
+                        // the batch query fails if any of the tokens doesn't exist
+                        if token_ids.iter().any(|id| id != "a" && id != "b") {
+                            return SystemResult::Ok(ContractResult::Err(
+                                "token not found".to_string(),
+                            ));
+                        }
+                        let nft_dossiers = token_ids
+                            .iter()
+                            .map(|token_id| BatchNftDossierElement {
+                                token_id: token_id.clone(),
+                                owner: None,
+                                public_metadata: Some(metadata(token_id)),
+                                private_metadata: None,
+                                display_private_metadata_error: None,
+                                owner_is_public: false,
+                                public_ownership_expiration: None,
+                                private_metadata_is_public: false,
+                                private_metadata_is_public_expiration: None,
+                                token_approvals: None,
+                                inventory_approvals: None,
+                            })
+                            .collect();
+                        to_binary(&BatchNftDossierResponse {
+                            batch_nft_dossier: BatchNftDossier { nft_dossiers },
+                        })
+                    }
+                    QueryMsg::NftInfo { token_id } if token_id == "a" || token_id == "b" => {
+                        to_binary(&NftInfoResponse {
+                            nft_info: metadata(&token_id),
+                        })
+                    }
+                    QueryMsg::NftInfo { .. } => {
+                        return SystemResult::Ok(ContractResult::Err("token not found".to_string()))
+                    }
+                    _ => return SystemResult::Ok(ContractResult::Err("unknown query".to_string())),
+                };
+                SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+            }
+        }
+
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        for supports_batch in [true, false] {
+            let querier = MyMockQuerier { supports_batch };
+            let results = batch_nft_info_query(
+                QuerierWrapper::<Empty>::new(&querier),
+                ids(&["b", "missing", "a"]),
+                256,
+                "code hash".to_string(),
+                "contract".to_string(),
+            );
+            assert_eq!(results.len(), 3);
+            assert_eq!(results[0].0, "b");
+            assert_eq!(results[0].1.as_ref().unwrap(), &metadata("b"));
+            assert_eq!(results[1].0, "missing");
+            assert!(results[1].1.is_err());
+            assert_eq!(results[2].1.as_ref().unwrap(), &metadata("a"));
+
+            let results = batch_nft_info_query(
+                QuerierWrapper::<Empty>::new(&querier),
+                ids(&["a", "b"]),
+                256,
+                "code hash".to_string(),
+                "contract".to_string(),
+            );
+            assert!(results.iter().all(|(_, metadata)| metadata.is_ok()));
+        }
+    }
 }
@@ -0,0 +1,142 @@
+use cosmwasm_std::{Binary, Reply, StdError, StdResult, SubMsg, SubMsgResult};
+
+use crate::events::{CONTRACT_ADDRESS_KEY, WASM_EVENT};
+use crate::send_nft_msg;
+
+/// Returns a StdResult<SubMsg> used to execute [`SendNft`](crate::HandleMsg::SendNft), with a
+/// reply that should be checked with [`assert_receive_confirmed`] before updating any state
+/// that depends on the receiving contract having processed the token.
+///
+/// # Arguments
+///
+/// * `contract` - the address of the contract the token is sent to
+/// * `token_id` - ID String of the token to send
+/// * `msg` - Optional base64 encoded message to pass to the recipient contract's
+///   (Batch)ReceiveNft function
+/// * `memo` - Optional String memo for the tx
+/// * `reply_id` - the id of the reply
+/// * `block_size` - pad the message to blocks of this size
+/// * `code_hash` - String holding the code hash of the contract being called
+/// * `contract_addr` - address of the contract being called
+#[allow(clippy::too_many_arguments)]
+pub fn safe_transfer_nft(
+    contract: String,
+    token_id: String,
+    msg: Option<Binary>,
+    memo: Option<String>,
+    reply_id: u64,
+    block_size: usize,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<SubMsg> {
+    let send = send_nft_msg(
+        contract,
+        token_id,
+        msg,
+        memo,
+        None,
+        block_size,
+        code_hash,
+        contract_addr,
+    )?;
+    Ok(SubMsg::reply_always(send, reply_id))
+}
+
+/// Checks the reply of a [`safe_transfer_nft`] sub-message, and returns an error unless the
+/// transfer succeeded and the receiving contract was executed, which means its
+/// (Batch)ReceiveNft handler processed the token without failing.
+///
+/// A SNIP-721 contract only calls the recipient if it registered its ReceiveNft handler, so
+/// a successful transfer alone does not prove the token was processed.
+///
+/// # Arguments
+///
+/// * `reply` - the reply of the sub-message
+/// * `recipient` - the address of the contract the token was sent to
+pub fn assert_receive_confirmed(reply: &Reply, recipient: &str) -> StdResult<()> {
+    let response = match &reply.result {
+        SubMsgResult::Ok(response) => response,
+        SubMsgResult::Err(err) => {
+            return Err(StdError::generic_err(format!("SendNft failed: {err}")));
+        }
+    };
+
+    let received = response.events.iter().any(|event| {
+        event.ty == WASM_EVENT
+            && event
+                .attributes
+                .iter()
+                .any(|attr| attr.key == CONTRACT_ADDRESS_KEY && attr.value == recipient)
+    });
+    if !received {
+        return Err(StdError::generic_err(format!(
+            "SendNft was not processed by {recipient}"
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{CosmosMsg, Event, ReplyOn, SubMsgResponse, WasmMsg};
+
+    fn reply(result: SubMsgResult) -> Reply {
+        Reply { id: 7, result }
+    }
+
+    fn wasm_event(contract: &str) -> Event {
+        Event::new(WASM_EVENT).add_attribute(CONTRACT_ADDRESS_KEY, contract)
+    }
+
+    #[test]
+    fn test_safe_transfer_nft() -> StdResult<()> {
+        let sub_msg = safe_transfer_nft(
+            "escrow".to_string(),
+            "NFT1".to_string(),
+            None,
+            None,
+            7,
+            256,
+            "code hash".to_string(),
+            "nft".to_string(),
+        )?;
+
+        assert_eq!(sub_msg.id, 7);
+        assert_eq!(sub_msg.reply_on, ReplyOn::Always);
+        match sub_msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) => {
+                assert_eq!(contract_addr, "nft")
+            }
+            other => panic!("unexpected CosmosMsg variant: {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assert_receive_confirmed() {
+        let confirmed = reply(SubMsgResult::Ok(SubMsgResponse {
+            events: vec![wasm_event("nft"), wasm_event("escrow")],
+            data: None,
+        }));
+        assert!(assert_receive_confirmed(&confirmed, "escrow").is_ok());
+
+        // the token was transferred, but the recipient was never called
+        let not_received = reply(SubMsgResult::Ok(SubMsgResponse {
+            events: vec![wasm_event("nft")],
+            data: None,
+        }));
+        assert_eq!(
+            assert_receive_confirmed(&not_received, "escrow"),
+            Err(StdError::generic_err("SendNft was not processed by escrow"))
+        );
+
+        let failed = reply(SubMsgResult::Err("receiver failed".to_string()));
+        assert_eq!(
+            assert_receive_confirmed(&failed, "escrow"),
+            Err(StdError::generic_err("SendNft failed: receiver failed"))
+        );
+    }
+}
@@ -0,0 +1,757 @@
+//! A minimal in-memory SNIP-721 contract, to run end-to-end tests of marketplace, staking and
+//! other contracts that hold NFTs, without deploying the real SNIP-721 code.
+//!
+//! `instantiate`, `execute` and `query` have the signatures of contract entry points, so they
+//! can be wrapped in the `ContractWrapper` of multi-test frameworks, or called directly in unit
+//! tests. The contract supports minting, burning, transfers, sends (including the
+//! (Batch)ReceiveNft callback), token and inventory-wide transfer approvals and viewing keys,
+//! along with the `ContractInfo`, `NumTokens`, `OwnerOf`, `NftInfo`, `PrivateMetadata`,
+//! `NftDossier`, `Tokens` and `Minters` queries.
+//!
+//! Unlike the reference implementation, the private metadata can only be viewed by the owner,
+//! and the owner of a token can only be viewed by the owner and by the addresses approved to
+//! transfer it, unless the contract is instantiated with public ownership.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Storage, WasmMsg,
+};
+
+use secret_toolkit_serialization::Bincode2;
+use secret_toolkit_storage::{Item, Keymap, KeymapBuilder, WithoutIter};
+use secret_toolkit_utils::router::variant_name;
+
+use crate::{
+    ContractInfo, Cw721Approval, Expiration, HandleMsg, Metadata, Minters, NftDossier, NumTokens,
+    OwnerOf, QueryMsg, Snip721Approval, TokenList, ViewerInfo,
+};
+
+static CONFIG: Item<MockConfig> = Item::new(b"mock_snip721_config");
+// The keymaps don't support iteration, so they don't cache a length that would be shared by the
+// mock contracts of different tests.
+static VIEWING_KEYS: Keymap<String, String, Bincode2, WithoutIter> =
+    KeymapBuilder::new(b"mock_snip721_viewing_keys")
+        .without_iter()
+        .build();
+static RECEIVERS: Keymap<String, MockReceiver, Bincode2, WithoutIter> =
+    KeymapBuilder::new(b"mock_snip721_receivers")
+        .without_iter()
+        .build();
+
+// The keymaps that are iterated are created for each use, as their cached length would be
+// shared too.
+fn tokens() -> Keymap<'static, String, MockToken> {
+    Keymap::new(b"mock_snip721_tokens")
+}
+
+fn operators() -> Keymap<'static, (String, String), Expiration> {
+    Keymap::new(b"mock_snip721_operators")
+}
+
+/// Instantiation message of the mock contract
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct MockSnip721InitMsg {
+    pub name: String,
+    pub symbol: String,
+    /// the addresses allowed to mint
+    pub minters: Vec<String>,
+    /// whether anyone can view the owners of the tokens
+    #[serde(default)]
+    pub public_owners: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockConfig {
+    name: String,
+    symbol: String,
+    minters: Vec<String>,
+    public_owners: bool,
+    /// the number of tokens minted, used as the id of tokens minted without one
+    mint_count: u32,
+    /// the number of tokens that were not burnt
+    token_count: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockToken {
+    owner: String,
+    public_metadata: Option<Metadata>,
+    private_metadata: Option<Metadata>,
+    /// the addresses approved to transfer the token
+    approvals: Vec<(String, Expiration)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockReceiver {
+    code_hash: String,
+    batch: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverHandleMsg {
+    ReceiveNft {
+        sender: String,
+        token_id: String,
+        msg: Option<Binary>,
+    },
+    BatchReceiveNft {
+        sender: String,
+        from: String,
+        token_ids: Vec<String>,
+        msg: Option<Binary>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryAnswer {
+    ContractInfo(ContractInfo),
+    NumTokens(NumTokens),
+    OwnerOf(OwnerOf),
+    NftInfo(Metadata),
+    PrivateMetadata(Metadata),
+    NftDossier(Box<NftDossier>),
+    TokenList(TokenList),
+    Minters(Minters),
+}
+
+/// Instantiates the mock contract
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MockSnip721InitMsg,
+) -> StdResult<Response> {
+    CONFIG.save(
+        deps.storage,
+        &MockConfig {
+            name: msg.name,
+            symbol: msg.symbol,
+            minters: msg.minters,
+            public_owners: msg.public_owners,
+            mint_count: 0,
+            token_count: 0,
+        },
+    )?;
+    Ok(Response::new())
+}
+
+/// Handles the SNIP-721 messages supported by the mock contract
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: HandleMsg) -> StdResult<Response> {
+    let sender = info.sender.to_string();
+    let mut response = Response::new().set_data(status_answer(&msg)?);
+    match msg {
+        HandleMsg::TransferNft {
+            recipient,
+            token_id,
+            ..
+        } => transfer(deps.storage, &env.block, &sender, &recipient, &token_id)?,
+        HandleMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+            ..
+        } => {
+            let owner = load_token(deps.storage, &token_id)?.owner;
+            transfer(deps.storage, &env.block, &sender, &contract, &token_id)?;
+            if let Some(receiver) = RECEIVERS.get(deps.storage, &contract) {
+                let receive = if receiver.batch {
+                    ReceiverHandleMsg::BatchReceiveNft {
+                        sender,
+                        from: owner,
+                        token_ids: vec![token_id],
+                        msg,
+                    }
+                } else {
+                    ReceiverHandleMsg::ReceiveNft {
+                        sender: owner,
+                        token_id,
+                        msg,
+                    }
+                };
+                response = response.add_message(WasmMsg::Execute {
+                    contract_addr: contract,
+                    code_hash: receiver.code_hash,
+                    msg: to_binary(&receive)?,
+                    funds: vec![],
+                });
+            }
+        }
+        HandleMsg::Approve {
+            spender,
+            token_id,
+            expires,
+            ..
+        } => {
+            let mut token = load_token(deps.storage, &token_id)?;
+            check_can_transfer(deps.storage, &env.block, &sender, &token_id, &token)?;
+            token.approvals.retain(|(address, _)| *address != spender);
+            token
+                .approvals
+                .push((spender, expires.unwrap_or(Expiration::Never)));
+            tokens().insert(deps.storage, &token_id, &token)?;
+        }
+        HandleMsg::Revoke {
+            spender, token_id, ..
+        } => {
+            let mut token = load_token(deps.storage, &token_id)?;
+            check_can_transfer(deps.storage, &env.block, &sender, &token_id, &token)?;
+            token.approvals.retain(|(address, _)| *address != spender);
+            tokens().insert(deps.storage, &token_id, &token)?;
+        }
+        HandleMsg::ApproveAll {
+            operator, expires, ..
+        } => operators().insert(
+            deps.storage,
+            &(sender, operator),
+            &expires.unwrap_or(Expiration::Never),
+        )?,
+        HandleMsg::RevokeAll { operator, .. } => {
+            operators().remove(deps.storage, &(sender, operator))?
+        }
+        HandleMsg::RegisterReceiveNft {
+            code_hash,
+            also_implements_batch_receive_nft,
+            ..
+        } => RECEIVERS.insert(
+            deps.storage,
+            &sender,
+            &MockReceiver {
+                code_hash,
+                batch: also_implements_batch_receive_nft.unwrap_or(false),
+            },
+        )?,
+        HandleMsg::SetViewingKey { key, .. } => VIEWING_KEYS.insert(deps.storage, &sender, &key)?,
+        HandleMsg::MintNft {
+            token_id,
+            owner,
+            public_metadata,
+            private_metadata,
+            ..
+        } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            if !config.minters.contains(&sender) {
+                return Err(StdError::generic_err(
+                    "Only designated minters are allowed to mint",
+                ));
+            }
+            let token_id = token_id.unwrap_or_else(|| config.mint_count.to_string());
+            if tokens().contains(deps.storage, &token_id) {
+                return Err(StdError::generic_err(format!(
+                    "Token ID {token_id} is already in use"
+                )));
+            }
+            let token = MockToken {
+                owner: owner.unwrap_or(sender),
+                public_metadata,
+                private_metadata,
+                approvals: vec![],
+            };
+            tokens().insert(deps.storage, &token_id, &token)?;
+            config.mint_count += 1;
+            config.token_count += 1;
+            CONFIG.save(deps.storage, &config)?;
+        }
+        HandleMsg::BurnNft { token_id, .. } => {
+            let token = load_token(deps.storage, &token_id)?;
+            check_can_transfer(deps.storage, &env.block, &sender, &token_id, &token)?;
+            tokens().remove(deps.storage, &token_id)?;
+            let mut config = CONFIG.load(deps.storage)?;
+            config.token_count -= 1;
+            CONFIG.save(deps.storage, &config)?;
+        }
+        msg => {
+            return Err(StdError::generic_err(format!(
+                "mock snip721: unsupported message '{}'",
+                variant_name(&msg)?
+            )))
+        }
+    }
+
+    Ok(response)
+}
+
+/// Answers the SNIP-721 queries supported by the mock contract
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let answer = match msg {
+        QueryMsg::ContractInfo {} => {
+            let config = CONFIG.load(deps.storage)?;
+            QueryAnswer::ContractInfo(ContractInfo {
+                name: config.name,
+                symbol: config.symbol,
+            })
+        }
+        QueryMsg::NumTokens { .. } => QueryAnswer::NumTokens(NumTokens {
+            count: CONFIG.load(deps.storage)?.token_count,
+        }),
+        QueryMsg::Minters {} => QueryAnswer::Minters(Minters {
+            minters: CONFIG.load(deps.storage)?.minters,
+        }),
+        QueryMsg::OwnerOf {
+            token_id, viewer, ..
+        } => {
+            let token = load_token(deps.storage, &token_id)?;
+            let viewer = authenticate(deps.storage, viewer)?;
+            if !can_view_owner(deps.storage, &env.block, viewer.as_deref(), &token)? {
+                return Err(StdError::generic_err(format!(
+                    "You are not authorized to view the owner of token {token_id}"
+                )));
+            }
+            let approvals = if viewer.as_deref() == Some(token.owner.as_str()) {
+                token
+                    .approvals
+                    .iter()
+                    .map(|(spender, expires)| Cw721Approval {
+                        spender: spender.clone(),
+                        expires: *expires,
+                    })
+                    .collect()
+            } else {
+                vec![]
+            };
+            QueryAnswer::OwnerOf(OwnerOf {
+                owner: Some(token.owner),
+                approvals,
+            })
+        }
+        QueryMsg::NftInfo { token_id } => QueryAnswer::NftInfo(
+            load_token(deps.storage, &token_id)?
+                .public_metadata
+                .unwrap_or_default(),
+        ),
+        QueryMsg::PrivateMetadata { token_id, viewer } => {
+            let token = load_token(deps.storage, &token_id)?;
+            let viewer = authenticate(deps.storage, viewer)?;
+            if viewer.as_deref() != Some(token.owner.as_str()) {
+                return Err(StdError::generic_err(format!(
+                    "You are not authorized to view the private metadata of token {token_id}"
+                )));
+            }
+            QueryAnswer::PrivateMetadata(token.private_metadata.unwrap_or_default())
+        }
+        QueryMsg::NftDossier {
+            token_id, viewer, ..
+        } => {
+            let token = load_token(deps.storage, &token_id)?;
+            let viewer = authenticate(deps.storage, viewer)?;
+            let public_owners = CONFIG.load(deps.storage)?.public_owners;
+            let is_owner = viewer.as_deref() == Some(token.owner.as_str());
+            let can_view_owner =
+                can_view_owner(deps.storage, &env.block, viewer.as_deref(), &token)?;
+
+            let (private_metadata, display_private_metadata_error) = if is_owner {
+                (token.private_metadata, None)
+            } else {
+                (
+                    None,
+                    Some(format!(
+                        "You are not authorized to view the private metadata of token {token_id}"
+                    )),
+                )
+            };
+            let (token_approvals, inventory_approvals) = if is_owner {
+                let token_approvals = token
+                    .approvals
+                    .iter()
+                    .map(|(address, expires)| transfer_approval(address, expires))
+                    .collect();
+                (
+                    Some(token_approvals),
+                    Some(inventory_approvals(deps.storage, &token.owner)?),
+                )
+            } else {
+                (None, None)
+            };
+
+            QueryAnswer::NftDossier(Box::new(NftDossier {
+                owner: can_view_owner.then(|| token.owner.clone()),
+                public_metadata: token.public_metadata,
+                private_metadata,
+                display_private_metadata_error,
+                owner_is_public: public_owners,
+                public_ownership_expiration: public_owners.then_some(Expiration::Never),
+                private_metadata_is_public: false,
+                private_metadata_is_public_expiration: None,
+                token_approvals,
+                inventory_approvals,
+            }))
+        }
+        QueryMsg::Tokens {
+            owner,
+            viewer,
+            viewing_key,
+            start_after,
+            limit,
+        } => {
+            let viewer = viewer.unwrap_or_else(|| owner.clone());
+            let authenticated = match viewing_key {
+                Some(key) => {
+                    check_viewing_key(deps.storage, &viewer, &key)?;
+                    Some(viewer)
+                }
+                None => None,
+            };
+            let mut owned = vec![];
+            for entry in tokens().iter(deps.storage)? {
+                let (token_id, token) = entry?;
+                if token.owner == owner
+                    && start_after.as_ref().is_none_or(|start| token_id > *start)
+                    && can_view_owner(deps.storage, &env.block, authenticated.as_deref(), &token)?
+                {
+                    owned.push(token_id);
+                }
+            }
+            owned.sort();
+            owned.truncate(limit.unwrap_or(30) as usize);
+            QueryAnswer::TokenList(TokenList { tokens: owned })
+        }
+        msg => {
+            return Err(StdError::generic_err(format!(
+                "mock snip721: unsupported query '{}'",
+                variant_name(&msg)?
+            )))
+        }
+    };
+
+    to_binary(&answer)
+}
+
+/// Returns the owner of a token, without requiring a viewing key
+pub fn owner_of(storage: &dyn Storage, token_id: &str) -> StdResult<String> {
+    Ok(load_token(storage, token_id)?.owner)
+}
+
+/// Returns the `{"<msg>":{"status":"success"}}` answer of a handle message
+fn status_answer(msg: &HandleMsg) -> StdResult<Binary> {
+    let answer = format!(r#"{{"{}":{{"status":"success"}}}}"#, variant_name(msg)?);
+    Ok(Binary::from(answer.as_bytes()))
+}
+
+fn load_token(storage: &dyn Storage, token_id: &str) -> StdResult<MockToken> {
+    tokens()
+        .get(storage, &token_id.to_string())
+        .ok_or_else(|| StdError::generic_err(format!("Token ID: {token_id} not found")))
+}
+
+fn check_viewing_key(storage: &dyn Storage, address: &str, key: &str) -> StdResult<()> {
+    if VIEWING_KEYS.get(storage, &address.to_string()).as_deref() != Some(key) {
+        return Err(StdError::generic_err(
+            "Wrong viewing key for this address or viewing key not set",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the address of the viewer, if its viewing key is valid
+fn authenticate(storage: &dyn Storage, viewer: Option<ViewerInfo>) -> StdResult<Option<String>> {
+    match viewer {
+        Some(viewer) => {
+            check_viewing_key(storage, &viewer.address, &viewer.viewing_key)?;
+            Ok(Some(viewer.address))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns whether `address` may transfer the token, as its owner or as an approved address
+fn may_transfer(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    address: &str,
+    token: &MockToken,
+) -> bool {
+    let approved = |expires: &Expiration| !expires.is_expired(block);
+    token.owner == address
+        || token
+            .approvals
+            .iter()
+            .any(|(spender, expires)| spender == address && approved(expires))
+        || operators()
+            .get(storage, &(token.owner.clone(), address.to_string()))
+            .is_some_and(|expires| approved(&expires))
+}
+
+fn check_can_transfer(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    sender: &str,
+    token_id: &str,
+    token: &MockToken,
+) -> StdResult<()> {
+    if !may_transfer(storage, block, sender, token) {
+        return Err(StdError::generic_err(format!(
+            "You are not authorized to perform this action on token {token_id}"
+        )));
+    }
+    Ok(())
+}
+
+fn can_view_owner(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    viewer: Option<&str>,
+    token: &MockToken,
+) -> StdResult<bool> {
+    if CONFIG.load(storage)?.public_owners {
+        return Ok(true);
+    }
+    Ok(viewer.is_some_and(|viewer| may_transfer(storage, block, viewer, token)))
+}
+
+fn transfer(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &str,
+    recipient: &str,
+    token_id: &str,
+) -> StdResult<()> {
+    let mut token = load_token(storage, token_id)?;
+    check_can_transfer(storage, block, sender, token_id, &token)?;
+    token.owner = recipient.to_string();
+    token.approvals.clear();
+    tokens().insert(storage, &token_id.to_string(), &token)
+}
+
+fn transfer_approval(address: &str, expires: &Expiration) -> Snip721Approval {
+    Snip721Approval {
+        address: address.to_string(),
+        view_owner_expiration: None,
+        view_private_metadata_expiration: None,
+        transfer_expiration: Some(*expires),
+    }
+}
+
+fn inventory_approvals(storage: &dyn Storage, owner: &str) -> StdResult<Vec<Snip721Approval>> {
+    let mut approvals = vec![];
+    for entry in operators().iter(storage)? {
+        let ((approver, operator), expires) = entry?;
+        if approver == owner {
+            approvals.push(transfer_approval(&operator, &expires));
+        }
+    }
+    Ok(approvals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NftDossierResponse, OwnerOfResponse, TokenListResponse};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier};
+    use cosmwasm_std::{from_binary, CosmosMsg, MemoryStorage, OwnedDeps};
+
+    type MockDeps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+    fn contract() -> StdResult<MockDeps> {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            MockSnip721InitMsg {
+                name: "Cards".to_string(),
+                symbol: "CARD".to_string(),
+                minters: vec!["admin".to_string()],
+                public_owners: false,
+            },
+        )?;
+        for owner in ["alice", "alice", "bob"] {
+            execute_as(
+                &mut deps,
+                "admin",
+                HandleMsg::MintNft {
+                    token_id: None,
+                    owner: Some(owner.to_string()),
+                    public_metadata: None,
+                    private_metadata: Some(Metadata {
+                        token_uri: Some(format!("ipfs://{owner}")),
+                        extension: None,
+                    }),
+                    memo: None,
+                    padding: None,
+                },
+            )?;
+        }
+        for address in ["alice", "bob", "market"] {
+            execute_as(
+                &mut deps,
+                address,
+                HandleMsg::SetViewingKey {
+                    key: "key".to_string(),
+                    padding: None,
+                },
+            )?;
+        }
+        Ok(deps)
+    }
+
+    fn execute_as(deps: &mut MockDeps, sender: &str, msg: HandleMsg) -> StdResult<Response> {
+        execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn viewer(address: &str) -> Option<ViewerInfo> {
+        Some(ViewerInfo {
+            address: address.to_string(),
+            viewing_key: "key".to_string(),
+        })
+    }
+
+    fn transfer_nft(token_id: &str, recipient: &str) -> HandleMsg {
+        HandleMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: token_id.to_string(),
+            memo: None,
+            padding: None,
+        }
+    }
+
+    #[test]
+    fn test_mint_and_transfer() -> StdResult<()> {
+        let mut deps = contract()?;
+        assert!(execute_as(&mut deps, "alice", transfer_nft("2", "alice")).is_err());
+
+        let response = execute_as(&mut deps, "alice", transfer_nft("0", "bob"))?;
+        assert_eq!(
+            response.data,
+            Some(Binary::from(br#"{"transfer_nft":{"status":"success"}}"#))
+        );
+        assert_eq!(owner_of(&deps.storage, "0")?, "bob");
+
+        let tokens = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Tokens {
+                owner: "bob".to_string(),
+                viewer: None,
+                viewing_key: Some("key".to_string()),
+                start_after: None,
+                limit: None,
+            },
+        )?;
+        let tokens: TokenListResponse = from_binary(&tokens)?;
+        assert_eq!(tokens.token_list.tokens, vec!["0", "2"]);
+
+        let unauthorized = execute_as(
+            &mut deps,
+            "bob",
+            HandleMsg::MintNft {
+                token_id: None,
+                owner: None,
+                public_metadata: None,
+                private_metadata: None,
+                memo: None,
+                padding: None,
+            },
+        );
+        assert!(unauthorized.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_approvals() -> StdResult<()> {
+        let mut deps = contract()?;
+        execute_as(
+            &mut deps,
+            "alice",
+            HandleMsg::Approve {
+                spender: "market".to_string(),
+                token_id: "0".to_string(),
+                expires: None,
+                padding: None,
+            },
+        )?;
+
+        // the approved address can view the owner, but not the private metadata
+        let owner_of_query = |address: &str| QueryMsg::OwnerOf {
+            token_id: "0".to_string(),
+            viewer: viewer(address),
+            include_expired: None,
+        };
+        let answer: OwnerOfResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), owner_of_query("market"))?)?;
+        assert_eq!(answer.owner_of.owner, Some("alice".to_string()));
+        assert!(query(deps.as_ref(), mock_env(), owner_of_query("bob")).is_err());
+
+        execute_as(&mut deps, "market", transfer_nft("0", "carol"))?;
+        assert_eq!(owner_of(&deps.storage, "0")?, "carol");
+        // approvals are cleared by transfers
+        assert!(execute_as(&mut deps, "market", transfer_nft("0", "market")).is_err());
+
+        // operators can transfer every token of the owner
+        execute_as(
+            &mut deps,
+            "alice",
+            HandleMsg::ApproveAll {
+                operator: "market".to_string(),
+                expires: Some(Expiration::AtHeight(mock_env().block.height + 1)),
+                padding: None,
+            },
+        )?;
+        execute_as(&mut deps, "market", transfer_nft("1", "dave"))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_send_and_dossier() -> StdResult<()> {
+        let mut deps = contract()?;
+        execute_as(
+            &mut deps,
+            "market",
+            HandleMsg::RegisterReceiveNft {
+                code_hash: "market_hash".to_string(),
+                also_implements_batch_receive_nft: None,
+                padding: None,
+            },
+        )?;
+
+        let response = execute_as(
+            &mut deps,
+            "bob",
+            HandleMsg::SendNft {
+                contract: "market".to_string(),
+                token_id: "2".to_string(),
+                msg: None,
+                memo: None,
+                padding: None,
+            },
+        )?;
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                code_hash: "market_hash".to_string(),
+                msg: Binary::from(br#"{"receive_nft":{"sender":"bob","token_id":"2","msg":null}}"#),
+                funds: vec![],
+            })
+        );
+
+        let dossier_query = |address: &str| QueryMsg::NftDossier {
+            token_id: "2".to_string(),
+            viewer: viewer(address),
+            include_expired: None,
+        };
+        let answer: NftDossierResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), dossier_query("market"))?)?;
+        assert_eq!(answer.nft_dossier.owner, Some("market".to_string()));
+        assert_eq!(
+            answer
+                .nft_dossier
+                .private_metadata
+                .and_then(|m| m.token_uri),
+            Some("ipfs://bob".to_string())
+        );
+
+        let answer: NftDossierResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), dossier_query("alice"))?)?;
+        assert_eq!(answer.nft_dossier.owner, None);
+        assert_eq!(answer.nft_dossier.private_metadata, None);
+        assert!(answer.nft_dossier.display_private_metadata_error.is_some());
+
+        Ok(())
+    }
+}
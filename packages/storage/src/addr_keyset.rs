@@ -0,0 +1,197 @@
+use cosmwasm_std::{Addr, Api, CanonicalAddr, StdResult, Storage};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+use crate::{IterOption, Keyset, WithIter, WithoutIter};
+
+/// A set of addresses, e.g. an allowlist, that validates and canonicalizes addresses on insert.
+///
+/// Addresses are stored as canonical bytes, which takes less space than their bech32 form, and
+/// makes sure the same address is never stored twice with a different formatting.
+pub struct AddrKeyset<'a, Ser = Bincode2, I = WithIter>
+where
+    Ser: Serde,
+    I: IterOption,
+{
+    keyset: Keyset<'a, Vec<u8>, Ser, I>,
+}
+
+impl<'a, Ser: Serde> AddrKeyset<'a, Ser> {
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self {
+            keyset: Keyset::new(namespace),
+        }
+    }
+}
+
+impl<'a, Ser: Serde, I: IterOption> AddrKeyset<'a, Ser, I> {
+    /// Creates an AddrKeyset that stores the addresses in the given keyset, e.g. one created by
+    /// a `KeysetBuilder`
+    pub const fn from_keyset(keyset: Keyset<'a, Vec<u8>, Ser, I>) -> Self {
+        Self { keyset }
+    }
+}
+
+impl<Ser: Serde> AddrKeyset<'_, Ser, WithIter> {
+    /// This is used to produce a new AddrKeyset. This can be used when you want to associate an AddrKeyset to each user
+    /// and you still get to define the AddrKeyset as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        Self {
+            keyset: self.keyset.add_suffix(suffix),
+        }
+    }
+
+    /// user facing insert function
+    ///
+    /// returns `Ok(true)` if the set did not previously contain the address
+    /// returns `Ok(false)` if the set already contained this address
+    /// returns `Err` if the address is invalid or the insertion fails
+    pub fn insert(&self, storage: &mut dyn Storage, api: &dyn Api, addr: &str) -> StdResult<bool> {
+        let canonical = api.addr_canonicalize(addr)?;
+        self.keyset.insert(storage, &canonical.to_vec())
+    }
+
+    /// user facing remove function, returns `Err` if the address is invalid or not in the set
+    pub fn remove(&self, storage: &mut dyn Storage, api: &dyn Api, addr: &str) -> StdResult<()> {
+        let canonical = api.addr_canonicalize(addr)?;
+        self.keyset.remove(storage, &canonical.to_vec())
+    }
+
+    /// user facing method that checks if this address is stored. Invalid addresses are never stored.
+    pub fn contains(&self, storage: &dyn Storage, api: &dyn Api, addr: &str) -> bool {
+        match api.addr_canonicalize(addr) {
+            Ok(canonical) => self.keyset.contains(storage, &canonical.to_vec()),
+            Err(_) => false,
+        }
+    }
+
+    /// get total number of addresses saved
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.keyset.get_len(storage)
+    }
+
+    /// checks if the collection has any elements
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        self.keyset.is_empty(storage)
+    }
+
+    /// paginates the addresses
+    pub fn paging(
+        &self,
+        storage: &dyn Storage,
+        api: &dyn Api,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<Addr>> {
+        self.keyset
+            .paging(storage, start_page, size)?
+            .into_iter()
+            .map(|canonical| api.addr_humanize(&CanonicalAddr::from(canonical)))
+            .collect()
+    }
+
+    /// Returns a readonly iterator over the addresses
+    pub fn iter<'b>(
+        &'b self,
+        storage: &'b dyn Storage,
+        api: &'b dyn Api,
+    ) -> StdResult<impl DoubleEndedIterator<Item = StdResult<Addr>> + 'b> {
+        Ok(self.keyset.iter(storage)?.map(move |canonical| {
+            canonical.and_then(|canonical| api.addr_humanize(&CanonicalAddr::from(canonical)))
+        }))
+    }
+}
+
+impl<Ser: Serde> AddrKeyset<'_, Ser, WithoutIter> {
+    /// user facing insert function, returns `Err` if the address is invalid
+    pub fn insert(&self, storage: &mut dyn Storage, api: &dyn Api, addr: &str) -> StdResult<()> {
+        let canonical = api.addr_canonicalize(addr)?;
+        self.keyset.insert(storage, &canonical.to_vec())
+    }
+
+    /// user facing remove function, returns `Err` if the address is invalid
+    pub fn remove(&self, storage: &mut dyn Storage, api: &dyn Api, addr: &str) -> StdResult<()> {
+        let canonical = api.addr_canonicalize(addr)?;
+        self.keyset.remove(storage, &canonical.to_vec())
+    }
+
+    /// user facing method that checks if this address is stored. Invalid addresses are never stored.
+    pub fn contains(&self, storage: &dyn Storage, api: &dyn Api, addr: &str) -> bool {
+        match api.addr_canonicalize(addr) {
+            Ok(canonical) => self.keyset.contains(storage, &canonical.to_vec()),
+            Err(_) => false,
+        }
+    }
+}
+
+impl<Ser, I> Namespaced for AddrKeyset<'_, Ser, I>
+where
+    Ser: Serde,
+    I: IterOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.keyset.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{MockApi, MockStorage};
+
+    use super::*;
+    use crate::KeysetBuilder;
+
+    #[test]
+    fn test_addr_keyset() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let api = MockApi::default();
+        let allowlist: AddrKeyset = AddrKeyset::new(b"allowlist");
+
+        assert!(allowlist.insert(&mut storage, &api, "alice")?);
+        assert!(allowlist.insert(&mut storage, &api, "bob")?);
+        // the same address with a different case is not stored twice
+        assert!(!allowlist.insert(&mut storage, &api, "ALICE")?);
+        assert_eq!(allowlist.get_len(&storage)?, 2);
+
+        assert!(allowlist.contains(&storage, &api, "Alice"));
+        assert!(!allowlist.contains(&storage, &api, "charlie"));
+        assert!(!allowlist.contains(&storage, &api, "a"));
+        assert!(allowlist.insert(&mut storage, &api, "a").is_err());
+
+        let addrs: Vec<Addr> = allowlist.iter(&storage, &api)?.collect::<StdResult<_>>()?;
+        assert_eq!(
+            addrs,
+            vec![Addr::unchecked("alice"), Addr::unchecked("bob")]
+        );
+        assert_eq!(
+            allowlist.paging(&storage, &api, 1, 1)?,
+            vec![Addr::unchecked("bob")]
+        );
+
+        allowlist.remove(&mut storage, &api, "alice")?;
+        assert!(!allowlist.contains(&storage, &api, "alice"));
+        assert_eq!(allowlist.get_len(&storage)?, 1);
+
+        let other = allowlist.add_suffix(b"other");
+        assert!(other.is_empty(&storage)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_addr_keyset_without_iter() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let api = MockApi::default();
+        let banned: AddrKeyset<Bincode2, WithoutIter> =
+            AddrKeyset::from_keyset(KeysetBuilder::new(b"banned").without_iter().build());
+
+        banned.insert(&mut storage, &api, "alice")?;
+        assert!(banned.contains(&storage, &api, "ALICE"));
+        banned.remove(&mut storage, &api, "Alice")?;
+        assert!(!banned.contains(&storage, &api, "alice"));
+
+        Ok(())
+    }
+}
@@ -5,56 +5,183 @@
 //! for storing the length of the collection so far.
 use std::marker::PhantomData;
 use std::sync::Mutex;
-use std::{collections::HashMap, convert::TryInto};
+use std::{collections::HashMap, convert::TryInto, fmt};
 
 use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
 
 use cosmwasm_std::{StdError, StdResult, Storage};
 use cosmwasm_storage::to_length_prefixed;
 
 use secret_toolkit_serialization::{Bincode2, Serde};
 
+use crate::namespace::Namespaced;
+use crate::{PageSizeOption, RuntimePageSize};
+
 const INDEXES: &[u8] = b"indexes";
 const LEN_KEY: &[u8] = b"len";
+/// the length of the checksum footer of each page, when checksums are enabled
+const CHECKSUM_SIZE: usize = 4;
+
+/// The error of a page of an append store whose checksum doesn't match its contents, e.g. after
+/// its storage was corrupted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CorruptedPage {
+    pub page: u32,
+}
 
-const DEFAULT_PAGE_SIZE: u32 = 1;
+impl fmt::Display for CorruptedPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "append_store page {} is corrupted", self.page)
+    }
+}
 
-pub struct AppendStore<'a, T, Ser = Bincode2>
-where
-    T: Serialize + DeserializeOwned,
-    Ser: Serde,
-{
-    /// prefix of the newly constructed Storage
+impl std::error::Error for CorruptedPage {}
+
+/// The error of [`AppendStore::verify`], which tells corrupted pages apart from the other
+/// failures to read the store
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+    /// a page of the store doesn't match its checksum
+    Corrupted(CorruptedPage),
+    /// the store couldn't be read, e.g. because its length is malformed
+    Std(StdError),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Corrupted(err) => err.fmt(f),
+            VerifyError::Std(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<CorruptedPage> for VerifyError {
+    fn from(err: CorruptedPage) -> Self {
+        VerifyError::Corrupted(err)
+    }
+}
+
+impl From<StdError> for VerifyError {
+    fn from(err: StdError) -> Self {
+        VerifyError::Std(err)
+    }
+}
+
+fn page_checksum(data: &[u8]) -> [u8; CHECKSUM_SIZE] {
+    let mut checksum = [0u8; CHECKSUM_SIZE];
+    checksum.copy_from_slice(&Sha256::digest(data)[..CHECKSUM_SIZE]);
+    checksum
+}
+
+pub struct AppendStoreBuilder<'a, T, Ser = Bincode2, P = RuntimePageSize> {
+    /// namespace of the newly constructed Storage
     namespace: &'a [u8],
-    /// needed if any suffixes were added to the original namespace.
-    prefix: Option<Vec<u8>>,
-    page_size: u32,
-    length: Mutex<Option<u32>>,
+    page_size: P,
+    checksums: bool,
     item_type: PhantomData<T>,
     serialization_type: PhantomData<Ser>,
 }
 
-impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
-    /// constructor
+impl<'a, T, Ser, P> AppendStoreBuilder<'a, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
+    /// Creates an AppendStoreBuilder with default features
     pub const fn new(namespace: &'a [u8]) -> Self {
         Self {
             namespace,
-            prefix: None,
-            page_size: DEFAULT_PAGE_SIZE,
-            length: Mutex::new(None),
+            page_size: P::DEFAULT,
+            checksums: false,
             item_type: PhantomData,
             serialization_type: PhantomData,
         }
     }
+    /// Ends each page written by the store with a checksum of its contents, which is validated
+    /// whenever the page is loaded, so that a corrupted page is reported as such, with its
+    /// number, instead of failing to deserialize, or deserializing to nonsense.
+    ///
+    /// This changes the storage layout, so it can't be enabled on a store that already has
+    /// items.
+    pub const fn with_checksums(&self) -> Self {
+        Self {
+            namespace: self.namespace,
+            page_size: self.page_size,
+            checksums: true,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+    /// Returns an append store with the given configuration
+    pub const fn build(&self) -> AppendStore<'a, T, Ser, P> {
+        AppendStore {
+            namespace: self.namespace,
+            prefix: None,
+            page_size: self.page_size,
+            checksums: self.checksums,
+            length: Mutex::new(None),
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+}
 
-    pub const fn new_with_page_size(namespace: &'a [u8], page_size: u32) -> Self {
+impl<'a, T, Ser> AppendStoreBuilder<'a, T, Ser, RuntimePageSize>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// Modifies the number of items stored in one page
+    pub const fn with_page_size(&self, page_size: u32) -> Self {
         if page_size == 0 {
             panic!("zero index page size used in append_store")
         }
+        Self {
+            namespace: self.namespace,
+            page_size: RuntimePageSize(page_size),
+            checksums: self.checksums,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+}
+
+pub struct AppendStore<'a, T, Ser = Bincode2, P = RuntimePageSize>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
+    /// prefix of the newly constructed Storage
+    namespace: &'a [u8],
+    /// needed if any suffixes were added to the original namespace.
+    prefix: Option<Vec<u8>>,
+    page_size: P,
+    /// whether each page ends with a checksum of its contents
+    checksums: bool,
+    length: Mutex<Option<u32>>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, T, Ser, P> AppendStore<'a, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
         Self {
             namespace,
             prefix: None,
-            page_size,
+            page_size: P::DEFAULT,
+            checksums: false,
             length: Mutex::new(None),
             item_type: PhantomData,
             serialization_type: PhantomData,
@@ -71,6 +198,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
             namespace: self.namespace,
             prefix: Some(prefix),
             page_size: self.page_size,
+            checksums: self.checksums,
             length: Mutex::new(None),
             item_type: self.item_type,
             serialization_type: self.serialization_type,
@@ -79,6 +207,28 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
 }
 
 impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
+    pub const fn new_with_page_size(namespace: &'a [u8], page_size: u32) -> Self {
+        if page_size == 0 {
+            panic!("zero index page size used in append_store")
+        }
+        Self {
+            namespace,
+            prefix: None,
+            page_size: RuntimePageSize(page_size),
+            checksums: false,
+            length: Mutex::new(None),
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, Ser, P> AppendStore<'a, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
     fn as_slice(&self) -> &[u8] {
         if let Some(prefix) = &self.prefix {
             prefix
@@ -88,25 +238,52 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     }
 
     fn page_from_position(&self, position: u32) -> u32 {
-        position / self.page_size
+        position / self.page_size.get()
     }
 
-    /// Used to get the indexes stored in the given page number
-    fn get_indexes(&self, storage: &dyn Storage, page: u32) -> StdResult<Vec<Vec<u8>>> {
+    /// Loads the raw contents of a page, after validating its checksum if checksums are enabled
+    fn get_page(&self, storage: &dyn Storage, page: u32) -> Result<Option<Vec<u8>>, CorruptedPage> {
         let indexes_key = [self.as_slice(), INDEXES, page.to_be_bytes().as_slice()].concat();
-        if self.page_size == 1 {
-            let maybe_item_data = storage.get(&indexes_key);
-            match maybe_item_data {
-                Some(item_data) => Ok(vec![item_data]),
-                None => Ok(vec![]),
-            }
-        } else {
-            let maybe_serialized = storage.get(&indexes_key);
-            match maybe_serialized {
-                Some(serialized) => Bincode2::deserialize(&serialized),
-                None => Ok(vec![]),
+        let Some(mut data) = storage.get(&indexes_key) else {
+            return Ok(None);
+        };
+        if self.checksums {
+            let content_len = data
+                .len()
+                .checked_sub(CHECKSUM_SIZE)
+                .ok_or(CorruptedPage { page })?;
+            if data[content_len..] != page_checksum(&data[..content_len]) {
+                return Err(CorruptedPage { page });
             }
+            data.truncate(content_len);
+        }
+        Ok(Some(data))
+    }
+
+    /// Used to get the indexes stored in the given page number
+    fn get_indexes(&self, storage: &dyn Storage, page: u32) -> StdResult<Vec<Vec<u8>>> {
+        let page = self
+            .get_page(storage, page)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+        match page {
+            Some(item_data) if self.page_size.get() == 1 => Ok(vec![item_data]),
+            Some(serialized) => Bincode2::deserialize(&serialized),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Validates the checksums of all the pages of the store, and returns the first page that is
+    /// corrupted as a [`VerifyError::Corrupted`], if any. Always succeeds if checksums are not
+    /// enabled.
+    pub fn verify(&self, storage: &dyn Storage) -> Result<(), VerifyError> {
+        let len = self.get_len(storage)?;
+        if !self.checksums || len == 0 {
+            return Ok(());
         }
+        for page in 0..=self.page_from_position(len - 1) {
+            self.get_page(storage, page)?;
+        }
+        Ok(())
     }
 
     /// Set an indexes page
@@ -117,15 +294,21 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
         indexes: &Vec<Vec<u8>>,
     ) -> StdResult<()> {
         let indexes_key = [self.as_slice(), INDEXES, page.to_be_bytes().as_slice()].concat();
-        if self.page_size == 1 {
+        let mut data = if self.page_size.get() == 1 {
             if let Some(item_data) = indexes.first() {
-                storage.set(&indexes_key, item_data);
+                item_data.clone()
             } else {
                 storage.remove(&indexes_key);
+                return Ok(());
             }
         } else {
-            storage.set(&indexes_key, &Bincode2::serialize(indexes)?);
+            Bincode2::serialize(indexes)?
+        };
+        if self.checksums {
+            let checksum = page_checksum(&data);
+            data.extend_from_slice(&checksum);
         }
+        storage.set(&indexes_key, &data);
         Ok(())
     }
 
@@ -158,6 +341,11 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     }
 
     /// gets the element at pos if within bounds
+    ///
+    /// With checksums, an element of a corrupted page is a generic error reading "append_store
+    /// page {page} is corrupted". It stays a `StdResult` like the other accessors of the store,
+    /// since `StdError` has no variant that could carry a [`CorruptedPage`]; use [`Self::verify`]
+    /// to tell corrupted pages apart from other errors with a typed [`VerifyError`].
     pub fn get_at(&self, storage: &dyn Storage, pos: u32) -> StdResult<T> {
         let len = self.get_len(storage)?;
         if pos > len {
@@ -170,7 +358,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     fn get_at_unchecked(&self, storage: &dyn Storage, pos: u32) -> StdResult<T> {
         let page = self.page_from_position(pos);
         let indexes = self.get_indexes(storage, page)?;
-        let index_pos = (pos % self.page_size) as usize;
+        let index_pos = (pos % self.page_size.get()) as usize;
         let item_data = &indexes[index_pos];
         Ser::deserialize(item_data)
     }
@@ -202,7 +390,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     fn set_at_unchecked(&self, storage: &mut dyn Storage, pos: u32, item: &T) -> StdResult<()> {
         let page = self.page_from_position(pos);
         let mut indexes = self.get_indexes(storage, page)?;
-        let index_pos = (pos % self.page_size) as usize;
+        let index_pos = (pos % self.page_size.get()) as usize;
         let item_data = Ser::serialize(item)?;
         if indexes.len() > index_pos {
             indexes[index_pos] = item_data
@@ -252,7 +440,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
             std::cmp::Ordering::Less => {
                 // shift items from indexes to indexes
                 let mut past_indexes: Vec<Vec<u8>> = self.get_indexes(storage, pos_page)?;
-                let item_data = past_indexes.remove((pos % self.page_size) as usize);
+                let item_data = past_indexes.remove((pos % self.page_size.get()) as usize);
                 // loop on
                 for page in (pos_page + 1)..=max_page {
                     let mut indexes: Vec<Vec<u8>> = self.get_indexes(storage, page)?;
@@ -269,7 +457,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
             std::cmp::Ordering::Equal => {
                 // if the pos is in the last indexes page
                 let mut indexes = self.get_indexes(storage, pos_page)?;
-                let item_data = indexes.remove((pos % self.page_size) as usize);
+                let item_data = indexes.remove((pos % self.page_size.get()) as usize);
                 self.set_indexes_page(storage, pos_page, &indexes)?;
                 self.set_len(storage, max_pos);
                 Ser::deserialize(&item_data)
@@ -281,7 +469,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
     }
 
     /// Returns a readonly iterator
-    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<AppendStoreIter<T, Ser>> {
+    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<AppendStoreIter<'_, T, Ser, P>> {
         let len = self.get_len(storage)?;
         let iter = AppendStoreIter::new(self, storage, 0, len);
         Ok(iter)
@@ -294,29 +482,75 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> AppendStore<'a, T, Ser> {
             .take(size as usize)
             .collect()
     }
+
+    /// Returns the items starting at position `cursor` whose serialized size adds up to at most
+    /// `max_bytes`, and the cursor to pass to the next call, or `None` once the end of the
+    /// collection was reached. Unlike `paging`, pages hold fewer items when the items are
+    /// large, so that responses stay within a predictable size. The first item is always
+    /// returned, even if it is larger than `max_bytes`, so that the paging makes progress.
+    pub fn paging_by_bytes(
+        &self,
+        storage: &dyn Storage,
+        cursor: u32,
+        max_bytes: usize,
+    ) -> StdResult<(Vec<T>, Option<u32>)> {
+        let len = self.get_len(storage)?;
+        let mut items = vec![];
+        let mut used = 0usize;
+        let mut cached: Option<(u32, Vec<Vec<u8>>)> = None;
+        let mut pos = cursor;
+        while pos < len {
+            let page = self.page_from_position(pos);
+            if cached.as_ref().map(|(cached_page, _)| *cached_page) != Some(page) {
+                cached = Some((page, self.get_indexes(storage, page)?));
+            }
+            let (_, indexes) = cached.as_ref().unwrap();
+            let item_data = &indexes[(pos % self.page_size.get()) as usize];
+            used = used.saturating_add(item_data.len());
+            if !items.is_empty() && used > max_bytes {
+                break;
+            }
+            items.push(Ser::deserialize(item_data)?);
+            pos += 1;
+        }
+        Ok((items, (pos < len).then_some(pos)))
+    }
+}
+
+impl<T, Ser, P> Namespaced for AppendStore<'_, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
 }
 
 /// An iterator over the contents of the append store.
-pub struct AppendStoreIter<'a, T, Ser>
+pub struct AppendStoreIter<'a, T, Ser, P = RuntimePageSize>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
-    append_store: &'a AppendStore<'a, T, Ser>,
+    append_store: &'a AppendStore<'a, T, Ser, P>,
     storage: &'a dyn Storage,
     start: u32,
     end: u32,
     cache: HashMap<u32, Vec<Vec<u8>>>,
 }
 
-impl<'a, T, Ser> AppendStoreIter<'a, T, Ser>
+impl<'a, T, Ser, P> AppendStoreIter<'a, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     /// constructor
     pub fn new(
-        append_store: &'a AppendStore<'a, T, Ser>,
+        append_store: &'a AppendStore<'a, T, Ser, P>,
         storage: &'a dyn Storage,
         start: u32,
         end: u32,
@@ -331,10 +565,11 @@ where
     }
 }
 
-impl<T, Ser> Iterator for AppendStoreIter<'_, T, Ser>
+impl<T, Ser, P> Iterator for AppendStoreIter<'_, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     type Item = StdResult<T>;
 
@@ -344,7 +579,7 @@ where
         }
         let item;
         let page = self.append_store.page_from_position(self.start);
-        let indexes_pos = (self.start % self.append_store.page_size) as usize;
+        let indexes_pos = (self.start % self.append_store.page_size.get()) as usize;
 
         match self.cache.get(&page) {
             Some(indexes) => {
@@ -384,10 +619,11 @@ where
     }
 }
 
-impl<T, Ser> DoubleEndedIterator for AppendStoreIter<'_, T, Ser>
+impl<T, Ser, P> DoubleEndedIterator for AppendStoreIter<'_, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.start >= self.end {
@@ -396,7 +632,7 @@ where
         self.end -= 1;
         let item;
         let page = self.append_store.page_from_position(self.end);
-        let indexes_pos = (self.end % self.append_store.page_size) as usize;
+        let indexes_pos = (self.end % self.append_store.page_size.get()) as usize;
         match self.cache.get(&page) {
             Some(indexes) => {
                 let item_data = &indexes[indexes_pos];
@@ -429,10 +665,11 @@ where
 }
 
 // This enables writing `append_store.iter().skip(n).rev()`
-impl<T, Ser> ExactSizeIterator for AppendStoreIter<'_, T, Ser>
+impl<T, Ser, P> ExactSizeIterator for AppendStoreIter<'_, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
 }
 
@@ -443,6 +680,7 @@ mod tests {
     use secret_toolkit_serialization::Json;
 
     use super::*;
+    use crate::PageSize;
 
     #[test]
     fn test_push_pop() -> StdResult<()> {
@@ -495,6 +733,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_const_page_size() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let append_store: AppendStore<i32, Bincode2, PageSize<3>> = AppendStore::new(b"test");
+
+        for i in 0..7 {
+            append_store.push(&mut storage, &i)?;
+        }
+        assert_eq!(append_store.remove(&mut storage, 1), Ok(1));
+        assert_eq!(append_store.pop(&mut storage), Ok(6));
+
+        // the layout is the same as with a page size set at runtime
+        let runtime_store: AppendStore<i32> = AppendStore::new_with_page_size(b"test", 3);
+        let values: StdResult<Vec<i32>> = runtime_store.iter(&storage)?.collect();
+        assert_eq!(values?, vec![0, 2, 3, 4, 5]);
+
+        let suffixed = append_store.add_suffix(b"suffix");
+        suffixed.push(&mut storage, &1)?;
+        assert_eq!(suffixed.get_len(&storage)?, 1);
+        assert_eq!(append_store.get_len(&storage)?, 5);
+
+        Ok(())
+    }
+
     #[test]
     fn test_iterator() -> StdResult<()> {
         let mut storage = MockStorage::new();
@@ -708,7 +970,7 @@ mod tests {
         append_store.push(&mut storage, &1234)?;
 
         let key = [append_store.as_slice(), INDEXES, &0_u32.to_be_bytes()].concat();
-        if append_store.page_size == 1 {
+        if append_store.page_size.get() == 1 {
             let item_data = storage.get(&key);
             let expected_data = Bincode2::serialize(&1234)?;
             assert_eq!(item_data, Some(expected_data));
@@ -725,7 +987,7 @@ mod tests {
         json_append_store.push(&mut storage, &1234)?;
 
         let key = [json_append_store.as_slice(), INDEXES, &0_u32.to_be_bytes()].concat();
-        if json_append_store.page_size == 1 {
+        if json_append_store.page_size.get() == 1 {
             let item_data = storage.get(&key);
             let expected_data = b"1234".to_vec();
             assert_eq!(item_data, Some(expected_data));
@@ -854,4 +1116,88 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_checksums() -> StdResult<()> {
+        test_checksums_with_page_size(1)?;
+        test_checksums_with_page_size(3)?;
+        Ok(())
+    }
+
+    fn test_checksums_with_page_size(page_size: u32) -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let append_store: AppendStore<u32> = AppendStoreBuilder::new(b"test")
+            .with_page_size(page_size)
+            .with_checksums()
+            .build();
+        for i in 0..10 {
+            append_store.push(&mut storage, &i)?;
+        }
+        append_store.set_at(&mut storage, 4, &40)?;
+        append_store.remove(&mut storage, 1)?;
+        assert_eq!(
+            append_store
+                .iter(&storage)?
+                .collect::<StdResult<Vec<_>>>()?,
+            vec![0, 2, 3, 40, 5, 6, 7, 8, 9]
+        );
+        assert_eq!(append_store.verify(&storage), Ok(()));
+
+        // suffixed stores keep the checksums
+        let suffixed = append_store.add_suffix(b"suffix");
+        suffixed.push(&mut storage, &1)?;
+        assert_eq!(suffixed.get_at(&storage, 0)?, 1);
+        assert!(suffixed.checksums);
+
+        // corrupt the contents of the third page
+        let key = [b"test".as_slice(), INDEXES, 2u32.to_be_bytes().as_slice()].concat();
+        let mut page = storage.get(&key).unwrap();
+        page[0] ^= 1;
+        storage.set(&key, &page);
+        let corrupted_pos = 2 * page_size;
+        assert_eq!(
+            append_store.get_at(&storage, corrupted_pos),
+            Err(StdError::generic_err("append_store page 2 is corrupted"))
+        );
+        assert_eq!(
+            append_store.verify(&storage),
+            Err(VerifyError::Corrupted(CorruptedPage { page: 2 }))
+        );
+        // the other pages are still readable
+        assert_eq!(append_store.get_at(&storage, 0)?, 0);
+
+        // a store without checksums can't detect it
+        let unchecked: AppendStore<u32> = AppendStore::new_with_page_size(b"test", page_size);
+        assert_eq!(unchecked.verify(&storage), Ok(()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paging_by_bytes() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let append_store: AppendStore<String> = AppendStore::new_with_page_size(b"test", 2);
+        // serialized with an 8 byte length prefix
+        for item in ["a", "b", "a long item of 20 b", "c", "d"] {
+            append_store.push(&mut storage, &item.to_string())?;
+        }
+
+        let (page, cursor) = append_store.paging_by_bytes(&storage, 0, 20)?;
+        assert_eq!(page, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(cursor, Some(2));
+        // an item larger than the budget is returned alone
+        let (page, cursor) = append_store.paging_by_bytes(&storage, 2, 20)?;
+        assert_eq!(page, vec!["a long item of 20 b".to_string()]);
+        assert_eq!(cursor, Some(3));
+        let (page, cursor) = append_store.paging_by_bytes(&storage, 3, 20)?;
+        assert_eq!(page, vec!["c".to_string(), "d".to_string()]);
+        assert_eq!(cursor, None);
+        assert_eq!(append_store.paging_by_bytes(&storage, 0, 1000)?.0.len(), 5);
+        assert_eq!(
+            append_store.paging_by_bytes(&storage, 5, 20)?,
+            (vec![], None)
+        );
+
+        Ok(())
+    }
 }
@@ -16,51 +16,44 @@ use cosmwasm_storage::to_length_prefixed;
 
 use secret_toolkit_serialization::{Bincode2, Serde};
 
+use crate::namespace::Namespaced;
+use crate::{AppendStore, PageSizeOption, RuntimePageSize};
+
 const INDEXES: &[u8] = b"indexes";
 const LEN_KEY: &[u8] = b"len";
 const OFFSET_KEY: &[u8] = b"off";
+const MIGRATED_FROM_KEY: &[u8] = b"migrated_from";
+const MIGRATED_TO_KEY: &[u8] = b"migrated_to";
 
-const DEFAULT_PAGE_SIZE: u32 = 1;
-
-pub struct DequeStore<'a, T, Ser = Bincode2>
+pub struct DequeStore<'a, T, Ser = Bincode2, P = RuntimePageSize>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     /// prefix of the newly constructed Storage
     namespace: &'a [u8],
     /// needed if any suffixes were added to the original namespace.
     prefix: Option<Vec<u8>>,
-    page_size: u32,
+    page_size: P,
     length: Mutex<Option<u32>>,
     offset: Mutex<Option<u32>>,
     item_type: PhantomData<T>,
     serialization_type: PhantomData<Ser>,
 }
 
-impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
+impl<'a, T, Ser, P> DequeStore<'a, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
     /// constructor
     pub const fn new(prefix: &'a [u8]) -> Self {
         Self {
             namespace: prefix,
             prefix: None,
-            page_size: DEFAULT_PAGE_SIZE,
-            length: Mutex::new(None),
-            offset: Mutex::new(None),
-            item_type: PhantomData,
-            serialization_type: PhantomData,
-        }
-    }
-
-    /// constructor with indexes size
-    pub const fn new_with_page_size(prefix: &'a [u8], page_size: u32) -> Self {
-        if page_size == 0 {
-            panic!("zero index page size used in deque_store")
-        }
-        Self {
-            namespace: prefix,
-            prefix: None,
-            page_size,
+            page_size: P::DEFAULT,
             length: Mutex::new(None),
             offset: Mutex::new(None),
             item_type: PhantomData,
@@ -87,6 +80,29 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
 }
 
 impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
+    /// constructor with indexes size
+    pub const fn new_with_page_size(prefix: &'a [u8], page_size: u32) -> Self {
+        if page_size == 0 {
+            panic!("zero index page size used in deque_store")
+        }
+        Self {
+            namespace: prefix,
+            prefix: None,
+            page_size: RuntimePageSize(page_size),
+            length: Mutex::new(None),
+            offset: Mutex::new(None),
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, Ser, P> DequeStore<'a, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
     fn as_slice(&self) -> &[u8] {
         if let Some(prefix) = &self.prefix {
             prefix
@@ -157,7 +173,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
     /// Used to get the indexes stored in the given page number
     fn get_indexes(&self, storage: &dyn Storage, page: u32) -> StdResult<HashMap<u32, Vec<u8>>> {
         let indexes_key = [self.as_slice(), INDEXES, page.to_be_bytes().as_slice()].concat();
-        if self.page_size == 1 {
+        if self.page_size.get() == 1 {
             let maybe_item_data = storage.get(&indexes_key);
             match maybe_item_data {
                 Some(item_data) => {
@@ -184,7 +200,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
         indexes: &HashMap<u32, Vec<u8>>,
     ) -> StdResult<()> {
         let indexes_key = [self.as_slice(), INDEXES, page.to_be_bytes().as_slice()].concat();
-        if self.page_size == 1 {
+        if self.page_size.get() == 1 {
             if let Some(item_data) = indexes.get(&0_u32) {
                 storage.set(&indexes_key, item_data);
             } else {
@@ -199,8 +215,8 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
     /// tries to get the element at pos
     fn get_at_unchecked(&self, storage: &dyn Storage, pos: u32) -> StdResult<T> {
         let offset_pos = self.get_offset_pos(storage, pos)?;
-        let indexes_page = offset_pos / self.page_size;
-        let index_pos = offset_pos % self.page_size;
+        let indexes_page = offset_pos / self.page_size.get();
+        let index_pos = offset_pos % self.page_size.get();
         let indexes = self.get_indexes(storage, indexes_page)?;
         let item_data = indexes
             .get(&index_pos)
@@ -252,8 +268,8 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
     /// Sets data at a given index
     fn set_at_unchecked(&self, storage: &mut dyn Storage, pos: u32, item: &T) -> StdResult<()> {
         let offset_pos = self.get_offset_pos(storage, pos)?;
-        let indexes_page = offset_pos / self.page_size;
-        let index_pos = offset_pos % self.page_size;
+        let indexes_page = offset_pos / self.page_size.get();
+        let index_pos = offset_pos % self.page_size.get();
         let mut indexes = self.get_indexes(storage, indexes_page)?;
         let item_data = Ser::serialize(item)?;
         indexes.insert(index_pos, item_data);
@@ -301,6 +317,49 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
         }
     }
 
+    /// Pops up to `n` items from the front, in order, updating the length and offset once
+    pub fn pop_front_n(&self, storage: &mut dyn Storage, n: u32) -> StdResult<Vec<T>> {
+        let len = self.get_len(storage)?;
+        let off = self.get_off(storage)?;
+        let count = n.min(len);
+
+        let mut items = Vec::with_capacity(count as usize);
+        let mut indexes = HashMap::new();
+        let mut indexes_page = None;
+        for pos in 0..count {
+            let offset_pos = off.overflowing_add(pos).0;
+            let page = offset_pos / self.page_size.get();
+            if indexes_page != Some(page) {
+                indexes = self.get_indexes(storage, page)?;
+                indexes_page = Some(page);
+            }
+            let item_data = indexes
+                .get(&(offset_pos % self.page_size.get()))
+                .ok_or_else(|| StdError::generic_err("item not found at this index"))?;
+            items.push(Ser::deserialize(item_data)?);
+        }
+
+        self.set_len(storage, len - count);
+        self.set_off(storage, off.overflowing_add(count).0);
+        Ok(items)
+    }
+
+    /// Returns the item at the front without removing it, or `None` if the collection is empty
+    pub fn peek_front(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        if self.is_empty(storage)? {
+            return Ok(None);
+        }
+        self.get_at_unchecked(storage, 0).map(Some)
+    }
+
+    /// Returns the item at the back without removing it, or `None` if the collection is empty
+    pub fn peek_back(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        match self.get_len(storage)?.checked_sub(1) {
+            Some(last) => self.get_at_unchecked(storage, last).map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// Remove an element from the collection at the specified position.
     ///
     /// Removing an element from the head (first) or tail (last) has a constant cost.
@@ -320,8 +379,8 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
         let to_tail = len - pos;
         if to_tail < pos {
             let past_offset_pos = self.get_offset_pos(storage, pos)?;
-            let mut past_indexes_page = past_offset_pos / self.page_size;
-            let mut past_index_pos = past_offset_pos % self.page_size;
+            let mut past_indexes_page = past_offset_pos / self.page_size.get();
+            let mut past_index_pos = past_offset_pos % self.page_size.get();
             let mut past_indexes = self.get_indexes(storage, past_indexes_page)?;
             res = Ser::deserialize(
                 &past_indexes
@@ -331,8 +390,8 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
             // closer to the tail
             for i in (pos + 1)..len {
                 let offset_pos = self.get_offset_pos(storage, i)?;
-                let current_page = offset_pos / self.page_size;
-                let index_pos = offset_pos % self.page_size;
+                let current_page = offset_pos / self.page_size.get();
+                let index_pos = offset_pos % self.page_size.get();
                 if current_page != past_indexes_page {
                     let mut indexes = self.get_indexes(storage, current_page)?;
                     let item_data = indexes
@@ -353,8 +412,8 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
             self.set_indexes_page(storage, past_indexes_page, &past_indexes)?;
         } else {
             let past_offset_pos = self.get_offset_pos(storage, pos)?;
-            let mut past_indexes_page = past_offset_pos / self.page_size;
-            let mut past_index_pos = past_offset_pos % self.page_size;
+            let mut past_indexes_page = past_offset_pos / self.page_size.get();
+            let mut past_index_pos = past_offset_pos % self.page_size.get();
             let mut past_indexes = self.get_indexes(storage, past_indexes_page)?;
             res = Ser::deserialize(
                 &past_indexes
@@ -364,8 +423,8 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
             // closer to the head
             for i in (0..pos).rev() {
                 let offset_pos = self.get_offset_pos(storage, i)?;
-                let current_page = offset_pos / self.page_size;
-                let index_pos = offset_pos % self.page_size;
+                let current_page = offset_pos / self.page_size.get();
+                let index_pos = offset_pos % self.page_size.get();
                 if current_page != past_indexes_page {
                     let mut indexes = self.get_indexes(storage, current_page)?;
                     let item_data = indexes
@@ -391,7 +450,7 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
     }
 
     /// Returns a readonly iterator
-    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<DequeStoreIter<T, Ser>> {
+    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<DequeStoreIter<'_, T, Ser, P>> {
         let len = self.get_len(storage)?;
         let iter = DequeStoreIter::new(self, storage, 0, len);
         Ok(iter)
@@ -404,29 +463,169 @@ impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> DequeStore<'a, T, Ser> {
             .take(size as usize)
             .collect()
     }
+
+    /// Returns up to `limit` items starting at position `cursor`, and the cursor to pass to the
+    /// next call, or `None` once the end of the collection was reached. Unlike `paging`, the
+    /// cursor doesn't have to be a multiple of the page size, so that a contract can keep reading
+    /// where the gas budget of the previous message ran out.
+    pub fn to_vec_paged(
+        &self,
+        storage: &dyn Storage,
+        limit: u32,
+        cursor: u32,
+    ) -> StdResult<(Vec<T>, Option<u32>)> {
+        let len = self.get_len(storage)?;
+        let items = self
+            .iter(storage)?
+            .skip(cursor as usize)
+            .take(limit as usize)
+            .collect::<StdResult<Vec<T>>>()?;
+        let next = cursor.saturating_add(items.len() as u32);
+        Ok((items, (next < len).then_some(next)))
+    }
+
+    /// Returns the items starting at position `cursor` whose serialized size adds up to at most
+    /// `max_bytes`, and the cursor to pass to the next call, or `None` once the end of the
+    /// collection was reached. Unlike `paging`, pages hold fewer items when the items are
+    /// large, so that responses stay within a predictable size. The first item is always
+    /// returned, even if it is larger than `max_bytes`, so that the paging makes progress.
+    pub fn paging_by_bytes(
+        &self,
+        storage: &dyn Storage,
+        cursor: u32,
+        max_bytes: usize,
+    ) -> StdResult<(Vec<T>, Option<u32>)> {
+        let len = self.get_len(storage)?;
+        let mut items = vec![];
+        let mut used = 0usize;
+        let mut cached: Option<(u32, HashMap<u32, Vec<u8>>)> = None;
+        let mut pos = cursor;
+        while pos < len {
+            let offset_pos = self.get_offset_pos(storage, pos)?;
+            let page = offset_pos / self.page_size.get();
+            if cached.as_ref().map(|(cached_page, _)| *cached_page) != Some(page) {
+                cached = Some((page, self.get_indexes(storage, page)?));
+            }
+            let (_, indexes) = cached.as_ref().unwrap();
+            let item_data = indexes
+                .get(&(offset_pos % self.page_size.get()))
+                .ok_or_else(|| StdError::generic_err("item not found at this index"))?;
+            used = used.saturating_add(item_data.len());
+            if !items.is_empty() && used > max_bytes {
+                break;
+            }
+            items.push(Ser::deserialize(item_data)?);
+            pos += 1;
+        }
+        Ok((items, (pos < len).then_some(pos)))
+    }
+
+    /// Appends up to `limit` items of an `AppendStore` to the back of this collection, continuing
+    /// from where the previous call stopped, and returns the number of items left to copy. The
+    /// position reached is kept in the storage of this collection, so a migration can copy the
+    /// `AppendStore` over several messages, until this returns 0. The `AppendStore` isn't
+    /// modified, clear it once the copy is complete if it is no longer needed.
+    pub fn from_append_store<S, Q>(
+        &self,
+        storage: &mut dyn Storage,
+        source: &AppendStore<T, S, Q>,
+        limit: u32,
+    ) -> StdResult<u32>
+    where
+        S: Serde,
+        Q: PageSizeOption,
+    {
+        let cursor = self.get_u32(storage, MIGRATED_FROM_KEY)?;
+        let len = source.get_len(storage)?;
+        let items = source
+            .iter(storage)?
+            .skip(cursor as usize)
+            .take(limit as usize)
+            .collect::<StdResult<Vec<T>>>()?;
+        for item in &items {
+            self.push_back(storage, item)?;
+        }
+        self.update_migration_cursor(storage, MIGRATED_FROM_KEY, cursor, items.len(), len)
+    }
+
+    /// Appends up to `limit` items of this collection to an `AppendStore`, from the front,
+    /// continuing from where the previous call stopped, and returns the number of items left to
+    /// copy. Like in `from_append_store`, the position reached is kept in the storage of this
+    /// collection, and this collection isn't modified.
+    pub fn to_append_store<S, Q>(
+        &self,
+        storage: &mut dyn Storage,
+        target: &AppendStore<T, S, Q>,
+        limit: u32,
+    ) -> StdResult<u32>
+    where
+        S: Serde,
+        Q: PageSizeOption,
+    {
+        let cursor = self.get_u32(storage, MIGRATED_TO_KEY)?;
+        let len = self.get_len(storage)?;
+        let (items, _) = self.to_vec_paged(storage, limit, cursor)?;
+        for item in &items {
+            target.push(storage, item)?;
+        }
+        self.update_migration_cursor(storage, MIGRATED_TO_KEY, cursor, items.len(), len)
+    }
+
+    /// Advances a migration cursor by `copied` items, and removes it once all `len` items were
+    /// copied so that the storage is left clean. Returns the number of items left to copy.
+    fn update_migration_cursor(
+        &self,
+        storage: &mut dyn Storage,
+        key: &[u8],
+        cursor: u32,
+        copied: usize,
+        len: u32,
+    ) -> StdResult<u32> {
+        let cursor = cursor + copied as u32;
+        if cursor >= len {
+            storage.remove(&[self.as_slice(), key].concat());
+            Ok(0)
+        } else {
+            self._set_u32(storage, key, cursor);
+            Ok(len - cursor)
+        }
+    }
+}
+
+impl<T, Ser, P> Namespaced for DequeStore<'_, T, Ser, P>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    P: PageSizeOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
 }
 
 /// An iterator over the contents of the deque store.
-pub struct DequeStoreIter<'a, T, Ser>
+pub struct DequeStoreIter<'a, T, Ser, P = RuntimePageSize>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
-    deque_store: &'a DequeStore<'a, T, Ser>,
+    deque_store: &'a DequeStore<'a, T, Ser, P>,
     storage: &'a dyn Storage,
     start: u32,
     end: u32,
     cache: HashMap<u32, HashMap<u32, Vec<u8>>>,
 }
 
-impl<'a, T, Ser> DequeStoreIter<'a, T, Ser>
+impl<'a, T, Ser, P> DequeStoreIter<'a, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     /// constructor
     pub fn new(
-        deque_store: &'a DequeStore<'a, T, Ser>,
+        deque_store: &'a DequeStore<'a, T, Ser, P>,
         storage: &'a dyn Storage,
         start: u32,
         end: u32,
@@ -441,10 +640,11 @@ where
     }
 }
 
-impl<T, Ser> Iterator for DequeStoreIter<'_, T, Ser>
+impl<T, Ser, P> Iterator for DequeStoreIter<'_, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     type Item = StdResult<T>;
 
@@ -455,8 +655,8 @@ where
         let item;
         match self.deque_store.get_offset_pos(self.storage, self.start) {
             Ok(offset_pos) => {
-                let indexes_page = offset_pos / self.deque_store.page_size;
-                let index_pos = offset_pos % self.deque_store.page_size;
+                let indexes_page = offset_pos / self.deque_store.page_size.get();
+                let index_pos = offset_pos % self.deque_store.page_size.get();
                 match self.cache.get(&indexes_page) {
                     Some(indexes) => {
                         if let Some(item_data) = indexes.get(&index_pos) {
@@ -506,10 +706,11 @@ where
     }
 }
 
-impl<T, Ser> DoubleEndedIterator for DequeStoreIter<'_, T, Ser>
+impl<T, Ser, P> DoubleEndedIterator for DequeStoreIter<'_, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.start >= self.end {
@@ -519,8 +720,8 @@ where
         let item;
         match self.deque_store.get_offset_pos(self.storage, self.end) {
             Ok(offset_pos) => {
-                let indexes_page = offset_pos / self.deque_store.page_size;
-                let index_pos = offset_pos % self.deque_store.page_size;
+                let indexes_page = offset_pos / self.deque_store.page_size.get();
+                let index_pos = offset_pos % self.deque_store.page_size.get();
                 match self.cache.get(&indexes_page) {
                     Some(indexes) => {
                         if let Some(item_data) = indexes.get(&index_pos) {
@@ -564,10 +765,11 @@ where
 }
 
 // This enables writing `deque_store.iter().skip(n).rev()`
-impl<T, Ser> ExactSizeIterator for DequeStoreIter<'_, T, Ser>
+impl<T, Ser, P> ExactSizeIterator for DequeStoreIter<'_, T, Ser, P>
 where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
+    P: PageSizeOption,
 {
 }
 
@@ -578,6 +780,7 @@ mod tests {
     use secret_toolkit_serialization::Json;
 
     use super::*;
+    use crate::PageSize;
 
     #[test]
     fn test_pushs_pops() -> StdResult<()> {
@@ -612,6 +815,60 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_const_page_size() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let deque_store: DequeStore<i32, Bincode2, PageSize<3>> = DequeStore::new(b"test");
+
+        for i in 0..4 {
+            deque_store.push_back(&mut storage, &i)?;
+            deque_store.push_front(&mut storage, &-i)?;
+        }
+        assert_eq!(deque_store.remove(&mut storage, 2), Ok(-1));
+        assert_eq!(deque_store.pop_front(&mut storage), Ok(-3));
+
+        // the layout is the same as with a page size set at runtime
+        let runtime_store: DequeStore<i32> = DequeStore::new_with_page_size(b"test", 3);
+        let values: StdResult<Vec<i32>> = runtime_store.iter(&storage)?.collect();
+        assert_eq!(values?, vec![-2, 0, 0, 1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_peeks_and_pop_front_n() -> StdResult<()> {
+        test_peeks_and_pop_front_n_with_size(1)?;
+        test_peeks_and_pop_front_n_with_size(3)?;
+
+        Ok(())
+    }
+
+    fn test_peeks_and_pop_front_n_with_size(page_size: u32) -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let deque_store: DequeStore<i32> = DequeStore::new_with_page_size(b"test", page_size);
+        assert_eq!(deque_store.peek_front(&storage)?, None);
+        assert_eq!(deque_store.peek_back(&storage)?, None);
+
+        for i in 0..5 {
+            deque_store.push_back(&mut storage, &i)?;
+        }
+        deque_store.push_front(&mut storage, &-1)?;
+        assert_eq!(deque_store.peek_front(&storage)?, Some(-1));
+        assert_eq!(deque_store.peek_back(&storage)?, Some(4));
+        assert_eq!(deque_store.get_len(&storage)?, 6);
+
+        assert_eq!(deque_store.pop_front_n(&mut storage, 4)?, vec![-1, 0, 1, 2]);
+        assert_eq!(deque_store.get_len(&storage)?, 2);
+        assert_eq!(deque_store.peek_front(&storage)?, Some(3));
+
+        // popping more than the length drains the collection
+        assert_eq!(deque_store.pop_front_n(&mut storage, 10)?, vec![3, 4]);
+        assert!(deque_store.is_empty(&storage)?);
+        assert!(deque_store.pop_front_n(&mut storage, 1)?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_removes() -> StdResult<()> {
         test_removes_with_page_size(1)?;
@@ -921,7 +1178,7 @@ mod tests {
         deque_store.push_back(&mut storage, &1234)?;
 
         let key = [deque_store.as_slice(), INDEXES, &0_u32.to_be_bytes()].concat();
-        if deque_store.page_size == 1 {
+        if deque_store.page_size.get() == 1 {
             let item_data = storage.get(&key);
             assert_eq!(item_data, Some(Bincode2::serialize(&1234)?));
         } else {
@@ -939,7 +1196,7 @@ mod tests {
 
         let key = [json_deque_store.as_slice(), INDEXES, &0_u32.to_be_bytes()].concat();
 
-        if deque_store.page_size == 1 {
+        if deque_store.page_size.get() == 1 {
             let item_data = storage.get(&key);
             assert_eq!(item_data, Some(b"1234".to_vec()));
         } else {
@@ -995,4 +1252,104 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_to_vec_paged() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let deque_store: DequeStore<u32> = DequeStore::new_with_page_size(b"test", 3);
+        for i in 1..=5 {
+            deque_store.push_back(&mut storage, &i)?;
+        }
+        deque_store.push_front(&mut storage, &0)?;
+
+        assert_eq!(
+            deque_store.to_vec_paged(&storage, 4, 0)?,
+            (vec![0, 1, 2, 3], Some(4))
+        );
+        assert_eq!(
+            deque_store.to_vec_paged(&storage, 4, 4)?,
+            (vec![4, 5], None)
+        );
+        assert_eq!(
+            deque_store.to_vec_paged(&storage, 2, 4)?,
+            (vec![4, 5], None)
+        );
+        assert_eq!(deque_store.to_vec_paged(&storage, 2, 7)?, (vec![], None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paging_by_bytes() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let deque_store: DequeStore<String> = DequeStore::new_with_page_size(b"test", 2);
+        // serialized with an 8 byte length prefix
+        for item in ["b", "a long item of 20 b", "c", "d"] {
+            deque_store.push_back(&mut storage, &item.to_string())?;
+        }
+        deque_store.push_front(&mut storage, &"a".to_string())?;
+
+        let (page, cursor) = deque_store.paging_by_bytes(&storage, 0, 20)?;
+        assert_eq!(page, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(cursor, Some(2));
+        // an item larger than the budget is returned alone
+        let (page, cursor) = deque_store.paging_by_bytes(&storage, 2, 20)?;
+        assert_eq!(page, vec!["a long item of 20 b".to_string()]);
+        assert_eq!(cursor, Some(3));
+        let (page, cursor) = deque_store.paging_by_bytes(&storage, 3, 20)?;
+        assert_eq!(page, vec!["c".to_string(), "d".to_string()]);
+        assert_eq!(cursor, None);
+        assert_eq!(deque_store.paging_by_bytes(&storage, 0, 1000)?.0.len(), 5);
+        assert_eq!(deque_store.paging_by_bytes(&storage, 5, 20)?, (vec![], None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_store_migration() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let append_store: AppendStore<u32> = AppendStore::new(b"append");
+        let deque_store: DequeStore<u32> = DequeStore::new_with_page_size(b"deque", 2);
+        for i in 0..7 {
+            append_store.push(&mut storage, &i)?;
+        }
+
+        // a migration copies the append store over several messages
+        assert_eq!(
+            deque_store.from_append_store(&mut storage, &append_store, 3)?,
+            4
+        );
+        assert_eq!(
+            deque_store.from_append_store(&mut storage, &append_store, 3)?,
+            1
+        );
+        assert_eq!(
+            deque_store.from_append_store(&mut storage, &append_store, 3)?,
+            0
+        );
+        assert_eq!(
+            deque_store.to_vec_paged(&storage, 10, 0)?.0,
+            (0..7).collect::<Vec<_>>()
+        );
+        assert_eq!(append_store.get_len(&storage)?, 7);
+        // the cursor is removed once the copy is complete
+        assert_eq!(deque_store.get_u32(&storage, MIGRATED_FROM_KEY)?, 0);
+        assert!(storage
+            .get(&[b"deque".as_slice(), MIGRATED_FROM_KEY].concat())
+            .is_none());
+
+        // and back, from the front of the deque
+        deque_store.pop_front(&mut storage)?;
+        deque_store.push_back(&mut storage, &7)?;
+        let copy: AppendStore<u32, Json> = AppendStore::new(b"copy");
+        assert_eq!(deque_store.to_append_store(&mut storage, &copy, 4)?, 3);
+        assert_eq!(deque_store.to_append_store(&mut storage, &copy, 4)?, 0);
+        assert_eq!(copy.paging(&storage, 0, 10)?, (1..8).collect::<Vec<_>>());
+
+        // an empty append store has nothing to copy
+        let empty: AppendStore<u32> = AppendStore::new(b"empty");
+        assert_eq!(deque_store.from_append_store(&mut storage, &empty, 3)?, 0);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,568 @@
+//! Storage adapters that encrypt values at rest.
+//!
+//! Secret Network already encrypts contract state, but values stored through these adapters
+//! are encrypted a second time, with a key derived from a seed stored by the contract. This
+//! protects them when some values of the raw state become readable without the seed, e.g. in
+//! dumps of some entries taken for debugging.
+//!
+//! The seed itself is stored in plaintext in contract storage, next to the values it protects,
+//! and is only kept secret by the network's encryption of contract state. Anyone who can read
+//! the whole raw state, e.g. on a fork of the chain that strips that encryption, can read the
+//! seed and decrypt the values too.
+//!
+//! Keys are stored in plaintext so entries can still be addressed, and values are encrypted
+//! with ChaCha20-Poly1305. The storage key of a value is authenticated along with it, so a
+//! ciphertext can not be moved to another key. Nonces are derived from the value and its key,
+//! which means that storing the same value at the same key twice produces the same ciphertext.
+
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::to_length_prefixed;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+use crate::{IterOption, Keymap, WithIter, WithoutIter};
+
+/// the minimum length of an encryption seed
+pub const MIN_SEED_LEN: usize = 32;
+
+const KEY_DOMAIN: &[u8] = b"secret_toolkit_storage_encryption";
+const NONCE_LEN: usize = 12;
+
+/// The seed the encryption keys are derived from, stored under its own storage key.
+///
+/// The seed should be random, e.g. taken from `env.block.random` at instantiation, and can only
+/// be set once, since values encrypted under an old seed could no longer be read. It is stored
+/// in plaintext, and relies on the encryption of contract state by Secret Network to stay
+/// secret: on a chain where the state can be read, the seed can be read too.
+#[derive(Clone, Copy)]
+pub struct EncryptionSeed<'a> {
+    storage_key: &'a [u8],
+}
+
+impl<'a> EncryptionSeed<'a> {
+    /// constructor
+    pub const fn new(storage_key: &'a [u8]) -> Self {
+        Self { storage_key }
+    }
+
+    /// Stores the seed. Returns an error if the seed is shorter than [`MIN_SEED_LEN`] bytes, or
+    /// if a seed was already stored.
+    pub fn init(&self, storage: &mut dyn Storage, seed: &[u8]) -> StdResult<()> {
+        if seed.len() < MIN_SEED_LEN {
+            return Err(StdError::generic_err(format!(
+                "encryption seed must be at least {MIN_SEED_LEN} bytes long"
+            )));
+        }
+        if self.is_initialized(storage) {
+            return Err(StdError::generic_err("encryption seed is already set"));
+        }
+        storage.set(self.storage_key, seed);
+        Ok(())
+    }
+
+    /// efficient way to see if the seed was stored.
+    pub fn is_initialized(&self, storage: &dyn Storage) -> bool {
+        storage.get(self.storage_key).is_some()
+    }
+
+    fn key(&self, storage: &dyn Storage) -> StdResult<[u8; 32]> {
+        let seed = storage
+            .get(self.storage_key)
+            .ok_or_else(|| StdError::generic_err("encryption seed is not set"))?;
+        Ok(Sha256::new()
+            .chain_update(KEY_DOMAIN)
+            .chain_update(seed)
+            .finalize()
+            .into())
+    }
+
+    fn encrypt(&self, storage: &dyn Storage, aad: &[u8], plaintext: &[u8]) -> StdResult<Vec<u8>> {
+        let key = self.key(storage)?;
+        // the nonce is derived from the secret key, so it reveals nothing about the value
+        let digest = Sha256::new()
+            .chain_update(key)
+            .chain_update((aad.len() as u64).to_be_bytes())
+            .chain_update(aad)
+            .chain_update(plaintext)
+            .finalize();
+        let nonce = Nonce::from_slice(&digest[..NONCE_LEN]);
+        let ciphertext = ChaCha20Poly1305::new(&key.into())
+            .encrypt(
+                nonce,
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|_| StdError::generic_err("failed to encrypt value"))?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    fn decrypt(&self, storage: &dyn Storage, aad: &[u8], data: &[u8]) -> StdResult<Vec<u8>> {
+        if data.len() < NONCE_LEN {
+            return Err(StdError::generic_err("failed to decrypt value"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        ChaCha20Poly1305::new(&self.key(storage)?.into())
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| StdError::generic_err("failed to decrypt value"))
+    }
+}
+
+/// An Item whose value is encrypted with a key derived from an [`EncryptionSeed`]
+pub struct EncryptedItem<'a, T, Ser = Bincode2>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    storage_key: &'a [u8],
+    /// needed if any suffixes were added to the original storage key.
+    prefix: Option<Vec<u8>>,
+    seed: EncryptionSeed<'a>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> EncryptedItem<'a, T, Ser> {
+    /// constructor
+    pub const fn new(key: &'a [u8], seed: EncryptionSeed<'a>) -> Self {
+        Self {
+            storage_key: key,
+            prefix: None,
+            seed,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new EncryptedItem. This can be used when you want to associate an EncryptedItem to each user
+    /// and you still get to define the EncryptedItem as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let suffix = to_length_prefixed(suffix);
+        let prefix = self.prefix.as_deref().unwrap_or(self.storage_key);
+        let prefix = [prefix, suffix.as_slice()].concat();
+        Self {
+            storage_key: self.storage_key,
+            prefix: Some(prefix),
+            seed: self.seed,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+
+    /// save will serialize and encrypt the model and store, returns an error on serialization
+    /// issues or if the seed is not set
+    pub fn save(&self, storage: &mut dyn Storage, data: &T) -> StdResult<()> {
+        let ciphertext = self
+            .seed
+            .encrypt(storage, self.as_slice(), &Ser::serialize(data)?)?;
+        storage.set(self.as_slice(), &ciphertext);
+        Ok(())
+    }
+
+    /// userfacing remove function
+    pub fn remove(&self, storage: &mut dyn Storage) {
+        storage.remove(self.as_slice());
+    }
+
+    /// load will return an error if no data is set at the given key, or on decryption or parse error
+    pub fn load(&self, storage: &dyn Storage) -> StdResult<T> {
+        self.may_load(storage)?
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))
+    }
+
+    /// may_load will decrypt and parse the data stored at the key if present, returns `Ok(None)` if no data there.
+    /// returns an error on issues decrypting or parsing
+    pub fn may_load(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        match storage.get(self.as_slice()) {
+            Some(data) => {
+                let plaintext = self.seed.decrypt(storage, self.as_slice(), &data)?;
+                Ser::deserialize(&plaintext).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// efficient way to see if any object is currently saved.
+    pub fn is_empty(&self, storage: &dyn Storage) -> bool {
+        storage.get(self.as_slice()).is_none()
+    }
+
+    /// Loads the data, perform the specified action, and store the result
+    /// in the database. This is shorthand for some common sequences, which may be useful.
+    ///
+    /// It assumes, that data was initialized before, and if it doesn't exist, `Err(StdError::NotFound)`
+    /// is returned.
+    pub fn update<A>(&self, storage: &mut dyn Storage, action: A) -> StdResult<T>
+    where
+        A: FnOnce(T) -> StdResult<T>,
+    {
+        let input = self.load(storage)?;
+        let output = action(input)?;
+        self.save(storage, &output)?;
+        Ok(output)
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.storage_key
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, Ser: Serde> Namespaced for EncryptedItem<'_, T, Ser> {
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// A Keymap whose values are encrypted with a key derived from an [`EncryptionSeed`].
+///
+/// Unlike `Keymap::get`, `get` returns an error if a stored value can not be decrypted, so
+/// tampered values are never mistaken for missing ones.
+pub struct EncryptedKeymap<'a, K, T, Ser = Bincode2, I = WithIter>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    keymap: Keymap<'a, K, Vec<u8>, Ser, I>,
+    seed: EncryptionSeed<'a>,
+    item_type: PhantomData<T>,
+}
+
+impl<'a, K, T, Ser> EncryptedKeymap<'a, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    pub const fn new(namespace: &'a [u8], seed: EncryptionSeed<'a>) -> Self {
+        Self {
+            keymap: Keymap::new(namespace),
+            seed,
+            item_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new EncryptedKeymap. This can be used when you want to associate an EncryptedKeymap to each user
+    /// and you still get to define the EncryptedKeymap as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        Self {
+            keymap: self.keymap.add_suffix(suffix),
+            seed: self.seed,
+            item_type: self.item_type,
+        }
+    }
+}
+
+impl<'a, K, T, Ser, I> EncryptedKeymap<'a, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    /// Creates an EncryptedKeymap that stores the encrypted values in the given keymap, e.g.
+    /// one created by a `KeymapBuilder`
+    pub const fn from_keymap(
+        keymap: Keymap<'a, K, Vec<u8>, Ser, I>,
+        seed: EncryptionSeed<'a>,
+    ) -> Self {
+        Self {
+            keymap,
+            seed,
+            item_type: PhantomData,
+        }
+    }
+
+    /// the data authenticated along with the value of `key`. The namespace is length-prefixed,
+    /// so that no two pairs of namespace and key have the same data.
+    fn aad(&self, key: &K) -> StdResult<Vec<u8>> {
+        let namespace = self.keymap.namespace();
+        Ok([
+            (namespace.len() as u32).to_be_bytes().as_slice(),
+            namespace,
+            &Ser::serialize(key)?,
+        ]
+        .concat())
+    }
+
+    fn encrypt(&self, storage: &dyn Storage, key: &K, item: &T) -> StdResult<Vec<u8>> {
+        self.seed
+            .encrypt(storage, &self.aad(key)?, &Ser::serialize(item)?)
+    }
+
+    fn decrypt(&self, storage: &dyn Storage, key: &K, data: &[u8]) -> StdResult<T> {
+        Ser::deserialize(&self.seed.decrypt(storage, &self.aad(key)?, data)?)
+    }
+}
+
+impl<K, T, Ser> EncryptedKeymap<'_, K, T, Ser, WithIter>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// user facing get function, returns an error if the value can not be decrypted
+    pub fn get(&self, storage: &dyn Storage, key: &K) -> StdResult<Option<T>> {
+        self.keymap
+            .get(storage, key)
+            .map(|data| self.decrypt(storage, key, &data))
+            .transpose()
+    }
+
+    /// user facing remove function
+    pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+        self.keymap.remove(storage, key)
+    }
+
+    /// user facing insert function
+    pub fn insert(&self, storage: &mut dyn Storage, key: &K, item: &T) -> StdResult<()> {
+        let data = self.encrypt(storage, key, item)?;
+        self.keymap.insert(storage, key, &data)
+    }
+
+    /// user facing method that checks if any item is stored with this key.
+    pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+        self.keymap.contains(storage, key)
+    }
+
+    /// get total number of objects saved
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.keymap.get_len(storage)
+    }
+
+    /// checks if the collection has any elements
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        self.keymap.is_empty(storage)
+    }
+
+    /// paginates (key, item) pairs.
+    pub fn paging(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<(K, T)>> {
+        self.keymap
+            .paging(storage, start_page, size)?
+            .into_iter()
+            .map(|(key, data)| {
+                let item = self.decrypt(storage, &key, &data)?;
+                Ok((key, item))
+            })
+            .collect()
+    }
+
+    /// paginates only the keys. More efficient than paginating both items and keys
+    pub fn paging_keys(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<K>> {
+        self.keymap.paging_keys(storage, start_page, size)
+    }
+
+    /// Returns a readonly iterator for (key-item) pairs
+    pub fn iter<'b>(
+        &'b self,
+        storage: &'b dyn Storage,
+    ) -> StdResult<impl DoubleEndedIterator<Item = StdResult<(K, T)>> + 'b> {
+        Ok(self.keymap.iter(storage)?.map(move |pair| {
+            let (key, data) = pair?;
+            let item = self.decrypt(storage, &key, &data)?;
+            Ok((key, item))
+        }))
+    }
+}
+
+impl<K, T, Ser> EncryptedKeymap<'_, K, T, Ser, WithoutIter>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// user facing get function, returns an error if the value can not be decrypted
+    pub fn get(&self, storage: &dyn Storage, key: &K) -> StdResult<Option<T>> {
+        self.keymap
+            .get(storage, key)
+            .map(|data| self.decrypt(storage, key, &data))
+            .transpose()
+    }
+
+    /// user facing remove function
+    pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+        self.keymap.remove(storage, key)
+    }
+
+    /// user facing insert function
+    pub fn insert(&self, storage: &mut dyn Storage, key: &K, item: &T) -> StdResult<()> {
+        let data = self.encrypt(storage, key, item)?;
+        self.keymap.insert(storage, key, &data)
+    }
+
+    /// user facing method that checks if any item is stored with this key.
+    pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+        self.keymap.contains(storage, key)
+    }
+}
+
+impl<K, T, Ser, I> Namespaced for EncryptedKeymap<'_, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.keymap.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+    use crate::{Item, KeymapBuilder};
+
+    const SEED: EncryptionSeed = EncryptionSeed::new(b"seed");
+
+    fn init_storage() -> StdResult<MockStorage> {
+        let mut storage = MockStorage::new();
+        SEED.init(&mut storage, &[7; 32])?;
+        Ok(storage)
+    }
+
+    #[test]
+    fn test_seed() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let secret: EncryptedItem<u64> = EncryptedItem::new(b"secret", SEED);
+        assert!(secret.save(&mut storage, &1).is_err());
+
+        assert!(SEED.init(&mut storage, &[7; 31]).is_err());
+        SEED.init(&mut storage, &[7; 32])?;
+        assert!(SEED.init(&mut storage, &[8; 32]).is_err());
+        secret.save(&mut storage, &1)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_item() -> StdResult<()> {
+        let mut storage = init_storage()?;
+        let secret: EncryptedItem<String> = EncryptedItem::new(b"secret", SEED);
+
+        assert_eq!(secret.may_load(&storage)?, None);
+        secret.save(&mut storage, &"hunter2".to_string())?;
+        assert_eq!(secret.load(&storage)?, "hunter2");
+
+        // the raw value is not readable as the plain item
+        let plain: Item<String> = Item::new(b"secret");
+        assert!(plain.load(&storage).is_err());
+        let raw = storage.get(b"secret").unwrap();
+        assert!(!raw.windows(7).any(|w| w == b"hunter2"));
+
+        // the ciphertext is bound to its key
+        let other = secret.add_suffix(b"other");
+        storage.set(other.as_slice(), &raw);
+        assert!(other.load(&storage).is_err());
+
+        secret.update(&mut storage, |s| Ok(s + "!"))?;
+        assert_eq!(secret.load(&storage)?, "hunter2!");
+        secret.remove(&mut storage);
+        assert!(secret.is_empty(&storage));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_keymap() -> StdResult<()> {
+        let mut storage = init_storage()?;
+        let secrets: EncryptedKeymap<String, u64> = EncryptedKeymap::new(b"secrets", SEED);
+
+        secrets.insert(&mut storage, &"alice".to_string(), &1)?;
+        secrets.insert(&mut storage, &"bob".to_string(), &2)?;
+        assert_eq!(secrets.get(&storage, &"alice".to_string())?, Some(1));
+        assert_eq!(secrets.get(&storage, &"carol".to_string())?, None);
+        assert_eq!(secrets.get_len(&storage)?, 2);
+
+        let pairs: Vec<(String, u64)> = secrets.iter(&storage)?.collect::<StdResult<_>>()?;
+        assert_eq!(
+            pairs,
+            vec![("alice".to_string(), 1), ("bob".to_string(), 2)]
+        );
+        assert_eq!(
+            secrets.paging(&storage, 1, 1)?,
+            vec![("bob".to_string(), 2)]
+        );
+
+        // a ciphertext copied to another key can not be decrypted
+        let plain: Keymap<String, Vec<u8>> = Keymap::new(b"secrets");
+        let alice = plain.get(&storage, &"alice".to_string()).unwrap();
+        plain.insert(&mut storage, &"bob".to_string(), &alice)?;
+        assert!(secrets.get(&storage, &"bob".to_string()).is_err());
+
+        secrets.remove(&mut storage, &"alice".to_string())?;
+        assert!(!secrets.contains(&storage, &"alice".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_keymap_namespace_boundary() -> StdResult<()> {
+        let mut storage = init_storage()?;
+        // the namespace and key of these entries have the same concatenation, `abc`
+        let short: EncryptedKeymap<u8, u64> = EncryptedKeymap::new(b"ab", SEED);
+        let long: EncryptedKeymap<u16, u64> = EncryptedKeymap::new(b"a", SEED);
+        let long_key = u16::from_le_bytes([b'b', b'c']);
+
+        short.insert(&mut storage, &b'c', &1)?;
+        let raw = Keymap::<u8, Vec<u8>>::new(b"ab")
+            .get(&storage, &b'c')
+            .unwrap();
+        Keymap::<u16, Vec<u8>>::new(b"a").insert(&mut storage, &long_key, &raw)?;
+        assert!(long.get(&storage, &long_key).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_keymap_without_iter() -> StdResult<()> {
+        let mut storage = init_storage()?;
+        let secrets: EncryptedKeymap<u32, String, Bincode2, WithoutIter> =
+            EncryptedKeymap::from_keymap(
+                KeymapBuilder::new(b"secrets").without_iter().build(),
+                SEED,
+            );
+
+        secrets.insert(&mut storage, &1, &"one".to_string())?;
+        assert_eq!(secrets.get(&storage, &1)?, Some("one".to_string()));
+        secrets.remove(&mut storage, &1)?;
+        assert!(!secrets.contains(&storage, &1));
+
+        Ok(())
+    }
+}
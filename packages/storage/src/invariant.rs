@@ -0,0 +1,230 @@
+//! Invariants checked on every guarded mutation of the storage.
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Env, StdError, StdResult, Storage};
+
+type Invariant<'a> = Box<dyn Fn(&dyn Storage, &Env) -> StdResult<()> + 'a>;
+
+/// Runs mutations of the storage through a set of invariants, such as "the sum of the balances
+/// equals the total supply", and rejects the mutations that break any of them before their
+/// writes reach the storage.
+///
+/// The invariants are checked in debug builds and when the `invariants` feature is enabled.
+/// Use `on_chain` to check them in every build, at the cost of the gas they use.
+///
+/// ```
+/// # use cosmwasm_std::{testing::{mock_env, MockStorage}, StdError, StdResult};
+/// # use secret_toolkit_storage::{invariant::InvariantGuard, Item};
+/// pub static BALANCE: Item<u128> = Item::new(b"balance");
+/// pub static TOTAL: Item<u128> = Item::new(b"total");
+///
+/// let mut storage = MockStorage::new();
+/// let env = mock_env();
+/// let guard = InvariantGuard::new().with_invariant("balances match total", |storage, _env| {
+///     if BALANCE.may_load(storage)? != TOTAL.may_load(storage)? {
+///         return Err(StdError::generic_err("balance differs from total"));
+///     }
+///     Ok(())
+/// });
+///
+/// guard.write(&mut storage, &env, |storage| {
+///     BALANCE.save(storage, &100)?;
+///     TOTAL.save(storage, &100)
+/// })?;
+///
+/// // the balance is not saved, as the total was not updated along with it
+/// let result = guard.write(&mut storage, &env, |storage| BALANCE.save(storage, &200));
+/// assert!(result.is_err());
+/// assert_eq!(BALANCE.load(&storage)?, 100);
+/// # Ok::<(), StdError>(())
+/// ```
+#[derive(Default)]
+pub struct InvariantGuard<'a> {
+    invariants: Vec<(&'a str, Invariant<'a>)>,
+    on_chain: bool,
+}
+
+impl<'a> InvariantGuard<'a> {
+    /// Creates a guard without invariants
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an invariant, which returns an error when it is violated
+    pub fn with_invariant(
+        mut self,
+        name: &'a str,
+        invariant: impl Fn(&dyn Storage, &Env) -> StdResult<()> + 'a,
+    ) -> Self {
+        self.invariants.push((name, Box::new(invariant)));
+        self
+    }
+
+    /// Checks the invariants in every build, including release builds without the
+    /// `invariants` feature
+    pub fn on_chain(mut self) -> Self {
+        self.on_chain = true;
+        self
+    }
+
+    /// Returns whether the invariants are checked in this build
+    pub fn is_enabled(&self) -> bool {
+        self.on_chain || cfg!(any(debug_assertions, feature = "invariants"))
+    }
+
+    /// Returns an error if any invariant is violated by the current state of the storage
+    pub fn check(&self, storage: &dyn Storage, env: &Env) -> StdResult<()> {
+        for (name, invariant) in &self.invariants {
+            invariant(storage, env).map_err(|err| {
+                StdError::generic_err(format!("invariant '{name}' violated: {err}"))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs `mutation`, and writes its changes to the storage only if they don't violate any
+    /// invariant. Otherwise, the storage is left as it was and the violation is returned.
+    ///
+    /// When the invariants are not checked in this build, `mutation` writes to the storage
+    /// directly.
+    ///
+    /// Collections may cache values such as their length, so instances used by a rejected
+    /// mutation should not be used afterwards.
+    pub fn write<T>(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        mutation: impl FnOnce(&mut dyn Storage) -> StdResult<T>,
+    ) -> StdResult<T> {
+        if !self.is_enabled() {
+            return mutation(storage);
+        }
+
+        let mut buffer = WriteBuffer::new(storage);
+        let result = mutation(&mut buffer)?;
+        self.check(&buffer, env)?;
+
+        let writes = buffer.writes;
+        for (key, value) in writes {
+            match value {
+                Some(value) => storage.set(&key, &value),
+                None => storage.remove(&key),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// A storage that holds writes in memory on top of another storage
+struct WriteBuffer<'a> {
+    storage: &'a dyn Storage,
+    /// the values written, or `None` for removed keys
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> WriteBuffer<'a> {
+    fn new(storage: &'a dyn Storage) -> Self {
+        Self {
+            storage,
+            writes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Storage for WriteBuffer<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.storage.get(key),
+        }
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Item, Keymap};
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    static BALANCES: Keymap<String, u128> = Keymap::new(b"balances");
+    static TOTAL: Item<u128> = Item::new(b"total");
+
+    fn guard() -> InvariantGuard<'static> {
+        InvariantGuard::new().with_invariant("total matches balances", |storage, _env| {
+            let sum: u128 = BALANCES
+                .iter(storage)?
+                .map(|entry| entry.map(|(_, balance)| balance))
+                .sum::<StdResult<u128>>()?;
+            if sum != TOTAL.may_load(storage)?.unwrap_or_default() {
+                return Err(StdError::generic_err("sum of balances differs from total"));
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_invariant_guard() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let env = mock_env();
+        let guard = guard();
+        assert!(guard.is_enabled());
+
+        guard.write(&mut storage, &env, |storage| {
+            BALANCES.insert(storage, &"alice".to_string(), &60)?;
+            BALANCES.insert(storage, &"bob".to_string(), &40)?;
+            TOTAL.save(storage, &100)
+        })?;
+        assert_eq!(TOTAL.load(&storage)?, 100);
+
+        let result = guard.write(&mut storage, &env, |storage| {
+            BALANCES.insert(storage, &"carol".to_string(), &10)?;
+            TOTAL.save(storage, &100)
+        });
+        assert_eq!(
+            result,
+            Err(StdError::generic_err(
+                "invariant 'total matches balances' violated: Generic error: sum of balances \
+                 differs from total"
+            ))
+        );
+        // nothing was written
+        let balances: Keymap<String, u128> = Keymap::new(b"balances");
+        assert_eq!(balances.get(&storage, &"carol".to_string()), None);
+        assert_eq!(balances.get_len(&storage)?, 2);
+
+        // errors of the mutation itself are returned as is
+        let result: StdResult<()> = guard.write(&mut storage, &env, |storage| {
+            TOTAL.save(storage, &0)?;
+            Err(StdError::generic_err("failed"))
+        });
+        assert_eq!(result, Err(StdError::generic_err("failed")));
+        assert_eq!(TOTAL.load(&storage)?, 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_buffer() {
+        let mut storage = MockStorage::new();
+        storage.set(b"a", b"1");
+        storage.set(b"b", b"2");
+
+        let mut buffer = WriteBuffer::new(&storage);
+        buffer.set(b"a", b"3");
+        buffer.remove(b"b");
+        buffer.set(b"c", b"4");
+        assert_eq!(buffer.get(b"a"), Some(b"3".to_vec()));
+        assert_eq!(buffer.get(b"b"), None);
+        assert_eq!(buffer.get(b"c"), Some(b"4".to_vec()));
+        assert_eq!(storage.get(b"a"), Some(b"1".to_vec()));
+    }
+}
@@ -6,8 +6,11 @@ use cosmwasm_std::{StdError, StdResult, Storage};
 use cosmwasm_storage::to_length_prefixed;
 
 use secret_toolkit_serialization::{Bincode2, Serde};
+
 use serde::{de::DeserializeOwned, Serialize};
 
+use crate::namespace::Namespaced;
+
 /// This storage struct is based on Item from cosmwasm-storage-plus
 pub struct Item<'a, T, Ser = Bincode2>
 where
@@ -149,6 +152,12 @@ where
     }
 }
 
+impl<T: Serialize + DeserializeOwned, Ser: Serde> Namespaced for Item<'_, T, Ser> {
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::MockStorage;
@@ -1,17 +1,22 @@
 use std::any::type_name;
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::fmt;
 use std::marker::PhantomData;
 use std::sync::Mutex;
 
-use serde::Deserialize;
-use serde::{de::DeserializeOwned, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 
-use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_std::{Env, StdError, StdResult, Storage};
 use cosmwasm_storage::to_length_prefixed;
+use sha2::{Digest, Sha256};
 
 use secret_toolkit_serialization::{Bincode2, Serde};
 
+use crate::keyset::Keyset;
+use crate::namespace::Namespaced;
 use crate::{IterOption, WithIter, WithoutIter};
 
 const INDEXES: &[u8] = b"indexes";
@@ -19,7 +24,64 @@ const MAP_LENGTH: &[u8] = b"length";
 
 const DEFAULT_PAGE_SIZE: u32 = 1;
 
-#[derive(Serialize, Deserialize)]
+/// Marks the interned prefixes of a namespace. A plain suffix can't start with it, as it would
+/// be the length prefix of a suffix that is 65535 bytes long, which is always interned.
+const INTERNED_MARKER: &[u8] = &[0xff, 0xff];
+/// Marks the registry of the full prefixes of the interned prefixes of a namespace
+const INTERNED_REGISTRY_MARKER: &[u8] = &[0xff, 0xfe];
+/// The number of bytes of the hash of the full prefix used in an interned prefix
+const INTERNED_HASH_LEN: usize = 8;
+
+/// Returns the interned form of `prefix` along with the full prefix, or `prefix` itself if it
+/// isn't longer than its interned form.
+fn intern_prefix(namespace: &[u8], prefix: Vec<u8>) -> (Vec<u8>, Option<Vec<u8>>) {
+    if prefix.len() <= namespace.len() + INTERNED_MARKER.len() + INTERNED_HASH_LEN {
+        return (prefix, None);
+    }
+    let hash = Sha256::digest(&prefix);
+    let interned = [namespace, INTERNED_MARKER, &hash[..INTERNED_HASH_LEN]].concat();
+    (interned, Some(prefix))
+}
+
+/// Returns the key of the registry entry that holds the full prefix of an interned prefix
+fn interned_registry_key(namespace: &[u8], interned: &[u8]) -> Vec<u8> {
+    let hash = &interned[namespace.len() + INTERNED_MARKER.len()..];
+    [namespace, INTERNED_REGISTRY_MARKER, hash].concat()
+}
+
+/// Returns the page `start_page` of the results of `f` over the items of `iter`, skipping the
+/// items for which it returns `None`, without scanning past the end of the page.
+pub(crate) fn filter_page<X, R>(
+    iter: impl Iterator<Item = StdResult<X>>,
+    start_page: u32,
+    size: u32,
+    mut f: impl FnMut(X) -> Option<R>,
+) -> StdResult<Vec<R>> {
+    let mut to_skip = start_page as u64 * size as u64;
+    let mut page = vec![];
+    for item in iter {
+        if page.len() == size as usize {
+            break;
+        }
+        if let Some(result) = f(item?) {
+            if to_skip > 0 {
+                to_skip -= 1;
+            } else {
+                page.push(result);
+            }
+        }
+    }
+    Ok(page)
+}
+
+/// The block heights at which an entry of a keymap built `with_timestamps` was created and
+/// last updated
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntryMeta {
+    pub created: u64,
+    pub updated: u64,
+}
+
 struct InternalItem<T, Ser>
 where
     T: Serialize + DeserializeOwned,
@@ -30,15 +92,25 @@ where
     index_pos: Option<u32>,
     item_type: PhantomData<T>,
     serialization_type: PhantomData<Ser>,
+    // only Some if we enabled timestamps
+    meta: Option<EntryMeta>,
 }
 
+/// Set in the tag of a stored item if it has an index position, which makes the tag the same
+/// as the one of the `Option<u32>` it replaced
+const ITEM_HAS_POS: u8 = 1;
+/// Set in the tag of a stored item if it has metadata. Items stored before it was added never
+/// have it set.
+const ITEM_HAS_META: u8 = 2;
+
 impl<T: Serialize + DeserializeOwned, Ser: Serde> InternalItem<T, Ser> {
-    fn new(index_pos: Option<u32>, item: &T) -> StdResult<Self> {
+    fn new(index_pos: Option<u32>, item: &T, meta: Option<EntryMeta>) -> StdResult<Self> {
         Ok(Self {
             item_vec: Ser::serialize(item)?,
             index_pos,
             item_type: PhantomData,
             serialization_type: PhantomData,
+            meta,
         })
     }
 
@@ -47,10 +119,107 @@ impl<T: Serialize + DeserializeOwned, Ser: Serde> InternalItem<T, Ser> {
     }
 }
 
+// Manual `Serialize` and `Deserialize` implementations, so that the metadata is only read when
+// the tag of the item says it was stored. The tag replaces the one of the `Option<u32>` index
+// position, which keeps the layout of items stored without metadata.
+impl<T, Ser> Serialize for InternalItem<T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tag = 0;
+        if self.index_pos.is_some() {
+            tag |= ITEM_HAS_POS;
+        }
+        if self.meta.is_some() {
+            tag |= ITEM_HAS_META;
+        }
+        let len = 2 + self.index_pos.is_some() as usize + self.meta.is_some() as usize;
+        let mut tuple = serializer.serialize_tuple(len)?;
+        tuple.serialize_element(&self.item_vec)?;
+        tuple.serialize_element(&tag)?;
+        if let Some(pos) = &self.index_pos {
+            tuple.serialize_element(pos)?;
+        }
+        if let Some(meta) = &self.meta {
+            tuple.serialize_element(meta)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, T, Ser> Deserialize<'de> for InternalItem<T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct InternalItemVisitor<T, Ser>(PhantomData<(T, Ser)>);
+
+        impl<'de, T, Ser> Visitor<'de> for InternalItemVisitor<T, Ser>
+        where
+            T: Serialize + DeserializeOwned,
+            Ser: Serde,
+        {
+            type Value = InternalItem<T, Ser>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct InternalItem")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let item_vec = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let tag: u8 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                if tag & !(ITEM_HAS_POS | ITEM_HAS_META) != 0 {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Unsigned(tag.into()),
+                        &"an item tag",
+                    ));
+                }
+                let index_pos = if tag & ITEM_HAS_POS != 0 {
+                    Some(
+                        seq.next_element()?
+                            .ok_or_else(|| de::Error::invalid_length(2, &self))?,
+                    )
+                } else {
+                    None
+                };
+                let meta = if tag & ITEM_HAS_META != 0 {
+                    Some(
+                        seq.next_element()?
+                            .ok_or_else(|| de::Error::invalid_length(3, &self))?,
+                    )
+                } else {
+                    None
+                };
+                Ok(InternalItem {
+                    item_vec,
+                    index_pos,
+                    item_type: PhantomData,
+                    serialization_type: PhantomData,
+                    meta,
+                })
+            }
+        }
+
+        deserializer.deserialize_tuple(4, InternalItemVisitor(PhantomData))
+    }
+}
+
 pub struct KeymapBuilder<'a, K, T, Ser = Bincode2, I = WithIter> {
     /// namespace of the newly constructed Storage
     namespace: &'a [u8],
     page_size: u32,
+    shared_len: bool,
+    counted: bool,
+    interned_prefix: bool,
+    timestamps: bool,
+    suffix_registry: Option<&'a [u8]>,
     key_type: PhantomData<K>,
     item_type: PhantomData<T>,
     serialization_type: PhantomData<Ser>,
@@ -68,6 +237,11 @@ where
         Self {
             namespace,
             page_size: DEFAULT_PAGE_SIZE,
+            shared_len: false,
+            counted: false,
+            interned_prefix: false,
+            timestamps: false,
+            suffix_registry: None,
             key_type: PhantomData,
             item_type: PhantomData,
             serialization_type: PhantomData,
@@ -82,6 +256,97 @@ where
         Self {
             namespace: self.namespace,
             page_size: indexes_size,
+            shared_len: self.shared_len,
+            counted: self.counted,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+            iter_option: self.iter_option,
+        }
+    }
+    /// Keeps the length consistent between all the instances of the keymap that use the
+    /// same namespace (and suffixes), e.g. when `add_suffix` is called twice with the same suffix
+    /// in one contract call. The length is then read from storage on every lookup instead of
+    /// being cached, which costs a storage read per lookup.
+    pub const fn with_shared_len(&self) -> Self {
+        Self {
+            namespace: self.namespace,
+            page_size: self.page_size,
+            shared_len: true,
+            counted: self.counted,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+            iter_option: self.iter_option,
+        }
+    }
+    /// Stores the prefix of suffixed keymaps once, and uses a short hash of it in the keys of
+    /// their entries instead, when that is shorter. This saves storage on keymaps with long
+    /// or many suffixes, at the cost of an extra read the first time each suffixed keymap is
+    /// accessed.
+    ///
+    /// Changing this option changes the keys of existing entries, so it can't be enabled on a
+    /// keymap that already has entries.
+    pub const fn with_interned_prefix(&self) -> Self {
+        Self {
+            namespace: self.namespace,
+            page_size: self.page_size,
+            shared_len: self.shared_len,
+            counted: self.counted,
+            interned_prefix: true,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+            iter_option: self.iter_option,
+        }
+    }
+    /// Records the block heights at which each entry was created and last updated, along with
+    /// its value, which lets contracts check the freshness of entries or prune stale ones. The
+    /// heights are recorded by `insert_at` and read with `get_with_meta`, while `insert` keeps
+    /// the heights of an existing entry as they are, and writes a new entry without them.
+    ///
+    /// This can be enabled on a keymap that already has entries, which get their heights at
+    /// their next `insert_at`. It requires the iterator, as the heights are kept next to the index
+    /// of the entry.
+    pub const fn with_timestamps(&self) -> Self {
+        Self {
+            namespace: self.namespace,
+            page_size: self.page_size,
+            shared_len: self.shared_len,
+            counted: self.counted,
+            interned_prefix: self.interned_prefix,
+            timestamps: true,
+            suffix_registry: self.suffix_registry,
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+            iter_option: self.iter_option,
+        }
+    }
+    /// Records each suffix added to the keymap with `add_suffix` in a keyset stored under
+    /// `registry_namespace`, the first time an entry is inserted in the suffixed keymap, so that
+    /// the suffixes in use can be enumerated with `iter_suffixes`, e.g. to sweep the per-user
+    /// keymaps of all users. Only the suffixes added to the keymap itself are recorded, not
+    /// the further suffixes of suffixed keymaps.
+    ///
+    /// Suffixes used before the registry was enabled are only recorded at their next insertion.
+    pub const fn with_suffix_registry(&self, registry_namespace: &'a [u8]) -> Self {
+        Self {
+            namespace: self.namespace,
+            page_size: self.page_size,
+            shared_len: self.shared_len,
+            counted: self.counted,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: Some(registry_namespace),
             key_type: self.key_type,
             item_type: self.item_type,
             serialization_type: self.serialization_type,
@@ -90,9 +355,17 @@ where
     }
     /// Disables the iterator of the keymap, saving at least 4000 gas in each insertion.
     pub const fn without_iter(&self) -> KeymapBuilder<'a, K, T, Ser, WithoutIter> {
+        if self.timestamps {
+            panic!("keymap timestamps require the iterator")
+        }
         KeymapBuilder {
             namespace: self.namespace,
             page_size: self.page_size,
+            shared_len: self.shared_len,
+            counted: self.counted,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
             key_type: PhantomData,
             item_type: PhantomData,
             serialization_type: PhantomData,
@@ -104,8 +377,17 @@ where
         Keymap {
             namespace: self.namespace,
             prefix: None,
+            interned: None,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            interned_checked: Mutex::new(false),
+            suffix: None,
+            suffix_registered: Mutex::new(false),
             page_size: self.page_size,
             length: Mutex::new(None),
+            shared_len: self.shared_len,
+            counted: self.counted,
             key_type: self.key_type,
             item_type: self.item_type,
             iter_option: self.iter_option,
@@ -121,12 +403,42 @@ where
     T: Serialize + DeserializeOwned,
     Ser: Serde,
 {
+    /// Keeps the length of the keymap, without the index pages of the iterator, so that
+    /// `get_len` can still tell how many entries it has. Each insertion and removal then costs
+    /// an extra read of the entry, and a write of the length when it changes.
+    ///
+    /// Entries inserted before this was enabled aren't counted, so the length is only exact
+    /// on a keymap that had it enabled from its first entry.
+    pub const fn with_len(&self) -> Self {
+        Self {
+            namespace: self.namespace,
+            page_size: self.page_size,
+            shared_len: self.shared_len,
+            counted: true,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+            iter_option: self.iter_option,
+        }
+    }
     pub const fn build(&self) -> Keymap<'a, K, T, Ser, WithoutIter> {
         Keymap {
             namespace: self.namespace,
             prefix: None,
+            interned: None,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            interned_checked: Mutex::new(false),
+            suffix: None,
+            suffix_registered: Mutex::new(false),
             page_size: self.page_size,
             length: Mutex::new(None),
+            shared_len: self.shared_len,
+            counted: self.counted,
             key_type: self.key_type,
             item_type: self.item_type,
             iter_option: self.iter_option,
@@ -146,8 +458,27 @@ where
     namespace: &'a [u8],
     /// needed if any suffixes were added to the original namespace.
     prefix: Option<Vec<u8>>,
+    /// the full prefix, if `prefix` holds its interned form
+    interned: Option<Vec<u8>>,
+    /// whether suffixed prefixes are interned
+    interned_prefix: bool,
+    /// whether the interned prefix was checked against the registry
+    interned_checked: Mutex<bool>,
+    /// whether entries record the block heights they were created and updated at
+    timestamps: bool,
+    /// the namespace of the registry of the suffixes added to the keymap, if they are recorded
+    suffix_registry: Option<&'a [u8]>,
+    /// the suffix added to the original keymap, as recorded in the registry
+    suffix: Option<Vec<u8>>,
+    /// whether the suffix was checked against the registry
+    suffix_registered: Mutex<bool>,
     page_size: u32,
     length: Mutex<Option<u32>>,
+    /// whether the length is read from storage on every lookup instead of being cached, so that
+    /// updates made through other instances are seen
+    shared_len: bool,
+    /// whether a keymap without the iterator keeps its length
+    counted: bool,
     key_type: PhantomData<K>,
     item_type: PhantomData<T>,
     iter_option: PhantomData<I>,
@@ -162,8 +493,17 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
         Self {
             namespace,
             prefix: None,
+            interned: None,
+            interned_prefix: false,
+            timestamps: false,
+            suffix_registry: None,
+            suffix: None,
+            suffix_registered: Mutex::new(false),
+            interned_checked: Mutex::new(false),
             page_size: DEFAULT_PAGE_SIZE,
             length: Mutex::new(None),
+            shared_len: false,
+            counted: false,
             key_type: PhantomData,
             item_type: PhantomData,
             serialization_type: PhantomData,
@@ -174,14 +514,29 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
     /// This is used to produce a new Keymap. This can be used when you want to associate an Keymap to each user
     /// and you still get to define the Keymap as a static constant
     pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let raw_suffix = self.suffix.clone().unwrap_or_else(|| suffix.to_vec());
         let suffix = to_length_prefixed(suffix);
-        let prefix = self.prefix.as_deref().unwrap_or(self.namespace);
+        let prefix = self.namespace();
         let prefix = [prefix, suffix.as_slice()].concat();
+        let (prefix, interned) = if self.interned_prefix {
+            intern_prefix(self.namespace, prefix)
+        } else {
+            (prefix, None)
+        };
         Self {
             namespace: self.namespace,
             prefix: Some(prefix),
+            interned,
+            interned_prefix: self.interned_prefix,
+            timestamps: self.timestamps,
+            suffix_registry: self.suffix_registry,
+            suffix: Some(raw_suffix),
+            suffix_registered: Mutex::new(false),
+            interned_checked: Mutex::new(false),
             page_size: self.page_size,
             length: Mutex::new(None),
+            shared_len: self.shared_len,
+            counted: self.counted,
             key_type: self.key_type,
             item_type: self.item_type,
             serialization_type: self.serialization_type,
@@ -190,6 +545,138 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
     }
 }
 
+impl<K, T, Ser, I> Keymap<'_, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    /// Returns whether the full prefix of the interned prefix is registered, or true if the
+    /// prefix isn't interned. Returns an error if the interned prefix is registered to another
+    /// full prefix, as two full prefixes whose hashes collide would otherwise read each
+    /// other's entries.
+    fn is_interned_registered(&self, storage: &dyn Storage) -> StdResult<bool> {
+        let (Some(prefix), Some(full_prefix)) = (&self.prefix, &self.interned) else {
+            return Ok(true);
+        };
+        let mut checked = self.interned_checked.lock().unwrap();
+        if *checked {
+            return Ok(true);
+        }
+        match storage.get(&interned_registry_key(self.namespace, prefix)) {
+            Some(registered) if &registered == full_prefix => {
+                *checked = true;
+                Ok(true)
+            }
+            Some(_) => Err(StdError::generic_err(
+                "keymap: interned prefix collides with another prefix",
+            )),
+            None => Ok(false),
+        }
+    }
+
+    /// Registers the full prefix of the interned prefix, if it wasn't yet. This must be done
+    /// before writing any entry.
+    fn register_interned(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        if let (Some(prefix), Some(full_prefix)) = (&self.prefix, &self.interned) {
+            if !self.is_interned_registered(storage)? {
+                storage.set(&interned_registry_key(self.namespace, prefix), full_prefix);
+                *self.interned_checked.lock().unwrap() = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, K, T, Ser, I> Keymap<'a, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    /// the prefix of the keys of the entries and of the length
+    fn prefix_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.namespace
+        }
+    }
+
+    /// returns the length of the map, from the cache if it is still valid
+    fn len_impl(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.is_interned_registered(storage)?;
+        let mut may_len = self.length.lock().unwrap();
+        if self.shared_len {
+            *may_len = None;
+        }
+        match *may_len {
+            Some(length) => Ok(length),
+            None => {
+                let len_key = [self.prefix_slice(), MAP_LENGTH].concat();
+                if let Some(len_vec) = storage.get(&len_key) {
+                    let len_bytes = len_vec
+                        .as_slice()
+                        .try_into()
+                        .map_err(|err| StdError::parse_err("u32", err))?;
+                    let len = u32::from_be_bytes(len_bytes);
+                    *may_len = Some(len);
+                    Ok(len)
+                } else {
+                    *may_len = Some(0);
+                    Ok(0)
+                }
+            }
+        }
+    }
+
+    /// set length of the map
+    fn set_len(&self, storage: &mut dyn Storage, len: u32) -> StdResult<()> {
+        let len_key = [self.prefix_slice(), MAP_LENGTH].concat();
+        storage.set(&len_key, &len.to_be_bytes());
+
+        let mut may_len = self.length.lock().unwrap();
+        *may_len = Some(len);
+
+        Ok(())
+    }
+
+    /// Records the suffix of the keymap in the suffix registry, if it wasn't yet. This is done
+    /// before writing any entry.
+    fn register_suffix(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        if let (Some(registry), Some(suffix)) = (self.suffix_registry, &self.suffix) {
+            let mut registered = self.suffix_registered.lock().unwrap();
+            if !*registered {
+                Keyset::<Vec<u8>>::new(registry).insert(storage, suffix)?;
+                *registered = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the suffixes added to the keymap with `add_suffix` that entries
+    /// were inserted under, in the order they were first used. Fails if the keymap wasn't built
+    /// `with_suffix_registry`.
+    pub fn iter_suffixes<'s>(&self, storage: &'s dyn Storage) -> StdResult<SuffixIter<'s>>
+    where
+        'a: 's,
+    {
+        let registry = self
+            .suffix_registry
+            .ok_or_else(|| StdError::generic_err("keymap: the suffixes are not recorded"))?;
+        let registry = Keyset::new(registry);
+        let end = registry.get_len(storage)?;
+        Ok(SuffixIter {
+            registry,
+            storage,
+            start: 0,
+            end,
+        })
+    }
+}
+
 impl<K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser: Serde>
     Keymap<'_, K, T, Ser, WithoutIter>
 {
@@ -205,13 +692,19 @@ impl<K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser: Serd
 
     /// internal item get function
     fn get_from_key(&self, storage: &dyn Storage, key: &K) -> StdResult<T> {
+        self.is_interned_registered(storage)?;
         let key_vec = self.serialize_key(key)?;
         self.load_impl(storage, &key_vec)
     }
 
     /// user facing remove function
     pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+        self.is_interned_registered(storage)?;
         let key_vec = self.serialize_key(key)?;
+        if self.counted && self.contains_impl(storage, &key_vec) {
+            let len = self.len_impl(storage)?;
+            self.set_len(storage, len.saturating_sub(1))?;
+        }
         self.remove_impl(storage, &key_vec);
 
         Ok(())
@@ -219,12 +712,43 @@ impl<K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser: Serd
 
     /// user facing insert function
     pub fn insert(&self, storage: &mut dyn Storage, key: &K, item: &T) -> StdResult<()> {
+        self.register_interned(storage)?;
+        self.register_suffix(storage)?;
         let key_vec = self.serialize_key(key)?;
+        if self.counted && !self.contains_impl(storage, &key_vec) {
+            let len = self.len_impl(storage)?;
+            self.set_len(storage, len + 1)?;
+        }
         self.save_impl(storage, &key_vec, item)
     }
 
+    /// get total number of objects saved. Fails unless the keymap was built `with_len`.
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        if !self.counted {
+            return Err(StdError::generic_err(
+                "keymap: the length of a keymap without the iterator is only kept with_len",
+            ));
+        }
+        self.len_impl(storage)
+    }
+
+    /// checks if the collection has any elements. Fails unless the keymap was built `with_len`.
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.get_len(storage)? == 0)
+    }
+
+    /// discards the cached length and reads it again from storage, see the method of keymaps
+    /// with the iterator
+    pub fn refresh_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        *self.length.lock().unwrap() = None;
+        self.get_len(storage)
+    }
+
     /// user facing method that checks if any item is stored with this key.
     pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+        if self.is_interned_registered(storage).is_err() {
+            return false;
+        }
         match self.serialize_key(key) {
             Ok(key_vec) => self.contains_impl(storage, &key_vec),
             Err(_) => false,
@@ -251,25 +775,7 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
 
     /// get total number of objects saved
     pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
-        let mut may_len = self.length.lock().unwrap();
-        match *may_len {
-            Some(length) => Ok(length),
-            None => {
-                let len_key = [self.as_slice(), MAP_LENGTH].concat();
-                if let Some(len_vec) = storage.get(&len_key) {
-                    let len_bytes = len_vec
-                        .as_slice()
-                        .try_into()
-                        .map_err(|err| StdError::parse_err("u32", err))?;
-                    let len = u32::from_be_bytes(len_bytes);
-                    *may_len = Some(len);
-                    Ok(len)
-                } else {
-                    *may_len = Some(0);
-                    Ok(0)
-                }
-            }
-        }
+        self.len_impl(storage)
     }
 
     /// checks if the collection has any elements
@@ -277,15 +783,13 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
         Ok(self.get_len(storage)? == 0)
     }
 
-    /// set length of the map
-    fn set_len(&self, storage: &mut dyn Storage, len: u32) -> StdResult<()> {
-        let len_key = [self.as_slice(), MAP_LENGTH].concat();
-        storage.set(&len_key, &len.to_be_bytes());
-
-        let mut may_len = self.length.lock().unwrap();
-        *may_len = Some(len);
-
-        Ok(())
+    /// discards the cached length and reads it again from storage.
+    ///
+    /// This is needed when the map may have been modified through another instance of it,
+    /// as each instance caches the length separately.
+    pub fn refresh_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        *self.length.lock().unwrap() = None;
+        self.get_len(storage)
     }
 
     /// Used to get the indexes stored in the given page number
@@ -335,12 +839,27 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
         }
     }
 
+    /// returns the value of an entry along with the block heights it was created and last
+    /// updated at. The heights are `None` for entries that were last written before the keymap
+    /// was built `with_timestamps`.
+    pub fn get_with_meta(&self, storage: &dyn Storage, key: &K) -> Option<(T, Option<EntryMeta>)> {
+        let internal_item = self.get_from_key(storage, key).ok()?;
+        Some((internal_item.get_item().ok()?, internal_item.meta))
+    }
+
     /// internal item get function
     fn get_from_key(&self, storage: &dyn Storage, key: &K) -> StdResult<InternalItem<T, Ser>> {
+        self.is_interned_registered(storage)?;
         let key_vec = self.serialize_key(key)?;
         self.load_impl(storage, &key_vec)
     }
 
+    /// internal item get function from a serialized key, as stored in the indexes
+    fn get_from_key_data(&self, storage: &dyn Storage, key_data: &[u8]) -> StdResult<T> {
+        self.is_interned_registered(storage)?;
+        self.load_impl(storage, key_data)?.get_item()
+    }
+
     /// user facing remove function
     pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
         let key_vec = self.serialize_key(key)?;
@@ -405,14 +924,55 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
         Ok(())
     }
 
-    /// user facing insert function
+    /// user facing insert function. In a keymap built `with_timestamps`, the block heights of an
+    /// existing entry are kept as they are, and a new entry is written without them, use
+    /// `insert_at` to record them.
     pub fn insert(&self, storage: &mut dyn Storage, key: &K, item: &T) -> StdResult<()> {
+        self.insert_impl(storage, key, item, None)
+    }
+
+    /// inserts an entry in a keymap built `with_timestamps`, recording the height of the current
+    /// block as the one it was updated at, and also created at if it is new.
+    pub fn insert_at(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        key: &K,
+        item: &T,
+    ) -> StdResult<()> {
+        if !self.timestamps {
+            return Err(StdError::generic_err(
+                "keymap without timestamps: use insert to insert entries",
+            ));
+        }
+        self.insert_impl(storage, key, item, Some(env.block.height))
+    }
+
+    fn insert_impl(
+        &self,
+        storage: &mut dyn Storage,
+        key: &K,
+        item: &T,
+        height: Option<u64>,
+    ) -> StdResult<()> {
+        self.register_interned(storage)?;
+        self.register_suffix(storage)?;
         let key_vec = self.serialize_key(key)?;
 
         match self.may_load_impl(storage, &key_vec)? {
             Some(existing_internal_item) => {
-                // if item already exists
-                let new_internal_item = InternalItem::new(existing_internal_item.index_pos, item)?;
+                // if item already exists, its heights are only updated with a new height
+                let meta = match height {
+                    Some(height) => Some(EntryMeta {
+                        created: existing_internal_item
+                            .meta
+                            .map_or(height, |meta| meta.created),
+                        updated: height,
+                    }),
+                    None => existing_internal_item.meta,
+                };
+                let new_internal_item =
+                    InternalItem::new(existing_internal_item.index_pos, item, meta)?;
                 self.save_impl(storage, &key_vec, &new_internal_item)
             }
             None => {
@@ -421,7 +981,11 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
                 self.set_len(storage, pos + 1)?;
                 let page = self.page_from_position(pos);
                 // save the item
-                let internal_item = InternalItem::new(Some(pos), item)?;
+                let meta = height.map(|height| EntryMeta {
+                    created: height,
+                    updated: height,
+                });
+                let internal_item = InternalItem::new(Some(pos), item, meta)?;
                 self.save_impl(storage, &key_vec, &internal_item)?;
                 // add index
                 let mut indexes = self.get_indexes(storage, page)?;
@@ -433,12 +997,50 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
 
     /// user facing method that checks if any item is stored with this key.
     pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+        if self.is_interned_registered(storage).is_err() {
+            return false;
+        }
         match self.serialize_key(key) {
             Ok(key_vec) => self.contains_impl(storage, &key_vec),
             Err(_) => false,
         }
     }
 
+    // Gas model of the indexes: each write to storage is charged a flat fee plus a fee per byte of
+    // the key and value, and each read a flat fee plus a fee per byte read. An insert writes the
+    // item, the length and the page of indexes it lands in, so its cost grows with the size of
+    // the page, and with a page size of 1 each index is stored in its own key instead. Opening
+    // a new page also creates a new key, which depending on the chain costs more than
+    // overwriting one, e.g. to grow the storage tree. Reserving moves that cost to a transaction
+    // chosen by the contract, such as its instantiation.
+
+    /// creates the empty pages of indexes needed to insert `additional` more items, so that the
+    /// inserts that land in them don't pay for creating their pages. Returns the number of pages
+    /// created.
+    ///
+    /// With a page size of 1, each index is stored in its own key, so there are no pages to create.
+    pub fn reserve(&self, storage: &mut dyn Storage, additional: u32) -> StdResult<u32> {
+        self.register_interned(storage)?;
+        if self.page_size == 1 || additional == 0 {
+            return Ok(0);
+        }
+        let len = self.get_len(storage)?;
+        let last_pos = len
+            .checked_add(additional)
+            .ok_or_else(|| StdError::generic_err("cannot reserve more than u32::MAX items"))?
+            - 1;
+
+        let mut created = 0;
+        for page in self.page_from_position(len)..=self.page_from_position(last_pos) {
+            let indexes_key = [self.as_slice(), INDEXES, page.to_be_bytes().as_slice()].concat();
+            if storage.get(&indexes_key).is_none() {
+                self.set_indexes_page(storage, page, &vec![])?;
+                created += 1;
+            }
+        }
+        Ok(created)
+    }
+
     /// paginates (key, item) pairs.
     pub fn paging(
         &self,
@@ -489,30 +1091,134 @@ impl<'a, K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser:
             .collect()
     }
 
+    /// paginates only the items. More efficient than paginating both items and keys
+    pub fn paging_values(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<T>> {
+        let start_pos = start_page * size;
+
+        let max_size = self.get_len(storage)?;
+
+        if max_size == 0 {
+            return Ok(vec![]);
+        }
+
+        if start_pos > max_size {
+            return Err(StdError::not_found("out of bounds"));
+        }
+
+        self.iter_values(storage)?
+            .skip(start_pos as usize)
+            .take(size as usize)
+            .collect()
+    }
+
+    /// paginates the (key, item) pairs that match `predicate`. The pages are pages of the
+    /// matching pairs, and the scan stops as soon as the requested page is full.
+    pub fn paging_filtered<P>(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+        mut predicate: P,
+    ) -> StdResult<Vec<(K, T)>>
+    where
+        P: FnMut(&K, &T) -> bool,
+    {
+        self.paging_filter_map(storage, start_page, size, |key, item| {
+            predicate(&key, &item).then_some((key, item))
+        })
+    }
+
+    /// paginates the results of `f` over the (key, item) pairs, skipping the pairs for which it
+    /// returns `None`. The pages are pages of the results, and the scan stops as soon as the
+    /// requested page is full.
+    pub fn paging_filter_map<R, F>(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+        mut f: F,
+    ) -> StdResult<Vec<R>>
+    where
+        F: FnMut(K, T) -> Option<R>,
+    {
+        filter_page(self.iter(storage)?, start_page, size, |(key, item)| {
+            f(key, item)
+        })
+    }
+
+    /// removes the (key, item) pairs that don't match `predicate`, scanning at most `limit` pairs,
+    /// so that a large keymap can be cleaned up over several transactions. `cursor` is `None` to
+    /// start a sweep, and otherwise the cursor returned by the previous call. Returns the cursor
+    /// to pass to the next call, or `None` once the sweep is complete.
+    ///
+    /// The pairs are scanned from the last position to the first, and the cursor is the number of
+    /// positions left to scan. As removing a pair moves the last pair in its place, and the last
+    /// pair was already scanned, pairs removed or inserted by other means between two calls
+    /// never make the sweep skip a pair that was there when it started. Pairs inserted during
+    /// the sweep are only scanned by the next one.
+    pub fn retain_paged<P>(
+        &self,
+        storage: &mut dyn Storage,
+        mut predicate: P,
+        limit: u32,
+        cursor: Option<u32>,
+    ) -> StdResult<Option<u32>>
+    where
+        P: FnMut(&K, &T) -> bool,
+    {
+        let len = self.get_len(storage)?;
+        // the pairs from this position on were scanned
+        let mut pos = cursor.map_or(len, |cursor| cursor.min(len));
+        for _ in 0..limit {
+            if pos == 0 {
+                break;
+            }
+            pos -= 1;
+            let Some(pair) = self.iter(storage)?.nth(pos as usize) else {
+                return Ok(None);
+            };
+            let (key, item) = pair?;
+            if !predicate(&key, &item) {
+                // the last pair, which was already scanned, moves to this position
+                self.remove(storage, &key)?;
+            }
+        }
+        Ok((pos > 0).then_some(pos))
+    }
+
     /// Returns a readonly iterator only for keys. More efficient than iter().
-    pub fn iter_keys(&self, storage: &'a dyn Storage) -> StdResult<KeyIter<K, T, Ser>> {
+    pub fn iter_keys(&self, storage: &'a dyn Storage) -> StdResult<KeyIter<'_, K, T, Ser>> {
         let len = self.get_len(storage)?;
         let iter = KeyIter::new(self, storage, 0, len);
         Ok(iter)
     }
 
     /// Returns a readonly iterator for (key-item) pairs
-    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<KeyItemIter<K, T, Ser>> {
+    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<KeyItemIter<'_, K, T, Ser>> {
         let len = self.get_len(storage)?;
         let iter = KeyItemIter::new(self, storage, 0, len);
         Ok(iter)
     }
+
+    /// Returns a readonly iterator only for items, which doesn't deserialize the keys. More
+    /// efficient than iter().
+    pub fn iter_values(&self, storage: &'a dyn Storage) -> StdResult<ValueIter<'_, K, T, Ser>> {
+        let len = self.get_len(storage)?;
+        let iter = ValueIter::new(self, storage, 0, len);
+        Ok(iter)
+    }
 }
 
 impl<K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser: Serde>
     PrefixedTypedStorage<InternalItem<T, Ser>, Bincode2> for Keymap<'_, K, T, Ser, WithIter>
 {
     fn as_slice(&self) -> &[u8] {
-        if let Some(prefix) = &self.prefix {
-            prefix
-        } else {
-            self.namespace
-        }
+        self.prefix_slice()
     }
 }
 
@@ -520,11 +1226,22 @@ impl<K: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned, Ser: Serd
     PrefixedTypedStorage<T, Ser> for Keymap<'_, K, T, Ser, WithoutIter>
 {
     fn as_slice(&self) -> &[u8] {
-        if let Some(prefix) = &self.prefix {
-            prefix
-        } else {
-            self.namespace
-        }
+        self.prefix_slice()
+    }
+}
+
+impl<K, T, Ser, I> Namespaced for Keymap<'_, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.interned
+            .as_deref()
+            .or(self.prefix.as_deref())
+            .unwrap_or(self.namespace)
     }
 }
 
@@ -840,6 +1557,119 @@ where
 {
 }
 
+// ===============================================================================================
+
+/// An iterator over the items of the Keymap. More efficient than iterating over (key, item)
+/// pairs, as the keys are not deserialized.
+pub struct ValueIter<'a, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    keymap: &'a Keymap<'a, K, T, Ser>,
+    storage: &'a dyn Storage,
+    start: u32,
+    end: u32,
+    cache: HashMap<u32, Vec<Vec<u8>>>,
+}
+
+impl<'a, K, T, Ser> ValueIter<'a, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    pub fn new(
+        keymap: &'a Keymap<'a, K, T, Ser>,
+        storage: &'a dyn Storage,
+        start: u32,
+        end: u32,
+    ) -> Self {
+        Self {
+            keymap,
+            storage,
+            start,
+            end,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Returns the item at a position, reading its serialized key from the indexes
+    fn item_at(&mut self, position: u32) -> StdResult<T> {
+        let page = self.keymap.page_from_position(position);
+        let indexes_pos = (position % self.keymap.page_size) as usize;
+
+        if !self.cache.contains_key(&page) {
+            let indexes = self.keymap.get_indexes(self.storage, page)?;
+            self.cache.insert(page, indexes);
+        }
+        let key_data = &self.cache[&page][indexes_pos];
+        self.keymap.get_from_key_data(self.storage, key_data)
+    }
+}
+
+impl<K, T, Ser> Iterator for ValueIter<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    type Item = StdResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start >= self.end {
+            return None;
+        }
+        let item = self.item_at(self.start);
+        self.start += 1;
+        Some(item)
+    }
+
+    // This needs to be implemented correctly for `ExactSizeIterator` to work.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
+
+    // `nth` skips over the elements without reading them, see `KeyIter::nth`.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.start = self.start.saturating_add(n as u32);
+        self.next()
+    }
+}
+
+impl<K, T, Ser> DoubleEndedIterator for ValueIter<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.item_at(self.end))
+    }
+
+    // `nth_back` skips over the elements without reading them, see `KeyIter::nth_back`.
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = self.end.saturating_sub(n as u32);
+        self.next_back()
+    }
+}
+
+// This enables writing `.iter_values().skip(n).rev()`
+impl<K, T, Ser> ExactSizeIterator for ValueIter<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+}
+
 trait PrefixedTypedStorage<T: Serialize + DeserializeOwned, Ser: Serde> {
     fn as_slice(&self) -> &[u8];
 
@@ -910,12 +1740,45 @@ trait PrefixedTypedStorage<T: Serialize + DeserializeOwned, Ser: Serde> {
     }
 }
 
+/// An iterator over the suffixes recorded in the suffix registry of a keymap
+pub struct SuffixIter<'a> {
+    registry: Keyset<'a, Vec<u8>>,
+    storage: &'a dyn Storage,
+    start: u32,
+    end: u32,
+}
+
+impl Iterator for SuffixIter<'_> {
+    type Item = StdResult<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start >= self.end {
+            return None;
+        }
+        let suffix = self
+            .registry
+            .paging(self.storage, self.start, 1)
+            .and_then(|page| {
+                page.into_iter()
+                    .next()
+                    .ok_or_else(|| StdError::generic_err("keymap: missing suffix in registry"))
+            });
+        self.start += 1;
+        Some(suffix)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use secret_toolkit_serialization::Json;
     use serde::{Deserialize, Serialize};
 
-    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
 
     use super::*;
 
@@ -1117,6 +1980,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_keymap_iter_values() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        let keymap: Keymap<String, u64> = KeymapBuilder::new(b"test").with_page_size(2).build();
+        for i in 0..5u64 {
+            keymap.insert(&mut storage, &format!("key{i}"), &(i * 10))?;
+        }
+        keymap.remove(&mut storage, &"key1".to_string())?;
+
+        let values: Vec<u64> = keymap.iter_values(&storage)?.collect::<StdResult<_>>()?;
+        let expected: Vec<u64> = keymap
+            .iter(&storage)?
+            .map(|entry| entry.map(|(_, value)| value))
+            .collect::<StdResult<_>>()?;
+        assert_eq!(values, expected);
+        assert_eq!(values.iter().sum::<u64>(), 90);
+
+        let mut iter = keymap.iter_values(&storage)?;
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next_back().unwrap()?, expected[3]);
+        assert_eq!(iter.nth(1).unwrap()?, expected[1]);
+
+        assert_eq!(keymap.paging_values(&storage, 1, 3)?, vec![expected[3]]);
+        assert!(keymap.paging_values(&storage, 3, 3).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_keymap_overwrite() -> StdResult<()> {
         let mut storage = MockStorage::new();
@@ -1185,6 +2077,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_keymap_interned_prefix() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        let keymap: Keymap<u32, u32> = KeymapBuilder::new(b"test").with_interned_prefix().build();
+        let long_suffix = [7u8; 64];
+        let suffixed = keymap.add_suffix(&long_suffix).add_suffix(b"second");
+        suffixed.insert(&mut storage, &1, &10)?;
+        suffixed.insert(&mut storage, &2, &20)?;
+
+        // the keys of the entries only hold a short hash of the prefix
+        let full_prefix = [
+            b"test".as_slice(),
+            &to_length_prefixed(&long_suffix),
+            &to_length_prefixed(b"second"),
+        ]
+        .concat();
+        assert_eq!(suffixed.namespace(), full_prefix.as_slice());
+        assert_eq!(suffixed.as_slice().len(), 4 + 2 + 8);
+
+        // another instance reads the same entries
+        let other = keymap.add_suffix(&long_suffix).add_suffix(b"second");
+        assert_eq!(other.get(&storage, &1), Some(10));
+        assert_eq!(other.get_len(&storage)?, 2);
+        assert!(keymap
+            .add_suffix(&long_suffix)
+            .add_suffix(b"third")
+            .is_empty(&storage)?);
+
+        // short prefixes are not interned
+        let short = keymap.add_suffix(b"a");
+        assert_eq!(short.as_slice(), short.namespace());
+
+        // a colliding prefix is detected instead of reading the entries of another prefix
+        let registry_key = interned_registry_key(b"test", suffixed.as_slice());
+        storage.set(&registry_key, b"another prefix");
+        let other = keymap.add_suffix(&long_suffix).add_suffix(b"second");
+        assert!(other.get_len(&storage).is_err());
+        assert_eq!(other.get(&storage, &1), None);
+        assert!(!other.contains(&storage, &1));
+        assert!(other.insert(&mut storage, &3, &30).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_keymap_length() -> StdResult<()> {
         test_keymap_length_with_page_size(1)?;
@@ -1242,6 +2179,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_keymap_length_across_instances() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<i32, i32> = Keymap::new(b"test");
+        let user_a = keymap.add_suffix(b"user");
+        let user_b = keymap.add_suffix(b"user");
+
+        assert_eq!(user_a.get_len(&storage)?, 0);
+        assert_eq!(user_b.get_len(&storage)?, 0);
+        user_a.insert(&mut storage, &1, &1)?;
+
+        // each instance caches the length separately, so `user_b` diverges from storage
+        assert_eq!(user_a.get_len(&storage)?, 1);
+        assert_eq!(user_b.get_len(&storage)?, 0);
+        assert_eq!(user_b.refresh_len(&storage)?, 1);
+        assert_eq!(user_b.get_len(&storage)?, 1);
+
+        let shared: Keymap<i32, i32> = KeymapBuilder::new(b"shared").with_shared_len().build();
+        let shared_a = shared.add_suffix(b"user");
+        let shared_b = shared.add_suffix(b"user");
+        let other = shared.add_suffix(b"other");
+
+        assert_eq!(shared_a.get_len(&storage)?, 0);
+        assert_eq!(shared_b.get_len(&storage)?, 0);
+        shared_a.insert(&mut storage, &1, &1)?;
+        assert_eq!(shared_b.get_len(&storage)?, 1);
+        shared_b.insert(&mut storage, &2, &2)?;
+        shared_b.remove(&mut storage, &1)?;
+        shared_b.insert(&mut storage, &3, &3)?;
+        assert_eq!(shared_a.get_len(&storage)?, 2);
+        assert_eq!(other.get_len(&storage)?, 0);
+
+        // the length isn't shared with the same keymap in another storage
+        let mut other_storage = MockStorage::new();
+        assert_eq!(shared_a.get_len(&other_storage)?, 0);
+        shared_b.insert(&mut other_storage, &4, &4)?;
+        assert_eq!(shared_a.get_len(&other_storage)?, 1);
+        assert_eq!(shared_a.get_len(&storage)?, 2);
+
+        Ok(())
+    }
+
     #[test]
     fn test_keymap_without_iter() -> StdResult<()> {
         test_keymap_without_iter_custom_page(1)?;
@@ -1287,6 +2266,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_keymap_without_iter_with_len() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        let uncounted: Keymap<u32, u32, Bincode2, WithoutIter> =
+            KeymapBuilder::new(b"test").without_iter().build();
+        assert!(uncounted.get_len(&storage).is_err());
+
+        let keymap: Keymap<u32, u32, Bincode2, WithoutIter> = KeymapBuilder::new(b"test")
+            .without_iter()
+            .with_len()
+            .build();
+        assert!(keymap.is_empty(&storage)?);
+        keymap.insert(&mut storage, &1, &10)?;
+        keymap.insert(&mut storage, &2, &20)?;
+        // overwriting an entry doesn't change the length
+        keymap.insert(&mut storage, &2, &21)?;
+        assert_eq!(keymap.get_len(&storage)?, 2);
+
+        keymap.remove(&mut storage, &1)?;
+        // removing a missing entry doesn't change the length
+        keymap.remove(&mut storage, &1)?;
+        assert_eq!(keymap.get_len(&storage)?, 1);
+        assert_eq!(keymap.get(&storage, &2), Some(21));
+
+        // the length is kept in storage
+        let other: Keymap<u32, u32, Bincode2, WithoutIter> = KeymapBuilder::new(b"test")
+            .without_iter()
+            .with_len()
+            .build();
+        assert_eq!(other.get_len(&storage)?, 1);
+        other.insert(&mut storage, &3, &30)?;
+        assert_eq!(keymap.refresh_len(&storage)?, 2);
+
+        Ok(())
+    }
+
     #[test]
     fn test_keymap_custom_paging() -> StdResult<()> {
         let mut storage = MockStorage::new();
@@ -1316,6 +2332,310 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_keymap_reserve() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, u32> = KeymapBuilder::new(b"test").with_page_size(5).build();
+
+        keymap.insert(&mut storage, &0, &0)?;
+        // the first page already exists, so 11 more items need two more pages
+        assert_eq!(keymap.reserve(&mut storage, 11)?, 2);
+        assert_eq!(keymap.reserve(&mut storage, 11)?, 0);
+        assert_eq!(keymap.reserve(&mut storage, 0)?, 0);
+        assert_eq!(keymap.get_len(&storage)?, 1);
+        assert!(keymap.reserve(&mut storage, u32::MAX).is_err());
+
+        for i in 1..15 {
+            keymap.insert(&mut storage, &i, &(i * 2))?;
+        }
+        for i in (0..15).step_by(3) {
+            keymap.remove(&mut storage, &i)?;
+        }
+        let items: Vec<_> = keymap.iter(&storage)?.collect::<StdResult<_>>()?;
+        assert_eq!(items.len(), 10);
+        assert!(items
+            .iter()
+            .all(|(key, value)| key % 3 != 0 && *value == key * 2));
+
+        let default: Keymap<u32, u32> = Keymap::new(b"default");
+        assert_eq!(default.reserve(&mut storage, 10)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keymap_retain_paged() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, u32> = KeymapBuilder::new(b"test").with_page_size(3).build();
+        for i in 0..20 {
+            keymap.insert(&mut storage, &i, &(i * 10))?;
+        }
+
+        // remove the odd keys, scanning 6 pairs per call
+        let mut cursor = None;
+        let mut calls = 0;
+        loop {
+            cursor = keymap.retain_paged(&mut storage, |key, _| key % 2 == 0, 6, cursor)?;
+            calls += 1;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(calls, 4);
+        assert_eq!(keymap.get_len(&storage)?, 10);
+        let mut kept: Vec<_> = keymap.iter(&storage)?.collect::<StdResult<_>>()?;
+        kept.sort();
+        assert_eq!(
+            kept,
+            (0..20).step_by(2).map(|i| (i, i * 10)).collect::<Vec<_>>()
+        );
+
+        // removing every pair
+        assert_eq!(
+            keymap.retain_paged(&mut storage, |_, _| false, 4, None)?,
+            Some(6)
+        );
+        assert_eq!(
+            keymap.retain_paged(&mut storage, |_, _| false, 10, Some(6))?,
+            None
+        );
+        assert!(keymap.is_empty(&storage)?);
+        assert_eq!(
+            keymap.retain_paged(&mut storage, |_, _| false, 10, Some(5))?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keymap_retain_paged_interleaved() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, u32> = KeymapBuilder::new(b"test").with_page_size(2).build();
+        for i in 0..30 {
+            keymap.insert(&mut storage, &i, &i)?;
+        }
+
+        // remove the odd keys, 3 pairs per call, while other transactions remove and insert
+        // pairs in between, which moves pairs across the cursor
+        let mut cursor = None;
+        let mut removed = vec![];
+        let mut next_key = 30;
+        loop {
+            cursor = keymap.retain_paged(&mut storage, |key, _| key % 2 == 0, 3, cursor)?;
+            if cursor.is_none() {
+                break;
+            }
+            for pos in [Some(0), cursor.map(|cursor| cursor / 2), None] {
+                let len = keymap.get_len(&storage)?;
+                let pos = pos.unwrap_or(len - 1).min(len - 1);
+                let key = keymap.iter_keys(&storage)?.nth(pos as usize).unwrap()?;
+                keymap.remove(&mut storage, &key)?;
+                removed.push(key);
+            }
+            keymap.insert(&mut storage, &next_key, &next_key)?;
+            next_key += 1;
+        }
+
+        // every pair that was there when the sweep started was scanned
+        let kept: Vec<u32> = keymap.iter_keys(&storage)?.collect::<StdResult<_>>()?;
+        for key in 0..30 {
+            assert_eq!(
+                kept.contains(&key),
+                key % 2 == 0 && !removed.contains(&key),
+                "key {key}"
+            );
+        }
+
+        Ok(())
+    }
+
+    fn env_at(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
+    }
+
+    #[test]
+    fn test_keymap_timestamps() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, String> = KeymapBuilder::new(b"test").with_timestamps().build();
+        keymap.insert_at(&mut storage, &env_at(100), &1, &"one".to_string())?;
+        keymap.insert_at(&mut storage, &env_at(110), &2, &"two".to_string())?;
+        keymap.insert_at(&mut storage, &env_at(120), &1, &"uno".to_string())?;
+
+        assert_eq!(
+            keymap.get_with_meta(&storage, &1),
+            Some((
+                "uno".to_string(),
+                Some(EntryMeta {
+                    created: 100,
+                    updated: 120
+                })
+            ))
+        );
+        assert_eq!(keymap.get(&storage, &2), Some("two".to_string()));
+        assert_eq!(keymap.get_with_meta(&storage, &3), None);
+
+        // plain inserts still work, and write a new entry without heights
+        keymap.insert(&mut storage, &3, &"three".to_string())?;
+        assert_eq!(
+            keymap.get_with_meta(&storage, &3),
+            Some(("three".to_string(), None))
+        );
+        assert_eq!(keymap.get_len(&storage)?, 3);
+
+        // but keep the heights of an existing entry, with or without timestamps in the keymap
+        keymap.insert(&mut storage, &2, &"dos".to_string())?;
+        let plain: Keymap<u32, String> = Keymap::new(b"test");
+        plain.insert(&mut storage, &1, &"eins".to_string())?;
+        assert_eq!(
+            keymap.get_with_meta(&storage, &2),
+            Some((
+                "dos".to_string(),
+                Some(EntryMeta {
+                    created: 110,
+                    updated: 110
+                })
+            ))
+        );
+        assert_eq!(
+            keymap.get_with_meta(&storage, &1),
+            Some((
+                "eins".to_string(),
+                Some(EntryMeta {
+                    created: 100,
+                    updated: 120
+                })
+            ))
+        );
+
+        // moving the last entry into the slot of a removed one keeps its heights
+        keymap.remove(&mut storage, &3)?;
+        keymap.remove(&mut storage, &1)?;
+        assert_eq!(
+            keymap.get_with_meta(&storage, &2),
+            Some((
+                "dos".to_string(),
+                Some(EntryMeta {
+                    created: 110,
+                    updated: 110
+                })
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keymap_timestamps_existing_entries() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, String> = Keymap::new(b"test");
+        keymap.insert(&mut storage, &1, &"one".to_string())?;
+        keymap.insert(&mut storage, &2, &"two".to_string())?;
+        assert!(keymap
+            .insert_at(&mut storage, &env_at(100), &3, &"three".to_string())
+            .is_err());
+
+        // entries written without timestamps have no heights until their next `insert_at`
+        let timestamped: Keymap<u32, String> =
+            KeymapBuilder::new(b"test").with_timestamps().build();
+        assert_eq!(
+            timestamped.get_with_meta(&storage, &1),
+            Some(("one".to_string(), None))
+        );
+        timestamped.insert_at(&mut storage, &env_at(100), &1, &"uno".to_string())?;
+        let meta = EntryMeta {
+            created: 100,
+            updated: 100,
+        };
+        assert_eq!(
+            timestamped.get_with_meta(&storage, &1),
+            Some(("uno".to_string(), Some(meta)))
+        );
+        assert_eq!(timestamped.get_len(&storage)?, 2);
+
+        // and the keymap without timestamps can still read them
+        assert_eq!(keymap.get(&storage, &1), Some("uno".to_string()));
+        assert_eq!(
+            keymap.iter(&storage)?.collect::<StdResult<Vec<_>>>()?,
+            vec![(1, "uno".to_string()), (2, "two".to_string())]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keymap_timestamps_corrupt_meta() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, String> = KeymapBuilder::new(b"test").with_timestamps().build();
+        keymap.insert_at(&mut storage, &env_at(100), &1, &"one".to_string())?;
+
+        // truncating the stored metadata is an error instead of an entry without heights
+        let full_key = [keymap.as_slice(), &keymap.serialize_key(&1)?].concat();
+        let stored = storage.get(&full_key).unwrap();
+        storage.set(&full_key, &stored[..stored.len() - 1]);
+        assert!(keymap.get_from_key(&storage, &1).is_err());
+        assert_eq!(keymap.get_with_meta(&storage, &1), None);
+
+        // and so is an unknown item tag
+        let mut tagged = stored.clone();
+        let tag_pos = Bincode2::serialize(&Bincode2::serialize(&"one".to_string())?)?.len();
+        assert_eq!(stored[tag_pos], ITEM_HAS_POS | ITEM_HAS_META);
+        tagged[tag_pos] = 4;
+        storage.set(&full_key, &tagged);
+        assert!(keymap.get_from_key(&storage, &1).is_err());
+
+        storage.set(&full_key, &stored);
+        assert_eq!(
+            keymap.get_with_meta(&storage, &1),
+            Some((
+                "one".to_string(),
+                Some(EntryMeta {
+                    created: 100,
+                    updated: 100
+                })
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keymap_paging_filtered() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keymap: Keymap<u32, u32> = KeymapBuilder::new(b"test").with_page_size(3).build();
+        for i in 0..20 {
+            keymap.insert(&mut storage, &i, &(i * 10))?;
+        }
+
+        let even = |key: &u32, _: &u32| key.is_multiple_of(2);
+        assert_eq!(
+            keymap.paging_filtered(&storage, 0, 3, even)?,
+            vec![(0, 0), (2, 20), (4, 40)]
+        );
+        assert_eq!(
+            keymap.paging_filtered(&storage, 3, 3, even)?,
+            vec![(18, 180)]
+        );
+        assert!(keymap.paging_filtered(&storage, 4, 3, even)?.is_empty());
+
+        let large = keymap.paging_filter_map(&storage, 1, 2, |key, item| {
+            (item >= 100).then(|| key.to_string())
+        })?;
+        assert_eq!(large, vec!["12".to_string(), "13".to_string()]);
+
+        // the scan stops once the page is full
+        let mut scanned = 0;
+        keymap.paging_filtered(&storage, 0, 2, |_, _| {
+            scanned += 1;
+            true
+        })?;
+        assert_eq!(scanned, 2);
+
+        Ok(())
+    }
+
     #[test]
     fn test_keymap_custom_paging_overflow() -> StdResult<()> {
         let mut storage = MockStorage::new();
@@ -1509,4 +2829,52 @@ mod tests {
         assert_eq!(keymap.get_len(&storage)?, 1);
         Ok(())
     }
+
+    #[test]
+    fn test_suffix_registry() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+
+        let balances: Keymap<String, u64> = KeymapBuilder::new(b"balances")
+            .with_suffix_registry(b"balances_users")
+            .build();
+        assert_eq!(balances.iter_suffixes(&storage)?.count(), 0);
+
+        balances
+            .add_suffix(b"alice")
+            .insert(&mut storage, &"sscrt".to_string(), &1)?;
+        balances
+            .add_suffix(b"bob")
+            .insert(&mut storage, &"sscrt".to_string(), &2)?;
+        // suffixes are only recorded once, and nested suffixes are recorded as their first one
+        balances
+            .add_suffix(b"alice")
+            .insert(&mut storage, &"susdc".to_string(), &3)?;
+        balances.add_suffix(b"bob").add_suffix(b"nested").insert(
+            &mut storage,
+            &"sscrt".to_string(),
+            &4,
+        )?;
+        // suffixes that no entry was inserted under are not recorded
+        let carol = balances.add_suffix(b"carol");
+        assert!(carol.get(&storage, &"sscrt".to_string()).is_none());
+
+        let suffixes = balances
+            .iter_suffixes(&storage)?
+            .collect::<StdResult<Vec<_>>>()?;
+        assert_eq!(suffixes, vec![b"alice".to_vec(), b"bob".to_vec()]);
+        let sscrt_total: u64 = suffixes
+            .iter()
+            .filter_map(|suffix| {
+                balances
+                    .add_suffix(suffix)
+                    .get(&storage, &"sscrt".to_string())
+            })
+            .sum();
+        assert_eq!(sscrt_total, 3);
+
+        let unregistered: Keymap<String, u64> = Keymap::new(b"balances");
+        assert!(unregistered.iter_suffixes(&storage).is_err());
+
+        Ok(())
+    }
 }
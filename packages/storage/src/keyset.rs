@@ -9,6 +9,8 @@ use cosmwasm_storage::to_length_prefixed;
 
 use secret_toolkit_serialization::{Bincode2, Serde};
 
+use crate::keymap::filter_page;
+use crate::namespace::Namespaced;
 use crate::{IterOption, WithIter, WithoutIter};
 
 const INDEXES: &[u8] = b"indexes";
@@ -413,6 +415,40 @@ impl<'a, K: Serialize + DeserializeOwned, Ser: Serde> Keyset<'a, K, Ser, WithIte
         }
     }
 
+    // Gas model of the indexes: each write to storage is charged a flat fee plus a fee per byte of
+    // the key and value, and each read a flat fee plus a fee per byte read. An insert writes the
+    // item, the length and the page of indexes it lands in, so its cost grows with the size of
+    // the page, and with a page size of 1 each index is stored in its own key instead. Opening
+    // a new page also creates a new key, which depending on the chain costs more than
+    // overwriting one, e.g. to grow the storage tree. Reserving moves that cost to a transaction
+    // chosen by the contract, such as its instantiation.
+
+    /// creates the empty pages of indexes needed to insert `additional` more values, so that the
+    /// inserts that land in them don't pay for creating their pages. Returns the number of pages
+    /// created.
+    ///
+    /// With a page size of 1, each index is stored in its own key, so there are no pages to create.
+    pub fn reserve(&self, storage: &mut dyn Storage, additional: u32) -> StdResult<u32> {
+        if self.page_size == 1 || additional == 0 {
+            return Ok(0);
+        }
+        let len = self.get_len(storage)?;
+        let last_pos = len
+            .checked_add(additional)
+            .ok_or_else(|| StdError::generic_err("cannot reserve more than u32::MAX items"))?
+            - 1;
+
+        let mut created = 0;
+        for page in self.page_from_position(len)..=self.page_from_position(last_pos) {
+            let indexes_key = [self.as_slice(), INDEXES, page.to_be_bytes().as_slice()].concat();
+            if storage.get(&indexes_key).is_none() {
+                self.set_indexes_page(storage, page, &vec![])?;
+                created += 1;
+            }
+        }
+        Ok(created)
+    }
+
     /// paginates only the values.
     pub fn paging(&self, storage: &dyn Storage, start_page: u32, size: u32) -> StdResult<Vec<K>> {
         let start_pos = start_page * size;
@@ -433,14 +469,93 @@ impl<'a, K: Serialize + DeserializeOwned, Ser: Serde> Keyset<'a, K, Ser, WithIte
             .collect()
     }
 
+    /// paginates the values that match `predicate`. The pages are pages of the matching values,
+    /// and the scan stops as soon as the requested page is full.
+    pub fn paging_filtered<P>(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+        mut predicate: P,
+    ) -> StdResult<Vec<K>>
+    where
+        P: FnMut(&K) -> bool,
+    {
+        self.paging_filter_map(storage, start_page, size, |value| {
+            predicate(&value).then_some(value)
+        })
+    }
+
+    /// paginates the results of `f` over the values, skipping the values for which it returns
+    /// `None`. The pages are pages of the results, and the scan stops as soon as the requested
+    /// page is full.
+    pub fn paging_filter_map<R, F>(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+        f: F,
+    ) -> StdResult<Vec<R>>
+    where
+        F: FnMut(K) -> Option<R>,
+    {
+        filter_page(self.iter(storage)?, start_page, size, f)
+    }
+
+    /// removes the values that don't match `predicate`, scanning at most `limit` values, so that
+    /// a large keyset can be cleaned up over several transactions. `cursor` is `None` to start a
+    /// sweep, and otherwise the cursor returned by the previous call. Returns the cursor to pass
+    /// to the next call, or `None` once the sweep is complete. See `Keymap::retain_paged` for
+    /// how other writes between two calls affect a sweep.
+    pub fn retain_paged<P>(
+        &self,
+        storage: &mut dyn Storage,
+        mut predicate: P,
+        limit: u32,
+        cursor: Option<u32>,
+    ) -> StdResult<Option<u32>>
+    where
+        P: FnMut(&K) -> bool,
+    {
+        let len = self.get_len(storage)?;
+        // the values from this position on were scanned
+        let mut pos = cursor.map_or(len, |cursor| cursor.min(len));
+        for _ in 0..limit {
+            if pos == 0 {
+                break;
+            }
+            pos -= 1;
+            let Some(value) = self.iter(storage)?.nth(pos as usize) else {
+                return Ok(None);
+            };
+            let value = value?;
+            if !predicate(&value) {
+                // the last value, which was already scanned, moves to this position
+                self.remove(storage, &value)?;
+            }
+        }
+        Ok((pos > 0).then_some(pos))
+    }
+
     /// Returns a readonly iterator only for values.
-    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<ValueIter<K, Ser>> {
+    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<ValueIter<'_, K, Ser>> {
         let len = self.get_len(storage)?;
         let iter = ValueIter::new(self, storage, 0, len);
         Ok(iter)
     }
 }
 
+impl<K, Ser, I> Namespaced for Keyset<'_, K, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.prefix.as_deref().unwrap_or(self.namespace)
+    }
+}
+
 /// An iterator over the keys of the Keyset.
 pub struct ValueIter<'a, K, Ser>
 where
@@ -919,6 +1034,69 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_keyset_reserve() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keyset: Keyset<u32> = Keyset::new(b"test");
+
+        assert_eq!(keyset.reserve(&mut storage, 12)?, 3);
+        assert_eq!(keyset.reserve(&mut storage, 15)?, 0);
+        assert!(keyset.is_empty(&storage)?);
+        assert_eq!(keyset.iter(&storage)?.count(), 0);
+
+        for i in 0..12 {
+            assert!(keyset.insert(&mut storage, &i)?);
+        }
+        keyset.remove(&mut storage, &4)?;
+        let mut values: Vec<_> = keyset.iter(&storage)?.collect::<StdResult<_>>()?;
+        values.sort();
+        assert_eq!(values, [0, 1, 2, 3, 5, 6, 7, 8, 9, 10, 11]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyset_retain_paged() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keyset: Keyset<u32> = KeysetBuilder::new(b"test").with_page_size(4).build();
+        for i in 0..10 {
+            keyset.insert(&mut storage, &i)?;
+        }
+
+        // the values are scanned from the last one, so 9, 8 and 7 are kept
+        let cursor = keyset.retain_paged(&mut storage, |value| *value >= 5, 3, None)?;
+        assert_eq!(cursor, Some(7));
+        assert_eq!(keyset.get_len(&storage)?, 10);
+        let cursor = keyset.retain_paged(&mut storage, |value| *value >= 5, 100, cursor)?;
+        assert_eq!(cursor, None);
+        let mut kept: Vec<_> = keyset.iter(&storage)?.collect::<StdResult<_>>()?;
+        kept.sort();
+        assert_eq!(kept, vec![5, 6, 7, 8, 9]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyset_paging_filtered() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let keyset: Keyset<u32> = Keyset::new(b"test");
+        for i in 0..20 {
+            keyset.insert(&mut storage, &i)?;
+        }
+
+        assert_eq!(
+            keyset.paging_filtered(&storage, 1, 4, |value| value.is_multiple_of(3))?,
+            vec![12, 15, 18]
+        );
+        assert_eq!(
+            keyset.paging_filter_map(&storage, 0, 2, |value| value.checked_sub(18))?,
+            vec![0, 1]
+        );
+        assert!(keyset.paging_filtered(&storage, 0, 0, |_| true)?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_keyset_custom_paging_overflow() -> StdResult<()> {
         let mut storage = MockStorage::new();
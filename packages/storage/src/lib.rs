@@ -1,19 +1,52 @@
 #![doc = include_str!("../Readme.md")]
 
+pub mod addr_keyset;
 pub mod append_store;
 pub mod deque_store;
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+pub mod invariant;
 pub mod item;
 pub mod keymap;
 pub mod keyset;
+pub mod namespace;
+pub mod ring_buffer;
 pub mod secure_item;
+pub mod set_once;
+pub mod snapshot;
+pub mod tenant;
+pub mod tombstone;
+pub mod versioned;
 
-pub use append_store::AppendStore;
+pub use addr_keyset::AddrKeyset;
+pub use append_store::{AppendStore, AppendStoreBuilder, CorruptedPage, VerifyError};
 pub use deque_store::DequeStore;
+#[cfg(feature = "encryption")]
+pub use encrypted::{EncryptedItem, EncryptedKeymap, EncryptionSeed};
+pub use invariant::InvariantGuard;
 pub use item::Item;
 pub use iter_options::WithoutIter;
 use iter_options::{IterOption, WithIter};
-pub use keymap::{Keymap, KeymapBuilder};
+pub use keymap::{EntryMeta, Keymap, KeymapBuilder, SuffixIter};
 pub use keyset::{Keyset, KeysetBuilder};
+pub use namespace::{check_namespaces, Namespaced};
+pub use page_options::PageSize;
+pub use ring_buffer::RingBuffer;
+use page_options::{PageSizeOption, RuntimePageSize};
+pub use set_once::SetOnce;
+pub use snapshot::{SnapshotItem, SnapshotMap};
+pub use tenant::Tenant;
+pub use tombstone::TombstoneKeymap;
+pub use versioned::{VersionedItem, VersionedKeymap};
+
+#[cfg(feature = "derive")]
+pub use secret_toolkit_derive::ContractState;
+
+/// The items used by the code generated by `#[derive(ContractState)]`
+#[doc(hidden)]
+pub mod __private {
+    pub use cosmwasm_std::{StdResult, Storage};
+}
 
 pub mod iter_options {
     pub struct WithIter;
@@ -23,3 +56,43 @@ pub mod iter_options {
     impl IterOption for WithIter {}
     impl IterOption for WithoutIter {}
 }
+
+pub mod page_options {
+    /// The page size of a store, either set at runtime or as part of the store's type.
+    pub trait PageSizeOption: Copy {
+        /// the page size used by the store's `new` constructor
+        const DEFAULT: Self;
+
+        fn get(self) -> u32;
+    }
+
+    /// A page size set at runtime, using `new_with_page_size`
+    #[derive(Clone, Copy, Debug)]
+    pub struct RuntimePageSize(pub(crate) u32);
+
+    impl PageSizeOption for RuntimePageSize {
+        const DEFAULT: Self = RuntimePageSize(1);
+
+        fn get(self) -> u32 {
+            self.0
+        }
+    }
+
+    /// A page size that is part of the store's type, so it doesn't take space in the store,
+    /// and a zero page size fails to compile.
+    #[derive(Clone, Copy, Debug)]
+    pub struct PageSize<const N: u32>;
+
+    impl<const N: u32> PageSizeOption for PageSize<N> {
+        const DEFAULT: Self = {
+            if N == 0 {
+                panic!("zero index page size used in PageSize")
+            }
+            PageSize
+        };
+
+        fn get(self) -> u32 {
+            N
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Detection of colliding storage namespaces.
+//!
+//! Every toolkit collection stores its entries under keys that start with its namespace. If one
+//! namespace is a prefix of another (e.g. `b"bal"` and `b"balance"`), entries of one collection
+//! can silently overwrite entries of the other.
+
+use cosmwasm_std::{StdError, StdResult};
+
+/// Anything that owns a storage namespace.
+pub trait Namespaced {
+    /// Returns the prefix under which all the entries of this collection are stored
+    fn namespace(&self) -> &[u8];
+}
+
+impl Namespaced for [u8] {
+    fn namespace(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const N: usize> Namespaced for [u8; N] {
+    fn namespace(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<T: Namespaced + ?Sized> Namespaced for &T {
+    fn namespace(&self) -> &[u8] {
+        (**self).namespace()
+    }
+}
+
+/// Checks that no namespace in the list is a prefix of (or equal to) another one.
+///
+/// # Arguments
+///
+/// * `namespaces` - pairs of a human readable name and the namespace it uses
+pub fn check_namespaces(namespaces: &[(&str, &[u8])]) -> StdResult<()> {
+    let mut collisions = vec![];
+    for (i, (name_a, ns_a)) in namespaces.iter().enumerate() {
+        for (name_b, ns_b) in &namespaces[i + 1..] {
+            if ns_a.starts_with(ns_b) || ns_b.starts_with(ns_a) {
+                collisions.push(format!(
+                    "{name_a} ({}) and {name_b} ({})",
+                    String::from_utf8_lossy(ns_a),
+                    String::from_utf8_lossy(ns_b)
+                ));
+            }
+        }
+    }
+
+    if collisions.is_empty() {
+        Ok(())
+    } else {
+        Err(StdError::generic_err(format!(
+            "colliding storage namespaces: {}",
+            collisions.join(", ")
+        )))
+    }
+}
+
+/// Generates a test that fails if any of the listed collections or namespaces collide.
+///
+/// Accepts any expression implementing [`Namespaced`], i.e. toolkit collections as well as raw
+/// byte strings.
+///
+/// ```
+/// # use secret_toolkit_storage::{namespace_registry, Item, Keymap};
+/// pub static OWNER: Item<String> = Item::new(b"owner");
+/// pub static BALANCES: Keymap<String, u128> = Keymap::new(b"balances");
+///
+/// namespace_registry!(OWNER, BALANCES, b"config");
+/// ```
+#[macro_export]
+macro_rules! namespace_registry {
+    ($($ns:expr),+ $(,)?) => {
+        #[test]
+        fn storage_namespaces_do_not_collide() {
+            $crate::namespace::check_namespaces(&[
+                $((stringify!($ns), $crate::namespace::Namespaced::namespace(&$ns))),+
+            ])
+            .unwrap();
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secret_toolkit_serialization::Bincode2;
+
+    use crate::{AppendStore, DequeStore, Item, Keymap, KeymapBuilder, Keyset, WithoutIter};
+
+    #[test]
+    fn test_check_namespaces() {
+        assert!(check_namespaces(&[("a", b"owner"), ("b", b"balances"), ("c", b"config")]).is_ok());
+
+        let err =
+            check_namespaces(&[("a", b"bal"), ("b", b"config"), ("c", b"balance")]).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("colliding storage namespaces: a (bal) and c (balance)")
+        );
+
+        assert!(check_namespaces(&[("a", b"owner"), ("b", b"owner")]).is_err());
+    }
+
+    #[test]
+    fn test_collection_namespaces() {
+        let item: Item<u8> = Item::new(b"item");
+        let keymap: Keymap<u8, u8> = Keymap::new(b"keymap");
+        let keymap_no_iter: Keymap<u8, u8, Bincode2, WithoutIter> =
+            KeymapBuilder::new(b"keymap").without_iter().build();
+        let keyset: Keyset<u8> = Keyset::new(b"keyset");
+        let append: AppendStore<u8> = AppendStore::new(b"append");
+        let deque: DequeStore<u8> = DequeStore::new(b"deque");
+
+        assert_eq!(item.namespace(), b"item");
+        assert_eq!(keymap.namespace(), b"keymap");
+        assert_eq!(keymap_no_iter.namespace(), b"keymap");
+        assert_eq!(keyset.namespace(), b"keyset");
+        assert_eq!(append.namespace(), b"append");
+        assert_eq!(deque.namespace(), b"deque");
+
+        let suffixed = keymap.add_suffix(b"user");
+        assert!(suffixed.namespace().starts_with(b"keymap"));
+        assert!(check_namespaces(&[
+            ("keymap", keymap.namespace()),
+            ("suffixed", suffixed.namespace())
+        ])
+        .is_err());
+    }
+
+    namespace_registry!(b"owner", b"config");
+}
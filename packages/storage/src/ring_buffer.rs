@@ -0,0 +1,373 @@
+//! A storage wrapper called RingBuffer, which keeps the last `N` items pushed to it, e.g. for
+//! windows of recent prices or TWAP samples, or the last events displayed to users.
+//!
+//! Each item is stored in one of `N` slots, and two special keys store the positions of the
+//! oldest item (the head) and of the next item to push (the tail). Pushing to a full buffer
+//! overwrites its oldest item, so every write has a constant cost and the buffer never grows.
+use std::any::type_name;
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::to_length_prefixed;
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+
+const HEAD_KEY: &[u8] = b"head";
+const TAIL_KEY: &[u8] = b"tail";
+const SLOTS: &[u8] = b"slots";
+
+pub struct RingBuffer<'a, T, const N: u32, Ser = Bincode2>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// prefix of the newly constructed Storage
+    namespace: &'a [u8],
+    /// needed if any suffixes were added to the original namespace.
+    prefix: Option<Vec<u8>>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, T, const N: u32, Ser> RingBuffer<'a, T, N, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    pub const fn new(prefix: &'a [u8]) -> Self {
+        if N == 0 {
+            panic!("zero capacity used in ring_buffer")
+        }
+        Self {
+            namespace: prefix,
+            prefix: None,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new RingBuffer. This can be used when you want to associate a RingBuffer to each user
+    /// and you still get to define the RingBuffer as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let suffix = to_length_prefixed(suffix);
+        let prefix = self.prefix.as_deref().unwrap_or(self.namespace);
+        let prefix = [prefix, suffix.as_slice()].concat();
+        Self {
+            namespace: self.namespace,
+            prefix: Some(prefix),
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.namespace
+        }
+    }
+
+    /// gets the head or the tail, which are 0 until the first push
+    fn get_u64(&self, storage: &dyn Storage, key: &[u8]) -> StdResult<u64> {
+        let num_key = [self.as_slice(), key].concat();
+        if let Some(num_vec) = storage.get(&num_key) {
+            let num_bytes = num_vec
+                .as_slice()
+                .try_into()
+                .map_err(|err| StdError::parse_err("u64", err))?;
+            Ok(u64::from_be_bytes(num_bytes))
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// sets the head or the tail
+    fn set_u64(&self, storage: &mut dyn Storage, key: &[u8], num: u64) {
+        let num_key = [self.as_slice(), key].concat();
+        storage.set(&num_key, &num.to_be_bytes());
+    }
+
+    /// returns the head and the tail
+    fn bounds(&self, storage: &dyn Storage) -> StdResult<(u64, u64)> {
+        Ok((
+            self.get_u64(storage, HEAD_KEY)?,
+            self.get_u64(storage, TAIL_KEY)?,
+        ))
+    }
+
+    fn slot_key(&self, position: u64) -> Vec<u8> {
+        let slot = (position % N as u64) as u32;
+        [self.as_slice(), SLOTS, slot.to_be_bytes().as_slice()].concat()
+    }
+
+    fn get_slot(&self, storage: &dyn Storage, position: u64) -> StdResult<T> {
+        let item_data = storage
+            .get(&self.slot_key(position))
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))?;
+        Ser::deserialize(&item_data)
+    }
+
+    /// Returns the number of items the buffer keeps
+    pub const fn capacity(&self) -> u32 {
+        N
+    }
+
+    /// gets the number of items in the buffer, which is at most `N`
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        let (head, tail) = self.bounds(storage)?;
+        Ok((tail - head) as u32)
+    }
+
+    /// checks if the buffer has any items
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.get_len(storage)? == 0)
+    }
+
+    /// checks if the next push will overwrite the oldest item
+    pub fn is_full(&self, storage: &dyn Storage) -> StdResult<bool> {
+        Ok(self.get_len(storage)? == N)
+    }
+
+    /// Pushes an item after the newest one. If the buffer is full, the oldest item is
+    /// overwritten, and returned, e.g. to subtract it from a running sum.
+    pub fn push(&self, storage: &mut dyn Storage, item: &T) -> StdResult<Option<T>> {
+        let (head, tail) = self.bounds(storage)?;
+        let overwritten = if tail - head == N as u64 {
+            let oldest = self.get_slot(storage, head)?;
+            self.set_u64(storage, HEAD_KEY, head + 1);
+            Some(oldest)
+        } else {
+            None
+        };
+        storage.set(&self.slot_key(tail), &Ser::serialize(item)?);
+        self.set_u64(storage, TAIL_KEY, tail + 1);
+        Ok(overwritten)
+    }
+
+    /// Pops the oldest item
+    pub fn pop_front(&self, storage: &mut dyn Storage) -> StdResult<T> {
+        let (head, tail) = self.bounds(storage)?;
+        if head == tail {
+            return Err(StdError::generic_err("cannot pop from empty ring_buffer"));
+        }
+        let item = self.get_slot(storage, head)?;
+        storage.remove(&self.slot_key(head));
+        self.set_u64(storage, HEAD_KEY, head + 1);
+        Ok(item)
+    }
+
+    /// gets the item at pos if within bounds, where 0 is the oldest item
+    pub fn get_at(&self, storage: &dyn Storage, pos: u32) -> StdResult<T> {
+        let (head, tail) = self.bounds(storage)?;
+        if pos as u64 >= tail - head {
+            return Err(StdError::generic_err("ring_buffer access out of bounds"));
+        }
+        self.get_slot(storage, head + pos as u64)
+    }
+
+    /// Returns the oldest item, if any
+    pub fn oldest(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        let (head, tail) = self.bounds(storage)?;
+        if head == tail {
+            return Ok(None);
+        }
+        self.get_slot(storage, head).map(Some)
+    }
+
+    /// Returns the newest item, if any
+    pub fn newest(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        let (head, tail) = self.bounds(storage)?;
+        if head == tail {
+            return Ok(None);
+        }
+        self.get_slot(storage, tail - 1).map(Some)
+    }
+
+    /// Empties the buffer. The slots are left in storage, and overwritten by the next pushes.
+    pub fn clear(&self, storage: &mut dyn Storage) -> StdResult<()> {
+        let tail = self.get_u64(storage, TAIL_KEY)?;
+        self.set_u64(storage, HEAD_KEY, tail);
+        Ok(())
+    }
+
+    /// Returns a readonly iterator over the items, from the oldest to the newest. Use `.rev()`
+    /// to display the newest items first.
+    pub fn iter(&self, storage: &'a dyn Storage) -> StdResult<RingBufferIter<'_, T, N, Ser>> {
+        let (head, tail) = self.bounds(storage)?;
+        Ok(RingBufferIter {
+            ring_buffer: self,
+            storage,
+            start: head,
+            end: tail,
+        })
+    }
+}
+
+impl<T, const N: u32, Ser> Namespaced for RingBuffer<'_, T, N, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// An iterator over the contents of the ring buffer.
+pub struct RingBufferIter<'a, T, const N: u32, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    ring_buffer: &'a RingBuffer<'a, T, N, Ser>,
+    storage: &'a dyn Storage,
+    start: u64,
+    end: u64,
+}
+
+impl<T, const N: u32, Ser> Iterator for RingBufferIter<'_, T, N, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    type Item = StdResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start >= self.end {
+            return None;
+        }
+        let item = self.ring_buffer.get_slot(self.storage, self.start);
+        self.start += 1;
+        Some(item)
+    }
+
+    // This needs to be implemented correctly for `ExactSizeIterator` to work.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.start = self.start.saturating_add(n as u64).min(self.end);
+        self.next()
+    }
+}
+
+impl<T, const N: u32, Ser> DoubleEndedIterator for RingBufferIter<'_, T, N, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start >= self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(self.ring_buffer.get_slot(self.storage, self.end))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = self.end.saturating_sub(n as u64).max(self.start);
+        self.next_back()
+    }
+}
+
+impl<T, const N: u32, Ser> ExactSizeIterator for RingBufferIter<'_, T, N, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use secret_toolkit_serialization::Json;
+
+    use super::*;
+
+    #[test]
+    fn test_push_overwrites_oldest() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let prices: RingBuffer<u64, 3> = RingBuffer::new(b"prices");
+        assert_eq!(prices.capacity(), 3);
+        assert!(prices.is_empty(&storage)?);
+        assert_eq!(prices.newest(&storage)?, None);
+
+        assert_eq!(prices.push(&mut storage, &10)?, None);
+        assert_eq!(prices.push(&mut storage, &20)?, None);
+        assert_eq!(prices.push(&mut storage, &30)?, None);
+        assert!(prices.is_full(&storage)?);
+        assert_eq!(prices.push(&mut storage, &40)?, Some(10));
+        assert_eq!(prices.push(&mut storage, &50)?, Some(20));
+
+        assert_eq!(prices.get_len(&storage)?, 3);
+        assert_eq!(prices.oldest(&storage)?, Some(30));
+        assert_eq!(prices.newest(&storage)?, Some(50));
+        assert_eq!(prices.get_at(&storage, 1)?, 40);
+        assert!(prices.get_at(&storage, 3).is_err());
+
+        // the buffer only uses its N slots
+        assert_eq!(prices.slot_key(4), prices.slot_key(1));
+        assert_eq!(storage.get(&prices.slot_key(4)), Some(Bincode2::serialize(&50u64)?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pop_front_and_clear() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let events: RingBuffer<String, 2, Json> = RingBuffer::new(b"events");
+        assert!(events.pop_front(&mut storage).is_err());
+
+        events.push(&mut storage, &"a".to_string())?;
+        events.push(&mut storage, &"b".to_string())?;
+        assert_eq!(events.pop_front(&mut storage)?, "a");
+        events.push(&mut storage, &"c".to_string())?;
+        assert_eq!(events.push(&mut storage, &"d".to_string())?, Some("b".to_string()));
+
+        events.clear(&mut storage)?;
+        assert!(events.is_empty(&storage)?);
+        assert_eq!(events.oldest(&storage)?, None);
+        events.push(&mut storage, &"e".to_string())?;
+        assert_eq!(events.get_len(&storage)?, 1);
+        assert_eq!(events.oldest(&storage)?, Some("e".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_iterator() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let samples: RingBuffer<u32, 4> = RingBuffer::new(b"samples");
+        for i in 0..10 {
+            samples.push(&mut storage, &i)?;
+        }
+
+        let items: StdResult<Vec<_>> = samples.iter(&storage)?.collect();
+        assert_eq!(items?, vec![6, 7, 8, 9]);
+        let items: StdResult<Vec<_>> = samples.iter(&storage)?.rev().take(2).collect();
+        assert_eq!(items?, vec![9, 8]);
+        let mut iter = samples.iter(&storage)?;
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.nth(2).transpose()?, Some(8));
+        assert_eq!(iter.nth_back(5).transpose()?, None);
+
+        // buffers with suffixes don't share their items
+        let user_samples = samples.add_suffix(b"alice");
+        assert!(user_samples.is_empty(&storage)?);
+        user_samples.push(&mut storage, &42)?;
+        assert_eq!(user_samples.newest(&storage)?, Some(42));
+        assert_eq!(samples.newest(&storage)?, Some(9));
+
+        Ok(())
+    }
+}
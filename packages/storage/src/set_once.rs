@@ -0,0 +1,143 @@
+use std::any::type_name;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+use crate::Item;
+
+/// A storage item that can only be written once, e.g. for configuration that must never change
+/// after instantiation. It can not be updated nor removed.
+pub struct SetOnce<'a, T, Ser = Bincode2>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    item: Item<'a, T, Ser>,
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> SetOnce<'a, T, Ser> {
+    /// constructor
+    pub const fn new(key: &'a [u8]) -> Self {
+        Self {
+            item: Item::new(key),
+        }
+    }
+
+    /// This is used to produce a new SetOnce. This can be used when you want to associate a SetOnce to each user
+    /// and you still get to define the SetOnce as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        Self {
+            item: self.item.add_suffix(suffix),
+        }
+    }
+}
+
+impl<T, Ser> SetOnce<'_, T, Ser>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// set will serialize the model and store it, returns an error if a value was already set
+    pub fn set(&self, storage: &mut dyn Storage, data: &T) -> StdResult<()> {
+        if self.is_set(storage) {
+            return Err(StdError::generic_err(format!(
+                "{} has already been set",
+                type_name::<T>()
+            )));
+        }
+        self.item.save(storage, data)
+    }
+
+    /// load will return an error if no data is set, or on parse error
+    pub fn load(&self, storage: &dyn Storage) -> StdResult<T> {
+        self.item.load(storage)
+    }
+
+    /// may_load will parse the data stored if present, returns `Ok(None)` if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        self.item.may_load(storage)
+    }
+
+    /// efficient way to see if the value was already set
+    pub fn is_set(&self, storage: &dyn Storage) -> bool {
+        !self.item.is_empty(storage)
+    }
+
+    /// Loads the value if it was set, and otherwise sets it to the result of `init`
+    pub fn get_or_init<F>(&self, storage: &mut dyn Storage, init: F) -> StdResult<T>
+    where
+        F: FnOnce() -> StdResult<T>,
+    {
+        if let Some(data) = self.may_load(storage)? {
+            return Ok(data);
+        }
+        let data = init()?;
+        self.item.save(storage, &data)?;
+        Ok(data)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, Ser: Serde> Namespaced for SetOnce<'_, T, Ser> {
+    fn namespace(&self) -> &[u8] {
+        self.item.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+
+    #[test]
+    fn test_set_once() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let admin: SetOnce<String> = SetOnce::new(b"admin");
+
+        assert!(!admin.is_set(&storage));
+        assert_eq!(admin.may_load(&storage)?, None);
+        assert!(admin.load(&storage).is_err());
+
+        admin.set(&mut storage, &"alice".to_string())?;
+        assert!(admin.is_set(&storage));
+        assert_eq!(admin.load(&storage)?, "alice");
+
+        assert_eq!(
+            admin.set(&mut storage, &"bob".to_string()),
+            Err(StdError::generic_err(
+                "alloc::string::String has already been set"
+            ))
+        );
+        assert_eq!(admin.load(&storage)?, "alice");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_or_init() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let config: SetOnce<u64> = SetOnce::new(b"config");
+
+        assert!(config
+            .get_or_init(&mut storage, || Err(StdError::generic_err("failed")))
+            .is_err());
+        assert!(!config.is_set(&storage));
+
+        assert_eq!(config.get_or_init(&mut storage, || Ok(1))?, 1);
+        assert_eq!(config.get_or_init(&mut storage, || Ok(2))?, 1);
+        assert!(config.set(&mut storage, &3).is_err());
+
+        let user_config = config.add_suffix(b"user");
+        assert!(!user_config.is_set(&storage));
+        user_config.set(&mut storage, &4)?;
+        assert_eq!(config.load(&storage)?, 1);
+        assert_eq!(user_config.load(&storage)?, 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+//! Storage that records a checkpoint of its values at each write, so that they can be loaded as
+//! of a past block height, e.g. the voting power of an account when a proposal was created.
+//!
+//! Writes are given the current block height, which must not decrease between writes of the
+//! same value. The writes made at a height are only visible from the next height on, so loading
+//! a value at the height of a proposal ignores changes made in the same block as the proposal.
+//! Loading a value at a height costs a binary search over its checkpoints, i.e. a number of
+//! reads that grows with the logarithm of the number of heights it was written at.
+
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::to_length_prefixed;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+use crate::{AppendStore, Item, Keymap};
+
+const VALUE: &[u8] = b"value";
+const VALUES: &[u8] = b"values";
+const CHANGELOG: &[u8] = b"changelog";
+
+/// The value of an item or entry after the writes made at a height, or `None` if it was removed
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    height: u64,
+    value: Option<Vec<u8>>,
+}
+
+/// Records the value written at `height`, replacing the checkpoint of an earlier write at the
+/// same height
+fn record(
+    changelog: &AppendStore<Checkpoint, Bincode2>,
+    storage: &mut dyn Storage,
+    height: u64,
+    value: Option<Vec<u8>>,
+) -> StdResult<()> {
+    let checkpoint = Checkpoint { height, value };
+    let len = changelog.get_len(storage)?;
+    if len > 0 {
+        let last = changelog.get_at(storage, len - 1)?;
+        if last.height > height {
+            return Err(StdError::generic_err(format!(
+                "snapshot: can't write at height {height}, after a write at height {}",
+                last.height
+            )));
+        }
+        if last.height == height {
+            return changelog.set_at(storage, len - 1, &checkpoint);
+        }
+    }
+    changelog.push(storage, &checkpoint)
+}
+
+/// Returns the serialized value as of the start of `height`, i.e. that of the last checkpoint
+/// before it
+fn value_at_height(
+    changelog: &AppendStore<Checkpoint, Bincode2>,
+    storage: &dyn Storage,
+    height: u64,
+) -> StdResult<Option<Vec<u8>>> {
+    // the number of checkpoints before `height`
+    let (mut low, mut high) = (0, changelog.get_len(storage)?);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if changelog.get_at(storage, mid)?.height < height {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    if low == 0 {
+        return Ok(None);
+    }
+    Ok(changelog.get_at(storage, low - 1)?.value)
+}
+
+/// An Item that can be loaded as of a past block height
+pub struct SnapshotItem<'a, T, Ser = Bincode2>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    namespace: &'a [u8],
+    /// needed if any suffixes were added to the original namespace.
+    prefix: Option<Vec<u8>>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> SnapshotItem<'a, T, Ser> {
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self {
+            namespace,
+            prefix: None,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new SnapshotItem. This can be used when you want to associate a SnapshotItem to each user
+    /// and you still get to define the SnapshotItem as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let suffix = to_length_prefixed(suffix);
+        let prefix = self.prefix.as_deref().unwrap_or(self.namespace);
+        let prefix = [prefix, suffix.as_slice()].concat();
+        Self {
+            namespace: self.namespace,
+            prefix: Some(prefix),
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, Ser: Serde> SnapshotItem<'_, T, Ser> {
+    /// the current value
+    fn value(&self) -> Item<'_, T, Ser> {
+        Item::new(self.as_slice()).add_suffix(VALUE)
+    }
+
+    /// the checkpoints of the value
+    fn changelog(&self) -> AppendStore<'_, Checkpoint, Bincode2> {
+        AppendStore::new(self.as_slice()).add_suffix(CHANGELOG)
+    }
+
+    /// saves the value, written at the current block `height`
+    pub fn save(&self, storage: &mut dyn Storage, data: &T, height: u64) -> StdResult<()> {
+        record(
+            &self.changelog(),
+            storage,
+            height,
+            Some(Ser::serialize(data)?),
+        )?;
+        self.value().save(storage, data)
+    }
+
+    /// removes the value, at the current block `height`
+    pub fn remove(&self, storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+        if self.value().is_empty(storage) {
+            return Ok(());
+        }
+        record(&self.changelog(), storage, height, None)?;
+        self.value().remove(storage);
+        Ok(())
+    }
+
+    /// load will return an error if no data is set, or on parse error
+    pub fn load(&self, storage: &dyn Storage) -> StdResult<T> {
+        self.value().load(storage)
+    }
+
+    /// may_load will parse the data if present, returns `Ok(None)` if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        self.value().may_load(storage)
+    }
+
+    /// efficient way to see if any object is currently saved.
+    pub fn is_empty(&self, storage: &dyn Storage) -> bool {
+        self.value().is_empty(storage)
+    }
+
+    /// loads the value as of the start of block `height`, returns an error if no data was
+    /// set then
+    pub fn load_at_height(&self, storage: &dyn Storage, height: u64) -> StdResult<T> {
+        self.may_load_at_height(storage, height)?
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))
+    }
+
+    /// loads the value as of the start of block `height`, returns `Ok(None)` if no data was
+    /// set then
+    pub fn may_load_at_height(&self, storage: &dyn Storage, height: u64) -> StdResult<Option<T>> {
+        value_at_height(&self.changelog(), storage, height)?
+            .map(|data| Ser::deserialize(&data))
+            .transpose()
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.namespace
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, Ser: Serde> Namespaced for SnapshotItem<'_, T, Ser> {
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// A Keymap whose entries can be loaded as of a past block height
+pub struct SnapshotMap<'a, K, T, Ser = Bincode2>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    namespace: &'a [u8],
+    /// needed if any suffixes were added to the original namespace.
+    prefix: Option<Vec<u8>>,
+    key_type: PhantomData<K>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, K, T, Ser> SnapshotMap<'a, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self {
+            namespace,
+            prefix: None,
+            key_type: PhantomData,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new SnapshotMap. This can be used when you want to associate a SnapshotMap to each user
+    /// and you still get to define the SnapshotMap as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let suffix = to_length_prefixed(suffix);
+        let prefix = self.prefix.as_deref().unwrap_or(self.namespace);
+        let prefix = [prefix, suffix.as_slice()].concat();
+        Self {
+            namespace: self.namespace,
+            prefix: Some(prefix),
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+}
+
+impl<K, T, Ser> SnapshotMap<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// the current entries
+    fn values(&self) -> Keymap<'_, K, T, Ser> {
+        Keymap::new(self.as_slice()).add_suffix(VALUES)
+    }
+
+    /// the checkpoints of the entry of `key`
+    fn changelog(&self, key: &K) -> StdResult<AppendStore<'_, Checkpoint, Bincode2>> {
+        Ok(AppendStore::new(self.as_slice())
+            .add_suffix(CHANGELOG)
+            .add_suffix(&Ser::serialize(key)?))
+    }
+
+    /// inserts an entry, written at the current block `height`
+    pub fn insert(
+        &self,
+        storage: &mut dyn Storage,
+        key: &K,
+        item: &T,
+        height: u64,
+    ) -> StdResult<()> {
+        record(
+            &self.changelog(key)?,
+            storage,
+            height,
+            Some(Ser::serialize(item)?),
+        )?;
+        self.values().insert(storage, key, item)
+    }
+
+    /// removes an entry, at the current block `height`
+    pub fn remove(&self, storage: &mut dyn Storage, key: &K, height: u64) -> StdResult<()> {
+        let values = self.values();
+        if !values.contains(storage, key) {
+            return Ok(());
+        }
+        record(&self.changelog(key)?, storage, height, None)?;
+        values.remove(storage, key)
+    }
+
+    /// returns the current value of the entry of `key`
+    pub fn get(&self, storage: &dyn Storage, key: &K) -> Option<T> {
+        self.values().get(storage, key)
+    }
+
+    /// checks if an entry is currently stored with this key
+    pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+        self.values().contains(storage, key)
+    }
+
+    /// get the current number of entries
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.values().get_len(storage)
+    }
+
+    /// checks if the map currently has any entries
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        self.values().is_empty(storage)
+    }
+
+    /// returns a page of the current entries, like `Keymap::paging`
+    pub fn paging(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<(K, T)>> {
+        self.values().paging(storage, start_page, size)
+    }
+
+    /// loads the entry of `key` as of the start of block `height`, returns an error if it
+    /// wasn't set then
+    pub fn load_at_height(&self, storage: &dyn Storage, key: &K, height: u64) -> StdResult<T> {
+        self.may_load_at_height(storage, key, height)?
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))
+    }
+
+    /// loads the entry of `key` as of the start of block `height`, returns `Ok(None)` if it
+    /// wasn't set then
+    pub fn may_load_at_height(
+        &self,
+        storage: &dyn Storage,
+        key: &K,
+        height: u64,
+    ) -> StdResult<Option<T>> {
+        value_at_height(&self.changelog(key)?, storage, height)?
+            .map(|data| Ser::deserialize(&data))
+            .transpose()
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.namespace
+        }
+    }
+}
+
+impl<K, T, Ser> Namespaced for SnapshotMap<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use secret_toolkit_serialization::Json;
+
+    #[test]
+    fn test_snapshot_item() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let supply: SnapshotItem<u128> = SnapshotItem::new(b"supply");
+
+        supply.save(&mut storage, &100, 10)?;
+        supply.save(&mut storage, &150, 20)?;
+        // the last write in a block wins
+        supply.save(&mut storage, &200, 30)?;
+        supply.save(&mut storage, &250, 30)?;
+        supply.remove(&mut storage, 40)?;
+        supply.save(&mut storage, &300, 50)?;
+
+        assert_eq!(supply.load(&storage)?, 300);
+        assert_eq!(supply.may_load_at_height(&storage, 0)?, None);
+        // writes are only visible from the next height on
+        assert_eq!(supply.may_load_at_height(&storage, 10)?, None);
+        assert_eq!(supply.load_at_height(&storage, 11)?, 100);
+        assert_eq!(supply.load_at_height(&storage, 20)?, 100);
+        assert_eq!(supply.load_at_height(&storage, 21)?, 150);
+        assert_eq!(supply.load_at_height(&storage, 31)?, 250);
+        assert!(supply.load_at_height(&storage, 45).is_err());
+        assert_eq!(supply.load_at_height(&storage, 1000)?, 300);
+
+        // heights can't decrease
+        assert!(supply.save(&mut storage, &1, 49).is_err());
+        assert_eq!(supply.load(&storage)?, 300);
+
+        // suffixes are kept separately
+        let other = supply.add_suffix(b"other");
+        assert!(other.is_empty(&storage));
+        assert_eq!(other.may_load_at_height(&storage, 1000)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_map() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let stakes: SnapshotMap<String, u64, Json> = SnapshotMap::new(b"stakes");
+        let (alice, bob) = ("alice".to_string(), "bob".to_string());
+
+        for height in 1..=20 {
+            stakes.insert(&mut storage, &alice, &(height * 10), height)?;
+        }
+        stakes.insert(&mut storage, &bob, &5, 8)?;
+        stakes.remove(&mut storage, &bob, 12)?;
+        // removing a missing entry doesn't record anything
+        stakes.remove(&mut storage, &bob, 15)?;
+
+        assert_eq!(stakes.get(&storage, &alice), Some(200));
+        assert!(!stakes.contains(&storage, &bob));
+        assert_eq!(stakes.get_len(&storage)?, 1);
+        assert_eq!(stakes.paging(&storage, 0, 10)?, vec![(alice.clone(), 200)]);
+
+        for height in 2..=21 {
+            assert_eq!(
+                stakes.load_at_height(&storage, &alice, height)?,
+                (height - 1) * 10
+            );
+        }
+        assert_eq!(stakes.may_load_at_height(&storage, &alice, 1)?, None);
+        assert_eq!(stakes.may_load_at_height(&storage, &bob, 8)?, None);
+        assert_eq!(stakes.load_at_height(&storage, &bob, 12)?, 5);
+        assert_eq!(stakes.may_load_at_height(&storage, &bob, 13)?, None);
+
+        let suffixed = stakes.add_suffix(b"pool");
+        assert!(suffixed.is_empty(&storage)?);
+        assert_eq!(suffixed.may_load_at_height(&storage, &alice, 21)?, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! Isolation of the storage of multiple logical instances served by one contract.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use secret_toolkit_serialization::Serde;
+
+use crate::{AppendStore, DequeStore, Item, Keymap, Keyset, PageSizeOption, SetOnce};
+
+const TENANT_TAG: &[u8] = b"tenant:";
+
+/// A logical instance of a contract that serves several isolated instances (e.g. white-label
+/// deployments).
+///
+/// Collections are declared once, as usual, and `Tenant` returns views of them that are
+/// suffixed with a prefix derived from the tenant id, so the data of different tenants never
+/// overlaps.
+///
+/// ```
+/// # use cosmwasm_std::{testing::MockStorage, StdError};
+/// # use secret_toolkit_storage::{tenant::Tenant, Item, Keymap};
+/// pub static CONFIG: Item<String> = Item::new(b"config");
+/// pub static BALANCES: Keymap<String, u128> = Keymap::new(b"balances");
+///
+/// let mut storage = MockStorage::new();
+/// let acme = Tenant::new("acme");
+/// let globex = Tenant::new("globex");
+///
+/// acme.item(&CONFIG).save(&mut storage, &"acme config".to_string())?;
+/// acme.map(&BALANCES).insert(&mut storage, &"alice".to_string(), &100)?;
+///
+/// assert_eq!(globex.item(&CONFIG).may_load(&storage)?, None);
+/// assert_eq!(globex.map(&BALANCES).get(&storage, &"alice".to_string()), None);
+/// # Ok::<(), StdError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tenant {
+    id: Vec<u8>,
+    suffix: Vec<u8>,
+}
+
+impl Tenant {
+    /// Creates a tenant whose collections are isolated by the given id
+    pub fn new(id: impl AsRef<[u8]>) -> Self {
+        let id = id.as_ref().to_vec();
+        let suffix = [TENANT_TAG, id.as_slice()].concat();
+        Self { id, suffix }
+    }
+
+    /// Returns the id of the tenant
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Returns the suffix appended to the namespace of every collection of the tenant
+    pub fn suffix(&self) -> &[u8] {
+        &self.suffix
+    }
+
+    /// Returns the view of an `Item` that belongs to this tenant
+    pub fn item<'a, T, Ser>(&self, item: &Item<'a, T, Ser>) -> Item<'a, T, Ser>
+    where
+        T: Serialize + DeserializeOwned,
+        Ser: Serde,
+    {
+        item.add_suffix(&self.suffix)
+    }
+
+    /// Returns the view of a `SetOnce` that belongs to this tenant
+    pub fn set_once<'a, T, Ser>(&self, item: &SetOnce<'a, T, Ser>) -> SetOnce<'a, T, Ser>
+    where
+        T: Serialize + DeserializeOwned,
+        Ser: Serde,
+    {
+        item.add_suffix(&self.suffix)
+    }
+
+    /// Returns the view of a `Keymap` that belongs to this tenant
+    pub fn map<'a, K, T, Ser>(&self, map: &Keymap<'a, K, T, Ser>) -> Keymap<'a, K, T, Ser>
+    where
+        K: Serialize + DeserializeOwned,
+        T: Serialize + DeserializeOwned,
+        Ser: Serde,
+    {
+        map.add_suffix(&self.suffix)
+    }
+
+    /// Returns the view of a `Keyset` that belongs to this tenant
+    pub fn set<'a, K, Ser>(&self, set: &Keyset<'a, K, Ser>) -> Keyset<'a, K, Ser>
+    where
+        K: Serialize + DeserializeOwned,
+        Ser: Serde,
+    {
+        set.add_suffix(&self.suffix)
+    }
+
+    /// Returns the view of an `AppendStore` that belongs to this tenant
+    pub fn append_store<'a, T, Ser, P>(
+        &self,
+        store: &AppendStore<'a, T, Ser, P>,
+    ) -> AppendStore<'a, T, Ser, P>
+    where
+        T: Serialize + DeserializeOwned,
+        Ser: Serde,
+        P: PageSizeOption,
+    {
+        store.add_suffix(&self.suffix)
+    }
+
+    /// Returns the view of a `DequeStore` that belongs to this tenant
+    pub fn deque_store<'a, T, Ser, P>(
+        &self,
+        store: &DequeStore<'a, T, Ser, P>,
+    ) -> DequeStore<'a, T, Ser, P>
+    where
+        T: Serialize + DeserializeOwned,
+        Ser: Serde,
+        P: PageSizeOption,
+    {
+        store.add_suffix(&self.suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::StdResult;
+
+    use crate::Namespaced;
+
+    static CONFIG: Item<u32> = Item::new(b"config");
+    static BALANCES: Keymap<String, u32> = Keymap::new(b"balances");
+    static MEMBERS: Keyset<String> = Keyset::new(b"members");
+    static HISTORY: AppendStore<u32> = AppendStore::new(b"history");
+    static QUEUE: DequeStore<u32> = DequeStore::new(b"queue");
+
+    #[test]
+    fn test_tenants_are_isolated() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let a = Tenant::new("a");
+        let b = Tenant::new(b"b");
+
+        a.item(&CONFIG).save(&mut storage, &1)?;
+        b.item(&CONFIG).save(&mut storage, &2)?;
+        assert_eq!(a.item(&CONFIG).load(&storage)?, 1);
+        assert_eq!(b.item(&CONFIG).load(&storage)?, 2);
+        assert_eq!(CONFIG.may_load(&storage)?, None);
+
+        a.map(&BALANCES)
+            .insert(&mut storage, &"alice".to_string(), &5)?;
+        assert_eq!(a.map(&BALANCES).get_len(&storage)?, 1);
+        assert_eq!(b.map(&BALANCES).get_len(&storage)?, 0);
+
+        a.set(&MEMBERS).insert(&mut storage, &"alice".to_string())?;
+        assert!(!b.set(&MEMBERS).contains(&storage, &"alice".to_string()));
+
+        a.append_store(&HISTORY).push(&mut storage, &7)?;
+        assert_eq!(b.append_store(&HISTORY).get_len(&storage)?, 0);
+
+        b.deque_store(&QUEUE).push_back(&mut storage, &8)?;
+        assert_eq!(a.deque_store(&QUEUE).get_len(&storage)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tenant_namespaces() {
+        let tenant = Tenant::new("acme");
+        assert_eq!(tenant.id(), b"acme");
+        assert_eq!(tenant.suffix(), b"tenant:acme");
+
+        // a tenant's views are regular suffixed collections
+        assert_eq!(
+            tenant.item(&CONFIG).namespace(),
+            CONFIG.add_suffix(b"tenant:acme").namespace()
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! A Keymap whose removed entries are kept as tombstones, which can be restored until they are
+//! purged, e.g. for undo flows or retention windows.
+//!
+//! Tombstoned entries are hidden from `get`, `contains`, `get_len` and `paging`, and listed by
+//! `paging_tombstones`. `purge_tombstones` deletes them for good, in the order they were
+//! removed, so a contract can purge a bounded number of them in each message.
+
+use std::marker::PhantomData;
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::to_length_prefixed;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+use crate::{DequeStore, Item, Keymap};
+
+const LIVE: &[u8] = b"live";
+const TOMBSTONES: &[u8] = b"tombstones";
+const PURGE_QUEUE: &[u8] = b"purge_queue";
+const SEQUENCE: &[u8] = b"sequence";
+
+/// A removed entry, along with the sequence number of its removal, which tells whether the
+/// entries of the purge queue are still current
+#[derive(Serialize, Deserialize)]
+struct Tombstone<T> {
+    seq: u64,
+    value: T,
+}
+
+/// A Keymap with soft deletes
+pub struct TombstoneKeymap<'a, K, T, Ser = Bincode2>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    namespace: &'a [u8],
+    /// needed if any suffixes were added to the original namespace.
+    prefix: Option<Vec<u8>>,
+    key_type: PhantomData<K>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, K, T, Ser> TombstoneKeymap<'a, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self {
+            namespace,
+            prefix: None,
+            key_type: PhantomData,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new TombstoneKeymap. This can be used when you want to associate a TombstoneKeymap to each user
+    /// and you still get to define the TombstoneKeymap as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let suffix = to_length_prefixed(suffix);
+        let prefix = self.prefix.as_deref().unwrap_or(self.namespace);
+        let prefix = [prefix, suffix.as_slice()].concat();
+        Self {
+            namespace: self.namespace,
+            prefix: Some(prefix),
+            key_type: self.key_type,
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+}
+
+impl<K, T, Ser> TombstoneKeymap<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// the entries that weren't removed
+    fn live(&self) -> Keymap<'_, K, T, Ser> {
+        Keymap::new(self.as_slice()).add_suffix(LIVE)
+    }
+
+    /// the removed entries
+    fn tombstones(&self) -> Keymap<'_, K, Tombstone<T>, Ser> {
+        Keymap::new(self.as_slice()).add_suffix(TOMBSTONES)
+    }
+
+    /// the serialized keys of the removed entries in the order they were removed, with the
+    /// sequence number of their removal
+    fn purge_queue(&self) -> DequeStore<'_, (u64, Vec<u8>)> {
+        DequeStore::new(self.as_slice()).add_suffix(PURGE_QUEUE)
+    }
+
+    /// the sequence number of the last removal
+    fn sequence(&self) -> Item<'_, u64> {
+        Item::new(self.as_slice()).add_suffix(SEQUENCE)
+    }
+
+    /// inserts an entry, which replaces its tombstone if it was removed
+    pub fn insert(&self, storage: &mut dyn Storage, key: &K, item: &T) -> StdResult<()> {
+        let tombstones = self.tombstones();
+        if tombstones.contains(storage, key) {
+            tombstones.remove(storage, key)?;
+        }
+        self.live().insert(storage, key, item)
+    }
+
+    /// removes an entry, keeping it as a tombstone. Fails if there is no entry with this key.
+    pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+        let live = self.live();
+        let value = live
+            .get(storage, key)
+            .ok_or_else(|| StdError::generic_err("tombstone keymap: no entry with this key"))?;
+        live.remove(storage, key)?;
+
+        let sequence = self.sequence();
+        let seq = sequence.may_load(storage)?.unwrap_or_default() + 1;
+        sequence.save(storage, &seq)?;
+        self.tombstones()
+            .insert(storage, key, &Tombstone { seq, value })?;
+        self.purge_queue()
+            .push_back(storage, &(seq, Bincode2::serialize(key)?))
+    }
+
+    /// restores a removed entry. Fails if the entry has no tombstone, e.g. if it was purged.
+    pub fn restore(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+        let tombstones = self.tombstones();
+        let tombstone = tombstones.get(storage, key).ok_or_else(|| {
+            StdError::generic_err("tombstone keymap: no removed entry with this key")
+        })?;
+        tombstones.remove(storage, key)?;
+        self.live().insert(storage, key, &tombstone.value)
+    }
+
+    /// deletes the tombstone of a removed entry for good, if it has one
+    pub fn purge(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+        let tombstones = self.tombstones();
+        if tombstones.contains(storage, key) {
+            tombstones.remove(storage, key)?;
+        }
+        Ok(())
+    }
+
+    /// deletes the tombstones of up to `limit` entries for good, the earliest removed first,
+    /// and returns how many were deleted. Removals whose entry was since restored or inserted
+    /// again count towards the limit, which bounds the gas used.
+    pub fn purge_tombstones(&self, storage: &mut dyn Storage, limit: u32) -> StdResult<u32> {
+        let tombstones = self.tombstones();
+        let mut purged = 0;
+        for (seq, key) in self.purge_queue().pop_front_n(storage, limit)? {
+            let key: K = Bincode2::deserialize(&key)?;
+            if tombstones
+                .get(storage, &key)
+                .is_some_and(|tombstone| tombstone.seq == seq)
+            {
+                tombstones.remove(storage, &key)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// returns the value of an entry, unless it was removed
+    pub fn get(&self, storage: &dyn Storage, key: &K) -> Option<T> {
+        self.live().get(storage, key)
+    }
+
+    /// checks if an entry that wasn't removed is stored with this key
+    pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+        self.live().contains(storage, key)
+    }
+
+    /// get the number of entries that weren't removed
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.live().get_len(storage)
+    }
+
+    /// checks if there are any entries that weren't removed
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        self.live().is_empty(storage)
+    }
+
+    /// returns a page of the entries that weren't removed, like `Keymap::paging`
+    pub fn paging(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<(K, T)>> {
+        self.live().paging(storage, start_page, size)
+    }
+
+    /// returns the value of a removed entry, if it wasn't purged
+    pub fn get_tombstoned(&self, storage: &dyn Storage, key: &K) -> Option<T> {
+        self.tombstones()
+            .get(storage, key)
+            .map(|tombstone| tombstone.value)
+    }
+
+    /// checks if a removed entry with this key can be restored
+    pub fn is_tombstoned(&self, storage: &dyn Storage, key: &K) -> bool {
+        self.tombstones().contains(storage, key)
+    }
+
+    /// get the number of removed entries that weren't purged
+    pub fn tombstones_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.tombstones().get_len(storage)
+    }
+
+    /// returns a page of the removed entries that weren't purged
+    pub fn paging_tombstones(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<(K, T)>> {
+        Ok(self
+            .tombstones()
+            .paging(storage, start_page, size)?
+            .into_iter()
+            .map(|(key, tombstone)| (key, tombstone.value))
+            .collect())
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.namespace
+        }
+    }
+}
+
+impl<K, T, Ser> Namespaced for TombstoneKeymap<'_, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_soft_delete_and_restore() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let notes: TombstoneKeymap<u32, String> = TombstoneKeymap::new(b"notes");
+
+        for id in 1..=3 {
+            notes.insert(&mut storage, &id, &format!("note {id}"))?;
+        }
+        notes.remove(&mut storage, &2)?;
+        assert!(notes.remove(&mut storage, &2).is_err());
+
+        assert_eq!(notes.get(&storage, &2), None);
+        assert!(!notes.contains(&storage, &2));
+        assert_eq!(notes.get_len(&storage)?, 2);
+        assert_eq!(notes.paging(&storage, 0, 10)?.len(), 2);
+        assert!(notes.is_tombstoned(&storage, &2));
+        assert_eq!(
+            notes.get_tombstoned(&storage, &2),
+            Some("note 2".to_string())
+        );
+        assert_eq!(
+            notes.paging_tombstones(&storage, 0, 10)?,
+            vec![(2, "note 2".to_string())]
+        );
+
+        notes.restore(&mut storage, &2)?;
+        assert_eq!(notes.get(&storage, &2), Some("note 2".to_string()));
+        assert!(!notes.is_tombstoned(&storage, &2));
+        assert!(notes.restore(&mut storage, &2).is_err());
+
+        // inserting a removed entry again replaces its tombstone
+        notes.remove(&mut storage, &3)?;
+        notes.insert(&mut storage, &3, &"new note".to_string())?;
+        assert_eq!(notes.tombstones_len(&storage)?, 0);
+        assert_eq!(notes.get(&storage, &3), Some("new note".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_purge_tombstones() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let notes: TombstoneKeymap<u32, u32> = TombstoneKeymap::new(b"notes");
+
+        for id in 1..=5 {
+            notes.insert(&mut storage, &id, &id)?;
+            notes.remove(&mut storage, &id)?;
+        }
+        // 2 is restored, and 3 is removed again, so their first removals are stale
+        notes.restore(&mut storage, &2)?;
+        notes.restore(&mut storage, &3)?;
+        notes.remove(&mut storage, &3)?;
+
+        assert_eq!(notes.purge_tombstones(&mut storage, 3)?, 1);
+        assert!(!notes.is_tombstoned(&storage, &1));
+        assert_eq!(notes.get(&storage, &2), Some(2));
+        assert!(notes.is_tombstoned(&storage, &3));
+
+        notes.purge(&mut storage, &5)?;
+        assert_eq!(notes.purge_tombstones(&mut storage, 10)?, 2);
+        assert_eq!(notes.tombstones_len(&storage)?, 0);
+        assert_eq!(notes.purge_tombstones(&mut storage, 10)?, 0);
+        assert!(notes.restore(&mut storage, &4).is_err());
+
+        // suffixes are kept separately
+        let other = notes.add_suffix(b"other");
+        other.insert(&mut storage, &1, &1)?;
+        assert_eq!(other.get_len(&storage)?, 1);
+        assert_eq!(notes.get_len(&storage)?, 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+//! Storage adapters that stamp values with a version, for optimistic concurrency.
+//!
+//! Contract executions don't interleave, but workflows that span several messages, e.g. a
+//! message that sends a submessage and continues in its reply, can see state that changed
+//! between their steps. Each save through these adapters bumps the version of the value, so a
+//! step can remember the version it read and use `save_if_version` (or `insert_if_version`) to
+//! fail if the value changed in the meantime.
+//!
+//! Values that were never saved have version 0. Removing a value also resets its version, so
+//! workflows that may remove values should check that the value still exists as well.
+
+use std::any::type_name;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_storage::to_length_prefixed;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use secret_toolkit_serialization::{Bincode2, Serde};
+
+use crate::namespace::Namespaced;
+use crate::{IterOption, Keymap, WithIter, WithoutIter};
+
+#[derive(Serialize, Deserialize)]
+struct Stamped<T> {
+    version: u64,
+    value: T,
+}
+
+fn check_version(expected: u64, current: u64) -> StdResult<()> {
+    if expected != current {
+        return Err(StdError::generic_err(format!(
+            "version mismatch: expected version {expected}, found version {current}"
+        )));
+    }
+    Ok(())
+}
+
+/// An Item whose value is stamped with a version, bumped by every save
+pub struct VersionedItem<'a, T, Ser = Bincode2>
+where
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    storage_key: &'a [u8],
+    /// needed if any suffixes were added to the original storage key.
+    prefix: Option<Vec<u8>>,
+    item_type: PhantomData<T>,
+    serialization_type: PhantomData<Ser>,
+}
+
+impl<'a, T: Serialize + DeserializeOwned, Ser: Serde> VersionedItem<'a, T, Ser> {
+    /// constructor
+    pub const fn new(key: &'a [u8]) -> Self {
+        Self {
+            storage_key: key,
+            prefix: None,
+            item_type: PhantomData,
+            serialization_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new VersionedItem. This can be used when you want to associate a VersionedItem to each user
+    /// and you still get to define the VersionedItem as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        let suffix = to_length_prefixed(suffix);
+        let prefix = self.prefix.as_deref().unwrap_or(self.storage_key);
+        let prefix = [prefix, suffix.as_slice()].concat();
+        Self {
+            storage_key: self.storage_key,
+            prefix: Some(prefix),
+            item_type: self.item_type,
+            serialization_type: self.serialization_type,
+        }
+    }
+
+    /// save will serialize the model and store it with the next version, which is returned.
+    /// returns an error on serialization issues
+    pub fn save(&self, storage: &mut dyn Storage, data: &T) -> StdResult<u64> {
+        let version = self.version(storage)? + 1;
+        let stamped = Ser::serialize(&Stamped {
+            version,
+            value: data,
+        })?;
+        storage.set(self.as_slice(), &stamped);
+        Ok(version)
+    }
+
+    /// saves the model like `save`, only if the current version is `expected_version`.
+    /// returns an error if the version differs, i.e. if the value was saved in the meantime
+    pub fn save_if_version(
+        &self,
+        storage: &mut dyn Storage,
+        data: &T,
+        expected_version: u64,
+    ) -> StdResult<u64> {
+        check_version(expected_version, self.version(storage)?)?;
+        self.save(storage, data)
+    }
+
+    /// userfacing remove function, which also resets the version
+    pub fn remove(&self, storage: &mut dyn Storage) {
+        storage.remove(self.as_slice());
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, storage: &dyn Storage) -> StdResult<T> {
+        Ok(self.load_versioned(storage)?.0)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns `Ok(None)` if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, storage: &dyn Storage) -> StdResult<Option<T>> {
+        Ok(self.may_load_versioned(storage)?.map(|(value, _)| value))
+    }
+
+    /// loads the data along with its version, returns an error if no data is set at the key
+    pub fn load_versioned(&self, storage: &dyn Storage) -> StdResult<(T, u64)> {
+        self.may_load_versioned(storage)?
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))
+    }
+
+    /// loads the data along with its version, returns `Ok(None)` if no data is set at the key
+    pub fn may_load_versioned(&self, storage: &dyn Storage) -> StdResult<Option<(T, u64)>> {
+        match storage.get(self.as_slice()) {
+            Some(data) => {
+                let stamped: Stamped<T> = Ser::deserialize(&data)?;
+                Ok(Some((stamped.value, stamped.version)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// returns the current version of the data, or 0 if no data is set at the key
+    pub fn version(&self, storage: &dyn Storage) -> StdResult<u64> {
+        Ok(self
+            .may_load_versioned(storage)?
+            .map_or(0, |(_, version)| version))
+    }
+
+    /// efficient way to see if any object is currently saved.
+    pub fn is_empty(&self, storage: &dyn Storage) -> bool {
+        storage.get(self.as_slice()).is_none()
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if let Some(prefix) = &self.prefix {
+            prefix
+        } else {
+            self.storage_key
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, Ser: Serde> Namespaced for VersionedItem<'_, T, Ser> {
+    fn namespace(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// A Keymap whose values are stamped with a version, bumped by every insert of their key
+pub struct VersionedKeymap<'a, K, T, Ser = Bincode2, I = WithIter>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    keymap: Keymap<'a, K, Vec<u8>, Ser, I>,
+    item_type: PhantomData<T>,
+}
+
+impl<'a, K, T, Ser> VersionedKeymap<'a, K, T, Ser>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+{
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self {
+            keymap: Keymap::new(namespace),
+            item_type: PhantomData,
+        }
+    }
+
+    /// This is used to produce a new VersionedKeymap. This can be used when you want to associate a VersionedKeymap to each user
+    /// and you still get to define the VersionedKeymap as a static constant
+    pub fn add_suffix(&self, suffix: &[u8]) -> Self {
+        Self {
+            keymap: self.keymap.add_suffix(suffix),
+            item_type: self.item_type,
+        }
+    }
+
+    /// get total number of objects saved
+    pub fn get_len(&self, storage: &dyn Storage) -> StdResult<u32> {
+        self.keymap.get_len(storage)
+    }
+
+    /// checks if the collection has any elements
+    pub fn is_empty(&self, storage: &dyn Storage) -> StdResult<bool> {
+        self.keymap.is_empty(storage)
+    }
+
+    /// paginates (key, item) pairs.
+    pub fn paging(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<(K, T)>> {
+        self.keymap
+            .paging(storage, start_page, size)?
+            .into_iter()
+            .map(|(key, data)| Ok((key, self.unstamp(&data)?.0)))
+            .collect()
+    }
+
+    /// paginates only the keys. More efficient than paginating both items and keys
+    pub fn paging_keys(
+        &self,
+        storage: &dyn Storage,
+        start_page: u32,
+        size: u32,
+    ) -> StdResult<Vec<K>> {
+        self.keymap.paging_keys(storage, start_page, size)
+    }
+
+    /// Returns a readonly iterator for (key-item) pairs
+    pub fn iter<'b>(
+        &'b self,
+        storage: &'b dyn Storage,
+    ) -> StdResult<impl DoubleEndedIterator<Item = StdResult<(K, T)>> + 'b> {
+        Ok(self.keymap.iter(storage)?.map(move |pair| {
+            let (key, data) = pair?;
+            Ok((key, self.unstamp(&data)?.0))
+        }))
+    }
+}
+
+impl<'a, K, T, Ser, I> VersionedKeymap<'a, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    /// Creates a VersionedKeymap that stores the stamped values in the given keymap, e.g. one
+    /// created by a `KeymapBuilder`
+    pub const fn from_keymap(keymap: Keymap<'a, K, Vec<u8>, Ser, I>) -> Self {
+        Self {
+            keymap,
+            item_type: PhantomData,
+        }
+    }
+
+    fn stamp(&self, item: &T, version: u64) -> StdResult<Vec<u8>> {
+        Ser::serialize(&Stamped {
+            version,
+            value: item,
+        })
+    }
+
+    fn unstamp(&self, data: &[u8]) -> StdResult<(T, u64)> {
+        let stamped: Stamped<T> = Ser::deserialize(data)?;
+        Ok((stamped.value, stamped.version))
+    }
+}
+
+macro_rules! versioned_keymap_methods {
+    ($iter:ty) => {
+        impl<K, T, Ser> VersionedKeymap<'_, K, T, Ser, $iter>
+        where
+            K: Serialize + DeserializeOwned,
+            T: Serialize + DeserializeOwned,
+            Ser: Serde,
+        {
+            /// user facing get function
+            pub fn get(&self, storage: &dyn Storage, key: &K) -> Option<T> {
+                self.get_versioned(storage, key).map(|(value, _)| value)
+            }
+
+            /// returns the item stored with this key along with its version
+            pub fn get_versioned(&self, storage: &dyn Storage, key: &K) -> Option<(T, u64)> {
+                self.keymap
+                    .get(storage, key)
+                    .and_then(|data| self.unstamp(&data).ok())
+            }
+
+            /// returns the current version of the item stored with this key, or 0 if there is none
+            pub fn version(&self, storage: &dyn Storage, key: &K) -> u64 {
+                self.get_versioned(storage, key)
+                    .map_or(0, |(_, version)| version)
+            }
+
+            /// user facing remove function, which also resets the version of the key
+            pub fn remove(&self, storage: &mut dyn Storage, key: &K) -> StdResult<()> {
+                self.keymap.remove(storage, key)
+            }
+
+            /// user facing insert function, returns the new version of the item
+            pub fn insert(&self, storage: &mut dyn Storage, key: &K, item: &T) -> StdResult<u64> {
+                let version = self.version(storage, key) + 1;
+                self.keymap
+                    .insert(storage, key, &self.stamp(item, version)?)?;
+                Ok(version)
+            }
+
+            /// inserts the item like `insert`, only if the current version of the key is
+            /// `expected_version`. returns an error if the version differs, i.e. if the item was
+            /// inserted or removed in the meantime
+            pub fn insert_if_version(
+                &self,
+                storage: &mut dyn Storage,
+                key: &K,
+                item: &T,
+                expected_version: u64,
+            ) -> StdResult<u64> {
+                check_version(expected_version, self.version(storage, key))?;
+                self.insert(storage, key, item)
+            }
+
+            /// user facing method that checks if any item is stored with this key.
+            pub fn contains(&self, storage: &dyn Storage, key: &K) -> bool {
+                self.keymap.contains(storage, key)
+            }
+        }
+    };
+}
+
+versioned_keymap_methods!(WithIter);
+versioned_keymap_methods!(WithoutIter);
+
+impl<K, T, Ser, I> Namespaced for VersionedKeymap<'_, K, T, Ser, I>
+where
+    K: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Ser: Serde,
+    I: IterOption,
+{
+    fn namespace(&self) -> &[u8] {
+        self.keymap.namespace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+    use crate::KeymapBuilder;
+
+    #[test]
+    fn test_versioned_item() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let config: VersionedItem<String> = VersionedItem::new(b"config");
+
+        assert_eq!(config.version(&storage)?, 0);
+        assert!(config.load_versioned(&storage).is_err());
+        assert_eq!(
+            config.save_if_version(&mut storage, &"a".to_string(), 0)?,
+            1
+        );
+        assert_eq!(config.save(&mut storage, &"b".to_string())?, 2);
+        assert_eq!(config.load_versioned(&storage)?, ("b".to_string(), 2));
+
+        // a step that read version 1 detects the save in between
+        assert_eq!(
+            config.save_if_version(&mut storage, &"c".to_string(), 1),
+            Err(StdError::generic_err(
+                "version mismatch: expected version 1, found version 2"
+            ))
+        );
+        assert_eq!(config.load(&storage)?, "b");
+        assert_eq!(
+            config.save_if_version(&mut storage, &"c".to_string(), 2)?,
+            3
+        );
+
+        let suffixed = config.add_suffix(b"user");
+        assert_eq!(suffixed.may_load(&storage)?, None);
+        assert_eq!(suffixed.save(&mut storage, &"d".to_string())?, 1);
+
+        config.remove(&mut storage);
+        assert!(config.is_empty(&storage));
+        assert_eq!(config.version(&storage)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_keymap() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let orders: VersionedKeymap<u32, u64> = VersionedKeymap::new(b"orders");
+
+        assert_eq!(orders.insert(&mut storage, &1, &100)?, 1);
+        assert_eq!(orders.insert(&mut storage, &2, &200)?, 1);
+        assert_eq!(orders.insert_if_version(&mut storage, &1, &150, 1)?, 2);
+        assert!(orders.insert_if_version(&mut storage, &1, &175, 1).is_err());
+        assert!(orders.insert_if_version(&mut storage, &3, &300, 1).is_err());
+        assert_eq!(orders.get_versioned(&storage, &1), Some((150, 2)));
+        assert_eq!(orders.version(&storage, &3), 0);
+
+        assert_eq!(orders.get_len(&storage)?, 2);
+        let pairs: Vec<(u32, u64)> = orders.iter(&storage)?.collect::<StdResult<_>>()?;
+        assert_eq!(pairs, vec![(1, 150), (2, 200)]);
+        assert_eq!(orders.paging(&storage, 1, 1)?, vec![(2, 200)]);
+
+        orders.remove(&mut storage, &1)?;
+        assert!(!orders.contains(&storage, &1));
+        assert_eq!(orders.get(&storage, &1), None);
+
+        let locks: VersionedKeymap<u32, bool, Bincode2, WithoutIter> =
+            VersionedKeymap::from_keymap(KeymapBuilder::new(b"locks").without_iter().build());
+        assert_eq!(locks.insert_if_version(&mut storage, &1, &true, 0)?, 1);
+        assert_eq!(locks.get(&storage, &1), Some(true));
+
+        Ok(())
+    }
+}
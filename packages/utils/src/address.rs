@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+
+/// number of characters kept after the human readable part by `shorten`
+pub const SHORTEN_HEAD_CHARS: usize = 4;
+/// number of characters kept at the end of the address by `shorten`
+pub const SHORTEN_TAIL_CHARS: usize = 4;
+
+/// Returns the first 4 bytes of the sha256 hash of the address.
+///
+/// This is enough to tell addresses apart in events and memos, without revealing them.
+pub fn fingerprint(addr: impl AsRef<str>) -> [u8; 4] {
+    let hash = Sha256::digest(addr.as_ref().as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Returns the fingerprint of the address as a lowercase hex string
+pub fn fingerprint_hex(addr: impl AsRef<str>) -> String {
+    fingerprint(addr)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Shortens an address for display, e.g. `secret1qyp5...hvq4-1a2b3c4d`.
+///
+/// The human readable part and the first and last characters of the data part are kept, and the
+/// fingerprint of the full address is appended, so that two addresses that only differ in the
+/// truncated characters still render differently.
+pub fn shorten(addr: impl AsRef<str>) -> String {
+    let addr = addr.as_ref();
+    let checksum = fingerprint_hex(addr);
+
+    // the data part of a bech32 address starts after the last '1'
+    let data_start = addr.rfind('1').map_or(0, |separator| separator + 1);
+    let chars: Vec<char> = addr[data_start..].chars().collect();
+    if chars.len() <= SHORTEN_HEAD_CHARS + SHORTEN_TAIL_CHARS {
+        return format!("{addr}-{checksum}");
+    }
+
+    let head: String = chars[..SHORTEN_HEAD_CHARS].iter().collect();
+    let tail: String = chars[chars.len() - SHORTEN_TAIL_CHARS..].iter().collect();
+    format!("{}{head}...{tail}-{checksum}", &addr[..data_start])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::Addr;
+
+    #[test]
+    fn test_shorten() {
+        let addr = "secret1rf03820fp8gngzg2w02vd30ns78qkc8rg8dxaq";
+        let short = shorten(addr);
+        assert_eq!(
+            short,
+            format!("secret1rf03...dxaq-{}", fingerprint_hex(addr))
+        );
+        assert_eq!(shorten(Addr::unchecked(addr)), short);
+
+        // addresses that only differ in the truncated part are still distinguishable
+        let other = "secret1rf03820fp8gngzg2w02vd30ns78qkc8rg9dxaq";
+        assert_ne!(shorten(other), short);
+        assert!(shorten(other).starts_with("secret1rf03...dxaq-"));
+
+        assert_eq!(shorten("abc"), format!("abc-{}", fingerprint_hex("abc")));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // sha256("abc") = ba7816bf...
+        assert_eq!(fingerprint("abc"), [0xba, 0x78, 0x16, 0xbf]);
+        assert_eq!(fingerprint_hex("abc"), "ba7816bf");
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use cosmwasm_std::{BlockInfo, Env, StdError, StdResult, Timestamp};
+
+/// A point in time that a block can reach, such as a sale or claim window boundary.
+///
+/// It is implemented for `Timestamp`, and for the SNIP-721 `Expiration`.
+pub trait Expires {
+    /// Returns true if the block is at or after this point in time
+    fn is_expired(&self, block: &BlockInfo) -> bool;
+}
+
+impl Expires for Timestamp {
+    fn is_expired(&self, block: &BlockInfo) -> bool {
+        block.time >= *self
+    }
+}
+
+/// Checks that the current block is inside the window that opens at `start` and closes at `end`.
+///
+/// # Arguments
+///
+/// * `env` - the environment of the current call
+/// * `start` - the window is open once the block reaches this point
+/// * `end` - the window is closed once the block reaches this point
+pub fn assert_within<S: Expires, E: Expires>(env: &Env, start: &S, end: &E) -> StdResult<()> {
+    if !start.is_expired(&env.block) {
+        return Err(StdError::generic_err("window has not opened yet"));
+    }
+    if end.is_expired(&env.block) {
+        return Err(StdError::generic_err("window has already closed"));
+    }
+
+    Ok(())
+}
+
+/// Returns the number of blocks since `height`.
+///
+/// # Errors
+/// Will return an error if `height` is after the current block.
+pub fn blocks_since(env: &Env, height: u64) -> StdResult<u64> {
+    env.block.height.checked_sub(height).ok_or_else(|| {
+        StdError::generic_err(format!(
+            "height {height} is after the current height {}",
+            env.block.height
+        ))
+    })
+}
+
+/// Estimates the height of the block at `timestamp`, assuming blocks are produced every
+/// `avg_block_time` from the current block on. Timestamps in the past are estimated backwards.
+pub fn estimate_height_at(env: &Env, timestamp: Timestamp, avg_block_time: Duration) -> u64 {
+    let avg_nanos = avg_block_time.as_nanos().max(1);
+    let now = env.block.time.nanos();
+    if timestamp.nanos() >= now {
+        let blocks = u128::from(timestamp.nanos() - now) / avg_nanos;
+        env.block.height.saturating_add(blocks as u64)
+    } else {
+        let blocks = u128::from(now - timestamp.nanos()) / avg_nanos;
+        env.block.height.saturating_sub(blocks as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+
+    #[test]
+    fn test_assert_within() {
+        let env = mock_env();
+        let now = env.block.time;
+
+        assert!(assert_within(&env, &now.minus_seconds(10), &now.plus_seconds(10)).is_ok());
+        assert!(assert_within(&env, &now, &now.plus_seconds(1)).is_ok());
+        assert_eq!(
+            assert_within(&env, &now.plus_seconds(1), &now.plus_seconds(10)),
+            Err(StdError::generic_err("window has not opened yet"))
+        );
+        assert_eq!(
+            assert_within(&env, &now.minus_seconds(10), &now),
+            Err(StdError::generic_err("window has already closed"))
+        );
+    }
+
+    #[test]
+    fn test_blocks_since() {
+        let env = mock_env();
+        let height = env.block.height;
+
+        assert_eq!(blocks_since(&env, height - 5), Ok(5));
+        assert_eq!(blocks_since(&env, height), Ok(0));
+        assert!(blocks_since(&env, height + 1).is_err());
+    }
+
+    #[test]
+    fn test_estimate_height_at() {
+        let env = mock_env();
+        let height = env.block.height;
+        let now = env.block.time;
+        let block_time = Duration::from_secs(6);
+
+        assert_eq!(estimate_height_at(&env, now, block_time), height);
+        assert_eq!(
+            estimate_height_at(&env, now.plus_seconds(60), block_time),
+            height + 10
+        );
+        assert_eq!(
+            estimate_height_at(&env, now.plus_seconds(65), block_time),
+            height + 10
+        );
+        assert_eq!(
+            estimate_height_at(&env, now.minus_seconds(60), block_time),
+            height - 10
+        );
+    }
+}
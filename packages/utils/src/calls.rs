@@ -1,8 +1,10 @@
+use std::fmt;
+
 use serde::{de::DeserializeOwned, Serialize};
 
 use cosmwasm_std::{
-    to_binary, Coin, CosmosMsg, CustomQuery, QuerierWrapper, QueryRequest, StdResult, Uint128,
-    WasmMsg, WasmQuery,
+    to_binary, to_vec, Coin, CosmosMsg, CustomQuery, Empty, QuerierWrapper, QueryRequest, Response,
+    StdError, StdResult, Uint128, WasmMsg, WasmQuery,
 };
 
 use super::space_pad;
@@ -151,11 +153,201 @@ pub trait Query: Serialize {
     }
 }
 
+/// A trait marking handle messages whose outcome can be simulated with a query of the same
+/// contract, e.g. a swap message with a `simulate_swap` query
+///
+/// Contracts that route messages to other contracts can run the simulation before committing
+/// to the message with [`simulate_or_fail`], so that a message that would fail is rejected with
+/// a clear error instead of failing the whole transaction later.
+pub trait Simulate: HandleCallback {
+    /// the query that simulates the message
+    type Query: Query;
+    /// the response of the simulation query
+    type Response: DeserializeOwned;
+
+    /// Returns the query that simulates this message
+    fn simulation_query(&self) -> Self::Query;
+
+    /// Checks the response of the simulation, e.g. that the simulated return of a swap is above
+    /// the minimum the user asked for. Accepts any response by default.
+    fn check_simulation(&self, _response: &Self::Response) -> StdResult<()> {
+        Ok(())
+    }
+}
+
+/// Simulates a handle message with its query twin and checks the outcome, returning the response
+/// of the simulation if the message is expected to succeed
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the calling contract
+/// * `msg` - the handle message to simulate
+/// * `code_hash` - String holding the code hash of the contract the message is for
+/// * `contract_addr` - address of the contract the message is for
+pub fn simulate_or_fail<C: CustomQuery, M: Simulate>(
+    querier: QuerierWrapper<C>,
+    msg: &M,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<M::Response> {
+    let response: M::Response = msg
+        .simulation_query()
+        .query(querier, code_hash, contract_addr.clone())
+        .map_err(|err| QueryError::new("simulation", contract_addr, err))?;
+    msg.check_simulation(&response)?;
+    Ok(response)
+}
+
+/// The error of a query to another contract, along with the name of the query and the address
+/// of the queried contract.
+///
+/// The error returned by the querier, such as the error of the queried contract, is kept as is,
+/// so callers can still match on it or show it to the user.
+#[derive(Debug, PartialEq)]
+pub struct QueryError {
+    /// the name of the query, e.g. "Balance"
+    pub query: String,
+    /// the address of the queried contract
+    pub contract_addr: String,
+    /// the error returned by the querier
+    pub error: StdError,
+}
+
+impl QueryError {
+    pub fn new(
+        query: impl Into<String>,
+        contract_addr: impl Into<String>,
+        error: StdError,
+    ) -> Self {
+        Self {
+            query: query.into(),
+            contract_addr: contract_addr.into(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Error performing {} query on {}: {}",
+            self.query, self.contract_addr, self.error
+        )
+    }
+}
+
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<QueryError> for StdError {
+    fn from(err: QueryError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+/// Accumulates messages and splits them into batches that each fit in one execution.
+///
+/// A batch holds at most `max_msgs` messages, and at most `max_bytes` bytes of serialized
+/// messages. A message that is larger than `max_bytes` on its own is sent in a batch of its own,
+/// so every batch makes progress. The messages that don't fit in the current execution can be
+/// stored, and dispatched by a follow-up execution.
+pub struct MsgBatcher<T = Empty> {
+    max_msgs: Option<usize>,
+    max_bytes: Option<usize>,
+    msgs: Vec<CosmosMsg<T>>,
+}
+
+impl<T: Serialize> MsgBatcher<T> {
+    /// Creates a MsgBatcher without limits
+    pub const fn new() -> Self {
+        Self {
+            max_msgs: None,
+            max_bytes: None,
+            msgs: Vec::new(),
+        }
+    }
+
+    /// Limits the number of messages in each batch
+    pub fn with_max_msgs(mut self, max_msgs: usize) -> Self {
+        if max_msgs == 0 {
+            panic!("zero max messages used in message batcher")
+        }
+        self.max_msgs = Some(max_msgs);
+        self
+    }
+
+    /// Limits the serialized size of the messages in each batch
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Adds a message to the batcher
+    pub fn push(&mut self, msg: impl Into<CosmosMsg<T>>) {
+        self.msgs.push(msg.into());
+    }
+
+    /// Returns the number of messages that were not batched yet
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+
+    /// Removes the messages of the next batch from the batcher and returns them
+    pub fn next_batch(&mut self) -> StdResult<Vec<CosmosMsg<T>>> {
+        let mut count = 0;
+        let mut bytes = 0;
+        for msg in &self.msgs {
+            if self.max_msgs.is_some_and(|max| count >= max) {
+                break;
+            }
+            if let Some(max_bytes) = self.max_bytes {
+                let size = to_vec(msg)?.len();
+                if count > 0 && bytes + size > max_bytes {
+                    break;
+                }
+                bytes += size;
+            }
+            count += 1;
+        }
+        Ok(self.msgs.drain(..count).collect())
+    }
+
+    /// Splits all the messages into batches
+    pub fn into_batches(mut self) -> StdResult<Vec<Vec<CosmosMsg<T>>>> {
+        let mut batches = vec![];
+        while !self.is_empty() {
+            batches.push(self.next_batch()?);
+        }
+        Ok(batches)
+    }
+
+    /// Returns a Response with the messages of the first batch, and the messages that didn't fit
+    /// in it, which should be dispatched by a follow-up execution
+    pub fn into_response(mut self) -> StdResult<(Response<T>, Vec<CosmosMsg<T>>)> {
+        let batch = self.next_batch()?;
+        Ok((Response::new().add_messages(batch), self.msgs))
+    }
+}
+
+impl<T: Serialize> Default for MsgBatcher<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use cosmwasm_std::{
-        to_vec, Binary, ContractResult, Empty, Querier, QuerierResult, SystemError, SystemResult,
+        BankMsg, Binary, ContractResult, Querier, QuerierResult, SystemError, SystemResult,
     };
     use serde::Deserialize;
 
@@ -260,6 +452,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_msg_batcher() -> StdResult<()> {
+        let msg = |amount: u128| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: "secret1xyzasdf".to_string(),
+                amount: vec![Coin::new(amount, "uscrt")],
+            }
+            .into()
+        };
+        let size = to_vec(&msg(1))?.len();
+
+        let mut batcher = MsgBatcher::new().with_max_msgs(2);
+        (1..=5).for_each(|amount| batcher.push(msg(amount)));
+        let batches = batcher.into_batches()?;
+        assert_eq!(
+            batches,
+            vec![vec![msg(1), msg(2)], vec![msg(3), msg(4)], vec![msg(5)]]
+        );
+
+        let mut batcher = MsgBatcher::new().with_max_bytes(size * 3);
+        (1..=5).for_each(|amount| batcher.push(msg(amount)));
+        let (response, overflow) = batcher.into_response()?;
+        assert_eq!(response.messages.len(), 3);
+        assert_eq!(overflow, vec![msg(4), msg(5)]);
+
+        // a message larger than the budget is still sent on its own
+        let mut batcher = MsgBatcher::new().with_max_bytes(1);
+        batcher.push(msg(1));
+        batcher.push(msg(2));
+        assert_eq!(batcher.next_batch()?, vec![msg(1)]);
+        assert_eq!(batcher.len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_query_works() -> StdResult<()> {
         #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -300,4 +527,99 @@ mod tests {
 
         Ok(())
     }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum SwapHandle {
+        Swap { amount: u64, min_return: u64 },
+    }
+
+    impl HandleCallback for SwapHandle {
+        const BLOCK_SIZE: usize = 256;
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum SwapQuery {
+        SimulateSwap { amount: u64 },
+    }
+
+    impl Query for SwapQuery {
+        const BLOCK_SIZE: usize = 256;
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct SimulationResponse {
+        return_amount: u64,
+    }
+
+    impl Simulate for SwapHandle {
+        type Query = SwapQuery;
+        type Response = SimulationResponse;
+
+        fn simulation_query(&self) -> SwapQuery {
+            let SwapHandle::Swap { amount, .. } = self;
+            SwapQuery::SimulateSwap { amount: *amount }
+        }
+
+        fn check_simulation(&self, response: &SimulationResponse) -> StdResult<()> {
+            let SwapHandle::Swap { min_return, .. } = self;
+            if response.return_amount < *min_return {
+                return Err(StdError::generic_err("return below the minimum"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_simulate_or_fail() -> StdResult<()> {
+        // simulates a pool returning half of the amount swapped, and failing above 1000
+        struct PoolQuerier {}
+
+        impl Querier for PoolQuerier {
+            fn raw_query(&self, request: &[u8]) -> QuerierResult {
+                let request: QueryRequest<Empty> = cosmwasm_std::from_slice(request).unwrap();
+                let QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) = request else {
+                    return SystemResult::Err(SystemError::Unknown {});
+                };
+                let msg = String::from_utf8(msg.0).unwrap();
+                let amount: u64 = msg
+                    .trim()
+                    .trim_start_matches(r#"{"simulate_swap":{"amount":"#)
+                    .trim_end_matches("}}")
+                    .parse()
+                    .unwrap();
+                if amount > 1000 {
+                    return SystemResult::Ok(ContractResult::Err("insufficient liquidity".into()));
+                }
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&SimulationResponse {
+                        return_amount: amount / 2,
+                    })
+                    .unwrap(),
+                ))
+            }
+        }
+
+        let querier = QuerierWrapper::<Empty>::new(&PoolQuerier {});
+        let simulate = |amount, min_return| {
+            simulate_or_fail(
+                querier,
+                &SwapHandle::Swap { amount, min_return },
+                "hash".to_string(),
+                "pool".to_string(),
+            )
+        };
+
+        assert_eq!(simulate(100, 50)?, SimulationResponse { return_amount: 50 });
+        assert_eq!(
+            simulate(100, 51).unwrap_err(),
+            StdError::generic_err("return below the minimum")
+        );
+        let err = simulate(2000, 0).unwrap_err().to_string();
+        assert!(err.starts_with("Generic error: Error performing simulation query on pool:"));
+        assert!(err.contains("insufficient liquidity"));
+
+        Ok(())
+    }
 }
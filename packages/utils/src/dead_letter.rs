@@ -0,0 +1,313 @@
+use cosmwasm_std::{
+    to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage,
+};
+use cosmwasm_storage::{Bucket, ReadonlyBucket};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use secret_toolkit_serialization::Json;
+use secret_toolkit_storage::{Item, Keymap};
+
+const PREFIX_LETTERS: &[u8] = b"letters";
+const PREFIX_NEXT_ID: &[u8] = b"next_id";
+const PREFIX_ADMINS: &[u8] = b"admins";
+
+/// This is the default implementation of the dead letter queue, using the "dead_letters"
+/// storage prefix.
+///
+/// You can use another storage location by implementing `DeadLetterQueueTrait` for your own type.
+pub struct DeadLetterQueue;
+
+impl DeadLetterQueueTrait for DeadLetterQueue {
+    const STORAGE_KEY: &'static [u8] = b"dead_letters";
+}
+
+/// A message that failed on the receiving end and was parked for later inspection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DeadLetter {
+    pub id: u64,
+    /// the message that failed
+    pub msg: CosmosMsg,
+    /// the error reported by the reply
+    pub error: String,
+    /// the block height at which the failure was recorded
+    pub block_height: u64,
+}
+
+/// A trait describing the interface of a dead letter queue.
+///
+/// A `reply` handler pushes the payload of a failed sub-message into the queue, and the
+/// queue's admins can later either retry or discard it. Retrying removes the letter and
+/// re-dispatches its message as a regular message, so if it fails again the whole retry
+/// transaction is reverted and the letter stays in the queue.
+pub trait DeadLetterQueueTrait {
+    const STORAGE_KEY: &'static [u8];
+
+    fn letters() -> Keymap<'static, u64, DeadLetter, Json> {
+        Keymap::new(Self::STORAGE_KEY).add_suffix(PREFIX_LETTERS)
+    }
+
+    fn next_id() -> Item<'static, u64> {
+        Item::new(Self::STORAGE_KEY).add_suffix(PREFIX_NEXT_ID)
+    }
+
+    fn init(storage: &mut dyn Storage, admins: Vec<Addr>) -> StdResult<()> {
+        for a in admins {
+            Self::set_admin(storage, &a)?;
+        }
+
+        Ok(())
+    }
+
+    /// Park a failed message in the queue and return the id it was stored under
+    fn push(storage: &mut dyn Storage, env: &Env, msg: CosmosMsg, error: String) -> StdResult<u64> {
+        let id = Self::next_id().may_load(storage)?.unwrap_or_default();
+        Self::next_id().save(storage, &(id + 1))?;
+
+        let letter = DeadLetter {
+            id,
+            msg,
+            error,
+            block_height: env.block.height,
+        };
+        Self::letters().insert(storage, &id, &letter)?;
+
+        Ok(id)
+    }
+
+    fn get(storage: &dyn Storage, id: u64) -> Option<DeadLetter> {
+        Self::letters().get(storage, &id)
+    }
+
+    fn len(storage: &dyn Storage) -> StdResult<u32> {
+        Self::letters().get_len(storage)
+    }
+
+    /// Remove a letter from the queue and return it
+    fn take(storage: &mut dyn Storage, id: u64) -> StdResult<DeadLetter> {
+        let letters = Self::letters();
+        let letter = letters
+            .get(storage, &id)
+            .ok_or_else(|| StdError::generic_err(format!("dead letter {id} not found")))?;
+        letters.remove(storage, &id)?;
+
+        Ok(letter)
+    }
+
+    fn is_admin(storage: &dyn Storage, key: &Addr) -> StdResult<bool> {
+        let admin_store: ReadonlyBucket<bool> =
+            ReadonlyBucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_ADMINS]);
+        admin_store.may_load(key.as_bytes()).map(|a| a.is_some())
+    }
+
+    fn set_admin(storage: &mut dyn Storage, key: &Addr) -> StdResult<()> {
+        let mut admin_store = Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_ADMINS]);
+        admin_store.save(key.as_bytes(), &true /* value is insignificant */)
+    }
+
+    fn remove_admin(storage: &mut dyn Storage, key: &Addr) {
+        let mut admin_store: Bucket<bool> =
+            Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_ADMINS]);
+        admin_store.remove(key.as_bytes())
+    }
+
+    fn handle_retry(deps: DepsMut, info: &MessageInfo, id: u64) -> StdResult<Response> {
+        if !Self::is_admin(deps.storage, &info.sender)? {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+
+        let letter = Self::take(deps.storage, id)?;
+
+        Ok(Response::new().add_message(letter.msg).set_data(to_binary(
+            &DeadLetterHandleAnswer::Retry {
+                status: ResponseStatus::Success,
+            },
+        )?))
+    }
+
+    fn handle_discard(deps: DepsMut, info: &MessageInfo, id: u64) -> StdResult<Response> {
+        if !Self::is_admin(deps.storage, &info.sender)? {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+
+        Self::take(deps.storage, id)?;
+
+        Ok(
+            Response::new().set_data(to_binary(&DeadLetterHandleAnswer::Discard {
+                status: ResponseStatus::Success,
+            })?),
+        )
+    }
+
+    fn query_dead_letters(deps: Deps, page: u32, page_size: u32) -> StdResult<Binary> {
+        let letters = Self::letters();
+        let total = letters.get_len(deps.storage)?;
+        let letters = letters
+            .paging(deps.storage, page, page_size)?
+            .into_iter()
+            .map(|(_, letter)| letter)
+            .collect();
+
+        to_binary(&DeadLetterQueryAnswer::DeadLetters { letters, total })
+    }
+
+    fn query_dead_letter(deps: Deps, id: u64) -> StdResult<Binary> {
+        let letter = Self::get(deps.storage, id)
+            .ok_or_else(|| StdError::generic_err(format!("dead letter {id} not found")))?;
+
+        to_binary(&DeadLetterQueryAnswer::DeadLetter { letter })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterHandleMsg {
+    Retry { id: u64 },
+    Discard { id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterQueryMsg {
+    DeadLetters { page: Option<u32>, page_size: u32 },
+    DeadLetter { id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterHandleAnswer {
+    Retry { status: ResponseStatus },
+    Discard { status: ResponseStatus },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterQueryAnswer {
+    DeadLetters {
+        letters: Vec<DeadLetter>,
+        total: u32,
+    },
+    DeadLetter {
+        letter: DeadLetter,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_binary, BankMsg};
+
+    fn bank_msg(amount: u128) -> CosmosMsg {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "bob".to_string(),
+            amount: coins(amount, "uscrt"),
+        })
+    }
+
+    #[test]
+    fn test_push_and_take() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let id0 = DeadLetterQueue::push(&mut deps.storage, &env, bank_msg(1), "err0".to_string())?;
+        let id1 = DeadLetterQueue::push(&mut deps.storage, &env, bank_msg(2), "err1".to_string())?;
+        assert_eq!((id0, id1), (0, 1));
+        assert_eq!(DeadLetterQueue::len(&deps.storage)?, 2);
+
+        let letter = DeadLetterQueue::take(&mut deps.storage, id0)?;
+        assert_eq!(letter.msg, bank_msg(1));
+        assert_eq!(letter.error, "err0");
+        assert_eq!(letter.block_height, env.block.height);
+        assert_eq!(DeadLetterQueue::len(&deps.storage)?, 1);
+        assert!(DeadLetterQueue::take(&mut deps.storage, id0).is_err());
+
+        // ids are never reused
+        let id2 = DeadLetterQueue::push(&mut deps.storage, &env, bank_msg(3), "err2".to_string())?;
+        assert_eq!(id2, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_retry() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        DeadLetterQueue::init(&mut deps.storage, vec![Addr::unchecked("admin")])?;
+        let id = DeadLetterQueue::push(&mut deps.storage, &env, bank_msg(1), "err".to_string())?;
+
+        let info = mock_info("non-admin", &[]);
+        let error = DeadLetterQueue::handle_retry(deps.as_mut(), &info, id);
+        assert_eq!(error, Err(StdError::generic_err("unauthorized")));
+
+        let info = mock_info("admin", &[]);
+        let response = DeadLetterQueue::handle_retry(deps.as_mut(), &info, id)?;
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(response.messages[0].msg, bank_msg(1));
+        let answer: DeadLetterHandleAnswer = from_binary(&response.data.unwrap())?;
+        assert_eq!(
+            answer,
+            DeadLetterHandleAnswer::Retry {
+                status: ResponseStatus::Success
+            }
+        );
+        assert_eq!(DeadLetterQueue::get(&deps.storage, id), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_discard() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        DeadLetterQueue::init(&mut deps.storage, vec![Addr::unchecked("admin")])?;
+        let id = DeadLetterQueue::push(&mut deps.storage, &env, bank_msg(1), "err".to_string())?;
+
+        let info = mock_info("non-admin", &[]);
+        let error = DeadLetterQueue::handle_discard(deps.as_mut(), &info, id);
+        assert_eq!(error, Err(StdError::generic_err("unauthorized")));
+
+        let info = mock_info("admin", &[]);
+        let response = DeadLetterQueue::handle_discard(deps.as_mut(), &info, id)?;
+        assert!(response.messages.is_empty());
+        assert_eq!(DeadLetterQueue::len(&deps.storage)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_dead_letters() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        for i in 0..5 {
+            DeadLetterQueue::push(&mut deps.storage, &env, bank_msg(i), format!("err{i}"))?;
+        }
+
+        let answer: DeadLetterQueryAnswer =
+            from_binary(&DeadLetterQueue::query_dead_letters(deps.as_ref(), 1, 2)?)?;
+        match answer {
+            DeadLetterQueryAnswer::DeadLetters { letters, total } => {
+                assert_eq!(total, 5);
+                assert_eq!(letters.iter().map(|l| l.id).collect::<Vec<_>>(), vec![2, 3]);
+            }
+            other => panic!("unexpected answer: {:?}", other),
+        }
+
+        let answer: DeadLetterQueryAnswer =
+            from_binary(&DeadLetterQueue::query_dead_letter(deps.as_ref(), 4)?)?;
+        match answer {
+            DeadLetterQueryAnswer::DeadLetter { letter } => assert_eq!(letter.error, "err4"),
+            other => panic!("unexpected answer: {:?}", other),
+        }
+
+        Ok(())
+    }
+}
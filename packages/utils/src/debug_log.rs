@@ -0,0 +1,214 @@
+//! Debug logs that only the developer of a contract can read.
+//!
+//! Contracts run in an enclave, so the only way to trace what they do on a testnet is to return
+//! it in their responses, where it would be visible to the sender of the transaction. With the
+//! `debug-log` feature, in builds with debug assertions, the lines logged with [`debug_log!`]
+//! are instead encrypted with a key held by the developer, and added to the response as a single
+//! `debug_log` attribute, which [`decrypt_debug_log`] decrypts. In other builds, [`debug_log!`]
+//! doesn't even format its arguments, and no attribute is added.
+
+use cosmwasm_std::{Env, Response, StdResult};
+
+#[cfg(feature = "debug-log")]
+use cosmwasm_std::{from_slice, to_vec, Binary, StdError};
+#[cfg(feature = "debug-log")]
+use secret_toolkit_notification::{cipher_data, decipher_data};
+#[cfg(feature = "debug-log")]
+use sha2::{Digest, Sha256};
+
+/// The key of the attribute holding the encrypted log
+pub const DEBUG_LOG_KEY: &str = "debug_log";
+
+/// Whether the logged lines are added to the response, i.e. with the `debug-log` feature in
+/// builds with debug assertions
+pub const ENABLED: bool = cfg!(all(feature = "debug-log", debug_assertions));
+
+#[cfg(feature = "debug-log")]
+const NONCE_LEN: usize = 12;
+#[cfg(feature = "debug-log")]
+const DEBUG_LOG_AAD: &[u8] = b"secret-toolkit-debug-log";
+
+/// Appends a line to a [`DebugLog`], formatted like `format!`, if logging is [`ENABLED`]
+///
+/// ```
+/// # use cosmwasm_std::testing::mock_env;
+/// use secret_toolkit_utils::{debug_log, debug_log::DebugLog};
+///
+/// # let env = mock_env();
+/// let mut log = DebugLog::new([7; 32], &env);
+/// let amount = 5;
+/// debug_log!(log, "withdrawing {amount}");
+/// ```
+#[macro_export]
+macro_rules! debug_log {
+    ($log:expr, $($arg:tt)+) => {
+        if $crate::debug_log::ENABLED {
+            $log.log(format!($($arg)+));
+        }
+    };
+}
+
+/// The lines logged while handling a message, to be added to its response encrypted
+pub struct DebugLog {
+    #[cfg_attr(not(feature = "debug-log"), allow(dead_code))]
+    key: [u8; 32],
+    #[cfg_attr(not(feature = "debug-log"), allow(dead_code))]
+    tx_seed: [u8; 32],
+    lines: Vec<String>,
+}
+
+impl DebugLog {
+    /// Creates an empty log, encrypted with the developer `key`, e.g. stored in the config of
+    /// the contract at its instantiation. The nonce of the encryption is derived from the key,
+    /// the block and transaction in `env`, and the lines, so it is only reused for the same lines
+    /// logged in the same transaction.
+    pub fn new(key: [u8; 32], env: &Env) -> Self {
+        Self {
+            key,
+            tx_seed: tx_seed(env),
+            lines: vec![],
+        }
+    }
+
+    /// Appends a line to the log. Use [`debug_log!`] instead, which skips the formatting when
+    /// logging isn't enabled.
+    pub fn log(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    /// Returns the lines logged so far
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Returns the response with the encrypted log added as the `debug_log` attribute, if
+    /// logging is [`ENABLED`] and any line was logged, and otherwise the response as is
+    pub fn add_to_response<T>(self, response: Response<T>) -> StdResult<Response<T>> {
+        #[cfg(all(feature = "debug-log", debug_assertions))]
+        if !self.lines.is_empty() {
+            return Ok(response.add_attribute(DEBUG_LOG_KEY, self.encrypt()?));
+        }
+        Ok(response)
+    }
+
+    /// Returns the lines encrypted with the developer key, as base64
+    #[cfg(feature = "debug-log")]
+    pub fn encrypt(&self) -> StdResult<String> {
+        let plaintext = to_vec(&self.lines)?;
+        // the nonce is derived from the secret key, so it reveals nothing about the lines, and
+        // from the lines, so that several logs of the same transaction get different nonces
+        let digest = Sha256::new()
+            .chain_update(self.key)
+            .chain_update(self.tx_seed)
+            .chain_update(&plaintext)
+            .finalize();
+        let nonce = &digest[..NONCE_LEN];
+        let ciphertext = cipher_data(&self.key, nonce, &plaintext, DEBUG_LOG_AAD)?;
+        Ok(Binary::from([nonce, &ciphertext].concat()).to_base64())
+    }
+}
+
+/// Returns a seed unique to the transaction, from which nonces are derived
+#[cfg(feature = "debug-log")]
+fn tx_seed(env: &Env) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    if let Some(random) = &env.block.random {
+        hasher.update(random.as_slice());
+    }
+    hasher.update(env.block.height.to_be_bytes());
+    hasher.update(env.block.time.nanos().to_be_bytes());
+    let tx_index = env.transaction.as_ref().map_or(u32::MAX, |tx| tx.index);
+    hasher.update(tx_index.to_be_bytes());
+    hasher.finalize().into()
+}
+
+#[cfg(not(feature = "debug-log"))]
+fn tx_seed(_env: &Env) -> [u8; 32] {
+    [0u8; 32]
+}
+
+/// Returns the lines of the `debug_log` attribute of a response, decrypted with the developer
+/// `key`
+#[cfg(feature = "debug-log")]
+pub fn decrypt_debug_log(key: &[u8; 32], value: &str) -> StdResult<Vec<String>> {
+    let data = Binary::from_base64(value)?;
+    if data.len() < NONCE_LEN {
+        return Err(StdError::generic_err("debug log is too short"));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    from_slice(&decipher_data(key, nonce, ciphertext, DEBUG_LOG_AAD)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{testing::mock_env, Empty};
+
+    #[test]
+    fn test_debug_log() -> StdResult<()> {
+        let mut log = DebugLog::new([7; 32], &mock_env());
+        let amount = 5;
+        debug_log!(log, "withdrawing {amount}");
+        debug_log!(log, "balance: {}", 10 - amount);
+
+        let response = log.add_to_response(Response::<Empty>::new())?;
+        if !ENABLED {
+            assert!(response.attributes.is_empty());
+            return Ok(());
+        }
+
+        #[cfg(feature = "debug-log")]
+        {
+            assert_eq!(response.attributes.len(), 1);
+            let attribute = &response.attributes[0];
+            assert_eq!(attribute.key, DEBUG_LOG_KEY);
+            assert!(!attribute.value.contains("withdrawing"));
+            assert_eq!(
+                decrypt_debug_log(&[7; 32], &attribute.value)?,
+                vec!["withdrawing 5".to_string(), "balance: 5".to_string()]
+            );
+            assert!(decrypt_debug_log(&[8; 32], &attribute.value).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "debug-log")]
+    #[test]
+    fn test_debug_log_nonces() -> StdResult<()> {
+        let env = mock_env();
+        let nonce = |lines: &[&str]| -> StdResult<Vec<u8>> {
+            let mut log = DebugLog::new([7; 32], &env);
+            for line in lines {
+                log.log(line.to_string());
+            }
+            Ok(Binary::from_base64(&log.encrypt()?)?[..NONCE_LEN].to_vec())
+        };
+
+        // two logs of the same transaction, e.g. of two submessages, don't share a nonce
+        assert_ne!(nonce(&["withdrawing 5"])?, nonce(&["withdrawing 6"])?);
+        assert_ne!(
+            nonce(&["withdrawing 5"])?,
+            nonce(&["withdrawing 5", "done"])?
+        );
+        assert_eq!(nonce(&["withdrawing 5"])?, nonce(&["withdrawing 5"])?);
+
+        // nor do the same lines logged in another block
+        let mut other_env = mock_env();
+        other_env.block.height += 1;
+        let mut log = DebugLog::new([7; 32], &other_env);
+        log.log("withdrawing 5".to_string());
+        let other = Binary::from_base64(&log.encrypt()?)?[..NONCE_LEN].to_vec();
+        assert_ne!(nonce(&["withdrawing 5"])?, other);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_debug_log() -> StdResult<()> {
+        let log = DebugLog::new([7; 32], &mock_env());
+        let response = log.add_to_response(Response::<Empty>::new())?;
+        assert!(response.attributes.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+//! IBC denominations, and a registry of the denominations a contract accepts under
+//! human-readable aliases.
+//!
+//! An IBC voucher has a denomination of the form `ibc/<hash>`, where the hash is the SHA-256 of
+//! its trace, e.g. `transfer/channel-0/uatom`. The same base denomination received through
+//! another channel is another token, so a contract must check the whole trace of the funds it
+//! accepts, and not only their base denomination.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{Coin, StdError, StdResult, Storage, Uint128};
+
+use secret_toolkit_storage::Keymap;
+
+/// The prefix of the denominations of IBC vouchers
+pub const IBC_PREFIX: &str = "ibc/";
+
+const ALIASES: &[u8] = b"aliases";
+const DENOMS: &[u8] = b"denoms";
+
+/// The trace of a denomination, as in ICS-20: the ports and channels it was transferred
+/// through, and its denomination on its source chain
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct DenomTrace {
+    /// e.g. `transfer/channel-0`, or empty for a native denomination
+    pub path: String,
+    /// e.g. `uatom`
+    pub base_denom: String,
+}
+
+impl DenomTrace {
+    /// Returns the trace of a native denomination
+    pub fn native(denom: impl Into<String>) -> Self {
+        Self {
+            path: String::new(),
+            base_denom: denom.into(),
+        }
+    }
+
+    /// Parses a full trace, e.g. `transfer/channel-0/uatom`, where the path is made of the
+    /// leading pairs of a port and a `channel-<n>` channel, and the base denomination is the
+    /// rest, which can contain slashes, e.g. `transfer/channel-1/gamm/pool/1`.
+    pub fn parse(full_path: &str) -> StdResult<Self> {
+        let parts: Vec<&str> = full_path.split('/').collect();
+        let mut path_len = 0;
+        while path_len + 2 < parts.len() && is_channel_id(parts[path_len + 1]) {
+            if parts[path_len].is_empty() {
+                break;
+            }
+            path_len += 2;
+        }
+        let base_denom = parts[path_len..].join("/");
+        if base_denom.is_empty() || base_denom.starts_with(IBC_PREFIX) {
+            return Err(StdError::generic_err(format!(
+                "invalid denom trace {full_path:?}"
+            )));
+        }
+        Ok(Self {
+            path: parts[..path_len].join("/"),
+            base_denom,
+        })
+    }
+
+    /// Returns the full trace, e.g. `transfer/channel-0/uatom`
+    pub fn full_path(&self) -> String {
+        if self.path.is_empty() {
+            self.base_denom.clone()
+        } else {
+            format!("{}/{}", self.path, self.base_denom)
+        }
+    }
+
+    /// Returns whether the trace is that of a native denomination
+    pub fn is_native(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Returns the denomination of the funds with this trace on this chain, which is
+    /// `ibc/<hash>` for vouchers, and the base denomination for native funds
+    pub fn denom(&self) -> String {
+        if self.is_native() {
+            return self.base_denom.clone();
+        }
+        let hash = Sha256::digest(self.full_path().as_bytes());
+        let hex: String = hash.iter().map(|byte| format!("{byte:02X}")).collect();
+        format!("{IBC_PREFIX}{hex}")
+    }
+}
+
+fn is_channel_id(id: &str) -> bool {
+    id.strip_prefix("channel-")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Returns the hash of an `ibc/<hash>` denomination, or `None` if the denomination isn't that
+/// of an IBC voucher. Fails if the hash isn't 64 uppercase hexadecimal digits, like those the
+/// transfer module creates.
+pub fn parse_ibc_denom(denom: &str) -> StdResult<Option<[u8; 32]>> {
+    let hex = match denom.strip_prefix(IBC_PREFIX) {
+        Some(hex) => hex,
+        None => return Ok(None),
+    };
+    let invalid = || StdError::generic_err(format!("invalid IBC denom {denom:?}"));
+    if hex.len() != 64 || !hex.chars().all(|c| matches!(c, '0'..='9' | 'A'..='F')) {
+        return Err(invalid());
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(Some(hash))
+}
+
+/// Returns the amount of the funds sent with a message, which must be a single coin with the
+/// denomination of `expected`
+pub fn must_pay(funds: &[Coin], expected: &DenomTrace) -> StdResult<Uint128> {
+    let denom = expected.denom();
+    match funds {
+        [coin] if coin.denom == denom && !coin.amount.is_zero() => Ok(coin.amount),
+        [] => Err(StdError::generic_err(format!(
+            "expected funds of {}",
+            expected.full_path()
+        ))),
+        _ => Err(StdError::generic_err(format!(
+            "expected only funds of {} ({denom}), got {}",
+            expected.full_path(),
+            funds
+                .iter()
+                .map(|coin| coin.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// The denominations a contract accepts, under human-readable aliases, e.g. `ATOM` for
+/// `transfer/channel-0/uatom`
+pub struct DenomRegistry<'a> {
+    namespace: &'a [u8],
+}
+
+impl<'a> DenomRegistry<'a> {
+    /// constructor
+    pub const fn new(namespace: &'a [u8]) -> Self {
+        Self { namespace }
+    }
+
+    /// the trace of each alias
+    fn aliases(&self) -> Keymap<'a, String, DenomTrace> {
+        Keymap::new(self.namespace).add_suffix(ALIASES)
+    }
+
+    /// the alias of each denomination
+    fn denoms(&self) -> Keymap<'a, String, String> {
+        Keymap::new(self.namespace).add_suffix(DENOMS)
+    }
+
+    /// Registers a denomination under an alias. Fails if the alias or the denomination is
+    /// already registered.
+    pub fn register(
+        &self,
+        storage: &mut dyn Storage,
+        alias: &str,
+        trace: &DenomTrace,
+    ) -> StdResult<()> {
+        let alias = alias.to_string();
+        let denom = trace.denom();
+        if self.aliases().contains(storage, &alias) {
+            return Err(StdError::generic_err(format!(
+                "denom alias {alias} is already registered"
+            )));
+        }
+        if let Some(other) = self.denoms().get(storage, &denom) {
+            return Err(StdError::generic_err(format!(
+                "denom {} is already registered as {other}",
+                trace.full_path()
+            )));
+        }
+        self.aliases().insert(storage, &alias, trace)?;
+        self.denoms().insert(storage, &denom, &alias)
+    }
+
+    /// Removes an alias and its denomination
+    pub fn unregister(&self, storage: &mut dyn Storage, alias: &str) -> StdResult<()> {
+        let trace = self.trace(storage, alias)?;
+        self.aliases().remove(storage, &alias.to_string())?;
+        self.denoms().remove(storage, &trace.denom())
+    }
+
+    /// Returns the trace of the denomination registered under `alias`
+    pub fn trace(&self, storage: &dyn Storage, alias: &str) -> StdResult<DenomTrace> {
+        self.aliases()
+            .get(storage, &alias.to_string())
+            .ok_or_else(|| StdError::generic_err(format!("unknown denom alias {alias}")))
+    }
+
+    /// Returns the denomination on this chain of the funds registered under `alias`
+    pub fn denom(&self, storage: &dyn Storage, alias: &str) -> StdResult<String> {
+        Ok(self.trace(storage, alias)?.denom())
+    }
+
+    /// Returns the alias of a denomination on this chain, if it is registered
+    pub fn alias_of(&self, storage: &dyn Storage, denom: &str) -> Option<String> {
+        self.denoms().get(storage, &denom.to_string())
+    }
+
+    /// Returns the amount of the funds sent with a message, which must be a single coin of the
+    /// denomination registered under `alias`
+    pub fn must_pay(
+        &self,
+        storage: &dyn Storage,
+        funds: &[Coin],
+        alias: &str,
+    ) -> StdResult<Uint128> {
+        must_pay(funds, &self.trace(storage, alias)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{coin, testing::MockStorage};
+
+    const ATOM_ON_OSMOSIS: &str =
+        "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    #[test]
+    fn test_denom_trace() -> StdResult<()> {
+        let atom = DenomTrace::parse("transfer/channel-0/uatom")?;
+        assert_eq!(atom.path, "transfer/channel-0");
+        assert_eq!(atom.base_denom, "uatom");
+        assert_eq!(atom.denom(), ATOM_ON_OSMOSIS);
+
+        let pool = DenomTrace::parse("transfer/channel-1/transfer/channel-2/gamm/pool/1")?;
+        assert_eq!(pool.path, "transfer/channel-1/transfer/channel-2");
+        assert_eq!(pool.base_denom, "gamm/pool/1");
+        assert_eq!(
+            pool.full_path(),
+            "transfer/channel-1/transfer/channel-2/gamm/pool/1"
+        );
+
+        let native = DenomTrace::parse("uscrt")?;
+        assert!(native.is_native());
+        assert_eq!(native, DenomTrace::native("uscrt"));
+        assert_eq!(native.denom(), "uscrt");
+        assert_eq!(DenomTrace::parse("transfer/channel-x/uatom")?.path, "");
+        assert!(DenomTrace::parse("transfer/channel-0/").is_err());
+        assert!(DenomTrace::parse(ATOM_ON_OSMOSIS).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ibc_denom() -> StdResult<()> {
+        let hash = parse_ibc_denom(ATOM_ON_OSMOSIS)?.unwrap();
+        assert_eq!(hash[..2], [0x27, 0x39]);
+        assert_eq!(parse_ibc_denom("uscrt")?, None);
+        assert!(parse_ibc_denom(&ATOM_ON_OSMOSIS.to_lowercase().replace("IBC", "ibc")).is_err());
+        assert!(parse_ibc_denom("ibc/27394FB0").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let registry = DenomRegistry::new(b"denoms");
+        let atom = DenomTrace::parse("transfer/channel-0/uatom")?;
+        registry.register(&mut storage, "ATOM", &atom)?;
+        assert!(registry.register(&mut storage, "ATOM", &atom).is_err());
+        assert!(registry.register(&mut storage, "ATOM2", &atom).is_err());
+
+        assert_eq!(registry.denom(&storage, "ATOM")?, ATOM_ON_OSMOSIS);
+        assert_eq!(
+            registry.alias_of(&storage, ATOM_ON_OSMOSIS),
+            Some("ATOM".to_string())
+        );
+        assert_eq!(
+            registry.must_pay(&storage, &[coin(5, ATOM_ON_OSMOSIS)], "ATOM")?,
+            Uint128::new(5)
+        );
+
+        // the same base denom through another channel is another token
+        let spoofed = DenomTrace::parse("transfer/channel-9/uatom")?.denom();
+        assert!(registry
+            .must_pay(&storage, &[coin(5, &spoofed)], "ATOM")
+            .is_err());
+        assert!(registry
+            .must_pay(&storage, &[coin(5, "uatom")], "ATOM")
+            .is_err());
+        assert!(registry.must_pay(&storage, &[], "ATOM").is_err());
+        assert!(registry
+            .must_pay(
+                &storage,
+                &[coin(5, ATOM_ON_OSMOSIS), coin(1, "uscrt")],
+                "ATOM"
+            )
+            .is_err());
+        assert!(registry
+            .must_pay(&storage, &[coin(5, "uscrt")], "SCRT")
+            .is_err());
+
+        registry.unregister(&mut storage, "ATOM")?;
+        assert!(registry.trace(&storage, "ATOM").is_err());
+        assert_eq!(registry.alias_of(&storage, ATOM_ON_OSMOSIS), None);
+        registry.register(&mut storage, "ATOM", &atom)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use cosmwasm_std::{from_binary, to_binary, Binary, StdError, StdResult};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// The version of the envelope format produced by `build_receive_envelope`
+pub const ENVELOPE_VERSION: u32 = 1;
+
+/// A typed envelope for the `msg` field of SNIP-20 `Send` and SNIP-721 `SendNft` messages,
+/// which is forwarded to the recipient's `Receive`/`ReceiveNft` handler.
+///
+/// `A` is usually an enum of the actions the recipient supports, e.g. swapping or depositing
+/// the received tokens.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct ReceiveEnvelope<A> {
+    pub version: u32,
+    pub action: A,
+}
+
+/// Returns StdResult<Binary> holding the envelope of `action`, to be used as the `msg` of a
+/// `Send`/`SendNft` message
+///
+/// # Arguments
+///
+/// * `action` - the action the recipient should take with the received tokens
+pub fn build_receive_envelope<A: Serialize>(action: A) -> StdResult<Binary> {
+    to_binary(&ReceiveEnvelope {
+        version: ENVELOPE_VERSION,
+        action,
+    })
+}
+
+/// Parses the `msg` received by a `Receive`/`ReceiveNft` handler and returns the action it holds
+///
+/// # Arguments
+///
+/// * `msg` - the optional msg forwarded by the token contract
+pub fn parse_receive_envelope<A: DeserializeOwned>(msg: Option<&Binary>) -> StdResult<A> {
+    let msg = msg.ok_or_else(|| StdError::generic_err("Receive msg is missing"))?;
+    let envelope: ReceiveEnvelope<A> = from_binary(msg)?;
+    if envelope.version != ENVELOPE_VERSION {
+        return Err(StdError::generic_err(format!(
+            "Unsupported receive envelope version {}, expected {}",
+            envelope.version, ENVELOPE_VERSION
+        )));
+    }
+
+    Ok(envelope.action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    enum Action {
+        Swap { min_out: u64 },
+        Deposit {},
+    }
+
+    #[test]
+    fn test_envelope_round_trip() -> StdResult<()> {
+        let msg = build_receive_envelope(Action::Swap { min_out: 10 })?;
+        assert_eq!(
+            msg,
+            Binary::from(br#"{"version":1,"action":{"swap":{"min_out":10}}}"#.as_slice())
+        );
+        assert_eq!(
+            parse_receive_envelope::<Action>(Some(&msg))?,
+            Action::Swap { min_out: 10 }
+        );
+
+        let msg = build_receive_envelope(Action::Deposit {})?;
+        assert_eq!(
+            parse_receive_envelope::<Action>(Some(&msg))?,
+            Action::Deposit {}
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_envelopes() {
+        assert_eq!(
+            parse_receive_envelope::<Action>(None),
+            Err(StdError::generic_err("Receive msg is missing"))
+        );
+
+        let msg = Binary::from(br#"{"version":2,"action":{"deposit":{}}}"#.as_slice());
+        assert_eq!(
+            parse_receive_envelope::<Action>(Some(&msg)),
+            Err(StdError::generic_err(
+                "Unsupported receive envelope version 2, expected 1"
+            ))
+        );
+
+        let msg = Binary::from(br#"{"swap":{"min_out":10}}"#.as_slice());
+        assert!(parse_receive_envelope::<Action>(Some(&msg)).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Numbered error codes, and error messages padded to a uniform length.
+//!
+//! Errors built here have messages of the form `E0042: insufficient funds`, so that clients can
+//! match on the code instead of the wording. As the length of an error message can leak why a
+//! transaction failed, e.g. whether an address had a balance, [`pad_error`] pads messages to a
+//! fixed size before they are returned.
+
+use std::fmt::Display;
+
+use cosmwasm_std::{StdError, StdResult};
+
+/// The default size that error messages are padded to.
+pub const ERROR_MESSAGE_SIZE: usize = 256;
+
+/// An error with a numbered code. Implemented by the enums declared with [`error_codes!`].
+///
+/// [`error_codes!`]: crate::error_codes
+pub trait ErrorCode {
+    /// The code of the error, at most 9999 to keep the codes of the same length.
+    fn code(&self) -> u16;
+
+    /// A description of the error, without the code.
+    fn message(&self) -> &'static str;
+
+    /// Returns the error as a `StdError`.
+    fn err(&self) -> StdError {
+        coded_err(self.code(), self.message())
+    }
+
+    /// Returns the error as a `StdError`, with details appended to its message.
+    fn err_with(&self, details: impl Display) -> StdError {
+        coded_err(self.code(), format!("{}: {details}", self.message()))
+    }
+}
+
+/// Returns a generic error with the message `E<code>: <message>`, the code being padded to four
+/// digits.
+pub fn coded_err(code: u16, message: impl Display) -> StdError {
+    StdError::generic_err(format!("E{code:04}: {message}"))
+}
+
+/// Returns the code of an error built with [`coded_err`], or `None` if it has no code.
+pub fn error_code(err: &StdError) -> Option<u16> {
+    let StdError::GenericErr { msg, .. } = err else {
+        return None;
+    };
+    let code = msg.strip_prefix('E')?.split(": ").next()?;
+    if code.len() != 4 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    code.parse().ok()
+}
+
+/// Pads the message of an error with spaces up to `size` bytes, and truncates longer messages,
+/// so that every error returned has the same length. Other errors than generic errors are
+/// turned into generic errors, as their kind would also be visible.
+pub fn pad_error(err: StdError, size: usize) -> StdError {
+    let mut msg = match err {
+        StdError::GenericErr { msg, .. } => msg,
+        err => err.to_string(),
+    };
+    if msg.len() > size {
+        let mut end = size;
+        while !msg.is_char_boundary(end) {
+            end -= 1;
+        }
+        msg.truncate(end);
+    }
+    let padding = size - msg.len();
+    msg.extend(std::iter::repeat_n(' ', padding));
+    StdError::generic_err(msg)
+}
+
+/// Pads the error of a result with [`pad_error`].
+pub fn pad_error_result<T>(result: StdResult<T>, size: usize) -> StdResult<T> {
+    result.map_err(|err| pad_error(err, size))
+}
+
+/// Declares an enum of numbered errors, implementing [`ErrorCode`] and converting into
+/// `StdError`.
+///
+/// ```
+/// # use cosmwasm_std::StdError;
+/// use secret_toolkit_utils::error_codes;
+/// use secret_toolkit_utils::errors::ErrorCode;
+///
+/// error_codes! {
+///     pub enum ContractError {
+///         Unauthorized = 1 => "unauthorized",
+///         InsufficientFunds = 2 => "insufficient funds",
+///     }
+/// }
+///
+/// let err: StdError = ContractError::InsufficientFunds.into();
+/// assert_eq!(err, StdError::generic_err("E0002: insufficient funds"));
+/// ```
+///
+/// [`ErrorCode`]: crate::errors::ErrorCode
+#[macro_export]
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $code:literal => $message:literal),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(u16)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant = $code),+
+        }
+
+        impl $crate::errors::ErrorCode for $name {
+            fn code(&self) -> u16 {
+                const _: () = assert!($($code <= 9999)&&+, "error codes must be at most 9999");
+                *self as u16
+            }
+
+            fn message(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $message),+
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                use $crate::errors::ErrorCode;
+                write!(f, "E{:04}: {}", self.code(), self.message())
+            }
+        }
+
+        impl From<$name> for ::cosmwasm_std::StdError {
+            fn from(err: $name) -> Self {
+                $crate::errors::ErrorCode::err(&err)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    error_codes! {
+        enum TestError {
+            /// the sender may not do this
+            Unauthorized = 1 => "unauthorized",
+            NotFound = 404 => "not found",
+        }
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(TestError::NotFound.code(), 404);
+        assert_eq!(TestError::NotFound.to_string(), "E0404: not found");
+        assert_eq!(
+            StdError::from(TestError::Unauthorized),
+            StdError::generic_err("E0001: unauthorized")
+        );
+        assert_eq!(
+            TestError::NotFound.err_with("token 12"),
+            StdError::generic_err("E0404: not found: token 12")
+        );
+
+        assert_eq!(error_code(&TestError::NotFound.err_with("a: b")), Some(404));
+        assert_eq!(error_code(&coded_err(7, "")), Some(7));
+        assert_eq!(error_code(&StdError::generic_err("E12: short")), None);
+        assert_eq!(error_code(&StdError::generic_err("Error: no code")), None);
+        assert_eq!(error_code(&StdError::not_found("Balance")), None);
+    }
+
+    #[test]
+    fn test_pad_error() {
+        let padded = pad_error(TestError::Unauthorized.into(), 32);
+        assert_eq!(
+            padded,
+            StdError::generic_err(format!("{:32}", "E0001: unauthorized"))
+        );
+        assert_eq!(error_code(&padded), Some(1));
+
+        // errors of other kinds are padded to the same length
+        let StdError::GenericErr { msg, .. } = pad_error(StdError::not_found("Balance"), 32) else {
+            panic!("expected a generic error");
+        };
+        assert_eq!(msg, format!("{:32}", "Balance not found"));
+
+        // longer messages are truncated, at a character boundary
+        let padded = pad_error(StdError::generic_err("é".repeat(20)), 5);
+        assert_eq!(padded, StdError::generic_err("éé "));
+
+        assert_eq!(pad_error_result(Ok(1), 32), Ok(1));
+    }
+}
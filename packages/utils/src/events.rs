@@ -0,0 +1,138 @@
+//! The events emitted by token contracts, as seen in the replies to submessages.
+//!
+//! Each contract executed by a transaction emits a `wasm` event holding its address under
+//! `contract_address`, along with the attributes it added. Token contracts name what they did in
+//! the `action` attribute. The action names and the keys of the other attributes of SNIP-20 and
+//! SNIP-721 tokens are defined in the `events` modules of their packages.
+
+use cosmwasm_std::{Attribute, StdError, StdResult, SubMsgResponse};
+
+/// The type of the events of contracts
+pub const WASM_EVENT: &str = "wasm";
+/// The key of the attribute holding the address of the contract that emitted a `wasm` event
+pub const CONTRACT_ADDRESS_KEY: &str = "contract_address";
+/// The key of the attribute naming the action of a token contract
+pub const ACTION_KEY: &str = "action";
+
+/// An action of a token contract, with the other attributes of its event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenEvent {
+    pub contract_address: String,
+    pub action: String,
+    pub attributes: Vec<Attribute>,
+}
+
+impl TokenEvent {
+    /// Returns the value of the first attribute with the given key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    /// Returns the value of the first attribute with the given key, or an error if the event
+    /// has none
+    pub fn require(&self, key: &str) -> StdResult<&str> {
+        self.get(key).ok_or_else(|| {
+            StdError::generic_err(format!(
+                "{} event of {} has no {key} attribute",
+                self.action, self.contract_address
+            ))
+        })
+    }
+}
+
+/// Returns the actions of the token contract at `contract` in the response to a submessage,
+/// in the order they were emitted. The `wasm` events of other contracts, and those without an
+/// `action` attribute, are skipped.
+pub fn parse_token_events(response: &SubMsgResponse, contract: &str) -> Vec<TokenEvent> {
+    response
+        .events
+        .iter()
+        .filter(|event| event.ty == WASM_EVENT)
+        .filter(|event| {
+            event.attributes.iter().any(|attribute| {
+                attribute.key == CONTRACT_ADDRESS_KEY && attribute.value == contract
+            })
+        })
+        .filter_map(|event| {
+            let action = event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == ACTION_KEY)?;
+            Some(TokenEvent {
+                contract_address: contract.to_string(),
+                action: action.value.clone(),
+                attributes: event
+                    .attributes
+                    .iter()
+                    .filter(|attribute| {
+                        attribute.key != CONTRACT_ADDRESS_KEY && attribute.key != ACTION_KEY
+                    })
+                    .cloned()
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the first action named `action` of the token contract at `contract` in the
+/// response to a submessage
+pub fn find_token_event(
+    response: &SubMsgResponse,
+    contract: &str,
+    action: &str,
+) -> Option<TokenEvent> {
+    parse_token_events(response, contract)
+        .into_iter()
+        .find(|event| event.action == action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::Event;
+
+    #[test]
+    fn test_parse_token_events() -> StdResult<()> {
+        let response = SubMsgResponse {
+            events: vec![
+                Event::new("message").add_attribute("module", "compute"),
+                Event::new(WASM_EVENT)
+                    .add_attribute(CONTRACT_ADDRESS_KEY, "secret1token")
+                    .add_attribute(ACTION_KEY, "transfer")
+                    .add_attribute("amount", "100"),
+                // the recipient of a send
+                Event::new(WASM_EVENT)
+                    .add_attribute(CONTRACT_ADDRESS_KEY, "secret1recipient")
+                    .add_attribute(ACTION_KEY, "receive"),
+                Event::new(WASM_EVENT).add_attribute(CONTRACT_ADDRESS_KEY, "secret1token"),
+                Event::new(WASM_EVENT)
+                    .add_attribute(CONTRACT_ADDRESS_KEY, "secret1token")
+                    .add_attribute(ACTION_KEY, "burn"),
+            ],
+            data: None,
+        };
+
+        let events = parse_token_events(&response, "secret1token");
+        let actions: Vec<&str> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["transfer", "burn"]);
+        assert_eq!(events[0].get("amount"), Some("100"));
+        assert_eq!(events[0].require("amount")?, "100");
+        assert_eq!(events[0].get(CONTRACT_ADDRESS_KEY), None);
+        assert_eq!(
+            events[1].require("amount").unwrap_err(),
+            StdError::generic_err("burn event of secret1token has no amount attribute")
+        );
+
+        assert_eq!(
+            find_token_event(&response, "secret1token", "burn"),
+            Some(events[1].clone())
+        );
+        assert_eq!(find_token_event(&response, "secret1token", "mint"), None);
+        assert!(parse_token_events(&response, "secret1other").is_empty());
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use cosmwasm_std::{
-    to_binary, to_vec, Addr, Binary, Deps, DepsMut, MessageInfo, Response, StdError, StdResult,
-    Storage,
+    from_slice, to_binary, to_vec, Addr, Binary, Deps, DepsMut, MessageInfo, Response, StdError,
+    StdResult, Storage,
 };
 use cosmwasm_storage::{Bucket, ReadonlyBucket};
 use schemars::JsonSchema;
@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 
 const PREFIX_FEATURES: &[u8] = b"features";
 const PREFIX_PAUSERS: &[u8] = b"pausers";
+const PREFIX_PAUSER_FEATURES: &[u8] = b"pauser_features";
 
 pub struct FeatureToggle;
 
@@ -82,15 +83,65 @@ pub trait FeatureToggleTrait {
         feature_store.may_load(key.as_bytes()).map(|p| p.is_some())
     }
 
+    /// Sets a pauser that can pause and unpause every feature, lifting any limit set by
+    /// `set_pauser_features`
     fn set_pauser(storage: &mut dyn Storage, key: &Addr) -> StdResult<()> {
         let mut feature_store = Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSERS]);
-        feature_store.save(key.as_bytes(), &true /* value is insignificant */)
+        feature_store.save(key.as_bytes(), &true /* value is insignificant */)?;
+        let mut scope_store: Bucket<Vec<Vec<u8>>> =
+            Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSER_FEATURES]);
+        scope_store.remove(key.as_bytes());
+        Ok(())
+    }
+
+    /// Sets a pauser that can only pause and unpause the given features, e.g. a monitoring bot
+    fn set_pauser_features<T: Serialize>(
+        storage: &mut dyn Storage,
+        key: &Addr,
+        features: &[T],
+    ) -> StdResult<()> {
+        let mut feature_store = Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSERS]);
+        feature_store.save(key.as_bytes(), &true /* value is insignificant */)?;
+        let features = features.iter().map(to_vec).collect::<StdResult<Vec<_>>>()?;
+        let mut scope_store =
+            Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSER_FEATURES]);
+        scope_store.save(key.as_bytes(), &features)
+    }
+
+    /// Returns the features a pauser is limited to, or `None` if it can pause and unpause every
+    /// feature, or is not a pauser
+    fn pauser_features<T: DeserializeOwned>(
+        storage: &dyn Storage,
+        key: &Addr,
+    ) -> StdResult<Option<Vec<T>>> {
+        let scope_store: ReadonlyBucket<Vec<Vec<u8>>> =
+            ReadonlyBucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSER_FEATURES]);
+        scope_store
+            .may_load(key.as_bytes())?
+            .map(|features| features.iter().map(|f| from_slice(f)).collect())
+            .transpose()
+    }
+
+    /// Returns whether `key` is a pauser allowed to pause and unpause `feature`
+    fn can_pause<T: Serialize>(storage: &dyn Storage, key: &Addr, feature: &T) -> StdResult<bool> {
+        if !Self::is_pauser(storage, key)? {
+            return Ok(false);
+        }
+        let scope_store: ReadonlyBucket<Vec<Vec<u8>>> =
+            ReadonlyBucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSER_FEATURES]);
+        match scope_store.may_load(key.as_bytes())? {
+            None => Ok(true),
+            Some(features) => Ok(features.contains(&to_vec(feature)?)),
+        }
     }
 
     fn remove_pauser(storage: &mut dyn Storage, key: &Addr) {
         let mut feature_store: Bucket<bool> =
             Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSERS]);
-        feature_store.remove(key.as_bytes())
+        feature_store.remove(key.as_bytes());
+        let mut scope_store: Bucket<Vec<Vec<u8>>> =
+            Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_PAUSER_FEATURES]);
+        scope_store.remove(key.as_bytes())
     }
 
     fn get_feature_status<T: Serialize>(
@@ -119,6 +170,12 @@ pub trait FeatureToggleTrait {
         if !Self::is_pauser(deps.storage, &info.sender)? {
             return Err(StdError::generic_err("unauthorized"));
         }
+        // pausers limited to some features can't pause or unpause the others
+        for feature in &features {
+            if !Self::can_pause(deps.storage, &info.sender, feature)? {
+                return Err(StdError::generic_err("unauthorized"));
+            }
+        }
 
         Self::pause(deps.storage, features)?;
 
@@ -135,6 +192,12 @@ pub trait FeatureToggleTrait {
         if !Self::is_pauser(deps.storage, &info.sender)? {
             return Err(StdError::generic_err("unauthorized"));
         }
+        // pausers limited to some features can't pause or unpause the others
+        for feature in &features {
+            if !Self::can_pause(deps.storage, &info.sender, feature)? {
+                return Err(StdError::generic_err("unauthorized"));
+            }
+        }
 
         Self::unpause(deps.storage, features)?;
 
@@ -153,6 +216,20 @@ pub trait FeatureToggleTrait {
         )
     }
 
+    fn handle_set_pauser_features<T: Serialize>(
+        deps: DepsMut,
+        address: Addr,
+        features: Vec<T>,
+    ) -> StdResult<Response> {
+        Self::set_pauser_features(deps.storage, &address, &features)?;
+
+        Ok(
+            Response::new().set_data(to_binary(&HandleAnswer::SetPauserFeatures {
+                status: ResponseStatus::Success,
+            })?),
+        )
+    }
+
     fn handle_remove_pauser(deps: DepsMut, address: Addr) -> StdResult<Response> {
         Self::remove_pauser(deps.storage, &address);
 
@@ -180,10 +257,17 @@ pub trait FeatureToggleTrait {
         to_binary(&FeatureToggleQueryAnswer::Status { features: status })
     }
 
-    fn query_is_pauser(deps: Deps, address: Addr) -> StdResult<Binary> {
+    fn query_is_pauser<T: Serialize + DeserializeOwned>(
+        deps: Deps,
+        address: Addr,
+    ) -> StdResult<Binary> {
         let is_pauser = Self::is_pauser(deps.storage, &address)?;
+        let features = Self::pauser_features::<T>(deps.storage, &address)?;
 
-        to_binary(&FeatureToggleQueryAnswer::<()>::IsPauser { is_pauser })
+        to_binary(&FeatureToggleQueryAnswer::IsPauser {
+            is_pauser,
+            features,
+        })
     }
 }
 
@@ -208,6 +292,12 @@ pub enum FeatureToggleHandleMsg<T: Serialize + DeserializeOwned> {
     SetPauser {
         address: String,
     },
+    /// Sets a pauser that can only pause and unpause the given features
+    #[serde(bound = "")]
+    SetPauserFeatures {
+        address: String,
+        features: Vec<T>,
+    },
     RemovePauser {
         address: String,
     },
@@ -226,6 +316,7 @@ enum HandleAnswer {
     Pause { status: ResponseStatus },
     Unpause { status: ResponseStatus },
     SetPauser { status: ResponseStatus },
+    SetPauserFeatures { status: ResponseStatus },
     RemovePauser { status: ResponseStatus },
 }
 
@@ -244,8 +335,15 @@ pub enum FeatureToggleQueryMsg<T: Serialize + DeserializeOwned> {
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 enum FeatureToggleQueryAnswer<T: Serialize> {
-    Status { features: Vec<FeatureStatus<T>> },
-    IsPauser { is_pauser: bool },
+    Status {
+        features: Vec<FeatureStatus<T>>,
+    },
+    IsPauser {
+        is_pauser: bool,
+        /// the features the pauser is limited to, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        features: Option<Vec<T>>,
+    },
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug)]
@@ -257,8 +355,8 @@ pub struct FeatureStatus<T: Serialize> {
 #[cfg(test)]
 mod tests {
     use crate::feature_toggle::{
-        FeatureStatus, FeatureToggle, FeatureToggleHandleMsg, FeatureToggleQueryMsg,
-        FeatureToggleTrait, HandleAnswer, ResponseStatus, Status,
+        FeatureStatus, FeatureToggle, FeatureToggleHandleMsg, FeatureToggleQueryAnswer,
+        FeatureToggleQueryMsg, FeatureToggleTrait, HandleAnswer, ResponseStatus, Status,
     };
     use cosmwasm_std::testing::{mock_dependencies, mock_info, MockStorage};
     use cosmwasm_std::{from_binary, Addr, MemoryStorage, StdError, StdResult};
@@ -436,6 +534,72 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pauser_features() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        init_features(&mut deps.storage)?;
+
+        let bot = Addr::unchecked("bot".to_string());
+        FeatureToggle::set_pauser_features(&mut deps.storage, &bot, &["Feature1".to_string()])?;
+        assert!(FeatureToggle::can_pause(
+            &deps.storage,
+            &bot,
+            &"Feature1".to_string()
+        )?);
+        assert!(!FeatureToggle::can_pause(
+            &deps.storage,
+            &bot,
+            &"Feature2".to_string()
+        )?);
+
+        let info = mock_info("bot", &[]);
+        FeatureToggle::handle_pause(deps.as_mut(), &info, vec!["Feature1".to_string()])?;
+        let error = FeatureToggle::handle_pause(
+            deps.as_mut(),
+            &info,
+            vec!["Feature1".to_string(), "Feature2".to_string()],
+        );
+        assert_eq!(error, Err(StdError::generic_err("unauthorized")));
+        assert_eq!(
+            FeatureToggle::get_feature_status(&deps.storage, &"Feature2".to_string())?,
+            Some(Status::NotPaused)
+        );
+
+        // the scope is part of the IsPauser answer
+        let answer: FeatureToggleQueryAnswer<String> = from_binary(
+            &FeatureToggle::query_is_pauser::<String>(deps.as_ref(), bot.clone())?,
+        )?;
+        assert!(matches!(
+            answer,
+            FeatureToggleQueryAnswer::IsPauser { is_pauser: true, features: Some(f) }
+                if f == ["Feature1"]
+        ));
+        let answer =
+            FeatureToggle::query_is_pauser::<String>(deps.as_ref(), Addr::unchecked("alice"))?;
+        assert_eq!(answer.as_slice(), br#"{"is_pauser":{"is_pauser":true}}"#);
+
+        // setting the pauser again lifts the limit, and removing it removes the limit too
+        FeatureToggle::set_pauser(&mut deps.storage, &bot)?;
+        assert!(FeatureToggle::can_pause(
+            &deps.storage,
+            &bot,
+            &"Feature2".to_string()
+        )?);
+        FeatureToggle::set_pauser_features(&mut deps.storage, &bot, &["Feature1".to_string()])?;
+        FeatureToggle::remove_pauser(&mut deps.storage, &bot);
+        assert_eq!(
+            FeatureToggle::pauser_features::<String>(&deps.storage, &bot)?,
+            None
+        );
+        assert!(!FeatureToggle::can_pause(
+            &deps.storage,
+            &bot,
+            &"Feature1".to_string()
+        )?);
+
+        Ok(())
+    }
+
     #[test]
     fn test_deserialize_messages() {
         use serde::{Deserialize, Serialize};
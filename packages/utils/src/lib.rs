@@ -1,9 +1,31 @@
 #![doc = include_str!("../Readme.md")]
 
+pub mod address;
+pub mod block;
 pub mod calls;
+#[cfg(feature = "dead-letter")]
+pub mod dead_letter;
+pub mod debug_log;
+#[cfg(feature = "denom")]
+pub mod denom;
+pub mod envelope;
+pub mod events;
+pub mod errors;
 pub mod feature_toggle;
+pub mod oracle;
 pub mod padding;
+pub mod panic;
+pub mod parse;
+#[cfg(feature = "replay")]
+pub mod replay;
+pub mod reply;
+pub mod response;
+pub mod router;
+#[cfg(feature = "supply-control")]
+pub mod supply_control;
 pub mod types;
+#[cfg(feature = "version")]
+pub mod version;
 
 pub use calls::*;
 pub use padding::*;
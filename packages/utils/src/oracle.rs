@@ -0,0 +1,243 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CustomQuery, Env, QuerierWrapper, StdError, StdResult, Uint256};
+
+use crate::Query;
+
+/// The number of decimals of the rates returned by the oracles, i.e. a rate of `10^18` is 1.0
+pub const RATE_DECIMALS: u32 = 18;
+
+/// The price oracle contracts deployed on Secret. They share the response format, but differ in
+/// the way the symbols are passed to their queries.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleKind {
+    Band,
+    Ojo,
+}
+
+/// The query messages of the Band standard reference contract
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BandQueryMsg {
+    GetReferenceData {
+        base_symbol: String,
+        quote_symbol: String,
+    },
+    GetReferenceDataBulk {
+        base_symbols: Vec<String>,
+        quote_symbols: Vec<String>,
+    },
+}
+
+impl Query for BandQueryMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+/// The query messages of the Ojo reference contract
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OjoQueryMsg {
+    GetReferenceData { symbol_pair: (String, String) },
+    GetReferenceDataBulk { symbol_pairs: Vec<(String, String)> },
+}
+
+impl Query for OjoQueryMsg {
+    const BLOCK_SIZE: usize = 256;
+}
+
+/// The rate of a base symbol in units of a quote symbol
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct ReferenceData {
+    /// the rate, with [`RATE_DECIMALS`] decimals
+    pub rate: Uint256,
+    /// the unix timestamp, in seconds, of the last update of the base symbol
+    pub last_updated_base: u64,
+    /// the unix timestamp, in seconds, of the last update of the quote symbol
+    pub last_updated_quote: u64,
+}
+
+impl ReferenceData {
+    /// Returns the unix timestamp, in seconds, of the oldest of the two symbol updates
+    pub fn last_updated(&self) -> u64 {
+        self.last_updated_base.min(self.last_updated_quote)
+    }
+
+    /// Returns an error if either symbol was last updated more than `max_age` seconds before
+    /// the current block
+    ///
+    /// # Arguments
+    ///
+    /// * `env` - the environment of the current call
+    /// * `max_age` - the maximum age of the rate in seconds
+    pub fn assert_fresh(&self, env: &Env, max_age: u64) -> StdResult<()> {
+        let age = env.block.time.seconds().saturating_sub(self.last_updated());
+        if age > max_age {
+            return Err(StdError::generic_err(format!(
+                "oracle rate is stale: last updated {age} seconds ago, max age is {max_age} seconds"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns StdResult<ReferenceData> from querying an oracle for the rate of `base` in units of
+/// `quote`
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `kind` - the kind of oracle being queried
+/// * `base` - the symbol to price, e.g. "SCRT"
+/// * `quote` - the symbol to price in, e.g. "USD"
+/// * `callback_code_hash` - String holding the code hash of the oracle being queried
+/// * `contract_addr` - address of the oracle being queried
+pub fn reference_data_query<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    kind: OracleKind,
+    base: String,
+    quote: String,
+    callback_code_hash: String,
+    contract_addr: String,
+) -> StdResult<ReferenceData> {
+    match kind {
+        OracleKind::Band => BandQueryMsg::GetReferenceData {
+            base_symbol: base,
+            quote_symbol: quote,
+        }
+        .query(querier, callback_code_hash, contract_addr),
+        OracleKind::Ojo => OjoQueryMsg::GetReferenceData {
+            symbol_pair: (base, quote),
+        }
+        .query(querier, callback_code_hash, contract_addr),
+    }
+}
+
+/// Returns StdResult<Vec<ReferenceData>> from querying an oracle for the rates of several
+/// `(base, quote)` pairs, in the same order as the pairs
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `kind` - the kind of oracle being queried
+/// * `pairs` - the `(base, quote)` symbol pairs to price
+/// * `callback_code_hash` - String holding the code hash of the oracle being queried
+/// * `contract_addr` - address of the oracle being queried
+pub fn reference_data_bulk_query<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    kind: OracleKind,
+    pairs: Vec<(String, String)>,
+    callback_code_hash: String,
+    contract_addr: String,
+) -> StdResult<Vec<ReferenceData>> {
+    match kind {
+        OracleKind::Band => {
+            let (base_symbols, quote_symbols) = pairs.into_iter().unzip();
+            BandQueryMsg::GetReferenceDataBulk {
+                base_symbols,
+                quote_symbols,
+            }
+            .query(querier, callback_code_hash, contract_addr)
+        }
+        OracleKind::Ojo => OjoQueryMsg::GetReferenceDataBulk {
+            symbol_pairs: pairs,
+        }
+        .query(querier, callback_code_hash, contract_addr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{
+        from_slice, Binary, ContractResult, Empty, Querier, QuerierResult, QueryRequest,
+        SystemResult, Timestamp, WasmQuery,
+    };
+
+    /// answers every query with the reference data of the first pair it asks for
+    struct MockOracle;
+
+    impl Querier for MockOracle {
+        fn raw_query(&self, request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(request).unwrap();
+            let msg = match request {
+                QueryRequest::Wasm(WasmQuery::Smart { msg, .. }) => msg,
+                _ => panic!("unexpected query"),
+            };
+            let base = if let Ok(msg) = from_slice::<BandQueryMsg>(&msg) {
+                match msg {
+                    BandQueryMsg::GetReferenceData { base_symbol, .. } => base_symbol,
+                    BandQueryMsg::GetReferenceDataBulk { base_symbols, .. } => {
+                        base_symbols[0].clone()
+                    }
+                }
+            } else {
+                match from_slice::<OjoQueryMsg>(&msg).unwrap() {
+                    OjoQueryMsg::GetReferenceData { symbol_pair } => symbol_pair.0,
+                    OjoQueryMsg::GetReferenceDataBulk { symbol_pairs } => symbol_pairs[0].0.clone(),
+                }
+            };
+            let data = format!(
+                r#"{{"rate":"{}","last_updated_base":100,"last_updated_quote":90}}"#,
+                base.len()
+            );
+            let response = if msg.to_vec().windows(4).any(|w| w == b"bulk") {
+                format!("[{data}]")
+            } else {
+                data
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        }
+    }
+
+    #[test]
+    fn test_reference_data_query() -> StdResult<()> {
+        for kind in [OracleKind::Band, OracleKind::Ojo] {
+            let data = reference_data_query(
+                QuerierWrapper::<Empty>::new(&MockOracle),
+                kind,
+                "SCRT".to_string(),
+                "USD".to_string(),
+                "code hash".to_string(),
+                "oracle".to_string(),
+            )?;
+            assert_eq!(data.rate, Uint256::from(4u32));
+            assert_eq!(data.last_updated(), 90);
+
+            let bulk = reference_data_bulk_query(
+                QuerierWrapper::<Empty>::new(&MockOracle),
+                kind,
+                vec![("ATOM".to_string(), "USD".to_string())],
+                "code hash".to_string(),
+                "oracle".to_string(),
+            )?;
+            assert_eq!(bulk, vec![data]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assert_fresh() {
+        let data = ReferenceData {
+            rate: Uint256::from(1u32),
+            last_updated_base: 1_000,
+            last_updated_quote: 900,
+        };
+        let mut env = mock_env();
+
+        env.block.time = Timestamp::from_seconds(960);
+        assert!(data.assert_fresh(&env, 60).is_ok());
+
+        env.block.time = Timestamp::from_seconds(961);
+        assert_eq!(
+            data.assert_fresh(&env, 60),
+            Err(StdError::generic_err(
+                "oracle rate is stale: last updated 61 seconds ago, max age is 60 seconds"
+            ))
+        );
+    }
+}
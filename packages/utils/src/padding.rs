@@ -10,7 +10,17 @@ pub fn space_pad(message: &mut Vec<u8>, block_size: usize) -> &mut Vec<u8> {
 
     let missing = block_size - surplus;
     message.reserve(missing);
-    message.extend(std::iter::repeat(b' ').take(missing));
+    message.resize(message.len() + missing, b' ');
+    message
+}
+
+/// Take a String and pad it up to a multiple of `block_size` bytes, using spaces at the end.
+pub fn space_pad_string(message: &mut String, block_size: usize) -> &mut String {
+    let surplus = message.len() % block_size;
+    if surplus != 0 {
+        let missing = block_size - surplus;
+        message.push_str(&" ".repeat(missing));
+    }
     message
 }
 
@@ -32,10 +42,8 @@ where
         for attribute in &mut response.attributes {
             // do not pad plaintext attributes
             if attribute.encrypted {
-                // Safety: These two are safe because we know the characters that
-                // `space_pad` appends are valid UTF-8
-                unsafe { space_pad(attribute.key.as_mut_vec(), block_size) };
-                unsafe { space_pad(attribute.value.as_mut_vec(), block_size) };
+                space_pad_string(&mut attribute.key, block_size);
+                space_pad_string(&mut attribute.value, block_size);
             }
         }
         response
@@ -0,0 +1,155 @@
+//! Turning panics into errors.
+//!
+//! A panic in a contract aborts it with an opaque VM error, which tells neither the user nor the
+//! developer what went wrong. Arithmetic overflows are the most common cause, so [`CheckedStd`]
+//! provides checked operations on primitive integers that return a `StdError` instead, and
+//! [`catch_std`] turns the panics of a handler into errors in unit tests, with the
+//! `catch-panic` feature.
+//!
+//! The remaining panics can be found at compile time by denying the matching clippy lints at the
+//! root of the contract:
+//!
+//! ```ignore
+//! #![deny(
+//!     clippy::arithmetic_side_effects,
+//!     clippy::unwrap_used,
+//!     clippy::expect_used,
+//!     clippy::panic,
+//!     clippy::indexing_slicing
+//! )]
+//! ```
+
+use std::panic::UnwindSafe;
+
+use cosmwasm_std::{DivideByZeroError, OverflowError, OverflowOperation, StdError, StdResult};
+
+/// Runs `f`, and with the `catch-panic` feature, returns its panics as generic errors carrying
+/// the panic message.
+///
+/// Contracts are built with `panic = "abort"`, so panics can only be caught in unit tests, where
+/// this lets a test assert on the error of a handler that panics. Without the feature, this just
+/// calls `f`.
+pub fn catch_std<T, F>(f: F) -> StdResult<T>
+where
+    F: FnOnce() -> StdResult<T> + UnwindSafe,
+{
+    #[cfg(feature = "catch-panic")]
+    {
+        std::panic::catch_unwind(f).unwrap_or_else(|payload| {
+            let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+                msg.to_string()
+            } else if let Some(msg) = payload.downcast_ref::<String>() {
+                msg.clone()
+            } else {
+                "unknown panic".to_string()
+            };
+            Err(StdError::generic_err(format!("panicked: {msg}")))
+        })
+    }
+    #[cfg(not(feature = "catch-panic"))]
+    {
+        f()
+    }
+}
+
+/// Checked arithmetic on primitive integers, returning the overflow and division by zero errors
+/// of `StdError` instead of panicking or wrapping.
+pub trait CheckedStd: Sized {
+    fn try_add(self, rhs: Self) -> StdResult<Self>;
+    fn try_sub(self, rhs: Self) -> StdResult<Self>;
+    fn try_mul(self, rhs: Self) -> StdResult<Self>;
+    fn try_div(self, rhs: Self) -> StdResult<Self>;
+}
+
+macro_rules! impl_checked_std {
+    ($($int:ty),+) => {
+        $(impl CheckedStd for $int {
+            fn try_add(self, rhs: Self) -> StdResult<Self> {
+                self.checked_add(rhs).ok_or_else(|| {
+                    StdError::overflow(OverflowError::new(OverflowOperation::Add, self, rhs))
+                })
+            }
+
+            fn try_sub(self, rhs: Self) -> StdResult<Self> {
+                self.checked_sub(rhs).ok_or_else(|| {
+                    StdError::overflow(OverflowError::new(OverflowOperation::Sub, self, rhs))
+                })
+            }
+
+            fn try_mul(self, rhs: Self) -> StdResult<Self> {
+                self.checked_mul(rhs).ok_or_else(|| {
+                    StdError::overflow(OverflowError::new(OverflowOperation::Mul, self, rhs))
+                })
+            }
+
+            fn try_div(self, rhs: Self) -> StdResult<Self> {
+                if rhs == 0 {
+                    return Err(StdError::divide_by_zero(DivideByZeroError::new(self)));
+                }
+                // only `MIN / -1` overflows for signed integers
+                self.checked_div(rhs).ok_or_else(|| {
+                    StdError::generic_err(format!("Cannot divide {self} by {rhs}: overflow"))
+                })
+            }
+        })+
+    };
+}
+
+impl_checked_std!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_std() {
+        assert_eq!(200u8.try_add(55), Ok(255));
+        assert_eq!(
+            200u8.try_add(56),
+            Err(StdError::overflow(OverflowError::new(
+                OverflowOperation::Add,
+                200,
+                56
+            )))
+        );
+        assert_eq!(
+            1u128.try_sub(2),
+            Err(StdError::overflow(OverflowError::new(
+                OverflowOperation::Sub,
+                1,
+                2
+            )))
+        );
+        assert_eq!((-3i64).try_mul(4), Ok(-12));
+        assert!(u64::MAX.try_mul(2).is_err());
+        assert_eq!(7u32.try_div(2), Ok(3));
+        assert_eq!(
+            7u32.try_div(0),
+            Err(StdError::divide_by_zero(DivideByZeroError::new(7)))
+        );
+        assert!(i32::MIN.try_div(-1).is_err());
+    }
+
+    #[test]
+    fn test_catch_std() {
+        assert_eq!(catch_std(|| 1u64.try_add(2)), Ok(3));
+        assert_eq!(
+            catch_std(|| Err::<(), _>(StdError::generic_err("failed"))),
+            Err(StdError::generic_err("failed"))
+        );
+    }
+
+    #[cfg(feature = "catch-panic")]
+    #[test]
+    fn test_catch_std_panics() {
+        let balance = 1u64;
+        assert_eq!(
+            catch_std(|| -> StdResult<u64> { panic!("balance {balance} too low") }),
+            Err(StdError::generic_err("panicked: balance 1 too low"))
+        );
+        assert_eq!(
+            catch_std(|| -> StdResult<()> { panic!("static message") }),
+            Err(StdError::generic_err("panicked: static message"))
+        );
+    }
+}
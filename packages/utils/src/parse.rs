@@ -0,0 +1,382 @@
+//! Parsers of human-friendly durations, amounts and percentages, such as `"7d12h"`,
+//! `"1_000_000uscrt"` and `"2.5%"`, for the configuration of contracts.
+//!
+//! The [`HumanDuration`], [`HumanCoin`] and [`Percent`] types deserialize from these strings,
+//! so instantiate messages can use them instead of raw integers, which are easy to mis-scale.
+
+use std::fmt;
+use std::str::FromStr;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use cosmwasm_std::{Coin, Decimal, StdError, StdResult, Uint128};
+
+/// The units of durations, from the largest to the smallest, along with their length in seconds
+const DURATION_UNITS: [(char, u64); 5] = [
+    ('w', 7 * 24 * 60 * 60),
+    ('d', 24 * 60 * 60),
+    ('h', 60 * 60),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Returns the number of seconds of a duration such as `"7d12h"` or `"90s"`.
+///
+/// A duration is a sequence of integers, each followed by one of the units `w` (weeks), `d`
+/// (days), `h` (hours), `m` (minutes) or `s` (seconds). Each unit can only be used once, from
+/// the largest to the smallest.
+pub fn parse_duration(duration: &str) -> StdResult<u64> {
+    let invalid = |reason: String| {
+        StdError::generic_err(format!("invalid duration \"{duration}\": {reason}"))
+    };
+    if duration.is_empty() {
+        return Err(invalid("it is empty".to_string()));
+    }
+
+    let mut seconds = 0u64;
+    // the index in DURATION_UNITS after the last unit used
+    let mut next_unit = 0;
+    let mut rest = duration;
+    while !rest.is_empty() {
+        let digits_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| invalid(format!("missing unit after \"{rest}\"")))?;
+        if digits_len == 0 {
+            return Err(invalid(format!("expected a number before \"{rest}\"")));
+        }
+        let (digits, after) = rest.split_at(digits_len);
+        let unit = after.chars().next().unwrap_or_default();
+
+        let position = DURATION_UNITS
+            .iter()
+            .position(|(name, _)| *name == unit)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "unknown unit '{unit}', expected one of w, d, h, m or s"
+                ))
+            })?;
+        if position < next_unit {
+            return Err(invalid(format!(
+                "unit '{unit}' is repeated or out of order, units must go from the largest \
+                 to the smallest"
+            )));
+        }
+        next_unit = position + 1;
+
+        let overflow = || invalid("it is too long".to_string());
+        let count = u64::from_str(digits).map_err(|_| overflow())?;
+        seconds = count
+            .checked_mul(DURATION_UNITS[position].1)
+            .and_then(|unit_seconds| seconds.checked_add(unit_seconds))
+            .ok_or_else(overflow)?;
+        rest = &after[unit.len_utf8()..];
+    }
+
+    Ok(seconds)
+}
+
+/// Returns the shortest string that [`parse_duration`] parses into `seconds`
+pub fn format_duration(mut seconds: u64) -> String {
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+    let mut duration = String::new();
+    for (unit, unit_seconds) in DURATION_UNITS {
+        let count = seconds / unit_seconds;
+        if count > 0 {
+            duration.push_str(&format!("{count}{unit}"));
+            seconds %= unit_seconds;
+        }
+    }
+    duration
+}
+
+/// Returns the coin of an amount such as `"1_000_000uscrt"`.
+///
+/// The digits of the amount can be grouped by thousands with underscores. The denomination
+/// must be 3 to 128 characters long, start with a letter, and only contain letters, digits
+/// and the characters `/:._-`.
+pub fn parse_coin(coin: &str) -> StdResult<Coin> {
+    let invalid =
+        |reason: &str| StdError::generic_err(format!("invalid amount \"{coin}\": {reason}"));
+
+    let amount_len = coin
+        .find(|c: char| !c.is_ascii_digit() && c != '_')
+        .ok_or_else(|| invalid("missing denomination"))?;
+    let (amount, denom) = coin.split_at(amount_len);
+    if amount.is_empty() {
+        return Err(invalid("expected a number before the denomination"));
+    }
+
+    let mut groups = amount.split('_');
+    let first_group = groups.next().unwrap_or_default();
+    if amount.contains('_')
+        && (first_group.is_empty() || first_group.len() > 3 || groups.any(|group| group.len() != 3))
+    {
+        return Err(invalid(
+            "underscores must separate the digits in groups of three",
+        ));
+    }
+    let amount = Uint128::from_str(&amount.replace('_', ""))
+        .map_err(|_| invalid("the amount is too large"))?;
+
+    let valid_denom = (3..=128).contains(&denom.len())
+        && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+        && denom
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+    if !valid_denom {
+        return Err(invalid(&format!("invalid denomination \"{denom}\"")));
+    }
+
+    Ok(Coin {
+        denom: denom.to_string(),
+        amount,
+    })
+}
+
+/// Returns the ratio of a percentage such as `"2.5%"`, e.g. `0.025`.
+///
+/// The percentage can have up to 16 decimal places, and can be larger than 100%.
+pub fn parse_percent(percent: &str) -> StdResult<Decimal> {
+    let invalid =
+        |reason: &str| StdError::generic_err(format!("invalid percentage \"{percent}\": {reason}"));
+
+    let number = percent
+        .strip_suffix('%')
+        .ok_or_else(|| invalid("missing '%' at the end"))?;
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+        return Err(invalid("expected a number such as 2.5%"));
+    }
+    if number.ends_with('.') {
+        return Err(invalid("expected digits after the decimal point"));
+    }
+    if fraction.len() > Decimal::DECIMAL_PLACES as usize - 2 {
+        return Err(invalid("too many decimal places"));
+    }
+
+    let atomics = Uint128::from_str(&[integer, fraction].concat())
+        .map_err(|_| invalid("the percentage is too large"))?;
+    Decimal::from_atomics(atomics, fraction.len() as u32 + 2)
+        .map_err(|_| invalid("the percentage is too large"))
+}
+
+/// Returns the string that [`parse_percent`] parses into `ratio`
+pub fn format_percent(ratio: &Decimal) -> String {
+    let ratio = ratio.to_string();
+    let (integer, fraction) = ratio.split_once('.').unwrap_or((&ratio, ""));
+    let fraction = format!("{fraction:0<2}");
+    let (shifted, fraction) = fraction.split_at(2);
+
+    let integer = [integer, shifted].concat();
+    let integer = match integer.trim_start_matches('0') {
+        "" => "0",
+        integer => integer,
+    };
+    match fraction.trim_end_matches('0') {
+        "" => format!("{integer}%"),
+        fraction => format!("{integer}.{fraction}%"),
+    }
+}
+
+/// Defines a newtype that is serialized as a human-friendly string
+macro_rules! human_type {
+    ($(#[$meta:meta])* $name:ident($inner:ty), $parse:expr, $format:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name(pub $inner);
+
+        impl FromStr for $name {
+            type Err = StdError;
+
+            fn from_str(s: &str) -> StdResult<Self> {
+                $parse(s).map(Self)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(&$format(&self.0))
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(de::Error::custom)
+            }
+        }
+
+        impl JsonSchema for $name {
+            fn schema_name() -> String {
+                String::schema_name()
+            }
+
+            fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+                String::json_schema(gen)
+            }
+        }
+    };
+}
+
+human_type!(
+    /// A number of seconds, serialized as a duration such as `"7d12h"`
+    HumanDuration(u64),
+    parse_duration,
+    |seconds: &u64| format_duration(*seconds)
+);
+
+human_type!(
+    /// A coin, serialized as an amount such as `"1_000_000uscrt"`
+    HumanCoin(Coin),
+    parse_coin,
+    |coin: &Coin| format!("{}{}", coin.amount, coin.denom)
+);
+
+human_type!(
+    /// A ratio, serialized as a percentage such as `"2.5%"`
+    Percent(Decimal),
+    parse_percent,
+    format_percent
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{from_slice, to_vec};
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7d12h"), Ok(7 * 86400 + 12 * 3600));
+        assert_eq!(parse_duration("1w1d1h1m1s"), Ok(694861));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("0s"), Ok(0));
+
+        assert_eq!(
+            parse_duration("12h7d"),
+            Err(StdError::generic_err(
+                "invalid duration \"12h7d\": unit 'd' is repeated or out of order, units must \
+                 go from the largest to the smallest"
+            ))
+        );
+        assert_eq!(
+            parse_duration("7"),
+            Err(StdError::generic_err(
+                "invalid duration \"7\": missing unit after \"7\""
+            ))
+        );
+        assert_eq!(
+            parse_duration("3y"),
+            Err(StdError::generic_err(
+                "invalid duration \"3y\": unknown unit 'y', expected one of w, d, h, m or s"
+            ))
+        );
+        for invalid in ["", "d", "1h1h", "1 h", "-1s", "99999999999999999999w"] {
+            assert!(parse_duration(invalid).is_err(), "{invalid}");
+        }
+
+        for seconds in [0, 90, 3600, 648000, 694861] {
+            assert_eq!(parse_duration(&format_duration(seconds)), Ok(seconds));
+        }
+        assert_eq!(format_duration(648000), "1w12h");
+    }
+
+    #[test]
+    fn test_parse_coin() {
+        assert_eq!(
+            parse_coin("1_000_000uscrt"),
+            Ok(Coin::new(1_000_000, "uscrt"))
+        );
+        assert_eq!(parse_coin("5ibc/ABC123"), Ok(Coin::new(5, "ibc/ABC123")));
+
+        assert_eq!(
+            parse_coin("1_0000uscrt"),
+            Err(StdError::generic_err(
+                "invalid amount \"1_0000uscrt\": underscores must separate the digits in \
+                 groups of three"
+            ))
+        );
+        assert_eq!(
+            parse_coin("1000"),
+            Err(StdError::generic_err(
+                "invalid amount \"1000\": missing denomination"
+            ))
+        );
+        for invalid in [
+            "uscrt",
+            "_100uscrt",
+            "100_uscrt",
+            "1__000uscrt",
+            "1.5uscrt",
+            "1ab",
+        ] {
+            assert!(parse_coin(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(parse_percent("2.5%"), Ok(Decimal::permille(25)));
+        assert_eq!(parse_percent("100%"), Ok(Decimal::one()));
+        assert_eq!(parse_percent("150%"), Ok(Decimal::percent(150)));
+        assert_eq!(
+            parse_percent("0.01%"),
+            Ok(Decimal::from_ratio(1u128, 10000u128))
+        );
+
+        assert_eq!(
+            parse_percent("2.5"),
+            Err(StdError::generic_err(
+                "invalid percentage \"2.5\": missing '%' at the end"
+            ))
+        );
+        for invalid in ["%", ".5%", "5.%", "-1%", "1e2%", "0.00000000000000001%"] {
+            assert!(parse_percent(invalid).is_err(), "{invalid}");
+        }
+
+        for percent in ["2.5%", "100%", "0%", "0.01%", "150.125%"] {
+            assert_eq!(format_percent(&parse_percent(percent).unwrap()), percent);
+        }
+    }
+
+    #[test]
+    fn test_human_types() -> StdResult<()> {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Config {
+            unbonding: HumanDuration,
+            min_stake: HumanCoin,
+            fee: Percent,
+        }
+
+        let config: Config =
+            from_slice(br#"{"unbonding":"21d","min_stake":"1_000_000uscrt","fee":"2.5%"}"#)?;
+        assert_eq!(
+            config,
+            Config {
+                unbonding: HumanDuration(21 * 86400),
+                min_stake: HumanCoin(Coin::new(1_000_000, "uscrt")),
+                fee: Percent(Decimal::permille(25)),
+            }
+        );
+        assert_eq!(
+            to_vec(&config)?,
+            br#"{"unbonding":"3w","min_stake":"1000000uscrt","fee":"2.5%"}"#
+        );
+
+        // raw integers are rejected
+        assert!(from_slice::<HumanDuration>(b"1814400").is_err());
+
+        Ok(())
+    }
+}
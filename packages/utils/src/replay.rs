@@ -0,0 +1,192 @@
+//! Rejecting messages that are submitted again within a window, such as an order or an oracle
+//! post that a client sent twice by accident.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{to_vec, Addr, Env, StdError, StdResult, Storage};
+
+use secret_toolkit_storage::{DequeStore, Keymap};
+
+const SEEN: &[u8] = b"seen";
+const EXPIRATIONS: &[u8] = b"expirations";
+
+/// Records the hashes of the messages handled in the last `ttl` seconds, and rejects the
+/// messages with the same sender, content and nonce.
+///
+/// Clients that do mean to send the same message twice, e.g. two identical orders, pass a
+/// different nonce with each of them.
+pub struct ReplayGuard<'a> {
+    namespace: &'a [u8],
+    ttl: u64,
+}
+
+impl<'a> ReplayGuard<'a> {
+    /// constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - the storage namespace of the recorded hashes
+    /// * `ttl` - the number of seconds a message is remembered for
+    pub const fn new(namespace: &'a [u8], ttl: u64) -> Self {
+        Self { namespace, ttl }
+    }
+
+    /// the expiration time of each recorded hash, in seconds
+    fn seen(&self) -> Keymap<'a, [u8; 32], u64> {
+        Keymap::new(self.namespace).add_suffix(SEEN)
+    }
+
+    /// the recorded hashes with their expiration time, in the order they expire in, as the
+    /// block time only goes forward
+    fn expirations(&self) -> DequeStore<'a, (u64, [u8; 32])> {
+        DequeStore::new(self.namespace).add_suffix(EXPIRATIONS)
+    }
+
+    /// Returns the hash identifying a message of `sender` with the given nonce
+    pub fn message_hash<M: Serialize>(sender: &Addr, msg: &M, nonce: &[u8]) -> StdResult<[u8; 32]> {
+        let msg = to_vec(msg)?;
+        let mut hasher = Sha256::new();
+        for part in [sender.as_bytes(), msg.as_slice(), nonce] {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Fails if the same message was recorded in the last `ttl` seconds, and otherwise records
+    /// it. Call this at the start of the handler, so that a duplicate is rejected before any
+    /// side effect.
+    pub fn check_and_record<M: Serialize>(
+        &self,
+        storage: &mut dyn Storage,
+        env: &Env,
+        sender: &Addr,
+        msg: &M,
+        nonce: &[u8],
+    ) -> StdResult<()> {
+        let hash = Self::message_hash(sender, msg, nonce)?;
+        let now = env.block.time.seconds();
+        if let Some(expiration) = self.seen().get(storage, &hash) {
+            if now < expiration {
+                return Err(StdError::generic_err(format!(
+                    "duplicate message, it can be submitted again after {expiration}"
+                )));
+            }
+        }
+        let expiration = now.saturating_add(self.ttl);
+        self.seen().insert(storage, &hash, &expiration)?;
+        self.expirations().push_back(storage, &(expiration, hash))
+    }
+
+    /// Removes up to `limit` expired records, the oldest first, to reclaim their storage, and
+    /// returns how many were removed. Expired hashes don't reject messages even if they are
+    /// never pruned.
+    pub fn prune(&self, storage: &mut dyn Storage, env: &Env, limit: u32) -> StdResult<u32> {
+        let now = env.block.time.seconds();
+        let seen = self.seen();
+        let expirations = self.expirations();
+        let mut removed = 0;
+        while removed < limit {
+            match expirations.peek_front(storage)? {
+                Some((expiration, _)) if expiration <= now => {}
+                _ => break,
+            }
+            let (expiration, hash) = expirations.pop_front(storage)?;
+            // a hash recorded again after it expired is only removed with its last record
+            if seen.get(storage, &hash) == Some(expiration) {
+                seen.remove(storage, &hash)?;
+            }
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    static GUARD: ReplayGuard = ReplayGuard::new(b"replay", 60);
+
+    #[test]
+    fn test_replay_guard() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut env = mock_env();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        GUARD.check_and_record(&mut storage, &env, &alice, &"order", b"1")?;
+        assert!(GUARD
+            .check_and_record(&mut storage, &env, &alice, &"order", b"1")
+            .is_err());
+        // another nonce, sender or message is not a duplicate
+        GUARD.check_and_record(&mut storage, &env, &alice, &"order", b"2")?;
+        GUARD.check_and_record(&mut storage, &env, &bob, &"order", b"1")?;
+        GUARD.check_and_record(&mut storage, &env, &alice, &"other order", b"1")?;
+
+        env.block.time = env.block.time.plus_seconds(59);
+        assert!(GUARD
+            .check_and_record(&mut storage, &env, &alice, &"order", b"1")
+            .is_err());
+        env.block.time = env.block.time.plus_seconds(1);
+        GUARD.check_and_record(&mut storage, &env, &alice, &"order", b"1")?;
+        assert!(GUARD
+            .check_and_record(&mut storage, &env, &alice, &"order", b"1")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        let mut env = mock_env();
+        let alice = Addr::unchecked("alice");
+
+        for nonce in 0u8..5 {
+            GUARD.check_and_record(&mut storage, &env, &alice, &"order", &[nonce])?;
+        }
+        assert_eq!(GUARD.prune(&mut storage, &env, 10)?, 0);
+
+        // fresh hashes are recorded among the expired ones, and again after they expire
+        env.block.time = env.block.time.plus_seconds(30);
+        GUARD.check_and_record(&mut storage, &env, &alice, &"order", b"late")?;
+        env.block.time = env.block.time.plus_seconds(30);
+        GUARD.check_and_record(&mut storage, &env, &alice, &"order", &[0])?;
+        GUARD.check_and_record(&mut storage, &env, &alice, &"order", b"fresh")?;
+        for nonce in 5u8..8 {
+            GUARD.check_and_record(&mut storage, &env, &alice, &"order", &[nonce])?;
+        }
+
+        // the five expired hashes are pruned over several calls, and the fresh ones are kept
+        assert_eq!(GUARD.prune(&mut storage, &env, 2)?, 2);
+        assert_eq!(GUARD.prune(&mut storage, &env, 2)?, 2);
+        assert_eq!(GUARD.prune(&mut storage, &env, 2)?, 1);
+        assert_eq!(GUARD.prune(&mut storage, &env, 2)?, 0);
+        assert_eq!(GUARD.seen().get_len(&storage)?, 6);
+        for nonce in [b"late".as_slice(), &[0], b"fresh", &[5], &[6], &[7]] {
+            assert!(GUARD
+                .check_and_record(&mut storage, &env, &alice, &"order", nonce)
+                .is_err());
+        }
+        for nonce in 1u8..5 {
+            assert!(!GUARD.seen().contains(
+                &storage,
+                &ReplayGuard::message_hash(&alice, &"order", &[nonce])?
+            ));
+        }
+
+        // the next hashes expire in the order they were recorded in
+        env.block.time = env.block.time.plus_seconds(30);
+        assert_eq!(GUARD.prune(&mut storage, &env, 10)?, 1);
+        env.block.time = env.block.time.plus_seconds(30);
+        assert_eq!(GUARD.prune(&mut storage, &env, 3)?, 3);
+        assert_eq!(GUARD.prune(&mut storage, &env, 3)?, 2);
+        assert_eq!(GUARD.seen().get_len(&storage)?, 0);
+        assert!(GUARD.expirations().is_empty(&storage)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+//! Parsing the replies to submessages.
+//!
+//! The data of the reply to an instantiation is a `MsgInstantiateContractResponse` protobuf,
+//! holding the address of the new contract and the data it returned, which some versions of the
+//! chain wrap in the `MsgData` (or `TxMsgData`) envelope of the SDK. [`parse_instantiate_reply`]
+//! handles all of them, and falls back to the `contract_address` attribute of the `instantiate`
+//! event when the reply has no data.
+
+use cosmwasm_std::{Addr, Binary, Reply, StdError, StdResult, SubMsgResponse};
+
+/// The type of the event emitted by an instantiation
+pub const INSTANTIATE_EVENT: &str = "instantiate";
+
+/// The keys the address of the new contract can have in the `instantiate` event
+const CONTRACT_ADDRESS_KEYS: &[&str] = &["contract_address", "_contract_address"];
+
+/// How deep envelopes are unwrapped
+const MAX_ENVELOPES: usize = 2;
+
+/// Returns the address of the contract instantiated by a submessage, and the data it returned,
+/// from the reply to the submessage. Fails if the submessage failed, or if the reply holds no
+/// address.
+pub fn parse_instantiate_reply(reply: Reply) -> StdResult<(Addr, Option<Binary>)> {
+    let response = reply
+        .result
+        .into_result()
+        .map_err(|err| StdError::generic_err(format!("instantiation failed: {err}")))?;
+
+    if let Some(data) = &response.data {
+        if let Some((address, data)) = parse_instantiate_response(data, 0) {
+            return Ok((Addr::unchecked(address), data.map(Binary::from)));
+        }
+    }
+    if let Some(address) = event_contract_address(&response) {
+        return Ok((Addr::unchecked(address), None));
+    }
+    Err(StdError::generic_err(
+        "the reply holds no address of an instantiated contract",
+    ))
+}
+
+/// Parses a `MsgInstantiateContractResponse`, possibly in envelopes
+fn parse_instantiate_response(data: &[u8], depth: usize) -> Option<(String, Option<Vec<u8>>)> {
+    let fields = parse_proto_fields(data)?;
+    let first = field(&fields, 1)?;
+    let second = field(&fields, 2);
+
+    if let Ok(first) = std::str::from_utf8(first) {
+        // `MsgData { msg_type, data }`, where `msg_type` is the type URL of the message
+        if first.starts_with('/') {
+            return match second {
+                Some(inner) if depth < MAX_ENVELOPES => {
+                    parse_instantiate_response(inner, depth + 1)
+                }
+                _ => None,
+            };
+        }
+        if is_address(first) {
+            let data = second.filter(|data| !data.is_empty()).map(<[u8]>::to_vec);
+            return Some((first.to_string(), data));
+        }
+    }
+    // `TxMsgData { data: [MsgData] }`
+    if depth < MAX_ENVELOPES {
+        return parse_instantiate_response(first, depth + 1);
+    }
+    None
+}
+
+/// Returns the first field with the given number
+fn field<'a>(fields: &[(u32, &'a [u8])], number: u32) -> Option<&'a [u8]> {
+    fields
+        .iter()
+        .find(|(n, _)| *n == number)
+        .map(|(_, value)| *value)
+}
+
+fn is_address(address: &str) -> bool {
+    !address.is_empty() && address.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Returns the number and value of the length-delimited fields of a protobuf message, skipping
+/// its other fields, or `None` if it isn't a valid message
+fn parse_proto_fields(mut data: &[u8]) -> Option<Vec<(u32, &[u8])>> {
+    let mut fields = vec![];
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        let number = u32::try_from(key >> 3).ok()?;
+        match key & 0x7 {
+            0 => {
+                read_varint(&mut data)?;
+            }
+            1 => data = data.get(8..)?,
+            2 => {
+                let len = usize::try_from(read_varint(&mut data)?).ok()?;
+                let value = data.get(..len)?;
+                fields.push((number, value));
+                data = &data[len..];
+            }
+            5 => data = data.get(4..)?,
+            _ => return None,
+        }
+    }
+    Some(fields)
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn event_contract_address(response: &SubMsgResponse) -> Option<String> {
+    response
+        .events
+        .iter()
+        .filter(|event| event.ty == INSTANTIATE_EVENT)
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| CONTRACT_ADDRESS_KEYS.contains(&attribute.key.as_str()))
+        .map(|attribute| attribute.value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{Event, SubMsgResult};
+
+    const ADDRESS: &str = "secret1mfk7n6mc2cg6lznujmeckdh4x0a5ezf6hx6y8q";
+
+    // `MsgInstantiateContractResponse { address: ADDRESS, data: b"{\"ok\":true}" }`
+    const RESPONSE: &str = concat!(
+        "0a2d736563726574316d666b376e366d63326367",
+        "366c7a6e756a6d65636b64683478306135657a66",
+        "36687836793871120b7b226f6b223a747275657d",
+    );
+
+    fn hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn length_delimited(number: u8, value: &[u8]) -> Vec<u8> {
+        [&[number << 3 | 2, value.len() as u8], value].concat()
+    }
+
+    fn reply(data: Option<Vec<u8>>, events: Vec<Event>) -> Reply {
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events,
+                data: data.map(Binary::from),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_parse_instantiate_reply() -> StdResult<()> {
+        let response = hex(RESPONSE);
+        let expected = (
+            Addr::unchecked(ADDRESS),
+            Some(Binary::from(br#"{"ok":true}"#)),
+        );
+        assert_eq!(
+            parse_instantiate_reply(reply(Some(response.clone()), vec![]))?,
+            expected
+        );
+
+        // in a `MsgData` envelope
+        let msg_data = [
+            length_delimited(1, b"/secret.compute.v1beta1.MsgInstantiateContract"),
+            length_delimited(2, &response),
+        ]
+        .concat();
+        assert_eq!(
+            parse_instantiate_reply(reply(Some(msg_data.clone()), vec![]))?,
+            expected
+        );
+        // in a `TxMsgData` envelope
+        let tx_msg_data = length_delimited(1, &msg_data);
+        assert_eq!(
+            parse_instantiate_reply(reply(Some(tx_msg_data), vec![]))?,
+            expected
+        );
+
+        // without data
+        let response = length_delimited(1, ADDRESS.as_bytes());
+        assert_eq!(
+            parse_instantiate_reply(reply(Some(response), vec![]))?,
+            (Addr::unchecked(ADDRESS), None)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_instantiate_reply_events() -> StdResult<()> {
+        let events = vec![
+            Event::new("message").add_attribute("module", "compute"),
+            Event::new("instantiate")
+                .add_attribute("code_id", "1")
+                .add_attribute("contract_address", ADDRESS),
+        ];
+        assert_eq!(
+            parse_instantiate_reply(reply(None, events))?,
+            (Addr::unchecked(ADDRESS), None)
+        );
+
+        assert!(parse_instantiate_reply(reply(None, vec![])).is_err());
+        assert!(parse_instantiate_reply(reply(Some(vec![0xff]), vec![])).is_err());
+        let failed = Reply {
+            id: 1,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        };
+        assert_eq!(
+            parse_instantiate_reply(failed).unwrap_err(),
+            StdError::generic_err("instantiation failed: out of gas")
+        );
+
+        Ok(())
+    }
+}
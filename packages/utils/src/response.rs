@@ -0,0 +1,222 @@
+//! A builder of responses that are private by default.
+//!
+//! [`SecretResponse`] pads the data and the values of the encrypted attributes of a response to
+//! a block size, so that their length doesn't leak their content, and rejects plaintext attributes whose key
+//! is in a deny-list, such as `amount` or `recipient`, unless they are explicitly marked public.
+
+use cosmwasm_std::{
+    Attribute, Binary, CosmosMsg, Empty, Event, Response, StdError, StdResult, SubMsg,
+};
+
+use crate::{space_pad, space_pad_string};
+
+/// The keys of the attributes that are rejected in plaintext by default, as they usually hold
+/// private data
+pub const DEFAULT_DENY_LIST: &[&str] = &[
+    "amount",
+    "balance",
+    "from",
+    "memo",
+    "owner",
+    "recipient",
+    "sender",
+    "spender",
+    "to",
+];
+
+/// A builder of `Response` that pads its data and the values of its encrypted attributes, and
+/// rejects plaintext
+/// attributes whose key is in a deny-list.
+///
+/// Attributes are encrypted by default, as with `Response::add_attribute`. Plaintext attributes
+/// are added with `add_plaintext_attribute`, and rejected by `build` if their key is in the
+/// deny-list, compared without case. Attributes that are meant to be public despite their key
+/// are added with `add_public_attribute`.
+pub struct SecretResponse<T = Empty> {
+    response: Response<T>,
+    block_size: usize,
+    deny_list: &'static [&'static str],
+    denied: Vec<String>,
+}
+
+impl<T> SecretResponse<T>
+where
+    T: Clone + std::fmt::Debug + PartialEq + schemars::JsonSchema,
+{
+    /// Creates an empty response, whose data and encrypted attribute values are padded to blocks
+    /// of `block_size` bytes, with the [`DEFAULT_DENY_LIST`]
+    pub fn new(block_size: usize) -> Self {
+        Self {
+            response: Response::new(),
+            block_size: block_size.max(1),
+            deny_list: DEFAULT_DENY_LIST,
+            denied: vec![],
+        }
+    }
+
+    /// Replaces the deny-list of plaintext attribute keys
+    pub fn with_deny_list(mut self, deny_list: &'static [&'static str]) -> Self {
+        self.deny_list = deny_list;
+        self
+    }
+
+    fn check_plaintext(&mut self, key: &str) {
+        if self
+            .deny_list
+            .iter()
+            .any(|denied| denied.eq_ignore_ascii_case(key))
+        {
+            self.denied.push(key.to_string());
+        }
+    }
+
+    /// Adds an encrypted attribute, whose value is padded
+    pub fn add_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.response = self.response.add_attribute(key, value);
+        self
+    }
+
+    /// Adds a plaintext attribute. `build` fails if its key is in the deny-list.
+    pub fn add_plaintext_attribute(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        let key = key.into();
+        self.check_plaintext(&key);
+        self.response = self.response.add_attribute_plaintext(key, value);
+        self
+    }
+
+    /// Adds a plaintext attribute that is meant to be public, even if its key is in the
+    /// deny-list
+    pub fn add_public_attribute(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.response = self.response.add_attribute_plaintext(key, value);
+        self
+    }
+
+    /// Adds an event. `build` fails if it has a plaintext attribute whose key is in the
+    /// deny-list.
+    pub fn add_event(mut self, event: Event) -> Self {
+        for Attribute { key, encrypted, .. } in &event.attributes {
+            if !encrypted {
+                self.check_plaintext(key);
+            }
+        }
+        self.response = self.response.add_event(event);
+        self
+    }
+
+    pub fn add_message(mut self, msg: impl Into<CosmosMsg<T>>) -> Self {
+        self.response = self.response.add_message(msg);
+        self
+    }
+
+    pub fn add_submessage(mut self, msg: SubMsg<T>) -> Self {
+        self.response = self.response.add_submessage(msg);
+        self
+    }
+
+    /// Sets the data of the response, which is padded
+    pub fn set_data(mut self, data: impl Into<Binary>) -> Self {
+        self.response = self.response.set_data(data);
+        self
+    }
+
+    /// Returns the response, with its data and encrypted attribute values padded, or an error
+    /// naming the plaintext attributes whose key is in the deny-list. Keys are not padded, as
+    /// they are set by the contract rather than by the data they describe.
+    pub fn build(self) -> StdResult<Response<T>> {
+        if !self.denied.is_empty() {
+            return Err(StdError::generic_err(format!(
+                "plaintext attributes would leak private data: {}",
+                self.denied.join(", ")
+            )));
+        }
+
+        let mut response = self.response;
+        if let Some(data) = &mut response.data {
+            space_pad(&mut data.0, self.block_size);
+        }
+        for attribute in &mut response.attributes {
+            if attribute.encrypted {
+                space_pad_string(&mut attribute.value, self.block_size);
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::BankMsg;
+
+    #[test]
+    fn test_padding() -> StdResult<()> {
+        let response: Response = SecretResponse::new(16)
+            .add_attribute("amount", "100")
+            .add_public_attribute("action", "transfer")
+            .add_message(BankMsg::Burn { amount: vec![] })
+            .set_data(b"ok".to_vec())
+            .build()?;
+
+        assert_eq!(
+            response.data,
+            Some(Binary::from(format!("{:16}", "ok").as_bytes()))
+        );
+        // only the values of encrypted attributes are padded, not their keys
+        assert_eq!(response.attributes[0].key, "amount");
+        assert_eq!(response.attributes[0].value, format!("{:16}", "100"));
+        // plaintext attributes are not padded
+        assert_eq!(
+            response.attributes[1],
+            Attribute {
+                key: "action".to_string(),
+                value: "transfer".to_string(),
+                encrypted: false,
+            }
+        );
+        assert_eq!(response.messages.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deny_list() -> StdResult<()> {
+        let err = SecretResponse::<Empty>::new(16)
+            .add_plaintext_attribute("action", "transfer")
+            .add_plaintext_attribute("Recipient", "secret1alice")
+            .add_event(Event::new("wasm-transfer").add_attribute_plaintext("amount", "100"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(
+                "plaintext attributes would leak private data: Recipient, amount"
+            )
+        );
+
+        // public and encrypted attributes are allowed, as are custom deny-lists
+        SecretResponse::<Empty>::new(16)
+            .add_public_attribute("recipient", "secret1pool")
+            .add_event(Event::new("wasm-transfer").add_attribute("amount", "100"))
+            .build()?;
+        let err = SecretResponse::<Empty>::new(16)
+            .with_deny_list(&["position"])
+            .add_plaintext_attribute("amount", "100")
+            .add_plaintext_attribute("position", "7")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("plaintext attributes would leak private data: position")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{
+    to_vec, Addr, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use serde::Serialize;
+
+use crate::feature_toggle::{FeatureToggle, FeatureToggleTrait};
+
+/// A function that handles an execute message
+pub type Handler<M, C = Empty> = fn(DepsMut, Env, MessageInfo, M) -> StdResult<Response<C>>;
+
+/// A function that returns whether an address was granted a role, e.g. by an admin module
+pub type RoleCheck = fn(&dyn Storage, &Addr, &str) -> StdResult<bool>;
+
+struct Route<M, F, C> {
+    handler: Handler<M, C>,
+    features: Vec<F>,
+    roles: Vec<String>,
+}
+
+/// Dispatches execute messages to the handlers registered for their variant, after checking
+/// that the features the variant requires are not paused, and that the sender has one of the
+/// roles it requires.
+///
+/// `M` is the execute message enum, and `F` is the feature enum of the contract's
+/// [`FeatureToggle`]. Variants are identified by their serialized name, e.g. `"redeem"` for
+/// `ExecuteMsg::Redeem { .. }` with `#[serde(rename_all = "snake_case")]`.
+pub struct ExecuteRouter<M, F, C = Empty, T = FeatureToggle>
+where
+    M: Serialize,
+    F: Serialize + Clone,
+    T: FeatureToggleTrait,
+{
+    routes: BTreeMap<String, Route<M, F, C>>,
+    role_check: Option<RoleCheck>,
+    feature_toggle: PhantomData<T>,
+}
+
+impl<M, F, C> ExecuteRouter<M, F, C>
+where
+    M: Serialize,
+    F: Serialize + Clone,
+{
+    /// Creates an ExecuteRouter without routes, that uses the default [`FeatureToggle`].
+    ///
+    /// Use `ExecuteRouter::default()` to create one that uses another `FeatureToggleTrait`
+    /// implementation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M, F, C, T> ExecuteRouter<M, F, C, T>
+where
+    M: Serialize,
+    F: Serialize + Clone,
+    T: FeatureToggleTrait,
+{
+    /// Sets the function used to check the roles required by guarded routes
+    pub fn with_role_check(mut self, role_check: RoleCheck) -> Self {
+        self.role_check = Some(role_check);
+        self
+    }
+
+    /// Registers the handler of a variant that anyone can execute at any time
+    pub fn route(self, variant: &str, handler: Handler<M, C>) -> Self {
+        self.guarded_route(variant, vec![], &[], handler)
+    }
+
+    /// Registers the handler of a variant that can only be executed while none of `features`
+    /// is paused, by a sender that has at least one of `roles`. If `roles` is empty, anyone
+    /// can execute it.
+    pub fn guarded_route(
+        mut self,
+        variant: &str,
+        features: Vec<F>,
+        roles: &[&str],
+        handler: Handler<M, C>,
+    ) -> Self {
+        self.routes.insert(
+            variant.to_string(),
+            Route {
+                handler,
+                features,
+                roles: roles.iter().map(|role| role.to_string()).collect(),
+            },
+        );
+        self
+    }
+
+    /// Checks that `msg` may be executed by the sender, without executing it
+    pub fn check(&self, storage: &dyn Storage, info: &MessageInfo, msg: &M) -> StdResult<()> {
+        self.check_route(storage, info, self.find_route(msg)?)
+    }
+
+    /// Checks that `msg` may be executed by the sender, and calls the handler of its variant
+    pub fn dispatch(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: M,
+    ) -> StdResult<Response<C>> {
+        let route = self.find_route(&msg)?;
+        self.check_route(deps.storage, &info, route)?;
+        (route.handler)(deps, env, info, msg)
+    }
+
+    fn find_route(&self, msg: &M) -> StdResult<&Route<M, F, C>> {
+        let variant = variant_name(msg)?;
+        self.routes
+            .get(&variant)
+            .ok_or_else(|| StdError::generic_err(format!("no handler for message '{variant}'")))
+    }
+
+    fn check_route(
+        &self,
+        storage: &dyn Storage,
+        info: &MessageInfo,
+        route: &Route<M, F, C>,
+    ) -> StdResult<()> {
+        T::require_not_paused(storage, route.features.clone())?;
+
+        if route.roles.is_empty() {
+            return Ok(());
+        }
+        let role_check = self
+            .role_check
+            .ok_or_else(|| StdError::generic_err("execute router: no role check was set"))?;
+        for role in &route.roles {
+            if role_check(storage, &info.sender, role)? {
+                return Ok(());
+            }
+        }
+
+        Err(StdError::generic_err("unauthorized"))
+    }
+}
+
+impl<M, F, C, T> Default for ExecuteRouter<M, F, C, T>
+where
+    M: Serialize,
+    F: Serialize + Clone,
+    T: FeatureToggleTrait,
+{
+    fn default() -> Self {
+        Self {
+            routes: BTreeMap::new(),
+            role_check: None,
+            feature_toggle: PhantomData,
+        }
+    }
+}
+
+/// Returns the serialized name of the variant of an externally tagged enum
+pub fn variant_name<M: Serialize>(msg: &M) -> StdResult<String> {
+    let json = to_vec(msg)?;
+    // unit variants are serialized as a string, other variants as an object whose single key
+    // is the name of the variant
+    let quoted = json.strip_prefix(b"{").unwrap_or(&json);
+    let name = quoted
+        .strip_prefix(b"\"")
+        .and_then(|rest| rest.iter().position(|&b| b == b'"').map(|end| &rest[..end]))
+        .ok_or_else(|| {
+            StdError::generic_err("execute router: message is not an externally tagged enum")
+        })?;
+    String::from_utf8(name.to_vec()).map_err(StdError::invalid_utf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feature_toggle::{FeatureStatus, Status};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::Uint128;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    enum ExecuteMsg {
+        Redeem { amount: Uint128 },
+        Mint { amount: Uint128 },
+        Ping,
+    }
+
+    #[derive(Serialize, Clone)]
+    enum Features {
+        Redeem,
+    }
+
+    fn handle(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        Ok(Response::new().add_attribute("handled", variant_name(&msg)?))
+    }
+
+    fn is_minter(_storage: &dyn Storage, addr: &Addr, role: &str) -> StdResult<bool> {
+        Ok(role == "minter" && addr.as_str() == "minter")
+    }
+
+    fn router() -> ExecuteRouter<ExecuteMsg, Features> {
+        ExecuteRouter::new()
+            .with_role_check(is_minter)
+            .guarded_route("redeem", vec![Features::Redeem], &[], handle)
+            .guarded_route("mint", vec![], &["admin", "minter"], handle)
+            .route("ping", handle)
+    }
+
+    #[test]
+    fn test_variant_name() -> StdResult<()> {
+        assert_eq!(
+            variant_name(&ExecuteMsg::Redeem {
+                amount: Uint128::zero()
+            })?,
+            "redeem"
+        );
+        assert_eq!(variant_name(&ExecuteMsg::Ping)?, "ping");
+        assert!(variant_name(&5).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispatch() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        FeatureToggle::init_features(
+            &mut deps.storage,
+            vec![FeatureStatus {
+                feature: Features::Redeem,
+                status: Status::NotPaused,
+            }],
+            vec![],
+        )?;
+        let router = router();
+        let redeem = ExecuteMsg::Redeem {
+            amount: Uint128::new(1),
+        };
+        let mint = ExecuteMsg::Mint {
+            amount: Uint128::new(1),
+        };
+
+        let response = router.dispatch(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            redeem.clone(),
+        )?;
+        assert_eq!(response.attributes[0].value, "redeem");
+
+        FeatureToggle::pause(&mut deps.storage, vec![Features::Redeem])?;
+        let paused = router.dispatch(deps.as_mut(), mock_env(), mock_info("alice", &[]), redeem);
+        assert_eq!(
+            paused,
+            Err(StdError::generic_err(
+                "feature toggle: feature '\"Redeem\"' is paused"
+            ))
+        );
+
+        let unauthorized = router.check(&deps.storage, &mock_info("alice", &[]), &mint);
+        assert_eq!(unauthorized, Err(StdError::generic_err("unauthorized")));
+        router.check(&deps.storage, &mock_info("minter", &[]), &mint)?;
+
+        router.check(&deps.storage, &mock_info("alice", &[]), &ExecuteMsg::Ping)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unrouted() {
+        let deps = mock_dependencies();
+        let router: ExecuteRouter<ExecuteMsg, Features> = ExecuteRouter::new();
+
+        assert_eq!(
+            router.check(&deps.storage, &mock_info("alice", &[]), &ExecuteMsg::Ping),
+            Err(StdError::generic_err("no handler for message 'ping'"))
+        );
+
+        // guarded routes are rejected when there is no way to check the roles
+        let router = router.guarded_route("ping", vec![], &["admin"], handle);
+        assert!(router
+            .check(&deps.storage, &mock_info("admin", &[]), &ExecuteMsg::Ping)
+            .is_err());
+    }
+}
@@ -0,0 +1,351 @@
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cosmwasm_storage::{Bucket, ReadonlyBucket};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use secret_toolkit_storage::Item;
+
+const PREFIX_CAPS: &[u8] = b"caps";
+const PREFIX_TOTAL_SUPPLY: &[u8] = b"total_supply";
+const PREFIX_MINTED: &[u8] = b"minted";
+const PREFIX_BURNED: &[u8] = b"burned";
+const PREFIX_ADMINS: &[u8] = b"admins";
+
+/// This is the default implementation of supply control, using the "supply_control" storage
+/// prefix.
+///
+/// You can use another storage location by implementing `SupplyControlTrait` for your own type.
+pub struct SupplyControl;
+
+impl SupplyControlTrait for SupplyControl {
+    const STORAGE_KEY: &'static [u8] = b"supply_control";
+}
+
+/// The caps enforced on minting and burning. A cap that is `None` is not enforced.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct SupplyCaps {
+    /// the maximum total supply
+    pub max_supply: Option<Uint128>,
+    /// the maximum amount each address can mint over its lifetime
+    pub max_minted_per_address: Option<Uint128>,
+    /// the maximum amount each address can burn over its lifetime
+    pub max_burned_per_address: Option<Uint128>,
+}
+
+/// A trait describing the interface of supply control, for contracts that mint and burn a
+/// token, such as SNIP-20 stablecoins or wrapped assets.
+///
+/// It tracks the total supply and the cumulative amounts minted and burned by each address
+/// (usually the minter), and rejects mints and burns that would exceed the caps. The caps
+/// can be updated by the admins.
+pub trait SupplyControlTrait {
+    const STORAGE_KEY: &'static [u8];
+
+    fn stored_caps() -> Item<'static, SupplyCaps> {
+        Item::new(Self::STORAGE_KEY).add_suffix(PREFIX_CAPS)
+    }
+
+    fn stored_total_supply() -> Item<'static, Uint128> {
+        Item::new(Self::STORAGE_KEY).add_suffix(PREFIX_TOTAL_SUPPLY)
+    }
+
+    fn stored_minted(addr: &Addr) -> Item<'static, Uint128> {
+        Item::new(Self::STORAGE_KEY)
+            .add_suffix(PREFIX_MINTED)
+            .add_suffix(addr.as_bytes())
+    }
+
+    fn stored_burned(addr: &Addr) -> Item<'static, Uint128> {
+        Item::new(Self::STORAGE_KEY)
+            .add_suffix(PREFIX_BURNED)
+            .add_suffix(addr.as_bytes())
+    }
+
+    fn init(storage: &mut dyn Storage, admins: Vec<Addr>, caps: SupplyCaps) -> StdResult<()> {
+        for a in admins {
+            Self::set_admin(storage, &a)?;
+        }
+        Self::stored_caps().save(storage, &caps)
+    }
+
+    fn caps(storage: &dyn Storage) -> StdResult<SupplyCaps> {
+        Ok(Self::stored_caps().may_load(storage)?.unwrap_or_default())
+    }
+
+    fn total_supply(storage: &dyn Storage) -> StdResult<Uint128> {
+        Ok(Self::stored_total_supply()
+            .may_load(storage)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the cumulative amount minted by `addr`
+    fn minted(storage: &dyn Storage, addr: &Addr) -> StdResult<Uint128> {
+        Ok(Self::stored_minted(addr)
+            .may_load(storage)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the cumulative amount burned by `addr`
+    fn burned(storage: &dyn Storage, addr: &Addr) -> StdResult<Uint128> {
+        Ok(Self::stored_burned(addr)
+            .may_load(storage)?
+            .unwrap_or_default())
+    }
+
+    /// Returns an error if `addr` minting `amount` would exceed the supply cap or the
+    /// address's mint limit
+    fn assert_can_mint(storage: &dyn Storage, addr: &Addr, amount: Uint128) -> StdResult<()> {
+        let caps = Self::caps(storage)?;
+
+        let total_supply = Self::total_supply(storage)?.checked_add(amount)?;
+        if let Some(max_supply) = caps.max_supply {
+            if total_supply > max_supply {
+                return Err(StdError::generic_err(format!(
+                    "minting {amount} would exceed the supply cap of {max_supply}"
+                )));
+            }
+        }
+
+        let minted = Self::minted(storage, addr)?.checked_add(amount)?;
+        if let Some(max_minted) = caps.max_minted_per_address {
+            if minted > max_minted {
+                return Err(StdError::generic_err(format!(
+                    "minting {amount} would exceed the mint limit of {max_minted} for {addr}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if `addr` burning `amount` would exceed the address's burn limit, or
+    /// the total supply
+    fn assert_can_burn(storage: &dyn Storage, addr: &Addr, amount: Uint128) -> StdResult<()> {
+        Self::total_supply(storage)?.checked_sub(amount)?;
+
+        let burned = Self::burned(storage, addr)?.checked_add(amount)?;
+        if let Some(max_burned) = Self::caps(storage)?.max_burned_per_address {
+            if burned > max_burned {
+                return Err(StdError::generic_err(format!(
+                    "burning {amount} would exceed the burn limit of {max_burned} for {addr}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the caps and records `addr` minting `amount`
+    fn mint(storage: &mut dyn Storage, addr: &Addr, amount: Uint128) -> StdResult<()> {
+        Self::assert_can_mint(storage, addr, amount)?;
+
+        let total_supply = Self::total_supply(storage)? + amount;
+        Self::stored_total_supply().save(storage, &total_supply)?;
+        let minted = Self::minted(storage, addr)? + amount;
+        Self::stored_minted(addr).save(storage, &minted)
+    }
+
+    /// Checks the caps and records `addr` burning `amount`
+    fn burn(storage: &mut dyn Storage, addr: &Addr, amount: Uint128) -> StdResult<()> {
+        Self::assert_can_burn(storage, addr, amount)?;
+
+        let total_supply = Self::total_supply(storage)? - amount;
+        Self::stored_total_supply().save(storage, &total_supply)?;
+        let burned = Self::burned(storage, addr)? + amount;
+        Self::stored_burned(addr).save(storage, &burned)
+    }
+
+    fn is_admin(storage: &dyn Storage, key: &Addr) -> StdResult<bool> {
+        let admin_store: ReadonlyBucket<bool> =
+            ReadonlyBucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_ADMINS]);
+        admin_store.may_load(key.as_bytes()).map(|a| a.is_some())
+    }
+
+    fn set_admin(storage: &mut dyn Storage, key: &Addr) -> StdResult<()> {
+        let mut admin_store = Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_ADMINS]);
+        admin_store.save(key.as_bytes(), &true /* value is insignificant */)
+    }
+
+    fn remove_admin(storage: &mut dyn Storage, key: &Addr) {
+        let mut admin_store: Bucket<bool> =
+            Bucket::multilevel(storage, &[Self::STORAGE_KEY, PREFIX_ADMINS]);
+        admin_store.remove(key.as_bytes())
+    }
+
+    /// Replaces the caps. Lowering a cap below the current amount does not undo past mints or
+    /// burns, it only blocks new ones.
+    fn handle_set_caps(deps: DepsMut, info: &MessageInfo, caps: SupplyCaps) -> StdResult<Response> {
+        if !Self::is_admin(deps.storage, &info.sender)? {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+
+        Self::stored_caps().save(deps.storage, &caps)?;
+
+        Ok(
+            Response::new().set_data(to_binary(&SupplyControlHandleAnswer::SetCaps {
+                status: ResponseStatus::Success,
+            })?),
+        )
+    }
+
+    fn query_supply(deps: Deps) -> StdResult<Binary> {
+        to_binary(&SupplyControlQueryAnswer::Supply {
+            total_supply: Self::total_supply(deps.storage)?,
+            caps: Self::caps(deps.storage)?,
+        })
+    }
+
+    fn query_address_totals(deps: Deps, address: Addr) -> StdResult<Binary> {
+        to_binary(&SupplyControlQueryAnswer::AddressTotals {
+            minted: Self::minted(deps.storage, &address)?,
+            burned: Self::burned(deps.storage, &address)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplyControlHandleMsg {
+    SetCaps { caps: SupplyCaps },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplyControlQueryMsg {
+    Supply {},
+    AddressTotals { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum SupplyControlHandleAnswer {
+    SetCaps { status: ResponseStatus },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum SupplyControlQueryAnswer {
+    Supply {
+        total_supply: Uint128,
+        caps: SupplyCaps,
+    },
+    AddressTotals {
+        minted: Uint128,
+        burned: Uint128,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_info};
+    use cosmwasm_std::{from_binary, MemoryStorage};
+
+    fn init(storage: &mut MemoryStorage) -> StdResult<()> {
+        SupplyControl::init(
+            storage,
+            vec![Addr::unchecked("admin")],
+            SupplyCaps {
+                max_supply: Some(Uint128::new(100)),
+                max_minted_per_address: Some(Uint128::new(60)),
+                max_burned_per_address: Some(Uint128::new(20)),
+            },
+        )
+    }
+
+    #[test]
+    fn test_mint_caps() -> StdResult<()> {
+        let mut storage = MemoryStorage::new();
+        init(&mut storage)?;
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        SupplyControl::mint(&mut storage, &alice, Uint128::new(60))?;
+        assert_eq!(
+            SupplyControl::mint(&mut storage, &alice, Uint128::new(1)),
+            Err(StdError::generic_err(
+                "minting 1 would exceed the mint limit of 60 for alice"
+            ))
+        );
+
+        SupplyControl::mint(&mut storage, &bob, Uint128::new(40))?;
+        assert_eq!(
+            SupplyControl::assert_can_mint(&storage, &bob, Uint128::new(1)),
+            Err(StdError::generic_err(
+                "minting 1 would exceed the supply cap of 100"
+            ))
+        );
+        assert_eq!(SupplyControl::total_supply(&storage)?, Uint128::new(100));
+
+        // burning makes room under the supply cap, but not under the mint limit
+        SupplyControl::burn(&mut storage, &alice, Uint128::new(20))?;
+        assert!(SupplyControl::assert_can_mint(&storage, &bob, Uint128::new(20)).is_ok());
+        assert!(SupplyControl::assert_can_mint(&storage, &alice, Uint128::new(1)).is_err());
+        assert_eq!(SupplyControl::minted(&storage, &alice)?, Uint128::new(60));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_burn_caps() -> StdResult<()> {
+        let mut storage = MemoryStorage::new();
+        init(&mut storage)?;
+        let alice = Addr::unchecked("alice");
+
+        SupplyControl::mint(&mut storage, &alice, Uint128::new(10))?;
+        // can not burn more than the total supply
+        assert!(SupplyControl::burn(&mut storage, &alice, Uint128::new(11)).is_err());
+
+        SupplyControl::mint(&mut storage, &alice, Uint128::new(40))?;
+        SupplyControl::burn(&mut storage, &alice, Uint128::new(20))?;
+        assert_eq!(
+            SupplyControl::burn(&mut storage, &alice, Uint128::new(1)),
+            Err(StdError::generic_err(
+                "burning 1 would exceed the burn limit of 20 for alice"
+            ))
+        );
+        assert_eq!(SupplyControl::total_supply(&storage)?, Uint128::new(30));
+        assert_eq!(SupplyControl::burned(&storage, &alice)?, Uint128::new(20));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handle_set_caps() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        init(&mut deps.storage)?;
+        let caps = SupplyCaps {
+            max_supply: Some(Uint128::new(1000)),
+            ..SupplyCaps::default()
+        };
+
+        let info = mock_info("non-admin", &[]);
+        let error = SupplyControl::handle_set_caps(deps.as_mut(), &info, caps.clone());
+        assert_eq!(error, Err(StdError::generic_err("unauthorized")));
+
+        let info = mock_info("admin", &[]);
+        SupplyControl::handle_set_caps(deps.as_mut(), &info, caps.clone())?;
+
+        let answer: SupplyControlQueryAnswer =
+            from_binary(&SupplyControl::query_supply(deps.as_ref())?)?;
+        assert_eq!(
+            answer,
+            SupplyControlQueryAnswer::Supply {
+                total_supply: Uint128::zero(),
+                caps,
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -1,6 +1,17 @@
+use std::any::type_name;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use cosmwasm_std::{Coin, StdError, StdResult, Uint128};
+
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, JsonSchema)]
 pub struct Contract {
     pub address: String,
@@ -19,3 +30,332 @@ pub enum Token {
     Snip20(Contract),
     Native(String),
 }
+
+/// A currency denomination, used to tag [`Amount`]s at the type level.
+///
+/// ```
+/// # use secret_toolkit_utils::types::Denom;
+/// pub struct Uscrt;
+///
+/// impl Denom for Uscrt {
+///     const NAME: &'static str = "uscrt";
+/// }
+/// ```
+pub trait Denom {
+    const NAME: &'static str;
+}
+
+/// An amount of a specific denomination.
+///
+/// Arithmetic is only defined between amounts of the same denomination, and converting between
+/// denominations requires an [`ExchangeRate`]. It is serialized exactly like a plain `Uint128`.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent, bound = "")]
+pub struct Amount<D> {
+    amount: Uint128,
+    #[serde(skip)]
+    denom: PhantomData<D>,
+}
+
+impl<D> Amount<D> {
+    pub const fn new(amount: u128) -> Self {
+        Self {
+            amount: Uint128::new(amount),
+            denom: PhantomData,
+        }
+    }
+
+    pub const fn zero() -> Self {
+        Self::new(0)
+    }
+
+    pub const fn from_uint128(amount: Uint128) -> Self {
+        Self {
+            amount,
+            denom: PhantomData,
+        }
+    }
+
+    pub const fn amount(&self) -> Uint128 {
+        self.amount
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    pub fn checked_add(self, other: Self) -> StdResult<Self> {
+        Ok(Self::from_uint128(self.amount.checked_add(other.amount)?))
+    }
+
+    pub fn checked_sub(self, other: Self) -> StdResult<Self> {
+        Ok(Self::from_uint128(self.amount.checked_sub(other.amount)?))
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self::from_uint128(self.amount.saturating_add(other.amount))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self::from_uint128(self.amount.saturating_sub(other.amount))
+    }
+
+    /// Returns `self * numerator / denominator`, e.g. for computing fees
+    pub fn checked_multiply_ratio(self, numerator: u128, denominator: u128) -> StdResult<Self> {
+        self.amount
+            .checked_multiply_ratio(numerator, denominator)
+            .map(Self::from_uint128)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+}
+
+impl<D: Denom> Amount<D> {
+    pub fn denom(&self) -> &'static str {
+        D::NAME
+    }
+
+    /// Converts a native amount to a `Coin`
+    pub fn to_coin(&self) -> Coin {
+        Coin::new(self.amount.u128(), D::NAME)
+    }
+
+    /// Converts a `Coin` to an amount, failing if its denomination doesn't match
+    pub fn from_coin(coin: &Coin) -> StdResult<Self> {
+        if coin.denom != D::NAME {
+            return Err(StdError::generic_err(format!(
+                "expected denomination {}, got {}",
+                D::NAME,
+                coin.denom
+            )));
+        }
+        Ok(Self::from_uint128(coin.amount))
+    }
+}
+
+// These are implemented by hand so they don't require the denomination marker to implement them.
+
+impl<D> Clone for Amount<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D> Copy for Amount<D> {}
+
+impl<D> Default for Amount<D> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<D> PartialEq for Amount<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.amount == other.amount
+    }
+}
+
+impl<D> Eq for Amount<D> {}
+
+impl<D> PartialOrd for Amount<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D> Ord for Amount<D> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.amount.cmp(&other.amount)
+    }
+}
+
+impl<D> fmt::Debug for Amount<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Amount<{}>({})", type_name::<D>(), self.amount)
+    }
+}
+
+impl<D: Denom> fmt::Display for Amount<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.amount, D::NAME)
+    }
+}
+
+impl<D> JsonSchema for Amount<D> {
+    fn schema_name() -> String {
+        Uint128::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        Uint128::json_schema(gen)
+    }
+}
+
+impl<D> Add for Amount<D> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::from_uint128(self.amount + other.amount)
+    }
+}
+
+impl<D> AddAssign for Amount<D> {
+    fn add_assign(&mut self, other: Self) {
+        self.amount += other.amount;
+    }
+}
+
+impl<D> Sub for Amount<D> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::from_uint128(self.amount - other.amount)
+    }
+}
+
+impl<D> SubAssign for Amount<D> {
+    fn sub_assign(&mut self, other: Self) {
+        self.amount -= other.amount;
+    }
+}
+
+impl<D> Sum for Amount<D> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+/// The rate at which `From` amounts convert to `To` amounts: `numerator` units of `To` for every
+/// `denominator` units of `From`.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ExchangeRate<From, To> {
+    pub numerator: Uint128,
+    pub denominator: Uint128,
+    #[serde(skip)]
+    denoms: PhantomData<(From, To)>,
+}
+
+impl<From, To> ExchangeRate<From, To> {
+    pub fn new(numerator: impl Into<Uint128>, denominator: impl Into<Uint128>) -> StdResult<Self> {
+        let denominator = denominator.into();
+        if denominator.is_zero() {
+            return Err(StdError::generic_err(
+                "exchange rate denominator can not be zero",
+            ));
+        }
+        Ok(Self {
+            numerator: numerator.into(),
+            denominator,
+            denoms: PhantomData,
+        })
+    }
+
+    /// Converts an amount, rounding down
+    pub fn convert(&self, amount: Amount<From>) -> StdResult<Amount<To>> {
+        amount
+            .amount
+            .checked_multiply_ratio(self.numerator, self.denominator)
+            .map(Amount::from_uint128)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    /// Returns the rate of the opposite conversion
+    pub fn inverse(&self) -> StdResult<ExchangeRate<To, From>> {
+        ExchangeRate::new(self.denominator, self.numerator)
+    }
+}
+
+impl<From, To> Clone for ExchangeRate<From, To> {
+    fn clone(&self) -> Self {
+        Self {
+            numerator: self.numerator,
+            denominator: self.denominator,
+            denoms: PhantomData,
+        }
+    }
+}
+
+impl<From, To> fmt::Debug for ExchangeRate<From, To> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExchangeRate")
+            .field("numerator", &self.numerator)
+            .field("denominator", &self.denominator)
+            .finish()
+    }
+}
+
+impl<From, To> PartialEq for ExchangeRate<From, To> {
+    fn eq(&self, other: &Self) -> bool {
+        self.numerator == other.numerator && self.denominator == other.denominator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{coin, from_slice, to_vec};
+
+    struct Uscrt;
+    impl Denom for Uscrt {
+        const NAME: &'static str = "uscrt";
+    }
+
+    struct Sscrt;
+    impl Denom for Sscrt {
+        const NAME: &'static str = "sscrt";
+    }
+
+    #[test]
+    fn test_amount_arithmetic() -> StdResult<()> {
+        let a = Amount::<Uscrt>::new(100);
+        let b = Amount::<Uscrt>::new(30);
+
+        assert_eq!(a + b, Amount::new(130));
+        assert_eq!(a - b, Amount::new(70));
+        assert_eq!(a.checked_add(b)?, Amount::new(130));
+        assert!(b.checked_sub(a).is_err());
+        assert_eq!(b.saturating_sub(a), Amount::zero());
+        assert_eq!(a.checked_multiply_ratio(1, 4)?, Amount::new(25));
+        assert_eq!(
+            vec![a, b, b].into_iter().sum::<Amount<Uscrt>>(),
+            Amount::new(160)
+        );
+        assert!(a > b);
+        assert_eq!(a.to_string(), "100uscrt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_amount_coin() {
+        let a = Amount::<Uscrt>::new(100);
+        assert_eq!(a.to_coin(), coin(100, "uscrt"));
+        assert_eq!(
+            Amount::<Uscrt>::from_coin(&coin(5, "uscrt")),
+            Ok(Amount::new(5))
+        );
+        assert!(Amount::<Uscrt>::from_coin(&coin(5, "uatom")).is_err());
+    }
+
+    #[test]
+    fn test_amount_serde() -> StdResult<()> {
+        let a = Amount::<Uscrt>::new(12345);
+        assert_eq!(to_vec(&a)?, to_vec(&Uint128::new(12345))?);
+        assert_eq!(from_slice::<Amount<Uscrt>>(b"\"12345\"")?, a);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exchange_rate() -> StdResult<()> {
+        let rate = ExchangeRate::<Uscrt, Sscrt>::new(3u128, 2u128)?;
+        assert_eq!(rate.convert(Amount::new(10))?, Amount::<Sscrt>::new(15));
+        assert_eq!(
+            rate.inverse()?.convert(Amount::new(15))?,
+            Amount::<Uscrt>::new(10)
+        );
+        assert!(ExchangeRate::<Uscrt, Sscrt>::new(1u128, 0u128).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+//! Storing the name and version of a contract with the same layout as cw2, so that tools that
+//! read cw2 info work with Secret contracts, and migrations can check the version they migrate
+//! from.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Binary, CustomQuery, QuerierWrapper, StdError, StdResult, Storage};
+
+use secret_toolkit_serialization::Json;
+use secret_toolkit_storage::Item;
+
+use crate::Query;
+
+/// The storage key of the contract version, the same as in cw2
+pub const CONTRACT_INFO_KEY: &[u8] = b"contract_info";
+
+static CONTRACT_INFO: Item<ContractVersion, Json> = Item::new(CONTRACT_INFO_KEY);
+
+/// The name and version of a contract
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct ContractVersion {
+    /// the name of the contract, usually its crate name, e.g. `crates.io:snip20-reference-impl`
+    pub contract: String,
+    /// the version of the contract, e.g. its semver version
+    pub version: String,
+}
+
+/// Stores the name and version of the contract, in its instantiation and at the end of each
+/// migration
+pub fn set_contract_version(
+    storage: &mut dyn Storage,
+    name: impl Into<String>,
+    version: impl Into<String>,
+) -> StdResult<()> {
+    CONTRACT_INFO.save(
+        storage,
+        &ContractVersion {
+            contract: name.into(),
+            version: version.into(),
+        },
+    )
+}
+
+/// Returns the name and version of the contract, or an error if they were never set
+pub fn get_contract_version(storage: &dyn Storage) -> StdResult<ContractVersion> {
+    CONTRACT_INFO.load(storage)
+}
+
+/// Fails unless the stored contract has the given name and version, e.g. at the start of a
+/// migration from a given version
+pub fn assert_contract_version(storage: &dyn Storage, name: &str, version: &str) -> StdResult<()> {
+    let stored = get_contract_version(storage)?;
+    if stored.contract != name || stored.version != version {
+        return Err(StdError::generic_err(format!(
+            "expected contract {name} version {version}, found {} version {}",
+            stored.contract, stored.version
+        )));
+    }
+    Ok(())
+}
+
+/// The query of the contract version, to add to the query messages of a contract
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractVersionQuery {
+    ContractVersion {},
+}
+
+impl Query for ContractVersionQuery {
+    const BLOCK_SIZE: usize = 256;
+}
+
+/// Returns the answer to [`ContractVersionQuery::ContractVersion`], which is the stored
+/// [`ContractVersion`]
+pub fn query_contract_version(storage: &dyn Storage) -> StdResult<Binary> {
+    to_binary(&get_contract_version(storage)?)
+}
+
+/// Returns the name and version of another contract that answers [`ContractVersionQuery`]
+///
+/// # Arguments
+///
+/// * `querier` - a reference to the Querier dependency of the querying contract
+/// * `code_hash` - String holding the code hash of the contract being queried
+/// * `contract_addr` - address of the contract being queried
+pub fn contract_version_query<C: CustomQuery>(
+    querier: QuerierWrapper<C>,
+    code_hash: String,
+    contract_addr: String,
+) -> StdResult<ContractVersion> {
+    ContractVersionQuery::ContractVersion {}.query(querier, code_hash, contract_addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn test_contract_version() -> StdResult<()> {
+        let mut storage = MockStorage::new();
+        assert!(get_contract_version(&storage).is_err());
+
+        set_contract_version(&mut storage, "crates.io:my-token", "1.0.0")?;
+        // the layout of cw2
+        assert_eq!(
+            storage.get(b"contract_info"),
+            Some(br#"{"contract":"crates.io:my-token","version":"1.0.0"}"#.to_vec())
+        );
+        assert_eq!(
+            get_contract_version(&storage)?,
+            ContractVersion {
+                contract: "crates.io:my-token".to_string(),
+                version: "1.0.0".to_string(),
+            }
+        );
+
+        assert_contract_version(&storage, "crates.io:my-token", "1.0.0")?;
+        assert!(assert_contract_version(&storage, "crates.io:my-token", "0.9.0").is_err());
+        assert!(assert_contract_version(&storage, "crates.io:other", "1.0.0").is_err());
+
+        assert_eq!(
+            query_contract_version(&storage)?,
+            Binary::from(br#"{"contract":"crates.io:my-token","version":"1.0.0"}"#.as_slice())
+        );
+
+        Ok(())
+    }
+}
@@ -5,15 +5,29 @@ extern crate core;
 use base64::{engine::general_purpose, Engine as _};
 use subtle::ConstantTimeEq;
 
-use cosmwasm_std::{Env, MessageInfo, StdError, StdResult, Storage};
+use cosmwasm_std::{to_binary, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage};
 use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
 
 use secret_toolkit_crypto::{sha_256, ContractPrng, SHA256_HASH_SIZE};
 
+pub mod msg;
+pub use msg::{CreateViewingKey, ResponseStatus, SetViewingKey, ViewingKeyMsg, ViewingKeyResponse};
+
 pub const VIEWING_KEY_SIZE: usize = SHA256_HASH_SIZE;
 pub const VIEWING_KEY_PREFIX: &str = "api_key_";
+/// The smallest number of random bytes of the keys created by a store
+pub const MIN_KEY_BYTES: usize = 16;
 const SEED_KEY: &[u8] = b"::seed";
 
+/// The encoding of the random bytes of the viewing keys created by a store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyEncoding {
+    /// standard base64, with padding
+    Base64,
+    /// lowercase hex, for systems that only accept alphanumeric keys
+    Hex,
+}
+
 /// This is the default implementation of the viewing key store, using the "viewing_keys"
 /// storage prefix.
 ///
@@ -30,6 +44,13 @@ impl ViewingKeyStore for ViewingKey {
 /// the keys should be held.
 pub trait ViewingKeyStore {
     const STORAGE_KEY: &'static [u8];
+    /// The number of random bytes of the keys created by `create`. Stores with fewer than
+    /// `MIN_KEY_BYTES` don't compile.
+    const KEY_BYTES: usize = VIEWING_KEY_SIZE;
+    /// The prefix of the keys created by `create`
+    const PREFIX: &'static str = VIEWING_KEY_PREFIX;
+    /// The encoding of the random bytes of the keys created by `create`
+    const ENCODING: KeyEncoding = KeyEncoding::Base64;
 
     /// Set the initial prng seed for the store
     fn set_seed(storage: &mut dyn Storage, seed: &[u8]) {
@@ -55,7 +76,7 @@ pub trait ViewingKeyStore {
         seed_key.extend_from_slice(SEED_KEY);
         let seed = storage.get(&seed_key).unwrap_or_default();
 
-        let (viewing_key, next_seed) = new_viewing_key(info, env, &seed, entropy);
+        let (viewing_key, next_seed) = new_viewing_key::<Self>(info, env, &seed, entropy);
         let mut balance_store = PrefixedStorage::new(storage, Self::STORAGE_KEY);
         let hashed_key = sha_256(viewing_key.as_bytes());
         balance_store.set(account.as_bytes(), &hashed_key);
@@ -65,12 +86,55 @@ pub trait ViewingKeyStore {
         viewing_key
     }
 
+    /// Create new viewing keys for several accounts, save them to storage, and return them in
+    /// the same order as the accounts.
+    ///
+    /// This reads and rotates the prng seed only once, so it is cheaper than calling `create`
+    /// for each account. The random entropy should be provided from some external source,
+    /// such as the user.
+    fn create_many(
+        storage: &mut dyn Storage,
+        info: &MessageInfo,
+        env: &Env,
+        accounts: &[&str],
+        entropy: &[u8],
+    ) -> Vec<String> {
+        let mut seed_key = Vec::with_capacity(Self::STORAGE_KEY.len() + SEED_KEY.len());
+        seed_key.extend_from_slice(Self::STORAGE_KEY);
+        seed_key.extend_from_slice(SEED_KEY);
+        let seed = storage.get(&seed_key).unwrap_or_default();
+
+        let mut rng = new_rng(info, env, &seed, entropy);
+        let mut balance_store = PrefixedStorage::new(storage, Self::STORAGE_KEY);
+        let viewing_keys = accounts
+            .iter()
+            .map(|account| {
+                let viewing_key = viewing_key_from_bytes::<Self>(&rng.rand_bytes());
+                let hashed_key = sha_256(viewing_key.as_bytes());
+                balance_store.set(account.as_bytes(), &hashed_key);
+                viewing_key
+            })
+            .collect();
+
+        storage.set(&seed_key, &rng.rand_bytes());
+
+        viewing_keys
+    }
+
     /// Set a new viewing key based on a predetermined value.
     fn set(storage: &mut dyn Storage, account: &str, viewing_key: &str) {
         let mut balance_store = PrefixedStorage::new(storage, Self::STORAGE_KEY);
         balance_store.set(account.as_bytes(), &sha_256(viewing_key.as_bytes()));
     }
 
+    /// Set new viewing keys for several accounts based on predetermined values.
+    fn set_many(storage: &mut dyn Storage, viewing_keys: &[(&str, &str)]) {
+        let mut balance_store = PrefixedStorage::new(storage, Self::STORAGE_KEY);
+        for (account, viewing_key) in viewing_keys {
+            balance_store.set(account.as_bytes(), &sha_256(viewing_key.as_bytes()));
+        }
+    }
+
     /// Check if a viewing key matches an account.
     fn check(storage: &dyn Storage, account: &str, viewing_key: &str) -> StdResult<()> {
         let balance_store = ReadonlyPrefixedStorage::new(storage, Self::STORAGE_KEY);
@@ -86,14 +150,74 @@ pub trait ViewingKeyStore {
             Err(StdError::generic_err("unauthorized"))
         }
     }
+
+    /// Handles a viewing key message sent by `info.sender`, and returns the standard answer
+    /// in the data of the response.
+    fn handle_msg(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: ViewingKeyMsg,
+    ) -> StdResult<Response> {
+        let (action, answer) = match msg {
+            ViewingKeyMsg::CreateViewingKey(CreateViewingKey { entropy, .. }) => {
+                let key = Self::create(
+                    deps.storage,
+                    &info,
+                    &env,
+                    info.sender.as_str(),
+                    entropy.as_bytes(),
+                );
+                (
+                    "create_viewing_key",
+                    ViewingKeyResponse::CreateViewingKey { key },
+                )
+            }
+            ViewingKeyMsg::SetViewingKey(SetViewingKey { key, .. }) => {
+                Self::set(deps.storage, info.sender.as_str(), &key);
+                (
+                    "set_viewing_key",
+                    ViewingKeyResponse::SetViewingKey {
+                        status: ResponseStatus::Success,
+                    },
+                )
+            }
+        };
+
+        Ok(Response::new()
+            .add_attribute("action", action)
+            .set_data(to_binary(&answer)?))
+    }
 }
 
-fn new_viewing_key(
+/// Handles a viewing key message with the default [`ViewingKey`] store.
+///
+/// The key is created or set for `info.sender`, and the standard answer is returned in the
+/// data of the response.
+pub fn handle_viewing_key_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ViewingKeyMsg,
+) -> StdResult<Response> {
+    ViewingKey::handle_msg(deps, env, info, msg)
+}
+
+fn new_viewing_key<S: ViewingKeyStore + ?Sized>(
     info: &MessageInfo,
     env: &Env,
     seed: &[u8],
     entropy: &[u8],
 ) -> (String, [u8; 32]) {
+    let mut rng = new_rng(info, env, seed, entropy);
+
+    let rand_slice = rng.rand_bytes();
+
+    let viewing_key = viewing_key_from_bytes::<S>(&rand_slice);
+    (viewing_key, rand_slice)
+}
+
+fn new_rng(info: &MessageInfo, env: &Env, seed: &[u8], entropy: &[u8]) -> ContractPrng {
     // 16 here represents the lengths in bytes of the block height and time.
     let entropy_len = 16 + info.sender.to_string().len() + entropy.len();
     let mut rng_entropy = Vec::with_capacity(entropy_len);
@@ -102,14 +226,31 @@ fn new_viewing_key(
     rng_entropy.extend_from_slice(info.sender.as_bytes());
     rng_entropy.extend_from_slice(entropy);
 
-    let mut rng = ContractPrng::new(seed, &rng_entropy);
-
-    let rand_slice = rng.rand_bytes();
+    ContractPrng::new(seed, &rng_entropy)
+}
 
-    let key = sha_256(&rand_slice);
+fn viewing_key_from_bytes<S: ViewingKeyStore + ?Sized>(rand_slice: &[u8]) -> String {
+    const {
+        assert!(
+            S::KEY_BYTES >= MIN_KEY_BYTES,
+            "viewing keys must have at least 16 random bytes"
+        )
+    };
+
+    // keys longer than a hash are extended with the hashes of the random bytes and a counter
+    let mut key = sha_256(rand_slice).to_vec();
+    let mut counter = 1u32;
+    while key.len() < S::KEY_BYTES {
+        key.extend_from_slice(&sha_256(&[rand_slice, &counter.to_be_bytes()].concat()));
+        counter += 1;
+    }
+    key.truncate(S::KEY_BYTES);
 
-    let viewing_key = VIEWING_KEY_PREFIX.to_string() + &general_purpose::STANDARD.encode(key);
-    (viewing_key, rand_slice)
+    let encoded = match S::ENCODING {
+        KeyEncoding::Base64 => general_purpose::STANDARD.encode(key),
+        KeyEncoding::Hex => key.iter().map(|byte| format!("{byte:02x}")).collect(),
+    };
+    S::PREFIX.to_string() + &encoded
 }
 
 fn ct_slice_compare(s1: &[u8], s2: &[u8]) -> bool {
@@ -120,6 +261,7 @@ fn ct_slice_compare(s1: &[u8], s2: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    use cosmwasm_std::from_binary;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
     #[test]
@@ -160,4 +302,128 @@ mod tests {
         let result = ViewingKey::check(&deps.storage, &account, "fake key");
         assert_eq!(result, Err(StdError::generic_err("unauthorized")));
     }
+
+    #[test]
+    fn test_viewing_keys_batch() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("admin", &[]);
+        let accounts = ["user-1", "user-2", "user-3"];
+
+        ViewingKey::set_seed(&mut deps.storage, b"seed");
+        let viewing_keys =
+            ViewingKey::create_many(&mut deps.storage, &info, &env, &accounts, b"entropy");
+        assert_eq!(viewing_keys.len(), accounts.len());
+        for (account, viewing_key) in accounts.iter().zip(&viewing_keys) {
+            assert_eq!(
+                ViewingKey::check(&deps.storage, account, viewing_key),
+                Ok(())
+            );
+        }
+        assert_ne!(viewing_keys[0], viewing_keys[1]);
+        assert_eq!(
+            ViewingKey::check(&deps.storage, "user-1", &viewing_keys[1]),
+            Err(StdError::generic_err("unauthorized"))
+        );
+
+        // the seed was rotated, so the same entropy yields different keys
+        let viewing_keys_2 =
+            ViewingKey::create_many(&mut deps.storage, &info, &env, &accounts, b"entropy");
+        assert_ne!(viewing_keys, viewing_keys_2);
+
+        ViewingKey::set_many(
+            &mut deps.storage,
+            &[("user-1", "key 1"), ("user-2", "key 2")],
+        );
+        assert_eq!(ViewingKey::check(&deps.storage, "user-1", "key 1"), Ok(()));
+        assert_eq!(ViewingKey::check(&deps.storage, "user-2", "key 2"), Ok(()));
+        assert_eq!(
+            ViewingKey::check(&deps.storage, "user-3", &viewing_keys_2[2]),
+            Ok(())
+        );
+    }
+    #[test]
+    fn test_handle_viewing_key_msg() -> StdResult<()> {
+        let mut deps = mock_dependencies();
+        let info = mock_info("user-1", &[]);
+
+        let msg: ViewingKeyMsg =
+            cosmwasm_std::from_slice(br#"{"create_viewing_key":{"entropy":"entropy"}}"#)?;
+        let response = handle_viewing_key_msg(deps.as_mut(), mock_env(), info.clone(), msg)?;
+        assert_eq!(response.attributes[0].value, "create_viewing_key");
+        let key = match from_binary(&response.data.unwrap())? {
+            ViewingKeyResponse::CreateViewingKey { key } => key,
+            other => panic!("unexpected answer {other:?}"),
+        };
+        assert_eq!(ViewingKey::check(&deps.storage, "user-1", &key), Ok(()));
+
+        let msg = ViewingKeyMsg::SetViewingKey(SetViewingKey {
+            key: "custom key".to_string(),
+            padding: None,
+        });
+        let response = handle_viewing_key_msg(deps.as_mut(), mock_env(), info, msg)?;
+        assert_eq!(
+            from_binary::<ViewingKeyResponse>(&response.data.unwrap())?,
+            ViewingKeyResponse::SetViewingKey {
+                status: ResponseStatus::Success
+            }
+        );
+        assert_eq!(
+            ViewingKey::check(&deps.storage, "user-1", "custom key"),
+            Ok(())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_policy() {
+        struct HexKey;
+
+        impl ViewingKeyStore for HexKey {
+            const STORAGE_KEY: &'static [u8] = b"hex_keys";
+            const KEY_BYTES: usize = 40;
+            const PREFIX: &'static str = "vk";
+            const ENCODING: KeyEncoding = KeyEncoding::Hex;
+        }
+
+        struct ShortKey;
+
+        impl ViewingKeyStore for ShortKey {
+            const STORAGE_KEY: &'static [u8] = b"short_keys";
+            const KEY_BYTES: usize = 16;
+            const PREFIX: &'static str = "";
+        }
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("user-1", &[]);
+
+        let key = HexKey::create(&mut deps.storage, &info, &env, "user-1", b"entropy");
+        assert_eq!(key.len(), 2 + 80);
+        assert!(key.starts_with("vk"));
+        assert!(key[2..].bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_eq!(HexKey::check(&deps.storage, "user-1", &key), Ok(()));
+        assert!(ViewingKey::check(&deps.storage, "user-1", &key).is_err());
+
+        // the shortest keys allowed, shorter ones don't compile
+        assert_eq!(ShortKey::KEY_BYTES, MIN_KEY_BYTES);
+        let keys = ShortKey::create_many(&mut deps.storage, &info, &env, &["user-1"], b"");
+        assert_eq!(keys[0].len(), 24);
+        assert_eq!(ShortKey::check(&deps.storage, "user-1", &keys[0]), Ok(()));
+
+        // the default policy is unchanged
+        let key = ViewingKey::create(&mut deps.storage, &info, &env, "user-1", b"entropy");
+        assert!(key.starts_with(VIEWING_KEY_PREFIX));
+        assert_eq!(
+            general_purpose::STANDARD
+                .decode(&key[VIEWING_KEY_PREFIX.len()..])
+                .map(|bytes| bytes.len()),
+            Ok(VIEWING_KEY_SIZE)
+        );
+        assert_eq!(
+            viewing_key_from_bytes::<ViewingKey>(b"random"),
+            VIEWING_KEY_PREFIX.to_string() + &general_purpose::STANDARD.encode(sha_256(b"random"))
+        );
+    }
 }
@@ -0,0 +1,55 @@
+//! The standard viewing key messages and answers of SNIP-20 and SNIP-721 contracts, for
+//! contracts to embed in their own messages.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Creates a viewing key for the sender, derived from the contract's seed and `entropy`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct CreateViewingKey {
+    pub entropy: String,
+    pub padding: Option<String>,
+}
+
+/// Sets the viewing key of the sender to `key`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct SetViewingKey {
+    pub key: String,
+    pub padding: Option<String>,
+}
+
+/// The viewing key execute messages. They are serialized like the variants of a SNIP-20
+/// `ExecuteMsg`, e.g. `{"create_viewing_key":{"entropy":"..."}}`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewingKeyMsg {
+    CreateViewingKey(CreateViewingKey),
+    SetViewingKey(SetViewingKey),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+/// The answers to the viewing key execute messages
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewingKeyResponse {
+    CreateViewingKey { key: String },
+    SetViewingKey { status: ResponseStatus },
+}
+
+impl From<CreateViewingKey> for ViewingKeyMsg {
+    fn from(msg: CreateViewingKey) -> Self {
+        ViewingKeyMsg::CreateViewingKey(msg)
+    }
+}
+
+impl From<SetViewingKey> for ViewingKeyMsg {
+    fn from(msg: SetViewingKey) -> Self {
+        ViewingKeyMsg::SetViewingKey(msg)
+    }
+}
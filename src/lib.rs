@@ -20,3 +20,5 @@ pub use secret_toolkit_storage as storage;
 pub use secret_toolkit_utils as utils;
 #[cfg(feature = "viewing-key")]
 pub use secret_toolkit_viewing_key as viewing_key;
+
+pub mod prelude;
@@ -0,0 +1,39 @@
+//! The most used types and functions of the toolkit, for a single glob import at the top of
+//! contract files:
+//!
+//! ```
+//! use secret_toolkit::prelude::*;
+//! ```
+//!
+//! Only the items of the enabled features are exported. The prelude also holds deprecated aliases
+//! of the items renamed in past releases, so contracts written against those releases keep
+//! compiling while they migrate.
+
+#[cfg(feature = "crypto")]
+pub use secret_toolkit_crypto::{sha_256, ContractPrng};
+#[cfg(feature = "permit")]
+pub use secret_toolkit_permit::{validate, Permit, RevokedPermits, TokenPermissions};
+#[cfg(feature = "serialization")]
+pub use secret_toolkit_serialization::{Base64, Base64Of, Bincode2, Json, Serde};
+#[cfg(feature = "storage")]
+pub use secret_toolkit_storage::{
+    iter_options::WithIter, AppendStore, DequeStore, Item, Keymap, KeymapBuilder, Keyset,
+    KeysetBuilder, WithoutIter,
+};
+#[cfg(feature = "utils")]
+pub use secret_toolkit_utils::{
+    pad_handle_result, pad_query_result, space_pad, types::Contract, HandleCallback, InitCallback,
+    Query,
+};
+#[cfg(feature = "viewing-key")]
+pub use secret_toolkit_viewing_key::{ViewingKey, ViewingKeyStore};
+
+/// `Prng` was renamed as `ContractPrng` in v0.9.0
+#[cfg(feature = "crypto")]
+#[deprecated(since = "0.9.0", note = "renamed as `ContractPrng`")]
+pub type Prng = ContractPrng;
+
+/// `Permission` was renamed as `TokenPermissions` in v0.8.2
+#[cfg(feature = "permit")]
+#[deprecated(since = "0.8.2", note = "renamed as `TokenPermissions`")]
+pub type Permission = TokenPermissions;